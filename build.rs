@@ -7,6 +7,8 @@ fn main() {
             "src/protocol/libp2p/schema/identify.proto",
             "src/protocol/libp2p/schema/kademlia.proto",
             "src/protocol/libp2p/schema/bitswap.proto",
+            "src/protocol/libp2p/schema/dcutr.proto",
+            "src/protocol/libp2p/schema/relay.proto",
         ],
         &["src"],
     )
@@ -22,6 +22,7 @@ use std::{collections::HashSet, sync::Arc};
 
 use multiaddr::{Multiaddr, Protocol};
 use parking_lot::RwLock;
+use tokio::sync::watch;
 
 use crate::PeerId;
 
@@ -41,6 +42,9 @@ use crate::PeerId;
 pub struct PublicAddresses {
     pub(crate) inner: Arc<RwLock<HashSet<Multiaddr>>>,
     local_peer_id: PeerId,
+
+    /// Notifies subscribers when the set of addresses changes.
+    changed: Arc<watch::Sender<()>>,
 }
 
 impl PublicAddresses {
@@ -49,6 +53,7 @@ impl PublicAddresses {
         Self {
             inner: Arc::new(RwLock::new(HashSet::new())),
             local_peer_id,
+            changed: Arc::new(watch::channel(()).0),
         }
     }
 
@@ -60,20 +65,43 @@ impl PublicAddresses {
     /// Returns true if the address was added, false if it was already present.
     pub fn add_address(&self, address: Multiaddr) -> Result<bool, InsertionError> {
         let address = ensure_local_peer(address, self.local_peer_id)?;
-        Ok(self.inner.write().insert(address))
+        let inserted = self.inner.write().insert(address);
+
+        if inserted {
+            self.changed.send_replace(());
+        }
+
+        Ok(inserted)
     }
 
     /// Remove the exact public address.
     ///
-    /// The provided address must contain the local peer ID.
+    /// In case the address does not contain any peer ID, the local peer ID is added to it
+    /// before removal.
     pub fn remove_address(&self, address: &Multiaddr) -> bool {
-        self.inner.write().remove(address)
+        let removed = match ensure_local_peer(address.clone(), self.local_peer_id) {
+            Ok(address) => self.inner.write().remove(&address),
+            Err(_) => false,
+        };
+
+        if removed {
+            self.changed.send_replace(());
+        }
+
+        removed
     }
 
     /// Returns a vector of the available listen addresses.
     pub fn get_addresses(&self) -> Vec<Multiaddr> {
         self.inner.read().iter().cloned().collect()
     }
+
+    /// Subscribe to changes in the set of addresses.
+    ///
+    /// The returned receiver is notified each time an address is added or removed.
+    pub(crate) fn subscribe(&self) -> watch::Receiver<()> {
+        self.changed.subscribe()
+    }
 }
 
 /// Check if the address contains the local peer ID.
@@ -156,4 +184,23 @@ mod tests {
         assert!(addresses.contains(&address1.with(Protocol::P2p(peer_id.into()))));
         assert!(addresses.contains(&address2.with(Protocol::P2p(peer_id.into()))));
     }
+
+    #[tokio::test]
+    async fn subscribers_notified_of_changes() {
+        let peer_id = PeerId::random();
+        let addresses = PublicAddresses::new(peer_id);
+        let address = Multiaddr::from_str("/dns/domain1.com/tcp/30333").unwrap();
+        let mut changes = addresses.subscribe();
+
+        assert!(addresses.add_address(address.clone()).unwrap());
+        assert!(changes.has_changed().unwrap());
+        changes.borrow_and_update();
+
+        // adding the same address again doesn't notify subscribers
+        assert!(!addresses.add_address(address.clone()).unwrap());
+        assert!(!changes.has_changed().unwrap());
+
+        assert!(addresses.remove_address(&address));
+        assert!(changes.has_changed().unwrap());
+    }
 }
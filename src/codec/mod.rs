@@ -32,6 +32,18 @@ pub enum ProtocolCodec {
     /// Unsigned varint where the argument denotes the maximum message size, if specified.
     UnsignedVarint(Option<usize>),
 
+    /// Newline-delimited messages where the argument denotes the maximum message size, excluding
+    /// the newline, if specified.
+    ///
+    /// Messages must not contain newlines.
+    NewlineDelimited(Option<usize>),
+
+    /// Raw byte stream without any framing.
+    ///
+    /// Messages are written to the substream as-is and reading from the substream returns
+    /// whatever bytes are available, so message boundaries are not preserved.
+    Raw,
+
     /// Protocol doens't need framing for its messages or is using a custom codec.
     Unspecified,
 }
@@ -21,23 +21,33 @@
 //! [`Litep2p`](`crate::Litep2p`) configuration.
 
 use crate::{
-    crypto::ed25519::Keypair,
+    crypto::{ed25519, Keypair},
     executor::{DefaultExecutor, Executor},
     protocol::{
-        libp2p::{bitswap, identify, kademlia, ping},
+        libp2p::{bitswap, dcutr, identify, kademlia, ping, relay},
         mdns::Config as MdnsConfig,
-        notification, request_response, UserProtocol,
+        notification, request_response, NegotiationObserver, UserProtocol,
     },
+    substream::{SubstreamRateLimit, SubstreamRegistryConfig},
     transport::{
-        manager::limits::ConnectionLimitsConfig, tcp::config::Config as TcpConfig,
+        manager::{
+            ip_filter::IpFilterConfig, limits::ConnectionLimitsConfig,
+            reputation::ReputationConfig, DuplicateConnectionPolicy,
+        },
+        tcp::config::Config as TcpConfig,
+        tunnel::config::Config as TunnelConfig,
         KEEP_ALIVE_TIMEOUT, MAX_PARALLEL_DIALS,
     },
     types::protocol::ProtocolName,
     PeerId,
 };
 
+#[cfg(feature = "traffic-tap")]
+use crate::substream::TrafficTap;
 #[cfg(feature = "quic")]
 use crate::transport::quic::config::Config as QuicConfig;
+#[cfg(unix)]
+use crate::transport::unix::config::Config as UnixConfig;
 #[cfg(feature = "webrtc")]
 use crate::transport::webrtc::config::Config as WebRtcConfig;
 #[cfg(feature = "websocket")]
@@ -45,7 +55,11 @@ use crate::transport::websocket::config::Config as WebSocketConfig;
 
 use multiaddr::Multiaddr;
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 /// Connection role.
 #[derive(Debug, Copy, Clone)]
@@ -83,6 +97,13 @@ pub struct ConfigBuilder {
     #[cfg(feature = "websocket")]
     websocket: Option<WebSocketConfig>,
 
+    /// Unix domain socket transport config.
+    #[cfg(unix)]
+    unix: Option<UnixConfig>,
+
+    /// Tunnel transport config.
+    tunnel: Option<TunnelConfig>,
+
     /// Keypair.
     keypair: Option<Keypair>,
 
@@ -98,6 +119,15 @@ pub struct ConfigBuilder {
     /// Bitswap protocol config.
     bitswap: Option<bitswap::Config>,
 
+    /// DCUtR protocol config.
+    dcutr: Option<dcutr::Config>,
+
+    /// Circuit relay server config.
+    relay: Option<relay::Config>,
+
+    /// Circuit relay client config.
+    relay_client: Option<relay::ClientConfig>,
+
     /// Notification protocols.
     notification_protocols: HashMap<ProtocolName, notification::Config>,
 
@@ -122,8 +152,39 @@ pub struct ConfigBuilder {
     /// Connection limits config.
     connection_limits: ConnectionLimitsConfig,
 
+    /// Peer reputation config.
+    reputation: ReputationConfig,
+
+    /// IP filter config.
+    ip_filter: IpFilterConfig,
+
+    /// Policy for duplicate connections.
+    duplicate_connection_policy: DuplicateConnectionPolicy,
+
+    /// Substream registry config.
+    substream_registry: Option<SubstreamRegistryConfig>,
+
+    /// Observer of `multistream-select` negotiations.
+    negotiation_observer: Option<NegotiationObserver>,
+
+    /// Negotiate previously negotiated protocols optimistically.
+    optimistic_negotiation: bool,
+
+    /// Maximum number of bytes buffered by all substreams of the node.
+    buffer_budget: Option<usize>,
+
+    /// Rate limit of substreams opened by remote peers over each connection.
+    substream_rate_limit: Option<SubstreamRateLimit>,
+
+    /// Traffic taps of protocols.
+    #[cfg(feature = "traffic-tap")]
+    traffic_taps: HashMap<ProtocolName, TrafficTap>,
+
     /// Close the connection if no substreams are open within this time frame.
     keep_alive_timeout: Duration,
+
+    /// Protocols whose event loops are run on dedicated threads.
+    isolated_protocols: HashSet<ProtocolName>,
 }
 
 impl Default for ConfigBuilder {
@@ -143,11 +204,17 @@ impl ConfigBuilder {
             webrtc: None,
             #[cfg(feature = "websocket")]
             websocket: None,
+            #[cfg(unix)]
+            unix: None,
+            tunnel: None,
             keypair: None,
             ping: None,
             identify: None,
             kademlia: None,
             bitswap: None,
+            dcutr: None,
+            relay: None,
+            relay_client: None,
             mdns: None,
             executor: None,
             max_parallel_dials: MAX_PARALLEL_DIALS,
@@ -156,7 +223,18 @@ impl ConfigBuilder {
             request_response_protocols: HashMap::new(),
             known_addresses: Vec::new(),
             connection_limits: ConnectionLimitsConfig::default(),
+            reputation: ReputationConfig::default(),
+            ip_filter: IpFilterConfig::default(),
+            duplicate_connection_policy: DuplicateConnectionPolicy::default(),
+            substream_registry: None,
+            negotiation_observer: None,
+            optimistic_negotiation: false,
+            buffer_budget: None,
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             keep_alive_timeout: KEEP_ALIVE_TIMEOUT,
+            isolated_protocols: HashSet::new(),
         }
     }
 
@@ -187,11 +265,30 @@ impl ConfigBuilder {
         self
     }
 
+    /// Add Unix domain socket transport configuration, enabling the transport.
+    #[cfg(unix)]
+    pub fn with_unix(mut self, config: UnixConfig) -> Self {
+        self.unix = Some(config);
+        self
+    }
+
+    /// Add tunnel transport configuration, enabling the transport.
+    ///
+    /// Connections of the tunnel transport are established over substreams of existing
+    /// connections, handed over to the transport using
+    /// [`TunnelHandle`](crate::transport::tunnel::TunnelHandle).
+    pub fn with_tunnel(mut self, config: TunnelConfig) -> Self {
+        self.tunnel = Some(config);
+        self
+    }
+
     /// Add keypair.
     ///
-    /// If no keypair is specified, litep2p creates a new keypair.
-    pub fn with_keypair(mut self, keypair: Keypair) -> Self {
-        self.keypair = Some(keypair);
+    /// Accepts any supported identity keypair, including a plain [`ed25519::Keypair`].
+    ///
+    /// If no keypair is specified, litep2p creates a new Ed25519 keypair.
+    pub fn with_keypair(mut self, keypair: impl Into<Keypair>) -> Self {
+        self.keypair = Some(keypair.into());
         self
     }
 
@@ -225,6 +322,36 @@ impl ConfigBuilder {
         self
     }
 
+    /// Enable libp2p DCUtR protocol.
+    ///
+    /// Relayed connections are upgraded to direct connections using hole punching.
+    pub fn with_libp2p_dcutr(mut self, config: dcutr::Config) -> Self {
+        self.dcutr = Some(config);
+        self
+    }
+
+    /// Enable libp2p circuit relay server.
+    ///
+    /// The node accepts reservations from other peers and relays connections to them.
+    pub fn with_libp2p_relay_server(mut self, config: relay::Config) -> Self {
+        self.relay = Some(config);
+        self
+    }
+
+    /// Enable libp2p circuit relay client.
+    ///
+    /// The node reserves a slot on each of the configured relays and, when a dial started with
+    /// [`Litep2p::dial()`](crate::Litep2p::dial) fails, dials the peer through them. The failure
+    /// is reported only if the peer can't be reached through the relays either. Each peer is
+    /// dialed through the relays at most once every 30 seconds.
+    ///
+    /// Requires the tunnel transport to be enabled with [`ConfigBuilder::with_tunnel()`] and
+    /// cannot be combined with the relay server.
+    pub fn with_libp2p_relay_client(mut self, config: relay::ClientConfig) -> Self {
+        self.relay_client = Some(config);
+        self
+    }
+
     /// Enable request-response protocol.
     pub fn with_request_response_protocol(mut self, config: request_response::Config) -> Self {
         self.request_response_protocols.insert(config.protocol_name().clone(), config);
@@ -272,17 +399,130 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set peer reputation configuration.
+    pub fn with_reputation(mut self, config: ReputationConfig) -> Self {
+        self.reputation = config;
+        self
+    }
+
+    /// Set IP filter configuration.
+    ///
+    /// The filter is applied to inbound connections before they're accepted and to outbound
+    /// connections before they're dialed. Inbound connections of transports which don't report
+    /// pending connections, such as WebRTC, are filtered once they've been established. DNS
+    /// addresses dialed over TCP are filtered once they've been resolved. By default all
+    /// addresses are allowed.
+    pub fn with_ip_filter(mut self, config: IpFilterConfig) -> Self {
+        self.ip_filter = config;
+        self
+    }
+
+    /// Set the policy for handling a new connection to a peer that is already connected.
+    ///
+    /// By default both connections are kept, with the new connection used as a secondary
+    /// connection of the peer.
+    pub fn with_duplicate_connection_policy(mut self, policy: DuplicateConnectionPolicy) -> Self {
+        self.duplicate_connection_policy = policy;
+        self
+    }
+
+    /// Track open substreams in a registry for debugging substream leaks.
+    ///
+    /// The tracked substreams can be queried with
+    /// [`Litep2p::open_substreams()`](crate::Litep2p::open_substreams). If an idle timeout is
+    /// configured, a watchdog periodically reports substreams that have been idle for too long.
+    /// Disabled by default.
+    pub fn with_substream_registry(mut self, config: SubstreamRegistryConfig) -> Self {
+        self.substream_registry = Some(config);
+        self
+    }
+
+    /// Observe the outcome of each `multistream-select` substream negotiation.
+    ///
+    /// The observer receives the protocols offered by the local node, the protocol that was
+    /// selected, if any, and the remote peer. Disabled by default.
+    pub fn with_negotiation_observer(mut self, observer: NegotiationObserver) -> Self {
+        self.negotiation_observer = Some(observer);
+        self
+    }
+
+    /// Negotiate outbound substreams optimistically for protocols the peer is known to support.
+    ///
+    /// Once a protocol has been negotiated with a peer, further substreams of the protocol are
+    /// opened with `multistream-select` `V1Lazy` negotiation which lets the protocol send data
+    /// before the remote peer has confirmed the protocol, saving one round trip. If the remote
+    /// peer rejects the protocol, the substream fails and the following substreams are negotiated
+    /// normally. The cached protocols of a peer are forgotten once it disconnects.
+    ///
+    /// Only used by the TCP-based transports. Disabled by default.
+    pub fn with_optimistic_negotiation(mut self, enabled: bool) -> Self {
+        self.optimistic_negotiation = enabled;
+        self
+    }
+
+    /// Limit the total amount of memory substreams may use for buffered data to `limit` bytes.
+    ///
+    /// Outbound frames buffered by the [`Sink`](futures::Sink) implementation of
+    /// [`Substream`](crate::substream::Substream) and the buffers of partially read inbound
+    /// frames of all substreams are counted against the limit. Once it's reached, substreams
+    /// first try to write out their own buffered frames and if that doesn't free enough memory,
+    /// new frames are refused with
+    /// [`SubstreamError::BufferBudgetExhausted`](crate::error::SubstreamError). Data that has
+    /// already been buffered is not affected.
+    ///
+    /// The current usage can be observed with
+    /// [`Litep2p::buffer_budget()`](crate::Litep2p::buffer_budget). Disabled by default.
+    pub fn with_buffer_memory_limit(mut self, limit: usize) -> Self {
+        self.buffer_budget = Some(limit);
+        self
+    }
+
+    /// Limit the rate at which remote peers may open substreams over a connection.
+    ///
+    /// The limit is applied to each connection separately and substreams opened in excess of it
+    /// are closed before they're negotiated, protecting the node from peers which churn through
+    /// substreams to burn CPU on negotiation. Substreams opened by the local node are not
+    /// limited. Disabled by default.
+    pub fn with_substream_rate_limit(mut self, limit: SubstreamRateLimit) -> Self {
+        self.substream_rate_limit = Some(limit);
+        self
+    }
+
+    /// Copy the frames of `protocol` to `tap` for debugging or recording.
+    ///
+    /// Inbound frames are always copied and outbound frames only if enabled with
+    /// [`TrafficTap::with_outbound()`]. The frames are copied after the framing of the protocol
+    /// codec has been removed. Any protocol can be tapped, including user protocols.
+    #[cfg(feature = "traffic-tap")]
+    pub fn with_traffic_tap(mut self, protocol: ProtocolName, tap: TrafficTap) -> Self {
+        self.traffic_taps.insert(protocol, tap);
+        self
+    }
+
     /// Set keep alive timeout for connections.
     pub fn with_keep_alive_timeout(mut self, timeout: Duration) -> Self {
         self.keep_alive_timeout = timeout;
         self
     }
 
+    /// Run the event loop of `protocol` on a dedicated thread instead of the executor.
+    ///
+    /// A protocol that does a lot of work between yields, such as Kademlia running many queries,
+    /// delays the other protocols sharing the executor's threads. An isolated protocol is driven
+    /// by a single-threaded runtime on a thread of its own, which costs one thread per protocol.
+    ///
+    /// Protocols are identified by their main protocol name, i.e., the first name of protocols
+    /// registering several. Any protocol can be isolated, including user protocols.
+    pub fn with_isolated_protocol(mut self, protocol: ProtocolName) -> Self {
+        self.isolated_protocols.insert(protocol);
+        self
+    }
+
     /// Build [`Litep2pConfig`].
     pub fn build(mut self) -> Litep2pConfig {
         let keypair = match self.keypair {
             Some(keypair) => keypair,
-            None => Keypair::generate(),
+            None => ed25519::Keypair::generate().into(),
         };
 
         Litep2pConfig {
@@ -295,10 +535,16 @@ impl ConfigBuilder {
             webrtc: self.webrtc.take(),
             #[cfg(feature = "websocket")]
             websocket: self.websocket.take(),
+            #[cfg(unix)]
+            unix: self.unix.take(),
+            tunnel: self.tunnel.take(),
             ping: self.ping.take(),
             identify: self.identify.take(),
             kademlia: self.kademlia.take(),
             bitswap: self.bitswap.take(),
+            dcutr: self.dcutr.take(),
+            relay: self.relay.take(),
+            relay_client: self.relay_client.take(),
             max_parallel_dials: self.max_parallel_dials,
             executor: self.executor.map_or(Arc::new(DefaultExecutor {}), |executor| executor),
             user_protocols: self.user_protocols,
@@ -306,7 +552,18 @@ impl ConfigBuilder {
             request_response_protocols: self.request_response_protocols,
             known_addresses: self.known_addresses,
             connection_limits: self.connection_limits,
+            reputation: self.reputation,
+            ip_filter: self.ip_filter,
+            duplicate_connection_policy: self.duplicate_connection_policy,
+            substream_registry: self.substream_registry,
+            negotiation_observer: self.negotiation_observer,
+            optimistic_negotiation: self.optimistic_negotiation,
+            buffer_budget: self.buffer_budget,
+            substream_rate_limit: self.substream_rate_limit,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: self.traffic_taps,
             keep_alive_timeout: self.keep_alive_timeout,
+            isolated_protocols: self.isolated_protocols,
         }
     }
 }
@@ -328,6 +585,13 @@ pub struct Litep2pConfig {
     #[cfg(feature = "websocket")]
     pub(crate) websocket: Option<WebSocketConfig>,
 
+    /// Unix domain socket transport config.
+    #[cfg(unix)]
+    pub(crate) unix: Option<UnixConfig>,
+
+    /// Tunnel transport config.
+    pub(crate) tunnel: Option<TunnelConfig>,
+
     /// Keypair.
     pub(crate) keypair: Keypair,
 
@@ -343,6 +607,15 @@ pub struct Litep2pConfig {
     /// Bitswap protocol configuration, if enabled.
     pub(crate) bitswap: Option<bitswap::Config>,
 
+    /// DCUtR protocol configuration, if enabled.
+    pub(crate) dcutr: Option<dcutr::Config>,
+
+    /// Circuit relay server configuration, if enabled.
+    pub(crate) relay: Option<relay::Config>,
+
+    /// Circuit relay client configuration, if enabled.
+    pub(crate) relay_client: Option<relay::ClientConfig>,
+
     /// Notification protocols.
     pub(crate) notification_protocols: HashMap<ProtocolName, notification::Config>,
 
@@ -367,6 +640,37 @@ pub struct Litep2pConfig {
     /// Connection limits config.
     pub(crate) connection_limits: ConnectionLimitsConfig,
 
+    /// Peer reputation config.
+    pub(crate) reputation: ReputationConfig,
+
+    /// IP filter config.
+    pub(crate) ip_filter: IpFilterConfig,
+
+    /// Policy for duplicate connections.
+    pub(crate) duplicate_connection_policy: DuplicateConnectionPolicy,
+
+    /// Substream registry config.
+    pub(crate) substream_registry: Option<SubstreamRegistryConfig>,
+
+    /// Observer of `multistream-select` negotiations.
+    pub(crate) negotiation_observer: Option<NegotiationObserver>,
+
+    /// Negotiate previously negotiated protocols optimistically.
+    pub(crate) optimistic_negotiation: bool,
+
+    /// Maximum number of bytes buffered by all substreams of the node.
+    pub(crate) buffer_budget: Option<usize>,
+
+    /// Rate limit of substreams opened by remote peers over each connection.
+    pub(crate) substream_rate_limit: Option<SubstreamRateLimit>,
+
+    /// Traffic taps of protocols.
+    #[cfg(feature = "traffic-tap")]
+    pub(crate) traffic_taps: HashMap<ProtocolName, TrafficTap>,
+
     /// Close the connection if no substreams are open within this time frame.
     pub(crate) keep_alive_timeout: Duration,
+
+    /// Protocols whose event loops are run on dedicated threads.
+    pub(crate) isolated_protocols: HashSet<ProtocolName>,
 }
@@ -25,6 +25,10 @@ use crate::{error::ParseError, peer_id::*};
 
 pub mod ed25519;
 pub(crate) mod noise;
+#[cfg(feature = "rsa")]
+pub mod rsa;
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
 #[cfg(feature = "quic")]
 pub(crate) mod tls;
 pub(crate) mod keys_proto {
@@ -36,6 +40,14 @@ pub(crate) mod keys_proto {
 pub enum PublicKey {
     /// A public Ed25519 key.
     Ed25519(ed25519::PublicKey),
+
+    /// A public RSA key.
+    #[cfg(feature = "rsa")]
+    Rsa(rsa::PublicKey),
+
+    /// A public Secp256k1 key.
+    #[cfg(feature = "secp256k1")]
+    Secp256k1(secp256k1::PublicKey),
 }
 
 impl PublicKey {
@@ -48,6 +60,10 @@ impl PublicKey {
         use PublicKey::*;
         match self {
             Ed25519(pk) => pk.verify(msg, sig),
+            #[cfg(feature = "rsa")]
+            Rsa(pk) => pk.verify(msg, sig),
+            #[cfg(feature = "secp256k1")]
+            Secp256k1(pk) => pk.verify(msg, sig),
         }
     }
 
@@ -86,6 +102,16 @@ impl From<&PublicKey> for keys_proto::PublicKey {
                 r#type: keys_proto::KeyType::Ed25519 as i32,
                 data: key.to_bytes().to_vec(),
             },
+            #[cfg(feature = "rsa")]
+            PublicKey::Rsa(key) => keys_proto::PublicKey {
+                r#type: keys_proto::KeyType::Rsa as i32,
+                data: key.encode_x509(),
+            },
+            #[cfg(feature = "secp256k1")]
+            PublicKey::Secp256k1(key) => keys_proto::PublicKey {
+                r#type: keys_proto::KeyType::Secp256k1 as i32,
+                data: key.to_bytes().to_vec(),
+            },
         }
     }
 }
@@ -97,10 +123,16 @@ impl TryFrom<keys_proto::PublicKey> for PublicKey {
         let key_type = keys_proto::KeyType::try_from(pubkey.r#type)
             .map_err(|_| ParseError::UnknownKeyType(pubkey.r#type))?;
 
-        if key_type == keys_proto::KeyType::Ed25519 {
-            Ok(ed25519::PublicKey::try_from_bytes(&pubkey.data).map(PublicKey::Ed25519)?)
-        } else {
-            Err(ParseError::UnknownKeyType(key_type as i32))
+        match key_type {
+            keys_proto::KeyType::Ed25519 =>
+                ed25519::PublicKey::try_from_bytes(&pubkey.data).map(PublicKey::Ed25519),
+            #[cfg(feature = "rsa")]
+            keys_proto::KeyType::Rsa =>
+                rsa::PublicKey::try_decode_x509(&pubkey.data).map(PublicKey::Rsa),
+            #[cfg(feature = "secp256k1")]
+            keys_proto::KeyType::Secp256k1 =>
+                secp256k1::PublicKey::try_from_bytes(&pubkey.data).map(PublicKey::Secp256k1),
+            _ => Err(ParseError::UnknownKeyType(key_type as i32)),
         }
     }
 }
@@ -110,3 +142,87 @@ impl From<ed25519::PublicKey> for PublicKey {
         PublicKey::Ed25519(public_key)
     }
 }
+
+#[cfg(feature = "rsa")]
+impl From<rsa::PublicKey> for PublicKey {
+    fn from(public_key: rsa::PublicKey) -> Self {
+        PublicKey::Rsa(public_key)
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+impl From<secp256k1::PublicKey> for PublicKey {
+    fn from(public_key: secp256k1::PublicKey) -> Self {
+        PublicKey::Secp256k1(public_key)
+    }
+}
+
+/// Identity keypair of a node.
+#[derive(Debug, Clone)]
+pub enum Keypair {
+    /// An Ed25519 keypair.
+    Ed25519(ed25519::Keypair),
+
+    /// An RSA keypair.
+    #[cfg(feature = "rsa")]
+    Rsa(rsa::Keypair),
+
+    /// A Secp256k1 keypair.
+    #[cfg(feature = "secp256k1")]
+    Secp256k1(secp256k1::Keypair),
+}
+
+impl Keypair {
+    /// Generate a new random Ed25519 keypair.
+    pub fn generate_ed25519() -> Self {
+        Keypair::Ed25519(ed25519::Keypair::generate())
+    }
+
+    /// Sign a message using the private key of this keypair, producing
+    /// a signature that can be verified using the corresponding public key.
+    pub fn sign(&self, msg: &[u8]) -> crate::Result<Vec<u8>> {
+        match self {
+            Keypair::Ed25519(keypair) => Ok(keypair.sign(msg)),
+            #[cfg(feature = "rsa")]
+            Keypair::Rsa(keypair) => keypair.sign(msg),
+            #[cfg(feature = "secp256k1")]
+            Keypair::Secp256k1(keypair) => Ok(keypair.sign(msg)),
+        }
+    }
+
+    /// Get the public key of this keypair.
+    pub fn public(&self) -> PublicKey {
+        match self {
+            Keypair::Ed25519(keypair) => PublicKey::Ed25519(keypair.public()),
+            #[cfg(feature = "rsa")]
+            Keypair::Rsa(keypair) => PublicKey::Rsa(keypair.public()),
+            #[cfg(feature = "secp256k1")]
+            Keypair::Secp256k1(keypair) => PublicKey::Secp256k1(keypair.public()),
+        }
+    }
+
+    /// Get the `PeerId` derived from the public key of this keypair.
+    pub fn to_peer_id(&self) -> PeerId {
+        self.public().to_peer_id()
+    }
+}
+
+impl From<ed25519::Keypair> for Keypair {
+    fn from(keypair: ed25519::Keypair) -> Self {
+        Keypair::Ed25519(keypair)
+    }
+}
+
+#[cfg(feature = "rsa")]
+impl From<rsa::Keypair> for Keypair {
+    fn from(keypair: rsa::Keypair) -> Self {
+        Keypair::Rsa(keypair)
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+impl From<secp256k1::Keypair> for Keypair {
+    fn from(keypair: secp256k1::Keypair) -> Self {
+        Keypair::Secp256k1(keypair)
+    }
+}
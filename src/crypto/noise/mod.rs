@@ -23,7 +23,7 @@
 
 use crate::{
     config::Role,
-    crypto::{ed25519::Keypair, PublicKey},
+    crypto::{Keypair, PublicKey},
     error::{NegotiationError, ParseError},
     PeerId,
 };
@@ -37,8 +37,14 @@ use std::{
     fmt, io,
     pin::Pin,
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
+#[cfg(feature = "noise-observer")]
+pub use observer::{HandshakeDirection, HandshakeMessage, NoiseObserver};
+
+#[cfg(feature = "noise-observer")]
+mod observer;
 mod protocol;
 mod x25519_spec;
 
@@ -67,12 +73,92 @@ pub(crate) const MAX_READ_AHEAD_FACTOR: usize = 5;
 /// Maximum write buffer size.
 pub(crate) const MAX_WRITE_BUFFER_SIZE: usize = 2;
 
+/// Length of a control frame.
+///
+/// Encrypted frames are never shorter than [`NOISE_EXTRA_ENCRYPT_SPACE`] so a frame of this length
+/// can't be mistaken for data.
+const CONTROL_FRAME_LEN: usize = 1;
+
+/// Control code which marks the rotation of the sender's key.
+const REKEY_CONTROL_CODE: u8 = 0x01;
+
+/// Length-prefixed re-key control frame.
+const REKEY_CONTROL_FRAME: [u8; 2 + CONTROL_FRAME_LEN] =
+    [0x00, CONTROL_FRAME_LEN as u8, REKEY_CONTROL_CODE];
+
 /// Max. length for Noise protocol message payloads.
 pub const MAX_FRAME_LEN: usize = MAX_NOISE_MSG_LEN - NOISE_EXTRA_ENCRYPT_SPACE;
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::crypto::noise";
 
+/// Noise session re-key configuration.
+///
+/// Once either threshold is reached, the next write is preceded by a re-key control frame and is
+/// encrypted with a new sending key derived from the current one. Control frames are shorter than
+/// any encrypted frame so the remote can tell them apart from data. Connection and its substreams
+/// are unaffected by the rotation.
+///
+/// Keys are only rotated if the remote has announced support for re-keying during the handshake.
+/// A forged control frame desynchronizes the keys, which makes the next frame fail to decrypt and
+/// closes the connection, the same as any other tampering with the stream.
+#[derive(Debug, Clone, Copy)]
+pub struct RekeyConfig {
+    /// Rotate the sending key after this many bytes have been encrypted with it.
+    ///
+    /// Defaults to `1 GiB`.
+    pub bytes: Option<u64>,
+
+    /// Rotate the sending key after it has been in use for this long.
+    ///
+    /// Defaults to `1 hour`.
+    pub interval: Option<Duration>,
+}
+
+impl Default for RekeyConfig {
+    fn default() -> Self {
+        Self {
+            bytes: Some(1024 * 1024 * 1024),
+            interval: Some(Duration::from_secs(60 * 60)),
+        }
+    }
+}
+
+/// Sending key rotation state.
+struct Rekey {
+    /// Re-key configuration.
+    config: RekeyConfig,
+
+    /// Number of bytes encrypted with the current sending key.
+    bytes: u64,
+
+    /// When was the current sending key taken into use.
+    since: Instant,
+}
+
+impl Rekey {
+    /// Create new [`Rekey`].
+    fn new(config: RekeyConfig) -> Self {
+        Self {
+            config,
+            bytes: 0u64,
+            since: Instant::now(),
+        }
+    }
+
+    /// Check if the sending key should be rotated.
+    fn is_due(&self) -> bool {
+        self.config.bytes.is_some_and(|bytes| self.bytes >= bytes)
+            || self.config.interval.is_some_and(|interval| self.since.elapsed() >= interval)
+    }
+
+    /// Reset the counters after the sending key has been rotated.
+    fn reset(&mut self) {
+        self.bytes = 0u64;
+        self.since = Instant::now();
+    }
+}
+
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 enum NoiseState {
@@ -85,6 +171,8 @@ pub struct NoiseContext {
     noise: NoiseState,
     role: Role,
     pub payload: Vec<u8>,
+    #[cfg(feature = "noise-observer")]
+    observer: Option<NoiseObserver>,
 }
 
 impl fmt::Debug for NoiseContext {
@@ -106,11 +194,16 @@ impl NoiseContext {
         role: Role,
     ) -> Result<Self, NegotiationError> {
         let noise_payload = handshake_schema::NoiseHandshakePayload {
-            identity_key: Some(PublicKey::Ed25519(id_keys.public()).to_protobuf_encoding()),
+            identity_key: Some(id_keys.public().to_protobuf_encoding()),
             identity_sig: Some(
-                id_keys.sign(&[STATIC_KEY_DOMAIN.as_bytes(), keypair.public.as_ref()].concat()),
+                id_keys
+                    .sign(&[STATIC_KEY_DOMAIN.as_bytes(), keypair.public.as_ref()].concat())
+                    .map_err(|_| NegotiationError::SigningFailed)?,
             ),
-            ..Default::default()
+            extensions: Some(handshake_schema::NoiseExtensions {
+                rekey: Some(true),
+                ..Default::default()
+            }),
         };
 
         let mut payload = Vec::with_capacity(noise_payload.encoded_len());
@@ -121,6 +214,8 @@ impl NoiseContext {
             keypair,
             payload,
             role,
+            #[cfg(feature = "noise-observer")]
+            observer: None,
         })
     }
 
@@ -143,9 +238,28 @@ impl NoiseContext {
         Self::assemble(noise, dh_keypair, keypair, role)
     }
 
+    /// Attach observer which receives a copy of each handshake message.
+    #[cfg(feature = "noise-observer")]
+    pub fn with_observer(mut self, observer: Option<NoiseObserver>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Copy handshake message to the observer, if one is attached.
+    #[cfg(feature = "noise-observer")]
+    fn observe(&self, direction: HandshakeDirection, message: &[u8]) {
+        if let Some(observer) = &self.observer {
+            observer.observe(direction, message);
+        }
+    }
+
     /// Create new [`NoiseContext`] with prologue.
     #[cfg(feature = "webrtc")]
-    pub fn with_prologue(id_keys: &Keypair, prologue: Vec<u8>) -> Result<Self, NegotiationError> {
+    pub fn with_prologue(
+        id_keys: &Keypair,
+        prologue: Vec<u8>,
+        role: Role,
+    ) -> Result<Self, NegotiationError> {
         let noise: Builder<'_> = Builder::with_resolver(
             NOISE_PARAMETERS.parse().expect("qed; Valid noise pattern"),
             Box::new(protocol::Resolver),
@@ -153,17 +267,30 @@ impl NoiseContext {
 
         let keypair = noise.generate_keypair()?;
 
-        let noise = noise
-            .local_private_key(&keypair.private)
-            .prologue(&prologue)
-            .build_initiator()?;
+        let noise = noise.local_private_key(&keypair.private).prologue(&prologue);
+        let noise = match role {
+            Role::Dialer => noise.build_initiator()?,
+            Role::Listener => noise.build_responder()?,
+        };
 
-        Self::assemble(noise, keypair, id_keys, Role::Dialer)
+        Self::assemble(noise, keypair, id_keys, role)
     }
 
     /// Get remote public key from the received Noise payload.
     #[cfg(feature = "webrtc")]
     pub fn get_remote_public_key(&mut self, reply: &[u8]) -> Result<PublicKey, NegotiationError> {
+        let buffer = self.read_message(reply)?;
+
+        let payload = handshake_schema::NoiseHandshakePayload::decode(buffer.as_slice())
+            .map_err(|err| NegotiationError::ParseError(err.into()))?;
+
+        let identity = payload.identity_key.ok_or(NegotiationError::PeerIdMissing)?;
+        PublicKey::from_protobuf_encoding(&identity).map_err(|err| err.into())
+    }
+
+    /// Read length-prefixed handshake message and return its payload.
+    #[cfg(feature = "webrtc")]
+    pub fn read_message(&mut self, reply: &[u8]) -> Result<Vec<u8>, NegotiationError> {
         let (len_slice, reply) = reply.split_at(2);
         let len = u16::from_be_bytes(
             len_slice
@@ -182,11 +309,10 @@ impl NoiseContext {
         let res = noise.read_message(reply, &mut buffer)?;
         buffer.truncate(res);
 
-        let payload = handshake_schema::NoiseHandshakePayload::decode(buffer.as_slice())
-            .map_err(|err| NegotiationError::ParseError(err.into()))?;
+        #[cfg(feature = "noise-observer")]
+        self.observe(HandshakeDirection::Received, reply);
 
-        let identity = payload.identity_key.ok_or(NegotiationError::PeerIdMissing)?;
-        PublicKey::from_protobuf_encoding(&identity).map_err(|err| err.into())
+        Ok(buffer)
     }
 
     /// Get first message.
@@ -207,6 +333,9 @@ impl NoiseContext {
                 let nwritten = noise.write_message(&[], &mut buffer)?;
                 buffer.truncate(nwritten);
 
+                #[cfg(feature = "noise-observer")]
+                self.observe(HandshakeDirection::Sent, &buffer);
+
                 let size = nwritten as u16;
                 let mut size = size.to_be_bytes().to_vec();
                 size.append(&mut buffer);
@@ -233,6 +362,9 @@ impl NoiseContext {
         let nwritten = noise.write_message(&self.payload, &mut buffer)?;
         buffer.truncate(nwritten);
 
+        #[cfg(feature = "noise-observer")]
+        self.observe(HandshakeDirection::Sent, &buffer);
+
         let size = nwritten as u16;
         let mut size = size.to_be_bytes().to_vec();
         size.append(&mut buffer);
@@ -252,6 +384,9 @@ impl NoiseContext {
         let mut message = BytesMut::zeroed(size as usize);
         io.read_exact(&mut message).await?;
 
+        #[cfg(feature = "noise-observer")]
+        self.observe(HandshakeDirection::Received, &message);
+
         let mut out = BytesMut::new();
         out.resize(message.len() + 200, 0u8); // TODO: correct overhead
 
@@ -281,6 +416,20 @@ impl NoiseContext {
         }
     }
 
+    /// Rotate the key used for encrypting outbound messages.
+    fn rekey_outgoing(&mut self) {
+        if let NoiseState::Transport(ref mut noise) = self.noise {
+            noise.rekey_outgoing();
+        }
+    }
+
+    /// Rotate the key used for decrypting inbound messages.
+    fn rekey_incoming(&mut self) {
+        if let NoiseState::Transport(ref mut noise) = self.noise {
+            noise.rekey_incoming();
+        }
+    }
+
     /// Convert Noise into transport mode.
     fn into_transport(self) -> Result<NoiseContext, NegotiationError> {
         let transport = match self.noise {
@@ -293,6 +442,8 @@ impl NoiseContext {
             payload: self.payload,
             role: self.role,
             noise: NoiseState::Transport(transport),
+            #[cfg(feature = "noise-observer")]
+            observer: None,
         })
     }
 }
@@ -335,6 +486,8 @@ pub struct NoiseSocket<S: AsyncRead + AsyncWrite + Unpin> {
     read_buffer: Vec<u8>,
     canonical_max_read: usize,
     decrypt_buffer: Option<Vec<u8>>,
+    rekey: Option<Rekey>,
+    remote_rekey: bool,
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> NoiseSocket<S> {
@@ -343,6 +496,8 @@ impl<S: AsyncRead + AsyncWrite + Unpin> NoiseSocket<S> {
         noise: NoiseContext,
         max_read_ahead_factor: usize,
         max_write_buffer_size: usize,
+        rekey: Option<RekeyConfig>,
+        remote_rekey: bool,
     ) -> Self {
         Self {
             io,
@@ -359,12 +514,19 @@ impl<S: AsyncRead + AsyncWrite + Unpin> NoiseSocket<S> {
                 size: 0usize,
                 encrypted_size: 0usize,
             },
-            encrypt_buffer: vec![0u8; max_write_buffer_size * (MAX_NOISE_MSG_LEN + 2)],
+            // reserve space for a re-key control frame in addition to the data frames
+            encrypt_buffer: vec![
+                0u8;
+                max_write_buffer_size * (MAX_NOISE_MSG_LEN + 2)
+                    + REKEY_CONTROL_FRAME.len()
+            ],
             decrypt_buffer: Some(vec![0u8; MAX_FRAME_LEN]),
             read_state: ReadState::ReadData {
                 max_read: max_read_ahead_factor * MAX_NOISE_MSG_LEN,
             },
             canonical_max_read: max_read_ahead_factor * MAX_NOISE_MSG_LEN,
+            rekey: rekey.map(Rekey::new),
+            remote_rekey,
         }
     }
 
@@ -471,7 +633,22 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for NoiseSocket<S> {
                         continue;
                     }
 
-                    if frame_size <= NOISE_EXTRA_ENCRYPT_SPACE {
+                    if frame_size == CONTROL_FRAME_LEN && this.remote_rekey {
+                        let control = this.read_buffer[this.offset];
+
+                        if control != REKEY_CONTROL_CODE {
+                            tracing::error!(target: LOG_TARGET, ?control, "unknown control frame");
+                            return Poll::Ready(Err(io::ErrorKind::InvalidData.into()));
+                        }
+
+                        tracing::trace!(target: LOG_TARGET, "remote rotated its sending key");
+
+                        this.noise.rekey_incoming();
+                        this.offset += frame_size;
+                        continue;
+                    }
+
+                    if frame_size < NOISE_EXTRA_ENCRYPT_SPACE {
                         tracing::error!(
                             target: LOG_TARGET,
                             ?frame_size,
@@ -481,6 +658,25 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for NoiseSocket<S> {
                         return Poll::Ready(Err(io::ErrorKind::InvalidData.into()));
                     }
 
+                    // frame with an empty payload carries no data, skip it so it's not reported
+                    // as end of stream
+                    if frame_size == NOISE_EXTRA_ENCRYPT_SPACE {
+                        if let Err(error) = this.noise.read_message(
+                            &this.read_buffer[this.offset..this.offset + frame_size],
+                            &mut [],
+                        ) {
+                            tracing::error!(
+                                target: LOG_TARGET,
+                                ?error,
+                                "failed to decrypt message",
+                            );
+                            return Poll::Ready(Err(io::ErrorKind::InvalidData.into()));
+                        }
+
+                        this.offset += frame_size;
+                        continue;
+                    }
+
                     this.current_frame_size = Some(frame_size);
                     this.read_state = ReadState::ProcessNextFrame {
                         pending: None,
@@ -580,14 +776,29 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for NoiseSocket<S> {
         loop {
             match this.write_state {
                 WriteState::Ready {
-                    offset,
+                    mut offset,
                     size,
-                    encrypted_size,
+                    mut encrypted_size,
                 } => {
                     let Some(chunk) = chunks.next() else {
                         break;
                     };
 
+                    if offset == 0 && this.rekey.as_ref().is_some_and(|rekey| rekey.is_due()) {
+                        // signal the rotation in front of the first frame encrypted with the new
+                        // key
+                        let control_len = REKEY_CONTROL_FRAME.len();
+
+                        this.encrypt_buffer[..control_len].copy_from_slice(&REKEY_CONTROL_FRAME);
+                        this.noise.rekey_outgoing();
+                        this.rekey.as_mut().expect("re-key to exist").reset();
+
+                        tracing::trace!(target: LOG_TARGET, "rotated sending key");
+
+                        offset += control_len;
+                        encrypted_size += control_len;
+                    }
+
                     match this.noise.write_message(chunk, &mut this.encrypt_buffer[offset + 2..]) {
                         Err(error) => {
                             tracing::error!(target: LOG_TARGET, ?error, "failed to encrypt message");
@@ -597,6 +808,10 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for NoiseSocket<S> {
                             this.encrypt_buffer[offset] = (nwritten >> 8) as u8;
                             this.encrypt_buffer[offset + 1] = (nwritten & 0xff) as u8;
 
+                            if let Some(rekey) = this.rekey.as_mut() {
+                                rekey.bytes += chunk.len() as u64;
+                            }
+
                             if let Some(next_chunk) = chunks.peek() {
                                 if next_chunk.len() + NOISE_EXTRA_ENCRYPT_SPACE + 2
                                     <= this.encrypt_buffer[offset + nwritten + 2..].len()
@@ -656,14 +871,17 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for NoiseSocket<S> {
     }
 }
 
-/// Try to parse `PeerId` from received `NoiseHandshakePayload`
-fn parse_peer_id(buf: &[u8]) -> Result<PeerId, NegotiationError> {
+/// Try to parse `PeerId` from received `NoiseHandshakePayload`.
+///
+/// Also returns whether the remote understands re-key control frames.
+fn parse_peer_id(buf: &[u8]) -> Result<(PeerId, bool), NegotiationError> {
     match handshake_schema::NoiseHandshakePayload::decode(buf) {
         Ok(payload) => {
             let identity = payload.identity_key.ok_or(NegotiationError::PeerIdMissing)?;
+            let rekey = payload.extensions.and_then(|extensions| extensions.rekey);
 
             let public_key = PublicKey::from_protobuf_encoding(&identity)?;
-            Ok(PeerId::from_public_key(&public_key))
+            Ok((PeerId::from_public_key(&public_key), rekey.unwrap_or(false)))
         }
         Err(err) => Err(ParseError::from(err).into()),
     }
@@ -676,11 +894,17 @@ pub async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
     role: Role,
     max_read_ahead_factor: usize,
     max_write_buffer_size: usize,
+    rekey: Option<RekeyConfig>,
+    #[cfg(feature = "noise-observer")] observer: Option<NoiseObserver>,
 ) -> Result<(NoiseSocket<S>, PeerId), NegotiationError> {
     tracing::debug!(target: LOG_TARGET, ?role, "start noise handshake");
 
     let mut noise = NoiseContext::new(keypair, role)?;
-    let peer = match role {
+    #[cfg(feature = "noise-observer")]
+    {
+        noise = noise.with_observer(observer);
+    }
+    let (peer, remote_rekey) = match role {
         Role::Dialer => {
             // write initial message
             let first_message = noise.first_message(Role::Dialer)?;
@@ -712,12 +936,18 @@ pub async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
         }
     };
 
+    if rekey.is_some() && !remote_rekey {
+        tracing::debug!(target: LOG_TARGET, ?peer, "remote doesn't support re-keying");
+    }
+
     Ok((
         NoiseSocket::new(
             io,
             noise.into_transport()?,
             max_read_ahead_factor,
             max_write_buffer_size,
+            rekey.filter(|_| remote_rekey),
+            remote_rekey,
         ),
         peer,
     ))
@@ -727,21 +957,22 @@ pub async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crypto::ed25519;
+    #[cfg(feature = "rsa")]
+    use crate::crypto::rsa;
+    #[cfg(feature = "secp256k1")]
+    use crate::crypto::secp256k1;
     use std::net::SocketAddr;
     use tokio::net::{TcpListener, TcpStream};
     use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
-    #[tokio::test]
-    async fn noise_handshake() {
+    async fn noise_handshake_with_keypairs(keypair1: Keypair, keypair2: Keypair) {
         let _ = tracing_subscriber::fmt()
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
             .try_init();
 
-        let keypair1 = Keypair::generate();
-        let keypair2 = Keypair::generate();
-
-        let peer1_id = PeerId::from_public_key(&keypair1.public().into());
-        let peer2_id = PeerId::from_public_key(&keypair2.public().into());
+        let peer1_id = keypair1.to_peer_id();
+        let peer2_id = keypair2.to_peer_id();
 
         let listener = TcpListener::bind("[::1]:0".parse::<SocketAddr>().unwrap()).await.unwrap();
 
@@ -764,14 +995,20 @@ mod tests {
                 &keypair1,
                 Role::Dialer,
                 MAX_READ_AHEAD_FACTOR,
-                MAX_WRITE_BUFFER_SIZE
+                MAX_WRITE_BUFFER_SIZE,
+                None,
+                #[cfg(feature = "noise-observer")]
+                None,
             ),
             handshake(
                 io2,
                 &keypair2,
                 Role::Listener,
                 MAX_READ_AHEAD_FACTOR,
-                MAX_WRITE_BUFFER_SIZE
+                MAX_WRITE_BUFFER_SIZE,
+                None,
+                #[cfg(feature = "noise-observer")]
+                None,
             )
         );
         let (mut res1, mut res2) = (res1.unwrap(), res2.unwrap());
@@ -787,6 +1024,183 @@ mod tests {
         assert_eq!(std::str::from_utf8(&buf[..sent]), Ok("hello, world"));
     }
 
+    #[tokio::test]
+    async fn noise_handshake() {
+        noise_handshake_with_keypairs(
+            ed25519::Keypair::generate().into(),
+            ed25519::Keypair::generate().into(),
+        )
+        .await;
+    }
+
+    #[cfg(feature = "rsa")]
+    #[tokio::test]
+    async fn noise_handshake_with_rsa_identity() {
+        let mut der = include_bytes!("../test/rsa-2048.pk8").to_vec();
+        let rsa = rsa::Keypair::try_decode_pkcs8(&mut der).unwrap();
+
+        noise_handshake_with_keypairs(rsa.into(), ed25519::Keypair::generate().into()).await;
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[tokio::test]
+    async fn noise_handshake_with_secp256k1_identity() {
+        noise_handshake_with_keypairs(
+            secp256k1::Keypair::generate().into(),
+            ed25519::Keypair::generate().into(),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn substreams_survive_key_rotation() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let keypair1: Keypair = ed25519::Keypair::generate().into();
+        let keypair2: Keypair = ed25519::Keypair::generate().into();
+        let (io1, io2) = tokio::io::duplex(64 * 1024);
+        let rekey = |bytes| RekeyConfig {
+            bytes: Some(bytes),
+            interval: None,
+        };
+
+        let (res1, res2) = tokio::join!(
+            handshake(
+                TokioAsyncReadCompatExt::compat(io1),
+                &keypair1,
+                Role::Dialer,
+                MAX_READ_AHEAD_FACTOR,
+                MAX_WRITE_BUFFER_SIZE,
+                Some(rekey(4 * 1024)),
+                #[cfg(feature = "noise-observer")]
+                None,
+            ),
+            handshake(
+                TokioAsyncReadCompatExt::compat(io2),
+                &keypair2,
+                Role::Listener,
+                MAX_READ_AHEAD_FACTOR,
+                MAX_WRITE_BUFFER_SIZE,
+                Some(rekey(10 * 1024)),
+                #[cfg(feature = "noise-observer")]
+                None,
+            )
+        );
+        let ((socket1, _), (socket2, _)) = (res1.unwrap(), res2.unwrap());
+
+        // both peers announced support so both rotate their sending keys
+        assert!(socket1.rekey.is_some());
+        assert!(socket2.rekey.is_some());
+
+        let mut client = crate::yamux::Connection::new(
+            socket1,
+            crate::yamux::Config::default(),
+            crate::yamux::Mode::Client,
+        );
+        let mut server = crate::yamux::Connection::new(
+            socket2,
+            crate::yamux::Config::default(),
+            crate::yamux::Mode::Server,
+        );
+
+        // echo everything written to inbound substreams back to the sender
+        tokio::spawn(async move {
+            while let Some(Ok(mut stream)) =
+                futures::future::poll_fn(|cx| server.poll_next_inbound(cx)).await
+            {
+                tokio::spawn(async move {
+                    let mut buffer = vec![0u8; 1024];
+
+                    loop {
+                        match stream.read(&mut buffer).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(nread) => stream.write_all(&buffer[..nread]).await.unwrap(),
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut streams = Vec::new();
+        for _ in 0..2 {
+            streams
+                .push(futures::future::poll_fn(|cx| client.poll_new_outbound(cx)).await.unwrap());
+        }
+        tokio::spawn(async move {
+            while let Some(Ok(_)) =
+                futures::future::poll_fn(|cx| client.poll_next_inbound(cx)).await
+            {}
+        });
+
+        // send enough data over both substreams to rotate the keys many times in both directions
+        let tasks = streams.into_iter().enumerate().map(|(i, mut stream)| async move {
+            let payload = (0..256 * 1024).map(|byte| (byte + i) as u8).collect::<Vec<_>>();
+
+            for chunk in payload.chunks(3000) {
+                let mut echo = vec![0u8; chunk.len()];
+
+                stream.write_all(chunk).await.unwrap();
+                stream.read_exact(&mut echo).await.unwrap();
+                assert_eq!(echo, chunk);
+            }
+        });
+
+        tokio::time::timeout(
+            std::time::Duration::from_secs(20),
+            futures::future::join_all(tasks),
+        )
+        .await
+        .expect("substreams to keep working across key rotations");
+    }
+
+    #[tokio::test]
+    async fn empty_frame_is_not_a_rekey() {
+        let keypair1: Keypair = ed25519::Keypair::generate().into();
+        let keypair2: Keypair = ed25519::Keypair::generate().into();
+        let (io1, io2) = tokio::io::duplex(64 * 1024);
+
+        let (res1, res2) = tokio::join!(
+            handshake(
+                TokioAsyncReadCompatExt::compat(io1),
+                &keypair1,
+                Role::Dialer,
+                MAX_READ_AHEAD_FACTOR,
+                MAX_WRITE_BUFFER_SIZE,
+                None,
+                #[cfg(feature = "noise-observer")]
+                None,
+            ),
+            handshake(
+                TokioAsyncReadCompatExt::compat(io2),
+                &keypair2,
+                Role::Listener,
+                MAX_READ_AHEAD_FACTOR,
+                MAX_WRITE_BUFFER_SIZE,
+                None,
+                #[cfg(feature = "noise-observer")]
+                None,
+            )
+        );
+        let ((mut socket1, _), (mut socket2, _)) = (res1.unwrap(), res2.unwrap());
+        assert!(socket2.remote_rekey);
+
+        // write a frame with an empty payload directly to the socket
+        let mut frame = vec![0u8; 2 + NOISE_EXTRA_ENCRYPT_SPACE];
+        let nwritten = socket1.noise.write_message(&[], &mut frame[2..]).unwrap();
+        frame[..2].copy_from_slice(&(nwritten as u16).to_be_bytes());
+        socket1.io.write_all(&frame).await.unwrap();
+
+        socket1.write_all(b"hello, world").await.unwrap();
+        socket1.flush().await.unwrap();
+
+        // the empty frame is skipped and the keys stay in sync
+        let mut buffer = vec![0u8; 12];
+        socket2.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, b"hello, world");
+    }
+
     #[test]
     fn invalid_peer_id_schema() {
         match parse_peer_id(&vec![1, 2, 3, 4]).unwrap_err() {
@@ -794,4 +1208,68 @@ mod tests {
             _ => panic!("invalid error"),
         }
     }
+
+    #[cfg(feature = "noise-observer")]
+    #[tokio::test]
+    async fn handshake_messages_observed() {
+        let keypair1: Keypair = ed25519::Keypair::generate().into();
+        let keypair2: Keypair = ed25519::Keypair::generate().into();
+        let (io1, io2) = tokio::io::duplex(64 * 1024);
+        let (tx1, mut rx1) = tokio::sync::mpsc::channel(8);
+        let (tx2, mut rx2) = tokio::sync::mpsc::channel(8);
+
+        let (res1, res2) = tokio::join!(
+            handshake(
+                TokioAsyncReadCompatExt::compat(io1),
+                &keypair1,
+                Role::Dialer,
+                MAX_READ_AHEAD_FACTOR,
+                MAX_WRITE_BUFFER_SIZE,
+                None,
+                Some(NoiseObserver::new(tx1)),
+            ),
+            handshake(
+                TokioAsyncReadCompatExt::compat(io2),
+                &keypair2,
+                Role::Listener,
+                MAX_READ_AHEAD_FACTOR,
+                MAX_WRITE_BUFFER_SIZE,
+                None,
+                Some(NoiseObserver::new(tx2)),
+            )
+        );
+        let (_, _) = (res1.unwrap(), res2.unwrap());
+
+        let mut dialer = Vec::new();
+        while let Ok(message) = rx1.try_recv() {
+            dialer.push(message);
+        }
+        let mut listener = Vec::new();
+        while let Ok(message) = rx2.try_recv() {
+            listener.push(message);
+        }
+
+        // XX pattern: -> e, <- e ee s es, -> s se
+        assert_eq!(
+            dialer.iter().map(|message| message.direction).collect::<Vec<_>>(),
+            vec![
+                HandshakeDirection::Sent,
+                HandshakeDirection::Received,
+                HandshakeDirection::Sent
+            ],
+        );
+        assert_eq!(
+            listener.iter().map(|message| message.direction).collect::<Vec<_>>(),
+            vec![
+                HandshakeDirection::Received,
+                HandshakeDirection::Sent,
+                HandshakeDirection::Received
+            ],
+        );
+
+        // both sides observed the same bytes
+        for (sent, received) in dialer.iter().zip(listener.iter()) {
+            assert_eq!(sent.message, received.message);
+        }
+    }
 }
@@ -0,0 +1,82 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Observer of raw Noise handshake messages.
+
+use bytes::Bytes;
+use tokio::sync::mpsc::{error::TrySendError, Sender};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::crypto::noise::observer";
+
+/// Direction of an observed handshake message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeDirection {
+    /// Message was sent to the remote peer.
+    Sent,
+
+    /// Message was received from the remote peer.
+    Received,
+}
+
+/// Noise handshake message copied to a [`NoiseObserver`].
+#[derive(Debug, Clone)]
+pub struct HandshakeMessage {
+    /// Direction of the message.
+    pub direction: HandshakeDirection,
+
+    /// Message as it appears on the wire, without the two-byte length prefix.
+    pub message: Bytes,
+}
+
+/// Observer which receives a copy of each Noise handshake message sent or received by the
+/// local node.
+///
+/// Meant for debugging handshake failures against other libp2p implementations. Messages are
+/// delivered with [`Sender::try_send()`] so a slow reader never stalls the handshake. If the
+/// channel is full, the message is dropped.
+#[derive(Debug, Clone)]
+pub struct NoiseObserver {
+    /// TX channel for sending the observed messages.
+    tx: Sender<HandshakeMessage>,
+}
+
+impl NoiseObserver {
+    /// Create new [`NoiseObserver`] which sends the observed messages to `tx`.
+    pub fn new(tx: Sender<HandshakeMessage>) -> Self {
+        Self { tx }
+    }
+
+    /// Copy handshake `message` to the observer.
+    pub(crate) fn observe(&self, direction: HandshakeDirection, message: &[u8]) {
+        let message = HandshakeMessage {
+            direction,
+            message: Bytes::copy_from_slice(message),
+        };
+
+        if let Err(TrySendError::Full(_)) = self.tx.try_send(message) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?direction,
+                "observer is full, dropping handshake message",
+            );
+        }
+    }
+}
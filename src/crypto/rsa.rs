@@ -0,0 +1,211 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! RSA keys.
+//!
+//! Only keypairs decoded from PKCS#8 are supported, key generation is not.
+
+use crate::{
+    error::{Error, ParseError},
+    PeerId,
+};
+
+use ring::{
+    rand::SystemRandom,
+    signature::{self, KeyPair as _, RsaKeyPair},
+};
+use yasna::{models::ObjectIdentifier, DERWriterSeq};
+use zeroize::Zeroize;
+
+use std::{fmt, sync::Arc};
+
+/// Object identifier of `rsaEncryption`, 1.2.840.113549.1.1.1.
+const RSA_ENCRYPTION_OID: &[u64] = &[1, 2, 840, 113549, 1, 1, 1];
+
+/// An RSA keypair.
+#[derive(Clone)]
+pub struct Keypair(Arc<RsaKeyPair>);
+
+impl Keypair {
+    /// Decode an RSA keypair from a DER-encoded private key in PKCS#8 `PrivateKeyInfo`
+    /// format, zeroing the input on success.
+    pub fn try_decode_pkcs8(der: &mut [u8]) -> Result<Keypair, Error> {
+        let keypair = RsaKeyPair::from_pkcs8(der)
+            .map_err(|e| Error::Other(format!("Failed to parse rsa keypair: {e}")))?;
+        der.zeroize();
+        Ok(Keypair(Arc::new(keypair)))
+    }
+
+    /// Get the public key of this keypair.
+    pub fn public(&self) -> PublicKey {
+        PublicKey(self.0.public_key().as_ref().to_vec())
+    }
+
+    /// Sign a message using the private key of this keypair.
+    ///
+    /// The signature uses PKCS#1 v1.5 padding over the SHA256 hash of the message.
+    pub fn sign(&self, msg: &[u8]) -> crate::Result<Vec<u8>> {
+        let mut signature = vec![0; self.0.public_modulus_len()];
+        let rng = SystemRandom::new();
+
+        self.0
+            .sign(&signature::RSA_PKCS1_SHA256, &rng, msg, &mut signature)
+            .map_err(|_| Error::Other(String::from("Failed to sign message with rsa key")))?;
+
+        Ok(signature)
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair").field("public", self.0.public_key()).finish()
+    }
+}
+
+/// An RSA public key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PublicKey(Vec<u8>);
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PublicKey(PKCS1): ")?;
+        for byte in &self.0 {
+            write!(f, "{byte:x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl PublicKey {
+    /// Verify an RSA signature on a message using the public key.
+    pub fn verify(&self, msg: &[u8], sig: &[u8]) -> bool {
+        let key =
+            signature::UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, &self.0);
+        key.verify(msg, sig).is_ok()
+    }
+
+    /// Encode the RSA public key in DER as a PKCS#1 `RSAPublicKey` structure.
+    pub fn to_pkcs1(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+
+    /// Encode the RSA public key in DER as an X.509 `SubjectPublicKeyInfo` structure.
+    pub fn encode_x509(&self) -> Vec<u8> {
+        yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                write_algorithm_identifier(writer);
+                writer.next().write_bitvec_bytes(&self.0, self.0.len() * 8);
+            })
+        })
+    }
+
+    /// Decode an RSA public key from a DER-encoded X.509 `SubjectPublicKeyInfo` structure.
+    pub fn try_decode_x509(der: &[u8]) -> Result<PublicKey, ParseError> {
+        yasna::parse_der(der, |reader| {
+            reader.read_sequence(|reader| {
+                reader.next().read_sequence(|reader| {
+                    let oid = reader.next().read_oid()?;
+                    reader.next().read_null()?;
+
+                    if oid != ObjectIdentifier::from_slice(RSA_ENCRYPTION_OID) {
+                        return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid));
+                    }
+
+                    Ok(())
+                })?;
+
+                let (key, bits) = reader.next().read_bitvec_bytes()?;
+                if bits % 8 != 0 {
+                    return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid));
+                }
+
+                Ok(PublicKey(key))
+            })
+        })
+        .map_err(|_| ParseError::InvalidPublicKey)
+    }
+
+    /// Convert public key to `PeerId`.
+    pub fn to_peer_id(&self) -> PeerId {
+        crate::crypto::PublicKey::Rsa(self.clone()).into()
+    }
+}
+
+/// Write the `AlgorithmIdentifier` of `rsaEncryption` with `NULL` parameters.
+fn write_algorithm_identifier(writer: &mut DERWriterSeq<'_>) {
+    writer.next().write_sequence(|writer| {
+        writer.next().write_oid(&ObjectIdentifier::from_slice(RSA_ENCRYPTION_OID));
+        writer.next().write_null();
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = include_bytes!("test/rsa-2048.pk8");
+
+    fn keypair() -> Keypair {
+        Keypair::try_decode_pkcs8(&mut KEY.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn rsa_signature() {
+        let kp = keypair();
+        let pk = kp.public();
+
+        let msg = "hello world".as_bytes();
+        let sig = kp.sign(msg).unwrap();
+        assert!(pk.verify(msg, &sig));
+
+        let invalid_msg = "h3ll0 w0rld".as_bytes();
+        assert!(!pk.verify(invalid_msg, &sig));
+    }
+
+    #[test]
+    fn rsa_x509_encode_decode() {
+        let pk = keypair().public();
+
+        assert_eq!(PublicKey::try_decode_x509(&pk.encode_x509()).unwrap(), pk);
+        assert!(PublicKey::try_decode_x509(&pk.to_pkcs1()).is_err());
+    }
+
+    #[test]
+    fn rsa_peer_id_matches_known_vector() {
+        let public = crate::crypto::PublicKey::from(keypair().public());
+
+        assert_eq!(public.to_protobuf_encoding().len(), 299);
+        assert_eq!(
+            public.to_peer_id(),
+            "QmVTX1rPJgdfygsqAM5jmXKcWX6yM4U2ranJYMZELgmJzx".parse::<PeerId>().unwrap()
+        );
+        assert_eq!(
+            crate::crypto::PublicKey::from_protobuf_encoding(&public.to_protobuf_encoding())
+                .unwrap(),
+            public
+        );
+    }
+
+    #[test]
+    fn invalid_pkcs8_is_rejected() {
+        let mut der = KEY[..KEY.len() / 2].to_vec();
+        assert!(Keypair::try_decode_pkcs8(&mut der).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Secp256k1 keys.
+
+use crate::{
+    error::{Error, ParseError},
+    PeerId,
+};
+
+use libsecp256k1::{Message, Signature};
+use sha2::{Digest as _, Sha256};
+use zeroize::Zeroize;
+
+use std::fmt;
+
+/// A Secp256k1 keypair.
+#[derive(Clone)]
+pub struct Keypair {
+    secret: SecretKey,
+    public: PublicKey,
+}
+
+impl Keypair {
+    /// Generate a new random Secp256k1 keypair.
+    pub fn generate() -> Keypair {
+        Keypair::from(SecretKey::generate())
+    }
+
+    /// Sign a message using the private key of this keypair.
+    ///
+    /// The message is hashed using SHA256 and the signature is DER-encoded.
+    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        self.secret.sign(msg)
+    }
+
+    /// Get the public key of this keypair.
+    pub fn public(&self) -> PublicKey {
+        self.public.clone()
+    }
+
+    /// Get the secret key of this keypair.
+    pub fn secret(&self) -> SecretKey {
+        self.secret.clone()
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair").field("public", &self.public).finish()
+    }
+}
+
+/// Promote a Secp256k1 secret key into a keypair.
+impl From<SecretKey> for Keypair {
+    fn from(secret: SecretKey) -> Keypair {
+        let public = PublicKey(libsecp256k1::PublicKey::from_secret_key(&secret.0));
+        Keypair { secret, public }
+    }
+}
+
+/// Demote a Secp256k1 keypair to a secret key.
+impl From<Keypair> for SecretKey {
+    fn from(kp: Keypair) -> SecretKey {
+        kp.secret
+    }
+}
+
+/// A Secp256k1 public key.
+#[derive(PartialEq, Eq, Clone)]
+pub struct PublicKey(libsecp256k1::PublicKey);
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PublicKey(compressed): ")?;
+        for byte in &self.to_bytes() {
+            write!(f, "{byte:x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl PublicKey {
+    /// Verify the Secp256k1 signature on a message using the public key.
+    ///
+    /// The signature is expected to be DER-encoded and made over the SHA256 hash of the message.
+    pub fn verify(&self, msg: &[u8], sig: &[u8]) -> bool {
+        let message = Message::parse(&Sha256::digest(msg).into());
+
+        Signature::parse_der(sig)
+            .map(|sig| libsecp256k1::verify(&message, &sig, &self.0))
+            .unwrap_or(false)
+    }
+
+    /// Convert the public key to a byte array in compressed form.
+    pub fn to_bytes(&self) -> [u8; 33] {
+        self.0.serialize_compressed()
+    }
+
+    /// Try to parse a public key from a byte array containing the compressed key as produced
+    /// by `to_bytes`.
+    pub fn try_from_bytes(k: &[u8]) -> Result<PublicKey, ParseError> {
+        libsecp256k1::PublicKey::parse_slice(k, Some(libsecp256k1::PublicKeyFormat::Compressed))
+            .map_err(|_| ParseError::InvalidPublicKey)
+            .map(PublicKey)
+    }
+
+    /// Convert public key to `PeerId`.
+    pub fn to_peer_id(&self) -> PeerId {
+        crate::crypto::PublicKey::Secp256k1(self.clone()).into()
+    }
+}
+
+/// A Secp256k1 secret key.
+#[derive(Clone)]
+pub struct SecretKey(libsecp256k1::SecretKey);
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey")
+    }
+}
+
+impl SecretKey {
+    /// Generate a new random Secp256k1 secret key.
+    pub fn generate() -> SecretKey {
+        SecretKey(libsecp256k1::SecretKey::random(&mut rand::rngs::OsRng))
+    }
+
+    /// Try to parse a Secp256k1 secret key from a byte slice containing the actual key,
+    /// zeroing the input on success.
+    ///
+    /// If the bytes do not constitute a valid Secp256k1 secret key, an error is returned.
+    pub fn try_from_bytes(mut sk_bytes: impl AsMut<[u8]>) -> crate::Result<SecretKey> {
+        let sk_bytes = sk_bytes.as_mut();
+        let secret = libsecp256k1::SecretKey::parse_slice(&*sk_bytes)
+            .map_err(|e| Error::Other(format!("Failed to parse secp256k1 secret key: {e}")))?;
+        sk_bytes.zeroize();
+        Ok(SecretKey(secret))
+    }
+
+    /// Convert this secret key to a byte array.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.serialize()
+    }
+
+    /// Sign a message with this secret key.
+    fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        let message = Message::parse(&Sha256::digest(msg).into());
+
+        libsecp256k1::sign(&message, &self.0).0.serialize_der().as_ref().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secp256k1_signature() {
+        let kp = Keypair::generate();
+        let pk = kp.public();
+
+        let msg = "hello world".as_bytes();
+        let sig = kp.sign(msg);
+        assert!(pk.verify(msg, &sig));
+
+        let invalid_msg = "h3ll0 w0rld".as_bytes();
+        assert!(!pk.verify(invalid_msg, &sig));
+        assert!(!Keypair::generate().public().verify(msg, &sig));
+    }
+
+    #[test]
+    fn secp256k1_keypair_from_secret() {
+        let kp1 = Keypair::generate();
+        let mut sk = kp1.secret().to_bytes();
+        let kp2 = Keypair::from(SecretKey::try_from_bytes(&mut sk).unwrap());
+
+        assert_eq!(kp1.public(), kp2.public());
+        assert_eq!(sk, [0u8; 32]);
+    }
+
+    #[test]
+    fn secp256k1_public_key_encode_decode() {
+        let pk = Keypair::generate().public();
+
+        assert_eq!(PublicKey::try_from_bytes(&pk.to_bytes()).unwrap(), pk);
+        assert!(PublicKey::try_from_bytes(&[0u8; 33]).is_err());
+    }
+
+    #[test]
+    fn secp256k1_peer_id_matches_known_vector() {
+        let mut secret =
+            hex_literal::hex!("9c6a1b3a3c8e1f0d2e4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a6978879601");
+        let keypair = Keypair::from(SecretKey::try_from_bytes(&mut secret).unwrap());
+        let public = crate::crypto::PublicKey::from(keypair.public());
+
+        assert_eq!(
+            public.to_protobuf_encoding(),
+            hex_literal::hex!(
+                "080212210220fb9e45c5e32c19c992e7d9a971e32d9aea1eb91772d6419ade304d1a85a21a"
+            )
+        );
+        assert_eq!(
+            keypair.public().to_peer_id(),
+            "16Uiu2HAkweRZN1m7nhLCMGohJUgtg3jwZF1BZkHoyMpXyJtvyNc9"
+                .parse::<PeerId>()
+                .unwrap()
+        );
+        assert_eq!(
+            crate::crypto::PublicKey::from_protobuf_encoding(&public.to_protobuf_encoding())
+                .unwrap(),
+            public
+        );
+    }
+}
@@ -23,7 +23,7 @@
 //! This module handles generation, signing, and verification of certificates.
 
 use crate::{
-    crypto::{ed25519::Keypair, PublicKey},
+    crypto::{Keypair, PublicKey},
     PeerId,
 };
 
@@ -205,7 +205,7 @@ fn make_libp2p_extension(
         msg.extend(P2P_SIGNING_PREFIX);
         msg.extend(certificate_keypair.public_key_der());
 
-        identity_keypair.sign(&msg)
+        identity_keypair.sign(&msg).map_err(|_| rcgen::RcgenError::RingUnspecified)?
     };
 
     // The public host key and the signature are ANS.1-encoded
@@ -216,9 +216,7 @@ fn make_libp2p_extension(
     //    signature OCTET STRING
     // }
     let extension_content = {
-        // TODO: this is ridiculous
-        let serialized_pubkey =
-            crate::crypto::PublicKey::Ed25519(identity_keypair.public()).to_protobuf_encoding();
+        let serialized_pubkey = identity_keypair.public().to_protobuf_encoding();
         yasna::encode_der(&(serialized_pubkey, signature))
     };
 
@@ -446,17 +444,13 @@ mod tests {
 
     #[test]
     fn sanity_check() {
-        // let keypair = identity::Keypair::generate_ed25519();
-        let keypair = crate::crypto::ed25519::Keypair::generate();
+        let keypair = crate::crypto::Keypair::generate_ed25519();
 
         let (cert, _) = generate(&keypair).unwrap();
         let parsed_cert = parse(&cert).unwrap();
 
         assert!(parsed_cert.verify().is_ok());
-        assert_eq!(
-            crate::crypto::PublicKey::Ed25519(keypair.public()),
-            parsed_cert.extension.public_key
-        );
+        assert_eq!(keypair.public(), parsed_cert.extension.public_key);
     }
 
     macro_rules! check_cert {
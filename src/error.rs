@@ -36,7 +36,10 @@ use crate::{
 use multiaddr::Multiaddr;
 use multihash::{Multihash, MultihashGeneric};
 
-use std::io::{self, ErrorKind};
+use std::{
+    io::{self, ErrorKind},
+    net::IpAddr,
+};
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, thiserror::Error)]
@@ -125,6 +128,12 @@ pub enum Error {
     ConnectionDoesntExist(ConnectionId),
     #[error("Exceeded connection limits `{0:?}`")]
     ConnectionLimit(ConnectionLimitsError),
+    #[error("Peer `{0}` is banned")]
+    PeerBanned(PeerId),
+    #[error("Address `{0}` denied by the IP filter")]
+    AddressDenied(Multiaddr),
+    #[error("Inbound connections are not accepted")]
+    NotAccepting,
     #[error("Failed to dial peer immediately")]
     ImmediateDialError(#[from] ImmediateDialError),
 }
@@ -150,6 +159,9 @@ pub enum AddressError {
     /// The provided address contains an invalid multihash.
     #[error("Multihash does not contain a valid peer ID : `{0:?}`")]
     InvalidPeerId(Multihash),
+    /// The IPv6 zone of the address doesn't name a local interface.
+    #[error("Unknown IPv6 zone: `{0}`")]
+    UnknownZone(String),
 }
 
 #[derive(Debug, thiserror::Error, PartialEq)]
@@ -198,8 +210,24 @@ pub enum SubstreamError {
     ReadFailure(Option<SubstreamId>),
     #[error("Failed to write to substream, substream id `{0:?}`")]
     WriteFailure(Option<SubstreamId>),
+    #[error("Frame exceeds maximum size, substream id `{0:?}`")]
+    FrameTooLarge(Option<SubstreamId>),
     #[error("Negotiation error: `{0:?}`")]
     NegotiationError(#[from] NegotiationError),
+    /// Failed to dial the peer before the substream could be opened.
+    ///
+    /// If the dial failure is immediate, the error is included.
+    #[error("Failed to dial peer: `{0:?}`")]
+    DialFailure(Option<ImmediateDialError>),
+    /// Deadline of the substream was reached.
+    #[error("Substream deadline exceeded, substream id `{0:?}`")]
+    DeadlineExceeded(Option<SubstreamId>),
+    /// Substream was opened as part of a group and another substream of the group failed.
+    #[error("Substream group aborted, substream id `{0:?}`")]
+    GroupAborted(SubstreamId),
+    /// Global memory budget for buffered substream data has been used up.
+    #[error("Buffer memory budget exhausted, substream id `{0:?}`")]
+    BufferBudgetExhausted(Option<SubstreamId>),
 }
 
 /// Error during the negotiation phase.
@@ -214,6 +242,9 @@ pub enum NegotiationError {
     /// The peer ID was not provided by the noise handshake.
     #[error("`PeerId` missing from Noise handshake")]
     PeerIdMissing,
+    /// Failed to sign the Noise handshake payload with the identity key.
+    #[error("Failed to sign Noise handshake payload")]
+    SigningFailed,
     /// The negotiation operation timed out.
     #[error("Operation timed out")]
     Timeout,
@@ -295,6 +326,35 @@ pub enum DialError {
     /// An error occurred during the negotiation process.
     #[error("Negotiation error: `{0}`")]
     NegotiationError(#[from] NegotiationError),
+    /// The SOCKS5 proxy failed to open the connection.
+    #[error("Proxy error: `{0}`")]
+    ProxyError(#[from] ProxyError),
+    /// The address the remote resolved to is denied by the IP filter.
+    #[error("Address `{0}` denied by the IP filter")]
+    AddressDenied(IpAddr),
+    /// The dial was aborted by the local node.
+    #[error("Dial aborted")]
+    Aborted,
+}
+
+/// Error during the SOCKS5 proxy handshake.
+#[derive(Debug, thiserror::Error, Copy, Clone, PartialEq, Eq)]
+pub enum ProxyError {
+    /// The proxy sent a response that doesn't conform to SOCKS5.
+    #[error("Invalid response from proxy")]
+    InvalidResponse,
+    /// The proxy doesn't support any of the offered authentication methods.
+    #[error("No acceptable authentication method")]
+    NoAcceptableMethod,
+    /// The proxy rejected the credentials.
+    #[error("Authentication failed")]
+    AuthenticationFailed,
+    /// The proxy failed to connect to the target, with the SOCKS5 reply code.
+    #[error("Proxy failed to connect to target: reply code `{0}`")]
+    ConnectFailed(u8),
+    /// The target domain or the credentials are longer than 255 bytes.
+    #[error("Field too long for SOCKS5")]
+    FieldTooLong,
 }
 
 /// Dialing resulted in an immediate error before performing any network operations.
@@ -318,6 +378,12 @@ pub enum ImmediateDialError {
     /// The channel is clogged.
     #[error("Connection channel clogged")]
     ChannelClogged,
+    /// The peer is temporarily banned because of its low reputation.
+    #[error("Peer is banned")]
+    PeerBanned,
+    /// Automatic dial suppressed because the node has reached its target number of connections.
+    #[error("Target connections reached")]
+    DialThrottled,
 }
 
 /// Error during the QUIC transport negotiation.
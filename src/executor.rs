@@ -20,7 +20,13 @@
 
 //! Behavior defining how futures running in the background should be executed.
 
-use std::{future::Future, pin::Pin};
+use parking_lot::Mutex;
+use tokio::task::AbortHandle;
+
+use std::{future::Future, pin::Pin, thread::JoinHandle};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::executor";
 
 /// Trait which defines the interface the executor must implement.
 pub trait Executor: Send + Sync {
@@ -44,6 +50,85 @@ impl Executor for DefaultExecutor {
     }
 }
 
+/// Thread started by [`DedicatedExecutor`].
+struct DedicatedThread {
+    /// Handle of the thread.
+    thread: JoinHandle<()>,
+
+    /// Handle of the future's task on the runtime of the thread.
+    task: AbortHandle,
+}
+
+/// Executor which runs each future on its own thread.
+///
+/// The thread drives the future with a single-threaded runtime so the future can't starve other
+/// futures, nor be starved by them, no matter how much time it spends between yields.
+///
+/// The threads keep running until the future completes or [`DedicatedExecutor::shutdown()`] is
+/// called.
+#[derive(Default)]
+pub(crate) struct DedicatedExecutor {
+    /// Threads started by the executor.
+    threads: Mutex<Vec<DedicatedThread>>,
+}
+
+impl DedicatedExecutor {
+    /// Create new [`DedicatedExecutor`].
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abort the futures of the executor.
+    ///
+    /// The call doesn't block: the futures are dropped at their next yield point, after which
+    /// their threads exit on their own.
+    pub(crate) fn shutdown(&self) {
+        let threads = std::mem::take(&mut *self.threads.lock());
+
+        for DedicatedThread { thread, task } in threads {
+            tracing::trace!(
+                target: LOG_TARGET,
+                thread = ?thread.thread().name(),
+                "abort dedicated thread",
+            );
+
+            // the thread is detached as joining it would block until the future yields
+            task.abort();
+        }
+    }
+}
+
+impl Executor for DedicatedExecutor {
+    fn run(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        self.run_with_name("litep2p-dedicated", future)
+    }
+
+    fn run_with_name(&self, name: &'static str, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime to be created");
+        let task = runtime.spawn(future);
+        let abort = task.abort_handle();
+
+        // dropping the runtime when the task has finished or was aborted drops any futures the
+        // task itself spawned
+        let thread = std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let _ = runtime.block_on(task);
+            })
+            .expect("thread to be spawned");
+
+        let mut threads = self.threads.lock();
+        threads.retain(|thread| !thread.thread.is_finished());
+        threads.push(DedicatedThread {
+            thread,
+            task: abort,
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,4 +154,58 @@ mod tests {
         assert_eq!(rx.recv().await.unwrap(), 1337usize);
         assert_eq!(rx.recv().await.unwrap(), 1337usize);
     }
+
+    #[tokio::test]
+    async fn dedicated_executor_runs_future_on_own_thread() {
+        let (tx, mut rx) = channel(1);
+
+        DedicatedExecutor::new().run_with_name(
+            "test-dedicated",
+            Box::pin(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                tx.send(std::thread::current().name().map(String::from)).await.unwrap();
+            }),
+        );
+
+        assert_eq!(rx.recv().await.unwrap().as_deref(), Some("test-dedicated"));
+    }
+
+    #[tokio::test]
+    async fn dedicated_executor_shutdown_stops_threads() {
+        let executor = DedicatedExecutor::new();
+        let (tx, mut rx) = channel::<()>(1);
+
+        executor.run(Box::pin(async move {
+            let _tx = tx;
+            futures::future::pending::<()>().await
+        }));
+
+        executor.shutdown();
+
+        assert!(executor.threads.lock().is_empty());
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn dedicated_executor_shutdown_does_not_block() {
+        let executor = DedicatedExecutor::new();
+        let (tx, mut rx) = channel::<()>(1);
+
+        // future which spends a long time between yields
+        executor.run(Box::pin(async move {
+            let _tx = tx;
+
+            loop {
+                std::thread::sleep(std::time::Duration::from_millis(500));
+                tokio::task::yield_now().await;
+            }
+        }));
+
+        let started = std::time::Instant::now();
+        executor.shutdown();
+        assert!(started.elapsed() < std::time::Duration::from_millis(250));
+
+        // the future is dropped once it yields
+        assert!(rx.recv().await.is_none());
+    }
 }
@@ -29,24 +29,37 @@
 #![allow(clippy::match_like_matches_macro)]
 
 use crate::{
-    addresses::PublicAddresses,
+    addresses::{InsertionError, PublicAddresses},
     config::Litep2pConfig,
     error::DialError,
+    executor::{DedicatedExecutor, Executor},
     protocol::{
-        libp2p::{bitswap::Bitswap, identify::Identify, kademlia::Kademlia, ping::Ping},
+        libp2p::{
+            bitswap::Bitswap,
+            dcutr::Dcutr,
+            identify::Identify,
+            kademlia::Kademlia,
+            ping::Ping,
+            relay::{Relay, RelayClient},
+        },
         mdns::Mdns,
         notification::NotificationProtocol,
         request_response::RequestResponseProtocol,
+        NegotiationCache,
     },
+    substream::{BufferBudget, SubstreamInfo, SubstreamRegistry},
     transport::{
-        manager::{SupportedTransport, TransportManager},
+        manager::{SupportedTransport, TransportManager, TransportStats},
         tcp::TcpTransport,
-        TransportBuilder, TransportEvent,
+        tunnel::TunnelTransport,
+        DialProgress, TransportBuilder, TransportEvent,
     },
 };
 
 #[cfg(feature = "quic")]
 use crate::transport::quic::QuicTransport;
+#[cfg(unix)]
+use crate::transport::unix::UnixTransport;
 #[cfg(feature = "webrtc")]
 use crate::transport::webrtc::WebRtcTransport;
 #[cfg(feature = "websocket")]
@@ -54,10 +67,14 @@ use crate::transport::websocket::WebSocketTransport;
 
 use multiaddr::{Multiaddr, Protocol};
 use multihash::Multihash;
-use transport::Endpoint;
+use tokio::sync::mpsc::Sender;
+use transport::{CloseReason, Endpoint, Muxer, SecurityProtocol};
 use types::ConnectionId;
 
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 pub use bandwidth::BandwidthSink;
 pub use error::Error;
@@ -81,8 +98,8 @@ pub mod yamux;
 mod bandwidth;
 mod multistream_select;
 
-#[cfg(test)]
-mod mock;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
 
 /// Public result type used by the crate.
 pub type Result<T> = std::result::Result<T, error::Error>;
@@ -103,6 +120,14 @@ pub enum Litep2pEvent {
 
         /// Endpoint.
         endpoint: Endpoint,
+
+        /// Security protocol negotiated for the connection.
+        security: SecurityProtocol,
+
+        /// Stream multiplexer negotiated for the connection.
+        ///
+        /// `None` if the transport provides streams natively, as QUIC does.
+        muxer: Option<Muxer>,
     },
 
     /// Connection closed to remote peer.
@@ -112,6 +137,13 @@ pub enum Litep2pEvent {
 
         /// Connection ID.
         connection_id: ConnectionId,
+
+        /// Reason the remote peer gave for closing the connection.
+        ///
+        /// Only set if the remote peer closed the connection with
+        /// [`Litep2p::disconnect_with_reason()`] or an equivalent and the transport delivers
+        /// application close reasons.
+        reason: Option<CloseReason>,
     },
 
     /// Failed to dial peer.
@@ -132,6 +164,42 @@ pub enum Litep2pEvent {
         /// Depending on the transport, the address might be different for each error.
         errors: Vec<(Multiaddr, DialError)>,
     },
+    /// Dials to a peer were aborted with [`Litep2p::cancel_dial()`].
+    DialAborted {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
+    /// Dial to a peer succeeded.
+    ///
+    /// Emitted after [`Litep2pEvent::ConnectionEstablished`] for dials started with
+    /// [`Litep2p::dial()`] and reports which of the peer's known addresses the connection was
+    /// established over.
+    DialSucceeded {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Address that was dialed successfully.
+        address: Multiaddr,
+    },
+
+    /// Node started listening on a new address.
+    ///
+    /// Emitted when a network interface gains an address while a transport is listening on an
+    /// unspecified address, such as `/ip4/0.0.0.0/tcp/0`.
+    NewListenAddr {
+        /// Listen address, including the local peer ID.
+        address: Multiaddr,
+    },
+
+    /// Node is no longer listening on an address.
+    ///
+    /// Emitted when a network interface loses an address while a transport is listening on an
+    /// unspecified address.
+    ExpiredListenAddr {
+        /// Listen address, including the local peer ID.
+        address: Multiaddr,
+    },
 }
 
 /// [`Litep2p`] object.
@@ -147,23 +215,59 @@ pub struct Litep2p {
 
     /// Bandwidth sink.
     bandwidth_sink: BandwidthSink,
+
+    /// Executor of the protocols running on dedicated threads.
+    dedicated_executor: Arc<DedicatedExecutor>,
 }
 
 impl Litep2p {
     /// Create new [`Litep2p`].
     pub fn new(mut litep2p_config: Litep2pConfig) -> crate::Result<Litep2p> {
-        let local_peer_id = PeerId::from_public_key(&litep2p_config.keypair.public().into());
+        let local_peer_id = litep2p_config.keypair.to_peer_id();
         let bandwidth_sink = BandwidthSink::new();
         let mut listen_addresses = vec![];
 
-        let supported_transports = Self::supported_transports(&litep2p_config);
+        let supported_transports = Self::configured_transports(&litep2p_config);
         let (mut transport_manager, transport_handle) = TransportManager::new(
             litep2p_config.keypair.clone(),
             supported_transports,
             bandwidth_sink.clone(),
             litep2p_config.max_parallel_dials,
             litep2p_config.connection_limits,
+            litep2p_config.reputation,
         );
+        transport_manager.set_ip_filter(litep2p_config.ip_filter);
+        transport_manager
+            .set_duplicate_connection_policy(litep2p_config.duplicate_connection_policy);
+
+        // start tracking substreams if the registry is enabled
+        if let Some(config) = litep2p_config.substream_registry.take() {
+            let registry = SubstreamRegistry::new(config);
+
+            litep2p_config.executor.run(Box::pin(registry.clone().run_watchdog()));
+            transport_manager.set_substream_registry(registry);
+        }
+
+        if let Some(observer) = litep2p_config.negotiation_observer.take() {
+            transport_manager.set_negotiation_observer(observer);
+        }
+
+        if litep2p_config.optimistic_negotiation {
+            transport_manager.set_negotiation_cache(NegotiationCache::new());
+        }
+
+        if let Some(limit) = litep2p_config.buffer_budget {
+            transport_manager.set_buffer_budget(BufferBudget::new(limit));
+        }
+
+        if let Some(limit) = litep2p_config.substream_rate_limit {
+            transport_manager.set_substream_rate_limit(limit);
+        }
+
+        #[cfg(feature = "traffic-tap")]
+        for (protocol, tap) in std::mem::take(&mut litep2p_config.traffic_taps) {
+            transport_manager.set_traffic_tap(protocol, tap);
+        }
 
         // add known addresses to `TransportManager`, if any exist
         if !litep2p_config.known_addresses.is_empty() {
@@ -172,28 +276,55 @@ impl Litep2p {
             }
         }
 
+        // isolated protocols are run on dedicated threads, all other protocols with the executor
+        let isolated_protocols = std::mem::take(&mut litep2p_config.isolated_protocols);
+        let shared_executor = Arc::clone(&litep2p_config.executor);
+        let dedicated_executor = Arc::new(DedicatedExecutor::new());
+        let protocol_executor = |protocol: &ProtocolName| -> Arc<dyn Executor> {
+            match isolated_protocols.contains(protocol) {
+                true => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?protocol,
+                        "run protocol on dedicated thread",
+                    );
+                    Arc::clone(&dedicated_executor) as Arc<dyn Executor>
+                }
+                false => Arc::clone(&shared_executor),
+            }
+        };
+
         // start notification protocol event loops
-        for (protocol, config) in litep2p_config.notification_protocols.into_iter() {
+        for (protocol, mut config) in litep2p_config.notification_protocols.into_iter() {
             tracing::debug!(
                 target: LOG_TARGET,
                 ?protocol,
                 "enable notification protocol",
             );
 
+            let (protocol, fallback_names) = config.negotiated_protocols();
             let service = transport_manager.register_protocol(
-                protocol,
-                config.fallback_names.clone(),
+                protocol.clone(),
+                fallback_names,
                 config.codec,
                 litep2p_config.keep_alive_timeout,
+                config.substream_keep_alive,
             );
+            transport_manager.set_substream_priority(&protocol, config.substream_priority);
+            transport_manager.set_latency_sensitive(&protocol, config.latency_sensitive);
+            transport_manager
+                .set_inbound_substream_order(&protocol, config.inbound_substream_order);
+            if let Some(filter) = config.inbound_substream_filter.take() {
+                transport_manager.set_inbound_substream_filter(&protocol, filter);
+            }
             let executor = Arc::clone(&litep2p_config.executor);
-            litep2p_config.executor.run(Box::pin(async move {
+            protocol_executor(&protocol).run(Box::pin(async move {
                 NotificationProtocol::new(service, config, executor).run().await
             }));
         }
 
         // start request-response protocol event loops
-        for (protocol, config) in litep2p_config.request_response_protocols.into_iter() {
+        for (protocol, mut config) in litep2p_config.request_response_protocols.into_iter() {
             tracing::debug!(
                 target: LOG_TARGET,
                 ?protocol,
@@ -201,12 +332,20 @@ impl Litep2p {
             );
 
             let service = transport_manager.register_protocol(
-                protocol,
+                protocol.clone(),
                 config.fallback_names.clone(),
                 config.codec,
                 litep2p_config.keep_alive_timeout,
+                true,
             );
-            litep2p_config.executor.run(Box::pin(async move {
+            transport_manager.set_substream_priority(&protocol, config.substream_priority);
+            transport_manager.set_latency_sensitive(&protocol, config.latency_sensitive);
+            transport_manager
+                .set_inbound_substream_order(&protocol, config.inbound_substream_order);
+            if let Some(filter) = config.inbound_substream_filter.take() {
+                transport_manager.set_inbound_substream_filter(&protocol, filter);
+            }
+            protocol_executor(&protocol).run(Box::pin(async move {
                 RequestResponseProtocol::new(service, config).run().await
             }));
         }
@@ -216,12 +355,13 @@ impl Litep2p {
             tracing::debug!(target: LOG_TARGET, protocol = ?protocol_name, "enable user protocol");
 
             let service = transport_manager.register_protocol(
-                protocol_name,
+                protocol_name.clone(),
                 Vec::new(),
                 protocol.codec(),
                 litep2p_config.keep_alive_timeout,
+                true,
             );
-            litep2p_config.executor.run(Box::pin(async move {
+            protocol_executor(&protocol_name).run(Box::pin(async move {
                 let _ = protocol.run(service).await;
             }));
         }
@@ -239,8 +379,9 @@ impl Litep2p {
                 Vec::new(),
                 ping_config.codec,
                 litep2p_config.keep_alive_timeout,
+                true,
             );
-            litep2p_config.executor.run(Box::pin(async move {
+            protocol_executor(&ping_config.protocol).run(Box::pin(async move {
                 Ping::new(service, ping_config).run().await
             }));
         }
@@ -262,8 +403,9 @@ impl Litep2p {
                 fallback_names,
                 kademlia_config.codec,
                 litep2p_config.keep_alive_timeout,
+                true,
             );
-            litep2p_config.executor.run(Box::pin(async move {
+            protocol_executor(main_protocol).run(Box::pin(async move {
                 let _ = Kademlia::new(service, kademlia_config).run().await;
             }));
         }
@@ -283,10 +425,18 @@ impl Litep2p {
                     Vec::new(),
                     identify_config.codec,
                     litep2p_config.keep_alive_timeout,
+                    true,
                 );
-                identify_config.public = Some(litep2p_config.keypair.public().into());
+                let push_service = transport_manager.register_protocol(
+                    identify_config.push_protocol.clone(),
+                    Vec::new(),
+                    identify_config.codec,
+                    litep2p_config.keep_alive_timeout,
+                    true,
+                );
+                identify_config.public = Some(litep2p_config.keypair.public());
 
-                Some((service, identify_config))
+                Some((service, push_service, identify_config))
             }
         };
 
@@ -303,12 +453,105 @@ impl Litep2p {
                 Vec::new(),
                 bitswap_config.codec,
                 litep2p_config.keep_alive_timeout,
+                true,
             );
-            litep2p_config.executor.run(Box::pin(async move {
+            protocol_executor(&bitswap_config.protocol).run(Box::pin(async move {
                 Bitswap::new(service, bitswap_config).run().await
             }));
         }
 
+        // start dcutr protocol event loop if enabled
+        if let Some(dcutr_config) = litep2p_config.dcutr.take() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                protocol = ?dcutr_config.protocol,
+                "enable libp2p dcutr protocol",
+            );
+
+            let service = transport_manager.register_protocol(
+                dcutr_config.protocol.clone(),
+                Vec::new(),
+                dcutr_config.codec,
+                litep2p_config.keep_alive_timeout,
+                true,
+            );
+            protocol_executor(&dcutr_config.protocol).run(Box::pin(async move {
+                Dcutr::new(service, dcutr_config).run().await
+            }));
+        }
+
+        // start circuit relay server event loop if enabled
+        if let Some(relay_config) = litep2p_config.relay.take() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                hop = ?relay_config.hop_protocol,
+                stop = ?relay_config.stop_protocol,
+                "enable libp2p circuit relay server",
+            );
+
+            let hop_service = transport_manager.register_protocol(
+                relay_config.hop_protocol.clone(),
+                Vec::new(),
+                relay_config.codec,
+                litep2p_config.keep_alive_timeout,
+                true,
+            );
+            let stop_service = transport_manager.register_protocol(
+                relay_config.stop_protocol.clone(),
+                Vec::new(),
+                relay_config.codec,
+                litep2p_config.keep_alive_timeout,
+                true,
+            );
+            protocol_executor(&relay_config.hop_protocol).run(Box::pin(async move {
+                Relay::new(hop_service, stop_service, relay_config).run().await
+            }));
+        }
+
+        // start circuit relay client event loop if enabled
+        if let Some(relay_config) = litep2p_config.relay_client.take() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                relays = ?relay_config.relays,
+                "enable libp2p circuit relay client",
+            );
+
+            if litep2p_config.tunnel.is_none() {
+                return Err(Error::Other(
+                    "Relay client requires the tunnel transport".to_string(),
+                ));
+            }
+
+            if transport_manager
+                .protocols()
+                .any(|protocol| protocol == &relay_config.hop_protocol)
+            {
+                return Err(Error::Other(
+                    "Relay client cannot be combined with the relay server".to_string(),
+                ));
+            }
+
+            let hop_service = transport_manager.register_protocol(
+                relay_config.hop_protocol.clone(),
+                Vec::new(),
+                relay_config.codec,
+                litep2p_config.keep_alive_timeout,
+                true,
+            );
+            let stop_service = transport_manager.register_protocol(
+                relay_config.stop_protocol.clone(),
+                Vec::new(),
+                relay_config.codec,
+                litep2p_config.keep_alive_timeout,
+                true,
+            );
+            let hop_protocol = relay_config.hop_protocol.clone();
+            let (relay_client, handle) = RelayClient::new(hop_service, stop_service, relay_config);
+            transport_manager.set_relay_client(handle);
+
+            protocol_executor(&hop_protocol).run(Box::pin(async move { relay_client.run().await }));
+        }
+
         // enable tcp transport if the config exists
         if let Some(config) = litep2p_config.tcp.take() {
             let handle = transport_manager.transport_handle(Arc::clone(&litep2p_config.executor));
@@ -316,7 +559,7 @@ impl Litep2p {
                 <TcpTransport as TransportBuilder>::new(handle, config)?;
 
             for address in transport_listen_addresses {
-                transport_manager.register_listen_address(address.clone());
+                transport_manager.register_listen_address(SupportedTransport::Tcp, address.clone());
                 listen_addresses.push(address.with(Protocol::P2p(
                     Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
                 )));
@@ -333,7 +576,8 @@ impl Litep2p {
                 <QuicTransport as TransportBuilder>::new(handle, config)?;
 
             for address in transport_listen_addresses {
-                transport_manager.register_listen_address(address.clone());
+                transport_manager
+                    .register_listen_address(SupportedTransport::Quic, address.clone());
                 listen_addresses.push(address.with(Protocol::P2p(
                     Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
                 )));
@@ -350,7 +594,8 @@ impl Litep2p {
                 <WebRtcTransport as TransportBuilder>::new(handle, config)?;
 
             for address in transport_listen_addresses {
-                transport_manager.register_listen_address(address.clone());
+                transport_manager
+                    .register_listen_address(SupportedTransport::WebRtc, address.clone());
                 listen_addresses.push(address.with(Protocol::P2p(
                     Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
                 )));
@@ -367,7 +612,8 @@ impl Litep2p {
                 <WebSocketTransport as TransportBuilder>::new(handle, config)?;
 
             for address in transport_listen_addresses {
-                transport_manager.register_listen_address(address.clone());
+                transport_manager
+                    .register_listen_address(SupportedTransport::WebSocket, address.clone());
                 listen_addresses.push(address.with(Protocol::P2p(
                     Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
                 )));
@@ -377,6 +623,32 @@ impl Litep2p {
                 .register_transport(SupportedTransport::WebSocket, Box::new(transport));
         }
 
+        // enable unix domain socket transport if the config exists
+        #[cfg(unix)]
+        if let Some(config) = litep2p_config.unix.take() {
+            let handle = transport_manager.transport_handle(Arc::clone(&litep2p_config.executor));
+            let (transport, transport_listen_addresses) =
+                <UnixTransport as TransportBuilder>::new(handle, config)?;
+
+            for address in transport_listen_addresses {
+                transport_manager
+                    .register_listen_address(SupportedTransport::Unix, address.clone());
+                listen_addresses.push(address.with(Protocol::P2p(
+                    Multihash::from_bytes(&local_peer_id.to_bytes()).unwrap(),
+                )));
+            }
+
+            transport_manager.register_transport(SupportedTransport::Unix, Box::new(transport));
+        }
+
+        // enable tunnel transport if the config exists
+        if let Some(config) = litep2p_config.tunnel.take() {
+            let handle = transport_manager.transport_handle(Arc::clone(&litep2p_config.executor));
+            let (transport, _) = <TunnelTransport as TransportBuilder>::new(handle, config)?;
+
+            transport_manager.register_transport(SupportedTransport::Tunnel, Box::new(transport));
+        }
+
         // enable mdns if the config exists
         if let Some(config) = litep2p_config.mdns.take() {
             let mdns = Mdns::new(transport_handle, config, listen_addresses.clone())?;
@@ -387,11 +659,12 @@ impl Litep2p {
         }
 
         // if identify was enabled, give it the enabled protocols and listen addresses and start it
-        if let Some((service, mut identify_config)) = identify_info.take() {
+        if let Some((service, push_service, mut identify_config)) = identify_info.take() {
             identify_config.protocols = transport_manager.protocols().cloned().collect();
-            let identify = Identify::new(service, identify_config);
+            let executor = protocol_executor(&identify_config.protocol);
+            let identify = Identify::new(service, push_service, identify_config);
 
-            litep2p_config.executor.run(Box::pin(async move {
+            executor.run(Box::pin(async move {
                 let _ = identify.run().await;
             }));
         }
@@ -413,6 +686,7 @@ impl Litep2p {
             bandwidth_sink,
             listen_addresses,
             transport_manager,
+            dedicated_executor,
         })
     }
 
@@ -421,13 +695,18 @@ impl Litep2p {
     /// Information of the supported transports is needed to initialize protocols but
     /// information about protocols must be known to initialize transports so the initialization
     /// has to be split.
-    fn supported_transports(config: &Litep2pConfig) -> HashSet<SupportedTransport> {
+    fn configured_transports(config: &Litep2pConfig) -> HashSet<SupportedTransport> {
         let mut supported_transports = HashSet::new();
 
         config
             .tcp
             .is_some()
             .then(|| supported_transports.insert(SupportedTransport::Tcp));
+        config
+            .tcp
+            .as_ref()
+            .is_some_and(|config| config.socks5_proxy.is_some())
+            .then(|| supported_transports.insert(SupportedTransport::Onion3));
         #[cfg(feature = "quic")]
         config
             .quic
@@ -443,6 +722,15 @@ impl Litep2p {
             .webrtc
             .is_some()
             .then(|| supported_transports.insert(SupportedTransport::WebRtc));
+        #[cfg(unix)]
+        config
+            .unix
+            .is_some()
+            .then(|| supported_transports.insert(SupportedTransport::Unix));
+        config
+            .tunnel
+            .is_some()
+            .then(|| supported_transports.insert(SupportedTransport::Tunnel));
 
         supported_transports
     }
@@ -452,11 +740,55 @@ impl Litep2p {
         &self.local_peer_id
     }
 
+    /// Get the transports enabled by the configuration.
+    pub fn supported_transports(&self) -> Vec<SupportedTransport> {
+        self.transport_manager.installed_transports().copied().collect()
+    }
+
+    /// Check if `address` can be dialed using one of the enabled transports.
+    ///
+    /// `address` must contain the peer ID of the remote peer. Dialing an address which is not
+    /// dialable fails with [`Error::TransportNotSupported`].
+    pub fn is_dialable(&self, address: &Multiaddr) -> bool {
+        self.transport_manager.is_dialable(address)
+    }
+
     /// Get the list of public addresses of the node.
     pub fn public_addresses(&self) -> PublicAddresses {
         self.transport_manager.public_addresses()
     }
 
+    /// Add an external address of the node.
+    ///
+    /// External addresses are advertised to remote peers over Identify and Kademlia and take
+    /// precedence over listen addresses. If `address` doesn't contain a peer ID, the local
+    /// peer ID is appended to it.
+    ///
+    /// Returns `true` if the address was added and `false` if it already existed.
+    pub fn add_external_address(&self, address: Multiaddr) -> Result<bool, InsertionError> {
+        self.transport_manager.public_addresses().add_address(address)
+    }
+
+    /// Remove an external address of the node.
+    ///
+    /// Returns `true` if the address existed.
+    pub fn remove_external_address(&self, address: &Multiaddr) -> bool {
+        self.transport_manager.public_addresses().remove_address(address)
+    }
+
+    /// Get the external addresses of the node.
+    pub fn external_addresses(&self) -> Vec<Multiaddr> {
+        self.transport_manager.public_addresses().get_addresses()
+    }
+
+    /// Get the listener, connection and dial statistics of each enabled transport.
+    ///
+    /// The statistics are updated as [`Litep2p::next_event()`] processes the events of the
+    /// transports.
+    pub fn transport_stats(&self) -> HashMap<SupportedTransport, TransportStats> {
+        self.transport_manager.transport_stats()
+    }
+
     /// Get the list of listen addresses of the node.
     pub fn listen_addresses(&self) -> impl Iterator<Item = &Multiaddr> {
         self.listen_addresses.iter()
@@ -468,13 +800,127 @@ impl Litep2p {
     }
 
     /// Dial peer.
+    ///
+    /// If the circuit relay client is enabled and the dial fails, the peer is dialed through the
+    /// relays and the failure is reported only if that fails as well.
     pub async fn dial(&mut self, peer: &PeerId) -> crate::Result<()> {
-        self.transport_manager.dial(*peer).await
+        self.transport_manager.dial(*peer).await?;
+        self.transport_manager.fall_back_to_relays(*peer);
+
+        Ok(())
+    }
+
+    /// Abort all in-flight dials to `peer`.
+    ///
+    /// The installed protocols are informed that the dial failed and
+    /// [`Litep2pEvent::DialAborted`] is emitted. Returns `false` if `peer` wasn't being dialed.
+    pub async fn cancel_dial(&mut self, peer: &PeerId) -> bool {
+        self.transport_manager.cancel_dial(*peer).await
+    }
+
+    /// Use only `transport` for communicating with `peer`.
+    ///
+    /// Dials to `peer` only consider the known addresses of `transport` and dialing an address of
+    /// another transport fails with [`Error::AddressDenied`]. If `close_other_connections` is
+    /// `true`, the open connections to `peer` over other transports are closed.
+    pub fn pin_peer_transport(
+        &mut self,
+        peer: PeerId,
+        transport: SupportedTransport,
+        close_other_connections: bool,
+    ) {
+        self.transport_manager
+            .pin_peer_transport(peer, transport, close_other_connections)
+    }
+
+    /// Allow `peer` to be dialed over all transports again.
+    pub fn unpin_peer_transport(&mut self, peer: &PeerId) {
+        self.transport_manager.unpin_peer_transport(peer)
+    }
+
+    /// Close all connections to `peer` with an application close code and reason.
+    ///
+    /// Over transports which support it, currently QUIC, the remote peer receives `code` and
+    /// `reason` in [`Litep2pEvent::ConnectionClosed`]. Connections over other transports are
+    /// closed without a reason.
+    ///
+    /// Returns [`Error::PeerDoesntExist`] if there are no open connections to `peer`.
+    pub fn disconnect_with_reason(
+        &mut self,
+        peer: PeerId,
+        code: u32,
+        reason: impl Into<Vec<u8>>,
+    ) -> crate::Result<()> {
+        self.transport_manager.disconnect_with_reason(
+            peer,
+            CloseReason {
+                code,
+                reason: reason.into(),
+            },
+        )
     }
 
     /// Dial address.
+    ///
+    /// Like with [`Litep2p::dial()`], the peer is dialed through the relays of the circuit relay
+    /// client if the dial fails.
     pub async fn dial_address(&mut self, address: Multiaddr) -> crate::Result<()> {
-        self.transport_manager.dial_address(address).await
+        let peer = Self::address_peer(&address);
+        self.transport_manager.dial_address(address).await?;
+
+        if let Some(peer) = peer {
+            self.transport_manager.fall_back_to_relays(peer);
+        }
+
+        Ok(())
+    }
+
+    /// Get the peer ID `address` ends with, if any.
+    fn address_peer(address: &Multiaddr) -> Option<PeerId> {
+        match address.iter().last() {
+            Some(Protocol::P2p(multihash)) => PeerId::from_multihash(multihash).ok(),
+            _ => None,
+        }
+    }
+
+    /// Dial address and report the progress of the dial to `progress`.
+    ///
+    /// Each milestone of the connection establishment, such as the completion of the security
+    /// handshake, is sent as a [`DialProgress`] event together
+    /// with the time it was reached. Events are dropped if `progress` is full.
+    ///
+    /// Currently only TCP reports dial progress, other transports dial the address without
+    /// reporting anything.
+    pub async fn dial_address_with_progress(
+        &mut self,
+        address: Multiaddr,
+        progress: Sender<DialProgress>,
+    ) -> crate::Result<()> {
+        let peer = Self::address_peer(&address);
+        self.transport_manager.dial_address_with_progress(address, progress).await?;
+
+        if let Some(peer) = peer {
+            self.transport_manager.fall_back_to_relays(peer);
+        }
+
+        Ok(())
+    }
+
+    /// Enable or disable accepting inbound connections.
+    ///
+    /// Listeners stay bound while accepting is disabled but inbound connections are closed
+    /// before they're upgraded, so they never reach the installed protocols. This allows the
+    /// node to learn its listen addresses before it's ready to serve peers. To not accept any
+    /// connections, accepting must be disabled before [`Litep2p::next_event()`] is polled.
+    ///
+    /// WebRTC connections are not affected.
+    pub fn set_accepting(&mut self, accepting: bool) {
+        self.transport_manager.set_accepting(accepting);
+    }
+
+    /// Check if inbound connections are accepted.
+    pub fn is_accepting(&self) -> bool {
+        self.transport_manager.is_accepting()
     }
 
     /// Add one ore more known addresses for peer.
@@ -489,21 +935,94 @@ impl Litep2p {
         self.transport_manager.add_known_address(peer, address)
     }
 
+    /// Attach `data` to an open connection.
+    ///
+    /// Any previous value of the same type is replaced. The data is dropped when the connection
+    /// is closed.
+    ///
+    /// Returns [`Error::ConnectionDoesntExist`] if the connection is not open.
+    pub fn set_connection_data<T: Send + 'static>(
+        &mut self,
+        connection_id: ConnectionId,
+        data: T,
+    ) -> crate::Result<()> {
+        self.transport_manager.set_connection_data(connection_id, data)
+    }
+
+    /// Get data of type `T` attached to an open connection.
+    pub fn connection_data<T: Send + 'static>(&self, connection_id: ConnectionId) -> Option<&T> {
+        self.transport_manager.connection_data(connection_id)
+    }
+
+    /// Gracefully drain an open connection.
+    ///
+    /// No new substreams can be opened over the connection but the substreams that are already
+    /// open are allowed to finish. Once all of them have been closed, or `deadline` is reached,
+    /// the connection is closed and [`Litep2pEvent::ConnectionClosed`] is emitted.
+    ///
+    /// Returns [`Error::ConnectionDoesntExist`] if the connection is not open.
+    pub fn drain_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        deadline: std::time::Instant,
+    ) -> crate::Result<()> {
+        self.transport_manager.drain_connection(connection_id, deadline)
+    }
+
+    /// Get the round-trip time estimate of an open connection.
+    ///
+    /// The estimate comes from the congestion controller of the underlying transport, so peers
+    /// can be compared by latency without running the ping protocol. Only QUIC connections
+    /// currently provide an estimate, `None` is returned for other transports and for
+    /// connections that are no longer open.
+    pub fn connection_rtt(&self, connection_id: ConnectionId) -> Option<std::time::Duration> {
+        self.transport_manager.connection_rtt(connection_id)
+    }
+
+    /// Get information about open substreams.
+    ///
+    /// Returns an empty list unless the substream registry has been enabled in
+    /// [`Litep2pConfig`].
+    pub fn open_substreams(&self) -> Vec<SubstreamInfo> {
+        self.transport_manager.open_substreams()
+    }
+
+    /// Get the memory budget for data buffered by substreams.
+    ///
+    /// The budget reports how much of the limit configured with
+    /// [`with_buffer_memory_limit()`](crate::config::ConfigBuilder::with_buffer_memory_limit) is
+    /// in use. Returns `None` if no limit was configured.
+    pub fn buffer_budget(&self) -> Option<BufferBudget> {
+        self.transport_manager.buffer_budget()
+    }
+
     /// Poll next event.
     ///
     /// This function must be called in order for litep2p to make progress.
     pub async fn next_event(&mut self) -> Option<Litep2pEvent> {
         loop {
             match self.transport_manager.next().await? {
-                TransportEvent::ConnectionEstablished { peer, endpoint, .. } =>
-                    return Some(Litep2pEvent::ConnectionEstablished { peer, endpoint }),
+                TransportEvent::ConnectionEstablished {
+                    peer,
+                    endpoint,
+                    security,
+                    muxer,
+                } =>
+                    return Some(Litep2pEvent::ConnectionEstablished {
+                        peer,
+                        endpoint,
+                        security,
+                        muxer,
+                    }),
                 TransportEvent::ConnectionClosed {
                     peer,
                     connection_id,
+                    reason,
                 } =>
                     return Some(Litep2pEvent::ConnectionClosed {
                         peer,
                         connection_id,
+                        reason,
                     }),
                 TransportEvent::DialFailure { address, error, .. } =>
                     return Some(Litep2pEvent::DialFailure { address, error }),
@@ -511,17 +1030,46 @@ impl Litep2p {
                 TransportEvent::OpenFailure { errors, .. } => {
                     return Some(Litep2pEvent::ListDialFailures { errors });
                 }
+                TransportEvent::DialSucceeded { peer, address } =>
+                    return Some(Litep2pEvent::DialSucceeded { peer, address }),
+                TransportEvent::DialAborted { peer } =>
+                    return Some(Litep2pEvent::DialAborted { peer }),
+                TransportEvent::NewListenAddress { address } => {
+                    let address = address.with(Protocol::P2p(
+                        Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+                    ));
+
+                    if !self.listen_addresses.contains(&address) {
+                        self.listen_addresses.push(address.clone());
+                    }
+                    return Some(Litep2pEvent::NewListenAddr { address });
+                }
+                TransportEvent::ExpiredListenAddress { address } => {
+                    let address = address.with(Protocol::P2p(
+                        Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+                    ));
+
+                    self.listen_addresses.retain(|listen_address| listen_address != &address);
+                    return Some(Litep2pEvent::ExpiredListenAddr { address });
+                }
                 _ => {}
             }
         }
     }
 }
 
+impl Drop for Litep2p {
+    fn drop(&mut self) {
+        self.dedicated_executor.shutdown();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         config::ConfigBuilder,
         protocol::{libp2p::ping, notification::Config as NotificationConfig},
+        transport::tcp::config::{Config as TcpConfig, Socks5Config},
         types::protocol::ProtocolName,
         Litep2p, Litep2pEvent, PeerId,
     };
@@ -662,4 +1210,36 @@ mod tests {
             _ => panic!("invalid event received"),
         }
     }
+
+    #[tokio::test]
+    async fn onion3_dialable_only_with_socks5_proxy() {
+        let address: Multiaddr =
+            "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234\
+            /p2p/12D3KooWKrUnV42yDR7G6DewmgHtFaVCJWLjQRi2G9t5eJD3BvTy"
+                .parse()
+                .unwrap();
+
+        let litep2p = Litep2p::new(
+            ConfigBuilder::new()
+                .with_tcp(TcpConfig {
+                    listen_addresses: Vec::new(),
+                    ..Default::default()
+                })
+                .build(),
+        )
+        .unwrap();
+        assert!(!litep2p.is_dialable(&address));
+
+        let litep2p = Litep2p::new(
+            ConfigBuilder::new()
+                .with_tcp(TcpConfig {
+                    listen_addresses: Vec::new(),
+                    socks5_proxy: Some(Socks5Config::new("127.0.0.1:9050".parse().unwrap())),
+                    ..Default::default()
+                })
+                .build(),
+        )
+        .unwrap();
+        assert!(litep2p.is_dialable(&address));
+    }
 }
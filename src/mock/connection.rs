@@ -0,0 +1,369 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Mock connection with deterministic fault injection.
+//!
+//! [`MockConnection`] plays the role of a transport connection for a single protocol: it reports
+//! the connection to the protocol's [`TransportService`](crate::protocol::TransportService),
+//! serves the substreams the protocol opens and hands the remote ends of the substreams to the
+//! test. Faults are injected into the connection with a [`FaultInjector`].
+
+use crate::{
+    error::SubstreamError,
+    mock::substream::{paired, PairedSubstream},
+    protocol::{ConnectionHandle, Direction, InnerTransportEvent, Permit, ProtocolCommand},
+    substream::Substream,
+    transport::Endpoint,
+    types::ConnectionId,
+    PeerId,
+};
+
+use bytes::{Bytes, BytesMut};
+use futures::{Sink, Stream};
+use multiaddr::Multiaddr;
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use std::{
+    io::ErrorKind,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+/// Faults injected into the substreams of a connection.
+#[derive(Debug, Default)]
+struct Faults {
+    /// Number of frames which are silently dropped instead of being sent.
+    drop_frames: usize,
+
+    /// Length the next sent frame is truncated to.
+    truncate: Option<usize>,
+
+    /// Has the connection been reset.
+    reset: bool,
+
+    /// Wakers of substreams waiting for a frame.
+    wakers: Vec<Waker>,
+}
+
+/// Handle for injecting faults into a [`MockConnection`] while it's in use.
+///
+/// Faults apply to frames sent by both the local and the remote end of every substream of the
+/// connection.
+#[derive(Debug, Clone, Default)]
+pub struct FaultInjector {
+    faults: Arc<Mutex<Faults>>,
+}
+
+impl FaultInjector {
+    /// Silently drop the next `count` frames.
+    pub fn drop_frames(&self, count: usize) {
+        self.faults.lock().drop_frames = count;
+    }
+
+    /// Send only the first `len` bytes of the next frame.
+    pub fn truncate_next_frame(&self, len: usize) {
+        self.faults.lock().truncate = Some(len);
+    }
+
+    /// Reset the connection.
+    ///
+    /// All pending and future operations on the substreams of the connection fail with
+    /// [`ErrorKind::NotConnected`].
+    pub fn reset(&self) {
+        let mut faults = self.faults.lock();
+
+        faults.reset = true;
+        faults.wakers.drain(..).for_each(Waker::wake);
+    }
+
+    /// Check if the connection has been reset.
+    pub fn is_reset(&self) -> bool {
+        self.faults.lock().reset
+    }
+
+    /// Apply faults to `frame` which is about to be sent.
+    ///
+    /// Returns `None` if the frame must be dropped.
+    fn on_send(&self, mut frame: Bytes) -> Result<Option<Bytes>, SubstreamError> {
+        let mut faults = self.faults.lock();
+
+        if faults.reset {
+            return Err(SubstreamError::IoError(ErrorKind::NotConnected));
+        }
+
+        if faults.drop_frames > 0 {
+            faults.drop_frames -= 1;
+            return Ok(None);
+        }
+
+        if let Some(len) = faults.truncate.take() {
+            frame.truncate(len);
+        }
+
+        Ok(Some(frame))
+    }
+
+    /// Register `waker` to be woken when the connection is reset.
+    ///
+    /// Returns `true` if the connection has already been reset.
+    fn register(&self, waker: &Waker) -> bool {
+        let mut faults = self.faults.lock();
+
+        if !faults.reset && !faults.wakers.iter().any(|other| other.will_wake(waker)) {
+            faults.wakers.push(waker.clone());
+        }
+
+        faults.reset
+    }
+}
+
+/// In-memory substream of a [`MockConnection`] whose frames are subject to the injected faults.
+#[derive(Debug)]
+pub struct FaultySubstream {
+    /// Underlying substream.
+    inner: PairedSubstream,
+
+    /// Faults of the connection.
+    faults: FaultInjector,
+
+    /// Permit keeping the connection open while the substream is alive.
+    _permit: Option<Permit>,
+}
+
+impl FaultySubstream {
+    /// Create two substreams connected to each other, returning the local and the remote end.
+    ///
+    /// `permit` is held by the local end.
+    fn paired(faults: &FaultInjector, permit: Option<Permit>) -> (Self, Self) {
+        let (local, remote) = paired();
+
+        (
+            Self {
+                inner: local,
+                faults: faults.clone(),
+                _permit: permit,
+            },
+            Self {
+                inner: remote,
+                faults: faults.clone(),
+                _permit: None,
+            },
+        )
+    }
+}
+
+impl Sink<Bytes> for FaultySubstream {
+    type Error = SubstreamError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.faults.is_reset() {
+            return Poll::Ready(Err(SubstreamError::IoError(ErrorKind::NotConnected)));
+        }
+
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        match self.faults.on_send(item)? {
+            Some(frame) => Pin::new(&mut self.inner).start_send(frame),
+            None => Ok(()),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.faults.is_reset() {
+            return Poll::Ready(Err(SubstreamError::IoError(ErrorKind::NotConnected)));
+        }
+
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl Stream for FaultySubstream {
+    type Item = Result<BytesMut, SubstreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.faults.register(cx.waker()) {
+            return Poll::Ready(Some(Err(SubstreamError::IoError(ErrorKind::NotConnected))));
+        }
+
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+/// Connection to a remote peer, as seen by a single protocol.
+#[derive(Debug)]
+pub struct MockConnection {
+    /// Remote peer.
+    peer: PeerId,
+
+    /// Connection ID.
+    connection_id: ConnectionId,
+
+    /// TX channel for sending events to the protocol.
+    tx: Sender<InnerTransportEvent>,
+
+    /// RX channel for receiving commands from the protocol.
+    rx: Receiver<ProtocolCommand>,
+
+    /// Faults of the connection.
+    faults: FaultInjector,
+}
+
+impl MockConnection {
+    /// Establish connection to `peer` after `delay` has passed.
+    ///
+    /// `tx` is the event channel of the tested protocol's
+    /// [`TransportService`](crate::protocol::TransportService).
+    pub async fn establish(peer: PeerId, tx: Sender<InnerTransportEvent>, delay: Duration) -> Self {
+        tokio::time::sleep(delay).await;
+
+        let connection_id = ConnectionId::new();
+        let (cmd_tx, rx) = channel(64);
+
+        tx.send(InnerTransportEvent::ConnectionEstablished {
+            peer,
+            connection: connection_id,
+            endpoint: Endpoint::dialer(Multiaddr::empty(), connection_id),
+            sender: ConnectionHandle::new(connection_id, cmd_tx),
+        })
+        .await
+        .expect("protocol to be running");
+
+        Self {
+            peer,
+            connection_id,
+            tx,
+            rx,
+            faults: FaultInjector::default(),
+        }
+    }
+
+    /// Get handle for injecting faults into the connection.
+    pub fn faults(&self) -> FaultInjector {
+        self.faults.clone()
+    }
+
+    /// Wait for the protocol to open a substream and return the remote end of it.
+    ///
+    /// If the connection has been reset, the open fails and `None` is returned. `None` is also
+    /// returned if the protocol closed the connection.
+    pub async fn accept_substream(&mut self) -> Option<FaultySubstream> {
+        let ProtocolCommand::OpenSubstream {
+            protocol,
+            substream_id,
+            permit,
+            ..
+        } = self.rx.recv().await?
+        else {
+            return None;
+        };
+
+        if self.faults.is_reset() {
+            let _ = self
+                .tx
+                .send(InnerTransportEvent::SubstreamOpenFailure {
+                    substream: substream_id,
+                    error: SubstreamError::ConnectionClosed,
+                })
+                .await;
+            return None;
+        }
+
+        let (local, remote) = FaultySubstream::paired(&self.faults, Some(permit));
+        let _ = self
+            .tx
+            .send(InnerTransportEvent::SubstreamOpened {
+                peer: self.peer,
+                protocol,
+                fallback: None,
+                direction: Direction::Outbound(substream_id),
+                substream: Substream::new_mock(self.peer, substream_id, Box::new(local)),
+            })
+            .await;
+
+        Some(remote)
+    }
+
+    /// Abruptly reset the connection.
+    ///
+    /// The substreams of the connection fail and the protocol is notified that the connection
+    /// was closed.
+    pub async fn reset(&mut self) {
+        self.faults.reset();
+
+        let _ = self
+            .tx
+            .send(InnerTransportEvent::ConnectionClosed {
+                peer: self.peer,
+                connection: self.connection_id,
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+
+    #[tokio::test]
+    async fn faults_applied_to_frames() {
+        let faults = FaultInjector::default();
+        let (mut local, mut remote) = FaultySubstream::paired(&faults, None);
+
+        faults.drop_frames(1);
+        local.send(Bytes::from_static(b"dropped")).await.unwrap();
+
+        faults.truncate_next_frame(4);
+        local.send(Bytes::from_static(b"partial")).await.unwrap();
+        assert_eq!(
+            remote.next().await.unwrap().unwrap(),
+            BytesMut::from(&b"part"[..])
+        );
+
+        local.send(Bytes::from_static(b"intact")).await.unwrap();
+        assert_eq!(
+            remote.next().await.unwrap().unwrap(),
+            BytesMut::from(&b"intact"[..])
+        );
+
+        // reset wakes up the pending reader
+        let reader = tokio::spawn(async move { remote.next().await });
+        tokio::task::yield_now().await;
+        faults.reset();
+
+        assert!(std::matches!(
+            reader.await.unwrap(),
+            Some(Err(SubstreamError::IoError(ErrorKind::NotConnected)))
+        ));
+        assert!(std::matches!(
+            local.send(Bytes::from_static(b"reset")).await,
+            Err(SubstreamError::IoError(ErrorKind::NotConnected))
+        ));
+    }
+}
@@ -18,4 +18,9 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+//! Mock implementations for testing protocols without a transport.
+//!
+//! Available when the `test-utils` feature is enabled.
+
+pub mod connection;
 pub mod substream;
@@ -21,12 +21,19 @@
 use crate::error::SubstreamError;
 
 use bytes::{Bytes, BytesMut};
-use futures::{Sink, Stream};
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    Sink, Stream, StreamExt,
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
 use std::{
+    collections::VecDeque,
     fmt::Debug,
+    io::{self, ErrorKind},
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
 };
 
 /// Trait which describes the behavior of a mock substream.
@@ -88,12 +95,11 @@ mockall::mock! {
 }
 
 /// Dummy substream which just implements `Stream + Sink` and returns `Poll::Pending`/`Ok(())`
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct DummySubstream {}
 
 impl DummySubstream {
     /// Create new [`DummySubstream`].
-    #[cfg(test)]
     pub fn new() -> Self {
         Self {}
     }
@@ -139,6 +145,248 @@ impl Stream for DummySubstream {
     }
 }
 
+/// Substream which echoes every frame written to it back to the writer.
+#[derive(Debug, Default)]
+pub struct EchoSubstream {
+    /// Frames waiting to be read.
+    frames: VecDeque<Bytes>,
+
+    /// Waker of the reader.
+    waker: Option<Waker>,
+
+    /// Has the substream been closed.
+    closed: bool,
+}
+
+impl EchoSubstream {
+    /// Create new [`EchoSubstream`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Sink<Bytes> for EchoSubstream {
+    type Error = SubstreamError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.closed {
+            true => Poll::Ready(Err(SubstreamError::ConnectionClosed)),
+            false => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        if self.closed {
+            return Err(SubstreamError::ConnectionClosed);
+        }
+
+        self.frames.push_back(item);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.closed = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Stream for EchoSubstream {
+    type Item = Result<BytesMut, SubstreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(frame) = self.frames.pop_front() {
+            return Poll::Ready(Some(Ok(BytesMut::from(&frame[..]))));
+        }
+
+        if self.closed {
+            return Poll::Ready(None);
+        }
+
+        self.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// In-memory substream connected to another [`PairedSubstream`].
+///
+/// Frames written to one substream are read from the other one. Closing one substream
+/// terminates the stream of the other one but, like a half-closed substream, the closed
+/// substream can still read frames sent by the other one.
+#[derive(Debug)]
+pub struct PairedSubstream {
+    /// TX channel for frames sent to the other substream.
+    tx: UnboundedSender<Bytes>,
+
+    /// RX channel for frames received from the other substream.
+    rx: UnboundedReceiver<Bytes>,
+}
+
+/// Create two in-memory substreams connected to each other.
+pub fn paired() -> (PairedSubstream, PairedSubstream) {
+    let (tx1, rx1) = unbounded();
+    let (tx2, rx2) = unbounded();
+
+    (
+        PairedSubstream { tx: tx1, rx: rx2 },
+        PairedSubstream { tx: tx2, rx: rx1 },
+    )
+}
+
+impl Sink<Bytes> for PairedSubstream {
+    type Error = SubstreamError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.tx.poll_ready(cx).map_err(|_| SubstreamError::ConnectionClosed)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        self.tx.unbounded_send(item).map_err(|_| SubstreamError::ConnectionClosed)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.tx.close_channel();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Stream for PairedSubstream {
+    type Item = Result<BytesMut, SubstreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx
+            .poll_next_unpin(cx)
+            .map(|frame| frame.map(|frame| Ok(BytesMut::from(&frame[..]))))
+    }
+}
+
+/// Adapter which exposes a mock [`Substream`] as a byte stream.
+///
+/// Each write is sent as one frame and frames are read into the caller's buffer in order.
+/// Bytes of a frame that don't fit in the buffer are returned by the next read.
+#[derive(Debug)]
+pub(crate) struct MockIo {
+    /// Underlying mock substream.
+    inner: Box<dyn Substream>,
+
+    /// Unread bytes of the last received frame.
+    read_buffer: BytesMut,
+}
+
+impl MockIo {
+    /// Create new [`MockIo`].
+    pub(crate) fn new(inner: Box<dyn Substream>) -> Self {
+        Self {
+            inner,
+            read_buffer: BytesMut::new(),
+        }
+    }
+}
+
+/// Convert [`SubstreamError`] into an I/O error.
+fn into_io_error(error: SubstreamError) -> io::Error {
+    match error {
+        SubstreamError::IoError(kind) => kind.into(),
+        SubstreamError::ConnectionClosed => ErrorKind::BrokenPipe.into(),
+        error => io::Error::new(ErrorKind::Other, error),
+    }
+}
+
+impl AsyncRead for MockIo {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.read_buffer.is_empty() {
+            match futures::ready!(self.inner.poll_next_unpin(cx)) {
+                Some(Ok(frame)) => self.read_buffer = frame,
+                Some(Err(error)) => return Poll::Ready(Err(into_io_error(error))),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+
+        let len = std::cmp::min(buf.remaining(), self.read_buffer.len());
+        buf.put_slice(&self.read_buffer.split_to(len));
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for MockIo {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        futures::ready!(Pin::new(&mut self.inner).poll_ready(cx)).map_err(into_io_error)?;
+        Pin::new(&mut self.inner)
+            .start_send(Bytes::copy_from_slice(buf))
+            .map_err(into_io_error)?;
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx).map_err(into_io_error)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx).map_err(into_io_error)
+    }
+}
+
+impl Sink<Bytes> for MockIo {
+    type Error = SubstreamError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        Pin::new(&mut self.inner).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl Stream for MockIo {
+    type Item = Result<BytesMut, SubstreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if !self.read_buffer.is_empty() {
+            let frame = std::mem::take(&mut self.read_buffer);
+            return Poll::Ready(Some(Ok(frame)));
+        }
+
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,4 +416,79 @@ mod tests {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn echo_substream() {
+        let mut substream = EchoSubstream::new();
+
+        substream.send(Bytes::from_static(b"hello")).await.unwrap();
+        substream.send(Bytes::from_static(b"world")).await.unwrap();
+
+        assert_eq!(
+            substream.next().await.unwrap().unwrap(),
+            BytesMut::from(&b"hello"[..])
+        );
+        assert_eq!(
+            substream.next().await.unwrap().unwrap(),
+            BytesMut::from(&b"world"[..])
+        );
+
+        substream.close().await.unwrap();
+        assert!(substream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn paired_substreams() {
+        let (mut substream1, mut substream2) = paired();
+
+        substream1.send(Bytes::from_static(b"ping")).await.unwrap();
+        assert_eq!(
+            substream2.next().await.unwrap().unwrap(),
+            BytesMut::from(&b"ping"[..])
+        );
+
+        substream2.send(Bytes::from_static(b"pong")).await.unwrap();
+        assert_eq!(
+            substream1.next().await.unwrap().unwrap(),
+            BytesMut::from(&b"pong"[..])
+        );
+
+        substream1.close().await.unwrap();
+        assert!(substream2.next().await.is_none());
+
+        substream2.send(Bytes::from_static(b"half-closed")).await.unwrap();
+        assert_eq!(
+            substream1.next().await.unwrap().unwrap(),
+            BytesMut::from(&b"half-closed"[..])
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_io_reads_and_writes_bytes() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (local, mut remote) = paired();
+        let mut io = MockIo::new(Box::new(local));
+
+        io.write_all(b"hello, world").await.unwrap();
+        io.flush().await.unwrap();
+        assert_eq!(
+            remote.next().await.unwrap().unwrap(),
+            BytesMut::from(&b"hello, world"[..])
+        );
+
+        // frames larger than the read buffer are returned over several reads
+        remote.send(Bytes::from_static(b"response")).await.unwrap();
+        let mut buffer = [0u8; 5];
+        io.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"respo");
+        assert_eq!(io.read(&mut buffer).await.unwrap(), 3);
+        assert_eq!(&buffer[..3], b"nse");
+
+        io.shutdown().await.unwrap();
+        assert!(remote.next().await.is_none());
+
+        remote.close().await.unwrap();
+        assert_eq!(io.read(&mut buffer).await.unwrap(), 0);
+    }
 }
@@ -99,6 +99,11 @@ impl<R> LengthDelimited<R> {
         self.inner
     }
 
+    /// Returns a mutable reference to the underlying I/O stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
     /// Converts the [`LengthDelimited`] into a [`LengthDelimitedReader`], dropping the
     /// uvi-framed `Sink` in favour of direct `AsyncWrite` access to the underlying
     /// I/O stream.
@@ -313,6 +318,11 @@ impl<R> LengthDelimitedReader<R> {
     pub fn into_inner(self) -> R {
         self.inner.into_inner()
     }
+
+    /// Returns a mutable reference to the underlying I/O stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
 }
 
 impl<R> Stream for LengthDelimitedReader<R>
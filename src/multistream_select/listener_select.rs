@@ -77,6 +77,7 @@ where
             io: MessageIO::new(inner),
         },
         last_sent_na: false,
+        rejected: Vec::new(),
     }
 }
 
@@ -95,6 +96,19 @@ pub struct ListenerSelectFuture<R, N> {
     /// considered failed, but not with a protocol violation or I/O
     /// error.
     last_sent_na: bool,
+    /// Protocols proposed by the dialer which were rejected.
+    rejected: Vec<Protocol>,
+}
+
+impl<R, N> ListenerSelectFuture<R, N> {
+    /// Get the protocols proposed by the dialer which were rejected because they're not
+    /// supported by the listener.
+    pub fn rejected_protocols(&self) -> Vec<ProtocolName> {
+        self.rejected
+            .iter()
+            .map(|protocol| ProtocolName::from(protocol.to_string()))
+            .collect()
+    }
 }
 
 enum State<R, N> {
@@ -249,6 +263,7 @@ where
                                     "Listener: rejecting protocol: {}",
                                     String::from_utf8_lossy(p.as_ref())
                                 );
+                                this.rejected.push(p.clone());
                                 Message::NotAvailable
                             };
 
@@ -334,12 +349,18 @@ pub enum ListenerSelectResult {
 
         /// `multistream-select` message.
         message: BytesMut,
+
+        /// Protocols proposed before the confirmed protocol which are not supported.
+        rejected: Vec<ProtocolName>,
     },
 
     /// Requested protocol is not available.
     Rejected {
         /// `multistream-select` message.
         message: BytesMut,
+
+        /// Proposed protocols, none of which are supported.
+        rejected: Vec<ProtocolName>,
     },
 }
 
@@ -371,6 +392,8 @@ pub fn listener_negotiate<'a>(
         ));
     }
 
+    let mut rejected = Vec::new();
+
     for protocol in protocol_iter {
         tracing::trace!(
             target: LOG_TARGET,
@@ -385,9 +408,12 @@ pub fn listener_negotiate<'a>(
                     message: encode_multistream_message(std::iter::once(Message::Protocol(
                         protocol,
                     )))?,
+                    rejected,
                 });
             }
         }
+
+        rejected.push(ProtocolName::from(protocol.to_string()));
     }
 
     tracing::trace!(
@@ -397,6 +423,7 @@ pub fn listener_negotiate<'a>(
 
     Ok(ListenerSelectResult::Rejected {
         message: encode_multistream_message(std::iter::once(Message::NotAvailable))?,
+        rejected,
     })
 }
 
@@ -426,8 +453,11 @@ mod tests {
         match listener_negotiate(&mut local_protocols.iter(), message) {
             Err(error) => panic!("error received: {error:?}"),
             Ok(ListenerSelectResult::Rejected { .. }) => panic!("message rejected"),
-            Ok(ListenerSelectResult::Accepted { protocol, message }) => {
+            Ok(ListenerSelectResult::Accepted {
+                protocol, rejected, ..
+            }) => {
                 assert_eq!(protocol, ProtocolName::from("/13371338/proto/1"));
+                assert!(rejected.is_empty());
             }
         }
     }
@@ -529,13 +559,14 @@ mod tests {
 
         match listener_negotiate(&mut local_protocols.iter(), message) {
             Err(error) => panic!("error received: {error:?}"),
-            Ok(ListenerSelectResult::Rejected { message }) => {
+            Ok(ListenerSelectResult::Rejected { message, rejected }) => {
                 assert_eq!(
                     message,
                     encode_multistream_message(std::iter::once(Message::NotAvailable)).unwrap()
                 );
+                assert_eq!(rejected, vec![ProtocolName::from("/13371339/proto/1")]);
             }
-            Ok(ListenerSelectResult::Accepted { protocol, message }) => panic!("message accepted"),
+            Ok(ListenerSelectResult::Accepted { .. }) => panic!("message accepted"),
         }
     }
 }
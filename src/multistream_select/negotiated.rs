@@ -118,6 +118,16 @@ impl<TInner> Negotiated<TInner> {
         }
     }
 
+    /// Returns a mutable reference to the underlying I/O stream, regardless of whether the
+    /// negotiation has been confirmed yet.
+    pub fn get_mut(&mut self) -> &mut TInner {
+        match &mut self.state {
+            State::Completed { io } => io,
+            State::Expecting { io, .. } => io.get_mut(),
+            State::Invalid => panic!("Negotiated: Invalid state"),
+        }
+    }
+
     /// Polls the `Negotiated` for completion.
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), NegotiationError>>
     where
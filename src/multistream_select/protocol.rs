@@ -291,6 +291,11 @@ impl<R> MessageIO<R> {
     pub fn into_inner(self) -> R {
         self.inner.into_inner()
     }
+
+    /// Returns a mutable reference to the underlying I/O stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
 }
 
 impl<R> Sink<Message> for MessageIO<R>
@@ -26,7 +26,23 @@ use crate::{
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
 };
 
-use tokio::sync::mpsc::{error::TrySendError, Sender, WeakSender};
+use futures::task::AtomicWaker;
+use parking_lot::Mutex;
+use tokio::{
+    sync::mpsc::{error::TrySendError, Sender, WeakSender},
+    time::Sleep,
+};
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::Context,
+    time::{Duration, Instant},
+};
 
 /// Connection type, from the point of view of the protocol.
 #[derive(Debug, Clone)]
@@ -40,6 +56,32 @@ enum ConnectionType {
     Inactive(WeakSender<ProtocolCommand>),
 }
 
+/// Drain state shared by all handles of a connection.
+#[derive(Debug, Default)]
+pub(crate) struct DrainState {
+    /// Deadline of the drain, `None` if the connection isn't being drained.
+    deadline: Mutex<Option<Instant>>,
+
+    /// Number of permits acquired through the connection handles that are still alive.
+    permits: AtomicUsize,
+
+    /// Waker of the connection, woken when the drain starts or the last permit is dropped.
+    waker: AtomicWaker,
+}
+
+impl DrainState {
+    /// Register the waker of the connection and get the deadline of the drain, if any.
+    pub(crate) fn poll_deadline(&self, waker: &std::task::Waker) -> Option<Instant> {
+        self.waker.register(waker);
+        *self.deadline.lock()
+    }
+
+    /// Get the number of permits that are still alive.
+    pub(crate) fn num_permits(&self) -> usize {
+        self.permits.load(Ordering::Acquire)
+    }
+}
+
 /// Type representing a handle to connection which allows protocols to communicate with the
 /// connection.
 #[derive(Debug, Clone)]
@@ -49,6 +91,9 @@ pub struct ConnectionHandle {
 
     /// Connection ID.
     connection_id: ConnectionId,
+
+    /// Drain state of the connection.
+    drain: Arc<DrainState>,
 }
 
 impl ConnectionHandle {
@@ -60,6 +105,7 @@ impl ConnectionHandle {
         Self {
             connection_id,
             connection: ConnectionType::Active(connection),
+            drain: Default::default(),
         }
     }
 
@@ -71,7 +117,11 @@ impl ConnectionHandle {
     pub fn downgrade(&mut self) -> Self {
         match &self.connection {
             ConnectionType::Active(connection) => {
-                let handle = Self::new(self.connection_id, connection.clone());
+                let handle = Self {
+                    connection_id: self.connection_id,
+                    connection: ConnectionType::Active(connection.clone()),
+                    drain: Arc::clone(&self.drain),
+                };
                 self.connection = ConnectionType::Inactive(connection.downgrade());
 
                 handle
@@ -96,10 +146,39 @@ impl ConnectionHandle {
 
     /// Attempt to acquire permit which will keep the connection open for indefinite time.
     pub fn try_get_permit(&self) -> Option<Permit> {
-        match &self.connection {
-            ConnectionType::Active(active) => Some(Permit::new(active.clone())),
-            ConnectionType::Inactive(inactive) => Some(Permit::new(inactive.upgrade()?)),
-        }
+        let connection = match &self.connection {
+            ConnectionType::Active(active) => active.clone(),
+            ConnectionType::Inactive(inactive) => inactive.upgrade()?,
+        };
+        self.drain.permits.fetch_add(1usize, Ordering::AcqRel);
+
+        Some(Permit {
+            _connection: Some(connection),
+            drain: Some(Arc::clone(&self.drain)),
+            idle: None,
+        })
+    }
+
+    /// Get the drain state of the connection.
+    pub(crate) fn drain_state(&self) -> &Arc<DrainState> {
+        &self.drain
+    }
+
+    /// Check if the connection is being drained.
+    pub fn is_draining(&self) -> bool {
+        self.drain.deadline.lock().is_some()
+    }
+
+    /// Drain the connection.
+    ///
+    /// No new substreams can be opened over the connection and once all of its substreams have
+    /// been closed, or `deadline` is reached, the connection is closed.
+    ///
+    /// Calling the function again for a connection that's already being drained only moves the
+    /// deadline.
+    pub fn drain(&mut self, deadline: Instant) {
+        *self.drain.deadline.lock() = Some(deadline);
+        self.drain.waker.wake();
     }
 
     /// Open substream to remote peer over `protocol` and send the acquired permit to the
@@ -111,6 +190,35 @@ impl ConnectionHandle {
         substream_id: SubstreamId,
         permit: Permit,
     ) -> Result<(), SubstreamError> {
+        self.send_open_substream(protocol, fallback_names, substream_id, permit, false)
+    }
+
+    /// Open substream for `protocol` whose first message may be sent as early data.
+    ///
+    /// See [`ProtocolCommand::OpenSubstream::early_data`] for more details.
+    pub fn open_early_data_substream(
+        &mut self,
+        protocol: ProtocolName,
+        fallback_names: Vec<ProtocolName>,
+        substream_id: SubstreamId,
+        permit: Permit,
+    ) -> Result<(), SubstreamError> {
+        self.send_open_substream(protocol, fallback_names, substream_id, permit, true)
+    }
+
+    /// Send substream open request to the connection.
+    fn send_open_substream(
+        &mut self,
+        protocol: ProtocolName,
+        fallback_names: Vec<ProtocolName>,
+        substream_id: SubstreamId,
+        permit: Permit,
+        early_data: bool,
+    ) -> Result<(), SubstreamError> {
+        if self.is_draining() {
+            return Err(SubstreamError::ConnectionClosed);
+        }
+
         match &self.connection {
             ConnectionType::Active(active) => active.clone(),
             ConnectionType::Inactive(inactive) =>
@@ -121,6 +229,7 @@ impl ConnectionHandle {
             fallback_names,
             substream_id,
             permit,
+            early_data,
         })
         .map_err(|error| match error {
             TrySendError::Full(_) => SubstreamError::ChannelClogged,
@@ -147,20 +256,95 @@ impl ConnectionHandle {
 #[derive(Debug)]
 pub struct Permit {
     /// Active connection.
-    _connection: Sender<ProtocolCommand>,
+    ///
+    /// `None` if the permit has been released.
+    _connection: Option<Sender<ProtocolCommand>>,
+
+    /// Drain state of the connection if the permit was acquired through a [`ConnectionHandle`].
+    ///
+    /// A released permit is still counted as the substream holding it is alive.
+    drain: Option<Arc<DrainState>>,
+
+    /// Idle timer of the permit, if it keeps the connection open only while there is activity.
+    idle: Option<IdleTimer>,
+}
+
+/// Idle timer of a [`Permit`].
+#[derive(Debug)]
+struct IdleTimer {
+    /// Connection which is upgraded when there is activity after the permit was released.
+    connection: WeakSender<ProtocolCommand>,
+
+    /// Idle timeout.
+    timeout: Duration,
+
+    /// Timer which expires once there has been no activity for `timeout`.
+    timer: Pin<Box<Sleep>>,
 }
 
 impl Permit {
     /// Create new [`Permit`] which allows the connection to be kept open.
     pub fn new(_connection: Sender<ProtocolCommand>) -> Self {
-        Self { _connection }
+        Self {
+            _connection: Some(_connection),
+            drain: None,
+            idle: None,
+        }
+    }
+
+    /// Keep the connection open only while there is activity.
+    ///
+    /// The permit is released once no activity has been reported with [`Permit::on_activity()`]
+    /// for `timeout` and it's acquired again by the next activity, if the connection is still
+    /// open. The owner of the permit must call [`Permit::poll_idle()`] for the timer to expire.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        if let Some(connection) = &self._connection {
+            self.idle = Some(IdleTimer {
+                connection: connection.downgrade(),
+                timeout,
+                timer: Box::pin(tokio::time::sleep(timeout)),
+            });
+        }
+
+        self
+    }
+
+    /// Report activity on the substream holding the permit.
+    pub fn on_activity(&mut self) {
+        if let Some(idle) = &mut self.idle {
+            if self._connection.is_none() {
+                self._connection = idle.connection.upgrade();
+            }
+
+            idle.timer.as_mut().reset(tokio::time::Instant::now() + idle.timeout);
+        }
+    }
+
+    /// Poll the idle timer of the permit and release the permit if it has expired.
+    pub fn poll_idle(&mut self, cx: &mut Context<'_>) {
+        if let Some(idle) = &mut self.idle {
+            if self._connection.is_some() && idle.timer.as_mut().poll(cx).is_ready() {
+                self._connection = None;
+            }
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(drain) = self.drain.take() {
+            if drain.permits.fetch_sub(1usize, Ordering::AcqRel) == 1 {
+                drain.waker.wake();
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::sync::mpsc::channel;
+    use std::task::Poll;
+    use tokio::sync::mpsc::{channel, error::TryRecvError};
 
     #[test]
     #[should_panic]
@@ -240,4 +424,35 @@ mod tests {
             error => panic!("invalid error: {error:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn idle_permit_kept_by_activity() {
+        let (tx, mut rx) = channel(1);
+        let mut permit = Permit::new(tx).with_idle_timeout(Duration::from_millis(500));
+
+        // activity keeps the connection open past the idle timeout
+        for _ in 0..10 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            permit.on_activity();
+
+            futures::future::poll_fn(|cx| {
+                permit.poll_idle(cx);
+                Poll::Ready(())
+            })
+            .await;
+            assert!(std::matches!(rx.try_recv(), Err(TryRecvError::Empty)));
+        }
+
+        // once the substream has been idle for the timeout, the permit is released
+        tokio::time::sleep(Duration::from_millis(700)).await;
+        futures::future::poll_fn(|cx| {
+            permit.poll_idle(cx);
+            Poll::Ready(())
+        })
+        .await;
+        assert!(std::matches!(
+            rx.try_recv(),
+            Err(TryRecvError::Disconnected)
+        ));
+    }
 }
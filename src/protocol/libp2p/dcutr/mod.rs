@@ -0,0 +1,616 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! [`/libp2p/dcutr`](https://github.com/libp2p/specs/blob/master/relay/DCUtR.md) implementation.
+//!
+//! When a connection is established over a relay, the peer that accepted the relayed connection
+//! opens a `/libp2p/dcutr` substream and the peers exchange their addresses in `CONNECT` messages.
+//! The initiator measures the round-trip time of the exchange, sends `SYNC` and starts dialing the
+//! remote peer after half of the round-trip time has elapsed. The remote peer dials immediately
+//! after receiving `SYNC` so that both dials happen at the same time, allowing them to pass
+//! through NATs and firewalls of both peers.
+//!
+//! If a direct connection is established, it replaces the relayed connection and new substreams
+//! are opened over the direct connection.
+
+use crate::{
+    error::{Error, SubstreamError},
+    protocol::{Direction, TransportEvent, TransportService},
+    substream::Substream,
+    transport::{is_relayed_address, Endpoint},
+    types::SubstreamId,
+    PeerId,
+};
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use multiaddr::{Multiaddr, Protocol};
+use multihash::Multihash;
+use prost::Message;
+use tokio::sync::mpsc::Sender;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+
+pub use config::{Config, ConfigBuilder};
+
+mod config;
+
+mod schema {
+    include!(concat!(env!("OUT_DIR"), "/dcutr.rs"));
+}
+
+/// Log target for the file.
+const LOG_TARGET: &str = "litep2p::dcutr";
+
+/// Events emitted by the DCUtR protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DcutrEvent {
+    /// Relayed connection was upgraded to a direct connection.
+    ConnectionUpgraded {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Address of the direct connection.
+        address: Multiaddr,
+    },
+
+    /// Failed to upgrade the relayed connection to a direct connection.
+    ///
+    /// The relayed connection stays open.
+    UpgradeFailed {
+        /// Peer ID.
+        peer: PeerId,
+    },
+}
+
+/// Create `CONNECT` message carrying `addresses`.
+fn connect_message(addresses: &[Multiaddr]) -> Bytes {
+    schema::HolePunch {
+        r#type: schema::hole_punch::Type::Connect as i32,
+        obs_addrs: addresses.iter().map(|address| address.to_vec()).collect(),
+    }
+    .encode_to_vec()
+    .into()
+}
+
+/// Create `SYNC` message.
+fn sync_message() -> Bytes {
+    schema::HolePunch {
+        r#type: schema::hole_punch::Type::Sync as i32,
+        obs_addrs: Vec::new(),
+    }
+    .encode_to_vec()
+    .into()
+}
+
+/// Read message of type `expected` from `substream` and return the addresses it carries.
+async fn read_message(
+    substream: &mut Substream,
+    expected: schema::hole_punch::Type,
+) -> crate::Result<Vec<Multiaddr>> {
+    let payload = substream
+        .next()
+        .await
+        .ok_or(Error::SubstreamError(SubstreamError::ReadFailure(None)))??;
+    let message = schema::HolePunch::decode(payload.freeze())?;
+
+    if message.r#type != expected as i32 {
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?expected,
+            received = ?message.r#type,
+            "unexpected message type",
+        );
+        return Err(Error::InvalidData);
+    }
+
+    Ok(message
+        .obs_addrs
+        .into_iter()
+        .filter_map(|address| Multiaddr::try_from(address).ok())
+        .collect())
+}
+
+/// Coordinate the hole punch as the initiator.
+///
+/// Exchanges `CONNECT` messages with the remote peer, sends `SYNC` and then waits for half of the
+/// measured round-trip time which is when `SYNC` is expected to reach the remote peer.
+///
+/// Returns the addresses of the remote peer and the measured round-trip time.
+async fn initiate_hole_punch(
+    mut substream: Substream,
+    addresses: Vec<Multiaddr>,
+    timeout: Duration,
+) -> crate::Result<(Vec<Multiaddr>, Duration)> {
+    let future = async move {
+        substream.send_framed(connect_message(&addresses)).await?;
+        let started = Instant::now();
+
+        let remote_addresses =
+            read_message(&mut substream, schema::hole_punch::Type::Connect).await?;
+        let rtt = started.elapsed();
+
+        substream.send_framed(sync_message()).await?;
+        substream.close().await;
+
+        Ok::<_, Error>((remote_addresses, rtt))
+    };
+
+    let (remote_addresses, rtt) = match tokio::time::timeout(timeout, future).await {
+        Err(_) => return Err(Error::Timeout),
+        Ok(result) => result?,
+    };
+
+    tokio::time::sleep(rtt / 2).await;
+
+    Ok((remote_addresses, rtt))
+}
+
+/// Coordinate the hole punch as the responder.
+///
+/// Exchanges `CONNECT` messages with the remote peer and returns the addresses of the remote peer
+/// after `SYNC` has been received.
+async fn respond_hole_punch(
+    mut substream: Substream,
+    addresses: Vec<Multiaddr>,
+    timeout: Duration,
+) -> crate::Result<Vec<Multiaddr>> {
+    let future = async move {
+        let remote_addresses =
+            read_message(&mut substream, schema::hole_punch::Type::Connect).await?;
+        substream.send_framed(connect_message(&addresses)).await?;
+        read_message(&mut substream, schema::hole_punch::Type::Sync).await?;
+
+        Ok(remote_addresses)
+    };
+
+    match tokio::time::timeout(timeout, future).await {
+        Err(_) => Err(Error::Timeout),
+        Ok(result) => result,
+    }
+}
+
+/// Convert addresses received from `peer` into dialable addresses.
+///
+/// Relayed addresses and addresses of other peers are discarded and the peer ID of `peer` is
+/// appended to addresses which don't contain it.
+fn dial_addresses(peer: &PeerId, addresses: Vec<Multiaddr>) -> VecDeque<Multiaddr> {
+    addresses
+        .into_iter()
+        .filter(|address| !is_relayed_address(address))
+        .filter_map(|address| {
+            let remote = match address.iter().last() {
+                Some(Protocol::P2p(multihash)) => Some(multihash),
+                _ => None,
+            };
+
+            match remote {
+                Some(multihash) => (multihash == Multihash::from(*peer)).then_some(address),
+                None => Some(address.with(Protocol::P2p(Multihash::from(*peer)))),
+            }
+        })
+        .collect()
+}
+
+/// DCUtR protocol.
+pub(crate) struct Dcutr {
+    // Connection service.
+    service: TransportService,
+
+    /// TX channel for sending events to the user protocol.
+    tx: Sender<DcutrEvent>,
+
+    /// Timeout for coordinating the hole punch over the relayed connection.
+    timeout: Duration,
+
+    /// Peers connected over a relay.
+    relayed: HashSet<PeerId>,
+
+    /// Pending outbound substreams.
+    pending_opens: HashMap<SubstreamId, PeerId>,
+
+    /// Pending hole punch coordinations.
+    hole_punches: FuturesUnordered<BoxFuture<'static, (PeerId, crate::Result<Vec<Multiaddr>>)>>,
+
+    /// Addresses that haven't been dialed yet for peers which are being dialed directly.
+    pending_dials: HashMap<PeerId, VecDeque<Multiaddr>>,
+}
+
+impl Dcutr {
+    /// Create new [`Dcutr`] protocol.
+    pub fn new(service: TransportService, config: Config) -> Self {
+        Self {
+            service,
+            tx: config.tx_event,
+            timeout: config.timeout,
+            relayed: HashSet::new(),
+            pending_opens: HashMap::new(),
+            hole_punches: FuturesUnordered::new(),
+            pending_dials: HashMap::new(),
+        }
+    }
+
+    /// Get the non-relayed addresses of the local node.
+    fn local_addresses(&self) -> Vec<Multiaddr> {
+        let mut addresses = self.service.public_addresses().get_addresses();
+
+        for address in self.service.listen_addresses() {
+            if !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+
+        addresses.retain(|address| !is_relayed_address(address));
+        addresses
+    }
+
+    /// Connection established to remote peer.
+    fn on_connection_established(&mut self, peer: PeerId, endpoint: Endpoint) {
+        if !endpoint.is_relayed() {
+            return;
+        }
+
+        tracing::trace!(target: LOG_TARGET, ?peer, ?endpoint, "relayed connection established");
+        self.relayed.insert(peer);
+
+        // the peer that accepted the relayed connection initiates the hole punch
+        if !endpoint.is_listener() {
+            return;
+        }
+
+        match self.service.open_substream(peer) {
+            Ok(substream_id) => {
+                self.pending_opens.insert(substream_id, peer);
+            }
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?error,
+                    "failed to open substream over relayed connection",
+                );
+            }
+        }
+    }
+
+    /// Connection closed to remote peer.
+    fn on_connection_closed(&mut self, peer: PeerId) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "connection closed");
+
+        self.relayed.remove(&peer);
+        self.pending_dials.remove(&peer);
+    }
+
+    /// Connection of the remote peer changed its address.
+    async fn on_connection_address_changed(&mut self, peer: PeerId, endpoint: Endpoint) {
+        if endpoint.is_relayed() || !self.relayed.remove(&peer) {
+            return;
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            address = ?endpoint.address(),
+            "relayed connection upgraded to a direct connection",
+        );
+
+        self.pending_dials.remove(&peer);
+        let _ = self
+            .tx
+            .send(DcutrEvent::ConnectionUpgraded {
+                peer,
+                address: endpoint.address().clone(),
+            })
+            .await;
+    }
+
+    /// Handle outbound substream.
+    fn on_outbound_substream(&mut self, peer: PeerId, substream: Substream) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "initiate hole punch");
+
+        let addresses = self.local_addresses();
+        let timeout = self.timeout;
+
+        self.hole_punches.push(Box::pin(async move {
+            let result =
+                initiate_hole_punch(substream, addresses, timeout)
+                    .await
+                    .map(|(addresses, rtt)| {
+                        tracing::trace!(target: LOG_TARGET, ?peer, ?rtt, "hole punch synchronized");
+                        addresses
+                    });
+
+            (peer, result)
+        }));
+    }
+
+    /// Handle inbound substream.
+    fn on_inbound_substream(&mut self, peer: PeerId, substream: Substream) {
+        if !self.relayed.contains(&peer) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                "inbound substream from a peer that is not connected over a relay",
+            );
+            return;
+        }
+
+        tracing::trace!(target: LOG_TARGET, ?peer, "respond to hole punch");
+
+        let addresses = self.local_addresses();
+        let timeout = self.timeout;
+
+        self.hole_punches.push(Box::pin(async move {
+            (
+                peer,
+                respond_hole_punch(substream, addresses, timeout).await,
+            )
+        }));
+    }
+
+    /// Hole punch coordination concluded.
+    async fn on_coordination_result(
+        &mut self,
+        peer: PeerId,
+        result: crate::Result<Vec<Multiaddr>>,
+    ) {
+        let addresses = match result {
+            Ok(addresses) => addresses,
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?error,
+                    "failed to coordinate hole punch",
+                );
+
+                let _ = self.tx.send(DcutrEvent::UpgradeFailed { peer }).await;
+                return;
+            }
+        };
+
+        // connection was either closed or upgraded while the hole punch was being coordinated
+        if !self.relayed.contains(&peer) {
+            return;
+        }
+
+        self.pending_dials.insert(peer, dial_addresses(&peer, addresses));
+        self.dial_next(peer).await;
+    }
+
+    /// Dial the next address of `peer`, reporting failure to the user if all addresses have been
+    /// tried.
+    async fn dial_next(&mut self, peer: PeerId) {
+        let Some(addresses) = self.pending_dials.get_mut(&peer) else {
+            return;
+        };
+
+        while let Some(address) = addresses.pop_front() {
+            match self.service.dial_address(address.clone()) {
+                Ok(()) => {
+                    tracing::trace!(target: LOG_TARGET, ?peer, ?address, "dial peer directly");
+                    return;
+                }
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?address,
+                        ?error,
+                        "failed to dial peer directly",
+                    );
+                }
+            }
+        }
+
+        self.pending_dials.remove(&peer);
+        let _ = self.tx.send(DcutrEvent::UpgradeFailed { peer }).await;
+    }
+
+    /// Start [`Dcutr`] event loop.
+    pub async fn run(mut self) {
+        tracing::debug!(target: LOG_TARGET, "starting dcutr event loop");
+
+        loop {
+            tokio::select! {
+                event = self.service.next() => match event {
+                    Some(TransportEvent::ConnectionEstablished { peer, endpoint }) => {
+                        self.on_connection_established(peer, endpoint);
+                    }
+                    Some(TransportEvent::ConnectionClosed { peer }) => {
+                        self.on_connection_closed(peer);
+                    }
+                    Some(TransportEvent::ConnectionAddressChanged { peer, endpoint }) => {
+                        self.on_connection_address_changed(peer, endpoint).await;
+                    }
+                    Some(TransportEvent::DialFailure { peer, address }) => {
+                        tracing::trace!(target: LOG_TARGET, ?peer, ?address, "dial failure");
+                        self.dial_next(peer).await;
+                    }
+                    Some(TransportEvent::SubstreamOpened {
+                        peer,
+                        substream,
+                        direction,
+                        ..
+                    }) => match direction {
+                        Direction::Inbound => {
+                            self.on_inbound_substream(peer, substream);
+                        }
+                        Direction::Outbound(substream_id) => {
+                            match self.pending_opens.remove(&substream_id) {
+                                Some(stored_peer) => {
+                                    debug_assert!(peer == stored_peer);
+                                    self.on_outbound_substream(peer, substream);
+                                }
+                                None => {
+                                    tracing::warn!(
+                                        target: LOG_TARGET,
+                                        ?substream_id,
+                                        "outbound dcutr substream ID does not exist",
+                                    );
+                                }
+                            }
+                        }
+                    },
+                    Some(TransportEvent::SubstreamOpenFailure { substream, error }) => {
+                        if let Some(peer) = self.pending_opens.remove(&substream) {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                ?error,
+                                "failed to open dcutr substream",
+                            );
+
+                            let _ = self.tx.send(DcutrEvent::UpgradeFailed { peer }).await;
+                        }
+                    }
+                    None => return,
+                },
+                event = self.hole_punches.next(), if !self.hole_punches.is_empty() => {
+                    if let Some((peer, result)) = event {
+                        self.on_coordination_result(peer, result).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::ProtocolCodec;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream, ReadHalf, WriteHalf};
+
+    /// Forward data from `from` to `to`, delaying each read chunk by `latency`.
+    fn relay(mut from: ReadHalf<DuplexStream>, mut to: WriteHalf<DuplexStream>, latency: Duration) {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; 1024];
+
+            while let Ok(nread) = from.read(&mut buffer).await {
+                if nread == 0 {
+                    break;
+                }
+
+                let deadline = tokio::time::Instant::now() + latency;
+                if tx.send((deadline, buffer[..nread].to_vec())).is_err() {
+                    break;
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            while let Some((deadline, data)) = rx.recv().await {
+                tokio::time::sleep_until(deadline).await;
+
+                if to.write_all(&data).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn dials_are_synchronized_over_relay() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        const LATENCY: Duration = Duration::from_millis(100);
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        // simulated relay which forwards data between the peers with `LATENCY` delay
+        let (initiator_io, relay_initiator) = tokio::io::duplex(1024);
+        let (responder_io, relay_responder) = tokio::io::duplex(1024);
+        let (initiator_read, initiator_write) = tokio::io::split(relay_initiator);
+        let (responder_read, responder_write) = tokio::io::split(relay_responder);
+
+        relay(initiator_read, responder_write, LATENCY);
+        relay(responder_read, initiator_write, LATENCY);
+
+        let codec = ProtocolCodec::UnsignedVarint(Some(4096));
+        let initiator_addresses: Vec<Multiaddr> = vec!["/ip4/1.1.1.1/tcp/8888".parse().unwrap()];
+        let responder_addresses: Vec<Multiaddr> =
+            vec!["/ip4/2.2.2.2/udp/8888/quic-v1".parse().unwrap()];
+
+        let (initiator, responder) = tokio::join!(
+            async {
+                let substream = Substream::new_duplex(
+                    PeerId::random(),
+                    SubstreamId::from(0usize),
+                    initiator_io,
+                    codec,
+                );
+                let result =
+                    initiate_hole_punch(substream, initiator_addresses.clone(), TIMEOUT).await;
+
+                (result, Instant::now())
+            },
+            async {
+                let substream = Substream::new_duplex(
+                    PeerId::random(),
+                    SubstreamId::from(1usize),
+                    responder_io,
+                    codec,
+                );
+                let result =
+                    respond_hole_punch(substream, responder_addresses.clone(), TIMEOUT).await;
+
+                (result, Instant::now())
+            },
+        );
+
+        let ((addresses, rtt), initiator_dial) = (initiator.0.unwrap(), initiator.1);
+        let (remote_addresses, responder_dial) = (responder.0.unwrap(), responder.1);
+
+        assert_eq!(addresses, responder_addresses);
+        assert_eq!(remote_addresses, initiator_addresses);
+
+        // `CONNECT` travels through the relay in both directions
+        assert!(
+            rtt >= 2 * LATENCY && rtt < 3 * LATENCY,
+            "invalid rtt: {rtt:?}"
+        );
+
+        // initiator dials half a round-trip after sending `SYNC` which is when the responder
+        // receives `SYNC` and dials
+        let skew = initiator_dial.max(responder_dial) - initiator_dial.min(responder_dial);
+        assert!(skew < LATENCY / 2, "dials not synchronized: {skew:?}");
+    }
+
+    #[test]
+    fn relayed_and_foreign_addresses_are_not_dialed() {
+        let peer = PeerId::random();
+        let direct: Multiaddr = "/ip4/1.1.1.1/tcp/8888".parse().unwrap();
+        let with_peer = direct.clone().with(Protocol::P2p(Multihash::from(peer)));
+        let foreign = direct.clone().with(Protocol::P2p(Multihash::from(PeerId::random())));
+        let relayed = foreign.clone().with(Protocol::P2pCircuit);
+
+        assert_eq!(
+            dial_addresses(&peer, vec![direct, with_peer.clone(), foreign, relayed]),
+            VecDeque::from([with_peer.clone(), with_peer]),
+        );
+    }
+}
@@ -32,9 +32,12 @@ use crate::{
 };
 
 use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
-use multiaddr::Multiaddr;
+use multiaddr::{Multiaddr, Protocol};
 use prost::Message;
-use tokio::sync::mpsc::{channel, Sender};
+use tokio::sync::{
+    mpsc::{channel, Sender},
+    watch,
+};
 use tokio_stream::wrappers::ReceiverStream;
 
 use std::{
@@ -49,7 +52,7 @@ const LOG_TARGET: &str = "litep2p::ipfs::identify";
 const PROTOCOL_NAME: &str = "/ipfs/id/1.0.0";
 
 /// IPFS Identify push protocol name.
-const _PUSH_PROTOCOL_NAME: &str = "/ipfs/id/push/1.0.0";
+const PUSH_PROTOCOL_NAME: &str = "/ipfs/id/push/1.0.0";
 
 /// Default agent version.
 const DEFAULT_AGENT: &str = "litep2p/1.0.0";
@@ -67,6 +70,9 @@ pub struct Config {
     /// Protocol name.
     pub(crate) protocol: ProtocolName,
 
+    /// Push protocol name.
+    pub(crate) push_protocol: ProtocolName,
+
     /// Codec used by the protocol.
     pub(crate) codec: ProtocolCodec,
 
@@ -106,6 +112,7 @@ impl Config {
                 codec: ProtocolCodec::UnsignedVarint(Some(IDENTIFY_PAYLOAD_SIZE)),
                 protocols: Vec::new(),
                 protocol: ProtocolName::from(PROTOCOL_NAME),
+                push_protocol: ProtocolName::from(PUSH_PROTOCOL_NAME),
             },
             Box::new(ReceiverStream::new(rx_event)),
         )
@@ -116,6 +123,10 @@ impl Config {
 #[derive(Debug)]
 pub enum IdentifyEvent {
     /// Peer identified.
+    ///
+    /// Emitted when the peer responds to an identify request and each time the peer pushes
+    /// updated information using `/ipfs/id/push/1.0.0`. In both cases, the listen addresses of
+    /// the peer are added to its known addresses before the event is emitted.
     PeerIdentified {
         /// Peer ID.
         peer: PeerId,
@@ -162,6 +173,12 @@ pub(crate) struct Identify {
     // Connection service.
     service: TransportService,
 
+    /// Connection service for `/ipfs/id/push/1.0.0`.
+    push_service: TransportService,
+
+    /// Notifies about changes in the public addresses of the local node.
+    address_changes: watch::Receiver<()>,
+
     /// TX channel for sending events to the user protocol.
     tx: Sender<IdentifyEvent>,
 
@@ -188,13 +205,28 @@ pub(crate) struct Identify {
 
     /// Pending inbound substreams.
     pending_inbound: FuturesUnordered<BoxFuture<'static, ()>>,
+
+    /// Pending outbound pushes.
+    pending_pushes: FuturesUnordered<BoxFuture<'static, ()>>,
+
+    /// Pending inbound pushes.
+    pending_inbound_pushes: FuturesUnordered<BoxFuture<'static, crate::Result<IdentifyResponse>>>,
 }
 
 impl Identify {
     /// Create new [`Identify`] protocol.
-    pub(crate) fn new(service: TransportService, config: Config) -> Self {
+    pub(crate) fn new(
+        service: TransportService,
+        push_service: TransportService,
+        config: Config,
+    ) -> Self {
+        let mut address_changes = service.public_addresses().subscribe();
+        address_changes.borrow_and_update();
+
         Self {
             service,
+            push_service,
+            address_changes,
             tx: config.tx_event,
             peers: HashMap::new(),
             public: config.public.expect("public key to be supplied"),
@@ -203,6 +235,8 @@ impl Identify {
             pending_opens: HashMap::new(),
             pending_inbound: FuturesUnordered::new(),
             pending_outbound: FuturesUnordered::new(),
+            pending_pushes: FuturesUnordered::new(),
+            pending_inbound_pushes: FuturesUnordered::new(),
             protocols: config.protocols.iter().map(|protocol| protocol.to_string()).collect(),
         }
     }
@@ -225,43 +259,33 @@ impl Identify {
         self.peers.remove(&peer);
     }
 
-    /// Inbound substream opened.
-    fn on_inbound_substream(
-        &mut self,
-        peer: PeerId,
-        protocol: ProtocolName,
-        mut substream: Substream,
-    ) {
-        tracing::trace!(
-            target: LOG_TARGET,
-            ?peer,
-            ?protocol,
-            "inbound substream opened"
-        );
-
-        let observed_addr = match self.peers.get(&peer) {
-            Some(endpoint) => Some(endpoint.address().to_vec()),
-            None => {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    ?peer,
-                    %protocol,
-                    "inbound identify substream opened for peer who doesn't exist",
-                );
-                None
+    /// Create identify message describing the local node to `peer`.
+    fn identify_message(&self, peer: &PeerId) -> Vec<u8> {
+        let observed_addr = self.peers.get(peer).map(|endpoint| endpoint.address().to_vec());
+
+        // external addresses set by the user are advertised first so remote peers prefer them
+        // over the addresses the local node is listening on
+        let mut listen_addr: Vec<_> = self
+            .service
+            .public_addresses()
+            .inner
+            .read()
+            .iter()
+            .map(|addr| addr.to_vec())
+            .collect();
+        for address in self.service.listen_addresses() {
+            let address = address.to_vec();
+
+            if !listen_addr.contains(&address) {
+                listen_addr.push(address);
             }
-        };
-
-        let mut listen_addr: HashSet<_> =
-            self.service.listen_addresses().into_iter().map(|addr| addr.to_vec()).collect();
-        listen_addr
-            .extend(self.service.public_addresses().inner.read().iter().map(|addr| addr.to_vec()));
+        }
 
         let identify = identify_schema::Identify {
             protocol_version: Some(self.protocol_version.clone()),
             agent_version: Some(self.user_agent.clone()),
             public_key: Some(self.public.to_protobuf_encoding()),
-            listen_addrs: listen_addr.into_iter().collect(),
+            listen_addrs: listen_addr,
             observed_addr,
             protocols: self.protocols.clone(),
         };
@@ -270,13 +294,22 @@ impl Identify {
             target: LOG_TARGET,
             ?peer,
             ?identify,
-            "sending identify response",
+            "create identify message",
         );
 
         let mut msg = Vec::with_capacity(identify.encoded_len());
         identify.encode(&mut msg).expect("`msg` to have enough capacity");
 
-        self.pending_inbound.push(Box::pin(async move {
+        msg
+    }
+
+    /// Send identify message to remote peer.
+    fn send_message(
+        peer: PeerId,
+        mut substream: Substream,
+        msg: Vec<u8>,
+    ) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
             match tokio::time::timeout(Duration::from_secs(10), substream.send_framed(msg.into()))
                 .await
             {
@@ -285,7 +318,7 @@ impl Identify {
                         target: LOG_TARGET,
                         ?peer,
                         ?error,
-                        "timed out while sending ipfs identify response",
+                        "timed out while sending ipfs identify message",
                     );
                 }
                 Ok(Err(error)) => {
@@ -293,38 +326,28 @@ impl Identify {
                         target: LOG_TARGET,
                         ?peer,
                         ?error,
-                        "failed to send ipfs identify response",
+                        "failed to send ipfs identify message",
                     );
                 }
                 Ok(_) => {}
             }
-        }))
+        })
     }
 
-    /// Outbound substream opened.
-    fn on_outbound_substream(
-        &mut self,
+    /// Read identify message from remote peer.
+    fn read_message(
         peer: PeerId,
-        protocol: ProtocolName,
-        substream_id: SubstreamId,
+        substream_id: Option<SubstreamId>,
         mut substream: Substream,
-    ) {
-        tracing::trace!(
-            target: LOG_TARGET,
-            ?peer,
-            ?protocol,
-            ?substream_id,
-            "outbound substream opened"
-        );
-
-        self.pending_outbound.push(Box::pin(async move {
+    ) -> BoxFuture<'static, crate::Result<IdentifyResponse>> {
+        Box::pin(async move {
             let payload =
                 match tokio::time::timeout(Duration::from_secs(10), substream.next()).await {
                     Err(_) => return Err(Error::Timeout),
                     Ok(None) =>
-                        return Err(Error::SubstreamError(SubstreamError::ReadFailure(Some(
+                        return Err(Error::SubstreamError(SubstreamError::ReadFailure(
                             substream_id,
-                        )))),
+                        ))),
                     Ok(Some(Err(error))) => return Err(error.into()),
                     Ok(Some(Ok(payload))) => payload,
                 };
@@ -351,7 +374,131 @@ impl Identify {
                 observed_address,
                 listen_addresses,
             })
-        }));
+        })
+    }
+
+    /// Inbound substream opened.
+    fn on_inbound_substream(&mut self, peer: PeerId, protocol: ProtocolName, substream: Substream) {
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?peer,
+            ?protocol,
+            "inbound substream opened"
+        );
+
+        if !self.peers.contains_key(&peer) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?peer,
+                %protocol,
+                "inbound identify substream opened for peer who doesn't exist",
+            );
+        }
+
+        let msg = self.identify_message(&peer);
+        self.pending_inbound.push(Self::send_message(peer, substream, msg));
+    }
+
+    /// Outbound substream opened.
+    fn on_outbound_substream(
+        &mut self,
+        peer: PeerId,
+        protocol: ProtocolName,
+        substream_id: SubstreamId,
+        substream: Substream,
+    ) {
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?peer,
+            ?protocol,
+            ?substream_id,
+            "outbound substream opened"
+        );
+
+        self.pending_outbound
+            .push(Self::read_message(peer, Some(substream_id), substream));
+    }
+
+    /// Public addresses of the local node have changed.
+    ///
+    /// Push the updated information to all connected peers.
+    fn on_addresses_changed(&mut self) {
+        tracing::trace!(
+            target: LOG_TARGET,
+            num_peers = ?self.peers.len(),
+            "public addresses changed, push identify to connected peers",
+        );
+
+        for peer in self.peers.keys() {
+            if let Err(error) = self.push_service.open_substream(*peer) {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    ?error,
+                    "failed to open substream for identify push",
+                );
+            }
+        }
+    }
+
+    /// Substream for `/ipfs/id/push/1.0.0` opened.
+    fn on_push_substream(&mut self, peer: PeerId, direction: Direction, substream: Substream) {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?direction, "push substream opened");
+
+        match direction {
+            Direction::Inbound =>
+                self.pending_inbound_pushes.push(Self::read_message(peer, None, substream)),
+            Direction::Outbound(_) => {
+                let msg = self.identify_message(&peer);
+                self.pending_pushes.push(Self::send_message(peer, substream, msg));
+            }
+        }
+    }
+
+    /// Get the addresses of `addresses` which can belong to `peer`.
+    ///
+    /// Addresses ending with the peer ID of another peer are ignored.
+    fn peer_addresses<'a>(
+        peer: &'a PeerId,
+        addresses: &'a [Multiaddr],
+    ) -> impl Iterator<Item = Multiaddr> + 'a {
+        addresses
+            .iter()
+            .filter(move |address| match address.iter().last() {
+                Some(Protocol::P2p(multihash)) =>
+                    PeerId::from_multihash(multihash).ok() == Some(*peer),
+                _ => true,
+            })
+            .cloned()
+    }
+
+    /// Add the listen addresses of the identified peer to its known addresses and report the
+    /// peer to the user protocol.
+    ///
+    /// Identify responses and pushes are handled the same way.
+    async fn on_peer_identified(&mut self, response: IdentifyResponse) {
+        self.service.add_known_address(
+            &response.peer,
+            Self::peer_addresses(&response.peer, &response.listen_addresses),
+        );
+
+        let _ = self
+            .tx
+            .send(IdentifyEvent::PeerIdentified {
+                peer: response.peer,
+                protocol_version: response.protocol_version,
+                user_agent: response.user_agent,
+                supported_protocols: response
+                    .supported_protocols
+                    .into_iter()
+                    .map(From::from)
+                    .collect(),
+                observed_address: response
+                    .observed_address
+                    .map_or(Multiaddr::empty(), |address| address),
+                listen_addresses: response.listen_addresses,
+            })
+            .await;
     }
 
     /// Start [`Identify`] event loop.
@@ -381,22 +528,28 @@ impl Identify {
                     _ => {}
                 },
                 _ = self.pending_inbound.next(), if !self.pending_inbound.is_empty() => {}
+                _ = self.pending_pushes.next(), if !self.pending_pushes.is_empty() => {}
                 event = self.pending_outbound.next(), if !self.pending_outbound.is_empty() => match event {
-                    Some(Ok(response)) => {
-                        let _ = self.tx
-                            .send(IdentifyEvent::PeerIdentified {
-                                peer: response.peer,
-                                protocol_version: response.protocol_version,
-                                user_agent: response.user_agent,
-                                supported_protocols: response.supported_protocols.into_iter().map(From::from).collect(),
-                                observed_address: response.observed_address.map_or(Multiaddr::empty(), |address| address),
-                                listen_addresses: response.listen_addresses,
-                            })
-                            .await;
-                    }
+                    Some(Ok(response)) => self.on_peer_identified(response).await,
                     Some(Err(error)) => tracing::debug!(target: LOG_TARGET, ?error, "failed to read ipfs identify response"),
                     None => return,
-                }
+                },
+                event = self.pending_inbound_pushes.next(), if !self.pending_inbound_pushes.is_empty() => match event {
+                    Some(Ok(response)) => self.on_peer_identified(response).await,
+                    Some(Err(error)) => tracing::debug!(target: LOG_TARGET, ?error, "failed to read ipfs identify push"),
+                    None => return,
+                },
+                event = self.push_service.next() => match event {
+                    None => return,
+                    Some(TransportEvent::SubstreamOpened {
+                        peer,
+                        direction,
+                        substream,
+                        ..
+                    }) => self.on_push_substream(peer, direction, substream),
+                    _ => {}
+                },
+                _ = self.address_changes.changed() => self.on_addresses_changed(),
             }
         }
     }
@@ -430,6 +583,27 @@ mod tests {
         (Litep2p::new(config).unwrap(), identify, peer)
     }
 
+    #[test]
+    fn addresses_of_other_peers_ignored() {
+        let peer = PeerId::random();
+        let addresses = vec![
+            "/ip4/1.1.1.1/tcp/8888".parse::<Multiaddr>().unwrap(),
+            "/ip4/1.1.1.1/tcp/8889"
+                .parse::<Multiaddr>()
+                .unwrap()
+                .with(Protocol::P2p(peer.into())),
+            "/ip4/1.1.1.1/tcp/8890"
+                .parse::<Multiaddr>()
+                .unwrap()
+                .with(Protocol::P2p(PeerId::random().into())),
+        ];
+
+        assert_eq!(
+            Identify::peer_addresses(&peer, &addresses).collect::<Vec<_>>(),
+            addresses[..2].to_vec(),
+        );
+    }
+
     #[tokio::test]
     async fn update_identify_addresses() {
         // Create two instances of litep2p
@@ -471,4 +645,102 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn external_address_advertised_first() {
+        let (mut litep2p1, mut event_stream1, peer1) = create_litep2p();
+        let (mut litep2p2, mut event_stream2, _peer2) = create_litep2p();
+        let litep2p1_address = litep2p1.listen_addresses().next().unwrap().clone();
+
+        let external: Multiaddr = "/ip4/1.2.3.4/tcp/30333".parse().unwrap();
+        let expected_multiaddr = external.clone().with(Protocol::P2p(peer1.into()));
+
+        assert!(litep2p1.add_external_address(external.clone()).unwrap());
+        assert!(!litep2p1.add_external_address(expected_multiaddr.clone()).unwrap());
+        assert_eq!(
+            litep2p1.external_addresses(),
+            vec![expected_multiaddr.clone()]
+        );
+
+        // removing and re-adding the address works without the peer ID as well
+        assert!(litep2p1.remove_external_address(&external));
+        assert!(litep2p1.external_addresses().is_empty());
+        assert!(litep2p1.add_external_address(external).unwrap());
+
+        litep2p2.dial_address(litep2p1_address).await.unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = litep2p1.next_event() => {}
+                    _event = event_stream1.next() => {}
+                }
+            }
+        });
+
+        loop {
+            tokio::select! {
+                _ = litep2p2.next_event() => {}
+                event = event_stream2.next() => if let Some(IdentifyEvent::PeerIdentified {
+                    listen_addresses,
+                    ..
+                }) = event {
+                    assert_eq!(listen_addresses.first(), Some(&expected_multiaddr));
+                    assert!(listen_addresses.len() > 1);
+                    break;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn address_change_pushed_to_connected_peers() {
+        let (mut litep2p1, mut event_stream1, peer1) = create_litep2p();
+        let (mut litep2p2, mut event_stream2, _peer2) = create_litep2p();
+        let litep2p1_address = litep2p1.listen_addresses().next().unwrap().clone();
+        let public_addresses = litep2p1.public_addresses();
+
+        litep2p2.dial_address(litep2p1_address).await.unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = litep2p1.next_event() => {}
+                    _event = event_stream1.next() => {}
+                }
+            }
+        });
+
+        let external: Multiaddr = "/ip4/1.2.3.4/tcp/30333".parse().unwrap();
+        let expected_multiaddr = external.clone().with(Protocol::P2p(peer1.into()));
+        let mut identified = false;
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                tokio::select! {
+                    _ = litep2p2.next_event() => {}
+                    event = event_stream2.next() => match event {
+                        Some(IdentifyEvent::PeerIdentified { peer, listen_addresses, .. })
+                            if !identified =>
+                        {
+                            assert_eq!(peer, peer1);
+                            assert!(!listen_addresses.contains(&expected_multiaddr));
+
+                            // change the address after the initial identify exchange is done
+                            identified = true;
+                            assert!(public_addresses.add_address(external.clone()).unwrap());
+                        }
+                        Some(IdentifyEvent::PeerIdentified { peer, listen_addresses, .. }) => {
+                            assert_eq!(peer, peer1);
+                            assert_eq!(listen_addresses.first(), Some(&expected_multiaddr));
+                            break;
+                        }
+                        None => panic!("event stream terminated"),
+                    }
+                }
+            }
+        })
+        .await
+        .expect("identify push to be received");
+    }
 }
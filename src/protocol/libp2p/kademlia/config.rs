@@ -20,9 +20,12 @@
 
 use crate::{
     codec::ProtocolCodec,
-    protocol::libp2p::kademlia::handle::{
-        IncomingRecordValidationMode, KademliaCommand, KademliaEvent, KademliaHandle,
-        RoutingTableUpdateMode,
+    protocol::libp2p::kademlia::{
+        handle::{
+            IncomingRecordValidationMode, KademliaCommand, KademliaEvent, KademliaHandle,
+            RoutingTableUpdateMode,
+        },
+        validator::RecordValidator,
     },
     types::protocol::ProtocolName,
     PeerId, DEFAULT_CHANNEL_SIZE,
@@ -84,6 +87,12 @@ pub struct Config {
     /// Provider republish interval.
     pub(super) provider_refresh_interval: Duration,
 
+    /// Number of disjoint paths used by `FIND_NODE` and `GET_VALUE` queries.
+    pub(super) disjoint_paths: usize,
+
+    /// Validator for inbound records, if any.
+    pub(super) validator: Option<Arc<dyn RecordValidator>>,
+
     /// TX channel for sending events to `KademliaHandle`.
     pub(super) event_tx: Sender<KademliaEvent>,
 
@@ -104,6 +113,8 @@ impl Config {
         record_ttl: Duration,
         provider_ttl: Duration,
         provider_refresh_interval: Duration,
+        disjoint_paths: usize,
+        validator: Option<Arc<dyn RecordValidator>>,
     ) -> (Self, KademliaHandle) {
         let (cmd_tx, cmd_rx) = channel(DEFAULT_CHANNEL_SIZE);
         let (event_tx, event_rx) = channel(DEFAULT_CHANNEL_SIZE);
@@ -122,6 +133,8 @@ impl Config {
                 record_ttl,
                 provider_ttl,
                 provider_refresh_interval,
+                disjoint_paths,
+                validator,
                 codec: ProtocolCodec::UnsignedVarint(None),
                 replication_factor,
                 known_peers,
@@ -144,6 +157,8 @@ impl Config {
             DEFAULT_TTL,
             DEFAULT_PROVIDER_TTL,
             DEFAULT_PROVIDER_REFRESH_INTERVAL,
+            1usize,
+            None,
         )
     }
 }
@@ -174,6 +189,12 @@ pub struct ConfigBuilder {
 
     /// Republish interval for the provider records.
     pub(super) provider_refresh_interval: Duration,
+
+    /// Number of disjoint lookup paths.
+    pub(super) disjoint_paths: usize,
+
+    /// Record validator.
+    pub(super) validator: Option<Arc<dyn RecordValidator>>,
 }
 
 impl Default for ConfigBuilder {
@@ -194,6 +215,8 @@ impl ConfigBuilder {
             record_ttl: DEFAULT_TTL,
             provider_ttl: DEFAULT_PROVIDER_TTL,
             provider_refresh_interval: DEFAULT_PROVIDER_REFRESH_INTERVAL,
+            disjoint_paths: 1usize,
+            validator: None,
         }
     }
 
@@ -262,6 +285,31 @@ impl ConfigBuilder {
         self
     }
 
+    /// Execute `FIND_NODE` and `GET_VALUE` queries over `disjoint_paths` independent paths.
+    ///
+    /// The closest known peers are split between the paths and a peer queried by one path is
+    /// never queried by another one, so a single adversarial peer cannot take over the whole
+    /// lookup. The results of all paths are merged once every path has finished.
+    ///
+    /// If unspecified, or set to `1`, queries use a single path.
+    pub fn with_disjoint_paths(mut self, disjoint_paths: usize) -> Self {
+        self.disjoint_paths = disjoint_paths;
+        self
+    }
+
+    /// Validate inbound records with `validator`.
+    ///
+    /// Records received in `PUT_VALUE` requests and `GET_VALUE` responses which the validator
+    /// rejects are discarded. Validation happens before
+    /// [`IncomingRecordValidationMode`] is applied, so in manual mode only valid records are
+    /// reported to the user.
+    ///
+    /// If unspecified, all records are considered valid.
+    pub fn with_record_validator(mut self, validator: Arc<dyn RecordValidator>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
     /// Build Kademlia [`Config`].
     pub fn build(self) -> (Config, KademliaHandle) {
         Config::new(
@@ -273,6 +321,8 @@ impl ConfigBuilder {
             self.record_ttl,
             self.provider_ttl,
             self.provider_refresh_interval,
+            self.disjoint_paths,
+            self.validator,
         )
     }
 }
@@ -96,6 +96,15 @@ pub(crate) enum KademliaCommand {
         query_id: QueryId,
     },
 
+    /// Send `FIND_NODE` message for an arbitrary key.
+    FindClosestPeers {
+        /// Target key.
+        key: RecordKey,
+
+        /// Query ID for the query.
+        query_id: QueryId,
+    },
+
     /// Store record to DHT.
     PutRecord {
         /// Record.
@@ -163,6 +172,12 @@ pub(crate) enum KademliaCommand {
         // Record.
         record: Record,
     },
+
+    /// Bootstrap the routing table.
+    Bootstrap {
+        /// Query ID for the bootstrap.
+        query_id: QueryId,
+    },
 }
 
 /// Kademlia events.
@@ -180,6 +195,18 @@ pub enum KademliaEvent {
         peers: Vec<(PeerId, Vec<Multiaddr>)>,
     },
 
+    /// Result for the issued `FIND_NODE` query for an arbitrary key.
+    FindNodeResult {
+        /// Query ID.
+        query_id: QueryId,
+
+        /// Target key of the query.
+        key: RecordKey,
+
+        /// Found nodes and their addresses, ordered by XOR distance to `key`.
+        peers: Vec<(PeerId, Vec<Multiaddr>)>,
+    },
+
     /// Routing table update.
     ///
     /// Kademlia has discovered one or more peers that should be added to the routing table.
@@ -249,6 +276,28 @@ pub enum KademliaEvent {
         /// Provider.
         provider: ContentProvider,
     },
+
+    /// One of the lookups of the bootstrap started with [`KademliaHandle::bootstrap()`] has
+    /// finished.
+    BootstrapProgress {
+        /// Query ID of the bootstrap.
+        query_id: QueryId,
+
+        /// Number of lookups finished so far.
+        queried: usize,
+
+        /// Number of lookups left.
+        ///
+        /// The bucket lookups are only known once the lookup of the local peer has finished so
+        /// the number may grow after the first lookup.
+        remaining: usize,
+    },
+
+    /// Bootstrap started with [`KademliaHandle::bootstrap()`] has finished.
+    BootstrapComplete {
+        /// Query ID of the bootstrap.
+        query_id: QueryId,
+    },
 }
 
 /// The type of the DHT records.
@@ -309,6 +358,22 @@ impl KademliaHandle {
         query_id
     }
 
+    /// Send `FIND_NODE` query for an arbitrary key to known peers.
+    ///
+    /// The query finishes once the set of closest peers no longer improves and the result is
+    /// reported in [`KademliaEvent::FindNodeResult`]. `key` can be any [`RecordKey`] or a
+    /// [`PeerId`], which finds the same peers as [`KademliaHandle::find_node()`].
+    ///
+    /// This is separate from [`KademliaHandle::find_node()`] because that method already takes
+    /// a [`PeerId`] and reports [`KademliaEvent::FindNodeSuccess`], which is kept unchanged.
+    pub async fn find_closest_peers(&mut self, key: impl Into<RecordKey>) -> QueryId {
+        let key = key.into();
+        let query_id = self.next_query_id();
+        let _ = self.cmd_tx.send(KademliaCommand::FindClosestPeers { key, query_id }).await;
+
+        query_id
+    }
+
     /// Store record to DHT.
     pub async fn put_record(&mut self, record: Record) -> QueryId {
         let query_id = self.next_query_id();
@@ -387,6 +452,23 @@ impl KademliaHandle {
         query_id
     }
 
+    /// Bootstrap the routing table.
+    ///
+    /// The bootstrap first looks up the local peer to find its closest peers and then looks up a
+    /// random peer in up to eight of the farthest buckets which are farther away than the closest
+    /// peer found to populate the rest of the routing table. [`KademliaEvent::BootstrapProgress`]
+    /// is emitted after each lookup and [`KademliaEvent::BootstrapComplete`] once all of them
+    /// have finished.
+    ///
+    /// If a bootstrap is already in progress, [`KademliaEvent::QueryFailed`] is emitted for the
+    /// new one.
+    pub async fn bootstrap(&mut self) -> QueryId {
+        let query_id = self.next_query_id();
+        let _ = self.cmd_tx.send(KademliaCommand::Bootstrap { query_id }).await;
+
+        query_id
+    }
+
     /// Store the record in the local store. Used in combination with
     /// [`IncomingRecordValidationMode::Manual`].
     pub async fn store_record(&mut self, record: Record) {
@@ -409,6 +491,19 @@ impl KademliaHandle {
             .map_err(|_| ())
     }
 
+    /// Try to initiate `FIND_NODE` query for an arbitrary key and if the channel is clogged,
+    /// return an error.
+    ///
+    /// See [`KademliaHandle::find_closest_peers()`] for the accepted keys.
+    pub fn try_find_closest_peers(&mut self, key: impl Into<RecordKey>) -> Result<QueryId, ()> {
+        let key = key.into();
+        let query_id = self.next_query_id();
+        self.cmd_tx
+            .try_send(KademliaCommand::FindClosestPeers { key, query_id })
+            .map(|_| query_id)
+            .map_err(|_| ())
+    }
+
     /// Try to initiate `PUT_VALUE` query and if the channel is clogged, return an error.
     pub fn try_put_record(&mut self, record: Record) -> Result<QueryId, ()> {
         let query_id = self.next_query_id();
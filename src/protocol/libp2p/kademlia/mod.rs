@@ -61,6 +61,7 @@ pub use handle::{
 };
 pub use query::QueryId;
 pub use record::{ContentProvider, Key as RecordKey, PeerRecord, Record};
+pub use validator::{RecordValidator, ValidationError};
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::ipfs::kademlia";
@@ -68,6 +69,12 @@ const LOG_TARGET: &str = "litep2p::ipfs::kademlia";
 /// Parallelism factor, `α`.
 const PARALLELISM_FACTOR: usize = 3;
 
+/// Maximum number of buckets populated by a bootstrap.
+///
+/// Random peers of a bucket are found by trial and error and each bucket closer to the local
+/// node is half as likely to be hit so the buckets closest to the local node are left alone.
+const MAX_BOOTSTRAP_BUCKETS: usize = 8;
+
 mod bucket;
 mod config;
 mod executor;
@@ -79,6 +86,7 @@ mod record;
 mod routing_table;
 mod store;
 mod types;
+mod validator;
 
 mod schema {
     pub(super) mod kademlia {
@@ -120,6 +128,21 @@ impl PeerContext {
     }
 }
 
+/// State of a bootstrap started with [`KademliaHandle::bootstrap()`].
+struct Bootstrap {
+    /// Query ID of the bootstrap.
+    query_id: QueryId,
+
+    /// Query ID of the lookup in progress.
+    lookup: QueryId,
+
+    /// Targets of the lookups that haven't been started yet.
+    pending: Vec<PeerId>,
+
+    /// Number of finished lookups.
+    queried: usize,
+}
+
 /// Main Kademlia object.
 pub(crate) struct Kademlia {
     /// Transport service.
@@ -164,11 +187,17 @@ pub(crate) struct Kademlia {
     /// Default record TTL.
     record_ttl: Duration,
 
+    /// Validator for inbound records.
+    validator: Option<Arc<dyn RecordValidator>>,
+
     /// Query engine.
     engine: QueryEngine,
 
     /// Query executor.
     executor: QueryExecutor,
+
+    /// Bootstrap in progress, if any.
+    bootstrap: Option<Bootstrap>,
 }
 
 impl Kademlia {
@@ -209,8 +238,32 @@ impl Kademlia {
             update_mode: config.update_mode,
             validation_mode: config.validation_mode,
             record_ttl: config.record_ttl,
+            validator: config.validator,
             replication_factor: config.replication_factor,
-            engine: QueryEngine::new(local_peer_id, config.replication_factor, PARALLELISM_FACTOR),
+            engine: QueryEngine::new(local_peer_id, config.replication_factor, PARALLELISM_FACTOR)
+                .with_disjoint_paths(config.disjoint_paths),
+            bootstrap: None,
+        }
+    }
+
+    /// Check if `record` is accepted by the record validator, if one is configured.
+    fn is_record_valid(&self, peer: PeerId, record: &Record) -> bool {
+        let Some(validator) = &self.validator else {
+            return true;
+        };
+
+        match validator.validate(record) {
+            Ok(()) => true,
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    record_key = ?record.key,
+                    ?error,
+                    "record rejected by validator",
+                );
+                false
+            }
         }
     }
 
@@ -465,6 +518,10 @@ impl Kademlia {
                     "handle `PUT_VALUE` message",
                 );
 
+                if !self.is_record_valid(peer, &record) {
+                    return Ok(());
+                }
+
                 if let IncomingRecordValidationMode::Automatic = self.validation_mode {
                     self.store.put(record.clone());
                 }
@@ -489,7 +546,18 @@ impl Kademlia {
 
                         // update routing table and inform user about the update
                         self.update_routing_table(peers).await;
-                        self.engine.register_response(query_id, peer, message.clone());
+
+                        // forged records are dropped but the peers are still used by the query
+                        let message = match record {
+                            Some(record) if !self.is_record_valid(peer, record) =>
+                                KademliaMessage::GetRecord {
+                                    key: key.clone(),
+                                    record: None,
+                                    peers: peers.clone(),
+                                },
+                            _ => message.clone(),
+                        };
+                        self.engine.register_response(query_id, peer, message);
                     }
                     (None, Some(key)) => {
                         tracing::trace!(
@@ -699,7 +767,7 @@ impl Kademlia {
             Err(err) => {
                 tracing::trace!(target: LOG_TARGET, ?query, ?peer, ?err, "Failed to open substream. Dialing peer");
 
-                match self.service.dial(&peer) {
+                match self.service.dial_automatic(&peer) {
                     Ok(()) => {
                         self.pending_dials.entry(peer).or_default().push(action);
                         Ok(())
@@ -734,6 +802,119 @@ impl Kademlia {
         }
     }
 
+    /// Start `FIND_NODE` lookup for `target` as part of the bootstrap.
+    fn start_bootstrap_lookup(&mut self, target: PeerId) -> QueryId {
+        let query_id = self.next_query_id();
+
+        self.engine.start_find_node(
+            query_id,
+            target,
+            self.routing_table.closest(Key::from(target), self.replication_factor).into(),
+        )
+    }
+
+    /// Start bootstrap by looking up the local peer.
+    async fn start_bootstrap(&mut self, query_id: QueryId) {
+        if self.bootstrap.is_some() {
+            tracing::debug!(target: LOG_TARGET, query = ?query_id, "bootstrap already in progress");
+
+            let _ = self.event_tx.send(KademliaEvent::QueryFailed { query_id }).await;
+            return;
+        }
+
+        tracing::debug!(target: LOG_TARGET, query = ?query_id, "start bootstrap");
+
+        let lookup = self.start_bootstrap_lookup(self.service.local_peer_id());
+        self.bootstrap = Some(Bootstrap {
+            query_id,
+            lookup,
+            pending: Vec::new(),
+            queried: 0usize,
+        });
+    }
+
+    /// Get random targets for the farthest buckets which are farther away from the local node than
+    /// its closest known peer.
+    fn bucket_lookup_targets(&mut self) -> Vec<PeerId> {
+        let Some(closest) = self
+            .routing_table
+            .closest(self.local_key.clone(), 1usize)
+            .first()
+            .and_then(|peer| peer.key.distance(&self.local_key).ilog2())
+        else {
+            return Vec::new();
+        };
+
+        ((closest + 1)..256u32)
+            .rev()
+            .take(MAX_BOOTSTRAP_BUCKETS)
+            .map(|bucket| loop {
+                let peer = PeerId::random();
+
+                if Key::from(peer).distance(&self.local_key).ilog2() == Some(bucket) {
+                    break peer;
+                }
+            })
+            .collect()
+    }
+
+    /// Handle finished query if it's a lookup of the bootstrap in progress.
+    ///
+    /// Returns `false` if the query is not part of the bootstrap.
+    async fn on_bootstrap_lookup_finished(&mut self, query: QueryId) -> bool {
+        if self.bootstrap.as_ref().map_or(true, |bootstrap| bootstrap.lookup != query) {
+            return false;
+        }
+
+        let mut bootstrap = self.bootstrap.take().expect("bootstrap to exist");
+
+        // buckets to populate can only be chosen once the self-lookup has found the closest peers
+        if bootstrap.queried == 0 {
+            bootstrap.pending = self.bucket_lookup_targets();
+        }
+        bootstrap.queried += 1;
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            query = ?bootstrap.query_id,
+            queried = ?bootstrap.queried,
+            remaining = ?bootstrap.pending.len(),
+            "bootstrap lookup finished",
+        );
+
+        let _ = self
+            .event_tx
+            .send(KademliaEvent::BootstrapProgress {
+                query_id: bootstrap.query_id,
+                queried: bootstrap.queried,
+                remaining: bootstrap.pending.len(),
+            })
+            .await;
+
+        match bootstrap.pending.pop() {
+            Some(target) => {
+                bootstrap.lookup = self.start_bootstrap_lookup(target);
+                self.bootstrap = Some(bootstrap);
+            }
+            None => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    query = ?bootstrap.query_id,
+                    "bootstrap complete",
+                );
+
+                let _ = self
+                    .event_tx
+                    .send(KademliaEvent::BootstrapComplete {
+                        query_id: bootstrap.query_id,
+                    })
+                    .await;
+            }
+        }
+
+        true
+    }
+
     /// Handle next query action.
     async fn on_query_action(&mut self, action: QueryAction) -> Result<(), (QueryId, PeerId)> {
         match action {
@@ -752,6 +933,10 @@ impl Kademlia {
                 peers,
                 query,
             } => {
+                if self.on_bootstrap_lookup_finished(query).await {
+                    return Ok(());
+                }
+
                 tracing::debug!(
                     target: LOG_TARGET,
                     ?query,
@@ -770,6 +955,25 @@ impl Kademlia {
                     .await;
                 Ok(())
             }
+            QueryAction::FindClosestPeersQuerySucceeded { query, key, peers } => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?query,
+                    ?key,
+                    num_peers = ?peers.len(),
+                    "`FIND_NODE` for key succeeded",
+                );
+
+                let _ = self
+                    .event_tx
+                    .send(KademliaEvent::FindNodeResult {
+                        query_id: query,
+                        key,
+                        peers: peers.into_iter().map(|info| (info.peer, info.addresses)).collect(),
+                    })
+                    .await;
+                Ok(())
+            }
             QueryAction::PutRecordToFoundNodes { record, peers } => {
                 tracing::trace!(
                     target: LOG_TARGET,
@@ -858,6 +1062,10 @@ impl Kademlia {
             QueryAction::QueryFailed { query } => {
                 tracing::debug!(target: LOG_TARGET, ?query, "query failed");
 
+                if self.on_bootstrap_lookup_finished(query).await {
+                    return Ok(());
+                }
+
                 let _ = self.event_tx.send(KademliaEvent::QueryFailed { query_id: query }).await;
                 Ok(())
             }
@@ -915,6 +1123,7 @@ impl Kademlia {
                     }
                     Some(TransportEvent::DialFailure { peer, address, .. }) =>
                         self.on_dial_failure(peer, address),
+                    Some(TransportEvent::ConnectionAddressChanged { .. }) => {}
                     None => return Err(Error::EssentialTaskClosed),
                 },
                 context = self.executor.next() => {
@@ -981,6 +1190,22 @@ impl Kademlia {
                                     .into()
                             );
                         }
+                        Some(KademliaCommand::FindClosestPeers { key, query_id }) => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?key,
+                                query = ?query_id,
+                                "starting `FIND_NODE` query for key",
+                            );
+
+                            self.engine.start_find_closest_peers(
+                                query_id,
+                                key.clone(),
+                                self.routing_table
+                                    .closest(Key::new(key), self.replication_factor)
+                                    .into()
+                            );
+                        }
                         Some(KademliaCommand::PutRecord { mut record, query_id }) => {
                             tracing::debug!(
                                 target: LOG_TARGET,
@@ -1164,6 +1389,9 @@ impl Kademlia {
 
                             self.store.put(record);
                         }
+                        Some(KademliaCommand::Bootstrap { query_id }) => {
+                            self.start_bootstrap(query_id).await;
+                        }
                         None => return Err(Error::EssentialTaskClosed),
                     }
                 },
@@ -1203,9 +1431,12 @@ mod tests {
     use super::*;
     use crate::{
         codec::ProtocolCodec,
-        crypto::ed25519::Keypair,
+        crypto::Keypair,
+        mock::substream::MockSubstream,
         transport::{
-            manager::{limits::ConnectionLimitsConfig, TransportManager},
+            manager::{
+                limits::ConnectionLimitsConfig, reputation::ReputationConfig, TransportManager,
+            },
             KEEP_ALIVE_TIMEOUT,
         },
         types::protocol::ProtocolName,
@@ -1220,12 +1451,19 @@ mod tests {
     }
 
     fn make_kademlia() -> (Kademlia, Context, TransportManager) {
+        make_kademlia_with_validator(None)
+    }
+
+    fn make_kademlia_with_validator(
+        validator: Option<Arc<dyn RecordValidator>>,
+    ) -> (Kademlia, Context, TransportManager) {
         let (manager, handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         let peer = PeerId::random();
@@ -1251,6 +1489,8 @@ mod tests {
             record_ttl: Duration::from_secs(36 * 60 * 60),
             provider_ttl: Duration::from_secs(48 * 60 * 60),
             provider_refresh_interval: Duration::from_secs(22 * 60 * 60),
+            disjoint_paths: 1usize,
+            validator,
             event_tx,
             cmd_rx,
             next_query_id,
@@ -1332,4 +1572,47 @@ mod tests {
         // Check the local storage should not get updated.
         assert!(kademlia.store.get(&key).is_none());
     }
+
+    /// Validator which only accepts records whose value carries a signature.
+    struct SignatureValidator;
+
+    impl RecordValidator for SignatureValidator {
+        fn validate(&self, record: &Record) -> Result<(), ValidationError> {
+            match record.value.starts_with(b"signature:") {
+                true => Ok(()),
+                false => Err(ValidationError("signature missing".to_string())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn put_value_rejected_by_validator() {
+        let (mut kademlia, mut context, _manager) =
+            make_kademlia_with_validator(Some(Arc::new(SignatureValidator)));
+        let peer = PeerId::random();
+
+        let forged = Record::new(RecordKey::from(vec![1, 2, 3]), b"value".to_vec());
+        let signed = Record::new(RecordKey::from(vec![4, 5, 6]), b"signature:value".to_vec());
+
+        for record in [forged.clone(), signed.clone()] {
+            let message = BytesMut::from(&KademliaMessage::put_value(record)[..]);
+            let substream = Substream::new_mock(
+                peer,
+                SubstreamId::from(0usize),
+                Box::new(MockSubstream::new()),
+            );
+
+            kademlia.on_message_received(peer, None, message, substream).await.unwrap();
+        }
+
+        // only the signed record is stored and reported
+        assert!(kademlia.store.get(&forged.key).is_none());
+        assert_eq!(kademlia.store.get(&signed.key).unwrap().value, signed.value);
+
+        match context.event_rx.try_recv() {
+            Ok(KademliaEvent::IncomingRecord { record }) => assert_eq!(record.key, signed.key),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert!(context.event_rx.try_recv().is_err());
+    }
 }
@@ -0,0 +1,303 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Disjoint-path lookups.
+//!
+//! The initial candidates are split between `d` independent lookup paths and each peer is only
+//! ever queried by one of them. A single malicious peer (or a group of colluding peers) can
+//! therefore only steer the paths that actually reached it, as described in S/Kademlia.
+
+use crate::{
+    protocol::libp2p::kademlia::{
+        query::{QueryAction, QueryId},
+        types::{KademliaPeer, Key},
+    },
+    PeerId,
+};
+
+use std::collections::{HashMap, VecDeque};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::ipfs::kademlia::query::disjoint";
+
+/// Single lookup path of a disjoint query.
+pub trait LookupPath {
+    /// Get next action for the path.
+    fn next_action(&mut self) -> Option<QueryAction>;
+
+    /// Get next action for `peer`.
+    fn next_peer_action(&mut self, peer: &PeerId) -> Option<QueryAction>;
+
+    /// Register response failure for `peer`.
+    fn register_response_failure(&mut self, peer: PeerId);
+
+    /// Exclude `peer` from the path because another path has already queried it.
+    fn exclude_peer(&mut self, peer: &PeerId);
+}
+
+/// Context for queries which are executed over disjoint paths.
+#[derive(Debug)]
+pub struct DisjointContext<C> {
+    /// Query ID.
+    query: QueryId,
+
+    /// Lookup paths.
+    paths: Vec<C>,
+
+    /// Result of each path, `None` if the path is still running.
+    results: Vec<Option<bool>>,
+
+    /// Index of the path that claimed the peer.
+    owners: HashMap<PeerId, usize>,
+}
+
+impl<C: LookupPath> DisjointContext<C> {
+    /// Create new [`DisjointContext`].
+    ///
+    /// `in_peers` are ordered by their distance to `target` and distributed between the paths in
+    /// a round-robin fashion so that each path starts with a fair share of the closest peers.
+    pub fn new<T: Clone>(
+        query: QueryId,
+        target: &Key<T>,
+        mut in_peers: VecDeque<KademliaPeer>,
+        num_paths: usize,
+        make_path: impl Fn(VecDeque<KademliaPeer>) -> C,
+    ) -> Self {
+        let num_paths = num_paths.max(1);
+        let mut candidates = vec![VecDeque::new(); num_paths];
+
+        in_peers.make_contiguous().sort_by_key(|peer| target.distance(&peer.key));
+
+        for (index, peer) in in_peers.into_iter().enumerate() {
+            candidates[index % num_paths].push_back(peer);
+        }
+
+        Self {
+            query,
+            paths: candidates.into_iter().map(make_path).collect(),
+            results: vec![None; num_paths],
+            owners: HashMap::new(),
+        }
+    }
+
+    /// Get the path which queried `peer`.
+    pub fn path_mut(&mut self, peer: &PeerId) -> Option<&mut C> {
+        let index = *self.owners.get(peer)?;
+
+        self.paths.get_mut(index)
+    }
+
+    /// Consume the context and return the lookup paths.
+    pub fn into_paths(self) -> Vec<C> {
+        self.paths
+    }
+
+    /// Register response failure for `peer`.
+    pub fn register_response_failure(&mut self, peer: PeerId) {
+        match self.path_mut(&peer) {
+            Some(path) => path.register_response_failure(peer),
+            None => tracing::debug!(
+                target: LOG_TARGET,
+                query = ?self.query,
+                ?peer,
+                "response failure from peer that wasn't queried",
+            ),
+        }
+    }
+
+    /// Get next action for `peer`.
+    pub fn next_peer_action(&mut self, peer: &PeerId) -> Option<QueryAction> {
+        self.path_mut(peer)?.next_peer_action(peer)
+    }
+
+    /// Get next action for the disjoint query.
+    ///
+    /// The query succeeds once all paths have finished and at least one of them succeeded.
+    pub fn next_action(&mut self) -> Option<QueryAction> {
+        for index in 0..self.paths.len() {
+            if self.results[index].is_some() {
+                continue;
+            }
+
+            match self.paths[index].next_action() {
+                Some(QueryAction::SendMessage {
+                    query,
+                    peer,
+                    message,
+                }) => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        ?query,
+                        ?peer,
+                        path = index,
+                        "peer claimed by path",
+                    );
+
+                    self.owners.insert(peer, index);
+                    self.paths
+                        .iter_mut()
+                        .enumerate()
+                        .filter(|(other, _)| *other != index)
+                        .for_each(|(_, path)| path.exclude_peer(&peer));
+
+                    return Some(QueryAction::SendMessage {
+                        query,
+                        peer,
+                        message,
+                    });
+                }
+                Some(QueryAction::QuerySucceeded { .. }) => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        query = ?self.query,
+                        path = index,
+                        "path succeeded",
+                    );
+                    self.results[index] = Some(true);
+                }
+                Some(QueryAction::QueryFailed { .. }) => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        query = ?self.query,
+                        path = index,
+                        "path failed",
+                    );
+                    self.results[index] = Some(false);
+                }
+                Some(action) => return Some(action),
+                None => {}
+            }
+        }
+
+        if self.results.iter().any(Option::is_none) {
+            return None;
+        }
+
+        if self.results.iter().any(|result| *result == Some(true)) {
+            Some(QueryAction::QuerySucceeded { query: self.query })
+        } else {
+            Some(QueryAction::QueryFailed { query: self.query })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::libp2p::kademlia::{
+        query::find_node::{FindNodeConfig, FindNodeContext},
+        types::ConnectionType,
+    };
+    use std::collections::HashSet;
+
+    fn peer_to_kad(peer: PeerId) -> KademliaPeer {
+        KademliaPeer {
+            peer,
+            key: Key::from(peer),
+            addresses: vec![],
+            connection: ConnectionType::Connected,
+        }
+    }
+
+    #[test]
+    fn paths_never_share_peers() {
+        let target = Key::from(PeerId::random());
+        let config = FindNodeConfig {
+            local_peer_id: PeerId::random(),
+            replication_factor: 5,
+            parallelism_factor: 2,
+            query: QueryId(0),
+            target: target.clone(),
+        };
+        let in_peers = (0..9).map(|_| peer_to_kad(PeerId::random())).collect();
+        let mut context = DisjointContext::new(QueryId(0), &target, in_peers, 3, |peers| {
+            FindNodeContext::new(config.clone(), peers)
+        });
+
+        let mut queried = HashSet::new();
+        let mut responded = Vec::new();
+        let mut paths_used = HashSet::new();
+
+        loop {
+            match context.next_action() {
+                Some(QueryAction::SendMessage { peer, .. }) => {
+                    // No peer may ever be queried twice, not even by two different paths.
+                    assert!(queried.insert(peer));
+                    paths_used.insert(*context.owners.get(&peer).unwrap());
+
+                    // Every peer responds with two fresh peers and every peer that has already
+                    // responded, which would make the paths converge without the exclusion.
+                    let mut peers: Vec<_> = responded.iter().copied().map(peer_to_kad).collect();
+                    if queried.len() < 60 {
+                        peers.extend((0..2).map(|_| peer_to_kad(PeerId::random())));
+                    }
+
+                    context.path_mut(&peer).unwrap().register_response(peer, peers);
+                    responded.push(peer);
+                }
+                Some(QueryAction::QuerySucceeded { query }) => {
+                    assert_eq!(query, QueryId(0));
+                    break;
+                }
+                event => panic!("unexpected event: {event:?}"),
+            }
+        }
+
+        assert_eq!(paths_used.len(), 3);
+
+        let paths = context.into_paths();
+        assert_eq!(paths.len(), 3);
+
+        for (index, path) in paths.iter().enumerate() {
+            for other in paths.iter().skip(index + 1) {
+                assert!(path.queried.is_disjoint(&other.queried));
+            }
+        }
+    }
+
+    #[test]
+    fn fails_when_all_paths_fail() {
+        let target = Key::from(PeerId::random());
+        let config = FindNodeConfig {
+            local_peer_id: PeerId::random(),
+            replication_factor: 5,
+            parallelism_factor: 2,
+            query: QueryId(1),
+            target: target.clone(),
+        };
+        let in_peers = (0..2).map(|_| peer_to_kad(PeerId::random())).collect();
+        let mut context = DisjointContext::new(QueryId(1), &target, in_peers, 3, |peers| {
+            FindNodeContext::new(config.clone(), peers)
+        });
+
+        loop {
+            match context.next_action() {
+                Some(QueryAction::SendMessage { peer, .. }) => {
+                    context.register_response_failure(peer);
+                }
+                Some(QueryAction::QueryFailed { query }) => {
+                    assert_eq!(query, QueryId(1));
+                    break;
+                }
+                event => panic!("unexpected event: {event:?}"),
+            }
+        }
+    }
+}
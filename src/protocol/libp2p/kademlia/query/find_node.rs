@@ -23,7 +23,7 @@ use bytes::Bytes;
 use crate::{
     protocol::libp2p::kademlia::{
         message::KademliaMessage,
-        query::{QueryAction, QueryId},
+        query::{disjoint::LookupPath, QueryAction, QueryId},
         types::{Distance, KademliaPeer, Key},
     },
     PeerId,
@@ -74,6 +74,9 @@ pub struct FindNodeContext<T: Clone + Into<Vec<u8>>> {
     /// and who have either returned their closest peers or failed to answer.
     pub queried: HashSet<PeerId>,
 
+    /// Peers queried by other paths of a disjoint lookup.
+    excluded: HashSet<PeerId>,
+
     /// Candidates.
     pub candidates: BTreeMap<Distance, KademliaPeer>,
 
@@ -112,6 +115,7 @@ impl<T: Clone + Into<Vec<u8>>> FindNodeContext<T> {
             candidates,
             pending: HashMap::new(),
             queried: HashSet::new(),
+            excluded: HashSet::new(),
             responses: BTreeMap::new(),
 
             peer_timeout: DEFAULT_PEER_TIMEOUT,
@@ -180,6 +184,11 @@ impl<T: Clone + Into<Vec<u8>>> FindNodeContext<T> {
                 return None;
             }
 
+            // Peer belongs to another path.
+            if self.excluded.contains(&peer.peer) {
+                return None;
+            }
+
             // Local node.
             if self.config.local_peer_id == peer.peer {
                 return None;
@@ -298,6 +307,25 @@ impl<T: Clone + Into<Vec<u8>>> FindNodeContext<T> {
     }
 }
 
+impl<T: Clone + Into<Vec<u8>>> LookupPath for FindNodeContext<T> {
+    fn next_action(&mut self) -> Option<QueryAction> {
+        FindNodeContext::next_action(self)
+    }
+
+    fn next_peer_action(&mut self, peer: &PeerId) -> Option<QueryAction> {
+        FindNodeContext::next_peer_action(self, peer)
+    }
+
+    fn register_response_failure(&mut self, peer: PeerId) {
+        FindNodeContext::register_response_failure(self, peer)
+    }
+
+    fn exclude_peer(&mut self, peer: &PeerId) {
+        self.candidates.retain(|_, candidate| candidate.peer != *peer);
+        self.excluded.insert(*peer);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -23,7 +23,7 @@ use bytes::Bytes;
 use crate::{
     protocol::libp2p::kademlia::{
         message::KademliaMessage,
-        query::{QueryAction, QueryId},
+        query::{disjoint::LookupPath, QueryAction, QueryId},
         record::{Key as RecordKey, PeerRecord, Record},
         types::{Distance, KademliaPeer, Key},
         Quorum,
@@ -37,7 +37,7 @@ use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 const LOG_TARGET: &str = "litep2p::ipfs::kademlia::query::get_record";
 
 /// The configuration needed to instantiate a new [`GetRecordContext`].
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GetRecordConfig {
     /// Local peer ID.
     pub local_peer_id: PeerId,
@@ -97,6 +97,9 @@ pub struct GetRecordContext {
     /// and who have either returned their closest peers or failed to answer.
     pub queried: HashSet<PeerId>,
 
+    /// Peers queried by other paths of a disjoint lookup.
+    excluded: HashSet<PeerId>,
+
     /// Candidates.
     pub candidates: BTreeMap<Distance, KademliaPeer>,
 
@@ -123,6 +126,7 @@ impl GetRecordContext {
             candidates,
             pending: HashMap::new(),
             queried: HashSet::new(),
+            excluded: HashSet::new(),
             found_records: Vec::new(),
         }
     }
@@ -195,6 +199,11 @@ impl GetRecordContext {
                 return None;
             }
 
+            // Peer belongs to another path.
+            if self.excluded.contains(&peer.peer) {
+                return None;
+            }
+
             // Local node.
             if self.config.local_peer_id == peer.peer {
                 return None;
@@ -290,6 +299,25 @@ impl GetRecordContext {
     }
 }
 
+impl LookupPath for GetRecordContext {
+    fn next_action(&mut self) -> Option<QueryAction> {
+        GetRecordContext::next_action(self)
+    }
+
+    fn next_peer_action(&mut self, peer: &PeerId) -> Option<QueryAction> {
+        GetRecordContext::next_peer_action(self, peer)
+    }
+
+    fn register_response_failure(&mut self, peer: PeerId) {
+        GetRecordContext::register_response_failure(self, peer)
+    }
+
+    fn exclude_peer(&mut self, peer: &PeerId) {
+        self.candidates.retain(|_, candidate| candidate.peer != *peer);
+        self.excluded.insert(*peer);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -22,6 +22,7 @@ use crate::{
     protocol::libp2p::kademlia::{
         message::KademliaMessage,
         query::{
+            disjoint::DisjointContext,
             find_node::{FindNodeConfig, FindNodeContext},
             get_providers::{GetProvidersConfig, GetProvidersContext},
             get_record::{GetRecordConfig, GetRecordContext},
@@ -35,10 +36,11 @@ use crate::{
 
 use bytes::Bytes;
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 use self::find_many_nodes::FindManyNodesContext;
 
+mod disjoint;
 mod find_many_nodes;
 mod find_node;
 mod get_providers;
@@ -60,6 +62,18 @@ enum QueryType {
         context: FindNodeContext<PeerId>,
     },
 
+    /// `FIND_NODE` query executed over disjoint paths.
+    DisjointFindNode {
+        /// Context for the disjoint `FIND_NODE` query.
+        context: DisjointContext<FindNodeContext<PeerId>>,
+    },
+
+    /// `FIND_NODE` query for an arbitrary key.
+    FindClosestPeers {
+        /// Context for the `FIND_NODE` query.
+        context: FindNodeContext<RecordKey>,
+    },
+
     /// `PUT_VALUE` query.
     PutRecord {
         /// Record that needs to be stored.
@@ -84,6 +98,12 @@ enum QueryType {
         context: GetRecordContext,
     },
 
+    /// `GET_VALUE` query executed over disjoint paths.
+    DisjointGetRecord {
+        /// Context for the disjoint `GET_VALUE` query.
+        context: DisjointContext<GetRecordContext>,
+    },
+
     /// `ADD_PROVIDER` query.
     AddProvider {
         /// Provided key.
@@ -130,6 +150,18 @@ pub enum QueryAction {
         peers: Vec<KademliaPeer>,
     },
 
+    /// `FIND_NODE` query for an arbitrary key succeeded.
+    FindClosestPeersQuerySucceeded {
+        /// ID of the query that succeeded.
+        query: QueryId,
+
+        /// Target key.
+        key: RecordKey,
+
+        /// Peers that were found, ordered by their distance to `key`.
+        peers: Vec<KademliaPeer>,
+    },
+
     /// Store the record to nodes closest to target key.
     // TODO: horrible name
     PutRecordToFoundNodes {
@@ -197,6 +229,9 @@ pub struct QueryEngine {
     /// Parallelism factor.
     parallelism_factor: usize,
 
+    /// Number of disjoint paths used by `FIND_NODE` and `GET_VALUE` queries.
+    disjoint_paths: usize,
+
     /// Active queries.
     queries: HashMap<QueryId, QueryType>,
 }
@@ -212,10 +247,20 @@ impl QueryEngine {
             local_peer_id,
             replication_factor,
             parallelism_factor,
+            disjoint_paths: 1usize,
             queries: HashMap::new(),
         }
     }
 
+    /// Execute `FIND_NODE` and `GET_VALUE` queries over `disjoint_paths` paths that never
+    /// query the same peer.
+    ///
+    /// Values `0` and `1` disable disjoint lookups.
+    pub fn with_disjoint_paths(mut self, disjoint_paths: usize) -> Self {
+        self.disjoint_paths = disjoint_paths.max(1);
+        self
+    }
+
     /// Start `FIND_NODE` query.
     pub fn start_find_node(
         &mut self,
@@ -240,9 +285,54 @@ impl QueryEngine {
             target,
         };
 
+        let query = if self.disjoint_paths > 1 {
+            QueryType::DisjointFindNode {
+                context: DisjointContext::new(
+                    query_id,
+                    &config.target,
+                    candidates,
+                    self.disjoint_paths,
+                    |candidates| FindNodeContext::new(config.clone(), candidates),
+                ),
+            }
+        } else {
+            QueryType::FindNode {
+                context: FindNodeContext::new(config, candidates),
+            }
+        };
+
+        self.queries.insert(query_id, query);
+
+        query_id
+    }
+
+    /// Start `FIND_NODE` query for an arbitrary key.
+    pub fn start_find_closest_peers(
+        &mut self,
+        query_id: QueryId,
+        key: RecordKey,
+        candidates: VecDeque<KademliaPeer>,
+    ) -> QueryId {
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?query_id,
+            ?key,
+            num_peers = ?candidates.len(),
+            "start `FIND_NODE` query for key"
+        );
+
+        let target = Key::new(key);
+        let config = FindNodeConfig {
+            local_peer_id: self.local_peer_id,
+            replication_factor: self.replication_factor,
+            parallelism_factor: self.parallelism_factor,
+            query: query_id,
+            target,
+        };
+
         self.queries.insert(
             query_id,
-            QueryType::FindNode {
+            QueryType::FindClosestPeers {
                 context: FindNodeContext::new(config, candidates),
             },
         );
@@ -339,12 +429,23 @@ impl QueryEngine {
             target,
         };
 
-        self.queries.insert(
-            query_id,
+        let query = if self.disjoint_paths > 1 {
+            QueryType::DisjointGetRecord {
+                context: DisjointContext::new(
+                    query_id,
+                    &config.target,
+                    candidates,
+                    self.disjoint_paths,
+                    |candidates| GetRecordContext::new(config.clone(), candidates),
+                ),
+            }
+        } else {
             QueryType::GetRecord {
                 context: GetRecordContext::new(config, candidates),
-            },
-        );
+            }
+        };
+
+        self.queries.insert(query_id, query);
 
         query_id
     }
@@ -431,6 +532,12 @@ impl QueryEngine {
             Some(QueryType::FindNode { context }) => {
                 context.register_response_failure(peer);
             }
+            Some(QueryType::DisjointFindNode { context }) => {
+                context.register_response_failure(peer);
+            }
+            Some(QueryType::FindClosestPeers { context }) => {
+                context.register_response_failure(peer);
+            }
             Some(QueryType::PutRecord { context, .. }) => {
                 context.register_response_failure(peer);
             }
@@ -440,6 +547,9 @@ impl QueryEngine {
             Some(QueryType::GetRecord { context }) => {
                 context.register_response_failure(peer);
             }
+            Some(QueryType::DisjointGetRecord { context }) => {
+                context.register_response_failure(peer);
+            }
             Some(QueryType::AddProvider { context, .. }) => {
                 context.register_response_failure(peer);
             }
@@ -463,6 +573,24 @@ impl QueryEngine {
                 }
                 _ => unreachable!(),
             },
+            Some(QueryType::DisjointFindNode { context }) => match message {
+                KademliaMessage::FindNode { peers, .. } => match context.path_mut(&peer) {
+                    Some(path) => path.register_response(peer, peers),
+                    None => tracing::debug!(
+                        target: LOG_TARGET,
+                        ?query,
+                        ?peer,
+                        "response from peer that wasn't queried",
+                    ),
+                },
+                _ => unreachable!(),
+            },
+            Some(QueryType::FindClosestPeers { context }) => match message {
+                KademliaMessage::FindNode { peers, .. } => {
+                    context.register_response(peer, peers);
+                }
+                _ => unreachable!(),
+            },
             Some(QueryType::PutRecord { context, .. }) => match message {
                 KademliaMessage::FindNode { peers, .. } => {
                     context.register_response(peer, peers);
@@ -481,6 +609,18 @@ impl QueryEngine {
                 }
                 _ => unreachable!(),
             },
+            Some(QueryType::DisjointGetRecord { context }) => match message {
+                KademliaMessage::GetRecord { record, peers, .. } => match context.path_mut(&peer) {
+                    Some(path) => path.register_response(peer, record, peers),
+                    None => tracing::debug!(
+                        target: LOG_TARGET,
+                        ?query,
+                        ?peer,
+                        "response from peer that wasn't queried",
+                    ),
+                },
+                _ => unreachable!(),
+            },
             Some(QueryType::AddProvider { context, .. }) => match message {
                 KademliaMessage::FindNode { peers, .. } => {
                     context.register_response(peer, peers);
@@ -510,9 +650,12 @@ impl QueryEngine {
                 None
             }
             Some(QueryType::FindNode { context }) => context.next_peer_action(peer),
+            Some(QueryType::DisjointFindNode { context }) => context.next_peer_action(peer),
+            Some(QueryType::FindClosestPeers { context }) => context.next_peer_action(peer),
             Some(QueryType::PutRecord { context, .. }) => context.next_peer_action(peer),
             Some(QueryType::PutRecordToPeers { context, .. }) => context.next_peer_action(peer),
             Some(QueryType::GetRecord { context }) => context.next_peer_action(peer),
+            Some(QueryType::DisjointGetRecord { context }) => context.next_peer_action(peer),
             Some(QueryType::AddProvider { context, .. }) => context.next_peer_action(peer),
             Some(QueryType::GetProviders { context }) => context.next_peer_action(peer),
         }
@@ -527,6 +670,27 @@ impl QueryEngine {
                 target: context.config.target.into_preimage(),
                 peers: context.responses.into_values().collect::<Vec<_>>(),
             },
+            QueryType::DisjointFindNode { context } => {
+                let paths = context.into_paths();
+                let target = paths[0].config.target.clone().into_preimage();
+                let replication_factor = paths[0].config.replication_factor;
+
+                // The paths are disjoint so the same peer is never reported twice.
+                let peers =
+                    paths.into_iter().flat_map(|path| path.responses).collect::<BTreeMap<_, _>>();
+
+                QueryAction::FindNodeQuerySucceeded {
+                    query,
+                    target,
+                    peers: peers.into_values().take(replication_factor).collect::<Vec<_>>(),
+                }
+            }
+            QueryType::FindClosestPeers { context } =>
+                QueryAction::FindClosestPeersQuerySucceeded {
+                    query,
+                    key: context.config.target.into_preimage(),
+                    peers: context.responses.into_values().collect::<Vec<_>>(),
+                },
             QueryType::PutRecord { record, context } => QueryAction::PutRecordToFoundNodes {
                 record,
                 peers: context.responses.into_values().collect::<Vec<_>>(),
@@ -539,6 +703,14 @@ impl QueryEngine {
                 query_id: context.config.query,
                 records: context.found_records(),
             },
+            QueryType::DisjointGetRecord { context } => QueryAction::GetRecordQueryDone {
+                query_id: query,
+                records: context
+                    .into_paths()
+                    .into_iter()
+                    .flat_map(|path| path.found_records())
+                    .collect::<Vec<_>>(),
+            },
             QueryType::AddProvider {
                 provided_key,
                 provider,
@@ -569,9 +741,12 @@ impl QueryEngine {
         for (_, state) in self.queries.iter_mut() {
             let action = match state {
                 QueryType::FindNode { context } => context.next_action(),
+                QueryType::DisjointFindNode { context } => context.next_action(),
+                QueryType::FindClosestPeers { context } => context.next_action(),
                 QueryType::PutRecord { context, .. } => context.next_action(),
                 QueryType::PutRecordToPeers { context, .. } => context.next_action(),
                 QueryType::GetRecord { context } => context.next_action(),
+                QueryType::DisjointGetRecord { context } => context.next_action(),
                 QueryType::AddProvider { context, .. } => context.next_action(),
                 QueryType::GetProviders { context } => context.next_action(),
             };
@@ -770,6 +945,80 @@ mod tests {
         assert!(engine.next_action().is_none());
     }
 
+    #[test]
+    fn find_closest_peers_returns_k_closest() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let mut engine = QueryEngine::new(PeerId::random(), 5usize, 3usize);
+        let key = RecordKey::new(&vec![1, 3, 3, 7]);
+        let target = Key::new(key.clone());
+
+        // simulated network where peers are ranked by their distance to `key` and a peer at
+        // rank `r` only knows the peers at ranks `r / 2..r / 2 + 5`
+        let network = {
+            let mut distances = std::collections::BTreeMap::new();
+
+            for i in 0..100u8 {
+                let peer = make_peer_id(i, i.wrapping_mul(7));
+                distances.insert(target.distance(&Key::from(peer)), peer);
+            }
+
+            distances.into_values().collect::<Vec<_>>()
+        };
+        let known_peers = |peer: &PeerId| -> Vec<KademliaPeer> {
+            let rank = network.iter().position(|p| p == peer).unwrap();
+
+            network[rank / 2..rank / 2 + 5]
+                .iter()
+                .map(|peer| KademliaPeer::new(*peer, vec![], ConnectionType::NotConnected))
+                .collect()
+        };
+
+        // start the query with only the three furthest peers known
+        engine.start_find_closest_peers(
+            QueryId(1337),
+            key.clone(),
+            network[97..]
+                .iter()
+                .map(|peer| KademliaPeer::new(*peer, vec![], ConnectionType::NotConnected))
+                .collect(),
+        );
+
+        for _ in 0..100 {
+            match engine.next_action() {
+                Some(QueryAction::SendMessage { query, peer, .. }) => {
+                    engine.register_response(
+                        query,
+                        peer,
+                        KademliaMessage::FindNode {
+                            target: key.to_vec(),
+                            peers: known_peers(&peer),
+                        },
+                    );
+                }
+                Some(QueryAction::FindClosestPeersQuerySucceeded {
+                    query,
+                    key: found_key,
+                    peers,
+                }) => {
+                    assert_eq!(query, QueryId(1337));
+                    assert_eq!(found_key, key);
+                    assert_eq!(
+                        peers.into_iter().map(|peer| peer.peer).collect::<Vec<_>>(),
+                        network[..5].to_vec(),
+                    );
+                    assert!(engine.next_action().is_none());
+                    return;
+                }
+                event => panic!("invalid event received: {event:?}"),
+            }
+        }
+
+        panic!("query didn't terminate");
+    }
+
     #[test]
     fn put_record_succeeds() {
         let _ = tracing_subscriber::fmt()
@@ -77,6 +77,12 @@ impl From<Multihash> for Key {
     }
 }
 
+impl From<PeerId> for Key {
+    fn from(peer: PeerId) -> Key {
+        Key::from(peer.to_bytes())
+    }
+}
+
 /// A record stored in the DHT.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Record {
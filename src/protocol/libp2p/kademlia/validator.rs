@@ -0,0 +1,48 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Application-specific validation of DHT records.
+
+use crate::protocol::libp2p::kademlia::record::Record;
+
+use std::fmt;
+
+/// Error returned by a [`RecordValidator`] for a record it rejects.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid record: {0}")]
+pub struct ValidationError(pub String);
+
+/// Validator for records stored in and retrieved from the DHT.
+///
+/// The validator is called for each record received in a `PUT_VALUE` request before it's stored
+/// or reported to the user, and for each record received in a `GET_VALUE` response before it's
+/// returned as a query result. Rejected records are discarded.
+///
+/// The validator is called from the Kademlia event loop so it must not block.
+pub trait RecordValidator: Send + Sync {
+    /// Validate `record`.
+    fn validate(&self, record: &Record) -> Result<(), ValidationError>;
+}
+
+impl fmt::Debug for dyn RecordValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordValidator").finish_non_exhaustive()
+    }
+}
@@ -21,6 +21,8 @@
 //! Supported [`libp2p`](https://libp2p.io/) protocols.
 
 pub mod bitswap;
+pub mod dcutr;
 pub mod identify;
 pub mod kademlia;
 pub mod ping;
+pub mod relay;
@@ -27,6 +27,8 @@ use futures::Stream;
 use tokio::sync::mpsc::{channel, Sender};
 use tokio_stream::wrappers::ReceiverStream;
 
+use std::time::Duration;
+
 /// IPFS Ping protocol name as a string.
 pub const PROTOCOL_NAME: &str = "/ipfs/ping/1.0.0";
 
@@ -36,6 +38,9 @@ const PING_PAYLOAD_SIZE: usize = 32;
 /// Maximum PING failures.
 const MAX_FAILURES: usize = 3;
 
+/// Interval between pings sent to a peer.
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+
 /// Ping configuration.
 pub struct Config {
     /// Protocol name.
@@ -47,6 +52,9 @@ pub struct Config {
     /// Maximum failures before the peer is considered unreachable.
     pub(crate) max_failures: usize,
 
+    /// Interval between pings sent to a peer.
+    pub(crate) interval: Duration,
+
     /// TX channel for sending events to the user protocol.
     pub(crate) tx_event: Sender<PingEvent>,
 }
@@ -62,6 +70,7 @@ impl Config {
             Self {
                 tx_event,
                 max_failures: MAX_FAILURES,
+                interval: PING_INTERVAL,
                 protocol: ProtocolName::from(PROTOCOL_NAME),
                 codec: ProtocolCodec::Identity(PING_PAYLOAD_SIZE),
             },
@@ -80,6 +89,9 @@ pub struct ConfigBuilder {
 
     /// Maximum failures before the peer is considered unreachable.
     max_failures: usize,
+
+    /// Interval between pings sent to a peer.
+    interval: Duration,
 }
 
 impl Default for ConfigBuilder {
@@ -93,14 +105,26 @@ impl ConfigBuilder {
     pub fn new() -> Self {
         Self {
             max_failures: MAX_FAILURES,
+            interval: PING_INTERVAL,
             protocol: ProtocolName::from(PROTOCOL_NAME),
             codec: ProtocolCodec::Identity(PING_PAYLOAD_SIZE),
         }
     }
 
-    /// Set maximum failures the protocol.
+    /// Set the number of consecutive failed pings after which the peer is considered unresponsive
+    /// and disconnected.
+    ///
+    /// A value of zero is treated as one. Defaults to 3.
     pub fn with_max_failure(mut self, max_failures: usize) -> Self {
-        self.max_failures = max_failures;
+        self.max_failures = max_failures.max(1);
+        self
+    }
+
+    /// Set the interval between pings sent to a peer.
+    ///
+    /// Defaults to 15 seconds.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
         self
     }
 
@@ -112,6 +136,7 @@ impl ConfigBuilder {
             Config {
                 tx_event,
                 max_failures: self.max_failures,
+                interval: self.interval,
                 protocol: self.protocol,
                 codec: self.codec,
             },
@@ -21,7 +21,8 @@
 //! [`/ipfs/ping/1.0.0`](https://github.com/libp2p/specs/blob/master/ping/ping.md) implementation.
 
 use crate::{
-    error::{Error, SubstreamError},
+    error::{Error, NegotiationError, SubstreamError},
+    multistream_select::NegotiationError as MultistreamNegotiationError,
     protocol::{Direction, TransportEvent, TransportService},
     substream::Substream,
     types::SubstreamId,
@@ -40,8 +41,6 @@ pub use config::{Config, ConfigBuilder};
 
 mod config;
 
-// TODO: handle max failures
-
 /// Log target for the file.
 const LOG_TARGET: &str = "litep2p::ipfs::ping";
 
@@ -56,12 +55,23 @@ pub enum PingEvent {
         /// Measured ping time with the peer.
         ping: Duration,
     },
+
+    /// Peer failed to respond to the configured number of consecutive pings.
+    ///
+    /// The connection to the peer is closed.
+    Unresponsive {
+        /// Peer ID.
+        peer: PeerId,
+    },
 }
 
 /// Ping protocol.
 pub(crate) struct Ping {
     /// Maximum failures before the peer is considered unreachable.
-    _max_failures: usize,
+    max_failures: usize,
+
+    /// Interval between pings sent to a peer.
+    interval: Duration,
 
     // Connection service.
     service: TransportService,
@@ -72,14 +82,20 @@ pub(crate) struct Ping {
     /// Connected peers.
     peers: HashSet<PeerId>,
 
+    /// Number of consecutive failed pings of each peer.
+    failures: HashMap<PeerId, usize>,
+
     /// Pending outbound substreams.
     pending_opens: HashMap<SubstreamId, PeerId>,
 
     /// Pending outbound substreams.
-    pending_outbound: FuturesUnordered<BoxFuture<'static, crate::Result<(PeerId, Duration)>>>,
+    pending_outbound: FuturesUnordered<BoxFuture<'static, (PeerId, crate::Result<Duration>)>>,
 
     /// Pending inbound substreams.
     pending_inbound: FuturesUnordered<BoxFuture<'static, crate::Result<()>>>,
+
+    /// Timers for the next ping of each peer.
+    timers: FuturesUnordered<BoxFuture<'static, PeerId>>,
 }
 
 impl Ping {
@@ -89,10 +105,13 @@ impl Ping {
             service,
             tx: config.tx_event,
             peers: HashSet::new(),
+            failures: HashMap::new(),
             pending_opens: HashMap::new(),
             pending_outbound: FuturesUnordered::new(),
             pending_inbound: FuturesUnordered::new(),
-            _max_failures: config.max_failures,
+            timers: FuturesUnordered::new(),
+            max_failures: config.max_failures,
+            interval: config.interval,
         }
     }
 
@@ -100,11 +119,8 @@ impl Ping {
     fn on_connection_established(&mut self, peer: PeerId) -> crate::Result<()> {
         tracing::trace!(target: LOG_TARGET, ?peer, "connection established");
 
-        let substream_id = self.service.open_substream(peer)?;
-        self.pending_opens.insert(substream_id, peer);
         self.peers.insert(peer);
-
-        Ok(())
+        self.ping(peer)
     }
 
     /// Connection closed to remote peer.
@@ -112,6 +128,94 @@ impl Ping {
         tracing::trace!(target: LOG_TARGET, ?peer, "connection closed");
 
         self.peers.remove(&peer);
+        self.failures.remove(&peer);
+    }
+
+    /// Open substream to `peer` for sending a ping.
+    fn ping(&mut self, peer: PeerId) -> crate::Result<()> {
+        let substream_id = self.service.open_substream(peer)?;
+        self.pending_opens.insert(substream_id, peer);
+
+        Ok(())
+    }
+
+    /// Schedule the next ping to `peer`.
+    fn schedule_ping(&mut self, peer: PeerId) {
+        let interval = self.interval;
+
+        self.timers.push(Box::pin(async move {
+            tokio::time::sleep(interval).await;
+            peer
+        }));
+    }
+
+    /// Timer for the next ping of `peer` has expired.
+    fn on_ping_timer(&mut self, peer: PeerId) {
+        if !self.peers.contains(&peer) {
+            return;
+        }
+
+        if let Err(error) = self.ping(peer) {
+            tracing::debug!(target: LOG_TARGET, ?peer, ?error, "failed to open ping substream");
+            self.schedule_ping(peer);
+        }
+    }
+
+    /// Ping to `peer` succeeded.
+    async fn on_ping_success(&mut self, peer: PeerId, elapsed: Duration) {
+        self.failures.remove(&peer);
+        self.schedule_ping(peer);
+
+        let _ = self
+            .tx
+            .send(PingEvent::Ping {
+                peer,
+                ping: elapsed,
+            })
+            .await;
+    }
+
+    /// Ping to `peer` failed.
+    ///
+    /// If the peer has failed `max_failures` consecutive pings, it's considered unresponsive and
+    /// the connection to it is closed.
+    async fn on_ping_failure(&mut self, peer: PeerId, error: Error) {
+        if !self.peers.contains(&peer) {
+            return;
+        }
+
+        // peer doesn't support the protocol so there's no point in pinging it again
+        if std::matches!(
+            error,
+            Error::SubstreamError(SubstreamError::NegotiationError(
+                NegotiationError::MultistreamSelectError(MultistreamNegotiationError::Failed)
+            ))
+        ) {
+            tracing::debug!(target: LOG_TARGET, ?peer, "peer doesn't support ping");
+            return;
+        }
+
+        let failures = self.failures.entry(peer).or_default();
+        *failures += 1;
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            ?error,
+            failures = *failures,
+            max_failures = self.max_failures,
+            "ping failed",
+        );
+
+        if *failures < self.max_failures {
+            return self.schedule_ping(peer);
+        }
+
+        tracing::debug!(target: LOG_TARGET, ?peer, "peer is unresponsive, disconnect");
+
+        self.failures.remove(&peer);
+        let _ = self.service.force_close(peer);
+        let _ = self.tx.send(PingEvent::Unresponsive { peer }).await;
     }
 
     /// Handle outbound substream.
@@ -137,13 +241,11 @@ impl Ping {
             };
 
             match tokio::time::timeout(Duration::from_secs(10), future).await {
-                Err(_) => Err(Error::Timeout),
-                Ok(Err(error)) => Err(error),
-                Ok(Ok(elapsed)) => Ok((peer, elapsed)),
+                Err(_) => (peer, Err(Error::Timeout)),
+                Ok(result) => (peer, result),
             }
         }));
     }
-
     /// Substream opened to remote peer.
     fn on_inbound_substream(&mut self, peer: PeerId, mut substream: Substream) {
         tracing::trace!(target: LOG_TARGET, ?peer, "handle inbound substream");
@@ -181,6 +283,11 @@ impl Ping {
                     Some(TransportEvent::ConnectionClosed { peer }) => {
                         self.on_connection_closed(peer);
                     }
+                    Some(TransportEvent::SubstreamOpenFailure { substream, error }) => {
+                        if let Some(peer) = self.pending_opens.remove(&substream) {
+                            self.on_ping_failure(peer, Error::SubstreamError(error)).await;
+                        }
+                    }
                     Some(TransportEvent::SubstreamOpened {
                         peer,
                         substream,
@@ -212,19 +319,113 @@ impl Ping {
                 _event = self.pending_inbound.next(), if !self.pending_inbound.is_empty() => {}
                 event = self.pending_outbound.next(), if !self.pending_outbound.is_empty() => {
                     match event {
-                        Some(Ok((peer, elapsed))) => {
-                            let _ = self
-                                .tx
-                                .send(PingEvent::Ping {
-                                    peer,
-                                    ping: elapsed,
-                                })
-                                .await;
-                        }
-                        event => tracing::debug!(target: LOG_TARGET, "failed to handle ping for an outbound peer: {event:?}"),
+                        Some((peer, Ok(elapsed))) => self.on_ping_success(peer, elapsed).await,
+                        Some((peer, Err(error))) => self.on_ping_failure(peer, error).await,
+                        None => {}
+                    }
+                }
+                peer = self.timers.next(), if !self.timers.is_empty() => {
+                    if let Some(peer) = peer {
+                        self.on_ping_timer(peer);
                     }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypto::Keypair,
+        mock::substream::MockSubstream,
+        protocol::{ConnectionHandle, InnerTransportEvent, ProtocolCommand},
+        transport::{
+            manager::{
+                limits::ConnectionLimitsConfig, reputation::ReputationConfig, TransportManager,
+            },
+            Endpoint, KEEP_ALIVE_TIMEOUT,
+        },
+        types::{protocol::ProtocolName, ConnectionId},
+        BandwidthSink,
+    };
+    use multiaddr::Multiaddr;
+    use tokio::sync::mpsc::channel;
+
+    use std::task::Poll;
+
+    #[tokio::test]
+    async fn unresponsive_peer_disconnected() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (_manager, handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
+        );
+        let (service, sender) = TransportService::new(
+            PeerId::random(),
+            ProtocolName::from(config::PROTOCOL_NAME),
+            Vec::new(),
+            Default::default(),
+            handle,
+            KEEP_ALIVE_TIMEOUT,
+        );
+        let (config, mut event_stream) = ConfigBuilder::new()
+            .with_max_failure(3usize)
+            .with_interval(Duration::from_millis(10))
+            .build();
+        tokio::spawn(Ping::new(service, config).run());
+
+        let peer = PeerId::random();
+        let (cmd_tx, mut cmd_rx) = channel(64);
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::listener(Multiaddr::empty(), ConnectionId::from(0usize)),
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx),
+            })
+            .await
+            .unwrap();
+
+        // every ping fails because the outbound substream can't be written to
+        for _ in 0..3 {
+            let substream_id = match cmd_rx.recv().await {
+                Some(ProtocolCommand::OpenSubstream { substream_id, .. }) => substream_id,
+                _ => panic!("expected ping substream to be opened"),
+            };
+
+            let mut substream = MockSubstream::new();
+            substream
+                .expect_poll_ready()
+                .returning(|_| Poll::Ready(Err(SubstreamError::ConnectionClosed)));
+
+            sender
+                .send(InnerTransportEvent::SubstreamOpened {
+                    peer,
+                    protocol: ProtocolName::from(config::PROTOCOL_NAME),
+                    fallback: None,
+                    direction: Direction::Outbound(substream_id),
+                    substream: Substream::new_mock(peer, substream_id, Box::new(substream)),
+                })
+                .await
+                .unwrap();
+        }
+
+        assert!(std::matches!(
+            cmd_rx.recv().await,
+            Some(ProtocolCommand::ForceClose)
+        ));
+        assert!(std::matches!(
+            event_stream.next().await,
+            Some(PingEvent::Unresponsive { peer: unresponsive }) if unresponsive == peer
+        ));
+    }
+}
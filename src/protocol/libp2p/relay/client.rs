@@ -0,0 +1,640 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Circuit relay client.
+//!
+//! The client reserves a slot on each configured relay so that other peers can reach the node
+//! through the relay. When a dial started with [`Litep2p::dial()`](crate::Litep2p::dial) or
+//! [`Litep2p::dial_address()`](crate::Litep2p::dial_address) fails, the transport manager asks the
+//! client to open a circuit to the peer through the relays instead. Circuits are carried by the
+//! tunnel transport, so the relayed connections are reported like any other connection.
+
+use crate::{
+    error::Error,
+    protocol::{
+        libp2p::relay::{
+            config::ClientConfig, read_message, schema, write_message, HANDSHAKE_TIMEOUT,
+        },
+        Direction, TransportEvent, TransportService,
+    },
+    substream::Substream,
+    transport::tunnel::TunnelHandle,
+    types::SubstreamId,
+    PeerId,
+};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, Future, StreamExt};
+use multiaddr::Multiaddr;
+use tokio::sync::{
+    mpsc::{channel, Receiver, Sender},
+    oneshot,
+};
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Log target for the file.
+const LOG_TARGET: &str = "litep2p::relay::client";
+
+/// Minimum time between two reservation requests sent to a relay.
+const MIN_RENEWAL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Minimum time between two attempts to dial a destination through the relays.
+const MIN_CIRCUIT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Size of the command channel of the client.
+const COMMAND_CHANNEL_SIZE: usize = 64;
+
+/// Handle for dialing peers through the relays of a [`RelayClient`].
+#[derive(Debug, Clone)]
+pub(crate) struct RelayClientHandle {
+    /// TX channel for sending dial requests to the client.
+    tx: Sender<(PeerId, oneshot::Sender<bool>)>,
+}
+
+impl RelayClientHandle {
+    /// Try to reach `destination` through the relays.
+    ///
+    /// The returned receiver resolves to `true` once a circuit to `destination` has been opened
+    /// and handed over to the tunnel transport. It resolves to `false`, or the sender is dropped,
+    /// if no relay could open a circuit or if `destination` was dialed through the relays too
+    /// recently.
+    pub(crate) fn dial(&self, destination: PeerId) -> oneshot::Receiver<bool> {
+        let (tx, rx) = oneshot::channel();
+
+        if self.tx.try_send((destination, tx)).is_err() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?destination,
+                "relay client clogged, cannot dial through relays",
+            );
+        }
+
+        rx
+    }
+}
+
+/// Result of a `HOP` or `STOP` handshake.
+enum Handshake {
+    /// Relay accepted the reservation.
+    Reserved {
+        /// Relay.
+        relay: PeerId,
+
+        /// `HOP` substream of the reservation.
+        substream: Substream,
+
+        /// When the reservation expires, in seconds since UNIX epoch.
+        expire: u64,
+    },
+
+    /// Relay opened a circuit to `destination`.
+    Connected {
+        /// Relay.
+        relay: PeerId,
+
+        /// Destination of the circuit.
+        destination: PeerId,
+
+        /// `HOP` substream carrying the circuit.
+        substream: Substream,
+    },
+
+    /// Relay opened a circuit from `source`.
+    Accepted {
+        /// Relay.
+        relay: PeerId,
+
+        /// Source of the circuit.
+        source: PeerId,
+
+        /// `STOP` substream carrying the circuit.
+        substream: Substream,
+    },
+
+    /// Handshake failed.
+    Failed {
+        /// Relay.
+        relay: PeerId,
+
+        /// Destination of the circuit, if the handshake was for a circuit opened by the client.
+        destination: Option<PeerId>,
+
+        /// Error.
+        error: Error,
+    },
+}
+
+/// Run `handshake` with a timeout.
+async fn with_timeout<T>(handshake: impl Future<Output = crate::Result<T>>) -> crate::Result<T> {
+    match tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await {
+        Err(_) => Err(Error::Timeout),
+        Ok(result) => result,
+    }
+}
+
+/// Send `HOP` `request` to the relay and wait for a successful response.
+async fn hop_request(
+    substream: &mut Substream,
+    r#type: schema::hop_message::Type,
+    peer: Option<PeerId>,
+) -> crate::Result<schema::HopMessage> {
+    let request = schema::HopMessage {
+        r#type: r#type as i32,
+        peer: peer.map(|peer| schema::Peer {
+            id: peer.to_bytes(),
+            addrs: Vec::new(),
+        }),
+        reservation: None,
+        limit: None,
+        status: None,
+    };
+    write_message(substream, &request).await?;
+
+    let response: schema::HopMessage = read_message(substream).await?;
+    if response.r#type != schema::hop_message::Type::Status as i32 {
+        return Err(Error::InvalidData);
+    }
+
+    match response.status {
+        Some(status) if status == schema::Status::Ok as i32 => Ok(response),
+        status => Err(Error::Other(format!("relay refused request: {status:?}"))),
+    }
+}
+
+/// Accept the circuit the relay opened over the `STOP` substream and return its source.
+async fn stop_handshake(substream: &mut Substream) -> crate::Result<PeerId> {
+    let request: schema::StopMessage = read_message(substream).await?;
+    if request.r#type != schema::stop_message::Type::Connect as i32 {
+        return Err(Error::InvalidData);
+    }
+
+    let source = request
+        .peer
+        .and_then(|peer| PeerId::from_bytes(&peer.id).ok())
+        .ok_or(Error::InvalidData)?;
+
+    let response = schema::StopMessage {
+        r#type: schema::stop_message::Type::Status as i32,
+        peer: None,
+        limit: None,
+        status: Some(schema::Status::Ok as i32),
+    };
+    write_message(substream, &response).await?;
+
+    Ok(source)
+}
+
+/// Circuit relay client.
+pub(crate) struct RelayClient {
+    /// Connection service of the `HOP` protocol.
+    hop_service: TransportService,
+
+    /// Connection service of the `STOP` protocol.
+    stop_service: TransportService,
+
+    /// Handle of the tunnel transport.
+    tunnel: TunnelHandle,
+
+    /// Relays and their addresses, in the order they're tried.
+    relays: Vec<(PeerId, Multiaddr)>,
+
+    /// Connected peers.
+    connected: HashSet<PeerId>,
+
+    /// `HOP` substreams of the active reservations.
+    ///
+    /// The substreams are kept open as the relay keeps the connection alive for as long as the
+    /// reservation substream is open.
+    reservations: HashMap<PeerId, Substream>,
+
+    /// Destinations being dialed through the relays, mapped to the relays not yet tried and the
+    /// sender for the result of the dial.
+    circuits: HashMap<PeerId, (Vec<PeerId>, oneshot::Sender<bool>)>,
+
+    /// Destinations recently dialed through the relays.
+    recent_circuits: HashMap<PeerId, Instant>,
+
+    /// Expiration times of the entries of `recent_circuits`, in the order they were inserted.
+    recent_circuit_expirations: VecDeque<(Instant, PeerId)>,
+
+    /// RX channel for receiving dial requests.
+    cmd_rx: Receiver<(PeerId, oneshot::Sender<bool>)>,
+
+    /// `HOP` substreams being opened, mapped to the relay and, for circuits, the destination.
+    pending_opens: HashMap<SubstreamId, (PeerId, Option<PeerId>)>,
+
+    /// Pending `HOP` and `STOP` handshakes.
+    handshakes: FuturesUnordered<BoxFuture<'static, Handshake>>,
+
+    /// Pending reservation renewals.
+    renewals: FuturesUnordered<BoxFuture<'static, PeerId>>,
+}
+
+impl RelayClient {
+    /// Create new [`RelayClient`] and a handle for dialing peers through it.
+    pub fn new(
+        hop_service: TransportService,
+        stop_service: TransportService,
+        config: ClientConfig,
+    ) -> (Self, RelayClientHandle) {
+        let (tx, cmd_rx) = channel(COMMAND_CHANNEL_SIZE);
+
+        (
+            Self {
+                hop_service,
+                stop_service,
+                tunnel: config.tunnel,
+                relays: config.relays,
+                connected: HashSet::new(),
+                reservations: HashMap::new(),
+                circuits: HashMap::new(),
+                recent_circuits: HashMap::new(),
+                recent_circuit_expirations: VecDeque::new(),
+                cmd_rx,
+                pending_opens: HashMap::new(),
+                handshakes: FuturesUnordered::new(),
+                renewals: FuturesUnordered::new(),
+            },
+            RelayClientHandle { tx },
+        )
+    }
+
+    /// Check if `peer` is one of the configured relays.
+    fn is_relay(&self, peer: &PeerId) -> bool {
+        self.relays.iter().any(|(relay, _)| relay == peer)
+    }
+
+    /// Ask `relay` for a reservation.
+    fn reserve(&mut self, relay: PeerId) {
+        match self.hop_service.open_substream(relay) {
+            Ok(substream_id) => {
+                self.pending_opens.insert(substream_id, (relay, None));
+            }
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?relay,
+                    ?error,
+                    "failed to request reservation",
+                );
+            }
+        }
+    }
+
+    /// Check if `destination` was dialed through the relays too recently to be dialed again and,
+    /// if not, record the new attempt.
+    fn is_rate_limited(&mut self, destination: PeerId) -> bool {
+        let now = Instant::now();
+
+        while let Some((expires, peer)) = self.recent_circuit_expirations.front().copied() {
+            if expires > now {
+                break;
+            }
+
+            self.recent_circuit_expirations.pop_front();
+            if self.recent_circuits.get(&peer) == Some(&expires) {
+                self.recent_circuits.remove(&peer);
+            }
+        }
+
+        if self.recent_circuits.contains_key(&destination) {
+            return true;
+        }
+
+        let expires = now + MIN_CIRCUIT_INTERVAL;
+        self.recent_circuits.insert(destination, expires);
+        self.recent_circuit_expirations.push_back((expires, destination));
+
+        false
+    }
+
+    /// Try to reach `destination` through the connected relays and report the result to `tx`.
+    fn dial_through_relays(&mut self, destination: PeerId, tx: oneshot::Sender<bool>) {
+        if self.is_relay(&destination) || self.connected.contains(&destination) {
+            let _ = tx.send(false);
+            return;
+        }
+
+        if self.circuits.contains_key(&destination) || self.is_rate_limited(destination) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?destination,
+                "destination dialed through relays too recently",
+            );
+            let _ = tx.send(false);
+            return;
+        }
+
+        // reversed so that the relays are popped in the order they were configured in
+        let relays = self
+            .relays
+            .iter()
+            .rev()
+            .map(|(relay, _)| *relay)
+            .filter(|relay| relay != &destination && self.connected.contains(relay))
+            .collect::<Vec<_>>();
+
+        if relays.is_empty() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?destination,
+                "no relay connected, cannot dial through relays",
+            );
+            let _ = tx.send(false);
+            return;
+        }
+
+        tracing::debug!(target: LOG_TARGET, ?destination, "dial peer through relays");
+
+        self.circuits.insert(destination, (relays, tx));
+        self.try_next_relay(destination);
+    }
+
+    /// Ask the next untried relay to open a circuit to `destination`.
+    fn try_next_relay(&mut self, destination: PeerId) {
+        let Some((relays, _)) = self.circuits.get_mut(&destination) else {
+            return;
+        };
+
+        while let Some(relay) = relays.pop() {
+            match self.hop_service.open_substream(relay) {
+                Ok(substream_id) => {
+                    self.pending_opens.insert(substream_id, (relay, Some(destination)));
+                    return;
+                }
+                Err(error) => tracing::debug!(
+                    target: LOG_TARGET,
+                    ?relay,
+                    ?destination,
+                    ?error,
+                    "failed to open hop substream",
+                ),
+            }
+        }
+
+        tracing::debug!(target: LOG_TARGET, ?destination, "failed to dial peer through relays");
+        if let Some((_, tx)) = self.circuits.remove(&destination) {
+            let _ = tx.send(false);
+        }
+    }
+
+    /// Handle outbound `HOP` substream.
+    fn on_hop_substream(&mut self, substream_id: SubstreamId, mut substream: Substream) {
+        let Some((relay, destination)) = self.pending_opens.remove(&substream_id) else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?substream_id,
+                "outbound hop substream ID does not exist",
+            );
+            return;
+        };
+
+        self.handshakes.push(Box::pin(async move {
+            let r#type = match destination {
+                Some(_) => schema::hop_message::Type::Connect,
+                None => schema::hop_message::Type::Reserve,
+            };
+
+            match with_timeout(hop_request(&mut substream, r#type, destination)).await {
+                Ok(response) => match destination {
+                    Some(destination) => Handshake::Connected {
+                        relay,
+                        destination,
+                        substream,
+                    },
+                    None => Handshake::Reserved {
+                        relay,
+                        substream,
+                        expire: response.reservation.map_or(0, |reservation| reservation.expire),
+                    },
+                },
+                Err(error) => Handshake::Failed {
+                    relay,
+                    destination,
+                    error,
+                },
+            }
+        }));
+    }
+
+    /// Handle inbound `STOP` substream.
+    fn on_stop_substream(&mut self, relay: PeerId, mut substream: Substream) {
+        if !self.reservations.contains_key(&relay) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?relay,
+                "stop substream from peer without reservation",
+            );
+            return;
+        }
+
+        self.handshakes.push(Box::pin(async move {
+            match with_timeout(stop_handshake(&mut substream)).await {
+                Ok(source) => Handshake::Accepted {
+                    relay,
+                    source,
+                    substream,
+                },
+                Err(error) => Handshake::Failed {
+                    relay,
+                    destination: None,
+                    error,
+                },
+            }
+        }));
+    }
+
+    /// Handle finished handshake.
+    async fn on_handshake(&mut self, handshake: Handshake) {
+        match handshake {
+            Handshake::Reserved {
+                relay,
+                substream,
+                expire,
+            } => {
+                let now =
+                    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs());
+                let renew_in = Duration::from_secs(expire.saturating_sub(now) * 3 / 4)
+                    .max(MIN_RENEWAL_INTERVAL);
+
+                tracing::trace!(target: LOG_TARGET, ?relay, ?renew_in, "reservation accepted");
+
+                // the substream of the previous reservation, if any, is closed
+                self.reservations.insert(relay, substream);
+                self.renewals.push(Box::pin(async move {
+                    tokio::time::sleep(renew_in).await;
+                    relay
+                }));
+            }
+            Handshake::Connected {
+                relay,
+                destination,
+                substream,
+            } => {
+                tracing::trace!(target: LOG_TARGET, ?relay, ?destination, "circuit opened");
+
+                let result = self.tunnel.open(substream).await;
+                if let Err(error) = &result {
+                    tracing::debug!(target: LOG_TARGET, ?error, "failed to open tunnel");
+                }
+
+                if let Some((_, tx)) = self.circuits.remove(&destination) {
+                    let _ = tx.send(result.is_ok());
+                }
+            }
+            Handshake::Accepted {
+                relay,
+                source,
+                substream,
+            } => {
+                tracing::trace!(target: LOG_TARGET, ?relay, ?source, "circuit accepted");
+
+                if let Err(error) = self.tunnel.accept(substream).await {
+                    tracing::debug!(target: LOG_TARGET, ?error, "failed to accept tunnel");
+                }
+            }
+            Handshake::Failed {
+                relay,
+                destination,
+                error,
+            } => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?relay,
+                    ?destination,
+                    ?error,
+                    "handshake failed",
+                );
+
+                if let Some(destination) = destination {
+                    self.try_next_relay(destination);
+                }
+            }
+        }
+    }
+
+    /// Start [`RelayClient`] event loop.
+    pub async fn run(mut self) {
+        tracing::debug!(target: LOG_TARGET, "starting relay client event loop");
+
+        for (relay, address) in self.relays.clone() {
+            self.hop_service.add_known_address(&relay, std::iter::once(address));
+
+            if let Err(error) = self.hop_service.dial(&relay) {
+                tracing::debug!(target: LOG_TARGET, ?relay, ?error, "failed to dial relay");
+            }
+        }
+
+        loop {
+            tokio::select! {
+                event = self.hop_service.next() => match event {
+                    Some(TransportEvent::ConnectionEstablished { peer, endpoint }) => {
+                        self.connected.insert(peer);
+
+                        if self.is_relay(&peer) && !endpoint.is_relayed() {
+                            self.reserve(peer);
+                        }
+                    }
+                    Some(TransportEvent::ConnectionClosed { peer }) => {
+                        self.connected.remove(&peer);
+
+                        if self.reservations.remove(&peer).is_some() {
+                            tracing::debug!(target: LOG_TARGET, relay = ?peer, "reservation lost");
+
+                            if let Err(error) = self.hop_service.dial(&peer) {
+                                tracing::debug!(
+                                    target: LOG_TARGET,
+                                    relay = ?peer,
+                                    ?error,
+                                    "failed to dial relay",
+                                );
+                            }
+                        }
+                    }
+                    Some(TransportEvent::SubstreamOpened {
+                        peer,
+                        substream,
+                        direction,
+                        ..
+                    }) => match direction {
+                        Direction::Outbound(substream_id) => {
+                            self.on_hop_substream(substream_id, substream);
+                        }
+                        Direction::Inbound => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                "relay client doesn't accept inbound hop substreams",
+                            );
+                        }
+                    },
+                    Some(TransportEvent::SubstreamOpenFailure { substream, error }) => {
+                        if let Some((relay, destination)) = self.pending_opens.remove(&substream) {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?relay,
+                                ?destination,
+                                ?error,
+                                "failed to open hop substream",
+                            );
+
+                            if let Some(destination) = destination {
+                                self.try_next_relay(destination);
+                            }
+                        }
+                    }
+                    Some(_) => {}
+                    None => return,
+                },
+                event = self.stop_service.next() => match event {
+                    Some(TransportEvent::SubstreamOpened {
+                        peer,
+                        substream,
+                        direction: Direction::Inbound,
+                        ..
+                    }) => {
+                        self.on_stop_substream(peer, substream);
+                    }
+                    Some(_) => {}
+                    None => return,
+                },
+                command = self.cmd_rx.recv() => match command {
+                    Some((destination, tx)) => self.dial_through_relays(destination, tx),
+                    None => return,
+                },
+                event = self.handshakes.next(), if !self.handshakes.is_empty() => {
+                    if let Some(handshake) = event {
+                        self.on_handshake(handshake).await;
+                    }
+                }
+                event = self.renewals.next(), if !self.renewals.is_empty() => {
+                    if let Some(relay) = event {
+                        if self.reservations.contains_key(&relay) {
+                            self.reserve(relay);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::{
+    codec::ProtocolCodec, protocol::libp2p::relay::RelayEvent, transport::tunnel::TunnelHandle,
+    types::protocol::ProtocolName, PeerId, DEFAULT_CHANNEL_SIZE,
+};
+
+use futures::Stream;
+use multiaddr::{Multiaddr, Protocol};
+use tokio::sync::mpsc::{channel, Sender};
+use tokio_stream::wrappers::ReceiverStream;
+
+use std::time::Duration;
+
+/// Protocol name of the `HOP` protocol, used by clients to talk to the relay.
+pub const HOP_PROTOCOL_NAME: &str = "/libp2p/circuit/relay/0.2.0/hop";
+
+/// Protocol name of the `STOP` protocol, used by the relay to talk to the destination of a
+/// circuit.
+pub const STOP_PROTOCOL_NAME: &str = "/libp2p/circuit/relay/0.2.0/stop";
+
+/// Maximum number of active reservations.
+const MAX_RESERVATIONS: usize = 128;
+
+/// How long a reservation is valid for.
+const RESERVATION_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum number of active circuits.
+const MAX_CIRCUITS: usize = 16;
+
+/// Maximum number of active circuits opened by a single peer.
+const MAX_CIRCUITS_PER_PEER: usize = 4;
+
+/// Maximum duration of a circuit.
+const CIRCUIT_DURATION: Duration = Duration::from_secs(2 * 60);
+
+/// Maximum number of bytes relayed over a circuit in each direction.
+const CIRCUIT_DATA: u64 = 1 << 17;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::relay::config";
+
+/// Circuit relay server configuration.
+pub struct Config {
+    /// Protocol name of the `HOP` protocol.
+    pub(crate) hop_protocol: ProtocolName,
+
+    /// Protocol name of the `STOP` protocol.
+    pub(crate) stop_protocol: ProtocolName,
+
+    /// Codec used by the protocols.
+    pub(crate) codec: ProtocolCodec,
+
+    /// Maximum number of active reservations.
+    pub(crate) max_reservations: usize,
+
+    /// How long a reservation is valid for.
+    pub(crate) reservation_duration: Duration,
+
+    /// Maximum number of active circuits.
+    pub(crate) max_circuits: usize,
+
+    /// Maximum number of active circuits opened by a single peer.
+    pub(crate) max_circuits_per_peer: usize,
+
+    /// Maximum duration of a circuit.
+    pub(crate) circuit_duration: Duration,
+
+    /// Maximum number of bytes relayed over a circuit in each direction.
+    pub(crate) circuit_data: u64,
+
+    /// TX channel for sending events to the user protocol.
+    pub(crate) tx_event: Sender<RelayEvent>,
+}
+
+impl Config {
+    /// Create new [`Config`] with default values.
+    ///
+    /// Returns a config that is given to `Litep2pConfig` and an event stream for [`RelayEvent`]s.
+    pub fn default() -> (Self, Box<dyn Stream<Item = RelayEvent> + Send + Unpin>) {
+        ConfigBuilder::new().build()
+    }
+}
+
+/// Circuit relay server configuration builder.
+pub struct ConfigBuilder {
+    /// Maximum number of active reservations.
+    max_reservations: usize,
+
+    /// How long a reservation is valid for.
+    reservation_duration: Duration,
+
+    /// Maximum number of active circuits.
+    max_circuits: usize,
+
+    /// Maximum number of active circuits opened by a single peer.
+    max_circuits_per_peer: usize,
+
+    /// Maximum duration of a circuit.
+    circuit_duration: Duration,
+
+    /// Maximum number of bytes relayed over a circuit in each direction.
+    circuit_data: u64,
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigBuilder {
+    /// Create new default [`Config`] which can be modified by the user.
+    pub fn new() -> Self {
+        Self {
+            max_reservations: MAX_RESERVATIONS,
+            reservation_duration: RESERVATION_DURATION,
+            max_circuits: MAX_CIRCUITS,
+            max_circuits_per_peer: MAX_CIRCUITS_PER_PEER,
+            circuit_duration: CIRCUIT_DURATION,
+            circuit_data: CIRCUIT_DATA,
+        }
+    }
+
+    /// Set maximum number of active reservations.
+    ///
+    /// Reservation requests are refused once the limit has been reached.
+    ///
+    /// Default value is `128`.
+    pub fn with_max_reservations(mut self, max_reservations: usize) -> Self {
+        self.max_reservations = max_reservations;
+        self
+    }
+
+    /// Set how long a reservation is valid for.
+    ///
+    /// Clients must renew their reservation before it expires, otherwise circuits to them are
+    /// refused. The reservation is also dropped if the client disconnects.
+    ///
+    /// Default value is one hour.
+    pub fn with_reservation_duration(mut self, duration: Duration) -> Self {
+        self.reservation_duration = duration;
+        self
+    }
+
+    /// Set maximum number of active circuits.
+    ///
+    /// Default value is `16`.
+    pub fn with_max_circuits(mut self, max_circuits: usize) -> Self {
+        self.max_circuits = max_circuits;
+        self
+    }
+
+    /// Set maximum number of active circuits opened by a single peer.
+    ///
+    /// Default value is `4`.
+    pub fn with_max_circuits_per_peer(mut self, max_circuits: usize) -> Self {
+        self.max_circuits_per_peer = max_circuits;
+        self
+    }
+
+    /// Set maximum duration of a circuit after which the circuit is closed.
+    ///
+    /// Default value is two minutes.
+    pub fn with_circuit_duration_limit(mut self, duration: Duration) -> Self {
+        self.circuit_duration = duration;
+        self
+    }
+
+    /// Set maximum number of bytes relayed over a circuit in each direction.
+    ///
+    /// The circuit is closed once either of the peers has sent more than `data` bytes.
+    ///
+    /// Default value is 128 KiB.
+    pub fn with_circuit_data_limit(mut self, data: u64) -> Self {
+        self.circuit_data = data;
+        self
+    }
+
+    /// Build [`Config`].
+    pub fn build(self) -> (Config, Box<dyn Stream<Item = RelayEvent> + Send + Unpin>) {
+        let (tx_event, rx_event) = channel(DEFAULT_CHANNEL_SIZE);
+
+        (
+            Config {
+                tx_event,
+                hop_protocol: ProtocolName::from(HOP_PROTOCOL_NAME),
+                stop_protocol: ProtocolName::from(STOP_PROTOCOL_NAME),
+                codec: ProtocolCodec::Unspecified,
+                max_reservations: self.max_reservations,
+                reservation_duration: self.reservation_duration,
+                max_circuits: self.max_circuits,
+                max_circuits_per_peer: self.max_circuits_per_peer,
+                circuit_duration: self.circuit_duration,
+                circuit_data: self.circuit_data,
+            },
+            Box::new(ReceiverStream::new(rx_event)),
+        )
+    }
+}
+
+/// Circuit relay client configuration.
+pub struct ClientConfig {
+    /// Protocol name of the `HOP` protocol.
+    pub(crate) hop_protocol: ProtocolName,
+
+    /// Protocol name of the `STOP` protocol.
+    pub(crate) stop_protocol: ProtocolName,
+
+    /// Codec used by the protocols.
+    pub(crate) codec: ProtocolCodec,
+
+    /// Relays and their addresses, in the order they're tried.
+    pub(crate) relays: Vec<(PeerId, Multiaddr)>,
+
+    /// Handle of the tunnel transport which carries the relayed connections.
+    pub(crate) tunnel: TunnelHandle,
+}
+
+impl ClientConfig {
+    /// Create new [`ClientConfig`].
+    ///
+    /// `relays` are the addresses of the relays, each ending with `/p2p/<relay peer ID>`, and
+    /// `tunnel` the handle of the tunnel transport, which must be enabled as well. Addresses
+    /// without a peer ID are ignored.
+    pub fn new(relays: Vec<Multiaddr>, tunnel: TunnelHandle) -> Self {
+        let relays = relays
+            .into_iter()
+            .filter_map(|address| {
+                let peer = match address.iter().last() {
+                    Some(Protocol::P2p(multihash)) => PeerId::from_multihash(multihash).ok(),
+                    _ => None,
+                };
+
+                if peer.is_none() {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        ?address,
+                        "relay address doesn't contain peer ID, ignoring",
+                    );
+                }
+
+                peer.map(|peer| (peer, address))
+            })
+            .collect();
+
+        Self {
+            hop_protocol: ProtocolName::from(HOP_PROTOCOL_NAME),
+            stop_protocol: ProtocolName::from(STOP_PROTOCOL_NAME),
+            codec: ProtocolCodec::Unspecified,
+            relays,
+            tunnel,
+        }
+    }
+}
@@ -0,0 +1,941 @@
+// Copyright 2023 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! [`/libp2p/circuit/relay/0.2.0`](https://github.com/libp2p/specs/blob/master/relay/circuit-v2.md)
+//! server and client implementation.
+//!
+//! Clients that aren't publicly reachable reserve a slot on the relay using the `HOP` protocol.
+//! Other peers can then ask the relay to connect them to a client with a reservation, in which
+//! case the relay opens a `STOP` substream to the client and, once the client accepts the
+//! circuit, forwards data between the two substreams.
+//!
+//! The client reserves a slot on each of the relays it's configured with and, when a peer can't be
+//! dialed directly, connects to it through the relays.
+//!
+//! Both reservations and circuits are limited: the number of reservations and circuits is
+//! bounded and each circuit is closed once it has been open for too long or once either side has
+//! sent more data than allowed.
+
+use crate::{
+    error::Error,
+    protocol::{Direction, TransportEvent, TransportService},
+    substream::Substream,
+    transport::is_relayed_address,
+    types::SubstreamId,
+    PeerId,
+};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use multiaddr::Protocol;
+use multihash::Multihash;
+use prost::Message;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::mpsc::Sender,
+};
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub use config::{ClientConfig, Config, ConfigBuilder, HOP_PROTOCOL_NAME, STOP_PROTOCOL_NAME};
+
+pub(crate) use client::{RelayClient, RelayClientHandle};
+
+mod client;
+mod config;
+
+mod schema {
+    include!(concat!(env!("OUT_DIR"), "/relay.rs"));
+}
+
+use schema::hop_message;
+
+/// Log target for the file.
+const LOG_TARGET: &str = "litep2p::relay";
+
+/// Maximum size of a `HOP` or `STOP` message.
+const MAX_MESSAGE_SIZE: usize = 4096;
+
+/// Maximum length of the message size prefix.
+const MAX_PREFIX_SIZE: usize = 10;
+
+/// Timeout for receiving a request from a client and for the `STOP` handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reason why a circuit was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitCloseReason {
+    /// Both peers closed the circuit.
+    Closed,
+
+    /// One of the peers sent more data than allowed.
+    DataLimitExceeded,
+
+    /// The circuit was open for longer than allowed.
+    DurationLimitExceeded,
+
+    /// Reading from or writing to one of the substreams failed.
+    Error,
+}
+
+/// Events emitted by the relay server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayEvent {
+    /// Reservation was accepted or renewed.
+    ReservationAccepted {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
+    /// Reservation was refused because the maximum number of reservations has been reached.
+    ReservationRefused {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
+    /// Circuit was opened between two peers.
+    CircuitOpened {
+        /// Peer which requested the circuit.
+        source: PeerId,
+
+        /// Peer which accepted the circuit.
+        destination: PeerId,
+    },
+
+    /// Circuit was closed.
+    CircuitClosed {
+        /// Peer which requested the circuit.
+        source: PeerId,
+
+        /// Peer which accepted the circuit.
+        destination: PeerId,
+
+        /// Why the circuit was closed.
+        reason: CircuitCloseReason,
+    },
+}
+
+/// Limits applied to each circuit.
+#[derive(Debug, Clone, Copy)]
+struct CircuitLimit {
+    /// Maximum duration of the circuit.
+    duration: Duration,
+
+    /// Maximum number of bytes relayed in each direction.
+    data: u64,
+}
+
+impl CircuitLimit {
+    /// Convert the limit into a message sent to the peers.
+    fn to_message(self) -> schema::Limit {
+        schema::Limit {
+            duration: Some(u32::try_from(self.duration.as_secs()).unwrap_or(u32::MAX)),
+            data: Some(self.data),
+        }
+    }
+}
+
+/// Active reservations.
+#[derive(Debug)]
+struct Reservations {
+    /// Maximum number of reservations.
+    max_reservations: usize,
+
+    /// How long a reservation is valid for.
+    duration: Duration,
+
+    /// Expiration times of the reservations.
+    expires: HashMap<PeerId, Instant>,
+}
+
+impl Reservations {
+    /// Create new [`Reservations`].
+    fn new(max_reservations: usize, duration: Duration) -> Self {
+        Self {
+            max_reservations,
+            duration,
+            expires: HashMap::new(),
+        }
+    }
+
+    /// Reserve a slot for `peer` or renew its existing reservation.
+    ///
+    /// Returns the time when the reservation expires or `None` if all slots are taken.
+    fn reserve(&mut self, peer: PeerId, now: Instant) -> Option<Instant> {
+        self.expires.retain(|_, expires| *expires > now);
+
+        if !self.expires.contains_key(&peer) && self.expires.len() >= self.max_reservations {
+            return None;
+        }
+
+        let expires = now + self.duration;
+        self.expires.insert(peer, expires);
+
+        Some(expires)
+    }
+
+    /// Check if `peer` has a reservation that hasn't expired.
+    fn is_reserved(&self, peer: &PeerId, now: Instant) -> bool {
+        self.expires.get(peer).is_some_and(|expires| *expires > now)
+    }
+
+    /// Remove the reservation of `peer`.
+    fn remove(&mut self, peer: &PeerId) {
+        self.expires.remove(peer);
+    }
+}
+
+/// Read a length-prefixed message from `io`.
+///
+/// The message is read byte-exactly so that no data following the message is consumed.
+async fn read_message<T: Message + Default, S: AsyncRead + Unpin>(io: &mut S) -> crate::Result<T> {
+    let mut prefix = Vec::with_capacity(MAX_PREFIX_SIZE);
+
+    let size = loop {
+        let byte = io.read_u8().await?;
+        prefix.push(byte);
+
+        if unsigned_varint::decode::is_last(byte) {
+            break unsigned_varint::decode::usize(&prefix).map_err(|_| Error::InvalidData)?.0;
+        }
+
+        if prefix.len() == MAX_PREFIX_SIZE {
+            return Err(Error::InvalidData);
+        }
+    };
+
+    if size > MAX_MESSAGE_SIZE {
+        return Err(Error::InvalidData);
+    }
+
+    let mut payload = vec![0u8; size];
+    io.read_exact(&mut payload).await?;
+
+    Ok(T::decode(payload.as_slice())?)
+}
+
+/// Write a length-prefixed message to `io`.
+async fn write_message<T: Message, S: AsyncWrite + Unpin>(
+    io: &mut S,
+    message: &T,
+) -> crate::Result<()> {
+    io.write_all(&message.encode_length_delimited_to_vec()).await?;
+    io.flush().await?;
+
+    Ok(())
+}
+
+/// Create `HOP` status message.
+fn hop_status(status: schema::Status) -> schema::HopMessage {
+    schema::HopMessage {
+        r#type: hop_message::Type::Status as i32,
+        peer: None,
+        reservation: None,
+        limit: None,
+        status: Some(status as i32),
+    }
+}
+
+/// Ask the destination of a circuit to accept it over the `STOP` substream.
+async fn stop_handshake(
+    substream: &mut Substream,
+    source: PeerId,
+    limit: CircuitLimit,
+) -> crate::Result<()> {
+    let request = schema::StopMessage {
+        r#type: schema::stop_message::Type::Connect as i32,
+        peer: Some(schema::Peer {
+            id: source.to_bytes(),
+            addrs: Vec::new(),
+        }),
+        limit: Some(limit.to_message()),
+        status: None,
+    };
+    write_message(substream, &request).await?;
+
+    let response: schema::StopMessage = read_message(substream).await?;
+    if response.r#type != schema::stop_message::Type::Status as i32 {
+        return Err(Error::InvalidData);
+    }
+
+    match response.status {
+        Some(status) if status == schema::Status::Ok as i32 => Ok(()),
+        status => Err(Error::Other(format!(
+            "destination refused circuit: {status:?}"
+        ))),
+    }
+}
+
+/// Copy data from `from` to `to` until `from` is closed or more than `limit` bytes have been
+/// received.
+///
+/// Data up to the limit is forwarded before the copy is aborted.
+async fn copy_limited<R, W>(mut from: R, mut to: W, limit: u64) -> Result<(), CircuitCloseReason>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buffer = vec![0u8; 4096];
+    let mut relayed = 0u64;
+
+    loop {
+        let nread = from.read(&mut buffer).await.map_err(|_| CircuitCloseReason::Error)?;
+
+        if nread == 0 {
+            let _ = to.shutdown().await;
+            return Ok(());
+        }
+
+        let allowed = usize::try_from(limit - relayed).unwrap_or(usize::MAX).min(nread);
+        to.write_all(&buffer[..allowed]).await.map_err(|_| CircuitCloseReason::Error)?;
+        to.flush().await.map_err(|_| CircuitCloseReason::Error)?;
+        relayed += allowed as u64;
+
+        if allowed < nread {
+            return Err(CircuitCloseReason::DataLimitExceeded);
+        }
+    }
+}
+
+/// Forward data between the substreams of a circuit until the circuit is closed.
+async fn relay_circuit(
+    source: Substream,
+    destination: Substream,
+    limit: CircuitLimit,
+) -> CircuitCloseReason {
+    let (source_read, source_write) = tokio::io::split(source);
+    let (destination_read, destination_write) = tokio::io::split(destination);
+
+    let future = futures::future::try_join(
+        copy_limited(source_read, destination_write, limit.data),
+        copy_limited(destination_read, source_write, limit.data),
+    );
+
+    match tokio::time::timeout(limit.duration, future).await {
+        Err(_) => CircuitCloseReason::DurationLimitExceeded,
+        Ok(Ok(_)) => CircuitCloseReason::Closed,
+        Ok(Err(reason)) => reason,
+    }
+}
+
+/// Open a circuit from `source` to `destination`.
+///
+/// Performs the `STOP` handshake with the destination, informs the source that the circuit was
+/// opened and forwards data until the circuit is closed. Returns `None` if the circuit couldn't
+/// be opened.
+async fn open_circuit(
+    mut hop: Substream,
+    mut stop: Substream,
+    source: PeerId,
+    destination: PeerId,
+    limit: CircuitLimit,
+    tx: Sender<RelayEvent>,
+) -> Option<CircuitCloseReason> {
+    match tokio::time::timeout(HANDSHAKE_TIMEOUT, stop_handshake(&mut stop, source, limit)).await {
+        Ok(Ok(())) => {}
+        result => {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?source,
+                ?destination,
+                ?result,
+                "failed to open circuit to destination",
+            );
+
+            let _ = write_message(&mut hop, &hop_status(schema::Status::ConnectionFailed)).await;
+            return None;
+        }
+    }
+
+    let mut response = hop_status(schema::Status::Ok);
+    response.limit = Some(limit.to_message());
+
+    if let Err(error) = write_message(&mut hop, &response).await {
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?source,
+            ?destination,
+            ?error,
+            "failed to send circuit status to source",
+        );
+        return None;
+    }
+
+    tracing::trace!(target: LOG_TARGET, ?source, ?destination, "circuit opened");
+
+    let _ = tx
+        .send(RelayEvent::CircuitOpened {
+            source,
+            destination,
+        })
+        .await;
+
+    Some(relay_circuit(hop, stop, limit).await)
+}
+
+/// Circuit relay server.
+pub(crate) struct Relay {
+    /// Connection service of the `HOP` protocol.
+    hop_service: TransportService,
+
+    /// Connection service of the `STOP` protocol.
+    stop_service: TransportService,
+
+    /// TX channel for sending events to the user protocol.
+    tx: Sender<RelayEvent>,
+
+    /// Active reservations.
+    reservations: Reservations,
+
+    /// How long a reservation is valid for.
+    reservation_duration: Duration,
+
+    /// Maximum number of active circuits.
+    max_circuits: usize,
+
+    /// Maximum number of active circuits opened by a single peer.
+    max_circuits_per_peer: usize,
+
+    /// Limits applied to each circuit.
+    limit: CircuitLimit,
+
+    /// Number of active circuits opened by each peer.
+    circuits_per_peer: HashMap<PeerId, usize>,
+
+    /// Pending `HOP` requests.
+    pending_requests: FuturesUnordered<
+        BoxFuture<'static, (PeerId, Substream, crate::Result<schema::HopMessage>)>,
+    >,
+
+    /// Pending responses to `HOP` requests.
+    pending_responses: FuturesUnordered<BoxFuture<'static, ()>>,
+
+    /// `STOP` substreams being opened, mapped to the source, destination and `HOP` substream of
+    /// the circuit.
+    pending_stops: HashMap<SubstreamId, (PeerId, PeerId, Substream)>,
+
+    /// Active circuits.
+    circuits: FuturesUnordered<BoxFuture<'static, (PeerId, PeerId, Option<CircuitCloseReason>)>>,
+}
+
+impl Relay {
+    /// Create new [`Relay`].
+    pub fn new(
+        hop_service: TransportService,
+        stop_service: TransportService,
+        config: Config,
+    ) -> Self {
+        Self {
+            hop_service,
+            stop_service,
+            tx: config.tx_event,
+            reservations: Reservations::new(config.max_reservations, config.reservation_duration),
+            reservation_duration: config.reservation_duration,
+            max_circuits: config.max_circuits,
+            max_circuits_per_peer: config.max_circuits_per_peer,
+            limit: CircuitLimit {
+                duration: config.circuit_duration,
+                data: config.circuit_data,
+            },
+            circuits_per_peer: HashMap::new(),
+            pending_requests: FuturesUnordered::new(),
+            pending_responses: FuturesUnordered::new(),
+            pending_stops: HashMap::new(),
+            circuits: FuturesUnordered::new(),
+        }
+    }
+
+    /// Get the non-relayed addresses of the relay that are advertised to clients.
+    fn relay_addresses(&self) -> Vec<Vec<u8>> {
+        let local_peer = self.hop_service.local_peer_id();
+        let mut addresses = self.hop_service.public_addresses().get_addresses();
+
+        for address in self.hop_service.listen_addresses() {
+            if !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+
+        addresses
+            .into_iter()
+            .filter(|address| !is_relayed_address(address))
+            .map(|address| match address.iter().last() {
+                Some(Protocol::P2p(_)) => address,
+                _ => address.with(Protocol::P2p(Multihash::from(local_peer))),
+            })
+            .map(|address| address.to_vec())
+            .collect()
+    }
+
+    /// Send `response` to the client and close the substream.
+    fn respond(&mut self, mut substream: Substream, response: schema::HopMessage) {
+        self.pending_responses.push(Box::pin(async move {
+            if write_message(&mut substream, &response).await.is_ok() {
+                substream.close().await;
+            }
+        }));
+    }
+
+    /// Send reservation `response` to the client and keep the substream open until the client
+    /// closes it or the reservation expires.
+    ///
+    /// Relay clients hold the substream of their reservation open which keeps the connection to
+    /// the relay alive while the client has no other substreams open.
+    fn respond_reserved(&mut self, mut substream: Substream, response: schema::HopMessage) {
+        let duration = self.reservation_duration;
+
+        self.pending_responses.push(Box::pin(async move {
+            if write_message(&mut substream, &response).await.is_err() {
+                return;
+            }
+
+            let mut buffer = [0u8; 64];
+            let _ = tokio::time::timeout(duration, async {
+                while let Ok(nread) = substream.read(&mut buffer).await {
+                    if nread == 0 {
+                        break;
+                    }
+                }
+            })
+            .await;
+
+            substream.close().await;
+        }));
+    }
+
+    /// Release the circuit slot of `source`.
+    fn release_circuit(&mut self, source: &PeerId) {
+        if let Some(circuits) = self.circuits_per_peer.get_mut(source) {
+            *circuits -= 1;
+
+            if *circuits == 0 {
+                self.circuits_per_peer.remove(source);
+            }
+        }
+    }
+
+    /// Handle inbound `HOP` substream.
+    fn on_inbound_substream(&mut self, peer: PeerId, mut substream: Substream) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "inbound hop substream");
+
+        self.pending_requests.push(Box::pin(async move {
+            let result = match tokio::time::timeout(
+                HANDSHAKE_TIMEOUT,
+                read_message::<schema::HopMessage, _>(&mut substream),
+            )
+            .await
+            {
+                Err(_) => Err(Error::Timeout),
+                Ok(result) => result,
+            };
+
+            (peer, substream, result)
+        }));
+    }
+
+    /// Handle `HOP` request.
+    async fn on_request(
+        &mut self,
+        peer: PeerId,
+        substream: Substream,
+        message: schema::HopMessage,
+    ) {
+        match hop_message::Type::try_from(message.r#type) {
+            Ok(hop_message::Type::Reserve) => self.on_reserve(peer, substream).await,
+            Ok(hop_message::Type::Connect) => self.on_connect(peer, substream, message),
+            _ => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    r#type = ?message.r#type,
+                    "unexpected hop message",
+                );
+                self.respond(substream, hop_status(schema::Status::UnexpectedMessage));
+            }
+        }
+    }
+
+    /// Handle reservation request.
+    async fn on_reserve(&mut self, peer: PeerId, substream: Substream) {
+        if self.reservations.reserve(peer, Instant::now()).is_none() {
+            tracing::debug!(target: LOG_TARGET, ?peer, "reservation refused");
+
+            self.respond(substream, hop_status(schema::Status::ResourceLimitExceeded));
+            let _ = self.tx.send(RelayEvent::ReservationRefused { peer }).await;
+            return;
+        }
+
+        tracing::trace!(target: LOG_TARGET, ?peer, "reservation accepted");
+
+        let expire = (SystemTime::now() + self.reservation_duration)
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |expire| expire.as_secs());
+
+        let mut response = hop_status(schema::Status::Ok);
+        response.reservation = Some(schema::Reservation {
+            expire,
+            addrs: self.relay_addresses(),
+            voucher: None,
+        });
+        response.limit = Some(self.limit.to_message());
+
+        self.respond_reserved(substream, response);
+        let _ = self.tx.send(RelayEvent::ReservationAccepted { peer }).await;
+    }
+
+    /// Handle request to open a circuit from `source` to the peer in `message`.
+    fn on_connect(&mut self, source: PeerId, substream: Substream, message: schema::HopMessage) {
+        let Some(destination) = message.peer.and_then(|peer| PeerId::from_bytes(&peer.id).ok())
+        else {
+            tracing::debug!(target: LOG_TARGET, ?source, "connect request without valid peer");
+
+            self.respond(substream, hop_status(schema::Status::MalformedMessage));
+            return;
+        };
+
+        if !self.reservations.is_reserved(&destination, Instant::now()) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?source,
+                ?destination,
+                "destination doesn't have a reservation",
+            );
+
+            self.respond(substream, hop_status(schema::Status::NoReservation));
+            return;
+        }
+
+        let circuits = self.circuits_per_peer.get(&source).copied().unwrap_or(0);
+        if self.pending_stops.len() + self.circuits.len() >= self.max_circuits
+            || circuits >= self.max_circuits_per_peer
+        {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?source,
+                ?destination,
+                ?circuits,
+                "circuit limit reached",
+            );
+
+            self.respond(substream, hop_status(schema::Status::ResourceLimitExceeded));
+            return;
+        }
+
+        match self.stop_service.open_substream(destination) {
+            Ok(substream_id) => {
+                self.pending_stops.insert(substream_id, (source, destination, substream));
+                *self.circuits_per_peer.entry(source).or_default() += 1;
+            }
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?source,
+                    ?destination,
+                    ?error,
+                    "failed to open stop substream",
+                );
+                self.respond(substream, hop_status(schema::Status::ConnectionFailed));
+            }
+        }
+    }
+
+    /// Handle outbound `STOP` substream.
+    fn on_stop_substream(&mut self, substream_id: SubstreamId, stop: Substream) {
+        let Some((source, destination, hop)) = self.pending_stops.remove(&substream_id) else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?substream_id,
+                "outbound stop substream ID does not exist",
+            );
+            return;
+        };
+
+        let limit = self.limit;
+        let tx = self.tx.clone();
+
+        self.circuits.push(Box::pin(async move {
+            let result = open_circuit(hop, stop, source, destination, limit, tx).await;
+
+            (source, destination, result)
+        }));
+    }
+
+    /// Circuit was closed or failed to open.
+    async fn on_circuit_closed(
+        &mut self,
+        source: PeerId,
+        destination: PeerId,
+        reason: Option<CircuitCloseReason>,
+    ) {
+        self.release_circuit(&source);
+
+        if let Some(reason) = reason {
+            tracing::trace!(target: LOG_TARGET, ?source, ?destination, ?reason, "circuit closed");
+
+            let _ = self
+                .tx
+                .send(RelayEvent::CircuitClosed {
+                    source,
+                    destination,
+                    reason,
+                })
+                .await;
+        }
+    }
+
+    /// Start [`Relay`] event loop.
+    pub async fn run(mut self) {
+        tracing::debug!(target: LOG_TARGET, "starting relay event loop");
+
+        loop {
+            tokio::select! {
+                event = self.hop_service.next() => match event {
+                    Some(TransportEvent::ConnectionClosed { peer }) => {
+                        self.reservations.remove(&peer);
+                    }
+                    Some(TransportEvent::SubstreamOpened {
+                        peer,
+                        substream,
+                        direction: Direction::Inbound,
+                        ..
+                    }) => {
+                        self.on_inbound_substream(peer, substream);
+                    }
+                    Some(_) => {}
+                    None => return,
+                },
+                event = self.stop_service.next() => match event {
+                    Some(TransportEvent::SubstreamOpened {
+                        peer,
+                        substream,
+                        direction,
+                        ..
+                    }) => match direction {
+                        Direction::Outbound(substream_id) => {
+                            self.on_stop_substream(substream_id, substream);
+                        }
+                        Direction::Inbound => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                "relay doesn't accept inbound stop substreams",
+                            );
+                        }
+                    },
+                    Some(TransportEvent::SubstreamOpenFailure { substream, error }) => {
+                        if let Some((source, destination, hop)) =
+                            self.pending_stops.remove(&substream)
+                        {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?source,
+                                ?destination,
+                                ?error,
+                                "failed to open stop substream",
+                            );
+
+                            self.release_circuit(&source);
+                            self.respond(hop, hop_status(schema::Status::ConnectionFailed));
+                        }
+                    }
+                    Some(_) => {}
+                    None => return,
+                },
+                event = self.pending_requests.next(), if !self.pending_requests.is_empty() => {
+                    match event {
+                        Some((peer, substream, Ok(message))) => {
+                            self.on_request(peer, substream, message).await;
+                        }
+                        Some((peer, _, Err(error))) => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                ?error,
+                                "failed to read hop request",
+                            );
+                        }
+                        None => {}
+                    }
+                }
+                _ = self.pending_responses.next(), if !self.pending_responses.is_empty() => {}
+                event = self.circuits.next(), if !self.circuits.is_empty() => {
+                    if let Some((source, destination, reason)) = event {
+                        self.on_circuit_closed(source, destination, reason).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::ProtocolCodec;
+    use tokio::{io::DuplexStream, sync::mpsc::channel};
+
+    fn make_substream(io: DuplexStream) -> Substream {
+        Substream::new_duplex(
+            PeerId::random(),
+            SubstreamId::from(0usize),
+            io,
+            ProtocolCodec::Unspecified,
+        )
+    }
+
+    #[test]
+    fn reservations_are_limited_and_expire() {
+        let (peer1, peer2) = (PeerId::random(), PeerId::random());
+        let mut reservations = Reservations::new(1, Duration::from_secs(10));
+        let now = Instant::now();
+
+        assert!(reservations.reserve(peer1, now).is_some());
+        assert!(reservations.is_reserved(&peer1, now));
+
+        // all slots taken but existing reservations can be renewed
+        assert!(reservations.reserve(peer2, now).is_none());
+        assert!(reservations.reserve(peer1, now + Duration::from_secs(5)).is_some());
+
+        // renewed reservation is still valid after the original one would've expired
+        let later = now + Duration::from_secs(12);
+        assert!(reservations.is_reserved(&peer1, later));
+
+        // once the reservation has expired, the slot is freed
+        let expired = now + Duration::from_secs(16);
+        assert!(!reservations.is_reserved(&peer1, expired));
+        assert!(reservations.reserve(peer2, expired).is_some());
+        assert!(reservations.is_reserved(&peer2, expired));
+    }
+
+    #[tokio::test]
+    async fn circuit_closed_after_data_limit() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (source, destination) = (PeerId::random(), PeerId::random());
+        let mut reservations = Reservations::new(16, Duration::from_secs(60));
+        assert!(reservations.reserve(destination, Instant::now()).is_some());
+        assert!(reservations.is_reserved(&destination, Instant::now()));
+
+        let (hop, mut source_io) = tokio::io::duplex(4096);
+        let (stop, mut destination_io) = tokio::io::duplex(4096);
+        let (tx, mut rx) = channel(64);
+        let limit = CircuitLimit {
+            duration: Duration::from_secs(10),
+            data: 1024,
+        };
+
+        let relay = tokio::spawn(open_circuit(
+            make_substream(hop),
+            make_substream(stop),
+            source,
+            destination,
+            limit,
+            tx,
+        ));
+
+        // destination accepts the circuit
+        let request: schema::StopMessage = read_message(&mut destination_io).await.unwrap();
+        assert_eq!(request.r#type, schema::stop_message::Type::Connect as i32);
+        assert_eq!(request.peer.unwrap().id, source.to_bytes());
+        assert_eq!(request.limit.unwrap().data, Some(1024));
+
+        let response = schema::StopMessage {
+            r#type: schema::stop_message::Type::Status as i32,
+            peer: None,
+            limit: None,
+            status: Some(schema::Status::Ok as i32),
+        };
+        write_message(&mut destination_io, &response).await.unwrap();
+
+        // source is informed that the circuit is open
+        let response: schema::HopMessage = read_message(&mut source_io).await.unwrap();
+        assert_eq!(response.status, Some(schema::Status::Ok as i32));
+        assert_eq!(response.limit.unwrap().data, Some(1024));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            RelayEvent::CircuitOpened {
+                source,
+                destination
+            }
+        );
+
+        // data within the limit is forwarded in both directions
+        source_io.write_all(&[1u8; 1000]).await.unwrap();
+        let mut buffer = [0u8; 1000];
+        destination_io.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, [1u8; 1000]);
+
+        destination_io.write_all(&[2u8; 16]).await.unwrap();
+        let mut buffer = [0u8; 16];
+        source_io.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, [2u8; 16]);
+
+        // only the remaining allowance is forwarded before the circuit is closed
+        source_io.write_all(&[3u8; 100]).await.unwrap();
+        let mut received = Vec::new();
+        destination_io.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, vec![3u8; 24]);
+
+        assert_eq!(
+            relay.await.unwrap(),
+            Some(CircuitCloseReason::DataLimitExceeded)
+        );
+    }
+
+    #[tokio::test]
+    async fn circuit_refused_by_destination() {
+        let (hop, mut source_io) = tokio::io::duplex(4096);
+        let (stop, mut destination_io) = tokio::io::duplex(4096);
+        let (tx, _rx) = channel(64);
+        let limit = CircuitLimit {
+            duration: Duration::from_secs(10),
+            data: 1024,
+        };
+
+        let relay = tokio::spawn(open_circuit(
+            make_substream(hop),
+            make_substream(stop),
+            PeerId::random(),
+            PeerId::random(),
+            limit,
+            tx,
+        ));
+
+        let _request: schema::StopMessage = read_message(&mut destination_io).await.unwrap();
+        let response = schema::StopMessage {
+            r#type: schema::stop_message::Type::Status as i32,
+            peer: None,
+            limit: None,
+            status: Some(schema::Status::PermissionDenied as i32),
+        };
+        write_message(&mut destination_io, &response).await.unwrap();
+
+        let response: schema::HopMessage = read_message(&mut source_io).await.unwrap();
+        assert_eq!(
+            response.status,
+            Some(schema::Status::ConnectionFailed as i32)
+        );
+        assert_eq!(relay.await.unwrap(), None);
+    }
+}
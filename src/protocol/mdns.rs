@@ -335,8 +335,10 @@ impl Mdns {
 mod tests {
     use super::*;
     use crate::{
-        crypto::ed25519::Keypair,
-        transport::manager::{limits::ConnectionLimitsConfig, TransportManager},
+        crypto::Keypair,
+        transport::manager::{
+            limits::ConnectionLimitsConfig, reputation::ReputationConfig, TransportManager,
+        },
         BandwidthSink,
     };
     use futures::StreamExt;
@@ -350,11 +352,12 @@ mod tests {
 
         let (config1, mut stream1) = Config::new(Duration::from_secs(5));
         let (_manager1, handle1) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         let mdns1 = Mdns::new(
@@ -373,11 +376,12 @@ mod tests {
 
         let (config2, mut stream2) = Config::new(Duration::from_secs(5));
         let (_manager1, handle2) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         let mdns2 = Mdns::new(
@@ -31,9 +31,10 @@ use crate::{
 
 use multiaddr::Multiaddr;
 
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
-pub(crate) use connection::Permit;
+pub(crate) use connection::{ConnectionHandle, Permit};
+pub(crate) use negotiation_cache::{NegotiationCache, OptimisticProtocol};
 pub(crate) use protocol_set::{InnerTransportEvent, ProtocolCommand, ProtocolSet};
 
 pub use transport_service::TransportService;
@@ -44,6 +45,7 @@ pub mod notification;
 pub mod request_response;
 
 mod connection;
+mod negotiation_cache;
 mod protocol_set;
 mod transport_service;
 
@@ -57,6 +59,156 @@ pub enum Direction {
     Outbound(SubstreamId),
 }
 
+/// Synchronous filter for inbound substreams.
+///
+/// The filter is called with the remote peer and the negotiated protocol name for each inbound
+/// substream of the protocol before the substream is given to the protocol. If the filter returns
+/// `false`, the substream is closed without reading any data from it.
+///
+/// This allows the protocol to reject substreams based on cheap criteria, such as the number of
+/// substreams already open to the peer, without reading the handshake or request first. The filter
+/// is called from the connection's event loop so it must not block.
+#[derive(Clone)]
+pub struct InboundSubstreamFilter(Arc<dyn Fn(&PeerId, &ProtocolName) -> bool + Send + Sync>);
+
+impl InboundSubstreamFilter {
+    /// Create new [`InboundSubstreamFilter`].
+    pub fn new(filter: impl Fn(&PeerId, &ProtocolName) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(filter))
+    }
+
+    /// Check if inbound substream opened by `peer` over `protocol` should be accepted.
+    pub(crate) fn accept(&self, peer: &PeerId, protocol: &ProtocolName) -> bool {
+        (self.0)(peer, protocol)
+    }
+}
+
+impl Debug for InboundSubstreamFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InboundSubstreamFilter").finish_non_exhaustive()
+    }
+}
+
+/// Priority of the substreams of a protocol.
+///
+/// When the substreams of a connection have queued more data than the connection can send right
+/// away, the data of higher priority substreams is sent first, so that, for example, requests
+/// don't wait behind a burst of notifications. Data of substreams with the same priority is sent
+/// in the order it was written.
+///
+/// The priority is applied by the stream multiplexer of the connection, which means it has an
+/// effect on TCP, WebSocket and QUIC connections.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SubstreamPriority {
+    /// Low priority.
+    Low,
+
+    /// Normal priority.
+    ///
+    /// Default for notification protocols and user protocols.
+    #[default]
+    Normal,
+
+    /// High priority.
+    ///
+    /// Default for request-response protocols.
+    High,
+}
+
+impl SubstreamPriority {
+    /// Get the priority as understood by the stream multiplexers, `0` being the normal priority.
+    pub(crate) fn value(self) -> i8 {
+        match self {
+            Self::Low => -1,
+            Self::Normal => 0,
+            Self::High => 1,
+        }
+    }
+}
+
+/// Order in which queued inbound substreams of a protocol are delivered to it.
+///
+/// Inbound substreams are queued by the connection when they're negotiated faster than the
+/// protocol reads them from its event channel. The order has no effect as long as the protocol
+/// keeps up with the inbound substreams.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InboundSubstreamOrder {
+    /// Deliver the oldest queued substream first.
+    #[default]
+    Fifo,
+
+    /// Deliver the most recently negotiated substream first.
+    ///
+    /// Useful for protocols where only the freshest requests are worth answering. If the queue
+    /// grows too large, the oldest substreams are closed.
+    Lifo,
+}
+
+/// Outcome of a `multistream-select` negotiation for a substream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negotiation {
+    /// Remote peer ID.
+    pub peer: PeerId,
+
+    /// Substream direction.
+    pub direction: Direction,
+
+    /// Protocols offered by the local node.
+    ///
+    /// For outbound substreams these are the proposed protocol and its fallbacks, in the order
+    /// they were proposed. For inbound substreams these are all protocols the local node supports.
+    pub offered: Vec<ProtocolName>,
+
+    /// Protocol selected by the negotiation or `None` if the negotiation failed.
+    pub selected: Option<ProtocolName>,
+
+    /// Protocols proposed by the remote peer which the local node doesn't support.
+    ///
+    /// Only reported for inbound substreams. A non-empty list means the remote peer tried to
+    /// open a substream for a protocol that isn't installed, for example because the peers run
+    /// different versions of the protocol.
+    pub unsupported: Vec<ProtocolName>,
+}
+
+/// Observer of `multistream-select` negotiations.
+///
+/// The observer is called for each substream negotiation over any transport once the negotiation
+/// has concluded, successfully or not. It is meant for debugging interoperability issues, such as
+/// mismatching protocol names, and it must not block as it's called from the connection's tasks.
+#[derive(Clone)]
+pub struct NegotiationObserver(Arc<dyn Fn(&Negotiation) + Send + Sync>);
+
+impl NegotiationObserver {
+    /// Create new [`NegotiationObserver`].
+    pub fn new(observer: impl Fn(&Negotiation) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(observer))
+    }
+
+    /// Report concluded negotiation to the observer.
+    pub(crate) fn observe(
+        &self,
+        peer: PeerId,
+        direction: Direction,
+        offered: Vec<ProtocolName>,
+        selected: Option<ProtocolName>,
+        unsupported: Vec<ProtocolName>,
+    ) {
+        (self.0)(&Negotiation {
+            peer,
+            direction,
+            offered,
+            selected,
+            unsupported,
+        })
+    }
+}
+
+impl Debug for NegotiationObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NegotiationObserver").finish_non_exhaustive()
+    }
+}
+
 /// Events emitted by one of the installed transports to protocol(s).
 #[derive(Debug)]
 pub enum TransportEvent {
@@ -86,6 +238,23 @@ pub enum TransportEvent {
         address: Multiaddr,
     },
 
+    /// Remote peer of an open connection has changed its address.
+    ///
+    /// Reported by transports which support connection migration, such as QUIC, when the remote
+    /// peer has moved to a new network path without closing the connection. Substreams open over
+    /// the connection stay open. Protocols which don't track peer addresses can ignore the event.
+    ///
+    /// Also reported when a direct connection is opened to a peer that was connected only over a
+    /// relay. New substreams are opened over the direct connection and the relayed connection is
+    /// closed once the substreams open over it have been closed.
+    ConnectionAddressChanged {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Updated endpoint of the connection.
+        endpoint: Endpoint,
+    },
+
     /// Substream opened for `peer`.
     SubstreamOpened {
         /// Peer ID.
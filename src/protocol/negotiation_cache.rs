@@ -0,0 +1,156 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Per-peer cache of negotiated protocols.
+
+use crate::{multistream_select::NegotiationError, types::protocol::ProtocolName, PeerId};
+
+use parking_lot::RwLock;
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    sync::Arc,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::protocol::negotiation-cache";
+
+/// Cache of protocols which have been successfully negotiated with each peer.
+///
+/// When an outbound substream is opened for a protocol the peer is known to support, the protocol
+/// is proposed alone with `V1Lazy` negotiation which allows the substream to be used before the
+/// remote peer has confirmed the protocol, saving one round trip. Protocols the remote peer
+/// rejects are removed from the cache and the next substream is negotiated normally.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NegotiationCache(Arc<RwLock<HashMap<PeerId, HashSet<ProtocolName>>>>);
+
+impl NegotiationCache {
+    /// Create new [`NegotiationCache`].
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the first protocol of `protocols` which has been negotiated with `peer` before.
+    pub(crate) fn select(&self, peer: &PeerId, protocols: &[ProtocolName]) -> Option<ProtocolName> {
+        let cache = self.0.read();
+        let negotiated = cache.get(peer)?;
+
+        protocols.iter().find(|protocol| negotiated.contains(*protocol)).cloned()
+    }
+
+    /// Record that `protocol` was negotiated with `peer`.
+    pub(crate) fn insert(&self, peer: PeerId, protocol: ProtocolName) {
+        self.0.write().entry(peer).or_default().insert(protocol);
+    }
+
+    /// Forget that `protocol` was negotiated with `peer`.
+    pub(crate) fn remove(&self, peer: &PeerId, protocol: &ProtocolName) {
+        let mut cache = self.0.write();
+
+        if let Some(negotiated) = cache.get_mut(peer) {
+            negotiated.remove(protocol);
+
+            if negotiated.is_empty() {
+                cache.remove(peer);
+            }
+        }
+    }
+
+    /// Forget all protocols negotiated with `peer`.
+    pub(crate) fn remove_peer(&self, peer: &PeerId) {
+        self.0.write().remove(peer);
+    }
+
+    /// Create handle for the substream which optimistically negotiated `protocol` with `peer`.
+    pub(crate) fn optimistic(&self, peer: PeerId, protocol: ProtocolName) -> OptimisticProtocol {
+        OptimisticProtocol {
+            cache: self.clone(),
+            peer,
+            protocol,
+        }
+    }
+}
+
+/// Protocol of a substream which hasn't been confirmed by the remote peer yet.
+#[derive(Debug)]
+pub(crate) struct OptimisticProtocol {
+    /// Cache the protocol was selected from.
+    cache: NegotiationCache,
+
+    /// Remote peer.
+    peer: PeerId,
+
+    /// Protocol.
+    protocol: ProtocolName,
+}
+
+impl OptimisticProtocol {
+    /// Inspect I/O error of the substream and if the remote peer rejected the protocol, remove the
+    /// protocol from the cache.
+    pub(crate) fn on_error(&self, error: &io::Error) {
+        let rejected = error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<NegotiationError>())
+            .is_some_and(|error| std::matches!(error, NegotiationError::Failed));
+
+        if rejected {
+            tracing::debug!(
+                target: LOG_TARGET,
+                peer = ?self.peer,
+                protocol = %self.protocol,
+                "optimistically negotiated protocol rejected",
+            );
+
+            self.cache.remove(&self.peer, &self.protocol);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejected_protocol_removed() {
+        let cache = NegotiationCache::new();
+        let peer = PeerId::random();
+        let protocols = vec![
+            ProtocolName::from("/proto/2"),
+            ProtocolName::from("/proto/1"),
+        ];
+
+        assert_eq!(cache.select(&peer, &protocols), None);
+
+        cache.insert(peer, ProtocolName::from("/proto/1"));
+        assert_eq!(
+            cache.select(&peer, &protocols),
+            Some(ProtocolName::from("/proto/1"))
+        );
+
+        // unrelated errors don't invalidate the protocol
+        let optimistic = cache.optimistic(peer, ProtocolName::from("/proto/1"));
+        optimistic.on_error(&io::ErrorKind::BrokenPipe.into());
+        assert!(cache.select(&peer, &protocols).is_some());
+
+        optimistic.on_error(&NegotiationError::Failed.into());
+        assert_eq!(cache.select(&peer, &protocols), None);
+    }
+}
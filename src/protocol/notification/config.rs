@@ -20,13 +20,18 @@
 
 use crate::{
     codec::ProtocolCodec,
-    protocol::notification::{
-        handle::NotificationHandle,
-        types::{
-            InnerNotificationEvent, NotificationCommand, ASYNC_CHANNEL_SIZE, SYNC_CHANNEL_SIZE,
+    protocol::{
+        notification::{
+            handle::NotificationHandle,
+            types::{
+                Compression, InnerNotificationEvent, NegotiationDuration, NotificationCommand,
+                PeerStateObserver, ProtocolVariant, QueuePolicy, StateCounts, ASYNC_CHANNEL_SIZE,
+                OUTBOUND_QUEUE_SIZE, SYNC_CHANNEL_SIZE,
+            },
         },
+        InboundSubstreamFilter, InboundSubstreamOrder, SubstreamPriority,
     },
-    types::protocol::ProtocolName,
+    types::protocol::{ProtocolName, VersionedProtocol},
     PeerId, DEFAULT_CHANNEL_SIZE,
 };
 
@@ -34,7 +39,11 @@ use bytes::BytesMut;
 use parking_lot::RwLock;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
 
 /// Notification configuration.
 #[derive(Debug)]
@@ -46,14 +55,36 @@ pub struct Config {
     pub(crate) codec: ProtocolCodec,
 
     /// Maximum notification size.
-    _max_notification_size: usize,
+    pub(crate) max_notification_size: usize,
 
     /// Handshake bytes.
     pub(crate) handshake: Arc<RwLock<Vec<u8>>>,
 
+    /// Is the protocol paused, shared with [`NotificationHandle`].
+    pub(crate) paused: Arc<AtomicBool>,
+
+    /// Number of peers in each state, shared with [`NotificationHandle`].
+    pub(crate) state_counts: Arc<RwLock<StateCounts>>,
+
+    /// Stalled peers and the time they stalled, shared with [`NotificationHandle`].
+    pub(crate) stalled_peers: Arc<RwLock<HashMap<PeerId, Instant>>>,
+
+    /// Negotiation durations of the open notification streams, shared with
+    /// [`NotificationHandle`].
+    pub(crate) negotiation_durations: Arc<RwLock<HashMap<PeerId, NegotiationDuration>>>,
+
+    /// Maximum size of the handshake received from remote peer.
+    pub(crate) max_handshake_size: Option<usize>,
+
+    /// Size of the handshake if it's sent without a length prefix.
+    pub(crate) fixed_handshake_size: Option<usize>,
+
     /// Auto accept inbound substream.
     pub(super) auto_accept: bool,
 
+    /// Auto accept all inbound substreams without validation.
+    pub(super) auto_accept_all_inbound: bool,
+
     /// Protocol aliases.
     pub(crate) fallback_names: Vec<ProtocolName>,
 
@@ -72,9 +103,48 @@ pub struct Config {
     /// Asynchronous channel size.
     pub(crate) async_channel_size: usize,
 
+    /// Outbound queue size.
+    pub(crate) outbound_queue_size: usize,
+
+    /// Policy applied to notifications when the outbound queue is full.
+    pub(crate) outbound_queue_policy: QueuePolicy,
+
     /// Should `NotificationProtocol` dial the peer if there is no connection to them
     /// when an outbound substream is requested.
     pub(crate) should_dial: bool,
+
+    /// Should idle substreams of the protocol keep the connection open.
+    pub(crate) substream_keep_alive: bool,
+
+    /// Priority of the substreams of the protocol.
+    pub(crate) substream_priority: SubstreamPriority,
+
+    /// Is the protocol latency-sensitive.
+    pub(crate) latency_sensitive: bool,
+
+    /// Order in which queued inbound substreams are delivered to the protocol.
+    pub(crate) inbound_substream_order: InboundSubstreamOrder,
+
+    /// Filter for inbound substreams.
+    pub(crate) inbound_substream_filter: Option<InboundSubstreamFilter>,
+
+    /// Compression applied to notification payloads, if enabled.
+    pub(crate) compression: Option<Compression>,
+
+    /// Observer of peer state transitions, if enabled.
+    pub(crate) peer_state_observer: Option<PeerStateObserver>,
+
+    /// Maximum number of unacknowledged notifications, if credit-based flow control is enabled.
+    pub(crate) credit_window: Option<usize>,
+
+    /// Peers allowed to open inbound substreams, if restricted.
+    pub(crate) allowed_peers: Option<HashSet<PeerId>>,
+
+    /// Duration after which stalled peers are closed, if enabled.
+    pub(crate) stall_timeout: Option<Duration>,
+
+    /// Peers whose notification streams are re-opened when they're closed.
+    pub(crate) reserved_peers: HashSet<PeerId>,
 }
 
 impl Config {
@@ -93,16 +163,35 @@ impl Config {
         let (notif_tx, notif_rx) = channel(DEFAULT_CHANNEL_SIZE);
         let (command_tx, command_rx) = channel(DEFAULT_CHANNEL_SIZE);
         let handshake = Arc::new(RwLock::new(handshake));
-        let handle =
-            NotificationHandle::new(event_rx, notif_rx, command_tx, Arc::clone(&handshake));
+        let paused = Arc::new(AtomicBool::new(false));
+        let state_counts = Arc::new(RwLock::new(StateCounts::default()));
+        let stalled_peers = Arc::new(RwLock::new(HashMap::new()));
+        let negotiation_durations = Arc::new(RwLock::new(HashMap::new()));
+        let handle = NotificationHandle::new(
+            event_rx,
+            notif_rx,
+            command_tx,
+            Arc::clone(&handshake),
+            Arc::clone(&paused),
+            Arc::clone(&state_counts),
+            Arc::clone(&stalled_peers),
+            Arc::clone(&negotiation_durations),
+        );
 
         (
             Self {
                 protocol_name,
                 codec: ProtocolCodec::UnsignedVarint(Some(max_notification_size)),
-                _max_notification_size: max_notification_size,
+                max_notification_size,
                 auto_accept,
+                auto_accept_all_inbound: false,
                 handshake,
+                paused,
+                state_counts,
+                stalled_peers,
+                negotiation_durations,
+                max_handshake_size: None,
+                fixed_handshake_size: None,
                 fallback_names,
                 event_tx,
                 notif_tx,
@@ -110,6 +199,19 @@ impl Config {
                 should_dial,
                 sync_channel_size,
                 async_channel_size,
+                outbound_queue_size: OUTBOUND_QUEUE_SIZE,
+                outbound_queue_policy: QueuePolicy::Block,
+                substream_keep_alive: true,
+                substream_priority: SubstreamPriority::Normal,
+                latency_sensitive: false,
+                inbound_substream_order: InboundSubstreamOrder::Fifo,
+                inbound_substream_filter: None,
+                compression: None,
+                peer_state_observer: None,
+                credit_window: None,
+                allowed_peers: None,
+                stall_timeout: None,
+                reserved_peers: HashSet::new(),
             },
             handle,
         )
@@ -120,6 +222,52 @@ impl Config {
         &self.protocol_name
     }
 
+    /// Get the variants of the protocol, in order of preference.
+    ///
+    /// Compression and credit-based flow control are negotiated by proposing variants of the
+    /// protocol name with the corresponding suffixes before the plain protocol name, which is
+    /// always the last variant.
+    pub(crate) fn protocol_variants(&self) -> Vec<(ProtocolName, ProtocolVariant)> {
+        let credit = self.credit_window.map(|_| true).into_iter().chain([false]);
+        let compression = self.compression.map(Some).into_iter().chain([None]);
+
+        credit
+            .flat_map(|credit| compression.clone().map(move |compression| (credit, compression)))
+            .map(|(credit, compression)| {
+                let name = match compression {
+                    Some(compression) => compression.protocol_name(&self.protocol_name),
+                    None => self.protocol_name.clone(),
+                };
+                let name = match credit {
+                    true => ProtocolName::from(format!("{name}/credit")),
+                    false => name,
+                };
+
+                (
+                    name,
+                    ProtocolVariant {
+                        compressed: compression.is_some(),
+                        credit,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Get the protocol name and fallback names the protocol is negotiated with.
+    ///
+    /// The most preferred variant of the protocol is the main protocol and the other variants are
+    /// the first fallbacks, followed by the configured fallback names.
+    pub(crate) fn negotiated_protocols(&self) -> (ProtocolName, Vec<ProtocolName>) {
+        let mut names = self.protocol_variants().into_iter().map(|(name, _)| name);
+        let protocol = names.next().expect("plain protocol to exist");
+
+        (
+            protocol,
+            names.chain(self.fallback_names.iter().cloned()).collect(),
+        )
+    }
+
     /// Set handshake for the protocol.
     ///
     /// This function is used to work around an issue in Polkadot SDK and users
@@ -141,6 +289,12 @@ pub struct ConfigBuilder {
     /// Handshake bytes.
     handshake: Option<Vec<u8>>,
 
+    /// Maximum handshake size.
+    max_handshake_size: Option<usize>,
+
+    /// Fixed handshake size.
+    fixed_handshake_size: Option<usize>,
+
     /// Should `NotificationProtocol` dial the peer if an outbound substream is requested but there
     /// is no connection to the peer.
     should_dial: bool,
@@ -151,11 +305,53 @@ pub struct ConfigBuilder {
     /// Auto accept inbound substream.
     auto_accept_inbound_for_initiated: bool,
 
+    /// Auto accept all inbound substreams.
+    auto_accept_all_inbound: bool,
+
     /// Synchronous channel size.
     sync_channel_size: usize,
 
     /// Asynchronous channel size.
     async_channel_size: usize,
+
+    /// Outbound queue size.
+    outbound_queue_size: usize,
+
+    /// Policy applied to notifications when the outbound queue is full.
+    outbound_queue_policy: QueuePolicy,
+
+    /// Should idle substreams of the protocol keep the connection open.
+    substream_keep_alive: bool,
+
+    /// Priority of the substreams of the protocol.
+    substream_priority: SubstreamPriority,
+
+    /// Is the protocol latency-sensitive.
+    latency_sensitive: bool,
+
+    /// Order in which queued inbound substreams are delivered to the protocol.
+    inbound_substream_order: InboundSubstreamOrder,
+
+    /// Filter for inbound substreams.
+    inbound_substream_filter: Option<InboundSubstreamFilter>,
+
+    /// Compression applied to notification payloads.
+    compression: Option<Compression>,
+
+    /// Observer of peer state transitions.
+    peer_state_observer: Option<PeerStateObserver>,
+
+    /// Maximum number of unacknowledged notifications.
+    credit_window: Option<usize>,
+
+    /// Peers allowed to open inbound substreams.
+    allowed_peers: Option<HashSet<PeerId>>,
+
+    /// Duration after which stalled peers are closed.
+    stall_timeout: Option<Duration>,
+
+    /// Peers whose notification streams are re-opened when they're closed.
+    reserved_peers: HashSet<PeerId>,
 }
 
 impl ConfigBuilder {
@@ -165,14 +361,41 @@ impl ConfigBuilder {
             protocol_name,
             max_notification_size: None,
             handshake: None,
+            max_handshake_size: None,
+            fixed_handshake_size: None,
             fallback_names: Vec::new(),
             auto_accept_inbound_for_initiated: false,
+            auto_accept_all_inbound: false,
             sync_channel_size: SYNC_CHANNEL_SIZE,
             async_channel_size: ASYNC_CHANNEL_SIZE,
+            outbound_queue_size: OUTBOUND_QUEUE_SIZE,
+            outbound_queue_policy: QueuePolicy::Block,
             should_dial: true,
+            substream_keep_alive: true,
+            substream_priority: SubstreamPriority::Normal,
+            latency_sensitive: false,
+            inbound_substream_order: InboundSubstreamOrder::Fifo,
+            inbound_substream_filter: None,
+            compression: None,
+            peer_state_observer: None,
+            credit_window: None,
+            allowed_peers: None,
+            stall_timeout: None,
+            reserved_peers: HashSet::new(),
         }
     }
 
+    /// Create new [`ConfigBuilder`] for a protocol that supports a range of versions.
+    ///
+    /// The highest version is used as the protocol name and the older versions as fallback
+    /// names, so the highest version supported by both peers is negotiated. The negotiated
+    /// version can be resolved with [`VersionedProtocol::version()`].
+    pub fn new_versioned(protocol: &VersionedProtocol) -> Self {
+        let (protocol_name, fallback_names) = protocol.names();
+
+        Self::new(protocol_name).with_fallback_names(fallback_names)
+    }
+
     /// Set maximum notification size.
     pub fn with_max_size(mut self, max_notification_size: usize) -> Self {
         self.max_notification_size = Some(max_notification_size);
@@ -185,6 +408,30 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set maximum size of the handshake received from remote peer.
+    ///
+    /// Inbound and outbound substreams whose handshake exceeds the limit are closed and
+    /// [`NotificationError::HandshakeTooLarge`](super::types::NotificationError::HandshakeTooLarge)
+    /// is reported if the user had opened or accepted the substream. The limit is enforced using
+    /// the length prefix of the handshake so the oversized handshake is never buffered.
+    ///
+    /// By default the handshake is only limited by the maximum notification size.
+    pub fn with_max_handshake_size(mut self, max_handshake_size: usize) -> Self {
+        self.max_handshake_size = Some(max_handshake_size);
+        self
+    }
+
+    /// Send and read the handshake as exactly `handshake_size` bytes without a length prefix.
+    ///
+    /// The local handshake must be exactly `handshake_size` bytes long and the substream is only
+    /// considered open once `handshake_size` bytes have been read from the remote peer. If the
+    /// remote closes the substream before sending a full handshake, the substream is rejected.
+    /// Notifications sent after the handshake are framed normally.
+    pub fn with_fixed_size_handshake(mut self, handshake_size: usize) -> Self {
+        self.fixed_handshake_size = Some(handshake_size);
+        self
+    }
+
     /// Set fallback names.
     pub fn with_fallback_names(mut self, fallback_names: Vec<ProtocolName>) -> Self {
         self.fallback_names = fallback_names;
@@ -216,6 +463,19 @@ impl ConfigBuilder {
         self
     }
 
+    /// Auto-accept all inbound substreams, regardless of which node initiated the connection.
+    ///
+    /// When enabled, inbound substreams are never reported to the user for validation: the local
+    /// handshake is sent right away and, once both substreams have been negotiated, the user is
+    /// only notified via
+    /// [`NotificationStreamOpened`](super::types::NotificationEvent::NotificationStreamOpened).
+    ///
+    /// Disabled by default.
+    pub fn with_auto_accept_all_inbound(mut self, auto_accept: bool) -> Self {
+        self.auto_accept_all_inbound = auto_accept;
+        self
+    }
+
     /// Configure size of the channel for sending synchronous notifications.
     ///
     /// Default value is `16`.
@@ -232,6 +492,21 @@ impl ConfigBuilder {
         self
     }
 
+    /// Configure the outbound notification queue of each peer.
+    ///
+    /// Notifications sent to a peer are moved from the notification channels to the outbound
+    /// queue of the peer where they wait to be written to the substream. If the queue holds `size`
+    /// notifications, `policy` decides what happens to the next notification. Notifications
+    /// discarded because of the policy are counted by
+    /// [`NotificationSink::dropped_notifications()`](super::NotificationSink::dropped_notifications).
+    ///
+    /// By default the queue holds one notification and the policy is [`QueuePolicy::Block`].
+    pub fn with_outbound_queue(mut self, size: usize, policy: QueuePolicy) -> Self {
+        self.outbound_queue_size = size;
+        self.outbound_queue_policy = policy;
+        self
+    }
+
     /// Should `NotificationProtocol` attempt to dial the peer if an outbound substream is opened
     /// but no connection to the peer exist.
     ///
@@ -241,9 +516,143 @@ impl ConfigBuilder {
         self
     }
 
+    /// Should idle substreams of the protocol keep the connection open.
+    ///
+    /// If disabled, a notification substream keeps the connection open only while notifications
+    /// are sent or received over it. Once the substream has been idle for the keep-alive timeout
+    /// and no other protocol is keeping the connection open, the connection is closed even though
+    /// the substream is still open. The substreams are closed along with the connection.
+    ///
+    /// Substreams keep the connection open by default.
+    pub fn with_substream_keep_alive(mut self, keep_alive: bool) -> Self {
+        self.substream_keep_alive = keep_alive;
+        self
+    }
+
+    /// Set the priority of the protocol's substreams in the connection's stream multiplexer.
+    ///
+    /// Notifications are sent with [`SubstreamPriority::Normal`] by default, meaning that they
+    /// wait behind the requests and responses of request-response protocols.
+    pub fn with_substream_priority(mut self, priority: SubstreamPriority) -> Self {
+        self.substream_priority = priority;
+        self
+    }
+
+    /// Mark the protocol as latency-sensitive.
+    ///
+    /// Nagle's algorithm is disabled (`TCP_NODELAY`) for the TCP and WebSocket connections of a
+    /// node which has at least one latency-sensitive protocol installed so that small
+    /// notifications are not held back by the kernel while it waits for more data.
+    pub fn with_latency_sensitive(mut self, latency_sensitive: bool) -> Self {
+        self.latency_sensitive = latency_sensitive;
+        self
+    }
+
+    /// Set the order in which inbound substreams are delivered to the protocol if they arrive
+    /// faster than the protocol processes them.
+    ///
+    /// Defaults to [`InboundSubstreamOrder::Fifo`].
+    pub fn with_inbound_substream_order(mut self, order: InboundSubstreamOrder) -> Self {
+        self.inbound_substream_order = order;
+        self
+    }
+
+    /// Set synchronous filter for inbound substreams.
+    ///
+    /// The filter is called for each inbound substream before its handshake is read and if it
+    /// returns `false`, the substream is closed without the substream being reported to the
+    /// protocol. See [`InboundSubstreamFilter`] for more details.
+    ///
+    /// By default all inbound substreams are validated by the user.
+    pub fn with_inbound_substream_filter(mut self, filter: InboundSubstreamFilter) -> Self {
+        self.inbound_substream_filter = Some(filter);
+        self
+    }
+
+    /// Set observer which is called each time a peer transitions to a new state.
+    ///
+    /// Meant for diagnosing the protocol's state machine. See [`PeerStateObserver`] for more
+    /// details.
+    ///
+    /// Disabled by default.
+    pub fn with_peer_state_observer(mut self, observer: PeerStateObserver) -> Self {
+        self.peer_state_observer = Some(observer);
+        self
+    }
+
+    /// Compress notification payloads using `compression`.
+    ///
+    /// The compressed variant of the protocol is proposed before the plain protocol and
+    /// notifications are compressed only over substreams where the remote node accepted it.
+    /// Compression is transparent to the user: sent and received notifications are always
+    /// uncompressed.
+    ///
+    /// Compression is disabled by default.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Enable credit-based flow control, allowing at most `window` unacknowledged notifications.
+    ///
+    /// Credit-based flow control is negotiated by proposing the protocol name with the `/credit`
+    /// suffix before the plain protocol name. Over substreams where both nodes agreed to it, the
+    /// sender only sends notifications for which the receiver has granted credit, keeping the
+    /// remaining notifications in the outbound queue of the peer. The receiver grants `window`
+    /// notifications worth of credit when the substream opens and grants more as the user reads
+    /// the received notifications from [`NotificationHandle`], so notifications the user isn't
+    /// keeping up with are never buffered by the receiver beyond `window`.
+    ///
+    /// A `window` of zero is treated as one. Disabled by default.
+    pub fn with_credit(mut self, window: usize) -> Self {
+        self.credit_window = Some(window.max(1));
+        self
+    }
+
+    /// Only accept inbound substreams from `peers`.
+    ///
+    /// Inbound substreams opened by any other peer are closed before their handshake is read,
+    /// without the user being asked to validate them, and the remote observes the open as
+    /// [`NotificationError::Rejected`](super::types::NotificationError::Rejected). Outbound
+    /// substreams are not affected.
+    ///
+    /// By default inbound substreams are accepted from all peers.
+    pub fn with_allowed_peers(mut self, peers: HashSet<PeerId>) -> Self {
+        self.allowed_peers = Some(peers);
+        self
+    }
+
+    /// Close the substreams of peers which have been unable to make send progress for longer
+    /// than `timeout`.
+    ///
+    /// A peer is stalled while it has notifications waiting to be sent but none of them can be
+    /// written to or flushed from its outbound substream, for example because the remote node
+    /// stopped reading. Closing a stalled peer is reported as
+    /// [`NotificationEvent::NotificationStreamClosed`](super::types::NotificationEvent).
+    ///
+    /// Stalled peers are reported by [`NotificationHandle::stalled_peers()`] regardless of this
+    /// setting but by default they're not closed.
+    pub fn with_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Set reserved peers.
+    ///
+    /// If the notification stream of a reserved peer is closed while the connection to the peer
+    /// stays open, for example because the remote node closed its substream, the stream is
+    /// re-opened automatically. A successful re-open is reported as
+    /// [`NotificationEvent::NotificationStreamReopened`](super::types::NotificationEvent) instead
+    /// of a closed and an opened event and if the re-open fails, the stream is reported as
+    /// closed.
+    pub fn with_reserved_peers(mut self, peers: HashSet<PeerId>) -> Self {
+        self.reserved_peers = peers;
+        self
+    }
+
     /// Build notification configuration.
     pub fn build(mut self) -> (Config, NotificationHandle) {
-        Config::new(
+        let (mut config, handle) = Config::new(
             self.protocol_name,
             self.max_notification_size.take().expect("notification size to be specified"),
             self.handshake.take().expect("handshake to be specified"),
@@ -252,6 +661,31 @@ impl ConfigBuilder {
             self.sync_channel_size,
             self.async_channel_size,
             self.should_dial,
-        )
+        );
+        config.max_handshake_size = self.max_handshake_size;
+        config.fixed_handshake_size = self.fixed_handshake_size;
+        config.auto_accept_all_inbound = self.auto_accept_all_inbound;
+        config.substream_keep_alive = self.substream_keep_alive;
+        config.substream_priority = self.substream_priority;
+        config.latency_sensitive = self.latency_sensitive;
+        config.inbound_substream_order = self.inbound_substream_order;
+        config.outbound_queue_size = self.outbound_queue_size;
+        config.outbound_queue_policy = self.outbound_queue_policy;
+        config.inbound_substream_filter = self.inbound_substream_filter;
+        config.peer_state_observer = self.peer_state_observer;
+        config.credit_window = self.credit_window;
+        config.allowed_peers = self.allowed_peers;
+        config.stall_timeout = self.stall_timeout;
+        config.reserved_peers = self.reserved_peers;
+
+        if let Some(compression) = self.compression {
+            // compressed payload of an incompressible notification can exceed the original size
+            config.codec = ProtocolCodec::UnsignedVarint(Some(
+                compression.max_compressed_len(config.max_notification_size),
+            ));
+            config.compression = Some(compression);
+        }
+
+        (config, handle)
     }
 }
@@ -19,25 +19,138 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    protocol::notification::handle::NotificationEventHandle, substream::Substream, PeerId,
+    protocol::notification::{
+        handle::NotificationEventHandle,
+        types::{Compression, QueuePolicy},
+    },
+    substream::Substream,
+    PeerId,
 };
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use futures::{FutureExt, SinkExt, Stream, StreamExt};
-use tokio::sync::{
-    mpsc::{Receiver, Sender},
-    oneshot,
+use parking_lot::RwLock;
+use tokio::{
+    sync::{
+        mpsc::{Receiver, Sender, UnboundedReceiver},
+        oneshot,
+    },
+    time::Sleep,
 };
 use tokio_util::sync::PollSender;
 
 use std::{
+    collections::{HashMap, VecDeque},
     pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::notification::connection";
 
+/// Notification and an optional oneshot sender signaled once the notification has been flushed.
+type QueuedNotification = (Vec<u8>, Option<oneshot::Sender<()>>);
+
+/// Queue of notifications waiting to be written to the outbound substream.
+pub(crate) struct OutboundQueue {
+    /// Queued notifications.
+    notifications: VecDeque<QueuedNotification>,
+
+    /// Maximum number of queued notifications.
+    size: usize,
+
+    /// Policy applied to new notifications when the queue is full.
+    policy: QueuePolicy,
+
+    /// Number of discarded notifications, shared with the
+    /// [`NotificationSink`](super::NotificationSink).
+    dropped: Arc<AtomicUsize>,
+}
+
+impl OutboundQueue {
+    /// Create new [`OutboundQueue`].
+    pub(crate) fn new(size: usize, policy: QueuePolicy, dropped: Arc<AtomicUsize>) -> Self {
+        Self {
+            notifications: VecDeque::with_capacity(size),
+            size: size.max(1),
+            policy,
+            dropped,
+        }
+    }
+
+    /// Check if the queue accepts new notifications.
+    ///
+    /// A full queue only accepts new notifications if they can be discarded.
+    fn is_accepting(&self) -> bool {
+        self.notifications.len() < self.size || self.policy != QueuePolicy::Block
+    }
+
+    /// Push `notification` to the queue, applying the queue policy if the queue is full.
+    ///
+    /// Discarding a notification drops its flush signal which fails the pending
+    /// [`NotificationSink::send_async_notification_flushed()`](super::NotificationSink) call.
+    fn push(&mut self, notification: QueuedNotification) {
+        if self.notifications.len() >= self.size {
+            match self.policy {
+                QueuePolicy::Block => {}
+                QueuePolicy::DropOldest => {
+                    self.notifications.pop_front();
+                    self.dropped.fetch_add(1usize, Ordering::Relaxed);
+                }
+                QueuePolicy::DropNewest => {
+                    self.dropped.fetch_add(1usize, Ordering::Relaxed);
+                    return;
+                }
+            }
+        }
+
+        self.notifications.push_back(notification);
+    }
+}
+
+/// Credit granted to the remote node for sending notifications over the inbound substream.
+pub(crate) struct InboundCredit {
+    /// Maximum number of unacknowledged notifications.
+    window: usize,
+
+    /// Credit earned by notifications read by the user but not yet granted to the remote node.
+    pending: usize,
+
+    /// RX channel for notifications read by the user.
+    read_rx: UnboundedReceiver<()>,
+}
+
+impl InboundCredit {
+    /// Create new [`InboundCredit`].
+    ///
+    /// The full window is granted to the remote node when the connection starts.
+    pub(crate) fn new(window: usize, read_rx: UnboundedReceiver<()>) -> Self {
+        Self {
+            window,
+            pending: window,
+            read_rx,
+        }
+    }
+
+    /// Poll notifications read by the user and return the credit which should be granted to the
+    /// remote node, if enough of it has been earned.
+    ///
+    /// Credit is granted in batches of half the window to avoid sending a message for each
+    /// received notification.
+    fn poll_credit(&mut self, cx: &mut Context<'_>) -> Option<usize> {
+        while let Poll::Ready(Some(())) = self.read_rx.poll_recv(cx) {
+            self.pending += 1;
+        }
+
+        (self.pending >= (self.window / 2).max(1)).then_some(self.pending)
+    }
+}
+
 /// Bidirectional substream pair representing a connection to a remote peer.
 pub(crate) struct Connection {
     /// Remote peer ID.
@@ -60,17 +173,52 @@ pub(crate) struct Connection {
     notif_tx: PollSender<(PeerId, BytesMut)>,
 
     /// Receiver for asynchronously sent notifications.
-    async_rx: Receiver<Vec<u8>>,
+    async_rx: Receiver<QueuedNotification>,
 
     /// Receiver for synchronously sent notifications.
     sync_rx: Receiver<Vec<u8>>,
 
     /// Oneshot receiver used by [`NotificationProtocol`](super::NotificationProtocol)
     /// to signal that local node wishes the close the connection.
-    rx: oneshot::Receiver<()>,
+    rx: oneshot::Receiver<NotifyProtocol>,
+
+    /// Notifications waiting to be sent.
+    queue: OutboundQueue,
+
+    /// Flush signals of notifications written to the outbound substream but not yet flushed.
+    flushing: Vec<oneshot::Sender<()>>,
+
+    /// Should the synchronous channel be polled first for the next notification.
+    sync_first: bool,
+
+    /// Compression of notifications received over the inbound substream.
+    inbound_compression: Option<Compression>,
+
+    /// Compression of notifications sent over the outbound substream.
+    outbound_compression: Option<Compression>,
+
+    /// Maximum size of a decompressed notification.
+    max_notification_size: usize,
+
+    /// Credit granted to the remote node, if the inbound substream uses credit-based flow control.
+    inbound_credit: Option<InboundCredit>,
+
+    /// Credit available for sending notifications, if the outbound substream uses credit-based
+    /// flow control.
+    outbound_credit: Option<usize>,
+
+    /// Peers unable to make send progress and the time they stalled, shared with
+    /// [`NotificationHandle`](super::NotificationHandle).
+    stalled_peers: Arc<RwLock<HashMap<PeerId, Instant>>>,
+
+    /// Time the connection stalled, if it's unable to make send progress.
+    stalled_since: Option<Instant>,
+
+    /// Duration after which a stalled connection is closed, if enabled.
+    stall_timeout: Option<Duration>,
 
-    /// Next notification to send, if any.
-    next_notification: Option<Vec<u8>>,
+    /// Timer which expires when the stalled connection should be closed.
+    stall_timer: Option<Pin<Box<Sleep>>>,
 }
 
 /// Notify [`NotificationProtocol`](super::NotificationProtocol) that the connection was closed.
@@ -81,6 +229,11 @@ pub enum NotifyProtocol {
 
     /// Do not notify protocol handler.
     No,
+
+    /// Notify the protocol handler so it re-opens the stream.
+    ///
+    /// Used when the local handshake has changed and the remote peer must validate it again.
+    Reopen,
 }
 
 impl Connection {
@@ -92,9 +245,10 @@ impl Connection {
         event_handle: NotificationEventHandle,
         conn_closed_tx: Sender<PeerId>,
         notif_tx: Sender<(PeerId, BytesMut)>,
-        async_rx: Receiver<Vec<u8>>,
+        async_rx: Receiver<QueuedNotification>,
         sync_rx: Receiver<Vec<u8>>,
-    ) -> (Self, oneshot::Sender<()>) {
+        queue: OutboundQueue,
+    ) -> (Self, oneshot::Sender<NotifyProtocol>) {
         let (tx, rx) = oneshot::channel();
 
         (
@@ -107,13 +261,100 @@ impl Connection {
                 outbound,
                 event_handle,
                 conn_closed_tx,
-                next_notification: None,
+                queue,
+                flushing: Vec::new(),
+                sync_first: true,
                 notif_tx: PollSender::new(notif_tx),
+                inbound_compression: None,
+                outbound_compression: None,
+                max_notification_size: usize::MAX,
+                inbound_credit: None,
+                outbound_credit: None,
+                stalled_peers: Default::default(),
+                stalled_since: None,
+                stall_timeout: None,
+                stall_timer: None,
             },
             tx,
         )
     }
 
+    /// Compress and decompress notifications using the compression negotiated for each substream.
+    ///
+    /// Received notifications that would exceed `max_notification_size` bytes when decompressed
+    /// close the connection.
+    pub(crate) fn with_compression(
+        mut self,
+        inbound: Option<Compression>,
+        outbound: Option<Compression>,
+        max_notification_size: usize,
+    ) -> Self {
+        self.inbound_compression = inbound;
+        self.outbound_compression = outbound;
+        self.max_notification_size = max_notification_size;
+        self
+    }
+
+    /// Use credit-based flow control for the substreams where it was negotiated.
+    ///
+    /// Over the outbound substream, notifications are sent only if the remote node has granted
+    /// credit for them and for the inbound substream, credit is granted using `inbound`.
+    pub(crate) fn with_credit(mut self, inbound: Option<InboundCredit>, outbound: bool) -> Self {
+        self.inbound_credit = inbound;
+        self.outbound_credit = outbound.then_some(0usize);
+        self
+    }
+
+    /// Record the time the connection stalled in `stalled_peers` and, if `timeout` is set, close
+    /// the connection once it has been unable to make send progress for longer than `timeout`.
+    pub(crate) fn with_stall_tracking(
+        mut self,
+        stalled_peers: Arc<RwLock<HashMap<PeerId, Instant>>>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        self.stalled_peers = stalled_peers;
+        self.stall_timeout = timeout;
+        self
+    }
+
+    /// Update the stall state of the connection.
+    ///
+    /// The connection is stalled if it has notifications to send but wasn't able to write or
+    /// flush any of them. Returns `true` if the connection has been stalled for longer than the
+    /// stall timeout.
+    fn poll_stall(&mut self, cx: &mut Context<'_>, stalled: bool) -> bool {
+        if !stalled {
+            if self.stalled_since.take().is_some() {
+                tracing::trace!(target: LOG_TARGET, peer = ?self.peer, "connection resumed");
+
+                self.stalled_peers.write().remove(&self.peer);
+                self.stall_timer = None;
+            }
+            return false;
+        }
+
+        let since = match self.stalled_since {
+            Some(since) => since,
+            None => {
+                tracing::trace!(target: LOG_TARGET, peer = ?self.peer, "connection stalled");
+
+                let now = Instant::now();
+                self.stalled_since = Some(now);
+                self.stalled_peers.write().insert(self.peer, now);
+                now
+            }
+        };
+
+        let Some(timeout) = self.stall_timeout else {
+            return false;
+        };
+        let timer = self
+            .stall_timer
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep_until((since + timeout).into())));
+
+        timer.poll_unpin(cx).is_ready()
+    }
+
     /// Connection closed, clean up state.
     ///
     /// If [`NotificationProtocol`](super::NotificationProtocol) was the one that initiated
@@ -126,14 +367,57 @@ impl Connection {
             "close notification protocol",
         );
 
+        if self.stalled_since.is_some() {
+            self.stalled_peers.write().remove(&self.peer);
+        }
+
         let _ = self.inbound.close().await;
         let _ = self.outbound.close().await;
 
-        if std::matches!(notify_protocol, NotifyProtocol::Yes) {
+        // the stream of a reserved peer, or a stream the protocol asked to be re-opened, is
+        // re-opened by the protocol once it's notified and the closure is reported to the user
+        // only if that fails
+        let reopen = match notify_protocol {
+            NotifyProtocol::Yes => self.event_handle.start_reopen(self.peer),
+            NotifyProtocol::Reopen => {
+                self.event_handle.force_reopen(self.peer);
+                true
+            }
+            NotifyProtocol::No => false,
+        };
+
+        if !std::matches!(notify_protocol, NotifyProtocol::No) {
             let _ = self.conn_closed_tx.send(self.peer).await;
         }
 
-        self.event_handle.report_notification_stream_closed(self.peer).await;
+        if !reopen {
+            self.event_handle.report_notification_stream_closed(self.peer).await;
+        }
+    }
+
+    /// Poll the next notification sent by the user.
+    ///
+    /// The channels for synchronous and asynchronous notifications are polled in turns so
+    /// neither of them can starve the other.
+    fn poll_notification(&mut self, cx: &mut Context<'_>) -> Poll<Option<QueuedNotification>> {
+        let sync_first = std::mem::replace(&mut self.sync_first, !self.sync_first);
+        let Self {
+            sync_rx, async_rx, ..
+        } = self;
+        let mut poll_sync = |cx: &mut Context<'_>| {
+            sync_rx.poll_recv(cx).map(|notification| notification.map(|n| (n, None)))
+        };
+
+        match sync_first {
+            true => match poll_sync(cx) {
+                Poll::Pending => async_rx.poll_recv(cx),
+                result => result,
+            },
+            false => match async_rx.poll_recv(cx) {
+                Poll::Pending => poll_sync(cx),
+                result => result,
+            },
+        }
     }
 
     pub async fn start(mut self) {
@@ -149,9 +433,8 @@ impl Connection {
                 | Some(ConnectionEvent::CloseConnection {
                     notify: NotifyProtocol::Yes,
                 }) => return self.close_connection(NotifyProtocol::Yes).await,
-                Some(ConnectionEvent::CloseConnection {
-                    notify: NotifyProtocol::No,
-                }) => return self.close_connection(NotifyProtocol::No).await,
+                Some(ConnectionEvent::CloseConnection { notify }) =>
+                    return self.close_connection(notify).await,
                 Some(ConnectionEvent::NotificationReceived { notification }) => {
                     if let Err(_) = self.notif_tx.send_item((self.peer, notification)) {
                         return self.close_connection(NotifyProtocol::Yes).await;
@@ -194,64 +477,168 @@ impl Stream for Connection {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = Pin::into_inner(self);
 
-        if let Poll::Ready(_) = this.rx.poll_unpin(cx) {
+        if let Poll::Ready(notify) = this.rx.poll_unpin(cx) {
             return Poll::Ready(Some(ConnectionEvent::CloseConnection {
-                notify: NotifyProtocol::No,
+                notify: notify.unwrap_or(NotifyProtocol::No),
             }));
         }
 
+        // read credit granted by the remote node
+        if let Some(credit) = this.outbound_credit.as_mut() {
+            loop {
+                match this.outbound.poll_next_unpin(cx) {
+                    Poll::Pending => break,
+                    Poll::Ready(None) | Poll::Ready(Some(Err(_))) =>
+                        return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                            notify: NotifyProtocol::Yes,
+                        })),
+                    Poll::Ready(Some(Ok(message))) =>
+                        match unsigned_varint::decode::usize(&message) {
+                            Ok((granted, rest)) if rest.is_empty() => {
+                                *credit = credit.saturating_add(granted);
+                            }
+                            _ => {
+                                tracing::debug!(
+                                    target: LOG_TARGET,
+                                    peer = ?this.peer,
+                                    "received invalid credit message",
+                                );
+
+                                return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                                    notify: NotifyProtocol::Yes,
+                                }));
+                            }
+                        },
+                }
+            }
+        }
+
+        let mut progress = false;
+
         loop {
-            let notification = match this.next_notification.take() {
-                Some(notification) => Some(notification),
-                None => {
-                    let future = async {
-                        tokio::select! {
-                            notification = this.async_rx.recv() => notification,
-                            notification = this.sync_rx.recv() => notification,
-                        }
-                    };
-                    futures::pin_mut!(future);
+            // move sent notifications to the outbound queue
+            let mut channels_empty = false;
+
+            while this.queue.is_accepting() {
+                match this.poll_notification(cx) {
+                    Poll::Pending => {
+                        channels_empty = true;
+                        break;
+                    }
+                    Poll::Ready(Some(notification)) => this.queue.push(notification),
+                    Poll::Ready(None) =>
+                        return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                            notify: NotifyProtocol::Yes,
+                        })),
+                }
+            }
 
-                    match future.poll_unpin(cx) {
-                        Poll::Pending => None,
-                        Poll::Ready(None) =>
+            // write queued notifications to the outbound substream while there is credit for them
+            while this.outbound_credit != Some(0) {
+                let Some((notification, flushed)) = this.queue.notifications.pop_front() else {
+                    break;
+                };
+
+                match this.outbound.poll_ready_unpin(cx) {
+                    Poll::Ready(Ok(())) => {}
+                    Poll::Pending => {
+                        this.queue.notifications.push_front((notification, flushed));
+                        break;
+                    }
+                    Poll::Ready(Err(_)) =>
+                        return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                            notify: NotifyProtocol::Yes,
+                        })),
+                }
+
+                let notification = match this.outbound_compression {
+                    None => notification,
+                    Some(compression) => match compression.compress(&notification) {
+                        Ok(notification) => notification,
+                        Err(_) =>
                             return Poll::Ready(Some(ConnectionEvent::CloseConnection {
                                 notify: NotifyProtocol::Yes,
                             })),
-                        Poll::Ready(Some(notification)) => Some(notification),
-                    }
+                    },
+                };
+
+                if let Err(_) = this.outbound.start_send_unpin(notification.into()) {
+                    return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                        notify: NotifyProtocol::Yes,
+                    }));
                 }
-            };
 
-            let Some(notification) = notification else {
-                break;
-            };
+                this.flushing.extend(flushed);
+                progress = true;
 
-            match this.outbound.poll_ready_unpin(cx) {
-                Poll::Ready(Ok(())) => {}
-                Poll::Pending => {
-                    this.next_notification = Some(notification);
-                    break;
+                if let Some(credit) = this.outbound_credit.as_mut() {
+                    *credit -= 1;
                 }
-                Poll::Ready(Err(_)) =>
-                    return Poll::Ready(Some(ConnectionEvent::CloseConnection {
-                        notify: NotifyProtocol::Yes,
-                    })),
             }
 
-            if let Err(_) = this.outbound.start_send_unpin(notification.into()) {
-                return Poll::Ready(Some(ConnectionEvent::CloseConnection {
-                    notify: NotifyProtocol::Yes,
-                }));
+            if channels_empty || !this.queue.notifications.is_empty() {
+                break;
             }
         }
 
-        match this.outbound.poll_flush_unpin(cx) {
+        let flushed = match this.outbound.poll_flush_unpin(cx) {
             Poll::Ready(Err(_)) =>
                 return Poll::Ready(Some(ConnectionEvent::CloseConnection {
                     notify: NotifyProtocol::Yes,
                 })),
-            Poll::Ready(Ok(())) | Poll::Pending => {}
+            Poll::Ready(Ok(())) => {
+                for tx in this.flushing.drain(..) {
+                    let _ = tx.send(());
+                }
+                true
+            }
+            Poll::Pending => false,
+        };
+
+        let stalled = !progress && (!flushed || !this.queue.notifications.is_empty());
+        if this.poll_stall(cx, stalled) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                peer = ?this.peer,
+                timeout = ?this.stall_timeout,
+                "connection stalled for too long, closing",
+            );
+
+            return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                notify: NotifyProtocol::Yes,
+            }));
+        }
+
+        // grant credit for the notifications read by the user
+        if let Some(credit) = this.inbound_credit.as_mut() {
+            if let Some(granted) = credit.poll_credit(cx) {
+                match this.inbound.poll_ready_unpin(cx) {
+                    Poll::Ready(Ok(())) => {
+                        let mut buffer = unsigned_varint::encode::usize_buffer();
+                        let message = unsigned_varint::encode::usize(granted, &mut buffer);
+
+                        if let Err(_) =
+                            this.inbound.start_send_unpin(Bytes::copy_from_slice(message))
+                        {
+                            return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                                notify: NotifyProtocol::Yes,
+                            }));
+                        }
+                        credit.pending = 0;
+                    }
+                    Poll::Ready(Err(_)) =>
+                        return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                            notify: NotifyProtocol::Yes,
+                        })),
+                    Poll::Pending => {}
+                }
+            }
+
+            if let Poll::Ready(Err(_)) = this.inbound.poll_flush_unpin(cx) {
+                return Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                    notify: NotifyProtocol::Yes,
+                }));
+            }
         }
 
         if let Err(_) = futures::ready!(this.notif_tx.poll_reserve(cx)) {
@@ -264,8 +651,27 @@ impl Stream for Connection {
             None | Some(Err(_)) => Poll::Ready(Some(ConnectionEvent::CloseConnection {
                 notify: NotifyProtocol::Yes,
             })),
-            Some(Ok(notification)) =>
-                Poll::Ready(Some(ConnectionEvent::NotificationReceived { notification })),
+            Some(Ok(notification)) => match this.inbound_compression {
+                None => Poll::Ready(Some(ConnectionEvent::NotificationReceived { notification })),
+                Some(compression) => match compression
+                    .decompress(&notification, this.max_notification_size)
+                {
+                    Ok(notification) => Poll::Ready(Some(ConnectionEvent::NotificationReceived {
+                        notification: BytesMut::from(&notification[..]),
+                    })),
+                    Err(_) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            peer = ?this.peer,
+                            "failed to decompress notification",
+                        );
+
+                        Poll::Ready(Some(ConnectionEvent::CloseConnection {
+                            notify: NotifyProtocol::Yes,
+                        }))
+                    }
+                },
+            },
         }
     }
 }
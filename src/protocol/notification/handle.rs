@@ -21,8 +21,8 @@
 use crate::{
     error::Error,
     protocol::notification::types::{
-        Direction, InnerNotificationEvent, NotificationCommand, NotificationError,
-        NotificationEvent, ValidationResult,
+        Direction, InnerNotificationEvent, NegotiationDuration, NotificationCommand,
+        NotificationError, NotificationEvent, QueuePolicy, StateCounts, ValidationResult,
     },
     types::protocol::ProtocolName,
     PeerId,
@@ -30,31 +30,93 @@ use crate::{
 
 use bytes::BytesMut;
 use futures::Stream;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use tokio::sync::{
-    mpsc::{error::TrySendError, Receiver, Sender},
+    mpsc::{error::TrySendError, Receiver, Sender, UnboundedSender},
     oneshot,
 };
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::notification::handle";
 
+/// Maximum number of events buffered while [`NotificationStream`] is polled.
+///
+/// Once the buffer is full, [`NotificationStream`] stops reading events until
+/// [`NotificationHandle`] is polled again.
+const MAX_PENDING_EVENTS: usize = 64;
+
 #[derive(Debug, Clone)]
 pub(crate) struct NotificationEventHandle {
     tx: Sender<InnerNotificationEvent>,
+
+    /// Peers whose notification streams are re-opened when they're closed.
+    reserved_peers: Arc<HashSet<PeerId>>,
+
+    /// Reserved peers whose notification streams are being re-opened.
+    ///
+    /// The user hasn't been told that the old stream was closed, so the outcome of the re-open
+    /// is reported either as a replacement of the old stream or as its closure.
+    reopening: Arc<Mutex<HashSet<PeerId>>>,
 }
 
 impl NotificationEventHandle {
     /// Create new [`NotificationEventHandle`].
     pub(crate) fn new(tx: Sender<InnerNotificationEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx,
+            reserved_peers: Default::default(),
+            reopening: Default::default(),
+        }
+    }
+
+    /// Re-open the notification streams of `peers` when they're closed.
+    pub(crate) fn with_reserved_peers(mut self, peers: HashSet<PeerId>) -> Self {
+        self.reserved_peers = Arc::new(peers);
+        self
+    }
+
+    /// Start re-opening the notification stream of `peer` if it's a reserved peer.
+    ///
+    /// Returns `true` if the stream will be re-opened, in which case its closure must not be
+    /// reported to the user.
+    pub(crate) fn start_reopen(&self, peer: PeerId) -> bool {
+        if !self.reserved_peers.contains(&peer) {
+            return false;
+        }
+
+        self.reopening.lock().insert(peer);
+        true
+    }
+
+    /// Start re-opening the notification stream of `peer`, regardless of whether it's a reserved
+    /// peer.
+    pub(crate) fn force_reopen(&self, peer: PeerId) {
+        self.reopening.lock().insert(peer);
+    }
+
+    /// Check if the notification stream of `peer` is being re-opened.
+    pub(crate) fn is_reopening(&self, peer: &PeerId) -> bool {
+        self.reopening.lock().contains(peer)
+    }
+
+    /// Re-opening the notification stream of `peer` failed, report the old stream as closed.
+    ///
+    /// Does nothing if the stream of `peer` wasn't being re-opened.
+    pub(crate) async fn report_reopen_failure(&self, peer: PeerId) {
+        if self.reopening.lock().remove(&peer) {
+            let _ = self.tx.send(InnerNotificationEvent::NotificationStreamClosed { peer }).await;
+        }
     }
 
     /// Validate inbound substream.
@@ -87,7 +149,9 @@ impl NotificationEventHandle {
         peer: PeerId,
         handshake: Vec<u8>,
         sink: NotificationSink,
+        read_tx: Option<UnboundedSender<()>>,
     ) {
+        let reopened = self.reopening.lock().remove(&peer);
         let _ = self
             .tx
             .send(InnerNotificationEvent::NotificationStreamOpened {
@@ -97,6 +161,8 @@ impl NotificationEventHandle {
                 peer,
                 handshake,
                 sink,
+                read_tx,
+                reopened,
             })
             .await;
     }
@@ -112,6 +178,10 @@ impl NotificationEventHandle {
         peer: PeerId,
         error: NotificationError,
     ) {
+        if self.reopening.lock().contains(&peer) {
+            return self.report_reopen_failure(peer).await;
+        }
+
         let _ = self
             .tx
             .send(InnerNotificationEvent::NotificationStreamOpenFailure { peer, error })
@@ -131,23 +201,54 @@ pub struct NotificationSink {
     sync_tx: Sender<Vec<u8>>,
 
     /// TX channel for sending notifications asynchronously.
-    async_tx: Sender<Vec<u8>>,
+    ///
+    /// Notifications may carry a oneshot sender which is signaled once the notification has
+    /// been flushed to the outbound substream.
+    async_tx: Sender<(Vec<u8>, Option<oneshot::Sender<()>>)>,
+
+    /// Number of notifications discarded because the outbound queue was full.
+    dropped: Arc<AtomicUsize>,
+
+    /// Is the notification protocol paused.
+    paused: Arc<AtomicBool>,
 }
 
 impl NotificationSink {
     /// Create new [`NotificationSink`].
-    pub(crate) fn new(peer: PeerId, sync_tx: Sender<Vec<u8>>, async_tx: Sender<Vec<u8>>) -> Self {
+    pub(crate) fn new(
+        peer: PeerId,
+        sync_tx: Sender<Vec<u8>>,
+        async_tx: Sender<(Vec<u8>, Option<oneshot::Sender<()>>)>,
+        dropped: Arc<AtomicUsize>,
+        paused: Arc<AtomicBool>,
+    ) -> Self {
         Self {
             peer,
             async_tx,
             sync_tx,
+            dropped,
+            paused,
         }
     }
 
+    /// Get the number of notifications discarded because the outbound queue of the peer was
+    /// full.
+    ///
+    /// Notifications are only discarded if the queue policy is [`QueuePolicy::DropOldest`] or
+    /// [`QueuePolicy::DropNewest`].
+    pub fn dropped_notifications(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
     /// Send notification to `peer` synchronously.
     ///
-    /// If the channel is clogged, [`NotificationError::ChannelClogged`] is returned.
+    /// If the channel is clogged, [`NotificationError::ChannelClogged`] is returned. If the
+    /// protocol is paused, [`NotificationError::Paused`] is returned.
     pub fn send_sync_notification(&self, notification: Vec<u8>) -> Result<(), NotificationError> {
+        if self.paused.load(Ordering::Relaxed) {
+            return Err(NotificationError::Paused);
+        }
+
         self.sync_tx.try_send(notification).map_err(|error| match error {
             TrySendError::Closed(_) => NotificationError::NoConnection,
             TrySendError::Full(_) => NotificationError::ChannelClogged,
@@ -157,14 +258,50 @@ impl NotificationSink {
     /// Send notification to `peer` asynchronously, waiting for the channel to have capacity
     /// if it's clogged.
     ///
+    /// The call resolves as soon as the notification has been accepted into the outbound queue
+    /// of the peer, which doesn't mean that it has been written to the substream yet. Use
+    /// [`NotificationSink::send_async_notification_flushed()`] to wait for the latter.
+    ///
     /// Returns [`Error::PeerDoesntExist(PeerId)`](crate::error::Error::PeerDoesntExist)
-    /// if the connection has been closed.
+    /// if the connection has been closed and [`NotificationError::Paused`] if the protocol is
+    /// paused.
     pub async fn send_async_notification(&self, notification: Vec<u8>) -> crate::Result<()> {
+        if self.paused.load(Ordering::Relaxed) {
+            return Err(Error::NotificationError(NotificationError::Paused));
+        }
+
         self.async_tx
-            .send(notification)
+            .send((notification, None))
             .await
             .map_err(|_| Error::PeerDoesntExist(self.peer))
     }
+
+    /// Send notification to `peer` asynchronously and wait until it has been flushed to the
+    /// outbound substream.
+    ///
+    /// Unlike [`NotificationSink::send_async_notification()`], the call doesn't resolve while the
+    /// notification is waiting in the outbound queue, so a stalled peer stalls the caller too.
+    ///
+    /// Returns [`Error::PeerDoesntExist(PeerId)`](crate::error::Error::PeerDoesntExist)
+    /// if the connection was closed or the notification was discarded by the queue policy
+    /// before it was flushed, and [`NotificationError::Paused`] if the protocol is paused.
+    pub async fn send_async_notification_flushed(
+        &self,
+        notification: Vec<u8>,
+    ) -> crate::Result<()> {
+        if self.paused.load(Ordering::Relaxed) {
+            return Err(Error::NotificationError(NotificationError::Paused));
+        }
+
+        let (tx, rx) = oneshot::channel();
+
+        self.async_tx
+            .send((notification, Some(tx)))
+            .await
+            .map_err(|_| Error::PeerDoesntExist(self.peer))?;
+
+        rx.await.map_err(|_| Error::PeerDoesntExist(self.peer))
+    }
 }
 
 /// Handle allowing the user protocol to interact with the notification protocol.
@@ -182,14 +319,36 @@ pub struct NotificationHandle {
     /// Peers.
     peers: HashMap<PeerId, NotificationSink>,
 
+    /// Handshakes received from peers with an open notification stream.
+    handshakes: HashMap<PeerId, Vec<u8>>,
+
     /// Clogged peers.
     clogged: HashSet<PeerId>,
 
+    /// TX channels for reporting read notifications to connections of peers that use
+    /// credit-based flow control.
+    readers: HashMap<PeerId, UnboundedSender<()>>,
+
     /// Pending validations.
     pending_validations: HashMap<PeerId, oneshot::Sender<ValidationResult>>,
 
     /// Handshake.
     handshake: Arc<RwLock<Vec<u8>>>,
+
+    /// Events received while [`NotificationStream`] was polled.
+    pending_events: VecDeque<NotificationEvent>,
+
+    /// Is the notification protocol paused.
+    paused: Arc<AtomicBool>,
+
+    /// Number of peers in each state.
+    state_counts: Arc<RwLock<StateCounts>>,
+
+    /// Stalled peers and the time they stalled.
+    stalled_peers: Arc<RwLock<HashMap<PeerId, Instant>>>,
+
+    /// Negotiation durations of the open notification streams.
+    negotiation_durations: Arc<RwLock<HashMap<PeerId, NegotiationDuration>>>,
 }
 
 impl NotificationHandle {
@@ -199,15 +358,26 @@ impl NotificationHandle {
         notif_rx: Receiver<(PeerId, BytesMut)>,
         command_tx: Sender<NotificationCommand>,
         handshake: Arc<RwLock<Vec<u8>>>,
+        paused: Arc<AtomicBool>,
+        state_counts: Arc<RwLock<StateCounts>>,
+        stalled_peers: Arc<RwLock<HashMap<PeerId, Instant>>>,
+        negotiation_durations: Arc<RwLock<HashMap<PeerId, NegotiationDuration>>>,
     ) -> Self {
         Self {
             event_rx,
             notif_rx,
             command_tx,
             handshake,
+            paused,
+            state_counts,
+            stalled_peers,
+            negotiation_durations,
             peers: HashMap::new(),
+            handshakes: HashMap::new(),
             clogged: HashSet::new(),
+            readers: HashMap::new(),
             pending_validations: HashMap::new(),
+            pending_events: VecDeque::new(),
         }
     }
 
@@ -219,9 +389,15 @@ impl NotificationHandle {
     /// If connection to peer is closed, `NotificationProtocol` tries to dial the peer and if the
     /// dial succeeds, tries to open a substream. This behavior can be disabled with
     /// [`ConfigBuilder::with_dialing_enabled(false)`](super::config::ConfigBuilder::with_dialing_enabled()).
+    ///
+    /// Returns [`NotificationError::Paused`] if the protocol is paused.
     pub async fn open_substream(&self, peer: PeerId) -> crate::Result<()> {
         tracing::trace!(target: LOG_TARGET, ?peer, "open substream");
 
+        if self.is_paused() {
+            return Err(Error::NotificationError(NotificationError::Paused));
+        }
+
         if self.peers.contains_key(&peer) {
             return Err(Error::PeerAlreadyExists(peer));
         }
@@ -234,16 +410,36 @@ impl NotificationHandle {
             .map_or(Ok(()), |_| Ok(()))
     }
 
+    /// Reset the state of `peer` which is stuck in substream validation.
+    ///
+    /// Aborts the validation and negotiation of any substreams to `peer` which have not yet
+    /// been fully opened and returns the peer to the closed state so a new substream can be
+    /// opened to them. If the user protocol was aware of the substream under negotiation,
+    /// [`NotificationEvent::NotificationStreamOpenFailure`] is emitted with
+    /// [`NotificationError::Reset`].
+    ///
+    /// Peers with a fully open or a closed notification stream are not affected.
+    pub async fn reset_peer(&self, peer: PeerId) {
+        tracing::trace!(target: LOG_TARGET, ?peer, "reset peer");
+
+        let _ = self.command_tx.send(NotificationCommand::ResetPeer { peer }).await;
+    }
+
     /// Open substreams to multiple peers.
     ///
     /// Similar to [`NotificationHandle::open_substream()`] but multiple substreams are initiated
     /// using a single call to `NotificationProtocol`.
     ///
-    /// Peers who are already connected are ignored and returned as `Err(HashSet<PeerId>>)`.
+    /// Peers who are already connected are ignored and returned as `Err(HashSet<PeerId>>)`. If
+    /// the protocol is paused, all peers are ignored.
     pub async fn open_substream_batch(
         &self,
         peers: impl Iterator<Item = PeerId>,
     ) -> Result<(), HashSet<PeerId>> {
+        if self.is_paused() {
+            return Err(peers.collect());
+        }
+
         let (to_add, to_ignore): (Vec<_>, Vec<_>) = peers
             .map(|peer| match self.peers.contains_key(&peer) {
                 true => (None, Some(peer)),
@@ -275,11 +471,15 @@ impl NotificationHandle {
     /// using a single call to `NotificationProtocol`.
     ///
     /// If the channel is clogged, peers for whom a connection is not yet open are returned as
-    /// `Err(HashSet<PeerId>)`.
+    /// `Err(HashSet<PeerId>)`. If the protocol is paused, all peers are returned.
     pub fn try_open_substream_batch(
         &self,
         peers: impl Iterator<Item = PeerId>,
     ) -> Result<(), HashSet<PeerId>> {
+        if self.is_paused() {
+            return Err(peers.collect());
+        }
+
         let (to_add, to_ignore): (Vec<_>, Vec<_>) = peers
             .map(|peer| match self.peers.contains_key(&peer) {
                 true => (None, Some(peer)),
@@ -372,6 +572,68 @@ impl NotificationHandle {
             .map_err(|_| peers)
     }
 
+    /// Pause the notification protocol.
+    ///
+    /// While the protocol is paused, new inbound substreams are rejected, new outbound substreams
+    /// cannot be opened and notifications cannot be sent, with [`NotificationError::Paused`]
+    /// returned instead. Notification streams already open are kept open, unless
+    /// `close_substreams` is `true`, and notifications received from them are still reported.
+    ///
+    /// The protocol stays paused until [`NotificationHandle::resume()`] is called.
+    pub async fn pause(&self, close_substreams: bool) {
+        tracing::debug!(target: LOG_TARGET, ?close_substreams, "pause protocol");
+
+        self.paused.store(true, Ordering::Relaxed);
+
+        if close_substreams {
+            self.close_substream_batch(self.peers.keys().copied()).await;
+        }
+    }
+
+    /// Resume the notification protocol paused with [`NotificationHandle::pause()`].
+    pub fn resume(&self) {
+        tracing::debug!(target: LOG_TARGET, "resume protocol");
+
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Check if the notification protocol is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of peers in each state.
+    ///
+    /// The counts are a snapshot taken after the protocol last processed an event and include
+    /// every peer the protocol is tracking, connected or not.
+    pub fn state_counts(&self) -> StateCounts {
+        *self.state_counts.read()
+    }
+
+    /// Get the open peers which have been unable to make send progress for at least `threshold`,
+    /// along with how long they've been stalled.
+    ///
+    /// Stalled peers can be closed with [`NotificationHandle::close_substream()`] or
+    /// automatically with
+    /// [`ConfigBuilder::with_stall_timeout()`](super::ConfigBuilder::with_stall_timeout).
+    pub fn stalled_peers(&self, threshold: Duration) -> Vec<(PeerId, Duration)> {
+        self.stalled_peers
+            .read()
+            .iter()
+            .map(|(peer, since)| (*peer, since.elapsed()))
+            .filter(|(_, stalled)| *stalled >= threshold)
+            .collect()
+    }
+
+    /// Get how long negotiating the notification stream currently open to `peer` took.
+    ///
+    /// The duration is split into the time the user protocol took to validate the inbound
+    /// substream and the time spent on the network, which helps telling slow validation apart
+    /// from slow peers. Returns `None` if no notification stream is open to `peer`.
+    pub fn negotiation_duration(&self, peer: PeerId) -> Option<NegotiationDuration> {
+        self.negotiation_durations.read().get(&peer).copied()
+    }
+
     /// Set new handshake.
     pub fn set_handshake(&mut self, handshake: Vec<u8>) {
         tracing::trace!(target: LOG_TARGET, ?handshake, "set handshake");
@@ -379,6 +641,20 @@ impl NotificationHandle {
         *self.handshake.write() = handshake;
     }
 
+    /// Set new handshake and re-open the notification streams of all open peers with it.
+    ///
+    /// Each open stream is closed and opened again so the remote peer validates the new handshake,
+    /// which is useful when the semantics of the handshake change. A stream which is opened again
+    /// is reported as [`NotificationEvent::NotificationStreamReopened`] and if the remote peer
+    /// rejects the new handshake, the stream is reported as closed.
+    pub async fn set_handshake_and_revalidate(&mut self, handshake: Vec<u8>) {
+        self.set_handshake(handshake);
+
+        tracing::trace!(target: LOG_TARGET, "revalidate open peers");
+
+        let _ = self.command_tx.send(NotificationCommand::Revalidate).await;
+    }
+
     /// Send validation result to the notification protocol for an inbound substream received from
     /// `peer`.
     pub fn send_validation_result(&mut self, peer: PeerId, result: ValidationResult) {
@@ -400,6 +676,7 @@ impl NotificationHandle {
                 Ok(()) => Ok(()),
                 Err(error) => match error {
                     NotificationError::NoConnection => Err(NotificationError::NoConnection),
+                    NotificationError::Paused => Err(NotificationError::Paused),
                     NotificationError::ChannelClogged => {
                         let _ = self.clogged.insert(peer).then(|| {
                             self.command_tx.try_send(NotificationCommand::ForceClose { peer })
@@ -431,20 +708,64 @@ impl NotificationHandle {
         }
     }
 
+    /// Send notification to `peer` asynchronously and wait until it has been flushed to the
+    /// outbound substream.
+    ///
+    /// See [`NotificationSink::send_async_notification_flushed()`] for more details.
+    pub async fn send_async_notification_flushed(
+        &mut self,
+        peer: PeerId,
+        notification: Vec<u8>,
+    ) -> crate::Result<()> {
+        match self.peers.get_mut(&peer) {
+            Some(sink) => sink.send_async_notification_flushed(notification).await,
+            None => Err(Error::PeerDoesntExist(peer)),
+        }
+    }
+
     /// Get a copy of the underlying notification sink for the peer.
     ///
     /// `None` is returned if `peer` doesn't exist.
     pub fn notification_sink(&self, peer: PeerId) -> Option<NotificationSink> {
         self.peers.get(&peer).cloned()
     }
-}
 
-impl Stream for NotificationHandle {
-    type Item = NotificationEvent;
+    /// Get the handshake received from `peer` when the notification stream was opened.
+    ///
+    /// `None` is returned if there is no open notification stream to `peer`.
+    pub fn peer_handshake(&self, peer: PeerId) -> Option<Vec<u8>> {
+        self.handshakes.get(&peer).cloned()
+    }
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    /// Get a stream of inbound notifications from all connected peers.
+    ///
+    /// The stream yields `(peer, notification)` pairs in the order they were received.
+    ///
+    /// Events other than [`NotificationEvent::NotificationReceived`] are buffered and returned
+    /// the next time [`NotificationHandle`] itself is polled. At most 64 events are buffered,
+    /// after which the stream stops reading events from the protocol, which in turn stops
+    /// receiving notifications, so the handle itself must also be polled periodically.
+    ///
+    /// Inbound substreams which must be validated while only the stream is polled are rejected.
+    pub fn notifications(&mut self) -> NotificationStream<'_> {
+        NotificationStream { handle: self }
+    }
+
+    /// Poll next event from the notification protocol.
+    ///
+    /// Only notifications are read if `read_events` is `false`.
+    fn poll_next_event(
+        &mut self,
+        cx: &mut Context<'_>,
+        read_events: bool,
+    ) -> Poll<Option<NotificationEvent>> {
         loop {
-            match self.event_rx.poll_recv(cx) {
+            let event = match read_events {
+                true => self.event_rx.poll_recv(cx),
+                false => Poll::Pending,
+            };
+
+            match event {
                 Poll::Pending => {}
                 Poll::Ready(None) => return Poll::Ready(None),
                 Poll::Ready(Some(event)) => match event {
@@ -455,8 +776,26 @@ impl Stream for NotificationHandle {
                         peer,
                         handshake,
                         sink,
+                        read_tx,
+                        reopened,
                     } => {
                         self.peers.insert(peer, sink);
+                        self.handshakes.insert(peer, handshake.clone());
+
+                        match read_tx {
+                            Some(read_tx) => self.readers.insert(peer, read_tx),
+                            None => self.readers.remove(&peer),
+                        };
+
+                        if reopened {
+                            return Poll::Ready(Some(
+                                NotificationEvent::NotificationStreamReopened {
+                                    peer,
+                                    direction,
+                                    handshake,
+                                },
+                            ));
+                        }
 
                         return Poll::Ready(Some(NotificationEvent::NotificationStreamOpened {
                             protocol,
@@ -468,7 +807,9 @@ impl Stream for NotificationHandle {
                     }
                     InnerNotificationEvent::NotificationStreamClosed { peer } => {
                         self.peers.remove(&peer);
+                        self.handshakes.remove(&peer);
                         self.clogged.remove(&peer);
+                        self.readers.remove(&peer);
 
                         return Poll::Ready(Some(NotificationEvent::NotificationStreamClosed {
                             peer,
@@ -499,8 +840,15 @@ impl Stream for NotificationHandle {
 
             match futures::ready!(self.notif_rx.poll_recv(cx)) {
                 None => return Poll::Ready(None),
+                // while events aren't read, the stream of the peer may not have been reported as
+                // open yet
                 Some((peer, notification)) =>
-                    if self.peers.contains_key(&peer) {
+                    if self.peers.contains_key(&peer) || !read_events {
+                        // the notification has been read, let the connection grant credit for it
+                        if let Some(read_tx) = self.readers.get(&peer) {
+                            let _ = read_tx.send(());
+                        }
+
                         return Poll::Ready(Some(NotificationEvent::NotificationReceived {
                             peer,
                             notification,
@@ -510,3 +858,50 @@ impl Stream for NotificationHandle {
         }
     }
 }
+
+impl Stream for NotificationHandle {
+    type Item = NotificationEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+
+        self.poll_next_event(cx, true)
+    }
+}
+
+/// Stream of inbound notifications from all peers of a notification protocol.
+///
+/// Created with [`NotificationHandle::notifications()`].
+#[derive(Debug)]
+pub struct NotificationStream<'a> {
+    /// Notification handle.
+    handle: &'a mut NotificationHandle,
+}
+
+impl Stream for NotificationStream<'_> {
+    type Item = (PeerId, Vec<u8>);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let read_events = self.handle.pending_events.len() < MAX_PENDING_EVENTS;
+
+            match futures::ready!(self.handle.poll_next_event(cx, read_events)) {
+                None => return Poll::Ready(None),
+                Some(NotificationEvent::NotificationReceived { peer, notification }) =>
+                    return Poll::Ready(Some((peer, notification.into()))),
+                Some(NotificationEvent::ValidateSubstream { peer, .. }) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        "only notifications are read, reject inbound substream",
+                    );
+
+                    self.handle.send_validation_result(peer, ValidationResult::Reject);
+                }
+                Some(event) => self.handle.pending_events.push_back(event),
+            }
+        }
+    }
+}
@@ -26,10 +26,10 @@ use crate::{
     protocol::{
         self,
         notification::{
-            connection::Connection,
+            connection::{Connection, InboundCredit, NotifyProtocol, OutboundQueue},
             handle::NotificationEventHandle,
             negotiation::{HandshakeEvent, HandshakeService},
-            types::NotificationCommand,
+            types::{Compression, NotificationCommand, ProtocolVariant},
         },
         TransportEvent, TransportService,
     },
@@ -41,16 +41,27 @@ use crate::{
 use bytes::BytesMut;
 use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
 use multiaddr::Multiaddr;
+use parking_lot::RwLock;
 use tokio::sync::{
-    mpsc::{channel, Receiver, Sender},
+    mpsc::{channel, unbounded_channel, Receiver, Sender},
     oneshot,
 };
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 pub use config::{Config, ConfigBuilder};
-pub use handle::{NotificationHandle, NotificationSink};
-pub use types::{Direction, NotificationError, NotificationEvent, ValidationResult};
+pub use handle::{NotificationHandle, NotificationSink, NotificationStream};
+pub use types::{
+    Compression, Direction, NegotiationDuration, NotificationError, NotificationEvent,
+    PeerStateKind, PeerStateObserver, QueuePolicy, StateCounts, ValidationResult,
+};
 
 mod config;
 mod connection;
@@ -209,10 +220,65 @@ enum PeerState {
     /// Notification stream has been opened.
     Open {
         /// `Oneshot::Sender` for shutting down the connection.
-        shutdown: oneshot::Sender<()>,
+        shutdown: oneshot::Sender<NotifyProtocol>,
     },
 }
 
+impl PeerState {
+    /// Get the [`PeerStateKind`] of the state.
+    fn kind(&self) -> Option<PeerStateKind> {
+        match self {
+            Self::Poisoned => None,
+            Self::ValidationPending { .. } => Some(PeerStateKind::ValidationPending),
+            Self::Closed { .. } => Some(PeerStateKind::Closed),
+            Self::Dialing => Some(PeerStateKind::Dialing),
+            Self::OutboundInitiated { .. } => Some(PeerStateKind::OutboundInitiated),
+            Self::Validating { .. } => Some(PeerStateKind::Validating),
+            Self::Open { .. } => Some(PeerStateKind::Open),
+        }
+    }
+}
+
+/// Timer measuring how long the negotiation of a notification stream takes.
+#[derive(Debug)]
+struct NegotiationTimer {
+    /// When the negotiation started.
+    started: Instant,
+
+    /// When the user protocol was asked to validate the inbound substream, if it's being
+    /// validated.
+    validation_started: Option<Instant>,
+
+    /// Time spent waiting for the user protocol to validate the inbound substream.
+    validation: Duration,
+}
+
+impl NegotiationTimer {
+    /// Create new [`NegotiationTimer`].
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            validation_started: None,
+            validation: Duration::ZERO,
+        }
+    }
+
+    /// Stop measuring validation time.
+    fn on_validated(&mut self) {
+        if let Some(started) = self.validation_started.take() {
+            self.validation += started.elapsed();
+        }
+    }
+
+    /// Get the duration of the finished negotiation.
+    fn finish(self) -> NegotiationDuration {
+        NegotiationDuration {
+            total: self.started.elapsed(),
+            validation: self.validation,
+        }
+    }
+}
+
 /// Peer context.
 #[derive(Debug)]
 struct PeerContext {
@@ -239,6 +305,9 @@ pub(crate) struct NotificationProtocol {
     /// Auto accept inbound substream if the outbound substream was initiated by the local node.
     auto_accept: bool,
 
+    /// Auto accept all inbound substreams without asking the user to validate them.
+    auto_accept_all_inbound: bool,
+
     /// TX channel passed to the protocol used for sending events.
     event_handle: NotificationEventHandle,
 
@@ -271,6 +340,12 @@ pub(crate) struct NotificationProtocol {
     /// Asynchronous channel size.
     async_channel_size: usize,
 
+    /// Outbound queue size.
+    outbound_queue_size: usize,
+
+    /// Policy applied to notifications when the outbound queue is full.
+    outbound_queue_policy: QueuePolicy,
+
     /// Executor for connection handlers.
     executor: Arc<dyn Executor>,
 
@@ -282,6 +357,54 @@ pub(crate) struct NotificationProtocol {
 
     /// Should `NotificationProtocol` attempt to dial the peer.
     should_dial: bool,
+
+    /// Is the protocol paused.
+    ///
+    /// Set by [`NotificationHandle::pause()`] and cleared by [`NotificationHandle::resume()`].
+    paused: Arc<AtomicBool>,
+
+    /// Maximum notification size.
+    max_notification_size: usize,
+
+    /// Compression applied to notification payloads, if enabled.
+    compression: Option<Compression>,
+
+    /// Maximum number of unacknowledged notifications, if credit-based flow control is enabled.
+    credit_window: Option<usize>,
+
+    /// Variants of the protocol, in order of preference.
+    variants: Vec<(ProtocolName, ProtocolVariant)>,
+
+    /// Protocol variants the inbound substreams of peers were negotiated with.
+    inbound_variants: HashMap<PeerId, ProtocolVariant>,
+
+    /// Protocol variants the outbound substreams of peers were negotiated with.
+    outbound_variants: HashMap<PeerId, ProtocolVariant>,
+
+    /// Observer of peer state transitions, if enabled.
+    peer_state_observer: Option<PeerStateObserver>,
+
+    /// States of the peers last reported to `peer_state_observer`.
+    observed_states: HashMap<PeerId, PeerStateKind>,
+
+    /// Number of peers in each state, shared with [`NotificationHandle`].
+    state_counts: Arc<RwLock<StateCounts>>,
+
+    /// Peers allowed to open inbound substreams, if restricted.
+    allowed_peers: Option<HashSet<PeerId>>,
+
+    /// Stalled peers and the time they stalled, shared with [`NotificationHandle`].
+    stalled_peers: Arc<RwLock<HashMap<PeerId, Instant>>>,
+
+    /// Duration after which stalled peers are closed, if enabled.
+    stall_timeout: Option<Duration>,
+
+    /// Timers of the notification streams being negotiated.
+    negotiation_timers: HashMap<PeerId, NegotiationTimer>,
+
+    /// Negotiation durations of the open notification streams, shared with
+    /// [`NotificationHandle`].
+    negotiation_durations: Arc<RwLock<HashMap<PeerId, NegotiationDuration>>>,
 }
 
 impl NotificationProtocol {
@@ -291,6 +414,7 @@ impl NotificationProtocol {
         executor: Arc<dyn Executor>,
     ) -> Self {
         let (shutdown_tx, shutdown_rx) = channel(DEFAULT_CHANNEL_SIZE);
+        let variants = config.protocol_variants();
 
         Self {
             service,
@@ -300,16 +424,59 @@ impl NotificationProtocol {
             peers: HashMap::new(),
             protocol: config.protocol_name,
             auto_accept: config.auto_accept,
+            auto_accept_all_inbound: config.auto_accept_all_inbound,
             pending_validations: FuturesUnordered::new(),
             timers: FuturesUnordered::new(),
-            event_handle: NotificationEventHandle::new(config.event_tx),
+            event_handle: NotificationEventHandle::new(config.event_tx)
+                .with_reserved_peers(config.reserved_peers),
             notif_tx: config.notif_tx,
             command_rx: config.command_rx,
             pending_outbound: HashMap::new(),
-            negotiation: HandshakeService::new(config.handshake),
+            negotiation: HandshakeService::new(config.handshake, config.max_handshake_size)
+                .with_fixed_handshake_size(config.fixed_handshake_size),
             sync_channel_size: config.sync_channel_size,
             async_channel_size: config.async_channel_size,
+            outbound_queue_size: config.outbound_queue_size,
+            outbound_queue_policy: config.outbound_queue_policy,
             should_dial: config.should_dial,
+            paused: config.paused,
+            max_notification_size: config.max_notification_size,
+            compression: config.compression,
+            credit_window: config.credit_window,
+            variants,
+            inbound_variants: HashMap::new(),
+            outbound_variants: HashMap::new(),
+            peer_state_observer: config.peer_state_observer,
+            observed_states: HashMap::new(),
+            state_counts: config.state_counts,
+            allowed_peers: config.allowed_peers,
+            stalled_peers: config.stalled_peers,
+            stall_timeout: config.stall_timeout,
+            negotiation_timers: HashMap::new(),
+            negotiation_durations: config.negotiation_durations,
+        }
+    }
+
+    /// Resolve the protocol a substream was negotiated with.
+    ///
+    /// If compression or credit-based flow control is enabled, the substream is negotiated either
+    /// with one of the variants of the protocol or with one of the configured fallbacks. Returns
+    /// the protocol and fallback reported to the user and the variant the substream was
+    /// negotiated with.
+    fn resolve_negotiated_protocol(
+        &self,
+        protocol: ProtocolName,
+        fallback: Option<ProtocolName>,
+    ) -> (ProtocolName, Option<ProtocolName>, ProtocolVariant) {
+        if self.variants.len() == 1 {
+            return (protocol, fallback, ProtocolVariant::default());
+        }
+
+        let negotiated = fallback.as_ref().unwrap_or(&protocol);
+
+        match self.variants.iter().find(|(name, _)| name == negotiated) {
+            Some((_, variant)) => (self.protocol.clone(), None, *variant),
+            None => (self.protocol.clone(), fallback, ProtocolVariant::default()),
         }
     }
 
@@ -398,6 +565,10 @@ impl NotificationProtocol {
         // clean up all pending state for the peer
         self.negotiation.remove_outbound(&peer);
         self.negotiation.remove_inbound(&peer);
+        self.inbound_variants.remove(&peer);
+        self.outbound_variants.remove(&peer);
+        self.negotiation_timers.remove(&peer);
+        self.negotiation_durations.write().remove(&peer);
 
         match context.state {
             // outbound initiated, report open failure to peer
@@ -408,7 +579,7 @@ impl NotificationProtocol {
             }
             // substream fully open, report that the notification stream is closed
             PeerState::Open { shutdown } => {
-                let _ = shutdown.send(());
+                let _ = shutdown.send(NotifyProtocol::No);
             }
             // if the substream was being validated, user must be notified that the substream is
             // now considered rejected if they had been made aware of the existence of the pending
@@ -485,6 +656,9 @@ impl NotificationProtocol {
             _ => {}
         }
 
+        // stream of a reserved peer can no longer be re-opened
+        self.event_handle.report_reopen_failure(peer).await;
+
         Ok(())
     }
 
@@ -707,9 +881,36 @@ impl NotificationProtocol {
                     pending_open: Some(substream_id),
                 };
             }
+            // the protocol is paused so fresh inbound substreams are rejected
+            PeerState::Closed { pending_open: None } if self.paused.load(Ordering::Relaxed) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    "protocol paused, rejecting inbound substream",
+                );
+                let _ = substream.close().await;
+
+                context.state = PeerState::Closed { pending_open: None };
+            }
+            // the peer is not in the allowed set so the substream is rejected without validation
+            PeerState::Closed { pending_open: None }
+                if self.allowed_peers.as_ref().is_some_and(|allowed| !allowed.contains(&peer)) =>
+            {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    "peer not allowed, rejecting inbound substream",
+                );
+                let _ = substream.close().await;
+
+                context.state = PeerState::Closed { pending_open: None };
+            }
             // the peer state is closed so this is a fresh inbound substream.
             PeerState::Closed { pending_open: None } => {
                 self.negotiation.read_handshake(peer, substream);
+                self.negotiation_timers.insert(peer, NegotiationTimer::new());
 
                 context.state = PeerState::Validating {
                     protocol,
@@ -967,6 +1168,7 @@ impl NotificationProtocol {
                             state: PeerState::Dialing,
                         },
                     );
+                    self.negotiation_timers.insert(peer, NegotiationTimer::new());
                     return Ok(());
                 }
             }
@@ -987,6 +1189,7 @@ impl NotificationProtocol {
                 );
 
                 self.pending_outbound.insert(substream_id, peer);
+                self.negotiation_timers.insert(peer, NegotiationTimer::new());
                 context.state = PeerState::OutboundInitiated {
                     substream: substream_id,
                 };
@@ -1002,6 +1205,7 @@ impl NotificationProtocol {
                     );
 
                     self.pending_outbound.insert(substream_id, peer);
+                    self.negotiation_timers.insert(peer, NegotiationTimer::new());
                     context.state = PeerState::OutboundInitiated {
                         substream: substream_id,
                     };
@@ -1062,7 +1266,7 @@ impl NotificationProtocol {
 
         match std::mem::replace(&mut context.state, PeerState::Poisoned) {
             PeerState::Open { shutdown } => {
-                let _ = shutdown.send(());
+                let _ = shutdown.send(NotifyProtocol::No);
 
                 context.state = PeerState::Closed { pending_open: None };
             }
@@ -1079,6 +1283,109 @@ impl NotificationProtocol {
         }
     }
 
+    /// Re-open the notification streams of all open peers so the remote peers validate the
+    /// current handshake.
+    ///
+    /// The streams are closed and once their connection handlers have exited, they're opened
+    /// again the same way as the streams of reserved peers.
+    fn on_revalidate(&mut self) {
+        for (peer, context) in self.peers.iter_mut() {
+            match std::mem::replace(&mut context.state, PeerState::Poisoned) {
+                PeerState::Open { shutdown } => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        "re-open notification stream to revalidate handshake",
+                    );
+
+                    let _ = shutdown.send(NotifyProtocol::Reopen);
+                    context.state = PeerState::Closed { pending_open: None };
+                }
+                state => context.state = state,
+            }
+        }
+    }
+
+    /// Reset `peer` which is stuck in validation.
+    ///
+    /// Any substreams under validation or negotiation are dropped and the peer is moved to
+    /// [`PeerState::Closed`], or removed if it's only tracked because of a pending validation for
+    /// a closed connection. If the user was aware of the substream, either because they were
+    /// asked to validate it or because they initiated an outbound substream, the failure is
+    /// reported to them. A validation result received for the peer later on is ignored.
+    async fn on_reset_peer(&mut self, peer: PeerId) {
+        let Some(context) = self.peers.get_mut(&peer) else {
+            tracing::debug!(target: LOG_TARGET, ?peer, "peer doesn't exist");
+            return;
+        };
+
+        let report = match std::mem::replace(&mut context.state, PeerState::Poisoned) {
+            PeerState::Validating {
+                outbound, inbound, ..
+            } => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    ?outbound,
+                    ?inbound,
+                    "reset peer under validation",
+                );
+
+                self.negotiation.remove_outbound(&peer);
+                self.negotiation.remove_inbound(&peer);
+                context.state = PeerState::Closed {
+                    pending_open: outbound.pending_open(),
+                };
+
+                !std::matches!(outbound, OutboundState::Closed)
+                    || !std::matches!(
+                        inbound,
+                        InboundState::Closed | InboundState::ReadingHandshake
+                    )
+            }
+            PeerState::ValidationPending { state } => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    ?state,
+                    "reset peer with pending validation",
+                );
+
+                match state {
+                    ConnectionState::Open => {
+                        context.state = PeerState::Closed { pending_open: None };
+                    }
+                    ConnectionState::Closed => {
+                        self.peers.remove(&peer);
+                    }
+                }
+
+                true
+            }
+            state => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    ?state,
+                    "peer not under validation, ignoring reset",
+                );
+
+                context.state = state;
+                false
+            }
+        };
+
+        if report {
+            self.event_handle
+                .report_notification_stream_open_failure(peer, NotificationError::Reset)
+                .await;
+        }
+    }
+
     /// Handle validation result.
     ///
     /// The validation result binary (accept/reject). If the node is rejected, the substreams are
@@ -1103,6 +1410,10 @@ impl NotificationProtocol {
             "handle validation result",
         );
 
+        if let Some(timer) = self.negotiation_timers.get_mut(&peer) {
+            timer.on_validated();
+        }
+
         let Some(context) = self.peers.get_mut(&peer) else {
             tracing::debug!(target: LOG_TARGET, ?peer, "peer doesn't exist");
             return Err(Error::PeerDoesntExist(peer));
@@ -1386,6 +1697,40 @@ impl NotificationProtocol {
                                 return;
                             }
 
+                            // accept the substream on behalf of the user, opening an outbound
+                            // substream if one doesn't exist yet
+                            if self.auto_accept_all_inbound {
+                                tracing::trace!(
+                                    target: LOG_TARGET,
+                                    ?peer,
+                                    %protocol,
+                                    ?fallback,
+                                    ?outbound,
+                                    "auto-accept inbound substream without validation",
+                                );
+
+                                context.state = PeerState::Validating {
+                                    protocol,
+                                    fallback,
+                                    direction,
+                                    inbound: InboundState::Validating { inbound: substream },
+                                    outbound,
+                                };
+
+                                if let Err(error) =
+                                    self.on_validation_result(peer, ValidationResult::Accept).await
+                                {
+                                    tracing::debug!(
+                                        target: LOG_TARGET,
+                                        ?peer,
+                                        ?error,
+                                        "failed to auto-accept inbound substream",
+                                    );
+                                }
+
+                                return;
+                            }
+
                             tracing::trace!(
                                 target: LOG_TARGET,
                                 ?peer,
@@ -1403,6 +1748,10 @@ impl NotificationProtocol {
                                 direction,
                             };
 
+                            if let Some(timer) = self.negotiation_timers.get_mut(&peer) {
+                                timer.validation_started = Some(Instant::now());
+                            }
+
                             let (tx, rx) = oneshot::channel();
                             self.pending_validations.push(Box::pin(async move {
                                 match rx.await {
@@ -1447,12 +1796,17 @@ impl NotificationProtocol {
             // user is notified of the error only if they've either initiated an outbound substream
             // or if they accepted an inbound substream and as a result initiated an outbound
             // substream.
-            HandshakeEvent::NegotiationError { peer, direction } => {
+            HandshakeEvent::NegotiationError {
+                peer,
+                direction,
+                error,
+            } => {
                 tracing::debug!(
                     target: LOG_TARGET,
                     ?peer,
                     protocol = %self.protocol,
                     ?direction,
+                    ?error,
                     state = ?context.state,
                     "failed to negotiate substream",
                 );
@@ -1472,10 +1826,7 @@ impl NotificationProtocol {
                         if !std::matches!(outbound, OutboundState::Closed) {
                             return self
                                 .event_handle
-                                .report_notification_stream_open_failure(
-                                    peer,
-                                    NotificationError::Rejected,
-                                )
+                                .report_notification_stream_open_failure(peer, error)
                                 .await;
                         }
                     }
@@ -1509,7 +1860,19 @@ impl NotificationProtocol {
 
                 let (async_tx, async_rx) = channel(self.async_channel_size);
                 let (sync_tx, sync_rx) = channel(self.sync_channel_size);
-                let sink = NotificationSink::new(peer, sync_tx, async_tx);
+                let dropped = Arc::new(AtomicUsize::new(0usize));
+                let sink = NotificationSink::new(
+                    peer,
+                    sync_tx,
+                    async_tx,
+                    Arc::clone(&dropped),
+                    Arc::clone(&self.paused),
+                );
+                let queue = OutboundQueue::new(
+                    self.outbound_queue_size,
+                    self.outbound_queue_policy,
+                    dropped,
+                );
 
                 // start connection handler for the peer which only deals with sending/receiving
                 // notifications
@@ -1531,12 +1894,45 @@ impl NotificationProtocol {
                     self.notif_tx.clone(),
                     async_rx,
                     sync_rx,
+                    queue,
                 );
+                let inbound_variant = self.inbound_variants.get(&peer).copied().unwrap_or_default();
+                let outbound_variant =
+                    self.outbound_variants.get(&peer).copied().unwrap_or_default();
+                let (inbound_credit, read_tx) =
+                    match self.credit_window.filter(|_| inbound_variant.credit) {
+                        Some(window) => {
+                            let (read_tx, read_rx) = unbounded_channel();
+                            (Some(InboundCredit::new(window, read_rx)), Some(read_tx))
+                        }
+                        None => (None, None),
+                    };
+                let connection = connection
+                    .with_compression(
+                        self.compression.filter(|_| inbound_variant.compressed),
+                        self.compression.filter(|_| outbound_variant.compressed),
+                        self.max_notification_size,
+                    )
+                    .with_credit(inbound_credit, outbound_variant.credit)
+                    .with_stall_tracking(Arc::clone(&self.stalled_peers), self.stall_timeout);
+
+                if let Some(timer) = self.negotiation_timers.remove(&peer) {
+                    let duration = timer.finish();
+
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?duration,
+                        "notification stream negotiated",
+                    );
+                    self.negotiation_durations.write().insert(peer, duration);
+                }
 
                 context.state = PeerState::Open { shutdown };
                 self.event_handle
                     .report_notification_stream_opened(
-                        protocol, fallback, direction, peer, handshake, sink,
+                        protocol, fallback, direction, peer, handshake, sink, read_tx,
                     )
                     .await;
 
@@ -1623,8 +2019,8 @@ impl NotificationProtocol {
             }
             event = self.shutdown_rx.recv() => match event {
                 None => (),
-                Some(peer) => {
-                    if let Some(context) = self.peers.get_mut(&peer) {
+                Some(peer) => match self.peers.get_mut(&peer) {
+                    Some(context) => {
                         tracing::trace!(
                             target: LOG_TARGET,
                             ?peer,
@@ -1632,8 +2028,30 @@ impl NotificationProtocol {
                             "notification stream to peer closed",
                         );
                         context.state = PeerState::Closed { pending_open: None };
+                        self.negotiation_durations.write().remove(&peer);
+
+                        if self.event_handle.is_reopening(&peer) {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                protocol = %self.protocol,
+                                "re-open notification stream to reserved peer",
+                            );
+
+                            if let Err(error) = self.on_open_substream(peer).await {
+                                tracing::debug!(
+                                    target: LOG_TARGET,
+                                    ?peer,
+                                    protocol = %self.protocol,
+                                    ?error,
+                                    "failed to re-open notification stream",
+                                );
+                            }
+                        }
                     }
-                }
+                    // connection was closed before the stream could be re-opened
+                    None => self.event_handle.report_reopen_failure(peer).await,
+                },
             },
             // TODO: this could be combined with `Negotiation`
             peer = self.timers.next(), if !self.timers.is_empty() => match peer {
@@ -1739,35 +2157,54 @@ impl NotificationProtocol {
                     direction,
                     protocol,
                     fallback,
-                }) => match direction {
-                    protocol::Direction::Inbound => {
-                        if let Err(error) = self.on_inbound_substream(protocol, fallback, peer, substream).await {
-                            tracing::debug!(
-                                target: LOG_TARGET,
-                                ?peer,
-                                ?error,
-                                "failed to handle inbound substream",
-                            );
+                }) => {
+                    let (protocol, fallback, variant) =
+                        self.resolve_negotiated_protocol(protocol, fallback);
+
+                    match direction {
+                        protocol::Direction::Inbound => {
+                            self.inbound_variants.insert(peer, variant);
+
+                            if let Err(error) = self
+                                .on_inbound_substream(protocol, fallback, peer, substream)
+                                .await
+                            {
+                                tracing::debug!(
+                                    target: LOG_TARGET,
+                                    ?peer,
+                                    ?error,
+                                    "failed to handle inbound substream",
+                                );
+                            }
                         }
-                    }
-                    protocol::Direction::Outbound(substream_id) => {
-                        if let Err(error) = self
-                            .on_outbound_substream(protocol, fallback, peer, substream_id, substream)
-                            .await
-                        {
-                            tracing::debug!(
-                                target: LOG_TARGET,
-                                ?peer,
-                                ?error,
-                                "failed to handle outbound substream",
-                            );
+                        protocol::Direction::Outbound(substream_id) => {
+                            self.outbound_variants.insert(peer, variant);
+
+                            if let Err(error) = self
+                                .on_outbound_substream(
+                                    protocol,
+                                    fallback,
+                                    peer,
+                                    substream_id,
+                                    substream,
+                                )
+                                .await
+                            {
+                                tracing::debug!(
+                                    target: LOG_TARGET,
+                                    ?peer,
+                                    ?error,
+                                    "failed to handle outbound substream",
+                                );
+                            }
                         }
                     }
-                },
+                }
                 Some(TransportEvent::SubstreamOpenFailure { substream, error }) => {
                     self.on_substream_open_failure(substream, error).await;
                 }
                 Some(TransportEvent::DialFailure { peer, address, .. }) => self.on_dial_failure(peer, address).await,
+                Some(TransportEvent::ConnectionAddressChanged { .. }) => {}
                 None => (),
             },
             result = self.pending_validations.select_next_some(), if !self.pending_validations.is_empty() => {
@@ -1806,17 +2243,76 @@ impl NotificationProtocol {
                     NotificationCommand::ForceClose { peer } => {
                         let _ = self.service.force_close(peer);
                     }
+                    NotificationCommand::ResetPeer { peer } => {
+                        self.on_reset_peer(peer).await;
+                    }
+                    NotificationCommand::Revalidate => {
+                        self.on_revalidate();
+                    }
                 }
             },
         }
     }
 
+    /// Report peers whose state has changed since the last call to the peer state observer.
+    ///
+    /// Peers that have been removed from `peers` are considered closed.
+    fn report_state_transitions(&mut self) {
+        let Some(observer) = &self.peer_state_observer else {
+            return;
+        };
+
+        let mut transitions = Vec::new();
+
+        for (peer, context) in &self.peers {
+            let Some(new) = context.state.kind() else {
+                continue;
+            };
+            let old = self.observed_states.insert(*peer, new).unwrap_or(PeerStateKind::Closed);
+
+            if old != new {
+                transitions.push((*peer, old, new));
+            }
+        }
+
+        let peers = &self.peers;
+        self.observed_states.retain(|peer, old| {
+            if peers.contains_key(peer) {
+                return true;
+            }
+
+            if *old != PeerStateKind::Closed {
+                transitions.push((*peer, *old, PeerStateKind::Closed));
+            }
+            false
+        });
+
+        for (peer, old, new) in transitions {
+            observer.observe(peer, old, new);
+        }
+    }
+
+    /// Update the number of peers in each state shared with [`NotificationHandle`].
+    fn update_state_counts(&self) {
+        let mut counts = StateCounts::default();
+
+        for context in self.peers.values() {
+            if let Some(kind) = context.state.kind() {
+                counts.increment(kind);
+            }
+        }
+
+        *self.state_counts.write() = counts;
+    }
+
     /// Start [`NotificationProtocol`] event loop.
     pub(crate) async fn run(mut self) {
         tracing::debug!(target: LOG_TARGET, "starting notification event loop");
 
         loop {
             self.next_event().await;
+            self.report_state_transitions();
+            self.update_state_counts();
         }
     }
 }
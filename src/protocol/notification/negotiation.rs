@@ -20,7 +20,10 @@
 
 //! Implementation of the notification handshaking.
 
-use crate::{substream::Substream, PeerId};
+use crate::{
+    codec::ProtocolCodec, error::SubstreamError, protocol::notification::types::NotificationError,
+    substream::Substream, PeerId,
+};
 
 use futures::{FutureExt, Sink, Stream};
 use futures_timer::Delay;
@@ -75,6 +78,9 @@ pub enum HandshakeEvent {
 
         /// Direction.
         direction: Direction,
+
+        /// Error.
+        error: NotificationError,
     },
 }
 
@@ -98,63 +104,106 @@ pub(crate) struct HandshakeService {
     /// Handshake.
     handshake: Arc<RwLock<Vec<u8>>>,
 
+    /// Maximum size of the handshake received from remote peer.
+    max_handshake_size: Option<usize>,
+
+    /// Size of the handshake, if the handshake is sent without a length prefix.
+    fixed_handshake_size: Option<usize>,
+
     /// Pending outbound substreams.
     /// Substreams:
     substreams: HashMap<(PeerId, Direction), (Substream, Delay, HandshakeState)>,
 
+    /// Codecs of the substreams negotiating a fixed-size handshake, restored once the
+    /// substream leaves the service.
+    codecs: HashMap<(PeerId, Direction), ProtocolCodec>,
+
     /// Ready substreams.
     ready: VecDeque<(PeerId, Direction, Vec<u8>)>,
 }
 
 impl HandshakeService {
     /// Create new [`HandshakeService`].
-    pub fn new(handshake: Arc<RwLock<Vec<u8>>>) -> Self {
+    pub fn new(handshake: Arc<RwLock<Vec<u8>>>, max_handshake_size: Option<usize>) -> Self {
         Self {
             handshake,
+            max_handshake_size,
+            fixed_handshake_size: None,
             ready: VecDeque::new(),
             substreams: HashMap::new(),
+            codecs: HashMap::new(),
         }
     }
 
+    /// Send and read handshakes of exactly `handshake_size` bytes without a length prefix.
+    pub fn with_fixed_handshake_size(mut self, handshake_size: Option<usize>) -> Self {
+        self.fixed_handshake_size = handshake_size;
+        self
+    }
+
     /// Remove outbound substream from [`HandshakeService`].
     pub fn remove_outbound(&mut self, peer: &PeerId) -> Option<Substream> {
-        self.substreams
-            .remove(&(*peer, Direction::Outbound))
-            .map(|(substream, _, _)| substream)
+        self.remove(*peer, Direction::Outbound)
     }
 
     /// Remove inbound substream from [`HandshakeService`].
     pub fn remove_inbound(&mut self, peer: &PeerId) -> Option<Substream> {
-        self.substreams
-            .remove(&(*peer, Direction::Inbound))
-            .map(|(substream, _, _)| substream)
+        self.remove(*peer, Direction::Inbound)
+    }
+
+    /// Remove substream from [`HandshakeService`], restoring its codec.
+    fn remove(&mut self, peer: PeerId, direction: Direction) -> Option<Substream> {
+        let (mut substream, _, _) = self.substreams.remove(&(peer, direction))?;
+
+        if let Some(codec) = self.codecs.remove(&(peer, direction)) {
+            substream.replace_codec(codec);
+        }
+
+        Some(substream)
+    }
+
+    /// Start negotiating the handshake over `substream`.
+    fn insert(
+        &mut self,
+        peer: PeerId,
+        direction: Direction,
+        mut substream: Substream,
+        state: HandshakeState,
+    ) {
+        if let Some(handshake_size) = self.fixed_handshake_size {
+            let codec = substream.replace_codec(ProtocolCodec::Identity(handshake_size));
+            self.codecs.insert((peer, direction), codec);
+        }
+
+        self.substreams.insert(
+            (peer, direction),
+            (substream, Delay::new(NEGOTIATION_TIMEOUT), state),
+        );
     }
 
     /// Negotiate outbound handshake.
     pub fn negotiate_outbound(&mut self, peer: PeerId, substream: Substream) {
         tracing::trace!(target: LOG_TARGET, ?peer, "negotiate outbound");
 
-        self.substreams.insert(
-            (peer, Direction::Outbound),
-            (
-                substream,
-                Delay::new(NEGOTIATION_TIMEOUT),
-                HandshakeState::SendHandshake,
-            ),
+        self.insert(
+            peer,
+            Direction::Outbound,
+            substream,
+            HandshakeState::SendHandshake,
         );
     }
 
     /// Read handshake from remote peer.
-    pub fn read_handshake(&mut self, peer: PeerId, substream: Substream) {
+    pub fn read_handshake(&mut self, peer: PeerId, mut substream: Substream) {
         tracing::trace!(target: LOG_TARGET, ?peer, "read handshake");
 
-        self.substreams.insert(
-            (peer, Direction::Inbound),
-            (
-                substream,
-                Delay::new(NEGOTIATION_TIMEOUT),
-                HandshakeState::ReadHandshake,
-            ),
+        substream.set_read_limit(self.max_handshake_size);
+
+        self.insert(
+            peer,
+            Direction::Inbound,
+            substream,
+            HandshakeState::ReadHandshake,
         );
     }
 
@@ -162,13 +211,11 @@ impl HandshakeService {
     pub fn send_handshake(&mut self, peer: PeerId, substream: Substream) {
         tracing::trace!(target: LOG_TARGET, ?peer, "send handshake");
 
-        self.substreams.insert(
-            (peer, Direction::Inbound),
-            (
-                substream,
-                Delay::new(NEGOTIATION_TIMEOUT),
-                HandshakeState::SendHandshake,
-            ),
+        self.insert(
+            peer,
+            Direction::Inbound,
+            substream,
+            HandshakeState::SendHandshake,
         );
     }
 
@@ -183,7 +230,7 @@ impl HandshakeService {
     /// by `NotificationProtocol` if either one of the substreams failed to negotiate.
     fn pop_event(&mut self) -> Option<(PeerId, HandshakeEvent)> {
         while let Some((peer, direction, handshake)) = self.ready.pop_front() {
-            if let Some((substream, _, _)) = self.substreams.remove(&(peer, direction)) {
+            if let Some(substream) = self.remove(peer, direction) {
                 return Some((
                     peer,
                     HandshakeEvent::Negotiated {
@@ -223,6 +270,7 @@ impl Stream for HandshakeService {
                     HandshakeEvent::NegotiationError {
                         peer: *peer,
                         direction: *direction,
+                        error: NotificationError::Rejected,
                     },
                 )));
             }
@@ -242,6 +290,7 @@ impl Stream for HandshakeService {
                                 HandshakeEvent::NegotiationError {
                                     peer: *peer,
                                     direction: *direction,
+                                    error: NotificationError::Rejected,
                                 },
                             ))),
                         Poll::Pending => continue 'outer,
@@ -258,6 +307,7 @@ impl Stream for HandshakeService {
                                     HandshakeEvent::NegotiationError {
                                         peer: *peer,
                                         direction: *direction,
+                                        error: NotificationError::Rejected,
                                     },
                                 ))),
                         }
@@ -265,6 +315,7 @@ impl Stream for HandshakeService {
                     HandshakeState::HandshakeSent => match pinned.poll_flush(cx) {
                         Poll::Ready(Ok(())) => match direction {
                             Direction::Outbound => {
+                                substream.set_read_limit(inner.max_handshake_size);
                                 *state = HandshakeState::ReadHandshake;
                                 continue;
                             }
@@ -279,21 +330,47 @@ impl Stream for HandshakeService {
                                 HandshakeEvent::NegotiationError {
                                     peer: *peer,
                                     direction: *direction,
+                                    error: NotificationError::Rejected,
                                 },
                             ))),
                         Poll::Pending => continue 'outer,
                     },
                     HandshakeState::ReadHandshake => match pinned.poll_next(cx) {
-                        Poll::Ready(Some(Ok(handshake))) => {
+                        Poll::Ready(Some(Ok(handshake)))
+                            if inner
+                                .max_handshake_size
+                                .map_or(true, |max_size| handshake.len() <= max_size) =>
+                        {
+                            substream.set_read_limit(None);
                             inner.ready.push_back((*peer, *direction, handshake.freeze().into()));
                             continue 'outer;
                         }
+                        Poll::Ready(Some(Ok(_)))
+                        | Poll::Ready(Some(Err(SubstreamError::FrameTooLarge(_)))) => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?peer,
+                                ?direction,
+                                max_handshake_size = ?inner.max_handshake_size,
+                                "remote sent a handshake that is too large",
+                            );
+
+                            return Poll::Ready(Some((
+                                *peer,
+                                HandshakeEvent::NegotiationError {
+                                    peer: *peer,
+                                    direction: *direction,
+                                    error: NotificationError::HandshakeTooLarge,
+                                },
+                            )));
+                        }
                         Poll::Ready(Some(Err(_))) | Poll::Ready(None) => {
                             return Poll::Ready(Some((
                                 *peer,
                                 HandshakeEvent::NegotiationError {
                                     peer: *peer,
                                     direction: *direction,
+                                    error: NotificationError::Rejected,
                                 },
                             )));
                         }
@@ -304,8 +381,7 @@ impl Stream for HandshakeService {
         }
 
         if let Some((peer, direction, handshake)) = inner.ready.pop_front() {
-            let (substream, _, _) =
-                inner.substreams.remove(&(peer, direction)).expect("peer to exist");
+            let substream = inner.remove(peer, direction).expect("peer to exist");
 
             return Poll::Ready(Some((
                 peer,
@@ -326,14 +402,20 @@ impl Stream for HandshakeService {
 mod tests {
     use super::*;
     use crate::{
-        mock::substream::{DummySubstream, MockSubstream},
+        mock::substream::{paired, DummySubstream, MockSubstream},
+        multistream_select::Negotiated,
+        protocol::connection::Permit,
+        transport::tcp,
         types::SubstreamId,
+        BandwidthSink,
     };
-    use futures::StreamExt;
+    use bytes::BytesMut;
+    use futures::{AsyncWriteExt, StreamExt};
+    use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
 
     #[tokio::test]
     async fn substream_error_when_sending_handshake() {
-        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])));
+        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])), None);
 
         futures::future::poll_fn(|cx| match service.poll_next_unpin(cx) {
             Poll::Pending => Poll::Ready(()),
@@ -358,6 +440,7 @@ mod tests {
                 HandshakeEvent::NegotiationError {
                     peer: event_peer,
                     direction,
+                    ..
                 },
             )) => {
                 assert_eq!(failed_peer, peer);
@@ -370,7 +453,7 @@ mod tests {
 
     #[tokio::test]
     async fn substream_error_when_flushing_substream() {
-        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])));
+        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])), None);
 
         futures::future::poll_fn(|cx| match service.poll_next_unpin(cx) {
             Poll::Pending => Poll::Ready(()),
@@ -396,6 +479,7 @@ mod tests {
                 HandshakeEvent::NegotiationError {
                     peer: event_peer,
                     direction,
+                    ..
                 },
             )) => {
                 assert_eq!(failed_peer, peer);
@@ -410,7 +494,7 @@ mod tests {
     // negotiate
     #[tokio::test]
     async fn pop_event_but_substream_doesnt_exist() {
-        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])));
+        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])), None);
         let peer = PeerId::random();
 
         // inbound substream has finished
@@ -451,4 +535,253 @@ mod tests {
         })
         .await
     }
+
+    #[tokio::test]
+    async fn handshake_too_large() {
+        let mut service =
+            HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])), Some(128usize));
+
+        let mut substream = MockSubstream::new();
+        substream
+            .expect_poll_next()
+            .times(1)
+            .return_once(|_| Poll::Ready(Some(Ok(BytesMut::zeroed(129)))));
+
+        let peer = PeerId::random();
+        let substream = Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(substream));
+
+        service.read_handshake(peer, substream);
+        match service.next().await {
+            Some((
+                failed_peer,
+                HandshakeEvent::NegotiationError {
+                    peer: event_peer,
+                    direction,
+                    error,
+                },
+            )) => {
+                assert_eq!(failed_peer, peer);
+                assert_eq!(event_peer, peer);
+                assert_eq!(direction, Direction::Inbound);
+                assert_eq!(error, NotificationError::HandshakeTooLarge);
+            }
+            _ => panic!("invalid event received"),
+        }
+    }
+
+    #[tokio::test]
+    async fn handshake_too_large_rejected_before_payload_is_read() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (dialer, listener) = tokio::io::duplex(1024);
+        let mut dialer = crate::yamux::Connection::new(
+            TokioAsyncReadCompatExt::compat(dialer),
+            crate::yamux::Config::default(),
+            crate::yamux::Mode::Client,
+        );
+        let mut listener = crate::yamux::Connection::new(
+            TokioAsyncReadCompatExt::compat(listener),
+            crate::yamux::Config::default(),
+            crate::yamux::Mode::Server,
+        );
+
+        let mut outbound =
+            futures::future::poll_fn(|cx| dialer.poll_new_outbound(cx)).await.unwrap();
+        tokio::spawn(async move {
+            while let Some(Ok(_)) =
+                futures::future::poll_fn(|cx| dialer.poll_next_inbound(cx)).await
+            {}
+        });
+
+        // announce a 512-byte handshake which is within the notification size limit but exceeds
+        // the handshake size limit and never send the payload itself
+        outbound.write_all(&[0x80, 0x04]).await.unwrap();
+        outbound.flush().await.unwrap();
+
+        let inbound = futures::future::poll_fn(|cx| listener.poll_next_inbound(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::spawn(async move {
+            while let Some(Ok(_)) =
+                futures::future::poll_fn(|cx| listener.poll_next_inbound(cx)).await
+            {}
+        });
+
+        let peer = PeerId::random();
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        let substream = Substream::new_tcp(
+            peer,
+            SubstreamId::from(0usize),
+            tcp::Substream::new(
+                FuturesAsyncReadCompatExt::compat(Negotiated::completed(inbound)),
+                BandwidthSink::new(),
+                Permit::new(tx),
+            ),
+            ProtocolCodec::UnsignedVarint(Some(1024usize)),
+        );
+
+        let mut service =
+            HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3, 4])), Some(128usize));
+        service.read_handshake(peer, substream);
+
+        // if the substream tried to buffer the handshake, it'd wait for the payload that is never
+        // sent and the negotiation would time out instead
+        match tokio::time::timeout(Duration::from_secs(5), service.next())
+            .await
+            .expect("oversized handshake to be rejected without reading the payload")
+        {
+            Some((
+                _,
+                HandshakeEvent::NegotiationError {
+                    peer: event_peer,
+                    direction,
+                    error,
+                },
+            )) => {
+                assert_eq!(event_peer, peer);
+                assert_eq!(direction, Direction::Inbound);
+                assert_eq!(error, NotificationError::HandshakeTooLarge);
+            }
+            _ => panic!("invalid event received"),
+        }
+
+        drop(outbound);
+    }
+
+    #[tokio::test]
+    async fn handshake_over_paired_substreams() {
+        let mut dialer = HandshakeService::new(Arc::new(RwLock::new(vec![1, 2, 3])), None);
+        let mut listener = HandshakeService::new(Arc::new(RwLock::new(vec![4, 5, 6])), None);
+
+        let dialer_peer = PeerId::random();
+        let listener_peer = PeerId::random();
+        let (outbound, inbound) = paired();
+
+        dialer.negotiate_outbound(
+            listener_peer,
+            Substream::new_mock(listener_peer, SubstreamId::from(0usize), Box::new(outbound)),
+        );
+        listener.read_handshake(
+            dialer_peer,
+            Substream::new_mock(dialer_peer, SubstreamId::from(0usize), Box::new(inbound)),
+        );
+
+        let mut dialer_open = false;
+        let mut listener_open = false;
+
+        while !dialer_open || !listener_open {
+            tokio::select! {
+                event = dialer.next() => match event.unwrap() {
+                    (peer, HandshakeEvent::Negotiated { handshake, direction, .. }) => {
+                        assert_eq!(peer, listener_peer);
+                        assert_eq!(direction, Direction::Outbound);
+                        assert_eq!(handshake, vec![4, 5, 6]);
+                        dialer_open = true;
+                    }
+                    event => panic!("invalid event: {event:?}"),
+                },
+                event = listener.next() => match event.unwrap() {
+                    (peer, HandshakeEvent::Negotiated { handshake, substream, direction, .. }) => {
+                        assert_eq!(peer, dialer_peer);
+                        assert_eq!(direction, Direction::Inbound);
+
+                        // first the handshake of the dialer is read, then the substream is
+                        // accepted by sending back the local handshake
+                        match handshake.is_empty() {
+                            false => {
+                                assert_eq!(handshake, vec![1, 2, 3]);
+                                listener.send_handshake(peer, substream);
+                            }
+                            true => listener_open = true,
+                        }
+                    }
+                    event => panic!("invalid event: {event:?}"),
+                },
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn fixed_size_handshake_read_raw() {
+        let peer = PeerId::random();
+        let (local, mut remote) = tokio::io::duplex(1024);
+        let substream = Substream::new_duplex(
+            peer,
+            SubstreamId::from(0usize),
+            local,
+            ProtocolCodec::UnsignedVarint(Some(1024usize)),
+        );
+
+        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1u8; 32])), None)
+            .with_fixed_handshake_size(Some(32usize));
+        service.read_handshake(peer, substream);
+
+        // handshake is sent without a length prefix and followed by a regular notification
+        let handshake = (0..32u8).collect::<Vec<_>>();
+        tokio::io::AsyncWriteExt::write_all(&mut remote, &handshake).await.unwrap();
+        tokio::io::AsyncWriteExt::write_all(&mut remote, &[3, 7, 8, 9]).await.unwrap();
+
+        match service.next().await {
+            Some((
+                _,
+                HandshakeEvent::Negotiated {
+                    handshake: received,
+                    mut substream,
+                    direction,
+                    ..
+                },
+            )) => {
+                assert_eq!(direction, Direction::Inbound);
+                assert_eq!(received, handshake);
+
+                // codec of the protocol is restored once the handshake has been read
+                assert_eq!(
+                    substream.next().await.unwrap().unwrap(),
+                    BytesMut::from(&[7u8, 8, 9][..])
+                );
+            }
+            event => panic!("invalid event received: {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn short_fixed_size_handshake_rejected() {
+        let peer = PeerId::random();
+        let (local, mut remote) = tokio::io::duplex(1024);
+        let substream = Substream::new_duplex(
+            peer,
+            SubstreamId::from(0usize),
+            local,
+            ProtocolCodec::UnsignedVarint(Some(1024usize)),
+        );
+
+        let mut service = HandshakeService::new(Arc::new(RwLock::new(vec![1u8; 32])), None)
+            .with_fixed_handshake_size(Some(32usize));
+        service.read_handshake(peer, substream);
+
+        tokio::io::AsyncWriteExt::write_all(&mut remote, &[0xaa; 16]).await.unwrap();
+        drop(remote);
+
+        match tokio::time::timeout(Duration::from_secs(5), service.next())
+            .await
+            .expect("short handshake to be rejected")
+        {
+            Some((
+                _,
+                HandshakeEvent::NegotiationError {
+                    peer: event_peer,
+                    direction,
+                    error,
+                },
+            )) => {
+                assert_eq!(event_peer, peer);
+                assert_eq!(direction, Direction::Inbound);
+                assert_eq!(error, NotificationError::Rejected);
+            }
+            event => panic!("invalid event received: {event:?}"),
+        }
+    }
 }
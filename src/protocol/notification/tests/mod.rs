@@ -21,7 +21,7 @@
 use std::collections::HashSet;
 
 use crate::{
-    crypto::ed25519::Keypair,
+    crypto::Keypair,
     executor::DefaultExecutor,
     protocol::{
         notification::{
@@ -30,7 +30,7 @@ use crate::{
         InnerTransportEvent, ProtocolCommand, TransportService,
     },
     transport::{
-        manager::{limits::ConnectionLimitsConfig, TransportManager},
+        manager::{limits::ConnectionLimitsConfig, reputation::ReputationConfig, TransportManager},
         KEEP_ALIVE_TIMEOUT,
     },
     types::protocol::ProtocolName,
@@ -52,11 +52,12 @@ fn make_notification_protocol() -> (
     Sender<InnerTransportEvent>,
 ) {
     let (manager, handle) = TransportManager::new(
-        Keypair::generate(),
+        Keypair::generate_ed25519(),
         HashSet::new(),
         BandwidthSink::new(),
         8usize,
         ConnectionLimitsConfig::default(),
+        ReputationConfig::default(),
     );
 
     let peer = PeerId::random();
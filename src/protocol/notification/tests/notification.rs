@@ -19,16 +19,22 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    mock::substream::{DummySubstream, MockSubstream},
+    codec::ProtocolCodec,
+    mock::substream::{paired, DummySubstream, MockSubstream},
     protocol::{
         self,
         connection::ConnectionHandle,
         notification::{
+            connection::{Connection, OutboundQueue},
+            handle::NotificationEventHandle,
             negotiation::HandshakeEvent,
             tests::make_notification_protocol,
-            types::{Direction, NotificationError, NotificationEvent},
-            ConnectionState, InboundState, NotificationProtocol, OutboundState, PeerContext,
-            PeerState, ValidationResult,
+            types::{
+                Direction, InnerNotificationEvent, NotificationError, NotificationEvent,
+                PeerStateKind, QueuePolicy, StateCounts,
+            },
+            ConnectionState, InboundState, NotificationProtocol, NotificationSink, OutboundState,
+            PeerContext, PeerState, ValidationResult,
         },
         InnerTransportEvent, ProtocolCommand, SubstreamError,
     },
@@ -38,14 +44,25 @@ use crate::{
     PeerId,
 };
 
-use futures::StreamExt;
+use bytes::{Bytes, BytesMut};
+use futures::{SinkExt, StreamExt};
 use multiaddr::Multiaddr;
 use tokio::sync::{
     mpsc::{channel, Receiver, Sender},
     oneshot,
 };
 
-use std::{task::Poll, time::Duration};
+use parking_lot::{Mutex, RwLock};
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::Poll,
+    time::Duration,
+};
 
 fn next_inbound_state(state: usize) -> InboundState {
     match state {
@@ -1115,3 +1132,927 @@ async fn second_inbound_substream_opened_while_outbound_substream_was_opening()
         state => panic!("invalid state for peer: {state:?}"),
     }
 }
+
+#[tokio::test]
+async fn notifications_from_all_peers_are_aggregated() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (config, mut handle) = protocol::notification::Config::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let peer1 = PeerId::random();
+    let peer2 = PeerId::random();
+
+    for peer in [peer1, peer2] {
+        let (sync_tx, _sync_rx) = channel(64);
+        let (async_tx, _async_rx) = channel(64);
+
+        config
+            .event_tx
+            .send(InnerNotificationEvent::NotificationStreamOpened {
+                protocol: ProtocolName::from("/notif/1"),
+                fallback: None,
+                direction: Direction::Inbound,
+                peer,
+                handshake: vec![1, 2, 3, 4],
+                sink: NotificationSink::new(
+                    peer,
+                    sync_tx,
+                    async_tx,
+                    Default::default(),
+                    Default::default(),
+                ),
+                read_tx: None,
+                reopened: false,
+            })
+            .await
+            .unwrap();
+    }
+
+    config.notif_tx.send((peer1, BytesMut::from(&b"hello"[..]))).await.unwrap();
+    config.notif_tx.send((peer2, BytesMut::from(&b"world"[..]))).await.unwrap();
+    config.notif_tx.send((peer1, BytesMut::from(&b"again"[..]))).await.unwrap();
+
+    let mut notifications = handle.notifications();
+    assert_eq!(
+        notifications.next().await.unwrap(),
+        (peer1, b"hello".to_vec())
+    );
+    assert_eq!(
+        notifications.next().await.unwrap(),
+        (peer2, b"world".to_vec())
+    );
+    assert_eq!(
+        notifications.next().await.unwrap(),
+        (peer1, b"again".to_vec())
+    );
+
+    // events received while polling the aggregated stream are not lost
+    for peer in [peer1, peer2] {
+        match handle.next().await {
+            Some(NotificationEvent::NotificationStreamOpened {
+                peer: opened_peer, ..
+            }) => assert_eq!(opened_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+    }
+    assert!(handle.notification_sink(peer1).is_some());
+    assert!(handle.notification_sink(peer2).is_some());
+}
+
+#[tokio::test]
+async fn aggregated_stream_rejects_validations_and_bounds_buffered_events() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (config, mut handle) = protocol::notification::Config::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let peer = PeerId::random();
+    let (tx, rx) = oneshot::channel();
+
+    config
+        .event_tx
+        .send(InnerNotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer,
+            handshake: vec![1, 2, 3, 4],
+            tx,
+        })
+        .await
+        .unwrap();
+    for _ in 0..70 {
+        config
+            .event_tx
+            .send(InnerNotificationEvent::NotificationStreamClosed {
+                peer: PeerId::random(),
+            })
+            .await
+            .unwrap();
+    }
+    config.notif_tx.send((peer, BytesMut::from(&b"hello"[..]))).await.unwrap();
+
+    let mut notifications = handle.notifications();
+    assert_eq!(
+        notifications.next().await.unwrap(),
+        (peer, b"hello".to_vec())
+    );
+
+    // validation was rejected and events beyond the buffer limit are left in the channel
+    assert_eq!(rx.await.unwrap(), ValidationResult::Reject);
+    assert_eq!(
+        config.event_tx.capacity(),
+        config.event_tx.max_capacity() - 6
+    );
+
+    for _ in 0..70 {
+        match handle.next().await {
+            Some(NotificationEvent::NotificationStreamClosed { .. }) => {}
+            event => panic!("invalid event: {event:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn peer_handshake_available_while_stream_is_open() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (config, mut handle) = protocol::notification::Config::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let peer = PeerId::random();
+    let (sync_tx, _sync_rx) = channel(64);
+    let (async_tx, _async_rx) = channel(64);
+
+    assert_eq!(handle.peer_handshake(peer), None);
+
+    config
+        .event_tx
+        .send(InnerNotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            direction: Direction::Outbound,
+            peer,
+            handshake: vec![1, 3, 3, 7],
+            sink: NotificationSink::new(
+                peer,
+                sync_tx,
+                async_tx,
+                Default::default(),
+                Default::default(),
+            ),
+            read_tx: None,
+            reopened: false,
+        })
+        .await
+        .unwrap();
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamOpened { handshake, .. }) =>
+            assert_eq!(handshake, vec![1, 3, 3, 7]),
+        event => panic!("invalid event: {event:?}"),
+    }
+    assert_eq!(handle.peer_handshake(peer), Some(vec![1, 3, 3, 7]));
+
+    config
+        .event_tx
+        .send(InnerNotificationEvent::NotificationStreamClosed { peer })
+        .await
+        .unwrap();
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamClosed { peer: closed_peer }) =>
+            assert_eq!(closed_peer, peer),
+        event => panic!("invalid event: {event:?}"),
+    }
+    assert_eq!(handle.peer_handshake(peer), None);
+}
+
+#[tokio::test]
+async fn oldest_notification_dropped_when_queue_full() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let peer = PeerId::random();
+    let sent = Arc::new(Mutex::new(Vec::new()));
+
+    // outbound substream is not writable until all notifications have been sent
+    let mut outbound = MockSubstream::new();
+    outbound.expect_poll_ready().times(1).return_once(|_| Poll::Pending);
+    outbound.expect_poll_ready().returning(|_| Poll::Ready(Ok(())));
+    outbound.expect_start_send().returning({
+        let sent = Arc::clone(&sent);
+        move |notification| {
+            sent.lock().push(notification);
+            Ok(())
+        }
+    });
+    outbound.expect_poll_flush().returning(|_| Poll::Ready(Ok(())));
+
+    let mut inbound = MockSubstream::new();
+    inbound.expect_poll_next().returning(|_| Poll::Pending);
+
+    let (event_tx, _event_rx) = channel(64);
+    let (conn_closed_tx, _conn_closed_rx) = channel(64);
+    let (notif_tx, _notif_rx) = channel(64);
+    let (async_tx, async_rx) = channel(64);
+    let (sync_tx, sync_rx) = channel(64);
+    let dropped = Arc::new(AtomicUsize::new(0usize));
+    let sink = NotificationSink::new(
+        peer,
+        sync_tx,
+        async_tx,
+        Arc::clone(&dropped),
+        Default::default(),
+    );
+
+    let (mut connection, _shutdown) = Connection::new(
+        peer,
+        Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(inbound)),
+        Substream::new_mock(peer, SubstreamId::from(1usize), Box::new(outbound)),
+        NotificationEventHandle::new(event_tx),
+        conn_closed_tx,
+        notif_tx,
+        async_rx,
+        sync_rx,
+        OutboundQueue::new(2, QueuePolicy::DropOldest, dropped),
+    );
+
+    for notification in [vec![1u8], vec![2u8], vec![3u8]] {
+        sink.send_sync_notification(notification).unwrap();
+    }
+
+    // the queue holds two notifications so the oldest one is discarded
+    assert!(futures::poll!(connection.next()).is_pending());
+    assert_eq!(sink.dropped_notifications(), 1);
+    assert!(sent.lock().is_empty());
+
+    // substream becomes writable and the queued notifications are sent
+    assert!(futures::poll!(connection.next()).is_pending());
+    assert_eq!(
+        *sent.lock(),
+        vec![Bytes::from(vec![2u8]), Bytes::from(vec![3u8])]
+    );
+    assert_eq!(sink.dropped_notifications(), 1);
+}
+
+#[tokio::test]
+async fn flushed_notification_waits_for_stalled_substream() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let peer = PeerId::random();
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let writable = Arc::new(AtomicBool::new(false));
+
+    // outbound substream is stalled until `writable` is set
+    let mut outbound = MockSubstream::new();
+    outbound.expect_poll_ready().returning({
+        let writable = Arc::clone(&writable);
+        move |_| match writable.load(Ordering::SeqCst) {
+            true => Poll::Ready(Ok(())),
+            false => Poll::Pending,
+        }
+    });
+    outbound.expect_start_send().returning({
+        let sent = Arc::clone(&sent);
+        move |notification| {
+            sent.lock().push(notification);
+            Ok(())
+        }
+    });
+    outbound.expect_poll_flush().returning(|_| Poll::Ready(Ok(())));
+
+    let mut inbound = MockSubstream::new();
+    inbound.expect_poll_next().returning(|_| Poll::Pending);
+
+    let (event_tx, _event_rx) = channel(64);
+    let (conn_closed_tx, _conn_closed_rx) = channel(64);
+    let (notif_tx, _notif_rx) = channel(64);
+    let (async_tx, async_rx) = channel(64);
+    let (sync_tx, sync_rx) = channel(64);
+    let dropped = Arc::new(AtomicUsize::new(0usize));
+    let sink = NotificationSink::new(
+        peer,
+        sync_tx,
+        async_tx,
+        Arc::clone(&dropped),
+        Default::default(),
+    );
+
+    let (mut connection, _shutdown) = Connection::new(
+        peer,
+        Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(inbound)),
+        Substream::new_mock(peer, SubstreamId::from(1usize), Box::new(outbound)),
+        NotificationEventHandle::new(event_tx),
+        conn_closed_tx,
+        notif_tx,
+        async_rx,
+        sync_rx,
+        OutboundQueue::new(8, QueuePolicy::Block, dropped),
+    );
+
+    // queued variant resolves even though the substream is stalled
+    sink.send_async_notification(vec![1u8]).await.unwrap();
+
+    let flushed = sink.send_async_notification_flushed(vec![2u8]);
+    futures::pin_mut!(flushed);
+
+    assert!(futures::poll!(&mut flushed).is_pending());
+    assert!(futures::poll!(connection.next()).is_pending());
+    assert!(futures::poll!(&mut flushed).is_pending());
+    assert!(sent.lock().is_empty());
+
+    // substream drains and the flushed variant resolves
+    writable.store(true, Ordering::SeqCst);
+    assert!(futures::poll!(connection.next()).is_pending());
+    assert_eq!(
+        *sent.lock(),
+        vec![Bytes::from(vec![1u8]), Bytes::from(vec![2u8])]
+    );
+    assert!(std::matches!(
+        futures::poll!(&mut flushed),
+        Poll::Ready(Ok(()))
+    ));
+}
+
+#[tokio::test]
+async fn stalled_peer_closed_after_stall_timeout() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let peer = PeerId::random();
+
+    // remote never reads so the outbound substream never becomes writable
+    let mut outbound = MockSubstream::new();
+    outbound.expect_poll_ready().returning(|_| Poll::Pending);
+    outbound.expect_poll_flush().returning(|_| Poll::Ready(Ok(())));
+    outbound.expect_poll_close().times(1).return_once(|_| Poll::Ready(Ok(())));
+
+    let mut inbound = MockSubstream::new();
+    inbound.expect_poll_next().returning(|_| Poll::Pending);
+    inbound.expect_poll_close().times(1).return_once(|_| Poll::Ready(Ok(())));
+
+    let (event_tx, mut event_rx) = channel(64);
+    let (conn_closed_tx, mut conn_closed_rx) = channel(64);
+    let (notif_tx, _notif_rx) = channel(64);
+    let (async_tx, async_rx) = channel(64);
+    let (sync_tx, sync_rx) = channel(64);
+    let dropped = Arc::new(AtomicUsize::new(0usize));
+    let stalled_peers = Arc::new(RwLock::new(HashMap::new()));
+    let sink = NotificationSink::new(
+        peer,
+        sync_tx,
+        async_tx,
+        Arc::clone(&dropped),
+        Default::default(),
+    );
+
+    let (connection, _shutdown) = Connection::new(
+        peer,
+        Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(inbound)),
+        Substream::new_mock(peer, SubstreamId::from(1usize), Box::new(outbound)),
+        NotificationEventHandle::new(event_tx),
+        conn_closed_tx,
+        notif_tx,
+        async_rx,
+        sync_rx,
+        OutboundQueue::new(8, QueuePolicy::Block, dropped),
+    );
+    let mut connection = connection
+        .with_stall_tracking(Arc::clone(&stalled_peers), Some(Duration::from_millis(200)));
+
+    sink.send_async_notification(vec![1u8]).await.unwrap();
+
+    // the peer is reported as stalled before the timeout expires
+    assert!(futures::poll!(connection.next()).is_pending());
+    assert!(stalled_peers.read().contains_key(&peer));
+
+    tokio::time::timeout(Duration::from_secs(5), connection.start())
+        .await
+        .expect("stalled connection to be closed");
+
+    assert_eq!(conn_closed_rx.try_recv(), Ok(peer));
+    assert!(std::matches!(
+        event_rx.try_recv(),
+        Ok(InnerNotificationEvent::NotificationStreamClosed { peer: closed }) if closed == peer
+    ));
+    assert!(stalled_peers.read().is_empty());
+}
+
+#[tokio::test]
+async fn flushed_notification_fails_when_discarded() {
+    let peer = PeerId::random();
+
+    let mut outbound = MockSubstream::new();
+    outbound.expect_poll_ready().returning(|_| Poll::Pending);
+    outbound.expect_poll_flush().returning(|_| Poll::Ready(Ok(())));
+
+    let mut inbound = MockSubstream::new();
+    inbound.expect_poll_next().returning(|_| Poll::Pending);
+
+    let (event_tx, _event_rx) = channel(64);
+    let (conn_closed_tx, _conn_closed_rx) = channel(64);
+    let (notif_tx, _notif_rx) = channel(64);
+    let (async_tx, async_rx) = channel(64);
+    let (sync_tx, sync_rx) = channel(64);
+    let dropped = Arc::new(AtomicUsize::new(0usize));
+    let sink = NotificationSink::new(
+        peer,
+        sync_tx,
+        async_tx,
+        Arc::clone(&dropped),
+        Default::default(),
+    );
+
+    let (mut connection, _shutdown) = Connection::new(
+        peer,
+        Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(inbound)),
+        Substream::new_mock(peer, SubstreamId::from(1usize), Box::new(outbound)),
+        NotificationEventHandle::new(event_tx),
+        conn_closed_tx,
+        notif_tx,
+        async_rx,
+        sync_rx,
+        OutboundQueue::new(1, QueuePolicy::DropOldest, dropped),
+    );
+
+    let flushed = sink.send_async_notification_flushed(vec![1u8]);
+    futures::pin_mut!(flushed);
+    assert!(futures::poll!(&mut flushed).is_pending());
+
+    // newer notification pushes the pending one out of the queue
+    sink.send_async_notification(vec![2u8]).await.unwrap();
+    assert!(futures::poll!(connection.next()).is_pending());
+
+    assert_eq!(sink.dropped_notifications(), 1);
+    assert!(std::matches!(
+        futures::poll!(&mut flushed),
+        Poll::Ready(Err(crate::Error::PeerDoesntExist(_)))
+    ));
+}
+
+#[tokio::test]
+async fn reset_peer_stuck_in_validation() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (mut notif, mut handle, _sender, mut tx) = make_notification_protocol();
+    let (peer, _receiver) = register_peer(&mut notif, &mut tx).await;
+
+    notif.peers.get_mut(&peer).unwrap().state = PeerState::Validating {
+        direction: Direction::Inbound,
+        protocol: ProtocolName::from("/notif/1"),
+        fallback: None,
+        outbound: OutboundState::OutboundInitiated {
+            substream: SubstreamId::new(),
+        },
+        inbound: InboundState::Validating {
+            inbound: Substream::new_mock(
+                peer,
+                SubstreamId::from(0usize),
+                Box::new(MockSubstream::new()),
+            ),
+        },
+    };
+
+    handle.reset_peer(peer).await;
+    notif.next_event().await;
+
+    assert!(std::matches!(
+        notif.peers.get(&peer),
+        Some(PeerContext {
+            state: PeerState::Closed {
+                pending_open: Some(_)
+            }
+        })
+    ));
+    assert_eq!(
+        handle.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer,
+            error: NotificationError::Reset,
+        }
+    );
+
+    // resetting a peer which is not under validation has no effect
+    handle.reset_peer(peer).await;
+    notif.next_event().await;
+
+    assert!(std::matches!(
+        notif.peers.get(&peer),
+        Some(PeerContext {
+            state: PeerState::Closed {
+                pending_open: Some(_)
+            }
+        })
+    ));
+    futures::future::poll_fn(|cx| match handle.poll_next_unpin(cx) {
+        Poll::Pending => Poll::Ready(()),
+        Poll::Ready(event) => panic!("invalid event received: {event:?}"),
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn state_counts_match_peer_states() {
+    let (mut notif, handle, _sender, _tx) = make_notification_protocol();
+    let (shutdown, _shutdown_rx) = oneshot::channel();
+
+    let states = vec![
+        PeerState::Closed { pending_open: None },
+        PeerState::Closed {
+            pending_open: Some(SubstreamId::new()),
+        },
+        PeerState::Dialing,
+        PeerState::OutboundInitiated {
+            substream: SubstreamId::new(),
+        },
+        PeerState::Validating {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            direction: Direction::Inbound,
+            outbound: OutboundState::Closed,
+            inbound: InboundState::ReadingHandshake,
+        },
+        PeerState::ValidationPending {
+            state: ConnectionState::Closed,
+        },
+        PeerState::Open { shutdown },
+        PeerState::Poisoned,
+    ];
+
+    for state in states {
+        notif.peers.insert(PeerId::random(), PeerContext { state });
+    }
+
+    // counts are only updated once the protocol has processed an event
+    assert_eq!(handle.state_counts(), StateCounts::default());
+
+    notif.update_state_counts();
+    assert_eq!(
+        handle.state_counts(),
+        StateCounts {
+            closed: 2,
+            dialing: 1,
+            outbound_initiated: 1,
+            validating: 1,
+            validation_pending: 1,
+            open: 1,
+        }
+    );
+    assert_eq!(handle.state_counts().get(PeerStateKind::Closed), 2);
+    assert_eq!(handle.state_counts().get(PeerStateKind::Open), 1);
+
+    // removed peers are no longer counted
+    notif
+        .peers
+        .retain(|_, context| !std::matches!(context.state, PeerState::Closed { .. }));
+    notif.update_state_counts();
+    assert_eq!(handle.state_counts().closed, 0);
+    assert_eq!(handle.state_counts().open, 1);
+}
+
+#[tokio::test]
+async fn reserved_peer_stream_reopened_after_close() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (mut notif, mut handle, _manager, mut tx) = make_notification_protocol();
+    let (peer, mut receiver) = register_peer(&mut notif, &mut tx).await;
+    notif.event_handle = notif.event_handle.clone().with_reserved_peers(HashSet::from([peer]));
+
+    let make_sink = || {
+        let (sync_tx, sync_rx) = channel(64);
+        let (async_tx, async_rx) = channel(64);
+        let sink = NotificationSink::new(
+            peer,
+            sync_tx,
+            async_tx,
+            Default::default(),
+            Default::default(),
+        );
+        (sink, sync_rx, async_rx)
+    };
+
+    // open the notification stream
+    let (shutdown, _shutdown_rx) = oneshot::channel();
+    notif.peers.get_mut(&peer).unwrap().state = PeerState::Open { shutdown };
+
+    let (sink, sync_rx, async_rx) = make_sink();
+    notif
+        .event_handle
+        .report_notification_stream_opened(
+            ProtocolName::from("/notif/1"),
+            None,
+            Direction::Outbound,
+            peer,
+            vec![1, 3, 3, 7],
+            sink,
+            None,
+        )
+        .await;
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamOpened { peer: opened, .. }) =>
+            assert_eq!(opened, peer),
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    // remote closes its inbound substream
+    let mut inbound = MockSubstream::new();
+    inbound.expect_poll_next().returning(|_| Poll::Ready(None));
+    inbound.expect_poll_close().times(1).return_once(|_| Poll::Ready(Ok(())));
+
+    let mut outbound = MockSubstream::new();
+    outbound.expect_poll_flush().returning(|_| Poll::Ready(Ok(())));
+    outbound.expect_poll_close().times(1).return_once(|_| Poll::Ready(Ok(())));
+
+    let (notif_tx, _notif_rx) = channel(64);
+    let (connection, _shutdown) = Connection::new(
+        peer,
+        Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(inbound)),
+        Substream::new_mock(peer, SubstreamId::from(1usize), Box::new(outbound)),
+        notif.event_handle.clone(),
+        notif.shutdown_tx.clone(),
+        notif_tx,
+        async_rx,
+        sync_rx,
+        OutboundQueue::new(8, QueuePolicy::Block, Default::default()),
+    );
+    tokio::time::timeout(Duration::from_secs(5), connection.start())
+        .await
+        .expect("connection to be closed");
+
+    // closure is not reported while the stream is being re-opened
+    assert!(futures::poll!(handle.next()).is_pending());
+
+    notif.next_event().await;
+
+    assert!(std::matches!(
+        notif.peers.get(&peer),
+        Some(PeerContext {
+            state: PeerState::OutboundInitiated { .. }
+        })
+    ));
+    assert!(std::matches!(
+        receiver.try_recv(),
+        Ok(ProtocolCommand::OpenSubstream { .. })
+    ));
+
+    let (sink, _sync_rx, _async_rx) = make_sink();
+    notif
+        .event_handle
+        .report_notification_stream_opened(
+            ProtocolName::from("/notif/1"),
+            None,
+            Direction::Outbound,
+            peer,
+            vec![1, 3, 3, 8],
+            sink,
+            None,
+        )
+        .await;
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamReopened {
+            peer: reopened,
+            direction,
+            handshake,
+        }) => {
+            assert_eq!(reopened, peer);
+            assert_eq!(direction, Direction::Outbound);
+            assert_eq!(handshake, vec![1, 3, 3, 8]);
+        }
+        event => panic!("invalid event: {event:?}"),
+    }
+    assert_eq!(handle.peer_handshake(peer), Some(vec![1, 3, 3, 8]));
+
+    // stream is closed again but this time the re-open fails
+    let (shutdown, _shutdown_rx) = oneshot::channel();
+    notif.peers.get_mut(&peer).unwrap().state = PeerState::Open { shutdown };
+
+    assert!(notif.event_handle.start_reopen(peer));
+    notif.shutdown_tx.send(peer).await.unwrap();
+    notif.next_event().await;
+
+    let substream = match notif.peers.get(&peer) {
+        Some(PeerContext {
+            state: PeerState::OutboundInitiated { substream },
+        }) => *substream,
+        state => panic!("invalid state: {state:?}"),
+    };
+    notif
+        .on_substream_open_failure(substream, SubstreamError::ConnectionClosed)
+        .await;
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamClosed { peer: closed }) =>
+            assert_eq!(closed, peer),
+        event => panic!("invalid event: {event:?}"),
+    }
+    assert_eq!(handle.peer_handshake(peer), None);
+}
+
+#[tokio::test]
+async fn open_peers_reopened_after_handshake_revalidation() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (mut notif, mut handle, _manager, mut tx) = make_notification_protocol();
+    let (peer, mut receiver) = register_peer(&mut notif, &mut tx).await;
+
+    // open the notification stream
+    let mut inbound = MockSubstream::new();
+    inbound.expect_poll_next().returning(|_| Poll::Pending);
+    inbound.expect_poll_close().times(1).return_once(|_| Poll::Ready(Ok(())));
+
+    let mut outbound = MockSubstream::new();
+    outbound.expect_poll_flush().returning(|_| Poll::Ready(Ok(())));
+    outbound.expect_poll_close().times(1).return_once(|_| Poll::Ready(Ok(())));
+
+    let (sync_tx, sync_rx) = channel(64);
+    let (async_tx, async_rx) = channel(64);
+    let (notif_tx, _notif_rx) = channel(64);
+    let (connection, shutdown) = Connection::new(
+        peer,
+        Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(inbound)),
+        Substream::new_mock(peer, SubstreamId::from(1usize), Box::new(outbound)),
+        notif.event_handle.clone(),
+        notif.shutdown_tx.clone(),
+        notif_tx,
+        async_rx,
+        sync_rx,
+        OutboundQueue::new(8, QueuePolicy::Block, Default::default()),
+    );
+    notif.peers.get_mut(&peer).unwrap().state = PeerState::Open { shutdown };
+
+    notif
+        .event_handle
+        .report_notification_stream_opened(
+            ProtocolName::from("/notif/1"),
+            None,
+            Direction::Outbound,
+            peer,
+            vec![1, 3, 3, 7],
+            NotificationSink::new(
+                peer,
+                sync_tx,
+                async_tx,
+                Default::default(),
+                Default::default(),
+            ),
+            None,
+        )
+        .await;
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamOpened { peer: opened, .. }) =>
+            assert_eq!(opened, peer),
+        event => panic!("invalid event: {event:?}"),
+    }
+    let connection = tokio::spawn(connection.start());
+
+    // change the handshake and close the open stream
+    handle.set_handshake_and_revalidate(vec![5, 6, 7, 8]).await;
+    notif.next_event().await;
+
+    tokio::time::timeout(Duration::from_secs(5), connection)
+        .await
+        .expect("connection to be closed")
+        .unwrap();
+
+    // closure is not reported while the stream is being re-opened
+    assert!(futures::poll!(handle.next()).is_pending());
+
+    // stream is re-opened once the connection has exited
+    notif.next_event().await;
+
+    let substream_id = match receiver.try_recv() {
+        Ok(ProtocolCommand::OpenSubstream { substream_id, .. }) => substream_id,
+        command => panic!("invalid command: {command:?}"),
+    };
+
+    let (local, mut remote) = tokio::io::duplex(1024);
+    remote.write_all(&[4, 1, 3, 3, 8]).await.unwrap();
+
+    notif
+        .on_outbound_substream(
+            ProtocolName::from("/notif/1"),
+            None,
+            peer,
+            substream_id,
+            Substream::new_duplex(
+                peer,
+                substream_id,
+                local,
+                ProtocolCodec::UnsignedVarint(Some(1024usize)),
+            ),
+        )
+        .await
+        .unwrap();
+    notif.next_event().await;
+
+    // the new handshake is sent to the remote peer
+    let mut handshake = [0u8; 5];
+    remote.read_exact(&mut handshake).await.unwrap();
+    assert_eq!(handshake, [4, 5, 6, 7, 8]);
+}
+
+#[tokio::test]
+async fn negotiation_duration_includes_handshake_and_validation_delay() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (mut notif, mut handle, _sender, tx) = make_notification_protocol();
+    let peer = PeerId::random();
+    let (proto_tx, mut proto_rx) = channel(256);
+
+    tx.send(InnerTransportEvent::ConnectionEstablished {
+        peer,
+        endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+        sender: ConnectionHandle::new(ConnectionId::from(0usize), proto_tx),
+        connection: ConnectionId::from(0usize),
+    })
+    .await
+    .unwrap();
+    notif.next_event().await;
+
+    // remote opens an inbound substream but sends its handshake only after a delay
+    let (inbound, mut remote_inbound) = paired();
+    notif
+        .on_inbound_substream(
+            ProtocolName::from("/notif/1"),
+            None,
+            peer,
+            Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(inbound)),
+        )
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    remote_inbound.send(Bytes::from_static(&[1, 3, 3, 7])).await.unwrap();
+
+    let (peer, event) = notif.negotiation.next().await.unwrap();
+    notif.on_handshake_event(peer, event).await;
+
+    match handle.next().await {
+        Some(NotificationEvent::ValidateSubstream { .. }) => {}
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    // user protocol takes a while to validate the substream
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    notif.on_validation_result(peer, ValidationResult::Accept).await.unwrap();
+
+    let (peer, event) = notif.negotiation.next().await.unwrap();
+    notif.on_handshake_event(peer, event).await;
+    assert_eq!(handle.negotiation_duration(peer), None);
+
+    // open the outbound substream and finish the negotiation
+    let ProtocolCommand::OpenSubstream { substream_id, .. } = proto_rx.recv().await.unwrap() else {
+        panic!("invalid command received");
+    };
+    let (outbound, mut remote_outbound) = paired();
+    remote_outbound.send(Bytes::from_static(&[1, 3, 3, 7])).await.unwrap();
+
+    notif
+        .on_outbound_substream(
+            ProtocolName::from("/notif/1"),
+            None,
+            peer,
+            substream_id,
+            Substream::new_mock(peer, substream_id, Box::new(outbound)),
+        )
+        .await
+        .unwrap();
+
+    let (peer, event) = notif.negotiation.next().await.unwrap();
+    notif.on_handshake_event(peer, event).await;
+
+    match handle.next().await {
+        Some(NotificationEvent::NotificationStreamOpened { peer: opened, .. }) =>
+            assert_eq!(opened, peer),
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    let duration = handle.negotiation_duration(peer).unwrap();
+    assert!(duration.validation >= Duration::from_millis(100));
+    assert!(duration.total - duration.validation >= Duration::from_millis(200));
+}
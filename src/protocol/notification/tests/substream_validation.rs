@@ -38,11 +38,11 @@ use crate::{
 };
 
 use bytes::BytesMut;
-use futures::StreamExt;
+use futures::{FutureExt, StreamExt};
 use multiaddr::Multiaddr;
 use tokio::sync::{mpsc::channel, oneshot};
 
-use std::task::Poll;
+use std::{collections::HashSet, task::Poll};
 
 #[tokio::test]
 async fn non_existent_peer() {
@@ -462,3 +462,39 @@ async fn open_substream_rejected() {
 
     assert!(notif.on_validation_result(peer, ValidationResult::Reject).await.is_err());
 }
+
+#[tokio::test]
+async fn inbound_substream_from_disallowed_peer_rejected() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (mut notif, mut handle, _sender, _tx) = make_notification_protocol();
+    let (peer, _service, _receiver) = add_peer();
+    notif.allowed_peers = Some(HashSet::from_iter([PeerId::random()]));
+
+    // the substream is closed without its handshake being read
+    let mut substream = MockSubstream::new();
+    substream.expect_poll_next().times(0);
+    substream.expect_poll_close().times(1).return_once(|_| Poll::Ready(Ok(())));
+
+    notif.on_connection_established(peer).await.unwrap();
+    notif
+        .on_inbound_substream(
+            ProtocolName::from("/notif/1"),
+            None,
+            peer,
+            Substream::new_mock(peer, SubstreamId::from(0usize), Box::new(substream)),
+        )
+        .await
+        .unwrap();
+
+    match &notif.peers.get(&peer).unwrap().state {
+        PeerState::Closed { pending_open: None } => {}
+        state => panic!("invalid state for peer: {state:?}"),
+    }
+
+    // neither a handshake is read nor is the user asked to validate the substream
+    assert!(notif.negotiation.next().now_or_never().is_none());
+    assert!(handle.next().now_or_never().is_none());
+}
@@ -23,9 +23,9 @@ use crate::{
 };
 
 use bytes::BytesMut;
-use tokio::sync::oneshot;
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
 
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt::Debug, sync::Arc, time::Duration};
 
 /// Default channel size for synchronous notifications.
 pub(super) const SYNC_CHANNEL_SIZE: usize = 2048;
@@ -33,6 +33,9 @@ pub(super) const SYNC_CHANNEL_SIZE: usize = 2048;
 /// Default channel size for asynchronous notifications.
 pub(super) const ASYNC_CHANNEL_SIZE: usize = 8;
 
+/// Default size of the outbound notification queue.
+pub(super) const OUTBOUND_QUEUE_SIZE: usize = 1;
+
 /// Direction of the connection.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Direction {
@@ -43,6 +46,203 @@ pub enum Direction {
     Outbound,
 }
 
+/// Policy applied to a notification when the outbound queue of the peer is full.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum QueuePolicy {
+    /// Stop reading notifications from the notification channels until the queue has capacity.
+    ///
+    /// Synchronous notifications are rejected with [`NotificationError::ChannelClogged`] once the
+    /// channel is also full and asynchronous notifications wait for the channel to have capacity.
+    #[default]
+    Block,
+
+    /// Discard the oldest queued notification to make room for the new notification.
+    DropOldest,
+
+    /// Discard the new notification.
+    DropNewest,
+}
+
+/// Compression applied to notification payloads.
+///
+/// Compression is negotiated by proposing the protocol name with a suffix identifying the
+/// compression algorithm before the plain protocol name, so peers that don't support compression
+/// negotiate the uncompressed protocol. Handshakes are never compressed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+    /// Snappy, using the raw block format. Negotiated with the `/snappy` suffix.
+    Snappy,
+}
+
+impl Compression {
+    /// Get the name of the compressed variant of `protocol`.
+    pub(crate) fn protocol_name(&self, protocol: &ProtocolName) -> ProtocolName {
+        match self {
+            Self::Snappy => ProtocolName::from(format!("{protocol}/snappy")),
+        }
+    }
+
+    /// Get the maximum size of a compressed payload of `size` bytes.
+    pub(crate) fn max_compressed_len(&self, size: usize) -> usize {
+        match self {
+            Self::Snappy => snap::raw::max_compress_len(size),
+        }
+    }
+
+    /// Compress `payload`.
+    pub(crate) fn compress(&self, payload: &[u8]) -> crate::Result<Vec<u8>> {
+        match self {
+            Self::Snappy => snap::raw::Encoder::new()
+                .compress_vec(payload)
+                .map_err(|_| crate::Error::InvalidData),
+        }
+    }
+
+    /// Decompress `payload`, failing if the decompressed payload would exceed `max_size` bytes.
+    pub(crate) fn decompress(&self, payload: &[u8], max_size: usize) -> crate::Result<Vec<u8>> {
+        match self {
+            Self::Snappy => {
+                let size =
+                    snap::raw::decompress_len(payload).map_err(|_| crate::Error::InvalidData)?;
+
+                if size > max_size {
+                    return Err(crate::Error::InvalidData);
+                }
+
+                snap::raw::Decoder::new()
+                    .decompress_vec(payload)
+                    .map_err(|_| crate::Error::InvalidData)
+            }
+        }
+    }
+}
+
+/// Features of a substream, decided by the variant of the protocol it was negotiated with.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct ProtocolVariant {
+    /// Notifications are compressed.
+    pub(crate) compressed: bool,
+
+    /// Notifications are flow-controlled using credit granted by the receiver.
+    pub(crate) credit: bool,
+}
+
+/// State of a peer in the notification protocol, as reported to a [`PeerStateObserver`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PeerStateKind {
+    /// No notification stream is open or being opened to the peer.
+    Closed,
+
+    /// Peer is being dialed in order to open an outbound substream to them.
+    Dialing,
+
+    /// Outbound substream has been requested but it hasn't opened yet.
+    OutboundInitiated,
+
+    /// Substreams are being negotiated or validated.
+    Validating,
+
+    /// Connection was closed while an inbound substream was still being validated by the user.
+    ValidationPending,
+
+    /// Notification stream is open.
+    Open,
+}
+
+/// Number of peers in each [`PeerStateKind`], returned by
+/// [`NotificationHandle::state_counts()`](super::NotificationHandle::state_counts).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct StateCounts {
+    /// Connected peers with no notification stream open or being opened.
+    pub closed: usize,
+
+    /// Peers being dialed.
+    pub dialing: usize,
+
+    /// Peers with an outbound substream requested but not yet opened.
+    pub outbound_initiated: usize,
+
+    /// Peers whose substreams are being negotiated or validated.
+    pub validating: usize,
+
+    /// Disconnected peers whose inbound substream is still being validated by the user.
+    pub validation_pending: usize,
+
+    /// Peers with an open notification stream.
+    pub open: usize,
+}
+
+/// How long negotiating a notification stream took, returned by
+/// [`NotificationHandle::negotiation_duration()`](super::NotificationHandle::negotiation_duration).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct NegotiationDuration {
+    /// Time from the arrival of the inbound substream, or from the request to open the stream,
+    /// until the stream was opened.
+    pub total: Duration,
+
+    /// Part of `total` spent waiting for the user protocol to validate the inbound substream.
+    ///
+    /// The rest of `total` was spent dialing the peer and exchanging handshakes with it.
+    pub validation: Duration,
+}
+
+impl StateCounts {
+    /// Get the number of peers in state `kind`.
+    pub fn get(&self, kind: PeerStateKind) -> usize {
+        match kind {
+            PeerStateKind::Closed => self.closed,
+            PeerStateKind::Dialing => self.dialing,
+            PeerStateKind::OutboundInitiated => self.outbound_initiated,
+            PeerStateKind::Validating => self.validating,
+            PeerStateKind::ValidationPending => self.validation_pending,
+            PeerStateKind::Open => self.open,
+        }
+    }
+
+    /// Count one more peer in state `kind`.
+    pub(crate) fn increment(&mut self, kind: PeerStateKind) {
+        match kind {
+            PeerStateKind::Closed => self.closed += 1,
+            PeerStateKind::Dialing => self.dialing += 1,
+            PeerStateKind::OutboundInitiated => self.outbound_initiated += 1,
+            PeerStateKind::Validating => self.validating += 1,
+            PeerStateKind::ValidationPending => self.validation_pending += 1,
+            PeerStateKind::Open => self.open += 1,
+        }
+    }
+}
+
+/// Observer of peer state transitions in the notification protocol.
+///
+/// The observer is called with the peer, its previous state and its new state each time the state
+/// changes, after the protocol has finished processing the event that caused the transition. A
+/// peer that moves through several states while a single event is processed is only reported once.
+/// Disconnected peers are reported as [`PeerStateKind::Closed`].
+///
+/// The observer is called from the protocol's event loop and it must not block.
+#[derive(Clone)]
+pub struct PeerStateObserver(Arc<dyn Fn(PeerId, PeerStateKind, PeerStateKind) + Send + Sync>);
+
+impl PeerStateObserver {
+    /// Create new [`PeerStateObserver`].
+    pub fn new(
+        observer: impl Fn(PeerId, PeerStateKind, PeerStateKind) + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(observer))
+    }
+
+    /// Report state transition of `peer` to the observer.
+    pub(crate) fn observe(&self, peer: PeerId, old: PeerStateKind, new: PeerStateKind) {
+        (self.0)(peer, old, new)
+    }
+}
+
+impl Debug for PeerStateObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PeerStateObserver").finish_non_exhaustive()
+    }
+}
+
 /// Validation result.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ValidationResult {
@@ -73,6 +273,17 @@ pub enum NotificationError {
 
     /// Notification protocol has been closed.
     EssentialTaskClosed,
+
+    /// Remote sent a handshake larger than the configured maximum handshake size.
+    HandshakeTooLarge,
+
+    /// Substream negotiation was aborted by
+    /// [`NotificationHandle::reset_peer()`](super::handle::NotificationHandle::reset_peer).
+    Reset,
+
+    /// Notification protocol has been paused with
+    /// [`NotificationHandle::pause()`](super::handle::NotificationHandle::pause).
+    Paused,
 }
 
 /// Notification events.
@@ -114,6 +325,13 @@ pub(crate) enum InnerNotificationEvent {
 
         /// Notification sink.
         sink: NotificationSink,
+
+        /// TX channel for reporting notifications read by the user, if the inbound substream
+        /// uses credit-based flow control.
+        read_tx: Option<UnboundedSender<()>>,
+
+        /// Did the stream replace a closed stream of a reserved peer.
+        reopened: bool,
     },
 
     /// Notification stream closed.
@@ -160,10 +378,8 @@ pub enum NotificationEvent {
 
         /// Direction of the substream.
         ///
-        /// [`Direction::Inbound`](crate::protocol::Direction::Outbound) indicates that the
-        /// substream was opened by the remote peer and
-        /// [`Direction::Outbound`](crate::protocol::Direction::Outbound) that it was
-        /// opened by the local node.
+        /// [`Direction::Inbound`] indicates that the substream was opened by the remote peer and
+        /// [`Direction::Outbound`] that it was opened by the local node.
         direction: Direction,
 
         /// Peer ID.
@@ -179,6 +395,24 @@ pub enum NotificationEvent {
         peer: PeerId,
     },
 
+    /// Notification stream of a reserved peer was closed and has been transparently re-opened.
+    ///
+    /// Also emitted for the streams re-opened after the handshake was changed with
+    /// [`set_handshake_and_revalidate()`](super::NotificationHandle::set_handshake_and_revalidate).
+    ///
+    /// The closure of the old stream is not reported separately. Notifications sent between
+    /// the closure and the re-open fail with [`NotificationError::NoConnection`].
+    NotificationStreamReopened {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Direction of the new stream.
+        direction: Direction,
+
+        /// Handshake of the new stream.
+        handshake: Vec<u8>,
+    },
+
     /// Failed to open notification stream.
     NotificationStreamOpenFailure {
         /// Peer ID.
@@ -217,4 +451,13 @@ pub(crate) enum NotificationCommand {
         /// Peer to disconnect.
         peer: PeerId,
     },
+
+    /// Abort substream validation and negotiation in progress for the peer.
+    ResetPeer {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
+    /// Re-open the notification streams of all open peers with the current handshake.
+    Revalidate,
 }
@@ -26,9 +26,11 @@ use crate::{
     },
     protocol::{
         connection::{ConnectionHandle, Permit},
-        Direction, TransportEvent,
+        Direction, InboundSubstreamOrder, NegotiationCache, NegotiationObserver, TransportEvent,
+    },
+    substream::{
+        BufferBudget, Substream, SubstreamRateLimit, SubstreamRateLimiter, SubstreamRegistry,
     },
-    substream::Substream,
     transport::{
         manager::{ProtocolContext, TransportManagerEvent},
         Endpoint,
@@ -37,14 +39,20 @@ use crate::{
     PeerId,
 };
 
-use futures::{stream::FuturesUnordered, Stream, StreamExt};
+use futures::{future::BoxFuture, stream::FuturesUnordered, Future, Stream, StreamExt};
 use multiaddr::Multiaddr;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::{
+    sync::mpsc::{channel, error::TrySendError, OwnedPermit, Receiver, Sender},
+    time::Sleep,
+};
+
+#[cfg(feature = "traffic-tap")]
+use crate::substream::TrafficTap;
 
 #[cfg(any(feature = "quic", feature = "webrtc", feature = "websocket"))]
 use std::sync::atomic::Ordering;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fmt::Debug,
     pin::Pin,
     sync::{atomic::AtomicUsize, Arc},
@@ -54,6 +62,16 @@ use std::{
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::protocol-set";
 
+/// Maximum number of substream open requests buffered by [`ProtocolSet`].
+const MAX_PENDING_OPENS: usize = 256;
+
+/// Maximum number of inbound substreams queued for a protocol by [`ProtocolSet`].
+const MAX_QUEUED_INBOUND: usize = 64;
+
+/// Slot reserved in the event channel of a protocol for a queued inbound substream, or `None` if
+/// the protocol has exited.
+type InboundReservation = (ProtocolName, Option<OwnedPermit<InnerTransportEvent>>);
+
 /// Events emitted by the underlying transport protocols.
 #[derive(Debug)]
 pub enum InnerTransportEvent {
@@ -92,6 +110,15 @@ pub enum InnerTransportEvent {
         address: Multiaddr,
     },
 
+    /// Remote peer of an open connection has changed its address.
+    ConnectionAddressChanged {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Updated endpoint of the connection.
+        endpoint: Endpoint,
+    },
+
     /// Substream opened for `peer`.
     SubstreamOpened {
         /// Peer ID.
@@ -143,6 +170,8 @@ impl From<InnerTransportEvent> for TransportEvent {
         match event {
             InnerTransportEvent::DialFailure { peer, address } =>
                 TransportEvent::DialFailure { peer, address },
+            InnerTransportEvent::ConnectionAddressChanged { peer, endpoint } =>
+                TransportEvent::ConnectionAddressChanged { peer, endpoint },
             InnerTransportEvent::SubstreamOpened {
                 peer,
                 protocol,
@@ -199,6 +228,13 @@ pub enum ProtocolCommand {
         /// permit is dropped and the connection may be closed if no other permit is being
         /// held.
         permit: Permit,
+
+        /// Whether the protocol allows its first message to be sent as early data.
+        ///
+        /// Transports which support it, currently only QUIC, may open the substream and send data
+        /// over it before the handshake of a resumed connection has completed. Other transports
+        /// ignore the flag.
+        early_data: bool,
     },
 
     /// Forcibly close the connection, even if other protocols have substreams open over it.
@@ -218,6 +254,52 @@ pub struct ProtocolSet {
     #[allow(unused)]
     next_substream_id: Arc<AtomicUsize>,
     fallback_names: HashMap<ProtocolName, ProtocolName>,
+
+    /// Outbound substreams returned to the connection which haven't been opened yet.
+    opening: HashMap<SubstreamId, ProtocolName>,
+
+    /// Registry where opened substreams are tracked, if enabled.
+    substream_registry: Option<SubstreamRegistry>,
+
+    /// Observer of `multistream-select` negotiations, if enabled.
+    negotiation_observer: Option<NegotiationObserver>,
+
+    /// Cache of protocols negotiated with each peer, if optimistic negotiation is enabled.
+    negotiation_cache: Option<NegotiationCache>,
+
+    /// Memory budget for data buffered by substreams, if enabled.
+    buffer_budget: Option<BufferBudget>,
+
+    /// Rate limiter of inbound substreams, if enabled.
+    substream_rate_limiter: Option<SubstreamRateLimiter>,
+
+    /// Traffic taps of the installed protocols.
+    #[cfg(feature = "traffic-tap")]
+    traffic_taps: HashMap<ProtocolName, TrafficTap>,
+
+    /// Substream open requests waiting to be returned to the connection, queued per protocol.
+    pending_opens: HashMap<ProtocolName, VecDeque<ProtocolCommand>>,
+
+    /// Protocols with pending substream open requests, in the order they're served.
+    open_order: VecDeque<ProtocolName>,
+
+    /// Total number of pending substream open requests.
+    num_pending_opens: usize,
+
+    /// Inbound substreams waiting for room in the event channel of their protocol.
+    ///
+    /// Only used by protocols which don't receive their inbound substreams in FIFO order.
+    queued_inbound: HashMap<ProtocolName, VecDeque<InnerTransportEvent>>,
+
+    /// Pending reservations of a slot in the event channels of protocols with queued inbound
+    /// substreams.
+    inbound_reservations: FuturesUnordered<BoxFuture<'static, InboundReservation>>,
+
+    /// Timer of the drain deadline, if the connection is being drained.
+    drain_timer: Option<Pin<Box<Sleep>>>,
+
+    /// Has the connection been drained.
+    drained: bool,
 }
 
 impl ProtocolSet {
@@ -247,9 +329,84 @@ impl ProtocolSet {
             next_substream_id,
             fallback_names,
             connection: ConnectionHandle::new(connection_id, tx),
+            opening: HashMap::new(),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            substream_rate_limiter: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
+            pending_opens: HashMap::new(),
+            open_order: VecDeque::new(),
+            num_pending_opens: 0usize,
+            queued_inbound: HashMap::new(),
+            inbound_reservations: FuturesUnordered::new(),
+            drain_timer: None,
+            drained: false,
         }
     }
 
+    /// Track substreams opened over the connection in `registry`.
+    pub(crate) fn with_substream_registry(mut self, registry: Option<SubstreamRegistry>) -> Self {
+        self.substream_registry = registry;
+        self
+    }
+
+    /// Report `multistream-select` negotiations of the connection to `observer`.
+    pub(crate) fn with_negotiation_observer(
+        mut self,
+        observer: Option<NegotiationObserver>,
+    ) -> Self {
+        self.negotiation_observer = observer;
+        self
+    }
+
+    /// Negotiate outbound substreams optimistically for protocols found in `cache`.
+    pub(crate) fn with_negotiation_cache(mut self, cache: Option<NegotiationCache>) -> Self {
+        self.negotiation_cache = cache;
+        self
+    }
+
+    /// Account data buffered by substreams opened over the connection against `budget`.
+    pub(crate) fn with_buffer_budget(mut self, budget: Option<BufferBudget>) -> Self {
+        self.buffer_budget = budget;
+        self
+    }
+
+    /// Limit the rate at which the remote peer may open substreams over the connection.
+    pub(crate) fn with_substream_rate_limit(mut self, limit: Option<SubstreamRateLimit>) -> Self {
+        self.substream_rate_limiter = limit.map(SubstreamRateLimiter::new);
+        self
+    }
+
+    /// Copy the frames of substreams opened over the connection to the protocol's traffic tap.
+    #[cfg(feature = "traffic-tap")]
+    pub(crate) fn with_traffic_taps(mut self, taps: HashMap<ProtocolName, TrafficTap>) -> Self {
+        self.traffic_taps = taps;
+        self
+    }
+
+    /// Get observer of `multistream-select` negotiations, if enabled.
+    pub(crate) fn negotiation_observer(&self) -> Option<NegotiationObserver> {
+        self.negotiation_observer.clone()
+    }
+
+    /// Get cache of negotiated protocols, if optimistic negotiation is enabled.
+    pub(crate) fn negotiation_cache(&self) -> Option<NegotiationCache> {
+        self.negotiation_cache.clone()
+    }
+
+    /// Check if the remote peer may open another substream over the connection.
+    ///
+    /// Returns `false` if the substream exceeds the substream rate limit and must be closed
+    /// before it's negotiated.
+    pub(crate) fn try_accept_substream(&mut self) -> bool {
+        self.substream_rate_limiter
+            .as_mut()
+            .map_or(true, |limiter| limiter.try_acquire())
+    }
+
     /// Try to acquire permit to keep the connection open.
     pub fn try_get_permit(&mut self) -> Option<Permit> {
         self.connection.try_get_permit()
@@ -276,10 +433,14 @@ impl ProtocolSet {
         peer: PeerId,
         protocol: ProtocolName,
         direction: Direction,
-        substream: Substream,
+        mut substream: Substream,
     ) -> Result<(), SubstreamError> {
         tracing::debug!(target: LOG_TARGET, %protocol, ?peer, ?direction, "substream opened");
 
+        if let Direction::Outbound(substream_id) = direction {
+            self.opening.remove(&substream_id);
+        }
+
         let (protocol, fallback) = match self.fallback_names.get(&protocol) {
             Some(main_protocol) => (main_protocol.clone(), Some(protocol)),
             None => (protocol, None),
@@ -294,6 +455,34 @@ impl ProtocolSet {
             .into());
         };
 
+        if let (Direction::Inbound, Some(filter)) = (direction, &protocol_context.inbound_filter) {
+            if !filter.accept(&peer, fallback.as_ref().unwrap_or(&protocol)) {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    %protocol,
+                    ?peer,
+                    "inbound substream rejected by filter",
+                );
+
+                // dropping the substream closes it before anything is read from it
+                drop(substream);
+                return Ok(());
+            }
+        }
+
+        if let Some(registry) = &self.substream_registry {
+            substream.register(registry, protocol.clone());
+        }
+
+        if let Some(budget) = &self.buffer_budget {
+            substream.set_buffer_budget(budget.clone());
+        }
+
+        #[cfg(feature = "traffic-tap")]
+        if let Some(tap) = self.traffic_taps.get(&protocol) {
+            substream.set_tap(tap.clone(), protocol.clone());
+        }
+
         let event = InnerTransportEvent::SubstreamOpened {
             peer,
             protocol: protocol.clone(),
@@ -302,6 +491,12 @@ impl ProtocolSet {
             substream,
         };
 
+        if direction == Direction::Inbound
+            && protocol_context.inbound_order != InboundSubstreamOrder::Fifo
+        {
+            return self.queue_inbound(protocol, event);
+        }
+
         protocol_context
             .tx
             .send(event)
@@ -319,6 +514,34 @@ impl ProtocolSet {
             .codec
     }
 
+    /// Get the priority of the substreams of `protocol`.
+    pub fn substream_priority(&self, protocol: &ProtocolName) -> SubstreamPriority {
+        // NOTE: `protocol` must exist in `self.protocol` as it was negotiated
+        // using the protocols from this set
+        self.protocols
+            .get(self.fallback_names.get(protocol).map_or(protocol, |protocol| protocol))
+            .expect("protocol to exist")
+            .priority
+    }
+
+    /// Get the permit for a substream that was negotiated for `protocol`.
+    ///
+    /// If the protocol doesn't allow idle substreams to keep the connection open, the permit
+    /// keeps the connection open only while there is traffic on the substream.
+    pub fn substream_permit(&self, protocol: &ProtocolName, permit: Permit) -> Permit {
+        // NOTE: `protocol` must exist in `self.protocol` as it was negotiated
+        // using the protocols from this set
+        match self
+            .protocols
+            .get(self.fallback_names.get(protocol).map_or(protocol, |protocol| protocol))
+            .expect("protocol to exist")
+            .idle_timeout
+        {
+            Some(timeout) => permit.with_idle_timeout(timeout),
+            None => permit,
+        }
+    }
+
     /// Report to `protocol` that connection failed to open substream for `peer`.
     pub async fn report_substream_open_failure(
         &mut self,
@@ -333,6 +556,7 @@ impl ProtocolSet {
             ?error,
             "failed to open substream",
         );
+        self.opening.remove(&substream);
 
         self.protocols
             .get_mut(&protocol)
@@ -377,15 +601,65 @@ impl ProtocolSet {
             }
         }
 
-        Ok(())
+        // the handle given to the transport manager doesn't keep the connection open
+        self.mgr_tx
+            .send(TransportManagerEvent::ConnectionEstablished {
+                connection: endpoint.connection_id(),
+                handle: self.connection.clone(),
+            })
+            .await
+            .map_err(From::from)
     }
 
     /// Report to protocols that a connection was closed.
+    ///
+    /// Outbound substreams which hadn't been opened when the connection was closed are reported
+    /// as failed before the connection is reported closed.
     pub(crate) async fn report_connection_closed(
         &mut self,
         peer: PeerId,
         connection_id: ConnectionId,
     ) -> crate::Result<()> {
+        let queued =
+            std::mem::take(&mut self.pending_opens)
+                .into_values()
+                .flatten()
+                .filter_map(|command| match command {
+                    ProtocolCommand::OpenSubstream {
+                        protocol,
+                        substream_id,
+                        ..
+                    } => Some((substream_id, protocol)),
+                    ProtocolCommand::ForceClose => None,
+                });
+        let mut pending =
+            std::mem::take(&mut self.opening).into_iter().chain(queued).collect::<Vec<_>>();
+        self.open_order.clear();
+        self.num_pending_opens = 0usize;
+
+        while let Ok(command) = self.rx.try_recv() {
+            if let ProtocolCommand::OpenSubstream {
+                protocol,
+                substream_id,
+                ..
+            } = command
+            {
+                pending.push((substream_id, protocol));
+            }
+        }
+
+        for (substream_id, protocol) in pending {
+            if let Some(context) = self.protocols.get(&protocol) {
+                let _ = context
+                    .tx
+                    .send(InnerTransportEvent::SubstreamOpenFailure {
+                        substream: substream_id,
+                        error: SubstreamError::ConnectionClosed,
+                    })
+                    .await;
+            }
+        }
+
         let mut futures = self
             .protocols
             .values()
@@ -400,6 +674,65 @@ impl ProtocolSet {
             })
             .collect::<FuturesUnordered<_>>();
 
+        // notify all protocols and the transport manager even if some of them have exited
+        // so the connection is cleaned up consistently, returning the first error, if any
+        let mut result: crate::Result<()> = Ok(());
+
+        while let Some(res) = futures.next().await {
+            if let (Err(error), true) = (res, result.is_ok()) {
+                result = Err(error.into());
+            }
+        }
+
+        let res = self
+            .mgr_tx
+            .send(TransportManagerEvent::ConnectionClosed {
+                peer,
+                connection: connection_id,
+            })
+            .await
+            .map_err(From::from);
+
+        result.and(res)
+    }
+
+    /// Track `command` until the substream it opens has been opened or has failed to open.
+    fn track_open(&mut self, command: ProtocolCommand) -> ProtocolCommand {
+        if let ProtocolCommand::OpenSubstream {
+            protocol,
+            substream_id,
+            ..
+        } = &command
+        {
+            self.opening.insert(*substream_id, protocol.clone());
+        }
+
+        command
+    }
+
+    /// Report to protocols and the transport manager that the remote peer of the connection has
+    /// changed its address.
+    #[cfg(feature = "quic")]
+    pub(crate) async fn report_connection_address_changed(
+        &mut self,
+        peer: PeerId,
+        endpoint: Endpoint,
+    ) -> crate::Result<()> {
+        let mut futures = self
+            .protocols
+            .values()
+            .map(|sender| {
+                let endpoint = endpoint.clone();
+
+                async move {
+                    sender
+                        .tx
+                        .send(InnerTransportEvent::ConnectionAddressChanged { peer, endpoint })
+                        .await
+                }
+            })
+            .collect::<FuturesUnordered<_>>();
+
         while !futures.is_empty() {
             if let Some(Err(error)) = futures.next().await {
                 return Err(error.into());
@@ -407,29 +740,259 @@ impl ProtocolSet {
         }
 
         self.mgr_tx
-            .send(TransportManagerEvent::ConnectionClosed {
+            .send(TransportManagerEvent::ConnectionAddressChanged {
                 peer,
-                connection: connection_id,
+                connection: endpoint.connection_id(),
+                address: endpoint.address().clone(),
             })
             .await
             .map_err(From::from)
     }
 }
 
+impl ProtocolSet {
+    /// Deliver inbound substream to `protocol` or queue it if the protocol's event channel is
+    /// full, delivering it later in the order configured for the protocol.
+    fn queue_inbound(
+        &mut self,
+        protocol: ProtocolName,
+        event: InnerTransportEvent,
+    ) -> Result<(), SubstreamError> {
+        // substreams already waiting for a slot would be overtaken by sending directly
+        let event = match self.queued_inbound.contains_key(&protocol) {
+            true => event,
+            false => {
+                let tx = &self.protocols.get(&protocol).expect("protocol to exist").tx;
+
+                match tx.try_send(event) {
+                    Ok(()) => return Ok(()),
+                    Err(TrySendError::Closed(_)) => return Err(SubstreamError::ConnectionClosed),
+                    Err(TrySendError::Full(event)) => {
+                        let tx = tx.clone();
+                        let protocol = protocol.clone();
+
+                        self.inbound_reservations.push(Box::pin(async move {
+                            (protocol, tx.reserve_owned().await.ok())
+                        }));
+                        event
+                    }
+                }
+            }
+        };
+
+        let queue = self.queued_inbound.entry(protocol.clone()).or_default();
+        if queue.len() == MAX_QUEUED_INBOUND {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %protocol,
+                "inbound substream queue full, closing oldest substream",
+            );
+            queue.pop_front();
+        }
+        queue.push_back(event);
+
+        Ok(())
+    }
+
+    /// Deliver queued inbound substreams to protocols which have room in their event channels.
+    fn poll_queued_inbound(&mut self, cx: &mut Context<'_>) {
+        while let Poll::Ready(Some((protocol, permit))) =
+            self.inbound_reservations.poll_next_unpin(cx)
+        {
+            let Some(permit) = permit else {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    %protocol,
+                    "protocol has exited, dropping queued inbound substreams",
+                );
+                self.queued_inbound.remove(&protocol);
+                continue;
+            };
+
+            let order = self.protocols.get(&protocol).expect("protocol to exist").inbound_order;
+            let queue = self.queued_inbound.get_mut(&protocol).expect("queue to exist");
+            let event = match order {
+                InboundSubstreamOrder::Fifo => queue.pop_front(),
+                InboundSubstreamOrder::Lifo => queue.pop_back(),
+            };
+            let tx = permit.send(event.expect("queue to be non-empty"));
+
+            match queue.is_empty() {
+                true => {
+                    self.queued_inbound.remove(&protocol);
+                }
+                false => self.inbound_reservations.push(Box::pin(async move {
+                    (protocol, tx.reserve_owned().await.ok())
+                })),
+            }
+        }
+    }
+
+    /// Queue substream open request of `protocol`.
+    fn queue_open(&mut self, protocol: ProtocolName, command: ProtocolCommand) {
+        let queue = self.pending_opens.entry(protocol.clone()).or_default();
+
+        if queue.is_empty() {
+            self.open_order.push_back(protocol);
+        }
+
+        queue.push_back(command);
+        self.num_pending_opens += 1;
+    }
+
+    /// Get the next substream open request, taking turns between protocols.
+    fn next_open(&mut self) -> Option<ProtocolCommand> {
+        let protocol = self.open_order.pop_front()?;
+        let queue = self.pending_opens.get_mut(&protocol).expect("protocol to have a queue");
+        let command = queue.pop_front().expect("queue to be non-empty");
+
+        match queue.is_empty() {
+            true => {
+                self.pending_opens.remove(&protocol);
+            }
+            false => self.open_order.push_back(protocol),
+        }
+        self.num_pending_opens -= 1;
+
+        Some(self.track_open(command))
+    }
+
+    /// Poll the drain of the connection, if it's being drained.
+    ///
+    /// Returns [`ProtocolCommand::ForceClose`] once all substreams of the connection have been
+    /// closed or the deadline of the drain has been reached.
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Option<ProtocolCommand> {
+        if self.drained {
+            return None;
+        }
+
+        let drain = Arc::clone(self.connection.drain_state());
+        let deadline = tokio::time::Instant::from(drain.poll_deadline(cx.waker())?);
+        let timer = self
+            .drain_timer
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+
+        if timer.deadline() != deadline {
+            timer.as_mut().reset(deadline);
+        }
+
+        let expired = timer.as_mut().poll(cx).is_ready();
+        let num_permits = drain.num_permits();
+
+        if num_permits != 0 && !expired {
+            return None;
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            connection_id = ?self.connection.connection_id(),
+            ?num_permits,
+            "connection drained, closing",
+        );
+
+        self.drained = true;
+        Some(ProtocolCommand::ForceClose)
+    }
+}
+
+/// Substream open requests are returned round-robin across protocols so a protocol opening many
+/// substreams at once can't starve the other protocols of the connection. Other commands are
+/// returned as soon as they're received.
 impl Stream for ProtocolSet {
     type Item = ProtocolCommand;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.rx.poll_recv(cx)
+        self.poll_queued_inbound(cx);
+
+        if let Some(command) = self.poll_drain(cx) {
+            return Poll::Ready(Some(command));
+        }
+
+        while self.num_pending_opens < MAX_PENDING_OPENS {
+            match self.rx.poll_recv(cx) {
+                Poll::Ready(Some(command)) => match &command {
+                    ProtocolCommand::OpenSubstream { protocol, .. } => {
+                        let protocol = protocol.clone();
+                        self.queue_open(protocol, command);
+                    }
+                    ProtocolCommand::ForceClose => return Poll::Ready(Some(command)),
+                },
+                Poll::Ready(None) | Poll::Pending => break,
+            }
+        }
+
+        match self.next_open() {
+            Some(command) => Poll::Ready(Some(command)),
+            None => self
+                .rx
+                .poll_recv(cx)
+                .map(|command| command.map(|command| self.track_open(command))),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mock::substream::MockSubstream;
+    use crate::{mock::substream::MockSubstream, protocol::InboundSubstreamFilter};
     use std::collections::HashSet;
 
+    #[tokio::test]
+    async fn substream_opens_are_interleaved_across_protocols() {
+        let (tx, _rx) = channel(64);
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            tx,
+            Default::default(),
+            HashMap::new(),
+        );
+        let mut handle = protocol_set.connection.clone();
+
+        // `/proto/1` requests all of its substreams before `/proto/2` gets to request any
+        for (protocol, offset) in [("/proto/1", 0usize), ("/proto/2", 100usize)] {
+            for i in 0..10 {
+                let permit = handle.try_get_permit().unwrap();
+                handle
+                    .open_substream(
+                        ProtocolName::from(protocol),
+                        Vec::new(),
+                        SubstreamId::from(offset + i),
+                        permit,
+                    )
+                    .unwrap();
+            }
+        }
+        handle.force_close().unwrap();
+
+        // force close isn't queued behind the substream open requests
+        assert!(std::matches!(
+            protocol_set.next().await,
+            Some(ProtocolCommand::ForceClose)
+        ));
+
+        let mut opened = Vec::new();
+        for _ in 0..20 {
+            match protocol_set.next().await {
+                Some(ProtocolCommand::OpenSubstream {
+                    protocol,
+                    substream_id,
+                    ..
+                }) => opened.push((protocol, substream_id)),
+                command => panic!("unexpected command: {command:?}"),
+            }
+        }
+
+        let expected = (0..10)
+            .flat_map(|i| {
+                [
+                    (ProtocolName::from("/proto/1"), SubstreamId::from(i)),
+                    (ProtocolName::from("/proto/2"), SubstreamId::from(100 + i)),
+                ]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(opened, expected);
+    }
+
     #[tokio::test]
     async fn fallback_is_provided() {
         let (tx, _rx) = channel(64);
@@ -448,6 +1011,11 @@ mod tests {
                         ProtocolName::from("/notif/1/fallback/1"),
                         ProtocolName::from("/notif/1/fallback/2"),
                     ],
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         );
@@ -495,6 +1063,11 @@ mod tests {
                         ProtocolName::from("/notif/1/fallback/1"),
                         ProtocolName::from("/notif/1/fallback/2"),
                     ],
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         );
@@ -542,6 +1115,11 @@ mod tests {
                         ProtocolName::from("/notif/1/fallback/1"),
                         ProtocolName::from("/notif/1/fallback/2"),
                     ],
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         );
@@ -570,4 +1148,321 @@ mod tests {
             _ => panic!("invalid event received"),
         }
     }
+
+    #[tokio::test]
+    async fn queued_inbound_substreams_delivered_most_recent_first() {
+        let (tx, _rx) = channel(64);
+        let (tx1, mut rx1) = channel(1);
+
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            tx,
+            Default::default(),
+            HashMap::from_iter([(
+                ProtocolName::from("/notif/1"),
+                ProtocolContext {
+                    tx: tx1.clone(),
+                    codec: ProtocolCodec::Identity(32),
+                    fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: InboundSubstreamOrder::Lifo,
+                },
+            )]),
+        );
+
+        // protocol is lagging behind so the inbound substreams are queued
+        tx1.try_send(InnerTransportEvent::ConnectionClosed {
+            peer: PeerId::random(),
+            connection: ConnectionId::from(0usize),
+        })
+        .unwrap();
+
+        let peers = (0..3).map(|_| PeerId::random()).collect::<Vec<_>>();
+        for (i, peer) in peers.iter().enumerate() {
+            protocol_set
+                .report_substream_open(
+                    *peer,
+                    ProtocolName::from("/notif/1"),
+                    Direction::Inbound,
+                    Substream::new_mock(
+                        *peer,
+                        SubstreamId::from(i),
+                        Box::new(MockSubstream::new()),
+                    ),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert!(std::matches!(
+            rx1.recv().await,
+            Some(InnerTransportEvent::ConnectionClosed { .. })
+        ));
+
+        let mut delivered = Vec::new();
+        for _ in 0..3 {
+            // poll the protocol set to deliver the next queued substream
+            assert!(futures::poll!(protocol_set.next()).is_pending());
+
+            match rx1.try_recv().unwrap() {
+                InnerTransportEvent::SubstreamOpened { peer, .. } => delivered.push(peer),
+                _ => panic!("invalid event received"),
+            }
+        }
+
+        assert_eq!(delivered, peers.into_iter().rev().collect::<Vec<_>>());
+        assert!(protocol_set.queued_inbound.is_empty());
+    }
+
+    #[tokio::test]
+    async fn inbound_substream_rejected_by_filter() {
+        let (tx, _rx) = channel(64);
+        let (tx1, mut rx1) = channel(64);
+
+        // accept only one inbound substream per peer
+        let open_substreams = Arc::new(parking_lot::Mutex::new(HashMap::<PeerId, usize>::new()));
+        let filter = {
+            let open_substreams = Arc::clone(&open_substreams);
+
+            InboundSubstreamFilter::new(move |peer, _protocol| {
+                let mut open_substreams = open_substreams.lock();
+                let count = open_substreams.entry(*peer).or_default();
+                *count += 1;
+                *count <= 1
+            })
+        };
+
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            tx,
+            Default::default(),
+            HashMap::from_iter([(
+                ProtocolName::from("/notif/1"),
+                ProtocolContext {
+                    tx: tx1,
+                    codec: ProtocolCodec::Identity(32),
+                    fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: Some(filter),
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
+                },
+            )]),
+        );
+
+        let peer = PeerId::random();
+
+        // the mock substreams have no expectations set so reading the handshake from a
+        // rejected substream would cause a panic
+        for i in 0..3usize {
+            protocol_set
+                .report_substream_open(
+                    peer,
+                    ProtocolName::from("/notif/1"),
+                    Direction::Inbound,
+                    Substream::new_mock(peer, SubstreamId::from(i), Box::new(MockSubstream::new())),
+                )
+                .await
+                .unwrap();
+        }
+
+        match rx1.try_recv().unwrap() {
+            InnerTransportEvent::SubstreamOpened {
+                peer: opened_peer,
+                direction,
+                ..
+            } => {
+                assert_eq!(opened_peer, peer);
+                assert_eq!(direction, Direction::Inbound);
+            }
+            _ => panic!("invalid event received"),
+        }
+        assert!(rx1.try_recv().is_err());
+        assert_eq!(open_substreams.lock().get(&peer), Some(&3usize));
+
+        // outbound substreams are not filtered
+        protocol_set
+            .report_substream_open(
+                peer,
+                ProtocolName::from("/notif/1"),
+                Direction::Outbound(SubstreamId::from(3usize)),
+                Substream::new_mock(
+                    peer,
+                    SubstreamId::from(3usize),
+                    Box::new(MockSubstream::new()),
+                ),
+            )
+            .await
+            .unwrap();
+
+        match rx1.try_recv().unwrap() {
+            InnerTransportEvent::SubstreamOpened { direction, .. } => {
+                assert_eq!(direction, Direction::Outbound(SubstreamId::from(3usize)));
+            }
+            _ => panic!("invalid event received"),
+        }
+        assert_eq!(open_substreams.lock().get(&peer), Some(&3usize));
+    }
+
+    #[tokio::test]
+    async fn connection_closed_reported_even_if_protocol_has_exited() {
+        let (tx, mut rx) = channel(64);
+        let (tx1, rx1) = channel(64);
+        let (tx2, mut rx2) = channel(64);
+
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            tx,
+            Default::default(),
+            HashMap::from_iter([
+                (
+                    ProtocolName::from("/notif/1"),
+                    ProtocolContext {
+                        tx: tx1,
+                        codec: ProtocolCodec::Identity(32),
+                        fallback_names: Vec::new(),
+                        idle_timeout: None,
+                        inbound_filter: None,
+                        priority: Default::default(),
+                        latency_sensitive: false,
+                        inbound_order: Default::default(),
+                    },
+                ),
+                (
+                    ProtocolName::from("/notif/2"),
+                    ProtocolContext {
+                        tx: tx2,
+                        codec: ProtocolCodec::Identity(32),
+                        fallback_names: Vec::new(),
+                        idle_timeout: None,
+                        inbound_filter: None,
+                        priority: Default::default(),
+                        latency_sensitive: false,
+                        inbound_order: Default::default(),
+                    },
+                ),
+            ]),
+        );
+        drop(rx1);
+
+        let peer = PeerId::random();
+        assert!(protocol_set
+            .report_connection_closed(peer, ConnectionId::from(0usize))
+            .await
+            .is_err());
+
+        match rx2.recv().await.unwrap() {
+            InnerTransportEvent::ConnectionClosed {
+                peer: closed_peer,
+                connection,
+            } => {
+                assert_eq!(closed_peer, peer);
+                assert_eq!(connection, ConnectionId::from(0usize));
+            }
+            _ => panic!("invalid event received"),
+        }
+
+        let Some(TransportManagerEvent::ConnectionClosed {
+            peer: closed_peer, ..
+        }) = rx.recv().await
+        else {
+            panic!("invalid event received");
+        };
+        assert_eq!(closed_peer, peer);
+    }
+
+    #[tokio::test]
+    async fn unopened_substreams_fail_when_connection_is_closed() {
+        let (tx, _rx) = channel(64);
+        let (tx1, mut rx1) = channel(64);
+
+        let mut protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            tx,
+            Default::default(),
+            HashMap::from_iter([(
+                ProtocolName::from("/notif/1"),
+                ProtocolContext {
+                    tx: tx1,
+                    codec: ProtocolCodec::Identity(32),
+                    fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
+                },
+            )]),
+        );
+        let mut handle = protocol_set.connection.clone();
+
+        for i in 0..3usize {
+            let permit = handle.try_get_permit().unwrap();
+            handle
+                .open_substream(
+                    ProtocolName::from("/notif/1"),
+                    Vec::new(),
+                    SubstreamId::from(i),
+                    permit,
+                )
+                .unwrap();
+        }
+
+        // first substream is opened, second is being opened by the connection
+        // and the third is still waiting to be returned to the connection
+        for _ in 0..2 {
+            assert!(std::matches!(
+                protocol_set.next().await,
+                Some(ProtocolCommand::OpenSubstream { .. })
+            ));
+        }
+        protocol_set
+            .report_substream_open(
+                PeerId::random(),
+                ProtocolName::from("/notif/1"),
+                Direction::Outbound(SubstreamId::from(0usize)),
+                Substream::new_mock(
+                    PeerId::random(),
+                    SubstreamId::from(0usize),
+                    Box::new(MockSubstream::new()),
+                ),
+            )
+            .await
+            .unwrap();
+        assert!(std::matches!(
+            rx1.recv().await,
+            Some(InnerTransportEvent::SubstreamOpened { .. })
+        ));
+
+        let peer = PeerId::random();
+        protocol_set
+            .report_connection_closed(peer, ConnectionId::from(0usize))
+            .await
+            .unwrap();
+
+        let mut failed = HashSet::new();
+        for _ in 0..2 {
+            match rx1.recv().await {
+                Some(InnerTransportEvent::SubstreamOpenFailure {
+                    substream,
+                    error: SubstreamError::ConnectionClosed,
+                }) => {
+                    failed.insert(substream);
+                }
+                event => panic!("invalid event received: {event:?}"),
+            }
+        }
+        assert_eq!(
+            failed,
+            HashSet::from_iter([SubstreamId::from(1usize), SubstreamId::from(2usize)])
+        );
+        assert!(std::matches!(
+            rx1.recv().await,
+            Some(InnerTransportEvent::ConnectionClosed { .. })
+        ));
+    }
 }
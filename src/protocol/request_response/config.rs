@@ -20,11 +20,14 @@
 
 use crate::{
     codec::ProtocolCodec,
-    protocol::request_response::{
-        handle::{InnerRequestResponseEvent, RequestResponseCommand, RequestResponseHandle},
-        REQUEST_TIMEOUT,
+    protocol::{
+        request_response::{
+            handle::{InnerRequestResponseEvent, RequestResponseCommand, RequestResponseHandle},
+            REQUEST_TIMEOUT,
+        },
+        InboundSubstreamFilter, InboundSubstreamOrder, SubstreamPriority,
     },
-    types::protocol::ProtocolName,
+    types::protocol::{ProtocolName, VersionedProtocol},
     DEFAULT_CHANNEL_SIZE,
 };
 
@@ -35,6 +38,25 @@ use std::{
     time::Duration,
 };
 
+/// Role of the local node in a request-response protocol.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Local node both sends requests and answers inbound requests.
+    #[default]
+    Full,
+
+    /// Local node only answers inbound requests.
+    ///
+    /// Sending a request over the protocol returns an error.
+    InboundOnly,
+
+    /// Local node only sends requests.
+    ///
+    /// Inbound substreams are closed before the request is read, which the remote node sees as
+    /// a rejected request.
+    OutboundOnly,
+}
+
 /// Request-response protocol configuration.
 pub struct Config {
     /// Protocol name.
@@ -60,6 +82,27 @@ pub struct Config {
 
     /// Maximum number of concurrent inbound requests.
     pub(crate) max_concurrent_inbound_request: Option<usize>,
+
+    /// Maximum number of concurrent outbound requests.
+    pub(crate) max_concurrent_outbound_requests: Option<usize>,
+
+    /// Maximum number of outbound requests waiting for a free slot.
+    pub(crate) max_queued_outbound_requests: Option<usize>,
+
+    /// Filter for inbound substreams.
+    pub(crate) inbound_substream_filter: Option<InboundSubstreamFilter>,
+
+    /// Priority of the substreams of the protocol.
+    pub(crate) substream_priority: SubstreamPriority,
+
+    /// Is the protocol latency-sensitive.
+    pub(crate) latency_sensitive: bool,
+
+    /// Order in which queued inbound substreams are delivered to the protocol.
+    pub(crate) inbound_substream_order: InboundSubstreamOrder,
+
+    /// Role of the local node in the protocol.
+    pub(crate) role: Role,
 }
 
 impl Config {
@@ -85,6 +128,13 @@ impl Config {
                 next_request_id,
                 timeout,
                 max_concurrent_inbound_request,
+                max_concurrent_outbound_requests: None,
+                max_queued_outbound_requests: None,
+                inbound_substream_filter: None,
+                substream_priority: SubstreamPriority::High,
+                latency_sensitive: false,
+                inbound_substream_order: InboundSubstreamOrder::Fifo,
+                role: Role::Full,
                 codec: ProtocolCodec::UnsignedVarint(Some(max_message_size)),
             },
             handle,
@@ -113,6 +163,27 @@ pub struct ConfigBuilder {
 
     /// Maximum number of concurrent inbound requests.
     max_concurrent_inbound_request: Option<usize>,
+
+    /// Maximum number of concurrent outbound requests.
+    max_concurrent_outbound_requests: Option<usize>,
+
+    /// Maximum number of outbound requests waiting for a free slot.
+    max_queued_outbound_requests: Option<usize>,
+
+    /// Filter for inbound substreams.
+    inbound_substream_filter: Option<InboundSubstreamFilter>,
+
+    /// Priority of the substreams of the protocol.
+    substream_priority: SubstreamPriority,
+
+    /// Is the protocol latency-sensitive.
+    latency_sensitive: bool,
+
+    /// Order in which queued inbound substreams are delivered to the protocol.
+    inbound_substream_order: InboundSubstreamOrder,
+
+    /// Role of the local node in the protocol.
+    role: Role,
 }
 
 impl ConfigBuilder {
@@ -124,9 +195,27 @@ impl ConfigBuilder {
             max_message_size: None,
             timeout: Some(REQUEST_TIMEOUT),
             max_concurrent_inbound_request: None,
+            max_concurrent_outbound_requests: None,
+            max_queued_outbound_requests: None,
+            inbound_substream_filter: None,
+            substream_priority: SubstreamPriority::High,
+            latency_sensitive: false,
+            inbound_substream_order: InboundSubstreamOrder::Fifo,
+            role: Role::Full,
         }
     }
 
+    /// Create new [`ConfigBuilder`] for a protocol that supports a range of versions.
+    ///
+    /// The highest version is used as the protocol name and the older versions as fallback
+    /// names, so the highest version supported by both peers is negotiated. The negotiated
+    /// version can be resolved with [`VersionedProtocol::version()`].
+    pub fn new_versioned(protocol: &VersionedProtocol) -> Self {
+        let (protocol_name, fallback_names) = protocol.names();
+
+        Self::new(protocol_name).with_fallback_names(fallback_names)
+    }
+
     /// Set maximum message size.
     pub fn with_max_size(mut self, max_message_size: usize) -> Self {
         self.max_message_size = Some(max_message_size);
@@ -134,6 +223,10 @@ impl ConfigBuilder {
     }
 
     /// Set fallback names.
+    ///
+    /// When opening an outbound substream, the main protocol name is proposed first, followed
+    /// by the fallback names in the order they were given. If the substream was negotiated
+    /// using one of the fallbacks, the name is reported in `fallback` of the received response.
     pub fn with_fallback_names(mut self, fallback_names: Vec<ProtocolName>) -> Self {
         self.fallback_names = fallback_names;
         self
@@ -158,14 +251,98 @@ impl ConfigBuilder {
         self
     }
 
+    /// Specify the maximum number of concurrent outbound requests. By default the number of
+    /// outbound requests is not limited.
+    ///
+    /// Requests sent while the number of outbound requests is already at a maximum are queued and
+    /// sent, in the order they were made, as earlier requests complete.
+    pub fn with_max_concurrent_outbound_requests(
+        mut self,
+        max_concurrent_outbound_requests: usize,
+    ) -> Self {
+        self.max_concurrent_outbound_requests = Some(max_concurrent_outbound_requests);
+        self
+    }
+
+    /// Specify the maximum number of outbound requests waiting for a free slot. By default the
+    /// queue is not bounded.
+    ///
+    /// Only has an effect together with
+    /// [`ConfigBuilder::with_max_concurrent_outbound_requests()`]. Requests sent while the queue
+    /// is full fail with [`RequestResponseError::Busy`](super::RequestResponseError::Busy).
+    pub fn with_max_queued_outbound_requests(
+        mut self,
+        max_queued_outbound_requests: usize,
+    ) -> Self {
+        self.max_queued_outbound_requests = Some(max_queued_outbound_requests);
+        self
+    }
+
+    /// Set synchronous filter for inbound substreams.
+    ///
+    /// The filter is called for each inbound substream before the request is read and if it
+    /// returns `false`, the substream is closed without the request being reported to the user.
+    /// See [`InboundSubstreamFilter`] for more details.
+    pub fn with_inbound_substream_filter(mut self, filter: InboundSubstreamFilter) -> Self {
+        self.inbound_substream_filter = Some(filter);
+        self
+    }
+
+    /// Set the priority of the protocol's substreams in the connection's stream multiplexer.
+    ///
+    /// Requests and responses are sent with [`SubstreamPriority::High`] by default so they aren't
+    /// delayed by notifications sent over the same connection.
+    pub fn with_substream_priority(mut self, priority: SubstreamPriority) -> Self {
+        self.substream_priority = priority;
+        self
+    }
+
+    /// Mark the protocol as latency-sensitive.
+    ///
+    /// If any installed protocol is latency-sensitive, the TCP and WebSocket connections of the
+    /// node are created with Nagle's algorithm disabled (`TCP_NODELAY`) so that requests and
+    /// responses are written to the network immediately.
+    pub fn with_latency_sensitive(mut self, latency_sensitive: bool) -> Self {
+        self.latency_sensitive = latency_sensitive;
+        self
+    }
+
+    /// Set the order in which inbound substreams are delivered to the protocol when it falls
+    /// behind, for example [`InboundSubstreamOrder::Lifo`] to answer the freshest requests first.
+    ///
+    /// Defaults to [`InboundSubstreamOrder::Fifo`].
+    pub fn with_inbound_substream_order(mut self, order: InboundSubstreamOrder) -> Self {
+        self.inbound_substream_order = order;
+        self
+    }
+
+    /// Set the role of the local node in the protocol. By default the node both sends and answers
+    /// requests.
+    ///
+    /// See [`Role`] for more details.
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
     /// Build [`Config`].
     pub fn build(mut self) -> (Config, RequestResponseHandle) {
-        Config::new(
+        let (mut config, mut handle) = Config::new(
             self.protocol_name,
             self.fallback_names,
             self.max_message_size.take().expect("maximum message size to be set"),
             self.timeout.take().expect("timeout to exist"),
             self.max_concurrent_inbound_request,
-        )
+        );
+        config.max_concurrent_outbound_requests = self.max_concurrent_outbound_requests;
+        config.max_queued_outbound_requests = self.max_queued_outbound_requests;
+        config.inbound_substream_filter = self.inbound_substream_filter;
+        config.substream_priority = self.substream_priority;
+        config.latency_sensitive = self.latency_sensitive;
+        config.inbound_substream_order = self.inbound_substream_order;
+        config.role = self.role;
+        handle.role = self.role;
+
+        (config, handle)
     }
 }
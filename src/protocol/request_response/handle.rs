@@ -21,6 +21,7 @@
 use crate::{
     error::{ImmediateDialError, SubstreamError},
     multistream_select::ProtocolError,
+    protocol::request_response::config::Role,
     types::{protocol::ProtocolName, RequestId},
     Error, PeerId,
 };
@@ -32,7 +33,7 @@ use tokio::sync::{
 };
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     io::ErrorKind,
     pin::Pin,
     sync::{
@@ -65,6 +66,10 @@ pub enum RequestResponseError {
 
     /// Protocol not supported.
     UnsupportedProtocol,
+
+    /// The maximum number of concurrent outbound requests has been reached and the queue for
+    /// outbound requests is full.
+    Busy,
 }
 
 /// The reason why a request was rejected.
@@ -319,6 +324,14 @@ pub struct RequestResponseHandle {
 
     /// Next ephemeral request ID.
     next_request_id: Arc<AtomicUsize>,
+
+    /// Events received while waiting for the responses of [`send_request_to_any()`].
+    ///
+    /// [`send_request_to_any()`]: RequestResponseHandle::send_request_to_any
+    pending_events: VecDeque<RequestResponseEvent>,
+
+    /// Role of the local node in the protocol.
+    pub(super) role: Role,
 }
 
 impl RequestResponseHandle {
@@ -333,6 +346,8 @@ impl RequestResponseHandle {
             command_tx,
             next_request_id,
             pending_responses: HashMap::new(),
+            pending_events: VecDeque::new(),
+            role: Role::Full,
         }
     }
 
@@ -370,12 +385,25 @@ impl RequestResponseHandle {
         RequestId::from(request_id)
     }
 
+    /// Check that the protocol is allowed to send requests.
+    fn ensure_outbound(&self) -> crate::Result<()> {
+        match self.role {
+            Role::InboundOnly => Err(Error::NotSupported(
+                "cannot send requests over inbound-only protocol".to_string(),
+            )),
+            Role::Full | Role::OutboundOnly => Ok(()),
+        }
+    }
+
     /// Send request to remote peer.
     ///
     /// While the returned `RequestId` is guaranteed to be unique for this request-response
     /// protocol, it's not unique across all installed request-response protocols. That is,
     /// multiple request-response protocols can return the same `RequestId` and this must be
     /// handled by the calling code correctly if the `RequestId`s are stored somewhere.
+    ///
+    /// Returns [`Error::NotSupported`] if the protocol was configured as
+    /// [`Role::InboundOnly`].
     pub async fn send_request(
         &mut self,
         peer: PeerId,
@@ -384,6 +412,7 @@ impl RequestResponseHandle {
     ) -> crate::Result<RequestId> {
         tracing::trace!(target: LOG_TARGET, ?peer, "send request to peer");
 
+        self.ensure_outbound()?;
         let request_id = self.next_request_id();
         self.command_tx
             .send(RequestResponseCommand::SendRequest {
@@ -412,6 +441,7 @@ impl RequestResponseHandle {
     ) -> crate::Result<RequestId> {
         tracing::trace!(target: LOG_TARGET, ?peer, "send request to peer");
 
+        self.ensure_outbound()?;
         let request_id = self.next_request_id();
         self.command_tx
             .try_send(RequestResponseCommand::SendRequest {
@@ -440,6 +470,7 @@ impl RequestResponseHandle {
             "send request with fallback to peer",
         );
 
+        self.ensure_outbound()?;
         let request_id = self.next_request_id();
         self.command_tx
             .send(RequestResponseCommand::SendRequestWithFallback {
@@ -471,6 +502,7 @@ impl RequestResponseHandle {
             "send request with fallback to peer",
         );
 
+        self.ensure_outbound()?;
         let request_id = self.next_request_id();
         self.command_tx
             .try_send(RequestResponseCommand::SendRequestWithFallback {
@@ -484,6 +516,78 @@ impl RequestResponseHandle {
             .map_err(|_| Error::ChannelClogged)
     }
 
+    /// Send request to each peer in `peers` and return the first response accepted by `validate`.
+    ///
+    /// The request is sent to all peers concurrently and peers which are not connected are
+    /// dialed. Responses which `validate` rejects are discarded and the function keeps waiting
+    /// for the remaining peers to respond. Once a valid response is received, the requests still
+    /// in flight are canceled and the response is returned with the ID of the peer that sent it.
+    ///
+    /// Other events received while waiting for the responses are not lost but returned when
+    /// [`RequestResponseHandle`] is polled next time.
+    ///
+    /// Returns [`Error::InsufficientPeers`] if `peers` is empty or if none of the peers sent
+    /// a valid response.
+    pub async fn send_request_to_any(
+        &mut self,
+        peers: &[PeerId],
+        request: Vec<u8>,
+        validate: fn(&[u8]) -> bool,
+    ) -> crate::Result<(PeerId, Vec<u8>)> {
+        tracing::trace!(target: LOG_TARGET, ?peers, "send request to any peer");
+
+        let mut pending = HashSet::with_capacity(peers.len());
+        for peer in peers {
+            pending.insert(self.send_request(*peer, request.clone(), DialOptions::Dial).await?);
+        }
+
+        while !pending.is_empty() {
+            let event = futures::future::poll_fn(|cx| self.poll_next_event(cx))
+                .await
+                .ok_or(Error::EssentialTaskClosed)?;
+
+            match event {
+                RequestResponseEvent::ResponseReceived {
+                    peer,
+                    request_id,
+                    response,
+                    ..
+                } if pending.remove(&request_id) => {
+                    if validate(&response) {
+                        for request_id in pending {
+                            self.cancel_request(request_id).await;
+                        }
+
+                        return Ok((peer, response));
+                    }
+
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?request_id,
+                        "discard invalid response",
+                    );
+                }
+                RequestResponseEvent::RequestFailed {
+                    peer,
+                    request_id,
+                    error,
+                } if pending.remove(&request_id) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        ?request_id,
+                        ?error,
+                        "request failed",
+                    );
+                }
+                event => self.pending_events.push_back(event),
+            }
+        }
+
+        Err(Error::InsufficientPeers)
+    }
+
     /// Send response to remote peer.
     pub fn send_response(&mut self, request_id: RequestId, response: Vec<u8>) {
         match self.pending_responses.remove(&request_id) {
@@ -529,10 +633,9 @@ impl RequestResponseHandle {
     }
 }
 
-impl futures::Stream for RequestResponseHandle {
-    type Item = RequestResponseEvent;
-
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+impl RequestResponseHandle {
+    /// Poll next event from the request-response protocol.
+    fn poll_next_event(&mut self, cx: &mut Context<'_>) -> Poll<Option<RequestResponseEvent>> {
         match futures::ready!(self.event_rx.poll_recv(cx)) {
             None => Poll::Ready(None),
             Some(event) => match event {
@@ -556,3 +659,15 @@ impl futures::Stream for RequestResponseHandle {
         }
     }
 }
+
+impl futures::Stream for RequestResponseHandle {
+    type Item = RequestResponseEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+
+        self.poll_next_event(cx)
+    }
+}
@@ -43,7 +43,7 @@ use tokio::{
 };
 
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     io::ErrorKind,
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -52,7 +52,7 @@ use std::{
     time::Duration,
 };
 
-pub use config::{Config, ConfigBuilder};
+pub use config::{Config, ConfigBuilder, Role};
 pub use handle::{
     DialOptions, RejectReason, RequestResponseError, RequestResponseEvent, RequestResponseHandle,
 };
@@ -112,6 +112,24 @@ impl RequestContext {
     }
 }
 
+/// Outbound request waiting for a free slot.
+struct QueuedRequest {
+    /// Peer ID.
+    peer: PeerId,
+
+    /// Request ID.
+    request_id: RequestId,
+
+    /// Request.
+    request: Vec<u8>,
+
+    /// Dial options.
+    dial_options: DialOptions,
+
+    /// Fallback request.
+    fallback: Option<(ProtocolName, Vec<u8>)>,
+}
+
 /// Peer context.
 struct PeerContext {
     /// Active requests.
@@ -183,6 +201,19 @@ pub(crate) struct RequestResponseProtocol {
 
     /// Maximum concurrent inbound requests, if specified.
     max_concurrent_inbound_requests: Option<usize>,
+
+    /// Maximum concurrent outbound requests, if specified.
+    max_concurrent_outbound_requests: Option<usize>,
+
+    /// Maximum number of queued outbound requests, if specified.
+    max_queued_outbound_requests: Option<usize>,
+
+    /// Outbound requests waiting for the number of active outbound requests to drop below
+    /// `max_concurrent_outbound_requests`.
+    queued_outbound: VecDeque<QueuedRequest>,
+
+    /// Role of the local node in the protocol.
+    role: Role,
 }
 
 impl RequestResponseProtocol {
@@ -203,6 +234,10 @@ impl RequestResponseProtocol {
             pending_inbound_requests: SubstreamSet::new(),
             pending_outbound_responses: FuturesUnordered::new(),
             max_concurrent_inbound_requests: config.max_concurrent_inbound_request,
+            max_concurrent_outbound_requests: config.max_concurrent_outbound_requests,
+            max_queued_outbound_requests: config.max_queued_outbound_requests,
+            queued_outbound: VecDeque::new(),
+            role: config.role,
         }
     }
 
@@ -591,6 +626,19 @@ impl RequestResponseProtocol {
     ) -> crate::Result<()> {
         tracing::trace!(target: LOG_TARGET, ?peer, protocol = %self.protocol, "handle inbound substream");
 
+        if self.role == Role::OutboundOnly {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                ?fallback,
+                "rejecting request, protocol is outbound-only",
+            );
+
+            let _ = substream.close().await;
+            return Ok(());
+        }
+
         if let Some(max_requests) = self.max_concurrent_inbound_requests {
             let num_inbound_requests =
                 self.pending_inbound_requests.len() + self.pending_outbound_responses.len();
@@ -711,7 +759,14 @@ impl RequestResponseProtocol {
             .map_err(From::from)
     }
 
-    /// Send request to remote peer.
+    /// Get the number of outbound requests in progress.
+    fn active_outbound_requests(&self) -> usize {
+        self.pending_dials.len()
+            + self.peers.values().map(|context| context.active.len()).sum::<usize>()
+    }
+
+    /// Send request to remote peer or queue it if the maximum number of concurrent outbound
+    /// requests has been reached.
     fn on_send_request(
         &mut self,
         peer: PeerId,
@@ -719,6 +774,103 @@ impl RequestResponseProtocol {
         request: Vec<u8>,
         dial_options: DialOptions,
         fallback: Option<(ProtocolName, Vec<u8>)>,
+    ) -> Result<(), RequestResponseError> {
+        let Some(max_requests) = self.max_concurrent_outbound_requests else {
+            return self.start_request(peer, request_id, request, dial_options, fallback);
+        };
+
+        // queued requests are sent first so requests are sent in the order they were made
+        if self.queued_outbound.is_empty() && self.active_outbound_requests() < max_requests {
+            return self.start_request(peer, request_id, request, dial_options, fallback);
+        }
+
+        if self
+            .max_queued_outbound_requests
+            .is_some_and(|max_queued| self.queued_outbound.len() >= max_queued)
+        {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                ?request_id,
+                "outbound request queue is full",
+            );
+
+            return Err(RequestResponseError::Busy);
+        }
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            ?request_id,
+            queued = ?self.queued_outbound.len(),
+            "maximum number of outbound requests reached, queue request",
+        );
+
+        self.queued_outbound.push_back(QueuedRequest {
+            peer,
+            request_id,
+            request,
+            dial_options,
+            fallback,
+        });
+
+        Ok(())
+    }
+
+    /// Send queued requests while the number of active outbound requests is below the limit.
+    async fn send_queued_requests(&mut self) {
+        let Some(max_requests) = self.max_concurrent_outbound_requests else {
+            return;
+        };
+
+        while !self.queued_outbound.is_empty() && self.active_outbound_requests() < max_requests {
+            let Some(QueuedRequest {
+                peer,
+                request_id,
+                request,
+                dial_options,
+                fallback,
+            }) = self.queued_outbound.pop_front()
+            else {
+                return;
+            };
+
+            if let Err(error) =
+                self.start_request(peer, request_id, request, dial_options, fallback)
+            {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    ?request_id,
+                    ?error,
+                    "failed to send queued request",
+                );
+
+                if let Err(error) = self.report_request_failure(peer, request_id, error).await {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?request_id,
+                        ?error,
+                        "failed to report request failure",
+                    );
+                }
+            }
+        }
+    }
+
+    /// Start sending request to remote peer.
+    fn start_request(
+        &mut self,
+        peer: PeerId,
+        request_id: RequestId,
+        request: Vec<u8>,
+        dial_options: DialOptions,
+        fallback: Option<(ProtocolName, Vec<u8>)>,
     ) -> Result<(), RequestResponseError> {
         tracing::trace!(
             target: LOG_TARGET,
@@ -863,6 +1015,13 @@ impl RequestResponseProtocol {
     fn on_cancel_request(&mut self, request_id: RequestId) -> crate::Result<()> {
         tracing::trace!(target: LOG_TARGET, protocol = %self.protocol, ?request_id, "cancel outbound request");
 
+        if let Some(index) =
+            self.queued_outbound.iter().position(|queued| queued.request_id == request_id)
+        {
+            self.queued_outbound.remove(index);
+            return Ok(());
+        }
+
         match self.pending_outbound_cancels.remove(&request_id) {
             Some(tx) => tx.send(()).map_err(|_| Error::SubstreamDoesntExist),
             None => {
@@ -933,6 +1092,8 @@ impl RequestResponseProtocol {
             }
 
             TransportEvent::DialFailure { peer, .. } => self.on_dial_failure(peer).await,
+
+            TransportEvent::ConnectionAddressChanged { .. } => {}
         }
     }
 
@@ -1080,6 +1241,8 @@ impl RequestResponseProtocol {
                     }
                 },
             }
+
+            self.send_queued_requests().await;
         }
     }
 }
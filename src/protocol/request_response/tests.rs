@@ -19,28 +19,32 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    crypto::ed25519::Keypair,
-    mock::substream::{DummySubstream, MockSubstream},
+    crypto::Keypair,
+    mock::{
+        connection::MockConnection,
+        substream::{DummySubstream, MockSubstream},
+    },
     protocol::{
         request_response::{
-            ConfigBuilder, DialOptions, RequestResponseError, RequestResponseEvent,
-            RequestResponseHandle, RequestResponseProtocol,
+            ConfigBuilder, DialOptions, RejectReason, RequestResponseError, RequestResponseEvent,
+            RequestResponseHandle, RequestResponseProtocol, Role,
         },
         InnerTransportEvent, SubstreamError, TransportService,
     },
     substream::Substream,
     transport::{
-        manager::{limits::ConnectionLimitsConfig, TransportManager},
+        manager::{limits::ConnectionLimitsConfig, reputation::ReputationConfig, TransportManager},
         KEEP_ALIVE_TIMEOUT,
     },
     types::{RequestId, SubstreamId},
     BandwidthSink, Error, PeerId, ProtocolName,
 };
 
-use futures::StreamExt;
+use bytes::{Bytes, BytesMut};
+use futures::{SinkExt, StreamExt};
 use tokio::sync::mpsc::Sender;
 
-use std::{collections::HashSet, task::Poll};
+use std::{collections::HashSet, task::Poll, time::Duration};
 
 // create new protocol for testing
 fn protocol() -> (
@@ -48,13 +52,26 @@ fn protocol() -> (
     RequestResponseHandle,
     TransportManager,
     Sender<InnerTransportEvent>,
+) {
+    protocol_with_config(ConfigBuilder::new(ProtocolName::from("/req/1")).with_max_size(1024))
+}
+
+// create new protocol for testing from `builder`
+fn protocol_with_config(
+    builder: ConfigBuilder,
+) -> (
+    RequestResponseProtocol,
+    RequestResponseHandle,
+    TransportManager,
+    Sender<InnerTransportEvent>,
 ) {
     let (manager, handle) = TransportManager::new(
-        Keypair::generate(),
+        Keypair::generate_ed25519(),
         HashSet::new(),
         BandwidthSink::new(),
         8usize,
         ConnectionLimitsConfig::default(),
+        ReputationConfig::default(),
     );
 
     let peer = PeerId::random();
@@ -66,8 +83,7 @@ fn protocol() -> (
         handle,
         KEEP_ALIVE_TIMEOUT,
     );
-    let (config, handle) =
-        ConfigBuilder::new(ProtocolName::from("/req/1")).with_max_size(1024).build();
+    let (config, handle) = builder.build();
 
     (
         RequestResponseProtocol::new(transport_service, config),
@@ -313,3 +329,180 @@ async fn request_failure_reported_once() {
     })
     .await;
 }
+
+#[tokio::test]
+async fn inbound_only_protocol_cannot_send_requests() {
+    let (_config, mut handle) = ConfigBuilder::new(ProtocolName::from("/req/1"))
+        .with_max_size(1024)
+        .with_role(Role::InboundOnly)
+        .build();
+    let peer = PeerId::random();
+
+    assert!(matches!(
+        handle.send_request(peer, vec![1, 2, 3], DialOptions::Reject).await,
+        Err(Error::NotSupported(_))
+    ));
+    assert!(matches!(
+        handle.try_send_request(peer, vec![1, 2, 3], DialOptions::Reject),
+        Err(Error::NotSupported(_))
+    ));
+    assert!(matches!(
+        handle
+            .send_request_with_fallback(
+                peer,
+                vec![1, 2, 3],
+                (ProtocolName::from("/req/0"), vec![4, 5, 6]),
+                DialOptions::Reject,
+            )
+            .await,
+        Err(Error::NotSupported(_))
+    ));
+    assert!(matches!(
+        handle.try_send_request_with_fallback(
+            peer,
+            vec![1, 2, 3],
+            (ProtocolName::from("/req/0"), vec![4, 5, 6]),
+            DialOptions::Reject,
+        ),
+        Err(Error::NotSupported(_))
+    ));
+}
+
+#[tokio::test]
+async fn connection_reset_mid_request() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (protocol, mut handle, _manager, tx) = protocol();
+    tokio::spawn(protocol.run());
+
+    let peer = PeerId::random();
+    let mut connection = MockConnection::establish(peer, tx, Duration::from_millis(50)).await;
+
+    let request_id =
+        handle.send_request(peer, vec![1, 2, 3, 4], DialOptions::Reject).await.unwrap();
+    let mut substream = connection.accept_substream().await.unwrap();
+    assert_eq!(
+        substream.next().await.unwrap().unwrap(),
+        BytesMut::from(&[1, 2, 3, 4][..])
+    );
+
+    // response is lost and the connection is reset before the remote can send it again
+    connection.faults().drop_frames(1);
+    substream.send(Bytes::from_static(&[5, 6, 7, 8])).await.unwrap();
+    connection.reset().await;
+
+    match tokio::time::timeout(Duration::from_secs(5), handle.next()).await.unwrap() {
+        Some(RequestResponseEvent::RequestFailed {
+            peer: request_peer,
+            request_id: failed,
+            error,
+        }) => {
+            assert_eq!(request_peer, peer);
+            assert_eq!(failed, request_id);
+            assert_eq!(
+                error,
+                RequestResponseError::Rejected(RejectReason::ConnectionClosed)
+            );
+        }
+        event => panic!("invalid event: {event:?}"),
+    }
+    assert!(substream.next().await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn outbound_requests_queued_over_concurrency_limit() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (protocol, mut handle, _manager, tx) = protocol_with_config(
+        ConfigBuilder::new(ProtocolName::from("/req/1"))
+            .with_max_size(1024)
+            .with_max_concurrent_outbound_requests(2)
+            .with_max_queued_outbound_requests(1),
+    );
+    tokio::spawn(protocol.run());
+
+    let peer = PeerId::random();
+    let mut connection = MockConnection::establish(peer, tx, Duration::from_millis(50)).await;
+
+    let mut request_ids = Vec::new();
+    for request in 0..4u8 {
+        request_ids
+            .push(handle.send_request(peer, vec![request], DialOptions::Reject).await.unwrap());
+    }
+
+    // third request is queued and the fourth request doesn't fit in the queue
+    match tokio::time::timeout(Duration::from_secs(5), handle.next()).await.unwrap() {
+        Some(RequestResponseEvent::RequestFailed {
+            request_id, error, ..
+        }) => {
+            assert_eq!(request_id, request_ids[3]);
+            assert_eq!(error, RequestResponseError::Busy);
+        }
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    let mut first = connection.accept_substream().await.unwrap();
+    let mut second = connection.accept_substream().await.unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), connection.accept_substream())
+            .await
+            .is_err()
+    );
+
+    // once the first request completes, the queued request is sent
+    assert_eq!(
+        first.next().await.unwrap().unwrap(),
+        BytesMut::from(&[0][..])
+    );
+    first.send(Bytes::from_static(&[10])).await.unwrap();
+
+    match tokio::time::timeout(Duration::from_secs(5), handle.next()).await.unwrap() {
+        Some(RequestResponseEvent::ResponseReceived {
+            request_id,
+            response,
+            ..
+        }) => {
+            assert_eq!(request_id, request_ids[0]);
+            assert_eq!(response, vec![10]);
+        }
+        event => panic!("invalid event: {event:?}"),
+    }
+
+    let mut third = tokio::time::timeout(Duration::from_secs(5), connection.accept_substream())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        third.next().await.unwrap().unwrap(),
+        BytesMut::from(&[2][..])
+    );
+
+    assert_eq!(
+        second.next().await.unwrap().unwrap(),
+        BytesMut::from(&[1][..])
+    );
+    second.send(Bytes::from_static(&[11])).await.unwrap();
+    third.send(Bytes::from_static(&[12])).await.unwrap();
+
+    let mut responses = HashSet::new();
+    for _ in 0..2 {
+        match tokio::time::timeout(Duration::from_secs(5), handle.next()).await.unwrap() {
+            Some(RequestResponseEvent::ResponseReceived {
+                request_id,
+                response,
+                ..
+            }) => {
+                responses.insert((request_id, response));
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+    }
+    assert_eq!(
+        responses,
+        HashSet::from([(request_ids[1], vec![11]), (request_ids[2], vec![12])])
+    );
+}
@@ -21,7 +21,8 @@
 use crate::{
     addresses::PublicAddresses,
     error::{Error, ImmediateDialError, SubstreamError},
-    protocol::{connection::ConnectionHandle, InnerTransportEvent, TransportEvent},
+    protocol::{connection::ConnectionHandle, Direction, InnerTransportEvent, TransportEvent},
+    substream::Substream,
     transport::{manager::TransportManagerHandle, Endpoint},
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     PeerId, DEFAULT_CHANNEL_SIZE,
@@ -30,11 +31,15 @@ use crate::{
 use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
 use multiaddr::{Multiaddr, Protocol};
 use multihash::Multihash;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::sync::{
+    mpsc::{channel, Receiver, Sender},
+    oneshot,
+};
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Debug,
+    future::Future,
     pin::Pin,
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -47,6 +52,13 @@ use std::{
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::transport-service";
 
+/// Timeout for [`TransportService::open_substream_or_dial()`], covering both the dial and the
+/// substream negotiation.
+const OPEN_SUBSTREAM_OR_DIAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Channel for returning the outcome of [`TransportService::open_substream_or_dial()`].
+type PendingOpen = oneshot::Sender<Result<Substream, SubstreamError>>;
+
 /// Connection context for the peer.
 ///
 /// Each peer is allowed to have at most two connections open. The first open connection is the
@@ -54,22 +66,31 @@ const LOG_TARGET: &str = "litep2p::transport-service";
 /// may be open if local and remote opened connections at the same time.
 ///
 /// Secondary connection may be promoted to a primary connection if the primary connections closes
-/// while the secondary connections remains open.
+/// while the secondary connections remains open. If the primary connection is relayed and a direct
+/// secondary connection is opened, the direct connection becomes the primary connection.
 #[derive(Debug)]
 struct ConnectionContext {
     /// Primary connection.
     primary: ConnectionHandle,
 
+    /// Is the primary connection relayed.
+    primary_relayed: bool,
+
     /// Secondary connection, if it exists.
     secondary: Option<ConnectionHandle>,
+
+    /// Is the secondary connection relayed.
+    secondary_relayed: bool,
 }
 
 impl ConnectionContext {
     /// Create new [`ConnectionContext`].
-    fn new(primary: ConnectionHandle) -> Self {
+    fn new(primary: ConnectionHandle, primary_relayed: bool) -> Self {
         Self {
             primary,
+            primary_relayed,
             secondary: None,
+            secondary_relayed: false,
         }
     }
 
@@ -98,6 +119,24 @@ impl ConnectionContext {
     }
 }
 
+/// Group of outbound substreams opened with [`TransportService::open_substreams()`].
+#[derive(Debug)]
+struct SubstreamGroup {
+    /// Substreams of the group that haven't been opened yet.
+    pending: HashSet<SubstreamId>,
+
+    /// Substreams of the group that have been opened, held back until the whole group is open.
+    opened: Vec<TransportEvent>,
+
+    /// Has a substream of the group failed to open.
+    failed: bool,
+
+    /// Has the group been abandoned before the substream IDs were returned to the protocol.
+    ///
+    /// Substreams of an abandoned group are closed without reporting any events.
+    abandoned: bool,
+}
+
 /// Provides an interfaces for [`Litep2p`](crate::Litep2p) protocols to interact
 /// with the underlying transport protocols.
 #[derive(Debug)]
@@ -128,6 +167,30 @@ pub struct TransportService {
 
     /// Pending keep-alive timeouts.
     pending_keep_alive_timeouts: FuturesUnordered<BoxFuture<'static, (PeerId, ConnectionId)>>,
+
+    /// Events waiting to be returned to the protocol.
+    ///
+    /// These are returned to the protocol before any new events are read from `rx`.
+    pending_events: VecDeque<TransportEvent>,
+
+    /// Substreams of [`TransportService::open_substream_or_dial()`] waiting for a connection to
+    /// the peer to be established.
+    pending_dials: HashMap<PeerId, Vec<(SubstreamId, PendingOpen)>>,
+
+    /// Substreams of [`TransportService::open_substream_or_dial()`] which are being opened.
+    ///
+    /// The channel is `None` if the operation has timed out and the substream is closed once it
+    /// opens.
+    pending_opens: HashMap<SubstreamId, (PeerId, Option<PendingOpen>)>,
+
+    /// Timeouts of [`TransportService::open_substream_or_dial()`] calls.
+    pending_open_timeouts: FuturesUnordered<BoxFuture<'static, (PeerId, SubstreamId)>>,
+
+    /// Substream groups, indexed by the ID of the first substream of the group.
+    substream_groups: HashMap<SubstreamId, SubstreamGroup>,
+
+    /// Group of each pending substream which was opened as part of a group.
+    group_members: HashMap<SubstreamId, SubstreamId>,
 }
 
 impl TransportService {
@@ -153,6 +216,12 @@ impl TransportService {
                 connections: HashMap::new(),
                 keep_alive_timeout,
                 pending_keep_alive_timeouts: FuturesUnordered::new(),
+                pending_events: VecDeque::new(),
+                pending_dials: HashMap::new(),
+                pending_opens: HashMap::new(),
+                pending_open_timeouts: FuturesUnordered::new(),
+                substream_groups: HashMap::new(),
+                group_members: HashMap::new(),
             },
             tx,
         )
@@ -203,13 +272,37 @@ impl TransportService {
                         tokio::time::sleep(keep_alive_timeout).await;
                         (peer, connection_id)
                     }));
+
+                    // prefer the direct connection over a relayed connection and let the relayed
+                    // connection close once its substreams have been closed
+                    if context.primary_relayed && !endpoint.is_relayed() {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?peer,
+                            relayed = ?context.primary.connection_id(),
+                            direct = ?connection_id,
+                            "switch from relayed connection to direct connection",
+                        );
+
+                        let mut relayed = std::mem::replace(&mut context.primary, handle);
+                        relayed.close();
+
+                        context.secondary = Some(relayed);
+                        context.secondary_relayed = true;
+                        context.primary_relayed = false;
+
+                        return Some(TransportEvent::ConnectionAddressChanged { peer, endpoint });
+                    }
+
                     context.secondary = Some(handle);
+                    context.secondary_relayed = endpoint.is_relayed();
 
                     None
                 }
             },
             None => {
-                self.connections.insert(peer, ConnectionContext::new(handle));
+                self.connections
+                    .insert(peer, ConnectionContext::new(handle, endpoint.is_relayed()));
                 self.pending_keep_alive_timeouts.push(Box::pin(async move {
                     tokio::time::sleep(keep_alive_timeout).await;
                     (peer, connection_id)
@@ -257,6 +350,7 @@ impl TransportService {
                     );
 
                     context.primary = handle;
+                    context.primary_relayed = context.secondary_relayed;
                     return None;
                 }
             }
@@ -294,6 +388,15 @@ impl TransportService {
         self.transport_handle.dial(peer)
     }
 
+    /// Dial `peer` using `PeerId` as part of automatic dialing, such as peer discovery.
+    ///
+    /// Unlike [`TransportService::dial()`], the call fails with
+    /// [`ImmediateDialError::DialThrottled`] if the node has reached its target number of
+    /// connections and hasn't yet dropped below the low water mark.
+    pub fn dial_automatic(&mut self, peer: &PeerId) -> Result<(), ImmediateDialError> {
+        self.transport_handle.dial_automatic(peer)
+    }
+
     /// Dial peer using a `Multiaddr`.
     ///
     /// Call fails if the address is not in correct format or it contains an unsupported/disabled
@@ -328,6 +431,46 @@ impl TransportService {
     /// Call fails if there is no connection open to `peer` or the channel towards
     /// the connection is clogged.
     pub fn open_substream(&mut self, peer: PeerId) -> Result<SubstreamId, SubstreamError> {
+        self.open_substream_inner(peer, false)
+    }
+
+    /// Open substream to `peer` and allow its first message to be sent as 0-RTT data.
+    ///
+    /// Over a QUIC connection that is being resumed with a session ticket from an earlier
+    /// connection, the substream is opened and written to before the handshake has completed,
+    /// saving a round trip. On other connections and transports the call is equivalent to
+    /// [`TransportService::open_substream()`].
+    ///
+    /// 0-RTT data is not protected against replay: an attacker who captured it can make the remote
+    /// peer process it again. Only use this for substreams whose first message is idempotent. If
+    /// the remote peer rejects the 0-RTT data, the substream fails to open and the protocol must
+    /// open a new substream to retry the request.
+    pub fn open_substream_with_early_data(
+        &mut self,
+        peer: PeerId,
+    ) -> Result<SubstreamId, SubstreamError> {
+        self.open_substream_inner(peer, true)
+    }
+
+    fn open_substream_inner(
+        &mut self,
+        peer: PeerId,
+        early_data: bool,
+    ) -> Result<SubstreamId, SubstreamError> {
+        let substream_id =
+            SubstreamId::from(self.next_substream_id.fetch_add(1usize, Ordering::Relaxed));
+
+        self.open_substream_with_id(peer, substream_id, early_data)
+            .map(|_| substream_id)
+    }
+
+    /// Open substream to `peer` using an already allocated `substream_id`.
+    fn open_substream_with_id(
+        &mut self,
+        peer: PeerId,
+        substream_id: SubstreamId,
+        early_data: bool,
+    ) -> Result<(), SubstreamError> {
         // always prefer the primary connection
         let connection = &mut self
             .connections
@@ -336,25 +479,396 @@ impl TransportService {
             .primary;
 
         let permit = connection.try_get_permit().ok_or(SubstreamError::ConnectionClosed)?;
-        let substream_id =
-            SubstreamId::from(self.next_substream_id.fetch_add(1usize, Ordering::Relaxed));
 
         tracing::trace!(
             target: LOG_TARGET,
             ?peer,
             protocol = %self.protocol,
             ?substream_id,
+            ?early_data,
             "open substream",
         );
 
-        connection
-            .open_substream(
+        match early_data {
+            true => connection.open_early_data_substream(
                 self.protocol.clone(),
                 self.fallback_names.clone(),
                 substream_id,
                 permit,
-            )
-            .map(|_| substream_id)
+            ),
+            false => connection.open_substream(
+                self.protocol.clone(),
+                self.fallback_names.clone(),
+                substream_id,
+                permit,
+            ),
+        }
+    }
+
+    /// Open `count` substreams to `peer` as a group.
+    ///
+    /// The substreams are reported to the protocol only once all of them have been opened, as
+    /// consecutive [`TransportEvent::SubstreamOpened`] events. If any substream of the group fails
+    /// to open, its own error is reported as [`TransportEvent::SubstreamOpenFailure`] and every
+    /// other substream of the group is closed and reported as failed with
+    /// [`SubstreamError::GroupAborted`].
+    ///
+    /// If one of the substreams can't be opened immediately, the call fails and the substreams
+    /// that were already requested are closed once they open, without reporting any events.
+    pub fn open_substreams(
+        &mut self,
+        peer: PeerId,
+        count: usize,
+    ) -> Result<Vec<SubstreamId>, SubstreamError> {
+        let mut substreams = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            match self.open_substream(peer) {
+                Ok(substream_id) => substreams.push(substream_id),
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        protocol = %self.protocol,
+                        ?error,
+                        opened = substreams.len(),
+                        "failed to open substream group",
+                    );
+
+                    self.insert_substream_group(substreams, true);
+                    return Err(error);
+                }
+            }
+        }
+
+        self.insert_substream_group(substreams.clone(), false);
+        Ok(substreams)
+    }
+
+    /// Start tracking `substreams` as a group.
+    fn insert_substream_group(&mut self, substreams: Vec<SubstreamId>, abandoned: bool) {
+        let Some(group) = substreams.first().copied() else {
+            return;
+        };
+
+        self.group_members
+            .extend(substreams.iter().map(|substream| (*substream, group)));
+        self.substream_groups.insert(
+            group,
+            SubstreamGroup {
+                pending: substreams.into_iter().collect(),
+                opened: Vec::new(),
+                failed: abandoned,
+                abandoned,
+            },
+        );
+    }
+
+    /// Handle outbound substream event of a substream which may belong to a group.
+    ///
+    /// Returns the event back if the substream is not part of a group. Otherwise the event is
+    /// held back until the group resolves, at which point the events of the group are pushed to
+    /// `pending_events`.
+    fn on_substream_event(&mut self, event: TransportEvent) -> Option<TransportEvent> {
+        let substream_id = match &event {
+            TransportEvent::SubstreamOpened {
+                direction: Direction::Outbound(substream_id),
+                ..
+            } => *substream_id,
+            TransportEvent::SubstreamOpenFailure { substream, .. } => *substream,
+            _ => return Some(event),
+        };
+
+        let Some(group_id) = self.group_members.remove(&substream_id) else {
+            return Some(event);
+        };
+        let group = self.substream_groups.get_mut(&group_id).expect("group to exist");
+        group.pending.remove(&substream_id);
+
+        match event {
+            TransportEvent::SubstreamOpened { .. } if group.failed => {
+                tracing::trace!(
+                    target: LOG_TARGET,
+                    protocol = %self.protocol,
+                    ?substream_id,
+                    "substream group failed, close substream",
+                );
+
+                if !group.abandoned {
+                    self.pending_events.push_back(TransportEvent::SubstreamOpenFailure {
+                        substream: substream_id,
+                        error: SubstreamError::GroupAborted(substream_id),
+                    });
+                }
+            }
+            event @ TransportEvent::SubstreamOpened { .. } => {
+                group.opened.push(event);
+
+                if group.pending.is_empty() {
+                    self.pending_events.extend(group.opened.drain(..));
+                }
+            }
+            event => {
+                if !group.abandoned {
+                    self.pending_events.push_back(event);
+                }
+
+                if !group.failed {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        protocol = %self.protocol,
+                        ?substream_id,
+                        opened = ?group.opened.len(),
+                        "substream of a group failed to open, close the group",
+                    );
+
+                    group.failed = true;
+
+                    // dropping the substream closes it
+                    for event in group.opened.drain(..) {
+                        let TransportEvent::SubstreamOpened {
+                            direction: Direction::Outbound(substream),
+                            ..
+                        } = event
+                        else {
+                            continue;
+                        };
+
+                        self.pending_events.push_back(TransportEvent::SubstreamOpenFailure {
+                            substream,
+                            error: SubstreamError::GroupAborted(substream),
+                        });
+                    }
+                }
+            }
+        }
+
+        if group.pending.is_empty() {
+            self.substream_groups.remove(&group_id);
+        }
+
+        None
+    }
+
+    /// Open substream to `peer`, dialing the peer first if there is no connection open to them.
+    ///
+    /// `addresses` are added to the known addresses of `peer` before the peer is dialed. The
+    /// returned future resolves to the opened substream once the peer has been dialed, if needed,
+    /// and the substream has been negotiated. The substream is not reported as
+    /// [`TransportEvent::SubstreamOpened`].
+    ///
+    /// The future fails with [`SubstreamError::DialFailure`] if the dial can't be started, every
+    /// address of the peer fails to connect or the peer is banned, with
+    /// [`SubstreamError::ConnectionClosed`] if the connection closes before the substream is
+    /// opened and with [`SubstreamError::DeadlineExceeded`] if the operation doesn't conclude in
+    /// time.
+    ///
+    /// The operation makes progress only while [`TransportService`] is polled.
+    pub fn open_substream_or_dial(
+        &mut self,
+        peer: PeerId,
+        addresses: impl Iterator<Item = Multiaddr>,
+    ) -> impl Future<Output = Result<Substream, SubstreamError>> + Send + 'static {
+        let (tx, rx) = oneshot::channel();
+        let substream_id =
+            SubstreamId::from(self.next_substream_id.fetch_add(1usize, Ordering::Relaxed));
+
+        if self.start_open_substream_or_dial(peer, substream_id, addresses, tx) {
+            self.pending_open_timeouts.push(Box::pin(async move {
+                tokio::time::sleep(OPEN_SUBSTREAM_OR_DIAL_TIMEOUT).await;
+                (peer, substream_id)
+            }));
+        }
+
+        async move { rx.await.unwrap_or(Err(SubstreamError::ConnectionClosed)) }
+    }
+
+    /// Open the substream over an existing connection or start dialing `peer`.
+    ///
+    /// Returns `true` if the operation is pending.
+    fn start_open_substream_or_dial(
+        &mut self,
+        peer: PeerId,
+        substream_id: SubstreamId,
+        addresses: impl Iterator<Item = Multiaddr>,
+        tx: PendingOpen,
+    ) -> bool {
+        if self.connections.contains_key(&peer) {
+            return self.open_pending_substream(peer, substream_id, tx);
+        }
+
+        self.add_known_address(&peer, addresses);
+
+        if let Err(error) = self.dial(&peer) {
+            if error != ImmediateDialError::AlreadyConnected {
+                let _ = tx.send(Err(SubstreamError::DialFailure(Some(error))));
+                return false;
+            }
+        }
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            ?substream_id,
+            "dial started, open substream once connected",
+        );
+
+        self.pending_dials.entry(peer).or_default().push((substream_id, tx));
+        true
+    }
+
+    /// Open substream of [`TransportService::open_substream_or_dial()`] over the connection to
+    /// `peer`.
+    ///
+    /// Returns `true` if the substream is being opened.
+    fn open_pending_substream(
+        &mut self,
+        peer: PeerId,
+        substream_id: SubstreamId,
+        tx: PendingOpen,
+    ) -> bool {
+        match self.open_substream_with_id(peer, substream_id, false) {
+            Ok(()) => {
+                self.pending_opens.insert(substream_id, (peer, Some(tx)));
+                true
+            }
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?peer,
+                    protocol = %self.protocol,
+                    ?substream_id,
+                    ?error,
+                    "failed to open substream",
+                );
+
+                let _ = tx.send(Err(error));
+                false
+            }
+        }
+    }
+
+    /// Open the substreams which were waiting for a connection to `peer` to be established.
+    fn open_pending_substreams(&mut self, peer: PeerId) {
+        for (substream_id, tx) in self.pending_dials.remove(&peer).unwrap_or_default() {
+            self.open_pending_substream(peer, substream_id, tx);
+        }
+    }
+
+    /// Fail every pending [`TransportService::open_substream_or_dial()`] call to `peer`.
+    fn fail_pending_substreams(&mut self, peer: PeerId, error: impl Fn() -> SubstreamError) {
+        for (_, tx) in self.pending_dials.remove(&peer).unwrap_or_default() {
+            let _ = tx.send(Err(error()));
+        }
+
+        for (pending_peer, tx) in self.pending_opens.values_mut() {
+            if *pending_peer == peer {
+                if let Some(tx) = tx.take() {
+                    let _ = tx.send(Err(error()));
+                }
+            }
+        }
+    }
+
+    /// Handle the outcome of a substream opened with
+    /// [`TransportService::open_substream_or_dial()`].
+    ///
+    /// Returns the event back if the substream wasn't opened with
+    /// [`TransportService::open_substream_or_dial()`].
+    fn on_pending_substream_event(&mut self, event: TransportEvent) -> Option<TransportEvent> {
+        let (substream_id, result) = match event {
+            TransportEvent::SubstreamOpened {
+                direction: Direction::Outbound(substream_id),
+                substream,
+                ..
+            } if self.pending_opens.contains_key(&substream_id) => (substream_id, Ok(substream)),
+            TransportEvent::SubstreamOpenFailure { substream, error }
+                if self.pending_opens.contains_key(&substream) =>
+                (substream, Err(error)),
+            event => return Some(event),
+        };
+
+        // dropping the substream closes it if the operation has already concluded
+        if let Some((_, Some(tx))) = self.pending_opens.remove(&substream_id) {
+            let _ = tx.send(result);
+        }
+
+        None
+    }
+
+    /// Handle the timeout of a [`TransportService::open_substream_or_dial()`] call.
+    fn on_pending_substream_timeout(&mut self, peer: PeerId, substream_id: SubstreamId) {
+        let tx = match self.pending_opens.get_mut(&substream_id) {
+            Some((_, tx)) => tx.take(),
+            None => {
+                let Some(pending) = self.pending_dials.get_mut(&peer) else {
+                    return;
+                };
+                let Some(index) = pending.iter().position(|(pending, _)| pending == &substream_id)
+                else {
+                    return;
+                };
+                let (_, tx) = pending.swap_remove(index);
+
+                if pending.is_empty() {
+                    self.pending_dials.remove(&peer);
+                }
+
+                Some(tx)
+            }
+        };
+
+        if let Some(tx) = tx {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                ?substream_id,
+                "timed out while opening substream",
+            );
+
+            let _ = tx.send(Err(SubstreamError::DeadlineExceeded(Some(substream_id))));
+        }
+    }
+
+    /// Handle dial failure to `peer`.
+    ///
+    /// The substreams waiting for the connection fail only once the peer is no longer being
+    /// dialed, that is, once every address of the peer has failed. If another dial to the peer
+    /// was started in the meantime, the substreams wait for it to conclude or for the operation
+    /// to time out.
+    fn on_dial_failure(&mut self, peer: PeerId) {
+        if !self.pending_dials.contains_key(&peer) {
+            return;
+        }
+
+        if self.is_banned(&peer) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                protocol = %self.protocol,
+                "peer banned, cannot open substreams",
+            );
+
+            self.fail_pending_substreams(peer, || {
+                SubstreamError::DialFailure(Some(ImmediateDialError::PeerBanned))
+            });
+            return;
+        }
+
+        if self.transport_handle.is_dialing(&peer) {
+            return;
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            "failed to dial peer, cannot open substreams",
+        );
+
+        self.fail_pending_substreams(peer, || SubstreamError::DialFailure(None));
     }
 
     /// Forcibly close the connection, even if other protocols have substreams open over it.
@@ -377,6 +891,38 @@ impl TransportService {
         connection.primary.force_close()
     }
 
+    /// Adjust the reputation of `peer` by `change`.
+    ///
+    /// Reputation is shared by all protocols and decays towards zero over time. If the reputation
+    /// of the peer falls to or below the configured ban threshold, the transport manager closes
+    /// all connections to the peer and aborts the dials to it in progress. New connections to and
+    /// from the peer are refused until the ban expires.
+    ///
+    /// Returns `true` if the peer was banned as a result of this report.
+    pub fn report_peer(&mut self, peer: PeerId, change: i32) -> bool {
+        if !self.transport_handle.report_peer(peer, change) {
+            return false;
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            protocol = %self.protocol,
+            "peer banned, closing connection",
+        );
+
+        let _ = self.force_close(peer);
+        self.fail_pending_substreams(peer, || {
+            SubstreamError::DialFailure(Some(ImmediateDialError::PeerBanned))
+        });
+        true
+    }
+
+    /// Check if `peer` is currently banned.
+    pub fn is_banned(&self, peer: &PeerId) -> bool {
+        self.transport_handle.is_banned(peer)
+    }
+
     /// Get local peer ID.
     pub fn local_peer_id(&self) -> PeerId {
         self.local_peer_id
@@ -387,6 +933,10 @@ impl Stream for TransportService {
     type Item = TransportEvent;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+
         while let Poll::Ready(event) = self.rx.poll_recv(cx) {
             match event {
                 None => {
@@ -399,18 +949,47 @@ impl Stream for TransportService {
                     sender,
                     connection,
                 }) => {
-                    if let Some(event) =
-                        self.on_connection_established(peer, endpoint, connection, sender)
-                    {
+                    let event = self.on_connection_established(peer, endpoint, connection, sender);
+                    self.open_pending_substreams(peer);
+
+                    if let Some(event) = event {
                         return Poll::Ready(Some(event));
                     }
                 }
                 Some(InnerTransportEvent::ConnectionClosed { peer, connection }) => {
-                    if let Some(event) = self.on_connection_closed(peer, connection) {
+                    let event = self.on_connection_closed(peer, connection);
+
+                    if !self.connections.contains_key(&peer) {
+                        match self.is_banned(&peer) {
+                            true => self.fail_pending_substreams(peer, || {
+                                SubstreamError::DialFailure(Some(ImmediateDialError::PeerBanned))
+                            }),
+                            false => self
+                                .fail_pending_substreams(peer, || SubstreamError::ConnectionClosed),
+                        }
+                    }
+
+                    if let Some(event) = event {
+                        return Poll::Ready(Some(event));
+                    }
+                }
+                Some(InnerTransportEvent::DialFailure { peer, address }) => {
+                    self.on_dial_failure(peer);
+
+                    return Poll::Ready(Some(TransportEvent::DialFailure { peer, address }));
+                }
+                Some(event) => {
+                    if let Some(event) = self
+                        .on_pending_substream_event(event.into())
+                        .and_then(|event| self.on_substream_event(event))
+                    {
+                        return Poll::Ready(Some(event));
+                    }
+
+                    if let Some(event) = self.pending_events.pop_front() {
                         return Poll::Ready(Some(event));
                     }
                 }
-                Some(event) => return Poll::Ready(Some(event.into())),
             }
         }
 
@@ -429,6 +1008,12 @@ impl Stream for TransportService {
             }
         }
 
+        while let Poll::Ready(Some((peer, substream_id))) =
+            self.pending_open_timeouts.poll_next_unpin(cx)
+        {
+            self.on_pending_substream_timeout(peer, substream_id);
+        }
+
         Poll::Pending
     }
 }
@@ -437,9 +1022,15 @@ impl Stream for TransportService {
 mod tests {
     use super::*;
     use crate::{
-        protocol::TransportService,
+        mock::substream::DummySubstream,
+        protocol::{ProtocolCommand, TransportService},
         transport::{
-            manager::{handle::InnerTransportManagerCommand, TransportManagerHandle},
+            manager::{
+                handle::InnerTransportManagerCommand,
+                reputation::Reputation,
+                types::{PeerContext, PeerState},
+                SupportedTransport, TransportManagerHandle,
+            },
             KEEP_ALIVE_TIMEOUT,
         },
     };
@@ -463,6 +1054,8 @@ mod tests {
             HashSet::new(),
             Default::default(),
             PublicAddresses::new(peer),
+            Arc::new(RwLock::new(Reputation::new(Default::default()))),
+            Default::default(),
         );
 
         let (service, sender) = TransportService::new(
@@ -531,6 +1124,88 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn direct_connection_replaces_relayed_connection() {
+        let (mut service, sender, _) = transport_service();
+        let peer = PeerId::random();
+        let relayed_address = Multiaddr::empty()
+            .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Tcp(8888))
+            .with(Protocol::P2p(Multihash::from(PeerId::random())))
+            .with(Protocol::P2pCircuit);
+        let direct_address = Multiaddr::empty()
+            .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Tcp(9999));
+
+        // register relayed connection
+        let (cmd_tx1, _cmd_rx1) = channel(64);
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::listener(relayed_address.clone(), ConnectionId::from(0usize)),
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionEstablished { endpoint, .. }) => {
+                assert!(endpoint.is_relayed());
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // register direct connection which becomes the primary connection
+        let (cmd_tx2, _cmd_rx2) = channel(64);
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(1usize),
+                endpoint: Endpoint::dialer(direct_address.clone(), ConnectionId::from(1usize)),
+                sender: ConnectionHandle::new(ConnectionId::from(1usize), cmd_tx2),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionAddressChanged {
+                peer: changed_peer,
+                endpoint,
+            }) => {
+                assert_eq!(changed_peer, peer);
+                assert_eq!(endpoint.address(), &direct_address);
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        let context = service.connections.get(&peer).unwrap();
+        assert_eq!(context.primary.connection_id(), &ConnectionId::from(1usize));
+        assert_eq!(
+            context.secondary.as_ref().unwrap().connection_id(),
+            &ConnectionId::from(0usize)
+        );
+
+        // relayed connection closes and the direct connection stays open
+        sender
+            .send(InnerTransportEvent::ConnectionClosed {
+                peer,
+                connection: ConnectionId::from(0usize),
+            })
+            .await
+            .unwrap();
+
+        futures::future::poll_fn(|cx| match service.poll_next_unpin(cx) {
+            std::task::Poll::Ready(_) => panic!("didn't expect event from `TransportService`"),
+            std::task::Poll::Pending => std::task::Poll::Ready(()),
+        })
+        .await;
+
+        let context = service.connections.get(&peer).unwrap();
+        assert_eq!(context.primary.connection_id(), &ConnectionId::from(1usize));
+        assert!(context.secondary.is_none());
+    }
+
     #[tokio::test]
     async fn tertiary_connection_ignored() {
         let (mut service, sender, _) = transport_service();
@@ -893,4 +1568,402 @@ mod tests {
             Err(_) => {}
         }
     }
+
+    /// Create new `TransportService` with TCP enabled so known addresses can be dialed.
+    fn transport_service_with_tcp() -> (
+        TransportService,
+        Sender<InnerTransportEvent>,
+        Receiver<InnerTransportManagerCommand>,
+    ) {
+        transport_service_with_peers(Default::default())
+    }
+
+    /// Create new `TransportService` with TCP enabled which shares `peers` with the test.
+    fn transport_service_with_peers(
+        peers: Arc<RwLock<HashMap<PeerId, PeerContext>>>,
+    ) -> (
+        TransportService,
+        Sender<InnerTransportEvent>,
+        Receiver<InnerTransportManagerCommand>,
+    ) {
+        let (cmd_tx, cmd_rx) = channel(64);
+        let peer = PeerId::random();
+
+        let handle = TransportManagerHandle::new(
+            peer,
+            peers,
+            cmd_tx,
+            HashSet::from_iter([SupportedTransport::Tcp]),
+            Default::default(),
+            PublicAddresses::new(peer),
+            Arc::new(RwLock::new(Reputation::new(Default::default()))),
+            Default::default(),
+        );
+
+        let (service, sender) = TransportService::new(
+            peer,
+            ProtocolName::from("/notif/1"),
+            Vec::new(),
+            Arc::new(AtomicUsize::new(0usize)),
+            handle,
+            KEEP_ALIVE_TIMEOUT,
+        );
+
+        (service, sender, cmd_rx)
+    }
+
+    #[tokio::test]
+    async fn open_substream_or_dial_dials_then_opens() {
+        let (mut service, sender, mut cmd_rx) = transport_service_with_tcp();
+        let peer = PeerId::random();
+        let address = "/ip4/1.1.1.1/tcp/8888".parse::<Multiaddr>().unwrap();
+        let (cmd_tx1, mut cmd_rx1) = channel(64);
+
+        let substream =
+            tokio::spawn(service.open_substream_or_dial(peer, std::iter::once(address)));
+
+        match cmd_rx.recv().await {
+            Some(InnerTransportManagerCommand::DialPeer { peer: dialed_peer }) => {
+                assert_eq!(dialed_peer, peer);
+            }
+            _ => panic!("expected peer to be dialed"),
+        }
+
+        // emulate `TransportManager` which establishes the connection
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionEstablished {
+                peer: connected_peer,
+                ..
+            }) => assert_eq!(connected_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // substream is opened over the new connection
+        let substream_id = match cmd_rx1.recv().await {
+            Some(ProtocolCommand::OpenSubstream {
+                protocol,
+                substream_id,
+                ..
+            }) => {
+                assert_eq!(protocol, ProtocolName::from("/notif/1"));
+                substream_id
+            }
+            _ => panic!("expected substream to be opened"),
+        };
+
+        sender
+            .send(InnerTransportEvent::SubstreamOpened {
+                peer,
+                protocol: ProtocolName::from("/notif/1"),
+                fallback: None,
+                direction: Direction::Outbound(substream_id),
+                substream: crate::substream::Substream::new_mock(
+                    peer,
+                    substream_id,
+                    Box::new(DummySubstream::new()),
+                ),
+            })
+            .await
+            .unwrap();
+
+        // the substream is returned by the future instead of being reported as an event
+        assert!(tokio::time::timeout(Duration::from_millis(200), service.next()).await.is_err());
+        assert_eq!(substream.await.unwrap().unwrap().peer(), peer);
+    }
+
+    #[tokio::test]
+    async fn open_substream_or_dial_fails_after_all_addresses_fail() {
+        let peers: Arc<RwLock<HashMap<PeerId, PeerContext>>> = Default::default();
+        let (mut service, sender, _cmd_rx) = transport_service_with_peers(Arc::clone(&peers));
+        let peer = PeerId::random();
+        let address1 = "/ip4/1.1.1.1/tcp/8888".parse::<Multiaddr>().unwrap();
+        let address2 = "/ip4/2.2.2.2/tcp/8888".parse::<Multiaddr>().unwrap();
+
+        let mut substream = Box::pin(
+            service.open_substream_or_dial(peer, vec![address1.clone(), address2].into_iter()),
+        );
+
+        // emulate `TransportManager` which is still dialing the second address
+        peers.write().get_mut(&peer).unwrap().state = PeerState::Opening {
+            records: HashMap::new(),
+            connection_id: ConnectionId::from(0usize),
+            transports: HashSet::new(),
+        };
+        sender
+            .send(InnerTransportEvent::DialFailure {
+                peer,
+                address: address1,
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::DialFailure {
+                peer: dialed_peer, ..
+            }) => assert_eq!(dialed_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert!(futures::poll!(&mut substream).is_pending());
+
+        // the last address fails as well
+        peers.write().get_mut(&peer).unwrap().state = PeerState::Disconnected { dial_record: None };
+        sender
+            .send(InnerTransportEvent::DialFailure {
+                peer,
+                address: Multiaddr::empty(),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::DialFailure {
+                peer: dialed_peer, ..
+            }) => assert_eq!(dialed_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert!(std::matches!(
+            substream.await,
+            Err(SubstreamError::DialFailure(None))
+        ));
+    }
+
+    #[tokio::test]
+    async fn open_substream_or_dial_no_address() {
+        let (mut service, _sender, _cmd_rx) = transport_service();
+
+        assert!(std::matches!(
+            service.open_substream_or_dial(PeerId::random(), std::iter::empty()).await,
+            Err(SubstreamError::DialFailure(Some(
+                ImmediateDialError::NoAddressAvailable
+            ))),
+        ));
+        assert!(service.pending_dials.is_empty());
+    }
+
+    #[tokio::test]
+    async fn open_substream_or_dial_fails_when_connection_closes() {
+        let (mut service, sender, _cmd_rx) = transport_service_with_tcp();
+        let peer = PeerId::random();
+        let (cmd_tx1, _cmd_rx1) = channel(64);
+
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
+            })
+            .await
+            .unwrap();
+        let _ = service.next().await;
+
+        let substream = service.open_substream_or_dial(peer, std::iter::empty());
+
+        sender
+            .send(InnerTransportEvent::ConnectionClosed {
+                peer,
+                connection: ConnectionId::from(0usize),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionClosed {
+                peer: disconnected_peer,
+            }) => assert_eq!(disconnected_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert!(std::matches!(
+            substream.await,
+            Err(SubstreamError::ConnectionClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn open_substream_or_dial_fails_on_ban_and_timeout() {
+        let (mut service, _sender, _cmd_rx) = transport_service_with_tcp();
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+        let address = "/ip4/1.1.1.1/tcp/8888".parse::<Multiaddr>().unwrap();
+
+        let substream1 = service.open_substream_or_dial(peer1, std::iter::once(address.clone()));
+        let substream2 = service.open_substream_or_dial(peer2, std::iter::once(address));
+
+        // peer is banned while it's being dialed
+        assert!(service.report_peer(peer1, i32::MIN));
+        assert!(std::matches!(
+            substream1.await,
+            Err(SubstreamError::DialFailure(Some(
+                ImmediateDialError::PeerBanned
+            )))
+        ));
+
+        // dial never concludes
+        service.on_pending_substream_timeout(peer2, SubstreamId::from(1usize));
+        assert!(std::matches!(
+            substream2.await,
+            Err(SubstreamError::DeadlineExceeded(Some(substream)))
+                if substream == SubstreamId::from(1usize)
+        ));
+        assert!(service.pending_dials.is_empty());
+    }
+
+    #[tokio::test]
+    async fn banned_peer_is_disconnected_and_not_dialed() {
+        let (mut service, sender, mut cmd_rx) = transport_service_with_tcp();
+        let peer = PeerId::random();
+        let (cmd_tx1, mut cmd_rx1) = channel(64);
+
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionEstablished {
+                peer: connected_peer,
+                ..
+            }) => assert_eq!(connected_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // reputation stays above the default ban threshold
+        assert!(!service.report_peer(peer, -600));
+        assert!(!service.is_banned(&peer));
+        assert!(cmd_rx1.try_recv().is_err());
+
+        // peer crosses the ban threshold and is disconnected
+        assert!(service.report_peer(peer, -600));
+        assert!(service.is_banned(&peer));
+        assert!(std::matches!(
+            cmd_rx1.try_recv(),
+            Ok(ProtocolCommand::ForceClose)
+        ));
+
+        // connections of other protocols are closed by the transport manager
+        assert!(std::matches!(
+            cmd_rx.try_recv(),
+            Ok(InnerTransportManagerCommand::BanPeer { peer: banned }) if banned == peer
+        ));
+
+        // banned peer cannot be dialed
+        let address = "/ip4/1.1.1.1/tcp/8888".parse::<Multiaddr>().unwrap();
+        service.add_known_address(&peer, std::iter::once(address));
+        sender
+            .send(InnerTransportEvent::ConnectionClosed {
+                peer,
+                connection: ConnectionId::from(0usize),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionClosed {
+                peer: disconnected_peer,
+            }) => assert_eq!(disconnected_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert_eq!(service.dial(&peer), Err(ImmediateDialError::PeerBanned));
+    }
+
+    #[tokio::test]
+    async fn substream_group_closed_when_substream_fails() {
+        let (mut service, sender, _cmd_rx) = transport_service_with_tcp();
+        let peer = PeerId::random();
+        let (cmd_tx1, mut cmd_rx1) = channel(64);
+
+        sender
+            .send(InnerTransportEvent::ConnectionEstablished {
+                peer,
+                connection: ConnectionId::from(0usize),
+                endpoint: Endpoint::dialer(Multiaddr::empty(), ConnectionId::from(0usize)),
+                sender: ConnectionHandle::new(ConnectionId::from(0usize), cmd_tx1),
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::ConnectionEstablished { .. }) => {}
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        let substreams = service.open_substreams(peer, 2).unwrap();
+        assert_eq!(substreams.len(), 2);
+
+        for expected in &substreams {
+            match cmd_rx1.recv().await {
+                Some(ProtocolCommand::OpenSubstream { substream_id, .. }) =>
+                    assert_eq!(substream_id, *expected),
+                _ => panic!("expected substream to be opened"),
+            }
+        }
+
+        // first substream opens but is held back until the whole group is open
+        let (local, mut remote) = crate::mock::substream::paired();
+        sender
+            .send(InnerTransportEvent::SubstreamOpened {
+                peer,
+                protocol: ProtocolName::from("/notif/1"),
+                fallback: None,
+                direction: Direction::Outbound(substreams[0]),
+                substream: crate::substream::Substream::new_mock(
+                    peer,
+                    substreams[0],
+                    Box::new(local),
+                ),
+            })
+            .await
+            .unwrap();
+
+        futures::future::poll_fn(|cx| match service.poll_next_unpin(cx) {
+            Poll::Pending => Poll::Ready(()),
+            Poll::Ready(event) => panic!("invalid event: {event:?}"),
+        })
+        .await;
+
+        // second substream fails which fails the whole group
+        sender
+            .send(InnerTransportEvent::SubstreamOpenFailure {
+                substream: substreams[1],
+                error: SubstreamError::ConnectionClosed,
+            })
+            .await
+            .unwrap();
+
+        match service.next().await {
+            Some(TransportEvent::SubstreamOpenFailure { substream, error }) => {
+                assert_eq!(substream, substreams[1]);
+                assert_eq!(error, SubstreamError::ConnectionClosed);
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        match service.next().await {
+            Some(TransportEvent::SubstreamOpenFailure { substream, error }) => {
+                assert_eq!(substream, substreams[0]);
+                assert_eq!(error, SubstreamError::GroupAborted(substreams[0]));
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // the substream that was opened has been closed
+        assert!(remote.next().await.is_none());
+        assert!(service.substream_groups.is_empty());
+        assert!(service.group_members.is_empty());
+    }
 }
@@ -0,0 +1,177 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Global memory budget for data buffered by substreams.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::substream::budget";
+
+#[derive(Debug)]
+struct Inner {
+    /// Maximum number of bytes substreams may buffer in total.
+    limit: usize,
+
+    /// Number of bytes currently buffered.
+    used: AtomicUsize,
+}
+
+/// Memory budget shared by the send and receive buffers of all substreams of the node.
+///
+/// Substreams reserve memory from the budget before buffering outbound frames sent with the
+/// [`Sink`](futures::Sink) implementation and before allocating the buffer of an inbound frame,
+/// and release it once the frame has been written to the transport or returned to the protocol.
+///
+/// Data held by the transport while a substream is corked is not counted, see
+/// [`Substream::cork()`](crate::substream::Substream::cork).
+#[derive(Debug, Clone)]
+pub struct BufferBudget(Arc<Inner>);
+
+impl BufferBudget {
+    /// Create new [`BufferBudget`] which allows at most `limit` bytes to be buffered.
+    pub(crate) fn new(limit: usize) -> Self {
+        Self(Arc::new(Inner {
+            limit,
+            used: AtomicUsize::new(0usize),
+        }))
+    }
+
+    /// Get the maximum number of bytes substreams may buffer in total.
+    pub fn limit(&self) -> usize {
+        self.0.limit
+    }
+
+    /// Get the number of bytes currently buffered by substreams.
+    pub fn used(&self) -> usize {
+        self.0.used.load(Ordering::Relaxed)
+    }
+
+    /// Check if the budget has been used up.
+    pub fn is_exhausted(&self) -> bool {
+        self.used() >= self.0.limit
+    }
+
+    /// Attempt to reserve `bytes` from the budget.
+    fn try_reserve(&self, bytes: usize) -> bool {
+        self.0
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes).filter(|used| *used <= self.0.limit)
+            })
+            .is_ok()
+    }
+
+    /// Return `bytes` to the budget.
+    fn release(&self, bytes: usize) {
+        let _ = self.0.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Memory reserved by a single substream from a [`BufferBudget`].
+///
+/// The reserved memory is returned to the budget when the reservation is dropped.
+#[derive(Debug)]
+pub(crate) struct BufferReservation {
+    /// Budget the memory is reserved from.
+    budget: BufferBudget,
+
+    /// Number of bytes reserved.
+    reserved: usize,
+}
+
+impl BufferReservation {
+    /// Create new, empty [`BufferReservation`].
+    pub(crate) fn new(budget: BufferBudget) -> Self {
+        Self {
+            budget,
+            reserved: 0usize,
+        }
+    }
+
+    /// Reserve `bytes` more from the budget.
+    ///
+    /// Returns `false` if the budget doesn't have enough memory left, in which case nothing is
+    /// reserved.
+    pub(crate) fn grow(&mut self, bytes: usize) -> bool {
+        if !self.budget.try_reserve(bytes) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?bytes,
+                used = ?self.budget.used(),
+                limit = ?self.budget.limit(),
+                "buffer budget exhausted",
+            );
+            return false;
+        }
+
+        self.reserved += bytes;
+        true
+    }
+
+    /// Return `bytes` of the reservation to the budget.
+    pub(crate) fn shrink(&mut self, bytes: usize) {
+        let bytes = std::cmp::min(bytes, self.reserved);
+
+        self.budget.release(bytes);
+        self.reserved -= bytes;
+    }
+
+    /// Check if the budget the memory is reserved from has been used up.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.budget.is_exhausted()
+    }
+}
+
+impl Drop for BufferReservation {
+    fn drop(&mut self) {
+        self.budget.release(self.reserved);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reservations_share_budget() {
+        let budget = BufferBudget::new(100);
+        let mut first = BufferReservation::new(budget.clone());
+        let mut second = BufferReservation::new(budget.clone());
+
+        assert!(first.grow(60));
+        assert!(!second.grow(50));
+        assert!(second.grow(40));
+        assert!(budget.is_exhausted());
+
+        first.shrink(30);
+        assert_eq!(budget.used(), 70);
+
+        drop(second);
+        assert_eq!(budget.used(), 30);
+
+        // shrinking more than was reserved only releases the reservation
+        first.shrink(1000);
+        assert_eq!(budget.used(), 0);
+    }
+}
@@ -22,7 +22,11 @@
 //! Substream-related helper code.
 
 use crate::{
-    codec::ProtocolCodec, error::SubstreamError, transport::tcp, types::SubstreamId, PeerId,
+    codec::ProtocolCodec,
+    error::SubstreamError,
+    transport::tcp,
+    types::{protocol::ProtocolName, SubstreamId},
+    PeerId,
 };
 
 #[cfg(feature = "quic")]
@@ -33,8 +37,11 @@ use crate::transport::webrtc;
 use crate::transport::websocket;
 
 use bytes::{Buf, Bytes, BytesMut};
-use futures::{Sink, Stream};
-use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+use futures::{FutureExt, Sink, Stream};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf},
+    time::Sleep,
+};
 use unsigned_varint::{decode, encode};
 
 use std::{
@@ -44,8 +51,24 @@ use std::{
     io::ErrorKind,
     pin::Pin,
     task::{Context, Poll},
+    time::Instant,
 };
 
+pub use budget::BufferBudget;
+pub(crate) use budget::BufferReservation;
+pub use rate_limit::SubstreamRateLimit;
+pub(crate) use rate_limit::SubstreamRateLimiter;
+pub(crate) use registry::SubstreamRegistry;
+pub use registry::{SubstreamInfo, SubstreamRegistryConfig};
+#[cfg(feature = "traffic-tap")]
+pub use tap::{TapDirection, TappedFrame, TrafficTap};
+
+mod budget;
+mod rate_limit;
+mod registry;
+#[cfg(feature = "traffic-tap")]
+mod tap;
+
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::substream";
 
@@ -59,8 +82,10 @@ macro_rules! poll_flush {
             SubstreamType::Quic(substream) => Pin::new(substream).poll_flush($cx),
             #[cfg(feature = "webrtc")]
             SubstreamType::WebRtc(substream) => Pin::new(substream).poll_flush($cx),
-            #[cfg(test)]
-            SubstreamType::Mock(_) => unreachable!(),
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Duplex(substream) => Pin::new(substream).poll_flush($cx),
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Mock(substream) => Pin::new(substream).poll_flush($cx),
         }
     }};
 }
@@ -75,25 +100,38 @@ macro_rules! poll_write {
             SubstreamType::Quic(substream) => Pin::new(substream).poll_write($cx, $frame),
             #[cfg(feature = "webrtc")]
             SubstreamType::WebRtc(substream) => Pin::new(substream).poll_write($cx, $frame),
-            #[cfg(test)]
-            SubstreamType::Mock(_) => unreachable!(),
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Duplex(substream) => Pin::new(substream).poll_write($cx, $frame),
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Mock(substream) => Pin::new(substream).poll_write($cx, $frame),
         }
     }};
 }
 
 macro_rules! poll_read {
-    ($substream:expr, $cx:ident, $buffer:expr) => {{
-        match $substream {
-            SubstreamType::Tcp(substream) => Pin::new(substream).poll_read($cx, $buffer),
+    ($substream:expr, $cx:ident, $buffer:expr, $nread:expr) => {{
+        let buffer: &mut ReadBuf<'_> = $buffer;
+        let filled = buffer.filled().len();
+
+        let result = match $substream {
+            SubstreamType::Tcp(substream) => Pin::new(substream).poll_read($cx, &mut *buffer),
             #[cfg(feature = "websocket")]
-            SubstreamType::WebSocket(substream) => Pin::new(substream).poll_read($cx, $buffer),
+            SubstreamType::WebSocket(substream) => Pin::new(substream).poll_read($cx, &mut *buffer),
             #[cfg(feature = "quic")]
-            SubstreamType::Quic(substream) => Pin::new(substream).poll_read($cx, $buffer),
+            SubstreamType::Quic(substream) => Pin::new(substream).poll_read($cx, &mut *buffer),
             #[cfg(feature = "webrtc")]
-            SubstreamType::WebRtc(substream) => Pin::new(substream).poll_read($cx, $buffer),
-            #[cfg(test)]
-            SubstreamType::Mock(_) => unreachable!(),
+            SubstreamType::WebRtc(substream) => Pin::new(substream).poll_read($cx, &mut *buffer),
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Duplex(substream) => Pin::new(substream).poll_read($cx, &mut *buffer),
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Mock(substream) => Pin::new(substream).poll_read($cx, &mut *buffer),
+        };
+
+        if let Poll::Ready(Ok(())) = result {
+            $nread += (buffer.filled().len() - filled) as u64;
         }
+
+        result
     }};
 }
 
@@ -107,27 +145,32 @@ macro_rules! poll_shutdown {
             SubstreamType::Quic(substream) => Pin::new(substream).poll_shutdown($cx),
             #[cfg(feature = "webrtc")]
             SubstreamType::WebRtc(substream) => Pin::new(substream).poll_shutdown($cx),
-            #[cfg(test)]
-            SubstreamType::Mock(substream) => {
-                let _ = Pin::new(substream).poll_close($cx);
-                todo!();
-            }
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Duplex(substream) => Pin::new(substream).poll_shutdown($cx),
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Mock(substream) => Pin::new(substream).poll_shutdown($cx),
         }
     }};
 }
 
 macro_rules! delegate_poll_next {
-    ($substream:expr, $cx:ident) => {{
-        #[cfg(test)]
+    ($substream:expr, $cx:ident, $nread:expr) => {{
+        #[cfg(any(test, feature = "test-utils"))]
         if let SubstreamType::Mock(inner) = $substream {
-            return Pin::new(inner).poll_next($cx);
+            let result = Pin::new(inner).poll_next($cx);
+
+            if let Poll::Ready(Some(Ok(frame))) = &result {
+                $nread += frame.len() as u64;
+            }
+
+            return result;
         }
     }};
 }
 
 macro_rules! delegate_poll_ready {
     ($substream:expr, $cx:ident) => {{
-        #[cfg(test)]
+        #[cfg(any(test, feature = "test-utils"))]
         if let SubstreamType::Mock(inner) = $substream {
             return Pin::new(inner).poll_ready($cx);
         }
@@ -135,23 +178,36 @@ macro_rules! delegate_poll_ready {
 }
 
 macro_rules! delegate_start_send {
-    ($substream:expr, $item:ident) => {{
-        #[cfg(test)]
+    ($substream:expr, $item:ident, $nwritten:expr) => {{
+        #[cfg(any(test, feature = "test-utils"))]
         if let SubstreamType::Mock(inner) = $substream {
-            return Pin::new(inner).start_send($item);
+            let len = $item.len();
+            Pin::new(inner).start_send($item)?;
+            $nwritten += len as u64;
+
+            return Ok(());
         }
     }};
 }
 
 macro_rules! delegate_poll_flush {
     ($substream:expr, $cx:ident) => {{
-        #[cfg(test)]
+        #[cfg(any(test, feature = "test-utils"))]
         if let SubstreamType::Mock(inner) = $substream {
             return Pin::new(inner).poll_flush($cx);
         }
     }};
 }
 
+macro_rules! delegate_poll_close {
+    ($substream:expr, $cx:ident) => {{
+        #[cfg(any(test, feature = "test-utils"))]
+        if let SubstreamType::Mock(inner) = $substream {
+            return Pin::new(inner).poll_close($cx);
+        }
+    }};
+}
+
 macro_rules! check_size {
     ($max_size:expr, $size:expr) => {{
         if let Some(max_size) = $max_size {
@@ -171,8 +227,10 @@ enum SubstreamType {
     Quic(quic::Substream),
     #[cfg(feature = "webrtc")]
     WebRtc(webrtc::Substream),
-    #[cfg(test)]
-    Mock(Box<dyn crate::mock::substream::Substream>),
+    #[cfg(any(test, feature = "test-utils"))]
+    Duplex(tokio::io::DuplexStream),
+    #[cfg(any(test, feature = "test-utils"))]
+    Mock(crate::mock::substream::MockIo),
 }
 
 impl fmt::Debug for SubstreamType {
@@ -185,7 +243,9 @@ impl fmt::Debug for SubstreamType {
             Self::Quic(_) => write!(f, "Quic"),
             #[cfg(feature = "webrtc")]
             Self::WebRtc(_) => write!(f, "WebRtc"),
-            #[cfg(test)]
+            #[cfg(any(test, feature = "test-utils"))]
+            Self::Duplex(_) => write!(f, "Duplex"),
+            #[cfg(any(test, feature = "test-utils"))]
             Self::Mock(_) => write!(f, "Mock"),
         }
     }
@@ -225,6 +285,31 @@ pub struct Substream {
     current_frame_size: Option<usize>,
 
     size_vec: BytesMut,
+
+    /// Maximum size of a frame read from the substream, in addition to the limit of the codec.
+    read_limit: Option<usize>,
+
+    /// Is the substream corked.
+    corked: bool,
+
+    /// Registration in the substream registry, if enabled.
+    registration: Option<registry::Registration>,
+
+    /// Memory reserved from the global buffer budget, if enabled.
+    reservation: Option<BufferReservation>,
+
+    /// Total number of bytes read from the substream.
+    bytes_read: u64,
+
+    /// Total number of bytes written to the substream.
+    bytes_written: u64,
+
+    /// Absolute deadline of the substream, if set.
+    deadline: Option<Pin<Box<Sleep>>>,
+
+    /// Traffic tap of the substream's protocol, if enabled.
+    #[cfg(feature = "traffic-tap")]
+    tap: Option<tap::SubstreamTap>,
 }
 
 impl fmt::Debug for Substream {
@@ -259,9 +344,134 @@ impl Substream {
             pending_out_frames: VecDeque::new(),
             pending_out_frame: None,
             size_vec: BytesMut::zeroed(10),
+            read_limit: None,
+            corked: false,
+            registration: None,
+            reservation: None,
+            bytes_read: 0u64,
+            bytes_written: 0u64,
+            deadline: None,
+            #[cfg(feature = "traffic-tap")]
+            tap: None,
         }
     }
 
+    /// Track the substream in `registry` as owned by `protocol`.
+    ///
+    /// The substream is removed from the registry when it's dropped.
+    pub(crate) fn register(&mut self, registry: &SubstreamRegistry, protocol: ProtocolName) {
+        self.registration = registry.register(self.peer, protocol, self.substream_id);
+    }
+
+    /// Account the data buffered by the substream against `budget`.
+    pub(crate) fn set_buffer_budget(&mut self, budget: BufferBudget) {
+        self.reservation = Some(BufferReservation::new(budget));
+    }
+
+    /// Reserve memory for `bytes` of buffered data from the buffer budget, if enabled.
+    fn reserve_buffer(&mut self, bytes: usize) -> Result<(), SubstreamError> {
+        match &mut self.reservation {
+            Some(reservation) if !reservation.grow(bytes) => Err(
+                SubstreamError::BufferBudgetExhausted(Some(self.substream_id)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Return memory of `bytes` of buffered data to the buffer budget, if enabled.
+    fn release_buffer(&mut self, bytes: usize) {
+        if let Some(reservation) = &mut self.reservation {
+            reservation.shrink(bytes);
+        }
+    }
+
+    /// Copy the frames of the substream to `tap`.
+    #[cfg(feature = "traffic-tap")]
+    pub(crate) fn set_tap(&mut self, tap: TrafficTap, protocol: ProtocolName) {
+        self.tap = Some(tap::SubstreamTap::new(
+            tap,
+            self.peer,
+            protocol,
+            self.substream_id,
+        ));
+    }
+
+    /// Record activity on the substream in the substream registry.
+    fn on_activity(&self) {
+        if let Some(registration) = &self.registration {
+            registration.on_activity();
+        }
+    }
+
+    /// Check if the substream has been closed by the substream registry watchdog.
+    fn poll_closed(&self, cx: &mut Context<'_>) -> bool {
+        self.registration
+            .as_ref()
+            .is_some_and(|registration| registration.poll_closed(cx))
+    }
+
+    /// Check if the substream has been closed by the substream registry watchdog.
+    fn is_closed(&self) -> bool {
+        self.registration.as_ref().is_some_and(|registration| registration.is_closed())
+    }
+
+    /// Check if the deadline of the substream has been reached.
+    ///
+    /// Registers the waker of `cx` to be woken up when the deadline is reached.
+    fn poll_deadline(&mut self, cx: &mut Context<'_>) -> bool {
+        self.deadline
+            .as_mut()
+            .is_some_and(|deadline| deadline.poll_unpin(cx).is_ready())
+    }
+
+    /// Check if the deadline of the substream has been reached.
+    fn is_past_deadline(&self) -> bool {
+        self.deadline
+            .as_ref()
+            .is_some_and(|deadline| deadline.deadline() <= tokio::time::Instant::now())
+    }
+
+    /// Set an absolute deadline for the substream.
+    ///
+    /// Once `deadline` is reached, reads and writes fail with
+    /// [`SubstreamError::DeadlineExceeded`], regardless of whether the substream is still active.
+    /// Unlike an idle timeout, the deadline bounds the entire exchange, making it possible to
+    /// limit how long a request-response exchange may take from open to close.
+    ///
+    /// Setting a new deadline replaces the previous one.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(Box::pin(tokio::time::sleep_until(deadline.into())));
+    }
+
+    /// Remove the deadline of the substream.
+    pub fn clear_deadline(&mut self) {
+        self.deadline = None;
+    }
+
+    /// Limit the size of frames read from the substream.
+    ///
+    /// The limit is checked against the length prefix so an oversized frame is rejected with
+    /// [`SubstreamError::FrameTooLarge`] before its payload is read. Only has an effect if the
+    /// substream uses [`ProtocolCodec::UnsignedVarint`] or [`ProtocolCodec::NewlineDelimited`].
+    /// Setting the limit to `None` removes it, leaving only the limit of the codec in place.
+    pub(crate) fn set_read_limit(&mut self, limit: Option<usize>) {
+        self.read_limit = limit;
+    }
+
+    /// Replace the codec of the substream, returning the previous codec.
+    ///
+    /// Must only be called when no partially read frame is buffered by the substream.
+    pub(crate) fn replace_codec(&mut self, codec: ProtocolCodec) -> ProtocolCodec {
+        self.read_buffer = match codec {
+            ProtocolCodec::Identity(payload_size) => BytesMut::zeroed(payload_size),
+            _ => BytesMut::zeroed(1024),
+        };
+        self.offset = 0usize;
+        self.current_frame_size = None;
+
+        std::mem::replace(&mut self.codec, codec)
+    }
+
     /// Create new [`Substream`] for TCP.
     pub(crate) fn new_tcp(
         peer: PeerId,
@@ -319,8 +529,13 @@ impl Substream {
     }
 
     /// Create new [`Substream`] for mocking.
-    #[cfg(test)]
-    pub(crate) fn new_mock(
+    ///
+    /// See [`crate::mock::substream`] for in-memory substreams which can be used with this
+    /// function. Frames are passed to the mock as-is, without codec framing. When the substream
+    /// is used through [`AsyncRead`](tokio::io::AsyncRead)/[`AsyncWrite`], each write is sent
+    /// as one frame and received frames are read as a byte stream.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn new_mock(
         peer: PeerId,
         substream_id: SubstreamId,
         substream: Box<dyn crate::mock::substream::Substream>,
@@ -330,11 +545,224 @@ impl Substream {
         Self::new(
             peer,
             substream_id,
-            SubstreamType::Mock(substream),
+            SubstreamType::Mock(crate::mock::substream::MockIo::new(substream)),
             ProtocolCodec::Unspecified,
         )
     }
 
+    /// Create new in-memory [`Substream`] which uses `codec` to frame messages.
+    ///
+    /// Unlike [`Substream::new_mock()`], reads and writes go through the framing of `codec`,
+    /// so two substreams created from the halves of [`tokio::io::duplex()`] can exchange
+    /// messages with each other.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn new_duplex(
+        peer: PeerId,
+        substream_id: SubstreamId,
+        substream: tokio::io::DuplexStream,
+        codec: ProtocolCodec,
+    ) -> Self {
+        tracing::trace!(target: LOG_TARGET, ?peer, ?codec, "create new in-memory substream");
+
+        Self::new(peer, substream_id, SubstreamType::Duplex(substream), codec)
+    }
+
+    /// Cork the substream.
+    ///
+    /// While the substream is corked, the transport buffers written data instead of sending it
+    /// to the remote peer on each write. This allows the protocol to coalesce several small frames
+    /// into a single transport write. The buffered data is sent when the substream is uncorked or
+    /// explicitly flushed with [`Substream::flush()`], or by the transport once the buffer is full.
+    ///
+    /// Data held by the transport is not counted against the [`BufferBudget`]. The transport
+    /// buffer is bounded per substream, by the yamux split send size for TCP and WebSocket, by
+    /// 16 KiB for QUIC and by the maximum message size for WebRTC. Frames waiting in the [`Sink`]
+    /// implementation are released from the budget once they've been handed over to the
+    /// transport.
+    ///
+    /// Corking has no effect on in-memory substreams.
+    pub fn cork(&mut self) {
+        self.corked = true;
+        self.set_transport_corked(true);
+    }
+
+    /// Uncork the substream and flush all buffered frames.
+    pub async fn uncork(&mut self) -> Result<(), SubstreamError> {
+        self.corked = false;
+        self.set_transport_corked(false);
+        self.flush().await
+    }
+
+    /// Cork or uncork the transport substream.
+    fn set_transport_corked(&mut self, corked: bool) {
+        match &mut self.substream {
+            SubstreamType::Tcp(substream) => substream.set_corked(corked),
+            #[cfg(feature = "websocket")]
+            SubstreamType::WebSocket(substream) => substream.set_corked(corked),
+            #[cfg(feature = "quic")]
+            SubstreamType::Quic(substream) => substream.set_corked(corked),
+            #[cfg(feature = "webrtc")]
+            SubstreamType::WebRtc(substream) => substream.set_corked(corked),
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Duplex(_) | SubstreamType::Mock(_) => {}
+        }
+    }
+
+    /// Check if the substream is corked.
+    pub fn is_corked(&self) -> bool {
+        self.corked
+    }
+
+    /// Get the remote peer of the substream.
+    pub fn peer(&self) -> PeerId {
+        self.peer
+    }
+
+    /// Get the total number of bytes read from the substream.
+    ///
+    /// The count includes the framing of the protocol codec, such as the length prefixes of
+    /// [`ProtocolCodec::UnsignedVarint`], but not the overhead of the underlying transport.
+    /// Data that has been read from the socket but not yet returned as a frame is included.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Get the total number of bytes written to the substream.
+    ///
+    /// Like [`Substream::bytes_read()`], the count includes the framing of the protocol codec.
+    /// Frames buffered by the [`Sink`] implementation are counted once they've been written
+    /// to the underlying socket.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Write all buffered frames to the underlying socket and flush it.
+    ///
+    /// The frames are flushed even if the substream is corked.
+    pub async fn flush(&mut self) -> Result<(), SubstreamError> {
+        // the transport must be uncorked for the flush to send the data it's holding
+        self.set_transport_corked(false);
+
+        let result = futures::future::poll_fn(|cx| {
+            // `MockSubstream` implements `Sink` so calls to `poll_flush()` must be delegated
+            delegate_poll_flush!(&mut self.substream, cx);
+
+            self.poll_write_pending(cx)
+        })
+        .await;
+
+        self.set_transport_corked(self.corked);
+        result
+    }
+
+    /// Write pending frames to the underlying socket and flush it.
+    ///
+    /// If the substream is corked, the transport holds the written frames until the substream
+    /// is uncorked.
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SubstreamError>> {
+        loop {
+            let mut pending_frame = match self.pending_out_frame.take() {
+                Some(frame) => frame,
+                None => match self.pending_out_frames.pop_front() {
+                    Some(frame) => frame,
+                    None => break,
+                },
+            };
+
+            match poll_write!(&mut self.substream, cx, &pending_frame) {
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error.into())),
+                Poll::Pending => {
+                    self.pending_out_frame = Some(pending_frame);
+                    return Poll::Pending;
+                }
+                Poll::Ready(Ok(nwritten)) => {
+                    pending_frame.advance(nwritten);
+                    self.bytes_written += nwritten as u64;
+                    self.pending_out_bytes = self.pending_out_bytes.saturating_sub(nwritten);
+                    self.release_buffer(nwritten);
+
+                    if !pending_frame.is_empty() {
+                        self.pending_out_frame = Some(pending_frame);
+                    }
+                }
+            }
+        }
+
+        poll_flush!(&mut self.substream, cx).map_err(From::from)
+    }
+
+    /// Close the write side of the substream.
+    ///
+    /// Buffered frames are written to the substream after which the remote peer is signaled that
+    /// no more data will be sent. Unlike [`Substream::close()`], the read side of the substream
+    /// stays open and any data the remote peer sends afterwards can still be read normally.
+    ///
+    /// Half-close maps to a yamux `FIN` for TCP and WebSocket, to stream finish for QUIC and to
+    /// the `FIN` flag for WebRTC.
+    pub async fn close_write(&mut self) -> Result<(), SubstreamError> {
+        futures::future::poll_fn(|cx| {
+            // `MockSubstream` implements `Sink` so calls to `poll_close()` must be delegated
+            delegate_poll_close!(&mut self.substream, cx);
+
+            futures::ready!(self.poll_write_pending(cx))?;
+
+            // WebRTC substreams send `FIN` instead of closing the data channel
+            #[cfg(feature = "webrtc")]
+            if let SubstreamType::WebRtc(substream) = &mut self.substream {
+                return substream.poll_close_write(cx).map_err(From::from);
+            }
+
+            poll_shutdown!(&mut self.substream, cx).map_err(From::from)
+        })
+        .await
+    }
+
+    /// Send `final_message` and close the write side of the substream.
+    ///
+    /// The message is framed using the codec of the substream and written after any buffered
+    /// frames, after which the substream is flushed and its write side closed as with
+    /// [`Substream::close_write()`], letting the remote peer read the message followed by EOF.
+    /// For WebRTC, the message is sent in the same message as the `FIN` flag.
+    ///
+    /// # Cancel safety
+    ///
+    /// If the future is dropped before it completes, the final message may have been partially
+    /// written and the substream should be closed with [`Substream::close()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no codec is provided.
+    pub async fn close_with(&mut self, final_message: Bytes) -> Result<(), SubstreamError> {
+        #[cfg(feature = "webrtc")]
+        if std::matches!(self.substream, SubstreamType::WebRtc(_)) {
+            // write buffered frames first so only the final frame is left in the buffer
+            self.flush().await?;
+            futures::SinkExt::feed(self, final_message).await?;
+
+            let mut frame = BytesMut::with_capacity(self.pending_out_bytes);
+            for pending in self.pending_out_frames.drain(..) {
+                frame.extend_from_slice(&pending);
+            }
+            let mut frame = frame.freeze();
+            let frame_len = frame.len();
+
+            futures::future::poll_fn(|cx| match &mut self.substream {
+                SubstreamType::WebRtc(substream) => substream.poll_close_write_with(cx, &mut frame),
+                _ => unreachable!(),
+            })
+            .await?;
+
+            self.pending_out_bytes = 0usize;
+            self.bytes_written += frame_len as u64;
+            self.release_buffer(frame_len);
+
+            return Ok(());
+        }
+
+        futures::SinkExt::feed(self, final_message).await?;
+        self.close_write().await
+    }
+
     /// Close the substream.
     pub async fn close(self) {
         let _ = match self.substream {
@@ -345,7 +773,9 @@ impl Substream {
             SubstreamType::Quic(mut substream) => substream.shutdown().await,
             #[cfg(feature = "webrtc")]
             SubstreamType::WebRtc(mut substream) => substream.shutdown().await,
-            #[cfg(test)]
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Duplex(mut substream) => substream.shutdown().await,
+            #[cfg(any(test, feature = "test-utils"))]
             SubstreamType::Mock(mut substream) => {
                 let _ = futures::SinkExt::close(&mut substream).await;
                 Ok(())
@@ -393,6 +823,54 @@ impl Substream {
         io.flush().await.map_err(From::from)
     }
 
+    /// Send newline-delimited payload to remote peer.
+    async fn send_newline_delimited_payload<T: AsyncWrite + Unpin>(
+        io: &mut T,
+        bytes: Bytes,
+        max_size: Option<usize>,
+    ) -> Result<(), SubstreamError> {
+        check_size!(max_size, bytes.len());
+
+        if bytes.contains(&b'\n') {
+            return Err(SubstreamError::IoError(ErrorKind::InvalidInput));
+        }
+
+        io.write_all(bytes.as_ref()).await?;
+        io.write_all(b"\n").await?;
+
+        // Flush the stream.
+        io.flush().await.map_err(From::from)
+    }
+
+    /// Send raw payload to remote peer.
+    async fn send_raw_payload<T: AsyncWrite + Unpin>(
+        io: &mut T,
+        bytes: Bytes,
+    ) -> Result<(), SubstreamError> {
+        io.write_all(bytes.as_ref()).await?;
+
+        // Flush the stream.
+        io.flush().await.map_err(From::from)
+    }
+
+    /// Send `bytes` to remote peer, framed using `codec`.
+    async fn send_payload<T: AsyncWrite + Unpin>(
+        io: &mut T,
+        codec: ProtocolCodec,
+        bytes: Bytes,
+    ) -> Result<(), SubstreamError> {
+        match codec {
+            ProtocolCodec::Unspecified => panic!("codec is unspecified"),
+            ProtocolCodec::Identity(payload_size) =>
+                Self::send_identity_payload(io, payload_size, bytes).await,
+            ProtocolCodec::UnsignedVarint(max_size) =>
+                Self::send_unsigned_varint_payload(io, bytes, max_size).await,
+            ProtocolCodec::NewlineDelimited(max_size) =>
+                Self::send_newline_delimited_payload(io, bytes, max_size).await,
+            ProtocolCodec::Raw => Self::send_raw_payload(io, bytes).await,
+        }
+    }
+
     /// Send framed data to remote peer.
     ///
     /// This function may be faster than the provided [`futures::Sink`] implementation for
@@ -404,6 +882,9 @@ impl Substream {
     /// This method is not cancellation safe. If that is required, use the provided
     /// [`futures::Sink`] implementation.
     ///
+    /// Frames buffered by the [`futures::Sink`] implementation are written before `bytes`. If the
+    /// substream is corked, the transport holds `bytes` along with them until it's uncorked.
+    ///
     /// # Panics
     ///
     /// Panics if no codec is provided.
@@ -416,31 +897,42 @@ impl Substream {
             "send framed"
         );
 
-        match &mut self.substream {
-            #[cfg(test)]
+        if self.is_closed() {
+            return Err(SubstreamError::ConnectionClosed);
+        }
+        if self.is_past_deadline() {
+            return Err(SubstreamError::DeadlineExceeded(Some(self.substream_id)));
+        }
+        self.on_activity();
+
+        if self.pending_out_frame.is_some() || !self.pending_out_frames.is_empty() {
+            futures::future::poll_fn(|cx| self.poll_write_pending(cx)).await?;
+        }
+
+        #[cfg(feature = "traffic-tap")]
+        if let Some(tap) = &self.tap {
+            tap.on_outbound(&bytes);
+        }
+
+        let nwritten = match &self.substream {
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Mock(_) => bytes.len(),
+            _ => Self::framed_len(self.codec, bytes.len()),
+        };
+
+        let result = match &mut self.substream {
+            #[cfg(any(test, feature = "test-utils"))]
             SubstreamType::Mock(ref mut substream) =>
                 futures::SinkExt::send(substream, bytes).await.map_err(Into::into),
-            SubstreamType::Tcp(ref mut substream) => match self.codec {
-                ProtocolCodec::Unspecified => panic!("codec is unspecified"),
-                ProtocolCodec::Identity(payload_size) =>
-                    Self::send_identity_payload(substream, payload_size, bytes).await,
-                ProtocolCodec::UnsignedVarint(max_size) =>
-                    Self::send_unsigned_varint_payload(substream, bytes, max_size).await,
-            },
+            SubstreamType::Tcp(ref mut substream) =>
+                Self::send_payload(substream, self.codec, bytes).await,
             #[cfg(feature = "websocket")]
-            SubstreamType::WebSocket(ref mut substream) => match self.codec {
-                ProtocolCodec::Unspecified => panic!("codec is unspecified"),
-                ProtocolCodec::Identity(payload_size) =>
-                    Self::send_identity_payload(substream, payload_size, bytes).await,
-                ProtocolCodec::UnsignedVarint(max_size) =>
-                    Self::send_unsigned_varint_payload(substream, bytes, max_size).await,
-            },
+            SubstreamType::WebSocket(ref mut substream) =>
+                Self::send_payload(substream, self.codec, bytes).await,
             #[cfg(feature = "quic")]
             SubstreamType::Quic(ref mut substream) => match self.codec {
-                ProtocolCodec::Unspecified => panic!("codec is unspecified"),
-                ProtocolCodec::Identity(payload_size) =>
-                    Self::send_identity_payload(substream, payload_size, bytes).await,
-                ProtocolCodec::UnsignedVarint(max_size) => {
+                // data written to a corked substream must go through its write buffer
+                ProtocolCodec::UnsignedVarint(max_size) if !self.corked => {
                     check_size!(max_size, bytes.len());
 
                     let mut buffer = unsigned_varint::encode::usize_buffer();
@@ -449,15 +941,33 @@ impl Substream {
 
                     substream.write_all_chunks(&mut [len.freeze(), bytes]).await
                 }
+                codec => Self::send_payload(substream, codec, bytes).await,
             },
             #[cfg(feature = "webrtc")]
-            SubstreamType::WebRtc(ref mut substream) => match self.codec {
-                ProtocolCodec::Unspecified => panic!("codec is unspecified"),
-                ProtocolCodec::Identity(payload_size) =>
-                    Self::send_identity_payload(substream, payload_size, bytes).await,
-                ProtocolCodec::UnsignedVarint(max_size) =>
-                    Self::send_unsigned_varint_payload(substream, bytes, max_size).await,
-            },
+            SubstreamType::WebRtc(ref mut substream) =>
+                Self::send_payload(substream, self.codec, bytes).await,
+            #[cfg(any(test, feature = "test-utils"))]
+            SubstreamType::Duplex(ref mut substream) =>
+                Self::send_payload(substream, self.codec, bytes).await,
+        };
+
+        if result.is_ok() {
+            self.bytes_written += nwritten as u64;
+        }
+
+        result
+    }
+
+    /// Get the number of bytes written to the substream for a `len`-byte payload framed using
+    /// `codec`.
+    fn framed_len(codec: ProtocolCodec, len: usize) -> usize {
+        match codec {
+            ProtocolCodec::UnsignedVarint(_) => {
+                let mut buffer = unsigned_varint::encode::usize_buffer();
+                unsigned_varint::encode::usize(len, &mut buffer).len() + len
+            }
+            ProtocolCodec::NewlineDelimited(_) => len + 1,
+            _ => len,
         }
     }
 }
@@ -468,7 +978,18 @@ impl tokio::io::AsyncRead for Substream {
         cx: &mut Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> Poll<std::io::Result<()>> {
-        poll_read!(&mut self.substream, cx, buf)
+        if self.poll_closed(cx) {
+            return Poll::Ready(Ok(()));
+        }
+        if self.poll_deadline(cx) {
+            return Poll::Ready(Err(ErrorKind::TimedOut.into()));
+        }
+
+        let this = &mut *self;
+        let result = futures::ready!(poll_read!(&mut this.substream, cx, buf, this.bytes_read));
+        self.on_activity();
+
+        Poll::Ready(result)
     }
 }
 
@@ -478,7 +999,20 @@ impl tokio::io::AsyncWrite for Substream {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, std::io::Error>> {
-        poll_write!(&mut self.substream, cx, buf)
+        if self.is_closed() {
+            return Poll::Ready(Err(ErrorKind::BrokenPipe.into()));
+        }
+        if self.is_past_deadline() {
+            return Poll::Ready(Err(ErrorKind::TimedOut.into()));
+        }
+        self.on_activity();
+
+        let result = poll_write!(&mut self.substream, cx, buf);
+        if let Poll::Ready(Ok(nwritten)) = result {
+            self.bytes_written += nwritten as u64;
+        }
+
+        result
     }
 
     fn poll_flush(
@@ -527,38 +1061,70 @@ impl Stream for Substream {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = Pin::into_inner(self);
 
+        if this.poll_closed(cx) {
+            return Poll::Ready(None);
+        }
+        if this.poll_deadline(cx) {
+            return Poll::Ready(Some(Err(SubstreamError::DeadlineExceeded(Some(
+                this.substream_id,
+            )))));
+        }
+
+        let frame = futures::ready!(this.poll_next_frame(cx));
+        this.on_activity();
+
+        #[cfg(feature = "traffic-tap")]
+        if let (Some(tap), Some(Ok(frame))) = (&this.tap, &frame) {
+            tap.on_inbound(frame);
+        }
+
+        Poll::Ready(frame)
+    }
+}
+
+impl Substream {
+    /// Read the next frame from the substream.
+    fn poll_next_frame(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<BytesMut, SubstreamError>>> {
         // `MockSubstream` implements `Stream` so calls to `poll_next()` must be delegated
-        delegate_poll_next!(&mut this.substream, cx);
+        delegate_poll_next!(&mut self.substream, cx, self.bytes_read);
 
         loop {
-            match this.codec {
+            match self.codec {
                 ProtocolCodec::Identity(payload_size) => {
                     let mut read_buf =
-                        ReadBuf::new(&mut this.read_buffer[this.offset..payload_size]);
-
-                    match futures::ready!(poll_read!(&mut this.substream, cx, &mut read_buf)) {
+                        ReadBuf::new(&mut self.read_buffer[self.offset..payload_size]);
+
+                    match futures::ready!(poll_read!(
+                        &mut self.substream,
+                        cx,
+                        &mut read_buf,
+                        self.bytes_read
+                    )) {
                         Ok(_) => {
                             let nread = read_buf.filled().len();
                             if nread == 0 {
                                 tracing::trace!(
                                     target: LOG_TARGET,
-                                    peer = ?this.peer,
+                                    peer = ?self.peer,
                                     "read zero bytes, substream closed"
                                 );
                                 return Poll::Ready(None);
                             }
 
-                            if nread == payload_size {
+                            self.offset += nread;
+
+                            if self.offset == payload_size {
                                 let mut payload = std::mem::replace(
-                                    &mut this.read_buffer,
+                                    &mut self.read_buffer,
                                     BytesMut::zeroed(payload_size),
                                 );
                                 payload.truncate(payload_size);
-                                this.offset = 0usize;
+                                self.offset = 0usize;
 
                                 return Poll::Ready(Some(Ok(payload)));
-                            } else {
-                                this.offset += read_buf.filled().len();
                             }
                         }
                         Err(error) => return Poll::Ready(Some(Err(error.into()))),
@@ -567,20 +1133,21 @@ impl Stream for Substream {
                 ProtocolCodec::UnsignedVarint(max_size) => {
                     loop {
                         // return all pending frames first
-                        if let Some(frame) = this.pending_frames.pop_front() {
+                        if let Some(frame) = self.pending_frames.pop_front() {
                             return Poll::Ready(Some(Ok(frame)));
                         }
 
-                        match this.current_frame_size.take() {
+                        match self.current_frame_size.take() {
                             Some(frame_size) => {
                                 let mut read_buf =
-                                    ReadBuf::new(&mut this.read_buffer[this.offset..]);
-                                this.current_frame_size = Some(frame_size);
+                                    ReadBuf::new(&mut self.read_buffer[self.offset..]);
+                                self.current_frame_size = Some(frame_size);
 
                                 match futures::ready!(poll_read!(
-                                    &mut this.substream,
+                                    &mut self.substream,
                                     cx,
-                                    &mut read_buf
+                                    &mut read_buf,
+                                    self.bytes_read
                                 )) {
                                     Err(_error) => return Poll::Ready(None),
                                     Ok(_) => {
@@ -589,19 +1156,20 @@ impl Stream for Substream {
                                             nread => nread,
                                         };
 
-                                        this.offset += nread;
+                                        self.offset += nread;
 
-                                        if this.offset == frame_size {
+                                        if self.offset == frame_size {
                                             let out_frame = std::mem::replace(
-                                                &mut this.read_buffer,
+                                                &mut self.read_buffer,
                                                 BytesMut::new(),
                                             );
-                                            this.offset = 0;
-                                            this.current_frame_size = None;
+                                            self.offset = 0;
+                                            self.current_frame_size = None;
+                                            self.release_buffer(frame_size);
 
                                             return Poll::Ready(Some(Ok(out_frame)));
                                         } else {
-                                            this.current_frame_size = Some(frame_size);
+                                            self.current_frame_size = Some(frame_size);
                                             continue;
                                         }
                                     }
@@ -609,44 +1177,51 @@ impl Stream for Substream {
                             }
                             None => {
                                 let mut read_buf =
-                                    ReadBuf::new(&mut this.size_vec[this.offset..this.offset + 1]);
+                                    ReadBuf::new(&mut self.size_vec[self.offset..self.offset + 1]);
 
                                 match futures::ready!(poll_read!(
-                                    &mut this.substream,
+                                    &mut self.substream,
                                     cx,
-                                    &mut read_buf
+                                    &mut read_buf,
+                                    self.bytes_read
                                 )) {
                                     Err(_error) => return Poll::Ready(None),
                                     Ok(_) => {
                                         if read_buf.filled().is_empty() {
                                             return Poll::Ready(None);
                                         }
-                                        this.offset += 1;
+                                        self.offset += 1;
 
-                                        match read_payload_size(&this.size_vec[..this.offset]) {
+                                        match read_payload_size(&self.size_vec[..self.offset]) {
                                             Err(ReadError::NotEnoughBytes) => continue,
                                             Err(_) =>
                                                 return Poll::Ready(Some(Err(
                                                     SubstreamError::ReadFailure(Some(
-                                                        this.substream_id,
+                                                        self.substream_id,
                                                     )),
                                                 ))),
                                             Ok((size, num_bytes)) => {
-                                                debug_assert_eq!(num_bytes, this.offset);
-
-                                                if let Some(max_size) = max_size {
-                                                    if size > max_size {
-                                                        return Poll::Ready(Some(Err(
-                                                            SubstreamError::ReadFailure(Some(
-                                                                this.substream_id,
-                                                            )),
-                                                        )));
-                                                    }
+                                                debug_assert_eq!(num_bytes, self.offset);
+
+                                                if max_size
+                                                    .into_iter()
+                                                    .chain(self.read_limit)
+                                                    .any(|max_size| size > max_size)
+                                                {
+                                                    return Poll::Ready(Some(Err(
+                                                        SubstreamError::FrameTooLarge(Some(
+                                                            self.substream_id,
+                                                        )),
+                                                    )));
                                                 }
 
-                                                this.offset = 0;
-                                                this.current_frame_size = Some(size);
-                                                this.read_buffer = BytesMut::zeroed(size);
+                                                if let Err(error) = self.reserve_buffer(size) {
+                                                    return Poll::Ready(Some(Err(error)));
+                                                }
+
+                                                self.offset = 0;
+                                                self.current_frame_size = Some(size);
+                                                self.read_buffer = BytesMut::zeroed(size);
                                             }
                                         }
                                     }
@@ -655,6 +1230,60 @@ impl Stream for Substream {
                         }
                     }
                 }
+                ProtocolCodec::NewlineDelimited(max_size) => {
+                    if let Some(position) =
+                        self.read_buffer[..self.offset].iter().position(|byte| *byte == b'\n')
+                    {
+                        let mut frame = self.read_buffer.split_to(position + 1);
+                        frame.truncate(position);
+                        self.offset -= position + 1;
+
+                        return Poll::Ready(Some(Ok(frame)));
+                    }
+
+                    if max_size
+                        .into_iter()
+                        .chain(self.read_limit)
+                        .any(|max_size| self.offset > max_size)
+                    {
+                        return Poll::Ready(Some(Err(SubstreamError::FrameTooLarge(Some(
+                            self.substream_id,
+                        )))));
+                    }
+
+                    if self.offset == self.read_buffer.len() {
+                        self.read_buffer.resize(self.offset + 1024, 0u8);
+                    }
+
+                    let mut read_buf = ReadBuf::new(&mut self.read_buffer[self.offset..]);
+
+                    match futures::ready!(poll_read!(
+                        &mut self.substream,
+                        cx,
+                        &mut read_buf,
+                        self.bytes_read
+                    )) {
+                        Err(error) => return Poll::Ready(Some(Err(error.into()))),
+                        Ok(_) => match read_buf.filled().len() {
+                            0 => return Poll::Ready(None),
+                            nread => self.offset += nread,
+                        },
+                    }
+                }
+                ProtocolCodec::Raw => {
+                    let mut read_buf = ReadBuf::new(&mut self.read_buffer[..]);
+
+                    return match futures::ready!(poll_read!(
+                        &mut self.substream,
+                        cx,
+                        &mut read_buf,
+                        self.bytes_read
+                    )) {
+                        Err(error) => Poll::Ready(Some(Err(error.into()))),
+                        Ok(_) if read_buf.filled().is_empty() => Poll::Ready(None),
+                        Ok(_) => Poll::Ready(Some(Ok(BytesMut::from(read_buf.filled())))),
+                    };
+                }
                 ProtocolCodec::Unspecified => panic!("codec is unspecified"),
             }
         }
@@ -666,19 +1295,42 @@ impl Sink<Bytes> for Substream {
     type Error = SubstreamError;
 
     fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.is_closed() {
+            return Poll::Ready(Err(SubstreamError::ConnectionClosed));
+        }
+        if self.is_past_deadline() {
+            return Poll::Ready(Err(SubstreamError::DeadlineExceeded(Some(
+                self.substream_id,
+            ))));
+        }
+
         // `MockSubstream` implements `Sink` so calls to `poll_ready()` must be delegated
         delegate_poll_ready!(&mut self.substream, cx);
 
         if self.pending_out_bytes >= BACKPRESSURE_BOUNDARY {
-            return poll_flush!(&mut self.substream, cx).map_err(From::from);
+            return self.poll_write_pending(cx);
+        }
+
+        // write out the buffered frames of the substream to free memory for new frames
+        if self.pending_out_bytes > 0
+            && self.reservation.as_ref().is_some_and(|reservation| reservation.is_exhausted())
+        {
+            return self.poll_write_pending(cx);
         }
 
         Poll::Ready(Ok(()))
     }
 
     fn start_send(mut self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        self.on_activity();
+
+        #[cfg(feature = "traffic-tap")]
+        if let Some(tap) = &self.tap {
+            tap.on_outbound(&item);
+        }
+
         // `MockSubstream` implements `Sink` so calls to `start_send()` must be delegated
-        delegate_start_send!(&mut self.substream, item);
+        delegate_start_send!(&mut self.substream, item, self.bytes_written);
 
         tracing::trace!(
             target: LOG_TARGET,
@@ -694,6 +1346,7 @@ impl Sink<Bytes> for Substream {
                     return Err(SubstreamError::IoError(ErrorKind::PermissionDenied));
                 }
 
+                self.reserve_buffer(item.len())?;
                 self.pending_out_bytes += item.len();
                 self.pending_out_frames.push_back(item);
             }
@@ -706,10 +1359,28 @@ impl Sink<Bytes> for Substream {
                     BytesMut::from(len)
                 };
 
+                self.reserve_buffer(len.len() + item.len())?;
                 self.pending_out_bytes += len.len() + item.len();
                 self.pending_out_frames.push_back(len.freeze());
                 self.pending_out_frames.push_back(item);
             }
+            ProtocolCodec::NewlineDelimited(max_size) => {
+                check_size!(max_size, item.len());
+
+                if item.contains(&b'\n') {
+                    return Err(SubstreamError::IoError(ErrorKind::InvalidInput));
+                }
+
+                self.reserve_buffer(item.len() + 1)?;
+                self.pending_out_bytes += item.len() + 1;
+                self.pending_out_frames.push_back(item);
+                self.pending_out_frames.push_back(Bytes::from_static(b"\n"));
+            }
+            ProtocolCodec::Raw => {
+                self.reserve_buffer(item.len())?;
+                self.pending_out_bytes += item.len();
+                self.pending_out_frames.push_back(item);
+            }
             ProtocolCodec::Unspecified => panic!("codec is unspecified"),
         }
 
@@ -720,32 +1391,7 @@ impl Sink<Bytes> for Substream {
         // `MockSubstream` implements `Sink` so calls to `poll_flush()` must be delegated
         delegate_poll_flush!(&mut self.substream, cx);
 
-        loop {
-            let mut pending_frame = match self.pending_out_frame.take() {
-                Some(frame) => frame,
-                None => match self.pending_out_frames.pop_front() {
-                    Some(frame) => frame,
-                    None => break,
-                },
-            };
-
-            match poll_write!(&mut self.substream, cx, &pending_frame) {
-                Poll::Ready(Err(error)) => return Poll::Ready(Err(error.into())),
-                Poll::Pending => {
-                    self.pending_out_frame = Some(pending_frame);
-                    break;
-                }
-                Poll::Ready(Ok(nwritten)) => {
-                    pending_frame.advance(nwritten);
-
-                    if !pending_frame.is_empty() {
-                        self.pending_out_frame = Some(pending_frame);
-                    }
-                }
-            }
-        }
-
-        poll_flush!(&mut self.substream, cx).map_err(From::from)
+        self.poll_write_pending(cx)
     }
 
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -844,6 +1490,7 @@ mod tests {
     use super::*;
     use crate::{mock::substream::MockSubstream, PeerId};
     use futures::{SinkExt, StreamExt};
+    use std::time::Duration;
 
     #[test]
     fn add_substream() {
@@ -1067,4 +1714,371 @@ mod tests {
             assert!(futures::poll!(set.next()).is_pending());
         }
     }
+
+    #[cfg(feature = "webrtc")]
+    #[tokio::test]
+    async fn corked_frames_are_written_together() {
+        use crate::transport::webrtc::SubstreamEvent;
+
+        let (inner, mut handle) = webrtc::Substream::new();
+        let mut substream = Substream::new_webrtc(
+            PeerId::random(),
+            SubstreamId::new(),
+            inner,
+            ProtocolCodec::UnsignedVarint(None),
+        );
+
+        substream.cork();
+        assert!(substream.is_corked());
+
+        for frame in [vec![1u8, 2], vec![3u8, 4, 5], vec![6u8]] {
+            substream.send(Bytes::from(frame)).await.unwrap();
+        }
+
+        // nothing is written while the substream is corked
+        assert!(futures::poll!(handle.next()).is_pending());
+
+        substream.uncork().await.unwrap();
+        assert!(!substream.is_corked());
+
+        assert_eq!(
+            handle.next().await,
+            Some(SubstreamEvent::Message(vec![2, 1, 2, 3, 3, 4, 5, 1, 6]))
+        );
+        assert!(futures::poll!(handle.next()).is_pending());
+    }
+
+    #[cfg(feature = "webrtc")]
+    #[tokio::test]
+    async fn explicit_flush_writes_corked_frames() {
+        use crate::transport::webrtc::SubstreamEvent;
+
+        let (inner, mut handle) = webrtc::Substream::new();
+        let mut substream = Substream::new_webrtc(
+            PeerId::random(),
+            SubstreamId::new(),
+            inner,
+            ProtocolCodec::UnsignedVarint(None),
+        );
+
+        substream.cork();
+        substream.send(Bytes::from(vec![1u8, 3, 3, 7])).await.unwrap();
+        assert!(futures::poll!(handle.next()).is_pending());
+
+        substream.flush().await.unwrap();
+        assert!(substream.is_corked());
+        assert_eq!(
+            handle.next().await,
+            Some(SubstreamEvent::Message(vec![4, 1, 3, 3, 7]))
+        );
+    }
+
+    #[tokio::test]
+    async fn async_read_write_mock_substream() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (inner1, mut inner2) = crate::mock::substream::paired();
+        let mut substream = Substream::new_mock(
+            PeerId::random(),
+            SubstreamId::from(0usize),
+            Box::new(inner1),
+        );
+
+        substream.write_all(b"ping").await.unwrap();
+        substream.flush().await.unwrap();
+        assert_eq!(inner2.next().await.unwrap().unwrap(), &b"ping"[..]);
+        assert_eq!(substream.bytes_written(), 4);
+
+        inner2.send(Bytes::from_static(b"pong")).await.unwrap();
+        let mut buffer = [0u8; 4];
+        substream.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"pong");
+        assert_eq!(substream.bytes_read(), 4);
+
+        substream.shutdown().await.unwrap();
+        assert!(inner2.next().await.is_none());
+    }
+
+    fn duplex_substreams(codec: ProtocolCodec) -> (Substream, Substream) {
+        let (io1, io2) = tokio::io::duplex(64 * 1024);
+
+        (
+            Substream::new_duplex(PeerId::random(), SubstreamId::from(0usize), io1, codec),
+            Substream::new_duplex(PeerId::random(), SubstreamId::from(1usize), io2, codec),
+        )
+    }
+
+    #[tokio::test]
+    async fn unsigned_varint_round_trip() {
+        let (mut substream1, mut substream2) =
+            duplex_substreams(ProtocolCodec::UnsignedVarint(Some(1024)));
+
+        substream1.send(Bytes::from(vec![1u8, 2, 3])).await.unwrap();
+        substream1.send_framed(Bytes::from(vec![4u8; 512])).await.unwrap();
+
+        assert_eq!(substream2.next().await.unwrap().unwrap(), vec![1u8, 2, 3]);
+        assert_eq!(substream2.next().await.unwrap().unwrap(), vec![4u8; 512]);
+        assert!(substream1.send_framed(Bytes::from(vec![0u8; 1025])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn newline_delimited_round_trip() {
+        let (mut substream1, mut substream2) =
+            duplex_substreams(ProtocolCodec::NewlineDelimited(Some(16)));
+
+        substream1.send(Bytes::from_static(b"hello")).await.unwrap();
+        substream1.send_framed(Bytes::from_static(b"")).await.unwrap();
+        substream1.send_framed(Bytes::from_static(b"world")).await.unwrap();
+
+        assert_eq!(substream2.next().await.unwrap().unwrap(), &b"hello"[..]);
+        assert_eq!(substream2.next().await.unwrap().unwrap(), &b""[..]);
+        assert_eq!(substream2.next().await.unwrap().unwrap(), &b"world"[..]);
+
+        // messages containing newlines cannot be sent
+        assert!(substream1.send_framed(Bytes::from_static(b"hello\nworld")).await.is_err());
+        assert!(substream1.send(Bytes::from_static(b"hello\nworld")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn newline_delimited_oversized_message() {
+        let (mut substream1, mut substream2) = duplex_substreams(ProtocolCodec::Raw);
+        substream2.codec = ProtocolCodec::NewlineDelimited(Some(16));
+
+        substream1.send(Bytes::from(vec![b'a'; 32])).await.unwrap();
+
+        assert!(std::matches!(
+            substream2.next().await,
+            Some(Err(SubstreamError::FrameTooLarge(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn raw_round_trip() {
+        let (mut substream1, mut substream2) = duplex_substreams(ProtocolCodec::Raw);
+
+        substream1.send(Bytes::from_static(b"hello, ")).await.unwrap();
+        substream1.send_framed(Bytes::from_static(b"world")).await.unwrap();
+        substream1.close().await;
+
+        // message boundaries are not preserved so read until the substream is closed
+        let mut received = Vec::new();
+        while let Some(bytes) = substream2.next().await {
+            received.extend_from_slice(&bytes.unwrap());
+        }
+
+        assert_eq!(received, b"hello, world");
+    }
+
+    #[tokio::test]
+    async fn close_write_keeps_read_side_open() {
+        let (mut substream1, mut substream2) =
+            duplex_substreams(ProtocolCodec::UnsignedVarint(Some(1024)));
+
+        substream1.send_framed(Bytes::from(vec![1u8, 2, 3])).await.unwrap();
+        substream1.close_write().await.unwrap();
+
+        assert_eq!(substream2.next().await.unwrap().unwrap(), vec![1u8, 2, 3]);
+        assert!(substream2.next().await.is_none());
+
+        // remote can still write to the half-closed substream
+        substream2.send_framed(Bytes::from(vec![4u8, 5, 6])).await.unwrap();
+        assert_eq!(substream1.next().await.unwrap().unwrap(), vec![4u8, 5, 6]);
+    }
+
+    #[tokio::test]
+    async fn close_with_final_message() {
+        let (mut substream1, mut substream2) =
+            duplex_substreams(ProtocolCodec::UnsignedVarint(Some(1024)));
+
+        // frames buffered before the final message are sent first
+        substream1.cork();
+        substream1.send(Bytes::from(vec![1u8, 2, 3])).await.unwrap();
+        substream1.close_with(Bytes::from_static(b"goodbye")).await.unwrap();
+
+        assert_eq!(substream2.next().await.unwrap().unwrap(), vec![1u8, 2, 3]);
+        assert_eq!(substream2.next().await.unwrap().unwrap(), &b"goodbye"[..]);
+        assert!(substream2.next().await.is_none());
+        assert_eq!(substream1.bytes_written(), 4 + 8);
+    }
+
+    #[tokio::test]
+    async fn buffering_refused_when_budget_exhausted() {
+        let budget = BufferBudget::new(100);
+        let (mut substream1, mut remote1) =
+            duplex_substreams(ProtocolCodec::UnsignedVarint(Some(1024)));
+        let (mut substream2, mut remote2) =
+            duplex_substreams(ProtocolCodec::UnsignedVarint(Some(1024)));
+        substream1.set_buffer_budget(budget.clone());
+        substream2.set_buffer_budget(budget.clone());
+        remote2.set_buffer_budget(budget.clone());
+
+        // 1-byte length prefix + 60 bytes stay buffered until the sink is flushed
+        substream1.feed(Bytes::from(vec![1u8; 60])).await.unwrap();
+        assert_eq!(budget.used(), 61);
+
+        assert_eq!(
+            substream2.feed(Bytes::from(vec![2u8; 50])).await,
+            Err(SubstreamError::BufferBudgetExhausted(Some(
+                SubstreamId::from(0usize)
+            )))
+        );
+        assert_eq!(budget.used(), 61);
+
+        // inbound frames which don't fit in the budget are refused as well
+        substream2.send_framed(Bytes::from(vec![3u8; 50])).await.unwrap();
+        assert_eq!(
+            remote2.next().await,
+            Some(Err(SubstreamError::BufferBudgetExhausted(Some(
+                SubstreamId::from(1usize)
+            ))))
+        );
+
+        // the buffered data is preserved and its memory released once it's been written
+        substream1.flush().await.unwrap();
+        assert_eq!(remote1.next().await.unwrap().unwrap(), vec![1u8; 60]);
+        assert_eq!(budget.used(), 0);
+
+        substream2.feed(Bytes::from(vec![2u8; 50])).await.unwrap();
+        assert_eq!(budget.used(), 51);
+
+        drop(substream2);
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[tokio::test]
+    async fn corked_data_released_from_budget_once_handed_to_transport() {
+        let budget = BufferBudget::new(100);
+        let (mut substream, mut remote) =
+            duplex_substreams(ProtocolCodec::UnsignedVarint(Some(1024)));
+        substream.set_buffer_budget(budget.clone());
+        substream.cork();
+
+        // frames waiting in the sink are counted against the budget while the substream is corked
+        substream.feed(Bytes::from(vec![1u8; 30])).await.unwrap();
+        substream.feed(Bytes::from(vec![2u8; 30])).await.unwrap();
+        assert_eq!(budget.used(), 62);
+
+        // but not once they've been written to the transport, which holds them until uncorked
+        futures::SinkExt::flush(&mut substream).await.unwrap();
+        assert!(substream.is_corked());
+        assert_eq!(budget.used(), 0);
+
+        substream.uncork().await.unwrap();
+        assert_eq!(remote.next().await.unwrap().unwrap(), vec![1u8; 30]);
+        assert_eq!(remote.next().await.unwrap().unwrap(), vec![2u8; 30]);
+    }
+
+    #[tokio::test]
+    async fn byte_counters_track_framed_traffic() {
+        let (mut substream1, mut substream2) =
+            duplex_substreams(ProtocolCodec::UnsignedVarint(Some(1024)));
+
+        // 1-byte length prefix + 3 bytes sent using `Sink`, 2-byte length prefix + 512 bytes
+        // sent using `send_framed()`
+        substream1.send(Bytes::from(vec![1u8, 2, 3])).await.unwrap();
+        substream1.send_framed(Bytes::from(vec![4u8; 512])).await.unwrap();
+        assert_eq!(substream1.bytes_written(), 4 + 514);
+        assert_eq!(substream1.bytes_read(), 0);
+
+        assert_eq!(substream2.next().await.unwrap().unwrap(), vec![1u8, 2, 3]);
+        assert_eq!(substream2.bytes_read(), 4);
+        assert_eq!(substream2.next().await.unwrap().unwrap(), vec![4u8; 512]);
+        assert_eq!(substream2.bytes_read(), 4 + 514);
+        assert_eq!(substream2.bytes_written(), 0);
+
+        // raw reads and writes through `AsyncRead`/`AsyncWrite` are counted as well
+        tokio::io::AsyncWriteExt::write_all(&mut substream2, &[0u8; 100]).await.unwrap();
+        let mut buffer = vec![0u8; 100];
+        tokio::io::AsyncReadExt::read_exact(&mut substream1, &mut buffer).await.unwrap();
+
+        assert_eq!(substream2.bytes_written(), 100);
+        assert_eq!(substream1.bytes_read(), 100);
+    }
+
+    #[tokio::test]
+    async fn byte_counters_mock_substream() {
+        let (inner1, inner2) = crate::mock::substream::paired();
+        let mut substream1 = Substream::new_mock(
+            PeerId::random(),
+            SubstreamId::from(0usize),
+            Box::new(inner1),
+        );
+        let mut substream2 = Substream::new_mock(
+            PeerId::random(),
+            SubstreamId::from(1usize),
+            Box::new(inner2),
+        );
+
+        substream1.send(Bytes::from(vec![0u8; 10])).await.unwrap();
+        substream1.send_framed(Bytes::from(vec![1u8; 20])).await.unwrap();
+        assert_eq!(substream1.bytes_written(), 30);
+
+        assert_eq!(substream2.next().await.unwrap().unwrap(), vec![0u8; 10]);
+        assert_eq!(substream2.next().await.unwrap().unwrap(), vec![1u8; 20]);
+        assert_eq!(substream2.bytes_read(), 30);
+    }
+
+    #[tokio::test]
+    async fn deadline_closes_active_substream() {
+        let (mut substream1, mut substream2) =
+            duplex_substreams(ProtocolCodec::UnsignedVarint(Some(1024)));
+
+        let deadline = Instant::now() + Duration::from_millis(300);
+        substream2.set_deadline(deadline);
+
+        // keep the substream active by sending a frame every 50 milliseconds
+        let sender = tokio::spawn(async move {
+            loop {
+                if substream1.send_framed(Bytes::from(vec![1u8, 2, 3])).await.is_err() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        });
+
+        let mut received = 0usize;
+        let error = loop {
+            match substream2.next().await {
+                Some(Ok(frame)) => {
+                    assert_eq!(frame, vec![1u8, 2, 3]);
+                    received += 1;
+                }
+                Some(Err(error)) => break error,
+                None => panic!("substream closed before the deadline"),
+            }
+        };
+
+        assert!(received > 1);
+        assert!(Instant::now() >= deadline);
+        assert_eq!(
+            error,
+            SubstreamError::DeadlineExceeded(Some(SubstreamId::from(1usize)))
+        );
+
+        // writes fail as well once the deadline has been reached
+        assert_eq!(
+            substream2.send_framed(Bytes::from(vec![4u8])).await,
+            Err(SubstreamError::DeadlineExceeded(Some(SubstreamId::from(
+                1usize
+            ))))
+        );
+
+        sender.abort();
+    }
+
+    #[tokio::test]
+    async fn close_write_mock_substream() {
+        let (inner1, mut inner2) = crate::mock::substream::paired();
+        let mut substream = Substream::new_mock(
+            PeerId::random(),
+            SubstreamId::from(0usize),
+            Box::new(inner1),
+        );
+
+        substream.close_write().await.unwrap();
+        assert!(inner2.next().await.is_none());
+
+        inner2.send(Bytes::from_static(b"hello")).await.unwrap();
+        assert_eq!(substream.next().await.unwrap().unwrap(), &b"hello"[..]);
+    }
 }
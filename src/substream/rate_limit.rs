@@ -0,0 +1,109 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Per-connection rate limit of inbound substreams.
+
+use std::time::Instant;
+
+/// Rate limit of the substreams a remote peer may open over a single connection.
+///
+/// The limit is enforced with a token bucket which holds up to `burst` tokens and is refilled at
+/// `rate` tokens per second. Each inbound substream takes one token and substreams opened while
+/// the bucket is empty are closed before they're negotiated.
+#[derive(Debug, Clone, Copy)]
+pub struct SubstreamRateLimit {
+    /// Number of substreams the remote peer may open per second, on average.
+    pub rate: u32,
+
+    /// Number of substreams the remote peer may open in quick succession.
+    pub burst: u32,
+}
+
+/// Token bucket enforcing [`SubstreamRateLimit`] for a connection.
+#[derive(Debug)]
+pub(crate) struct SubstreamRateLimiter {
+    /// Rate limit.
+    limit: SubstreamRateLimit,
+
+    /// Number of tokens left in the bucket.
+    tokens: f64,
+
+    /// When was the bucket last refilled.
+    refilled: Instant,
+}
+
+impl SubstreamRateLimiter {
+    /// Create new [`SubstreamRateLimiter`] with a full bucket.
+    pub(crate) fn new(limit: SubstreamRateLimit) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.burst),
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Attempt to take a token for an inbound substream.
+    ///
+    /// Returns `false` if the substream exceeds the rate limit.
+    pub(crate) fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// Attempt to take a token at `now`.
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+
+        self.tokens =
+            (self.tokens + elapsed * f64::from(self.limit.rate)).min(f64::from(self.limit.burst));
+        self.refilled = now;
+
+        if self.tokens < 1f64 {
+            return false;
+        }
+
+        self.tokens -= 1f64;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn burst_then_rate() {
+        let mut limiter = SubstreamRateLimiter::new(SubstreamRateLimit { rate: 10, burst: 3 });
+        let start = limiter.refilled;
+
+        assert!((0..3).all(|_| limiter.try_acquire_at(start)));
+        assert!(!limiter.try_acquire_at(start));
+
+        // one token is refilled every 100ms
+        assert!(!limiter.try_acquire_at(start + Duration::from_millis(50)));
+        assert!(limiter.try_acquire_at(start + Duration::from_millis(100)));
+        assert!(!limiter.try_acquire_at(start + Duration::from_millis(100)));
+
+        // the bucket never holds more than `burst` tokens
+        let later = start + Duration::from_secs(60);
+        assert!((0..3).all(|_| limiter.try_acquire_at(later)));
+        assert!(!limiter.try_acquire_at(later));
+    }
+}
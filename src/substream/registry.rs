@@ -0,0 +1,404 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Registry of open substreams for debugging substream leaks.
+
+use crate::{
+    types::{protocol::ProtocolName, SubstreamId},
+    PeerId,
+};
+
+use futures::task::AtomicWaker;
+use parking_lot::Mutex;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    task::Context,
+    time::{Duration, Instant},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::substream::registry";
+
+/// Default maximum number of tracked substreams.
+const MAX_SUBSTREAMS: usize = 4096;
+
+/// How often the watchdog checks for idle substreams.
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Substream registry configuration.
+#[derive(Debug, Clone)]
+pub struct SubstreamRegistryConfig {
+    /// Maximum number of tracked substreams.
+    ///
+    /// Substreams opened while the registry is full are not tracked.
+    pub max_substreams: usize,
+
+    /// How long can a substream stay idle before the watchdog reports it.
+    ///
+    /// A substream is idle if nothing has been read from or written to it. If `None`, the
+    /// watchdog is disabled and substreams are only tracked.
+    pub idle_timeout: Option<Duration>,
+
+    /// Close substreams reported by the watchdog.
+    ///
+    /// A closed substream fails all subsequent writes and reports the end of the stream to the
+    /// reader, waking up any task waiting to read from it.
+    pub close_idle: bool,
+}
+
+impl Default for SubstreamRegistryConfig {
+    fn default() -> Self {
+        Self {
+            max_substreams: MAX_SUBSTREAMS,
+            idle_timeout: None,
+            close_idle: false,
+        }
+    }
+}
+
+/// Information about an open substream.
+#[derive(Debug, Clone)]
+pub struct SubstreamInfo {
+    /// Remote peer ID.
+    pub peer: PeerId,
+
+    /// Protocol which owns the substream.
+    pub protocol: ProtocolName,
+
+    /// Substream ID.
+    pub substream_id: SubstreamId,
+
+    /// When was the substream opened.
+    pub opened: Instant,
+
+    /// When was the substream last read from or written to.
+    pub last_activity: Instant,
+}
+
+/// Registry entry of an open substream.
+struct Entry {
+    /// Remote peer ID.
+    peer: PeerId,
+
+    /// Protocol which owns the substream.
+    protocol: ProtocolName,
+
+    /// Substream ID.
+    substream_id: SubstreamId,
+
+    /// When was the substream opened.
+    opened: Instant,
+
+    /// When was the substream last read from or written to.
+    last_activity: Mutex<Instant>,
+
+    /// Has the substream been reported idle by the watchdog.
+    reported: AtomicBool,
+
+    /// Has the substream been closed by the watchdog.
+    closed: AtomicBool,
+
+    /// Waker of the task reading from the substream.
+    waker: AtomicWaker,
+}
+
+impl Entry {
+    /// Get [`SubstreamInfo`] of the entry.
+    fn info(&self) -> SubstreamInfo {
+        SubstreamInfo {
+            peer: self.peer,
+            protocol: self.protocol.clone(),
+            substream_id: self.substream_id,
+            opened: self.opened,
+            last_activity: *self.last_activity.lock(),
+        }
+    }
+}
+
+/// Open substreams, indexed by registration key.
+type Entries = Mutex<HashMap<usize, Arc<Entry>>>;
+
+/// Registry of open substreams.
+#[derive(Clone)]
+pub(crate) struct SubstreamRegistry {
+    /// Registry configuration.
+    config: Arc<SubstreamRegistryConfig>,
+
+    /// Open substreams.
+    entries: Arc<Entries>,
+
+    /// Next registration key.
+    next_key: Arc<AtomicUsize>,
+}
+
+impl SubstreamRegistry {
+    /// Create new [`SubstreamRegistry`].
+    pub(crate) fn new(config: SubstreamRegistryConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            next_key: Arc::new(AtomicUsize::new(0usize)),
+        }
+    }
+
+    /// Register new substream.
+    ///
+    /// Returns `None` if the registry is full.
+    pub(crate) fn register(
+        &self,
+        peer: PeerId,
+        protocol: ProtocolName,
+        substream_id: SubstreamId,
+    ) -> Option<Registration> {
+        let mut entries = self.entries.lock();
+
+        if entries.len() >= self.config.max_substreams {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                %protocol,
+                ?substream_id,
+                "substream registry full, substream not tracked",
+            );
+            return None;
+        }
+
+        let now = Instant::now();
+        let key = self.next_key.fetch_add(1usize, Ordering::Relaxed);
+        let entry = Arc::new(Entry {
+            peer,
+            protocol,
+            substream_id,
+            opened: now,
+            last_activity: Mutex::new(now),
+            reported: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        });
+        entries.insert(key, Arc::clone(&entry));
+
+        Some(Registration {
+            key,
+            entry,
+            entries: Arc::downgrade(&self.entries),
+        })
+    }
+
+    /// Get information about all tracked substreams.
+    pub(crate) fn substreams(&self) -> Vec<SubstreamInfo> {
+        self.entries.lock().values().map(|entry| entry.info()).collect()
+    }
+
+    /// Check for substreams which have been idle longer than the configured idle timeout.
+    ///
+    /// Each idle substream is reported once, unless it becomes active again. If configured, the
+    /// reported substreams are closed.
+    pub(crate) fn check_idle(&self) -> Vec<SubstreamInfo> {
+        check_idle(&self.config, &self.entries)
+    }
+
+    /// Run the watchdog which periodically checks for idle substreams.
+    ///
+    /// Exits when the registry is dropped or if the watchdog is disabled.
+    pub(crate) async fn run_watchdog(self) {
+        let Some(idle_timeout) = self.config.idle_timeout else {
+            return;
+        };
+        let interval = idle_timeout.min(WATCHDOG_INTERVAL);
+        let entries = Arc::downgrade(&self.entries);
+        let config = self.config;
+
+        loop {
+            tokio::time::sleep(interval).await;
+
+            match entries.upgrade() {
+                Some(entries) => {
+                    check_idle(&config, &entries);
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+/// Report, and optionally close, substreams which have been idle longer than the idle timeout.
+fn check_idle(config: &SubstreamRegistryConfig, entries: &Entries) -> Vec<SubstreamInfo> {
+    let Some(idle_timeout) = config.idle_timeout else {
+        return Vec::new();
+    };
+
+    let entries = entries.lock();
+    let mut idle = Vec::new();
+
+    for entry in entries.values() {
+        let last_activity = *entry.last_activity.lock();
+
+        if last_activity.elapsed() < idle_timeout || entry.reported.swap(true, Ordering::Relaxed) {
+            continue;
+        }
+
+        tracing::warn!(
+            target: LOG_TARGET,
+            peer = ?entry.peer,
+            protocol = %entry.protocol,
+            substream_id = ?entry.substream_id,
+            age = ?entry.opened.elapsed(),
+            idle = ?last_activity.elapsed(),
+            "substream has been idle for too long, possible leak",
+        );
+
+        if config.close_idle {
+            entry.closed.store(true, Ordering::Release);
+            entry.waker.wake();
+        }
+
+        idle.push(entry.info());
+    }
+
+    idle
+}
+
+/// Registration of a substream in [`SubstreamRegistry`].
+///
+/// Removes the substream from the registry when dropped.
+pub(crate) struct Registration {
+    /// Registration key.
+    key: usize,
+
+    /// Registry entry.
+    entry: Arc<Entry>,
+
+    /// Open substreams of the registry.
+    entries: Weak<Entries>,
+}
+
+impl Registration {
+    /// Record activity on the substream.
+    pub(crate) fn on_activity(&self) {
+        *self.entry.last_activity.lock() = Instant::now();
+        self.entry.reported.store(false, Ordering::Relaxed);
+    }
+
+    /// Check if the substream has been closed by the watchdog.
+    ///
+    /// Registers the waker of `cx` to be woken up if the substream is closed later.
+    pub(crate) fn poll_closed(&self, cx: &mut Context<'_>) -> bool {
+        self.entry.waker.register(cx.waker());
+        self.is_closed()
+    }
+
+    /// Check if the substream has been closed by the watchdog.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.entry.closed.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Some(entries) = self.entries.upgrade() {
+            entries.lock().remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::substream::DummySubstream, substream::Substream};
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn idle_substream_reported_and_closed() {
+        let registry = SubstreamRegistry::new(SubstreamRegistryConfig {
+            idle_timeout: Some(Duration::from_millis(100)),
+            close_idle: true,
+            ..Default::default()
+        });
+        let peer = PeerId::random();
+        let mut substream = Substream::new_mock(
+            peer,
+            SubstreamId::from(1337usize),
+            Box::new(DummySubstream::new()),
+        );
+        substream.register(&registry, ProtocolName::from("/proto/1"));
+
+        let substreams = registry.substreams();
+        assert_eq!(substreams.len(), 1);
+        assert_eq!(substreams[0].peer, peer);
+        assert_eq!(substreams[0].protocol, ProtocolName::from("/proto/1"));
+        assert_eq!(substreams[0].substream_id, SubstreamId::from(1337usize));
+
+        // substream is not idle yet
+        assert!(registry.check_idle().is_empty());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let idle = registry.check_idle();
+        assert_eq!(idle.len(), 1);
+        assert_eq!(idle[0].peer, peer);
+        assert_eq!(idle[0].substream_id, SubstreamId::from(1337usize));
+
+        // idle substream is reported only once
+        assert!(registry.check_idle().is_empty());
+
+        // closed substream reports end of stream even though the inner substream never returns
+        assert!(substream.next().await.is_none());
+
+        drop(substream);
+        assert!(registry.substreams().is_empty());
+    }
+
+    #[test]
+    fn registry_is_bounded() {
+        let registry = SubstreamRegistry::new(SubstreamRegistryConfig {
+            max_substreams: 1,
+            ..Default::default()
+        });
+        let peer = PeerId::random();
+
+        let registration = registry.register(
+            peer,
+            ProtocolName::from("/proto/1"),
+            SubstreamId::from(0usize),
+        );
+        assert!(registration.is_some());
+        assert!(registry
+            .register(
+                peer,
+                ProtocolName::from("/proto/1"),
+                SubstreamId::from(1usize)
+            )
+            .is_none());
+
+        drop(registration);
+        assert!(registry
+            .register(
+                peer,
+                ProtocolName::from("/proto/1"),
+                SubstreamId::from(2usize)
+            )
+            .is_some());
+    }
+}
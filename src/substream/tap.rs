@@ -0,0 +1,156 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Traffic tap for recording the frames of a protocol.
+
+use crate::{
+    types::{protocol::ProtocolName, SubstreamId},
+    PeerId,
+};
+
+use bytes::Bytes;
+use tokio::sync::mpsc::{error::TrySendError, Sender};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::substream::tap";
+
+/// Direction of a tapped frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapDirection {
+    /// Frame was read from the substream.
+    Inbound,
+
+    /// Frame was written to the substream.
+    Outbound,
+}
+
+/// Frame copied to a [`TrafficTap`].
+#[derive(Debug, Clone)]
+pub struct TappedFrame {
+    /// Remote peer.
+    pub peer: PeerId,
+
+    /// Protocol of the substream.
+    pub protocol: ProtocolName,
+
+    /// Substream ID.
+    pub substream_id: SubstreamId,
+
+    /// Direction of the frame.
+    pub direction: TapDirection,
+
+    /// Frame payload, without the framing of the protocol codec.
+    pub frame: Bytes,
+}
+
+/// Tap which receives a copy of the frames of a protocol.
+///
+/// Frames are delivered with [`Sender::try_send()`] so a slow reader never blocks the protocol.
+/// If the channel is full, the frame is dropped from the recording.
+#[derive(Debug, Clone)]
+pub struct TrafficTap {
+    /// TX channel for sending the tapped frames.
+    tx: Sender<TappedFrame>,
+
+    /// Are outbound frames tapped.
+    outbound: bool,
+}
+
+impl TrafficTap {
+    /// Create new [`TrafficTap`] which sends inbound frames to `tx`.
+    pub fn new(tx: Sender<TappedFrame>) -> Self {
+        Self {
+            tx,
+            outbound: false,
+        }
+    }
+
+    /// Tap outbound frames in addition to inbound frames.
+    pub fn with_outbound(mut self, outbound: bool) -> Self {
+        self.outbound = outbound;
+        self
+    }
+}
+
+/// [`TrafficTap`] attached to a substream.
+#[derive(Debug)]
+pub(crate) struct SubstreamTap {
+    /// Traffic tap.
+    tap: TrafficTap,
+
+    /// Remote peer.
+    peer: PeerId,
+
+    /// Protocol of the substream.
+    protocol: ProtocolName,
+
+    /// Substream ID.
+    substream_id: SubstreamId,
+}
+
+impl SubstreamTap {
+    /// Create new [`SubstreamTap`].
+    pub(crate) fn new(
+        tap: TrafficTap,
+        peer: PeerId,
+        protocol: ProtocolName,
+        substream_id: SubstreamId,
+    ) -> Self {
+        Self {
+            tap,
+            peer,
+            protocol,
+            substream_id,
+        }
+    }
+
+    /// Copy frame read from the substream to the tap.
+    pub(crate) fn on_inbound(&self, frame: &[u8]) {
+        self.send(TapDirection::Inbound, Bytes::copy_from_slice(frame));
+    }
+
+    /// Copy frame written to the substream to the tap, if outbound frames are tapped.
+    pub(crate) fn on_outbound(&self, frame: &Bytes) {
+        if self.tap.outbound {
+            self.send(TapDirection::Outbound, frame.clone());
+        }
+    }
+
+    fn send(&self, direction: TapDirection, frame: Bytes) {
+        let frame = TappedFrame {
+            peer: self.peer,
+            protocol: self.protocol.clone(),
+            substream_id: self.substream_id,
+            direction,
+            frame,
+        };
+
+        if let Err(TrySendError::Full(_)) = self.tap.tx.try_send(frame) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                peer = ?self.peer,
+                protocol = %self.protocol,
+                substream_id = ?self.substream_id,
+                ?direction,
+                "tap is full, dropping frame",
+            );
+        }
+    }
+}
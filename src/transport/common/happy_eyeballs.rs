@@ -0,0 +1,285 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Happy-eyeballs style dialing of dual-stack peers shared between TCP and WebSocket.
+
+use futures::{stream::FuturesUnordered, StreamExt};
+use multiaddr::{Multiaddr, Protocol};
+
+use std::{collections::VecDeque, future::Future, time::Duration};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::transport::happy-eyeballs";
+
+/// Check if `address` is an IPv4 address.
+fn is_ipv4(address: &Multiaddr) -> bool {
+    matches!(
+        address.iter().next(),
+        Some(Protocol::Ip4(_)) | Some(Protocol::Dns4(_))
+    )
+}
+
+/// Dial `addresses` concurrently and return the first connection that was successfully opened.
+///
+/// At most `max_concurrent` addresses are dialed at a time and the remaining addresses are dialed
+/// as the earlier dial attempts fail. The exception is an IPv4 address which is dialed once the
+/// head start has elapsed even if all slots are taken by IPv6 dial attempts, so that hanging IPv6
+/// dial attempts can't hold back IPv4 until they time out.
+///
+/// If `addresses` contains both IPv4 and IPv6 addresses, IPv6 addresses are given a head start
+/// of `delay` before IPv4 addresses are dialed. IPv4 addresses are dialed immediately if all IPv6
+/// dial attempts fail before the head start has elapsed. Addresses which are neither IPv4 nor
+/// IPv6, such as `/dns`, are dialed along with IPv6 addresses.
+///
+/// Once a connection has been opened, all other pending dial attempts are cancelled.
+pub async fn dial<T, E, F, Fut>(
+    addresses: Vec<Multiaddr>,
+    delay: Duration,
+    max_concurrent: usize,
+    mut dial: F,
+) -> Result<T, Vec<E>>
+where
+    F: FnMut(Multiaddr) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let max_concurrent = max_concurrent.max(1);
+    let mut errors = Vec::with_capacity(addresses.len());
+    let (mut ipv4_queue, mut ipv6_queue): (VecDeque<_>, VecDeque<_>) =
+        addresses.into_iter().partition(is_ipv4);
+
+    let mut ipv6 = FuturesUnordered::new();
+    let mut ipv4 = FuturesUnordered::new();
+
+    let mut ipv4_started = ipv6_queue.is_empty() || ipv4_queue.is_empty() || delay.is_zero();
+    let head_start = tokio::time::sleep(delay);
+    tokio::pin!(head_start);
+
+    loop {
+        // start new dial attempts until the limit is reached, splitting the attempts evenly
+        // between IPv6 and IPv4 addresses once IPv4 addresses may be dialed
+        while ipv6.len() + ipv4.len() < max_concurrent {
+            let use_ipv4 = ipv4_started
+                && !ipv4_queue.is_empty()
+                && (ipv6_queue.is_empty() || ipv4.len() < ipv6.len());
+
+            match use_ipv4 {
+                true => ipv4.push(dial(ipv4_queue.pop_front().expect("queue to be non-empty"))),
+                false => match ipv6_queue.pop_front() {
+                    Some(address) => ipv6.push(dial(address)),
+                    None => break,
+                },
+            }
+        }
+
+        // IPv4 may exceed the limit by one so it's not blocked by IPv6 dial attempts which
+        // take up every slot and may never complete
+        if ipv4_started && ipv4.is_empty() {
+            if let Some(address) = ipv4_queue.pop_front() {
+                ipv4.push(dial(address));
+            }
+        }
+
+        let result = tokio::select! {
+            result = ipv6.next(), if !ipv6.is_empty() => result,
+            result = ipv4.next(), if !ipv4.is_empty() => result,
+            _ = &mut head_start, if !ipv4_started => {
+                tracing::trace!(target: LOG_TARGET, "head start elapsed, dial ipv4 addresses");
+
+                ipv4_started = true;
+                continue;
+            }
+            else => return Err(errors),
+        };
+
+        match result {
+            Some(Ok(connection)) => return Ok(connection),
+            Some(Err(error)) => errors.push(error),
+            None => {}
+        }
+
+        if ipv6.is_empty() && ipv6_queue.is_empty() && !ipv4_started {
+            tracing::trace!(target: LOG_TARGET, "all ipv6 dial attempts failed, dial ipv4 addresses");
+
+            ipv4_started = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    const DELAY: Duration = Duration::from_millis(500);
+
+    fn ipv4_address() -> Multiaddr {
+        "/ip4/127.0.0.1/tcp/8888".parse().unwrap()
+    }
+
+    fn ipv6_address() -> Multiaddr {
+        "/ip6/::1/tcp/8888".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn ipv4_dialed_after_head_start() {
+        let started = Instant::now();
+
+        // IPv6 address is unreachable and the dial attempt never completes
+        let address = dial(
+            vec![ipv4_address(), ipv6_address()],
+            DELAY,
+            usize::MAX,
+            |address| async move {
+                if !is_ipv4(&address) {
+                    futures::future::pending::<()>().await;
+                }
+
+                Ok::<_, ()>(address)
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(address, ipv4_address());
+        assert!(started.elapsed() >= DELAY);
+    }
+
+    #[tokio::test]
+    async fn ipv4_not_blocked_by_hanging_ipv6_dials() {
+        let started = Instant::now();
+
+        // both IPv6 addresses are unreachable and take up every slot
+        let address = dial(
+            vec![
+                ipv6_address(),
+                "/ip6/::2/tcp/8888".parse().unwrap(),
+                ipv4_address(),
+            ],
+            DELAY,
+            2,
+            |address| async move {
+                if !is_ipv4(&address) {
+                    futures::future::pending::<()>().await;
+                }
+
+                Ok::<_, ()>(address)
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(address, ipv4_address());
+        assert!(started.elapsed() >= DELAY);
+        assert!(started.elapsed() < DELAY * 2);
+    }
+
+    #[tokio::test]
+    async fn ipv6_preferred_during_head_start() {
+        let started = Instant::now();
+
+        let address = dial(
+            vec![ipv4_address(), ipv6_address()],
+            DELAY,
+            usize::MAX,
+            |address| async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok::<_, ()>(address)
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(address, ipv6_address());
+        assert!(started.elapsed() < DELAY);
+    }
+
+    #[tokio::test]
+    async fn ipv4_dialed_immediately_if_ipv6_fails() {
+        let started = Instant::now();
+
+        let address = dial(
+            vec![ipv4_address(), ipv6_address()],
+            DELAY,
+            usize::MAX,
+            |address| async move {
+                match is_ipv4(&address) {
+                    true => Ok(address),
+                    false => Err(address),
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(address, ipv4_address());
+        assert!(started.elapsed() < DELAY);
+    }
+
+    #[tokio::test]
+    async fn all_errors_reported() {
+        let errors = dial(
+            vec![ipv4_address(), ipv6_address()],
+            DELAY,
+            usize::MAX,
+            |address| async move { Err::<(), _>(address) },
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors.contains(&ipv4_address()));
+        assert!(errors.contains(&ipv6_address()));
+    }
+
+    #[tokio::test]
+    async fn concurrent_dials_limited() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let in_flight = Arc::new(AtomicUsize::new(0usize));
+        let max_in_flight = Arc::new(AtomicUsize::new(0usize));
+        let addresses = (0..5u16)
+            .map(|port| format!("/ip4/127.0.0.1/tcp/{}", 8888 + port).parse().unwrap())
+            .collect();
+
+        let errors = dial(addresses, DELAY, 2, |address| {
+            let in_flight = Arc::clone(&in_flight);
+            let max_in_flight = Arc::clone(&max_in_flight);
+
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                Err::<(), _>(address)
+            }
+        })
+        .await
+        .unwrap_err();
+
+        // every address was dialed but never more than two at a time
+        assert_eq!(errors.len(), 5);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+}
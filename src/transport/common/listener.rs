@@ -30,22 +30,60 @@ use hickory_resolver::{
     config::{ResolverConfig, ResolverOpts},
     TokioAsyncResolver,
 };
-use multiaddr::{Multiaddr, Protocol};
+use multiaddr::{Multiaddr, Onion3Addr, Protocol};
 use network_interface::{Addr, NetworkInterface, NetworkInterfaceConfig};
+use parking_lot::RwLock;
 use socket2::{Domain, Socket, Type};
 use tokio::net::{TcpListener as TokioTcpListener, TcpStream};
 
 use std::{
+    collections::{HashSet, VecDeque},
     io,
-    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6},
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::transport::listener";
 
+/// Source of the IP addresses of the local network interfaces.
+pub(crate) trait InterfaceSource: Send {
+    /// Get the IP addresses of all network interfaces.
+    fn addresses(&mut self) -> io::Result<Vec<IpAddr>>;
+}
+
+/// [`InterfaceSource`] which queries the network interfaces of the operating system.
+struct SystemInterfaces;
+
+impl InterfaceSource for SystemInterfaces {
+    fn addresses(&mut self) -> io::Result<Vec<IpAddr>> {
+        let interfaces = NetworkInterface::show()
+            .map_err(|error| io::Error::new(io::ErrorKind::Other, error.to_string()))?;
+
+        Ok(interfaces
+            .into_iter()
+            .flat_map(|interface| interface.addr)
+            .map(|address| match address {
+                Addr::V4(address) => IpAddr::V4(address.ip),
+                Addr::V6(address) => IpAddr::V6(address.ip),
+            })
+            .collect())
+    }
+}
+
+/// Change in the listen addresses of a [`SocketListener`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddressChange {
+    /// Listener became reachable at a new address.
+    New(Multiaddr),
+
+    /// Listener is no longer reachable at the address.
+    Expired(Multiaddr),
+}
+
 /// Address type.
 #[derive(Debug)]
 pub enum AddressType {
@@ -58,6 +96,11 @@ pub enum AddressType {
         port: u16,
         dns_type: DnsType,
     },
+
+    /// Tor onion service address.
+    ///
+    /// Can only be dialed through a SOCKS5 proxy.
+    Onion3(Onion3Addr<'static>),
 }
 
 /// The DNS type of the address.
@@ -82,6 +125,16 @@ impl AddressType {
                 port,
                 dns_type,
             } => (address, port, dns_type),
+            AddressType::Onion3(address) => {
+                let hostname = onion3_hostname(&address);
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    %hostname,
+                    "onion addresses can only be dialed through a proxy",
+                );
+
+                return Err(DnsError::ResolveError(hostname));
+            }
         };
 
         let lookup =
@@ -123,8 +176,11 @@ impl AddressType {
 #[derive(Clone, Default)]
 pub enum DialAddresses {
     /// Reuse port from listen addresses.
+    ///
+    /// The addresses of listeners bound to an unspecified address are kept up to date by
+    /// [`SocketListener`] if the interface refresh is enabled.
     Reuse {
-        listen_addresses: Arc<Vec<SocketAddr>>,
+        listen_addresses: Arc<RwLock<Vec<SocketAddr>>>,
     },
     /// Do not reuse port.
     #[default]
@@ -136,7 +192,7 @@ impl DialAddresses {
     pub fn local_dial_address(&self, remote_address: &IpAddr) -> Result<Option<SocketAddr>, ()> {
         match self {
             DialAddresses::Reuse { listen_addresses } => {
-                for address in listen_addresses.iter() {
+                for address in listen_addresses.read().iter() {
                     if remote_address.is_ipv4() == address.is_ipv4()
                         && remote_address.is_loopback() == address.ip().is_loopback()
                     {
@@ -167,6 +223,20 @@ pub struct SocketListener {
     listeners: Vec<TokioTcpListener>,
     /// The index in the listeners from which the polling is resumed.
     poll_index: usize,
+    /// Unspecified addresses the listeners are bound to, with the bound port.
+    wildcards: Vec<SocketAddr>,
+    /// Concrete addresses currently reported for the listeners bound to unspecified addresses.
+    concrete_addresses: HashSet<SocketAddr>,
+    /// Source of the addresses of the network interfaces.
+    interfaces: Box<dyn InterfaceSource>,
+    /// Timer for refreshing the concrete addresses, if enabled.
+    refresh: Option<tokio::time::Interval>,
+    /// Local addresses for outbound connections, shared with the transport.
+    dial_addresses: DialAddresses,
+    /// Listen address changes which haven't been polled yet.
+    pending_changes: VecDeque<ListenAddressChange>,
+    /// Conversion of the listen addresses into `Multiaddr`s.
+    to_multiaddr: fn(&SocketAddr) -> Multiaddr,
 }
 
 /// Trait to convert between `Multiaddr` and `SocketAddr`.
@@ -202,9 +272,7 @@ impl GetSocketAddr for TcpAddress {
     }
 
     fn socket_address_to_multiaddr(address: &SocketAddr) -> Multiaddr {
-        Multiaddr::empty()
-            .with(Protocol::from(address.ip()))
-            .with(Protocol::Tcp(address.port()))
+        ip_to_multiaddr(address).with(Protocol::Tcp(address.port()))
     }
 }
 
@@ -219,8 +287,7 @@ impl GetSocketAddr for WebSocketAddress {
     }
 
     fn socket_address_to_multiaddr(address: &SocketAddr) -> Multiaddr {
-        Multiaddr::empty()
-            .with(Protocol::from(address.ip()))
+        ip_to_multiaddr(address)
             .with(Protocol::Tcp(address.port()))
             .with(Protocol::Ws(std::borrow::Cow::Borrowed("/")))
     }
@@ -228,11 +295,38 @@ impl GetSocketAddr for WebSocketAddress {
 
 impl SocketListener {
     /// Create new [`SocketListener`]
+    ///
+    /// Listeners bound to an unspecified address, such as `0.0.0.0`, are reported at the
+    /// non-loopback addresses of the network interfaces of the matching IP version.
     pub fn new<T: GetSocketAddr>(
         addresses: Vec<Multiaddr>,
+        reuse_address: bool,
+        reuse_port: bool,
+        nodelay: bool,
+    ) -> (Self, Vec<Multiaddr>, DialAddresses) {
+        Self::new_with_interfaces::<T>(
+            addresses,
+            reuse_address,
+            reuse_port,
+            nodelay,
+            Box::new(SystemInterfaces),
+        )
+    }
+
+    /// Create new [`SocketListener`] which gets the addresses of the network interfaces from
+    /// `interfaces`.
+    pub(crate) fn new_with_interfaces<T: GetSocketAddr>(
+        addresses: Vec<Multiaddr>,
+        reuse_address: bool,
         reuse_port: bool,
         nodelay: bool,
+        mut interfaces: Box<dyn InterfaceSource>,
     ) -> (Self, Vec<Multiaddr>, DialAddresses) {
+        let mut interface_addresses = None;
+        let mut wildcards = Vec::new();
+        let mut concrete_addresses = HashSet::new();
+        let mut dial_addresses = Vec::new();
+
         let (listeners, listen_addresses): (_, Vec<Vec<_>>) = addresses
             .into_iter()
             .filter_map(|address| {
@@ -247,6 +341,15 @@ impl SocketListener {
 
                         return None;
                     }
+                    AddressType::Onion3(address) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            hostname = %onion3_hostname(&address),
+                            "onion address not supported as bind address"
+                        );
+
+                        return None;
+                    }
                     AddressType::Socket(address) => address,
                 };
 
@@ -262,7 +365,7 @@ impl SocketListener {
 
                 socket.set_nodelay(nodelay).ok()?;
                 socket.set_nonblocking(true).ok()?;
-                socket.set_reuse_address(true).ok()?;
+                socket.set_reuse_address(reuse_address).ok()?;
                 #[cfg(unix)]
                 if reuse_port {
                     socket.set_reuse_port(true).ok()?;
@@ -274,30 +377,14 @@ impl SocketListener {
                 let listener = TokioTcpListener::from_std(socket).ok()?;
                 let local_address = listener.local_addr().ok()?;
 
-                let listen_addresses = if address.ip().is_unspecified() {
-                    match NetworkInterface::show() {
-                        Ok(ifaces) => ifaces
-                            .into_iter()
-                            .flat_map(|record| {
-                                record.addr.into_iter().filter_map(|iface_address| {
-                                    match (iface_address, address.is_ipv4()) {
-                                        (Addr::V4(inner), true) => Some(SocketAddr::new(
-                                            IpAddr::V4(inner.ip),
-                                            local_address.port(),
-                                        )),
-                                        (Addr::V6(inner), false) =>
-                                            match inner.ip.segments().first() {
-                                                Some(0xfe80) => None,
-                                                _ => Some(SocketAddr::new(
-                                                    IpAddr::V6(inner.ip),
-                                                    local_address.port(),
-                                                )),
-                                            },
-                                        _ => None,
-                                    }
-                                })
-                            })
-                            .collect(),
+                if !address.ip().is_unspecified() {
+                    dial_addresses.push(local_address);
+                    return Some((listener, vec![local_address]));
+                }
+
+                if interface_addresses.is_none() {
+                    match interfaces.addresses() {
+                        Ok(addresses) => interface_addresses = Some(addresses),
                         Err(error) => {
                             tracing::warn!(
                                 target: LOG_TARGET,
@@ -308,21 +395,37 @@ impl SocketListener {
                             return None;
                         }
                     }
-                } else {
-                    vec![local_address]
+                }
+
+                let listen_addresses = wildcard_addresses(
+                    local_address,
+                    interface_addresses.as_deref().unwrap_or_default(),
+                );
+
+                // loopback addresses are not reported but connections to them must still be
+                // dialed from the listener's port
+                let loopback = match local_address {
+                    SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
                 };
+                dial_addresses.extend(listen_addresses.iter().copied());
+                dial_addresses.push(SocketAddr::new(loopback, local_address.port()));
+                concrete_addresses.extend(listen_addresses.iter().copied());
+                wildcards.push(local_address);
 
                 Some((listener, listen_addresses))
             })
             .unzip();
 
-        let listen_addresses = listen_addresses.into_iter().flatten().collect::<Vec<_>>();
-        let listen_multi_addresses =
-            listen_addresses.iter().map(T::socket_address_to_multiaddr).collect();
+        let listen_multi_addresses = listen_addresses
+            .into_iter()
+            .flatten()
+            .map(|address| T::socket_address_to_multiaddr(&address))
+            .collect();
 
         let dial_addresses = if reuse_port {
             DialAddresses::Reuse {
-                listen_addresses: Arc::new(listen_addresses),
+                listen_addresses: Arc::new(RwLock::new(dial_addresses)),
             }
         } else {
             DialAddresses::NoReuse
@@ -332,11 +435,126 @@ impl SocketListener {
             Self {
                 listeners,
                 poll_index: 0,
+                wildcards,
+                concrete_addresses,
+                interfaces,
+                refresh: None,
+                dial_addresses: dial_addresses.clone(),
+                pending_changes: VecDeque::new(),
+                to_multiaddr: T::socket_address_to_multiaddr,
             },
             listen_multi_addresses,
             dial_addresses,
         )
     }
+
+    /// Refresh the addresses of the network interfaces every `interval`.
+    ///
+    /// Changes in the addresses of listeners bound to an unspecified address are returned by
+    /// [`SocketListener::poll_address_change()`]. Has no effect if no listener is bound to an
+    /// unspecified address.
+    pub fn with_interface_refresh(mut self, interval: Duration) -> Self {
+        if !self.wildcards.is_empty() {
+            let mut refresh =
+                tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            self.refresh = Some(refresh);
+        }
+
+        self
+    }
+
+    /// Poll the next change in the listen addresses.
+    pub fn poll_address_change(&mut self, cx: &mut Context<'_>) -> Poll<ListenAddressChange> {
+        loop {
+            if let Some(change) = self.pending_changes.pop_front() {
+                return Poll::Ready(change);
+            }
+
+            let Some(refresh) = self.refresh.as_mut() else {
+                return Poll::Pending;
+            };
+
+            futures::ready!(refresh.poll_tick(cx));
+            self.refresh_addresses();
+        }
+    }
+
+    /// Fetch the addresses of the network interfaces and record which concrete addresses of the
+    /// listeners have appeared or disappeared.
+    ///
+    /// The dial addresses shared with the transport are updated to match.
+    fn refresh_addresses(&mut self) {
+        let interfaces = match self.interfaces.addresses() {
+            Ok(interfaces) => interfaces,
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to refresh network interfaces",
+                );
+                return;
+            }
+        };
+
+        let current: HashSet<_> = self
+            .wildcards
+            .iter()
+            .flat_map(|address| wildcard_addresses(*address, &interfaces))
+            .collect();
+
+        for address in self.concrete_addresses.difference(&current) {
+            tracing::debug!(target: LOG_TARGET, ?address, "listen address expired");
+
+            self.pending_changes
+                .push_back(ListenAddressChange::Expired((self.to_multiaddr)(address)));
+        }
+
+        for address in current.difference(&self.concrete_addresses) {
+            tracing::debug!(target: LOG_TARGET, ?address, "new listen address");
+
+            self.pending_changes
+                .push_back(ListenAddressChange::New((self.to_multiaddr)(address)));
+        }
+
+        if let DialAddresses::Reuse { listen_addresses } = &self.dial_addresses {
+            let mut listen_addresses = listen_addresses.write();
+
+            listen_addresses.retain(|address| {
+                !self.concrete_addresses.contains(address) || current.contains(address)
+            });
+            for address in current.difference(&self.concrete_addresses) {
+                if !listen_addresses.contains(address) {
+                    listen_addresses.push(*address);
+                }
+            }
+        }
+
+        self.concrete_addresses = current;
+    }
+}
+
+/// Get the concrete addresses a listener bound to the unspecified `address` is reachable at.
+///
+/// Loopback and IPv6 link-local addresses are skipped as they're of no use to remote peers.
+fn wildcard_addresses(address: SocketAddr, interfaces: &[IpAddr]) -> Vec<SocketAddr> {
+    let mut addresses = Vec::new();
+
+    for ip in interfaces {
+        let link_local = std::matches!(ip, IpAddr::V6(ip) if ip.segments()[0] == 0xfe80);
+
+        if ip.is_ipv4() != address.is_ipv4() || ip.is_loopback() || link_local {
+            continue;
+        }
+
+        let concrete = SocketAddr::new(*ip, address.port());
+        if !addresses.contains(&concrete) {
+            addresses.push(concrete);
+        }
+    }
+
+    addresses
 }
 
 /// The type of the socket listener.
@@ -375,6 +593,23 @@ fn multiaddr_to_socket_address(
         };
 
     let socket_address = match iter.next() {
+        Some(Protocol::Ip6zone(zone)) => {
+            let scope_id = ipv6_scope_id(&zone)?;
+
+            match (iter.next(), iter.next()) {
+                (Some(Protocol::Ip6(address)), Some(Protocol::Tcp(port))) => AddressType::Socket(
+                    SocketAddr::V6(SocketAddrV6::new(address, port, 0, scope_id)),
+                ),
+                protocol => {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        ?protocol,
+                        "invalid protocol, expected `Ip6` followed by `Tcp`",
+                    );
+                    return Err(AddressError::InvalidProtocol);
+                }
+            }
+        }
         Some(Protocol::Ip6(address)) => match iter.next() {
             Some(Protocol::Tcp(port)) =>
                 AddressType::Socket(SocketAddr::new(IpAddr::V6(address), port)),
@@ -404,6 +639,9 @@ fn multiaddr_to_socket_address(
             handle_dns_type(address.into(), DnsType::Dns4, iter.next())?,
         Some(Protocol::Dns6(address)) =>
             handle_dns_type(address.into(), DnsType::Dns6, iter.next())?,
+        // the port is part of the onion address so it's not followed by `/tcp`
+        Some(Protocol::Onion3(address)) if ty == SocketListenerType::Tcp =>
+            AddressType::Onion3(address.acquire()),
         protocol => {
             tracing::error!(target: LOG_TARGET, ?protocol, "invalid transport protocol");
             return Err(AddressError::InvalidProtocol);
@@ -446,6 +684,77 @@ fn multiaddr_to_socket_address(
     Ok((socket_address, maybe_peer))
 }
 
+/// Get the hostname of an onion service, as understood by a Tor SOCKS5 proxy.
+pub(crate) fn onion3_hostname(address: &Onion3Addr<'_>) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    // 35 bytes encode into exactly 56 base32 characters so no padding is needed
+    let mut hostname = String::with_capacity(62);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+
+    for byte in address.hash() {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            hostname.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+
+    hostname.push_str(".onion");
+    hostname
+}
+
+/// Resolve the zone of an `/ip6zone` address into an IPv6 scope id.
+///
+/// The zone is either the numeric scope id itself or the name of a local interface,
+/// in which case the index of that interface is used.
+pub(crate) fn ipv6_scope_id(zone: &str) -> Result<u32, AddressError> {
+    if let Ok(scope_id) = zone.parse::<u32>() {
+        return Ok(scope_id);
+    }
+
+    NetworkInterface::show()
+        .ok()
+        .and_then(|interfaces| interfaces.into_iter().find(|interface| interface.name == zone))
+        .map(|interface| interface.index)
+        .ok_or_else(|| AddressError::UnknownZone(zone.to_string()))
+}
+
+/// Bind `socket` to the network interface named `device`.
+///
+/// Traffic sent over the socket leaves through `device` regardless of the routing table. Only
+/// supported on Linux and Android, other platforms return [`io::ErrorKind::Unsupported`].
+#[cfg(any(feature = "quic", feature = "webrtc"))]
+pub(crate) fn bind_to_device(socket: &Socket, device: &str) -> io::Result<()> {
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    {
+        socket.bind_device(Some(device.as_bytes()))
+    }
+
+    #[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+    {
+        let _ = socket;
+
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("cannot bind socket to `{device}`, binding to a device is not supported"),
+        ))
+    }
+}
+
+/// Convert the IP address of `address` into a `Multiaddr`, including the `/ip6zone`
+/// of a scoped IPv6 address.
+fn ip_to_multiaddr(address: &SocketAddr) -> Multiaddr {
+    match address {
+        SocketAddr::V6(address) if address.scope_id() != 0 => Multiaddr::empty()
+            .with(Protocol::Ip6zone(address.scope_id().to_string().into()))
+            .with(Protocol::Ip6(*address.ip())),
+        address => Multiaddr::empty().with(Protocol::from(address.ip())),
+    }
+}
+
 impl Stream for SocketListener {
     type Item = io::Result<(TcpStream, SocketAddr)>;
 
@@ -613,9 +922,84 @@ mod tests {
         .is_ok());
     }
 
+    #[test]
+    fn parse_onion3_multiaddress() {
+        let (address, _) = multiaddr_to_socket_address(
+            &"/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"
+                .parse()
+                .expect("valid multiaddress"),
+            SocketListenerType::Tcp,
+        )
+        .unwrap();
+
+        match address {
+            AddressType::Onion3(address) => {
+                assert_eq!(
+                    onion3_hostname(&address),
+                    "vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd.onion"
+                );
+                assert_eq!(address.port(), 1234);
+            }
+            address => panic!("invalid address: {address:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_multiaddress_with_ipv6_zone() {
+        let (address, _) = multiaddr_to_socket_address(
+            &"/ip6zone/3/ip6/fe80::1/tcp/8888".parse().expect("valid multiaddress"),
+            SocketListenerType::Tcp,
+        )
+        .unwrap();
+
+        match address {
+            AddressType::Socket(SocketAddr::V6(address)) => {
+                assert_eq!(address.ip(), &"fe80::1".parse::<Ipv6Addr>().unwrap());
+                assert_eq!(address.port(), 8888);
+                assert_eq!(address.scope_id(), 3);
+                assert_eq!(
+                    TcpAddress::socket_address_to_multiaddr(&SocketAddr::V6(address)),
+                    "/ip6zone/3/ip6/fe80::1/tcp/8888".parse().unwrap(),
+                );
+            }
+            address => panic!("invalid address: {address:?}"),
+        }
+
+        // zone given as an interface name
+        let interface = NetworkInterface::show().unwrap().into_iter().next().unwrap();
+        let (address, _) = multiaddr_to_socket_address(
+            &format!("/ip6zone/{}/ip6/fe80::1/tcp/8888/ws", interface.name)
+                .parse()
+                .expect("valid multiaddress"),
+            SocketListenerType::WebSocket,
+        )
+        .unwrap();
+
+        match address {
+            AddressType::Socket(SocketAddr::V6(address)) =>
+                assert_eq!(address.scope_id(), interface.index),
+            address => panic!("invalid address: {address:?}"),
+        }
+
+        assert!(std::matches!(
+            multiaddr_to_socket_address(
+                &"/ip6zone/no-such-interface/ip6/fe80::1/tcp/8888"
+                    .parse()
+                    .expect("valid multiaddress"),
+                SocketListenerType::Tcp,
+            ),
+            Err(AddressError::UnknownZone(zone)) if zone == "no-such-interface"
+        ));
+        assert!(multiaddr_to_socket_address(
+            &"/ip6zone/3/ip4/127.0.0.1/tcp/8888".parse().expect("valid multiaddress"),
+            SocketListenerType::Tcp,
+        )
+        .is_err());
+    }
+
     #[tokio::test]
     async fn no_listeners_tcp() {
-        let (mut listener, _, _) = SocketListener::new::<TcpAddress>(Vec::new(), true, false);
+        let (mut listener, _, _) = SocketListener::new::<TcpAddress>(Vec::new(), true, true, false);
 
         futures::future::poll_fn(|cx| match listener.poll_next_unpin(cx) {
             Poll::Pending => Poll::Ready(()),
@@ -626,7 +1010,8 @@ mod tests {
 
     #[tokio::test]
     async fn no_listeners_websocket() {
-        let (mut listener, _, _) = SocketListener::new::<WebSocketAddress>(Vec::new(), true, false);
+        let (mut listener, _, _) =
+            SocketListener::new::<WebSocketAddress>(Vec::new(), true, true, false);
 
         futures::future::poll_fn(|cx| match listener.poll_next_unpin(cx) {
             Poll::Pending => Poll::Ready(()),
@@ -639,7 +1024,7 @@ mod tests {
     async fn one_listener_tcp() {
         let address: Multiaddr = "/ip6/::1/tcp/0".parse().unwrap();
         let (mut listener, listen_addresses, _) =
-            SocketListener::new::<TcpAddress>(vec![address.clone()], true, false);
+            SocketListener::new::<TcpAddress>(vec![address.clone()], true, true, false);
 
         let Some(Protocol::Tcp(port)) =
             listen_addresses.iter().next().unwrap().clone().iter().skip(1).next()
@@ -657,7 +1042,7 @@ mod tests {
     async fn one_listener_websocket() {
         let address: Multiaddr = "/ip6/::1/tcp/0/ws".parse().unwrap();
         let (mut listener, listen_addresses, _) =
-            SocketListener::new::<WebSocketAddress>(vec![address.clone()], true, false);
+            SocketListener::new::<WebSocketAddress>(vec![address.clone()], true, true, false);
         let Some(Protocol::Tcp(port)) =
             listen_addresses.iter().next().unwrap().clone().iter().skip(1).next()
         else {
@@ -675,7 +1060,7 @@ mod tests {
         let address1: Multiaddr = "/ip6/::1/tcp/0".parse().unwrap();
         let address2: Multiaddr = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
         let (mut listener, listen_addresses, _) =
-            SocketListener::new::<TcpAddress>(vec![address1, address2], true, false);
+            SocketListener::new::<TcpAddress>(vec![address1, address2], true, true, false);
         let Some(Protocol::Tcp(port1)) =
             listen_addresses.iter().next().unwrap().clone().iter().skip(1).next()
         else {
@@ -703,7 +1088,7 @@ mod tests {
         let address1: Multiaddr = "/ip6/::1/tcp/0/ws".parse().unwrap();
         let address2: Multiaddr = "/ip4/127.0.0.1/tcp/0/ws".parse().unwrap();
         let (mut listener, listen_addresses, _) =
-            SocketListener::new::<WebSocketAddress>(vec![address1, address2], true, false);
+            SocketListener::new::<WebSocketAddress>(vec![address1, address2], true, true, false);
 
         let Some(Protocol::Tcp(port1)) =
             listen_addresses.iter().next().unwrap().clone().iter().skip(1).next()
@@ -727,13 +1112,39 @@ mod tests {
         assert!(res1.is_ok() && res2.is_ok());
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rebind_with_reuse_address() {
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
+        let (mut listener, listen_addresses, _) =
+            SocketListener::new::<TcpAddress>(vec![address], true, false, false);
+        let listen_address = listen_addresses[0].clone();
+        let Some(Protocol::Tcp(port)) = listen_address.iter().nth(1) else {
+            panic!("invalid address");
+        };
+
+        // close the connection from the listener's side first so it's left in `TIME_WAIT`
+        let (inbound, outbound) = tokio::join!(
+            listener.next(),
+            TcpStream::connect(format!("127.0.0.1:{port}"))
+        );
+        let (inbound, _) = inbound.unwrap().unwrap();
+        drop(inbound);
+        drop(outbound.unwrap());
+        drop(listener);
+
+        let (_listener, listen_addresses, _) =
+            SocketListener::new::<TcpAddress>(vec![listen_address.clone()], true, false, false);
+        assert_eq!(listen_addresses, vec![listen_address]);
+    }
+
     #[tokio::test]
     async fn local_dial_address() {
         let dial_addresses = DialAddresses::Reuse {
-            listen_addresses: Arc::new(vec![
+            listen_addresses: Arc::new(RwLock::new(vec![
                 "[2001:7d0:84aa:3900:2a5d:9e85::]:8888".parse().unwrap(),
                 "92.168.127.1:9999".parse().unwrap(),
-            ]),
+            ])),
         };
 
         assert_eq!(
@@ -752,4 +1163,116 @@ mod tests {
             ))),
         );
     }
+
+    /// [`InterfaceSource`] whose addresses are controlled by the test.
+    #[derive(Clone, Default)]
+    struct StubInterfaces(Arc<parking_lot::Mutex<Vec<IpAddr>>>);
+
+    impl InterfaceSource for StubInterfaces {
+        fn addresses(&mut self) -> io::Result<Vec<IpAddr>> {
+            Ok(self.0.lock().clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn wildcard_listener_tracks_interfaces() {
+        let interfaces = StubInterfaces::default();
+        *interfaces.0.lock() = vec![
+            "10.0.0.1".parse().unwrap(),
+            "192.168.1.2".parse().unwrap(),
+            "127.0.0.1".parse().unwrap(),
+            "2001:db8::1".parse().unwrap(),
+        ];
+
+        let (listener, listen_addresses, _) = SocketListener::new_with_interfaces::<TcpAddress>(
+            vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()],
+            true,
+            false,
+            false,
+            Box::new(interfaces.clone()),
+        );
+        let mut listener = listener.with_interface_refresh(Duration::from_millis(50));
+
+        let Some(Protocol::Tcp(port)) = listen_addresses[0].iter().nth(1) else {
+            panic!("invalid address");
+        };
+        let address = |ip: &str| -> Multiaddr { format!("/ip4/{ip}/tcp/{port}").parse().unwrap() };
+
+        // loopback and addresses of the other IP version are not reported
+        assert_eq!(
+            listen_addresses,
+            vec![address("10.0.0.1"), address("192.168.1.2")]
+        );
+
+        interfaces.0.lock().retain(|ip| ip != &"192.168.1.2".parse::<IpAddr>().unwrap());
+        interfaces.0.lock().push("172.16.0.3".parse().unwrap());
+
+        let mut changes = Vec::new();
+        for _ in 0..2 {
+            let change = tokio::time::timeout(
+                Duration::from_secs(5),
+                futures::future::poll_fn(|cx| listener.poll_address_change(cx)),
+            )
+            .await
+            .expect("address change");
+            changes.push(change);
+        }
+
+        assert_eq!(
+            changes,
+            vec![
+                ListenAddressChange::Expired(address("192.168.1.2")),
+                ListenAddressChange::New(address("172.16.0.3")),
+            ]
+        );
+        assert!(futures::poll!(futures::future::poll_fn(
+            |cx| listener.poll_address_change(cx)
+        ))
+        .is_pending());
+    }
+
+    #[tokio::test]
+    async fn interface_refresh_updates_dial_addresses() {
+        let interfaces = StubInterfaces::default();
+        *interfaces.0.lock() = vec!["10.0.0.1".parse().unwrap()];
+
+        let (listener, listen_addresses, dial_addresses) =
+            SocketListener::new_with_interfaces::<TcpAddress>(
+                vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()],
+                true,
+                true,
+                false,
+                Box::new(interfaces.clone()),
+            );
+        let mut listener = listener.with_interface_refresh(Duration::from_millis(50));
+
+        let Some(Protocol::Tcp(port)) = listen_addresses[0].iter().nth(1) else {
+            panic!("invalid address");
+        };
+        let DialAddresses::Reuse { listen_addresses } = &dial_addresses else {
+            panic!("port reuse enabled");
+        };
+        let address = |ip: &str| SocketAddr::new(ip.parse().unwrap(), port);
+
+        assert_eq!(
+            *listen_addresses.read(),
+            vec![address("10.0.0.1"), address("127.0.0.1")]
+        );
+
+        *interfaces.0.lock() = vec!["172.16.0.3".parse().unwrap()];
+
+        for _ in 0..2 {
+            tokio::time::timeout(
+                Duration::from_secs(5),
+                futures::future::poll_fn(|cx| listener.poll_address_change(cx)),
+            )
+            .await
+            .expect("address change");
+        }
+
+        assert_eq!(
+            *listen_addresses.read(),
+            vec![address("127.0.0.1"), address("172.16.0.3")]
+        );
+    }
 }
@@ -20,4 +20,5 @@
 
 //! Shared transport protocol implementation
 
+pub mod happy_eyeballs;
 pub mod listener;
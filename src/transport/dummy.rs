@@ -104,7 +104,11 @@ impl Transport for DummyTransport {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{error::DialError, transport::Endpoint, PeerId};
+    use crate::{
+        error::DialError,
+        transport::{Endpoint, Muxer, SecurityProtocol},
+        PeerId,
+    };
     use futures::StreamExt;
 
     #[tokio::test]
@@ -123,6 +127,8 @@ mod tests {
         transport.inject_event(TransportEvent::ConnectionEstablished {
             peer,
             endpoint: endpoint.clone(),
+            security: SecurityProtocol::Noise,
+            muxer: Some(Muxer::Yamux),
         });
 
         match transport.next().await.unwrap() {
@@ -141,9 +147,13 @@ mod tests {
             TransportEvent::ConnectionEstablished {
                 peer: event_peer,
                 endpoint: event_endpoint,
+                security,
+                muxer,
             } => {
                 assert_eq!(peer, event_peer);
                 assert_eq!(endpoint, event_endpoint);
+                assert_eq!(security, SecurityProtocol::Noise);
+                assert_eq!(muxer, Some(Muxer::Yamux));
             }
             _ => panic!("invalid event"),
         }
@@ -105,6 +105,11 @@ impl AddressRecord {
         self.score = self.score.saturating_add(score);
     }
 
+    /// Update the address of the record, keeping its score and `ConnectionId`.
+    pub fn set_address(&mut self, peer: &PeerId, address: Multiaddr) {
+        *self = AddressRecord::new(peer, address, self.score, self.connection_id);
+    }
+
     /// Set `ConnectionId` for the [`AddressRecord`].
     pub fn set_connection_id(&mut self, connection_id: ConnectionId) {
         self.connection_id = Some(connection_id);
@@ -233,6 +238,29 @@ impl AddressStore {
 
         records
     }
+
+    /// Take at most `limit` `AddressRecord`s accepted by `filter` from [`AddressStore`].
+    ///
+    /// Records rejected by `filter` are kept in the store.
+    pub fn take_matching(
+        &mut self,
+        limit: usize,
+        filter: impl Fn(&AddressRecord) -> bool,
+    ) -> Vec<AddressRecord> {
+        let mut records = Vec::new();
+        let mut rejected = Vec::new();
+
+        while records.len() < limit {
+            match self.pop() {
+                Some(record) if filter(&record) => records.push(record),
+                Some(record) => rejected.push(record),
+                None => break,
+            }
+        }
+        self.extend(rejected);
+
+        records
+    }
 }
 
 #[cfg(test)]
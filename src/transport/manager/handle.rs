@@ -20,12 +20,15 @@
 
 use crate::{
     addresses::PublicAddresses,
-    crypto::ed25519::Keypair,
+    crypto::Keypair,
     error::ImmediateDialError,
     executor::Executor,
-    protocol::ProtocolSet,
+    protocol::{NegotiationCache, NegotiationObserver, ProtocolSet},
+    substream::{BufferBudget, SubstreamRateLimit, SubstreamRegistry},
     transport::manager::{
         address::{AddressRecord, AddressStore},
+        ip_filter::IpFilterConfig,
+        reputation::Reputation,
         types::{PeerContext, PeerState, SupportedTransport},
         ProtocolContext, TransportManagerEvent, LOG_TARGET,
     },
@@ -33,6 +36,9 @@ use crate::{
     BandwidthSink, PeerId,
 };
 
+#[cfg(feature = "traffic-tap")]
+use crate::substream::TrafficTap;
+
 use multiaddr::{Multiaddr, Protocol};
 use parking_lot::RwLock;
 use tokio::sync::mpsc::{error::TrySendError, Sender};
@@ -40,7 +46,7 @@ use tokio::sync::mpsc::{error::TrySendError, Sender};
 use std::{
     collections::{HashMap, HashSet},
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
 };
@@ -59,6 +65,12 @@ pub enum InnerTransportManagerCommand {
         /// Remote address.
         address: Multiaddr,
     },
+
+    /// Peer was banned, close all connections to it and abort dials in progress.
+    BanPeer {
+        /// Remote peer ID.
+        peer: PeerId,
+    },
 }
 
 /// Handle for communicating with [`crate::transport::manager::TransportManager`].
@@ -81,6 +93,12 @@ pub struct TransportManagerHandle {
 
     /// Public addresses.
     public_addresses: PublicAddresses,
+
+    /// Peer reputation.
+    reputation: Arc<RwLock<Reputation>>,
+
+    /// Are automatic dials suppressed because the node has reached its target connections.
+    dial_throttled: Arc<AtomicBool>,
 }
 
 impl TransportManagerHandle {
@@ -92,6 +110,8 @@ impl TransportManagerHandle {
         supported_transport: HashSet<SupportedTransport>,
         listen_addresses: Arc<RwLock<HashSet<Multiaddr>>>,
         public_addresses: PublicAddresses,
+        reputation: Arc<RwLock<Reputation>>,
+        dial_throttled: Arc<AtomicBool>,
     ) -> Self {
         Self {
             peers,
@@ -100,6 +120,8 @@ impl TransportManagerHandle {
             supported_transport,
             listen_addresses,
             public_addresses,
+            reputation,
+            dial_throttled,
         }
     }
 
@@ -131,7 +153,18 @@ impl TransportManagerHandle {
                 if address.is_unspecified() {
                     return false;
                 },
+            Some(Protocol::Ip6zone(_)) => match iter.next() {
+                Some(Protocol::Ip6(address)) if !address.is_unspecified() => {}
+                _ => return false,
+            },
             Some(Protocol::Dns(_)) | Some(Protocol::Dns4(_)) | Some(Protocol::Dns6(_)) => {}
+            Some(Protocol::Onion3(_)) =>
+                return std::matches!(iter.next(), Some(Protocol::P2p(_)))
+                    && self.supported_transport.contains(&SupportedTransport::Onion3),
+            #[cfg(unix)]
+            Some(Protocol::Unix(_)) =>
+                return std::matches!(iter.next(), Some(Protocol::P2p(_)))
+                    && self.supported_transport.contains(&SupportedTransport::Unix),
             _ => return false,
         }
 
@@ -160,6 +193,54 @@ impl TransportManagerHandle {
         }
     }
 
+    /// Adjust the reputation of `peer` by `change`.
+    ///
+    /// If the peer crossed the ban threshold, [`crate::transport::manager::TransportManager`] is
+    /// told to close all connections to the peer and to abort dials to it.
+    ///
+    /// Returns `true` if the peer crossed the ban threshold and is now banned.
+    pub fn report_peer(&self, peer: PeerId, change: i32) -> bool {
+        if !self.reputation.write().report(peer, change) {
+            return false;
+        }
+
+        if let Err(error) = self.cmd_tx.try_send(InnerTransportManagerCommand::BanPeer { peer }) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?peer,
+                ?error,
+                "failed to disconnect banned peer",
+            );
+        }
+
+        true
+    }
+
+    /// Get current reputation of `peer`.
+    pub fn reputation(&self, peer: &PeerId) -> i32 {
+        self.reputation.read().reputation(peer)
+    }
+
+    /// Check if `peer` is banned.
+    pub fn is_banned(&self, peer: &PeerId) -> bool {
+        self.reputation.read().is_banned(peer)
+    }
+
+    /// Check if `peer` is being dialed.
+    pub(crate) fn is_dialing(&self, peer: &PeerId) -> bool {
+        std::matches!(
+            self.peers.read().get(peer),
+            Some(PeerContext {
+                state: PeerState::Dialing { .. }
+                    | PeerState::Opening { .. }
+                    | PeerState::Disconnected {
+                        dial_record: Some(_)
+                    },
+                ..
+            })
+        )
+    }
+
     /// Check if the address is a local listen address and if so, discard it.
     fn is_local_address(&self, address: &Multiaddr) -> bool {
         let address: Multiaddr = address
@@ -237,6 +318,10 @@ impl TransportManagerHandle {
             return Err(ImmediateDialError::TriedToDialSelf);
         }
 
+        if self.is_banned(peer) {
+            return Err(ImmediateDialError::PeerBanned);
+        }
+
         {
             match self.peers.read().get(peer) {
                 Some(PeerContext {
@@ -279,6 +364,27 @@ impl TransportManagerHandle {
             })
     }
 
+    /// Dial peer using `PeerId` unless automatic dials are suppressed.
+    ///
+    /// Automatic dials are suppressed when the number of established connections has reached
+    /// the high water mark of the target connections and are resumed when it drops below
+    /// the low water mark, see [`ConnectionLimitsConfig::target_connections()`].
+    ///
+    /// [`ConnectionLimitsConfig::target_connections()`]:
+    /// crate::transport::manager::limits::ConnectionLimitsConfig::target_connections
+    pub fn dial_automatic(&self, peer: &PeerId) -> Result<(), ImmediateDialError> {
+        if self.dial_throttled.load(Ordering::Acquire) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?peer,
+                "target connections reached, suppressing automatic dial",
+            );
+            return Err(ImmediateDialError::DialThrottled);
+        }
+
+        self.dial(peer)
+    }
+
     /// Dial peer using `Multiaddr`.
     ///
     /// Returns an error if address it not valid.
@@ -287,6 +393,12 @@ impl TransportManagerHandle {
             return Err(ImmediateDialError::PeerIdMissing);
         }
 
+        if let Some(peer) = PeerId::try_from_multiaddr(&address) {
+            if self.is_banned(&peer) {
+                return Err(ImmediateDialError::PeerBanned);
+            }
+        }
+
         self.cmd_tx
             .try_send(InnerTransportManagerCommand::DialAddress { address })
             .map_err(|error| match error {
@@ -305,16 +417,35 @@ pub struct TransportHandle {
     pub next_substream_id: Arc<AtomicUsize>,
     pub bandwidth_sink: BandwidthSink,
     pub executor: Arc<dyn Executor>,
+    pub(crate) substream_registry: Option<SubstreamRegistry>,
+    pub(crate) negotiation_observer: Option<NegotiationObserver>,
+    pub(crate) negotiation_cache: Option<NegotiationCache>,
+    pub(crate) buffer_budget: Option<BufferBudget>,
+    pub(crate) ip_filter: IpFilterConfig,
+    pub(crate) substream_rate_limit: Option<SubstreamRateLimit>,
+    #[cfg(feature = "traffic-tap")]
+    pub(crate) traffic_taps: HashMap<ProtocolName, TrafficTap>,
 }
 
 impl TransportHandle {
+    #[cfg_attr(not(feature = "traffic-tap"), allow(clippy::let_and_return))]
     pub fn protocol_set(&self, connection_id: ConnectionId) -> ProtocolSet {
-        ProtocolSet::new(
+        let protocol_set = ProtocolSet::new(
             connection_id,
             self.tx.clone(),
             self.next_substream_id.clone(),
             self.protocols.clone(),
         )
+        .with_substream_registry(self.substream_registry.clone())
+        .with_negotiation_observer(self.negotiation_observer.clone())
+        .with_negotiation_cache(self.negotiation_cache.clone())
+        .with_buffer_budget(self.buffer_budget.clone())
+        .with_substream_rate_limit(self.substream_rate_limit);
+
+        #[cfg(feature = "traffic-tap")]
+        let protocol_set = protocol_set.with_traffic_taps(self.traffic_taps.clone());
+
+        protocol_set
     }
 
     /// Get next connection ID.
@@ -323,6 +454,11 @@ impl TransportHandle {
 
         ConnectionId::from(connection_id)
     }
+
+    /// Check if any of the installed protocols is latency-sensitive.
+    pub(crate) fn has_latency_sensitive_protocol(&self) -> bool {
+        self.protocols.values().any(|context| context.latency_sensitive)
+    }
 }
 
 #[cfg(test)]
@@ -347,6 +483,8 @@ mod tests {
                 supported_transport: HashSet::new(),
                 listen_addresses: Default::default(),
                 public_addresses: PublicAddresses::new(local_peer_id),
+                reputation: Arc::new(RwLock::new(Reputation::new(Default::default()))),
+                dial_throttled: Default::default(),
             },
             cmd_rx,
         )
@@ -364,6 +502,22 @@ mod tests {
         assert!(handle.supported_transport(&address));
     }
 
+    #[test]
+    fn onion3_supported_only_with_proxy() {
+        let (mut handle, _rx) = make_transport_manager_handle();
+        handle.supported_transport.insert(SupportedTransport::Tcp);
+
+        let address: Multiaddr =
+            "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234\
+            /p2p/12D3KooWKrUnV42yDR7G6DewmgHtFaVCJWLjQRi2G9t5eJD3BvTy"
+                .parse()
+                .unwrap();
+        assert!(!handle.supported_transport(&address));
+
+        handle.supported_transport.insert(SupportedTransport::Onion3);
+        assert!(handle.supported_transport(&address));
+    }
+
     #[cfg(feature = "websocket")]
     #[tokio::test]
     async fn websocket_supported() {
@@ -597,6 +751,36 @@ mod tests {
         assert!(rx.try_recv().is_err());
     }
 
+    #[tokio::test]
+    async fn banned_peer_is_not_dialed() {
+        let (mut handle, mut rx) = make_transport_manager_handle();
+        handle.supported_transport.insert(SupportedTransport::Tcp);
+
+        let peer = PeerId::random();
+        let address = Multiaddr::empty()
+            .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Tcp(8888))
+            .with(Protocol::P2p(Multihash::from(peer)));
+        handle.add_known_address(&peer, std::iter::once(address.clone()));
+
+        assert!(!handle.report_peer(peer, -500));
+        assert!(handle.report_peer(peer, -500));
+        assert!(handle.is_banned(&peer));
+
+        // transport manager is told to disconnect the peer
+        match rx.try_recv() {
+            Ok(InnerTransportManagerCommand::BanPeer { peer: banned }) => assert_eq!(banned, peer),
+            _ => panic!("invalid command"),
+        }
+
+        assert_eq!(handle.dial(&peer), Err(ImmediateDialError::PeerBanned));
+        assert_eq!(
+            handle.dial_address(address),
+            Err(ImmediateDialError::PeerBanned)
+        );
+        assert!(rx.try_recv().is_err());
+    }
+
     #[test]
     fn is_local_address() {
         let (cmd_tx, _cmd_rx) = channel(64);
@@ -617,6 +801,8 @@ mod tests {
             supported_transport: HashSet::new(),
             listen_addresses,
             public_addresses: PublicAddresses::new(local_peer_id),
+            reputation: Arc::new(RwLock::new(Reputation::new(Default::default()))),
+            dial_throttled: Default::default(),
         };
 
         // local addresses
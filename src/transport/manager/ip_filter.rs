@@ -0,0 +1,261 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! IP range based allow and deny lists for the transport manager.
+
+use multiaddr::{Multiaddr, Protocol};
+
+use std::{fmt, net::IpAddr, str::FromStr};
+
+/// IP network in CIDR notation, such as `10.0.0.0/8` or `fd00::/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpNetwork {
+    /// Network address.
+    address: IpAddr,
+
+    /// Prefix length.
+    prefix: u8,
+}
+
+/// Error returned when an [`IpNetwork`] cannot be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum IpNetworkError {
+    /// The address part of the network is not a valid IP address.
+    #[error("Invalid IP address")]
+    InvalidAddress,
+
+    /// The prefix length is not valid for the address family.
+    #[error("Invalid prefix length")]
+    InvalidPrefix,
+}
+
+impl IpNetwork {
+    /// Create new [`IpNetwork`] from `address` and prefix length.
+    ///
+    /// Host bits of `address` are ignored.
+    pub fn new(address: IpAddr, prefix: u8) -> Result<Self, IpNetworkError> {
+        let max_prefix = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+
+        if prefix > max_prefix {
+            return Err(IpNetworkError::InvalidPrefix);
+        }
+
+        Ok(Self { address, prefix })
+    }
+
+    /// Check if `address` belongs to the network.
+    ///
+    /// IPv4-mapped IPv6 addresses are matched against IPv4 networks.
+    pub fn contains(&self, address: &IpAddr) -> bool {
+        let address = match address {
+            IpAddr::V6(address) =>
+                address.to_ipv4_mapped().map_or(IpAddr::V6(*address), IpAddr::V4),
+            address => *address,
+        };
+
+        match (self.address, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = IpNetworkError;
+
+    /// Parse network from CIDR notation.
+    ///
+    /// An address without a prefix length is treated as a single host.
+    fn from_str(network: &str) -> Result<Self, Self::Err> {
+        let (address, prefix) = match network.split_once('/') {
+            Some((address, prefix)) => (
+                address,
+                Some(prefix.parse::<u8>().map_err(|_| IpNetworkError::InvalidPrefix)?),
+            ),
+            None => (network, None),
+        };
+
+        let address = address.parse::<IpAddr>().map_err(|_| IpNetworkError::InvalidAddress)?;
+        let prefix = prefix.unwrap_or(match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        });
+
+        Self::new(address, prefix)
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix)
+    }
+}
+
+/// Configuration for the IP filter.
+///
+/// The filter is applied to the remote address of inbound connections before they're accepted
+/// and to the addresses of outbound connections before they're dialed.
+///
+/// An address is rejected if it belongs to any of the denied networks. If any allowed networks
+/// are configured, an address is also rejected unless it belongs to one of them. Denied networks
+/// take precedence over allowed networks.
+///
+/// Addresses which don't contain an IP address, such as `/dns` or `/unix` addresses, are not
+/// filtered.
+#[derive(Debug, Clone, Default)]
+pub struct IpFilterConfig {
+    /// Allowed networks.
+    allowed: Vec<IpNetwork>,
+
+    /// Denied networks.
+    denied: Vec<IpNetwork>,
+}
+
+impl IpFilterConfig {
+    /// Allow connections to and from `network`.
+    ///
+    /// Once a network has been allowed, connections to and from networks that have not been
+    /// allowed are rejected.
+    pub fn allow(mut self, network: IpNetwork) -> Self {
+        self.allowed.push(network);
+        self
+    }
+
+    /// Deny connections to and from `network`.
+    pub fn deny(mut self, network: IpNetwork) -> Self {
+        self.denied.push(network);
+        self
+    }
+
+    /// Check if the filter has no allowed or denied networks.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.allowed.is_empty() && self.denied.is_empty()
+    }
+
+    /// Check if connections to and from `address` are allowed.
+    pub fn is_allowed(&self, address: &IpAddr) -> bool {
+        if self.denied.iter().any(|network| network.contains(address)) {
+            return false;
+        }
+
+        self.allowed.is_empty() || self.allowed.iter().any(|network| network.contains(address))
+    }
+
+    /// Check if connections to and from `address` are allowed.
+    ///
+    /// Addresses without an IP address are always allowed.
+    pub fn is_address_allowed(&self, address: &Multiaddr) -> bool {
+        match address.iter().next() {
+            Some(Protocol::Ip4(address)) => self.is_allowed(&IpAddr::V4(address)),
+            Some(Protocol::Ip6(address)) => self.is_allowed(&IpAddr::V6(address)),
+            Some(Protocol::Ip6zone(_)) => match address.iter().nth(1) {
+                Some(Protocol::Ip6(address)) => self.is_allowed(&IpAddr::V6(address)),
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_network() {
+        assert_eq!(
+            "10.0.0.0/8".parse::<IpNetwork>(),
+            IpNetwork::new("10.0.0.0".parse().unwrap(), 8)
+        );
+        assert_eq!(
+            "fd00::/8".parse::<IpNetwork>(),
+            IpNetwork::new("fd00::".parse().unwrap(), 8)
+        );
+        assert_eq!(
+            "192.168.1.1".parse::<IpNetwork>(),
+            IpNetwork::new("192.168.1.1".parse().unwrap(), 32)
+        );
+        assert_eq!(
+            "10.0.0.0/33".parse::<IpNetwork>(),
+            Err(IpNetworkError::InvalidPrefix)
+        );
+        assert_eq!(
+            "::/129".parse::<IpNetwork>(),
+            Err(IpNetworkError::InvalidPrefix)
+        );
+        assert_eq!(
+            "10.0.0/8".parse::<IpNetwork>(),
+            Err(IpNetworkError::InvalidAddress)
+        );
+    }
+
+    #[test]
+    fn network_contains_address() {
+        let network = "10.1.0.0/16".parse::<IpNetwork>().unwrap();
+
+        assert!(network.contains(&"10.1.2.3".parse().unwrap()));
+        assert!(network.contains(&"::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!network.contains(&"10.2.0.1".parse().unwrap()));
+        assert!(!network.contains(&"::1".parse().unwrap()));
+
+        let network = "2001:db8::/32".parse::<IpNetwork>().unwrap();
+
+        assert!(network.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(!network.contains(&"2001:db9::1".parse().unwrap()));
+        assert!(!network.contains(&"10.1.2.3".parse().unwrap()));
+
+        let network = "0.0.0.0/0".parse::<IpNetwork>().unwrap();
+        assert!(network.contains(&"1.2.3.4".parse().unwrap()));
+    }
+
+    #[test]
+    fn deny_takes_precedence() {
+        let filter = IpFilterConfig::default()
+            .allow("10.0.0.0/8".parse().unwrap())
+            .deny("10.1.0.0/16".parse().unwrap());
+
+        assert!(filter.is_allowed(&"10.2.0.1".parse().unwrap()));
+        assert!(!filter.is_allowed(&"10.1.0.1".parse().unwrap()));
+        assert!(!filter.is_allowed(&"192.168.0.1".parse().unwrap()));
+
+        assert!(filter.is_address_allowed(&"/ip4/10.2.0.1/tcp/8888".parse().unwrap()));
+        assert!(!filter.is_address_allowed(&"/ip4/10.1.0.1/tcp/8888".parse().unwrap()));
+        assert!(!filter.is_address_allowed(&"/ip6/::1/tcp/8888".parse().unwrap()));
+        assert!(filter.is_address_allowed(&"/dns/example.com/tcp/8888".parse().unwrap()));
+    }
+
+    #[test]
+    fn everything_allowed_by_default() {
+        let filter = IpFilterConfig::default();
+
+        assert!(filter.is_allowed(&"10.2.0.1".parse().unwrap()));
+        assert!(filter.is_allowed(&"::1".parse().unwrap()));
+    }
+}
@@ -22,7 +22,13 @@
 
 use crate::types::ConnectionId;
 
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 /// Configuration for the connection limits.
 #[derive(Debug, Clone, Default)]
@@ -31,6 +37,8 @@ pub struct ConnectionLimitsConfig {
     max_incoming_connections: Option<usize>,
     /// Maximum number of outgoing connections that can be established.
     max_outgoing_connections: Option<usize>,
+    /// Low and high water marks for the number of established connections.
+    target_connections: Option<(usize, usize)>,
 }
 
 impl ConnectionLimitsConfig {
@@ -45,6 +53,21 @@ impl ConnectionLimitsConfig {
         self.max_outgoing_connections = limit;
         self
     }
+
+    /// Configures the target number of established connections.
+    ///
+    /// Once the node has `high` established connections, automatic dials, such as the ones made
+    /// by Kademlia when discovering new peers, are suppressed until the number of established
+    /// connections drops below `low`. Dials requested explicitly always proceed.
+    pub fn target_connections(mut self, low: usize, high: usize) -> Self {
+        assert!(
+            low <= high,
+            "low water mark must not exceed high water mark"
+        );
+
+        self.target_connections = Some((low, high));
+        self
+    }
 }
 
 /// Error type for connection limits.
@@ -66,6 +89,12 @@ pub struct ConnectionLimits {
     incoming_connections: HashSet<ConnectionId>,
     /// Established outgoing connections.
     outgoing_connections: HashSet<ConnectionId>,
+
+    /// Established connections, tracked only if target connections are configured.
+    connections: HashSet<ConnectionId>,
+
+    /// Are automatic dials suppressed.
+    dial_throttled: Arc<AtomicBool>,
 }
 
 impl ConnectionLimits {
@@ -78,9 +107,19 @@ impl ConnectionLimits {
             config,
             incoming_connections: HashSet::with_capacity(max_incoming_connections),
             outgoing_connections: HashSet::with_capacity(max_outgoing_connections),
+            connections: HashSet::new(),
+            dial_throttled: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Get the flag indicating whether automatic dials are suppressed.
+    ///
+    /// The flag is set when the number of established connections reaches the high water mark
+    /// and cleared when it drops below the low water mark.
+    pub fn dial_throttle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.dial_throttled)
+    }
+
     /// Called when dialing an address.
     ///
     /// Returns the number of outgoing connections permitted to be established.
@@ -140,6 +179,14 @@ impl ConnectionLimits {
             self.outgoing_connections.insert(connection_id);
         }
 
+        if let Some((_, high)) = self.config.target_connections {
+            self.connections.insert(connection_id);
+
+            if self.connections.len() >= high {
+                self.dial_throttled.store(true, Ordering::Release);
+            }
+        }
+
         Ok(())
     }
 
@@ -147,6 +194,12 @@ impl ConnectionLimits {
     pub fn on_connection_closed(&mut self, connection_id: ConnectionId) {
         self.incoming_connections.remove(&connection_id);
         self.outgoing_connections.remove(&connection_id);
+
+        if let Some((low, _)) = self.config.target_connections {
+            if self.connections.remove(&connection_id) && self.connections.len() < low {
+                self.dial_throttled.store(false, Ordering::Release);
+            }
+        }
     }
 }
 
@@ -208,4 +261,34 @@ mod tests {
         assert_eq!(limits.incoming_connections.len(), 2);
         assert_eq!(limits.outgoing_connections.len(), 1);
     }
+
+    #[test]
+    fn target_connections() {
+        let config = ConnectionLimitsConfig::default().target_connections(2, 3);
+        let mut limits = ConnectionLimits::new(config);
+        let throttle = limits.dial_throttle();
+
+        let connections = (0..3).map(|_| ConnectionId::random()).collect::<Vec<_>>();
+
+        for connection_id in &connections {
+            assert!(!throttle.load(Ordering::Acquire));
+            assert!(limits.on_connection_established(*connection_id, false).is_ok());
+        }
+
+        // high water mark reached
+        assert!(throttle.load(Ordering::Acquire));
+
+        // still above the low water mark
+        limits.on_connection_closed(connections[0]);
+        assert!(throttle.load(Ordering::Acquire));
+
+        // reaching the high water mark again doesn't change anything
+        let connection_id = ConnectionId::random();
+        assert!(limits.on_connection_established(connection_id, true).is_ok());
+        assert!(throttle.load(Ordering::Acquire));
+
+        limits.on_connection_closed(connection_id);
+        limits.on_connection_closed(connections[1]);
+        assert!(!throttle.load(Ordering::Acquire));
+    }
 }
@@ -21,23 +21,32 @@
 use crate::{
     addresses::PublicAddresses,
     codec::ProtocolCodec,
-    crypto::ed25519::Keypair,
+    crypto::Keypair,
     error::{AddressError, DialError, Error},
     executor::Executor,
-    protocol::{InnerTransportEvent, TransportService},
+    protocol::{
+        libp2p::relay::RelayClientHandle, ConnectionHandle, InboundSubstreamFilter,
+        InboundSubstreamOrder, InnerTransportEvent, NegotiationCache, NegotiationObserver,
+        SubstreamPriority, TransportService,
+    },
+    substream::{BufferBudget, SubstreamInfo, SubstreamRateLimit, SubstreamRegistry},
     transport::{
+        is_relayed_address,
         manager::{
             address::{AddressRecord, AddressStore},
             handle::InnerTransportManagerCommand,
             types::{PeerContext, PeerState},
         },
-        Endpoint, Transport, TransportEvent,
+        CloseReason, DialProgress, DialProgressSink, Endpoint, Transport, TransportEvent,
     },
     types::{protocol::ProtocolName, ConnectionId},
     BandwidthSink, PeerId,
 };
 
-use futures::{Stream, StreamExt};
+#[cfg(feature = "traffic-tap")]
+use crate::substream::TrafficTap;
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
 use indexmap::IndexMap;
 use multiaddr::{Multiaddr, Protocol};
 use multihash::Multihash;
@@ -45,22 +54,25 @@ use parking_lot::RwLock;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    any::{Any, TypeId},
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     pin::Pin,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 pub use handle::{TransportHandle, TransportManagerHandle};
-pub use types::SupportedTransport;
+pub use types::{SupportedTransport, TransportStats};
 
 mod address;
+pub mod ip_filter;
 pub mod limits;
-mod types;
+pub mod reputation;
+pub(crate) mod types;
 
 pub(crate) mod handle;
 
@@ -88,8 +100,35 @@ enum ConnectionEstablishedResult {
     Reject,
 }
 
+/// Policy for handling a new connection to a peer the local node is already connected to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateConnectionPolicy {
+    /// Keep the existing connection and use the new connection as a secondary connection.
+    ///
+    /// If the peer already has a secondary connection, the new connection is rejected.
+    #[default]
+    KeepBoth,
+
+    /// Keep the existing connection and reject the new connection.
+    KeepOldest,
+
+    /// Accept the new connection and close the existing connections of the peer.
+    ///
+    /// The closed connections are reported as closed to protocols as usual.
+    KeepNewest,
+}
+
 /// [`crate::transport::manager::TransportManager`] events.
 pub enum TransportManagerEvent {
+    /// Connection has been established and reported to protocols.
+    ConnectionEstablished {
+        /// Connection ID.
+        connection: ConnectionId,
+
+        /// Handle to the connection.
+        handle: ConnectionHandle,
+    },
+
     /// Connection closed to remote peer.
     ConnectionClosed {
         /// Peer ID.
@@ -98,6 +137,18 @@ pub enum TransportManagerEvent {
         /// Connection ID.
         connection: ConnectionId,
     },
+
+    /// Remote peer of an open connection has changed its address.
+    ConnectionAddressChanged {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Connection ID.
+        connection: ConnectionId,
+
+        /// New address of the remote peer.
+        address: Multiaddr,
+    },
 }
 
 // Protocol context.
@@ -111,6 +162,24 @@ pub struct ProtocolContext {
 
     /// Fallback names for the protocol.
     pub fallback_names: Vec<ProtocolName>,
+
+    /// Idle timeout of the substreams of the protocol.
+    ///
+    /// If set, open substreams of the protocol keep the connection open only until they have been
+    /// idle for the duration. Otherwise they keep the connection open for as long as they're open.
+    pub idle_timeout: Option<Duration>,
+
+    /// Filter for inbound substreams of the protocol.
+    pub inbound_filter: Option<InboundSubstreamFilter>,
+
+    /// Priority of the substreams of the protocol.
+    pub priority: SubstreamPriority,
+
+    /// Is the protocol latency-sensitive.
+    pub latency_sensitive: bool,
+
+    /// Order in which queued inbound substreams are delivered to the protocol.
+    pub inbound_order: InboundSubstreamOrder,
 }
 
 impl ProtocolContext {
@@ -119,11 +188,17 @@ impl ProtocolContext {
         codec: ProtocolCodec,
         tx: Sender<InnerTransportEvent>,
         fallback_names: Vec<ProtocolName>,
+        idle_timeout: Option<Duration>,
     ) -> Self {
         Self {
             tx,
             codec,
             fallback_names,
+            idle_timeout,
+            inbound_filter: None,
+            priority: SubstreamPriority::Normal,
+            latency_sensitive: false,
+            inbound_order: InboundSubstreamOrder::Fifo,
         }
     }
 }
@@ -167,6 +242,20 @@ impl TransportContext {
     ) {
         assert!(self.transports.insert(name, transport).is_none());
     }
+
+    /// Ask the transport that owns `connection_id` to abort the dial.
+    ///
+    /// Returns `false` if none of the transports aborted the dial.
+    pub fn abort(&mut self, connection_id: ConnectionId) -> bool {
+        self.transports.values_mut().any(|transport| transport.abort(connection_id))
+    }
+
+    /// Get the round-trip time estimate of `connection_id` from the transport that owns it.
+    pub fn connection_rtt(&self, connection_id: ConnectionId) -> Option<Duration> {
+        self.transports
+            .values()
+            .find_map(|transport| transport.connection_rtt(connection_id))
+    }
 }
 
 impl Stream for TransportContext {
@@ -253,8 +342,101 @@ pub struct TransportManager {
     /// Connection limits.
     connection_limits: limits::ConnectionLimits,
 
+    /// Peer reputation.
+    reputation: Arc<RwLock<reputation::Reputation>>,
+
+    /// IP filter applied to inbound and outbound connections.
+    ip_filter: ip_filter::IpFilterConfig,
+
+    /// Application data attached to open connections.
+    connection_data: HashMap<ConnectionId, HashMap<TypeId, Box<dyn Any + Send>>>,
+
+    /// Policy for handling additional connections to connected peers.
+    duplicate_connection_policy: DuplicateConnectionPolicy,
+
+    /// Are inbound connections accepted.
+    accepting: bool,
+
+    /// Handles to established connections, used to close replaced connections.
+    connection_handles: HashMap<ConnectionId, ConnectionHandle>,
+
+    /// Connections which should be closed once their handle is received.
+    pending_closes: HashSet<ConnectionId>,
+
+    /// Secondary connections replaced by newer connections which are not tracked in
+    /// the peer state anymore.
+    replaced_connections: HashSet<ConnectionId>,
+
+    /// Registry of open substreams, if enabled.
+    substream_registry: Option<SubstreamRegistry>,
+
+    /// Observer of `multistream-select` negotiations, if enabled.
+    negotiation_observer: Option<NegotiationObserver>,
+
+    /// Cache of protocols negotiated with each peer, if optimistic negotiation is enabled.
+    negotiation_cache: Option<NegotiationCache>,
+
+    /// Memory budget for data buffered by substreams, if enabled.
+    buffer_budget: Option<BufferBudget>,
+
+    /// Rate limit of substreams opened by remote peers over each connection, if enabled.
+    substream_rate_limit: Option<SubstreamRateLimit>,
+
+    /// Traffic taps of the installed protocols.
+    #[cfg(feature = "traffic-tap")]
+    traffic_taps: HashMap<ProtocolName, TrafficTap>,
+
     /// Opening connections errors.
     opening_errors: HashMap<ConnectionId, Vec<(Multiaddr, DialError)>>,
+
+    /// Addresses that won the race of a multi-address dial and are being negotiated.
+    opened_dials: HashMap<ConnectionId, Multiaddr>,
+
+    /// Aborted dials of transports which report the outcome of the dial.
+    aborted_dials: HashSet<ConnectionId>,
+
+    /// Handle of the circuit relay client, if enabled.
+    relay_client: Option<RelayClientHandle>,
+
+    /// Peers dialed by the user which are dialed through the relays if the dial fails.
+    relay_fallback_dials: HashSet<PeerId>,
+
+    /// Dial failures held back until the dial through the relays has finished.
+    ///
+    /// Resolves to the failure event if the peer couldn't be reached through the relays either.
+    relayed_dials: FuturesUnordered<BoxFuture<'static, Option<TransportEvent>>>,
+
+    /// Pending events to be returned to the caller.
+    pending_events: VecDeque<TransportEvent>,
+
+    /// Listener and connection statistics of the installed transports.
+    transport_stats: HashMap<SupportedTransport, TransportStats>,
+
+    /// Transports of the open connections.
+    connection_transports: HashMap<ConnectionId, SupportedTransport>,
+
+    /// Peers pinned to a transport.
+    pinned_transports: HashMap<PeerId, SupportedTransport>,
+}
+
+/// Get the transport used for dialing `address`.
+fn address_transport(address: &Multiaddr) -> SupportedTransport {
+    #[cfg(unix)]
+    if std::matches!(address.iter().next(), Some(Protocol::Unix(_))) {
+        return SupportedTransport::Unix;
+    }
+
+    #[cfg(feature = "quic")]
+    if address.iter().any(|p| std::matches!(&p, Protocol::QuicV1)) {
+        return SupportedTransport::Quic;
+    }
+
+    #[cfg(feature = "websocket")]
+    if address.iter().any(|p| std::matches!(&p, Protocol::Ws(_) | Protocol::Wss(_))) {
+        return SupportedTransport::WebSocket;
+    }
+
+    SupportedTransport::Tcp
 }
 
 impl TransportManager {
@@ -266,13 +448,16 @@ impl TransportManager {
         bandwidth_sink: BandwidthSink,
         max_parallel_dials: usize,
         connection_limits_config: limits::ConnectionLimitsConfig,
+        reputation_config: reputation::ReputationConfig,
     ) -> (Self, TransportManagerHandle) {
-        let local_peer_id = PeerId::from_public_key(&keypair.public().into());
+        let local_peer_id = keypair.to_peer_id();
         let peers = Arc::new(RwLock::new(HashMap::new()));
+        let reputation = Arc::new(RwLock::new(reputation::Reputation::new(reputation_config)));
         let (cmd_tx, cmd_rx) = channel(256);
         let (event_tx, event_rx) = channel(256);
         let listen_addresses = Arc::new(RwLock::new(HashSet::new()));
         let public_addresses = PublicAddresses::new(local_peer_id);
+        let connection_limits = limits::ConnectionLimits::new(connection_limits_config);
         let handle = TransportManagerHandle::new(
             local_peer_id,
             peers.clone(),
@@ -280,11 +465,28 @@ impl TransportManager {
             supported_transports,
             listen_addresses.clone(),
             public_addresses.clone(),
+            reputation.clone(),
+            connection_limits.dial_throttle(),
         );
 
         (
             Self {
                 peers,
+                reputation,
+                ip_filter: ip_filter::IpFilterConfig::default(),
+                connection_data: HashMap::new(),
+                duplicate_connection_policy: DuplicateConnectionPolicy::default(),
+                accepting: true,
+                connection_handles: HashMap::new(),
+                pending_closes: HashSet::new(),
+                replaced_connections: HashSet::new(),
+                substream_registry: None,
+                negotiation_observer: None,
+                negotiation_cache: None,
+                buffer_budget: None,
+                substream_rate_limit: None,
+                #[cfg(feature = "traffic-tap")]
+                traffic_taps: HashMap::new(),
                 cmd_rx,
                 keypair,
                 event_tx,
@@ -301,8 +503,17 @@ impl TransportManager {
                 pending_connections: HashMap::new(),
                 next_substream_id: Arc::new(AtomicUsize::new(0usize)),
                 next_connection_id: Arc::new(AtomicUsize::new(0usize)),
-                connection_limits: limits::ConnectionLimits::new(connection_limits_config),
+                connection_limits,
                 opening_errors: HashMap::new(),
+                opened_dials: HashMap::new(),
+                aborted_dials: HashSet::new(),
+                relay_client: None,
+                relay_fallback_dials: HashSet::new(),
+                relayed_dials: FuturesUnordered::new(),
+                pending_events: VecDeque::new(),
+                transport_stats: HashMap::new(),
+                connection_transports: HashMap::new(),
+                pinned_transports: HashMap::new(),
             },
             handle,
         )
@@ -318,6 +529,11 @@ impl TransportManager {
         self.transports.keys()
     }
 
+    /// Check if `address` can be dialed using one of the installed transports.
+    pub fn is_dialable(&self, address: &Multiaddr) -> bool {
+        self.transport_manager_handle.supported_transport(address)
+    }
+
     /// Get next connection ID.
     fn next_connection_id(&mut self) -> ConnectionId {
         let connection_id = self.next_connection_id.fetch_add(1usize, Ordering::Relaxed);
@@ -329,12 +545,17 @@ impl TransportManager {
     ///
     /// This allocates new context for the protocol and returns a handle
     /// which the protocol can use the interact with the transport subsystem.
+    ///
+    /// If `substream_keep_alive` is `false`, open substreams of the protocol keep the connection
+    /// open only while there is traffic on them: a substream that has been idle for
+    /// `keep_alive_timeout` no longer prevents the connection from being closed.
     pub fn register_protocol(
         &mut self,
         protocol: ProtocolName,
         fallback_names: Vec<ProtocolName>,
         codec: ProtocolCodec,
         keep_alive_timeout: Duration,
+        substream_keep_alive: bool,
     ) -> TransportService {
         assert!(!self.protocol_names.contains(&protocol));
 
@@ -355,7 +576,12 @@ impl TransportManager {
 
         self.protocols.insert(
             protocol.clone(),
-            ProtocolContext::new(codec, sender, fallback_names.clone()),
+            ProtocolContext::new(
+                codec,
+                sender,
+                fallback_names.clone(),
+                (!substream_keep_alive).then_some(keep_alive_timeout),
+            ),
         );
         self.protocol_names.insert(protocol);
         self.protocol_names.extend(fallback_names);
@@ -363,6 +589,221 @@ impl TransportManager {
         service
     }
 
+    /// Set the IP filter applied to inbound connections and outbound dials.
+    pub fn set_ip_filter(&mut self, ip_filter: ip_filter::IpFilterConfig) {
+        self.ip_filter = ip_filter;
+    }
+
+    /// Set the policy for handling additional connections to connected peers.
+    pub fn set_duplicate_connection_policy(&mut self, policy: DuplicateConnectionPolicy) {
+        self.duplicate_connection_policy = policy;
+    }
+
+    /// Enable or disable accepting inbound connections.
+    ///
+    /// While disabled, inbound connections are rejected right after the transport has accepted
+    /// them at the socket level, before they're upgraded.
+    pub fn set_accepting(&mut self, accepting: bool) {
+        tracing::debug!(target: LOG_TARGET, ?accepting, "set accepting");
+
+        self.accepting = accepting;
+    }
+
+    /// Check if inbound connections are accepted.
+    pub fn is_accepting(&self) -> bool {
+        self.accepting
+    }
+
+    /// Track substreams opened over connections in `registry`.
+    ///
+    /// Must be called before transport handles are acquired.
+    pub(crate) fn set_substream_registry(&mut self, registry: SubstreamRegistry) {
+        self.substream_registry = Some(registry);
+    }
+
+    /// Dial peers through the relays of the circuit relay client if their dial fails.
+    ///
+    /// Only dials started with [`TransportManager::fall_back_to_relays()`] are retried through the
+    /// relays.
+    pub(crate) fn set_relay_client(&mut self, handle: RelayClientHandle) {
+        self.relay_client = Some(handle);
+    }
+
+    /// Dial `peer` through the relays if the ongoing dial to it fails.
+    ///
+    /// The failure of the dial is reported only after the dial through the relays has failed as
+    /// well. If it succeeds, the relayed connection is reported instead of the failure.
+    pub(crate) fn fall_back_to_relays(&mut self, peer: PeerId) {
+        if self.relay_client.is_some() {
+            self.relay_fallback_dials.insert(peer);
+        }
+    }
+
+    /// Hold back dial failure `event` of `peer` if the peer is dialed through the relays instead.
+    ///
+    /// Returns the event if it must be reported now.
+    fn on_relay_fallback(&mut self, peer: PeerId, event: TransportEvent) -> Option<TransportEvent> {
+        if !self.relay_fallback_dials.remove(&peer) {
+            return Some(event);
+        }
+        let Some(relay_client) = &self.relay_client else {
+            return Some(event);
+        };
+
+        tracing::trace!(target: LOG_TARGET, ?peer, "dial failed, dial peer through relays");
+
+        let result = relay_client.dial(peer);
+        self.relayed_dials.push(Box::pin(async move {
+            match result.await {
+                Ok(true) => None,
+                _ => Some(event),
+            }
+        }));
+
+        None
+    }
+
+    /// Report `multistream-select` negotiations of connections to `observer`.
+    ///
+    /// Must be called before transport handles are acquired.
+    pub(crate) fn set_negotiation_observer(&mut self, observer: NegotiationObserver) {
+        self.negotiation_observer = Some(observer);
+    }
+
+    /// Negotiate outbound substreams optimistically for protocols already negotiated with the
+    /// peer.
+    ///
+    /// Must be called before transport handles are acquired.
+    pub(crate) fn set_negotiation_cache(&mut self, cache: NegotiationCache) {
+        self.negotiation_cache = Some(cache);
+    }
+
+    /// Account data buffered by substreams against `budget`.
+    ///
+    /// Must be called before transport handles are acquired.
+    pub(crate) fn set_buffer_budget(&mut self, budget: BufferBudget) {
+        self.buffer_budget = Some(budget);
+    }
+
+    /// Limit the rate at which remote peers may open substreams over each connection.
+    ///
+    /// Must be called before transport handles are acquired.
+    pub(crate) fn set_substream_rate_limit(&mut self, limit: SubstreamRateLimit) {
+        self.substream_rate_limit = Some(limit);
+    }
+
+    /// Get the memory budget for data buffered by substreams, if enabled.
+    pub fn buffer_budget(&self) -> Option<BufferBudget> {
+        self.buffer_budget.clone()
+    }
+
+    /// Copy the frames of `protocol` to `tap`.
+    ///
+    /// Must be called before transport handles are acquired.
+    #[cfg(feature = "traffic-tap")]
+    pub(crate) fn set_traffic_tap(&mut self, protocol: ProtocolName, tap: TrafficTap) {
+        self.traffic_taps.insert(protocol, tap);
+    }
+
+    /// Get information about open substreams.
+    ///
+    /// Returns an empty list unless the substream registry has been enabled.
+    pub fn open_substreams(&self) -> Vec<SubstreamInfo> {
+        self.substream_registry
+            .as_ref()
+            .map_or_else(Vec::new, |registry| registry.substreams())
+    }
+
+    /// Attach `data` to an open connection.
+    ///
+    /// Any previous value of the same type is replaced. The data is dropped when the connection
+    /// is closed.
+    pub fn set_connection_data<T: Send + 'static>(
+        &mut self,
+        connection_id: ConnectionId,
+        data: T,
+    ) -> crate::Result<()> {
+        self.connection_data
+            .get_mut(&connection_id)
+            .ok_or(Error::ConnectionDoesntExist(connection_id))?
+            .insert(TypeId::of::<T>(), Box::new(data));
+
+        Ok(())
+    }
+
+    /// Get data of type `T` attached to an open connection.
+    pub fn connection_data<T: Send + 'static>(&self, connection_id: ConnectionId) -> Option<&T> {
+        self.connection_data
+            .get(&connection_id)?
+            .get(&TypeId::of::<T>())
+            .and_then(|data| data.downcast_ref::<T>())
+    }
+
+    /// Drain an open connection.
+    ///
+    /// No new substreams can be opened over the connection and once its substreams have been
+    /// closed, or `deadline` is reached, the connection is closed.
+    pub fn drain_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        deadline: Instant,
+    ) -> crate::Result<()> {
+        let handle = self
+            .connection_handles
+            .get_mut(&connection_id)
+            .ok_or(Error::ConnectionDoesntExist(connection_id))?;
+
+        tracing::debug!(target: LOG_TARGET, ?connection_id, ?deadline, "drain connection");
+
+        handle.drain(deadline);
+        Ok(())
+    }
+
+    /// Get the round-trip time estimate of an open connection.
+    pub fn connection_rtt(&self, connection_id: ConnectionId) -> Option<Duration> {
+        self.transports.connection_rtt(connection_id)
+    }
+
+    /// Install synchronous filter for inbound substreams of `protocol`.
+    ///
+    /// Inbound substreams rejected by the filter are closed before they're reported to the
+    /// protocol.
+    pub fn set_inbound_substream_filter(
+        &mut self,
+        protocol: &ProtocolName,
+        filter: InboundSubstreamFilter,
+    ) {
+        self.protocols
+            .get_mut(protocol)
+            .expect("protocol to be registered")
+            .inbound_filter = Some(filter);
+    }
+
+    /// Set the priority of the substreams of `protocol`.
+    pub fn set_substream_priority(&mut self, protocol: &ProtocolName, priority: SubstreamPriority) {
+        self.protocols.get_mut(protocol).expect("protocol to be registered").priority = priority;
+    }
+
+    /// Mark `protocol` as latency-sensitive.
+    pub fn set_latency_sensitive(&mut self, protocol: &ProtocolName, latency_sensitive: bool) {
+        self.protocols
+            .get_mut(protocol)
+            .expect("protocol to be registered")
+            .latency_sensitive = latency_sensitive;
+    }
+
+    /// Set the order in which queued inbound substreams are delivered to `protocol`.
+    pub fn set_inbound_substream_order(
+        &mut self,
+        protocol: &ProtocolName,
+        order: InboundSubstreamOrder,
+    ) {
+        self.protocols
+            .get_mut(protocol)
+            .expect("protocol to be registered")
+            .inbound_order = order;
+    }
+
     /// Acquire `TransportHandle`.
     pub fn transport_handle(&self, executor: Arc<dyn Executor>) -> TransportHandle {
         TransportHandle {
@@ -373,6 +814,14 @@ impl TransportManager {
             bandwidth_sink: self.bandwidth_sink.clone(),
             next_substream_id: self.next_substream_id.clone(),
             next_connection_id: self.next_connection_id.clone(),
+            substream_registry: self.substream_registry.clone(),
+            negotiation_observer: self.negotiation_observer.clone(),
+            negotiation_cache: self.negotiation_cache.clone(),
+            buffer_budget: self.buffer_budget.clone(),
+            ip_filter: self.ip_filter.clone(),
+            substream_rate_limit: self.substream_rate_limit,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: self.traffic_taps.clone(),
         }
     }
 
@@ -386,6 +835,17 @@ impl TransportManager {
 
         self.transports.register_transport(name, transport);
         self.transport_manager_handle.register_transport(name);
+        self.transport_stats.entry(name).or_default();
+    }
+
+    /// Get the listener and connection statistics of the installed transports.
+    pub fn transport_stats(&self) -> HashMap<SupportedTransport, TransportStats> {
+        self.transport_stats.clone()
+    }
+
+    /// Get mutable access to the statistics of `transport`.
+    fn stats_mut(&mut self, transport: SupportedTransport) -> &mut TransportStats {
+        self.transport_stats.entry(transport).or_default()
     }
 
     /// Get the list of public addresses of the node.
@@ -393,10 +853,12 @@ impl TransportManager {
         self.public_addresses.clone()
     }
 
-    /// Register local listen address.
-    pub fn register_listen_address(&mut self, address: Multiaddr) {
+    /// Register local listen address of `transport`.
+    pub fn register_listen_address(&mut self, transport: SupportedTransport, address: Multiaddr) {
         assert!(!address.iter().any(|protocol| std::matches!(protocol, Protocol::P2p(_))));
 
+        self.stats_mut(transport).listeners += 1;
+
         let mut listen_addresses = self.listen_addresses.write();
 
         listen_addresses.insert(address.clone());
@@ -427,6 +889,9 @@ impl TransportManager {
         if peer == self.local_peer_id {
             return Err(Error::TriedToDialSelf);
         }
+        if self.reputation.read().is_banned(&peer) {
+            return Err(Error::PeerBanned(peer));
+        }
         let mut peers = self.peers.write();
 
         // if the peer is disconnected, return its context
@@ -481,9 +946,26 @@ impl TransportManager {
             return Ok(());
         }
 
+        let pinned = self.pinned_transports.get(&peer).copied();
         let mut records: HashMap<_, _> = addresses
-            .take(limit)
+            .take_matching(limit, |record| {
+                pinned.map_or(true, |pinned| address_transport(record.address()) == pinned)
+            })
             .into_iter()
+            .filter(|record| {
+                let allowed = self.ip_filter.is_address_allowed(record.address());
+
+                if !allowed {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        address = ?record.address(),
+                        "address denied by ip filter",
+                    );
+                }
+
+                allowed
+            })
             .map(|record| (record.address().clone(), record))
             .collect();
 
@@ -523,27 +1005,24 @@ impl TransportManager {
         let mut websocket = Vec::new();
         #[cfg(feature = "quic")]
         let mut quic = Vec::new();
+        #[cfg(unix)]
+        let mut unix = Vec::new();
         let mut tcp = Vec::new();
 
         for (address, record) in &mut records {
             record.set_connection_id(connection_id);
 
-            #[cfg(feature = "quic")]
-            if address.iter().any(|p| std::matches!(&p, Protocol::QuicV1)) {
-                quic.push(address.clone());
-                transports.insert(SupportedTransport::Quic);
-                continue;
-            }
-
-            #[cfg(feature = "websocket")]
-            if address.iter().any(|p| std::matches!(&p, Protocol::Ws(_) | Protocol::Wss(_))) {
-                websocket.push(address.clone());
-                transports.insert(SupportedTransport::WebSocket);
-                continue;
+            let transport = address_transport(address);
+            match transport {
+                #[cfg(unix)]
+                SupportedTransport::Unix => unix.push(address.clone()),
+                #[cfg(feature = "quic")]
+                SupportedTransport::Quic => quic.push(address.clone()),
+                #[cfg(feature = "websocket")]
+                SupportedTransport::WebSocket => websocket.push(address.clone()),
+                _ => tcp.push(address.clone()),
             }
-
-            tcp.push(address.clone());
-            transports.insert(SupportedTransport::Tcp);
+            transports.insert(transport);
         }
 
         peers.insert(
@@ -564,6 +1043,7 @@ impl TransportManager {
                 .get_mut(&SupportedTransport::Tcp)
                 .expect("transport to be supported")
                 .open(connection_id, tcp)?;
+            self.stats_mut(SupportedTransport::Tcp).dial_attempts += 1;
         }
 
         #[cfg(feature = "quic")]
@@ -572,6 +1052,7 @@ impl TransportManager {
                 .get_mut(&SupportedTransport::Quic)
                 .expect("transport to be supported")
                 .open(connection_id, quic)?;
+            self.stats_mut(SupportedTransport::Quic).dial_attempts += 1;
         }
 
         #[cfg(feature = "websocket")]
@@ -580,6 +1061,16 @@ impl TransportManager {
                 .get_mut(&SupportedTransport::WebSocket)
                 .expect("transport to be supported")
                 .open(connection_id, websocket)?;
+            self.stats_mut(SupportedTransport::WebSocket).dial_attempts += 1;
+        }
+
+        #[cfg(unix)]
+        if !unix.is_empty() {
+            self.transports
+                .get_mut(&SupportedTransport::Unix)
+                .expect("transport to be supported")
+                .open(connection_id, unix)?;
+            self.stats_mut(SupportedTransport::Unix).dial_attempts += 1;
         }
 
         self.pending_connections.insert(connection_id, peer);
@@ -587,10 +1078,213 @@ impl TransportManager {
         Ok(())
     }
 
+    /// Pin `peer` to `transport`.
+    ///
+    /// Dials to `peer` only consider the addresses of `transport` and dialing an address of any
+    /// other transport fails. If `close_other_connections` is `true`, the open connections to
+    /// `peer` over other transports are closed.
+    pub fn pin_peer_transport(
+        &mut self,
+        peer: PeerId,
+        transport: SupportedTransport,
+        close_other_connections: bool,
+    ) {
+        tracing::debug!(target: LOG_TARGET, ?peer, ?transport, "pin peer to transport");
+
+        self.pinned_transports.insert(peer, transport);
+
+        if !close_other_connections {
+            return;
+        }
+
+        for connection_id in self.peer_connections(&peer) {
+            if self.connection_transports.get(&connection_id) != Some(&transport) {
+                self.close_connection(connection_id);
+            }
+        }
+    }
+
+    /// Get the primary and secondary connection of `peer`, if they're open.
+    fn peer_connections(&self, peer: &PeerId) -> Vec<ConnectionId> {
+        let peers = self.peers.read();
+        let Some(context) = peers.get(peer) else {
+            return Vec::new();
+        };
+
+        let primary = match &context.state {
+            PeerState::Connected { record, .. } => *record.connection_id(),
+            _ => None,
+        };
+        let secondary =
+            context.secondary_connection.as_ref().and_then(|record| *record.connection_id());
+
+        primary.into_iter().chain(secondary).collect()
+    }
+
+    /// Close all connections to `peer`, delivering `reason` to the remote peer.
+    ///
+    /// Connections over transports which don't support application close reasons are closed
+    /// without one. Returns [`Error::PeerDoesntExist`] if there are no open connections to `peer`.
+    pub fn disconnect_with_reason(
+        &mut self,
+        peer: PeerId,
+        reason: CloseReason,
+    ) -> crate::Result<()> {
+        let connections = self.peer_connections(&peer);
+
+        if connections.is_empty() {
+            return Err(Error::PeerDoesntExist(peer));
+        }
+
+        tracing::debug!(target: LOG_TARGET, ?peer, ?reason, "disconnect peer");
+
+        for connection_id in connections {
+            let closed = self
+                .connection_transports
+                .get(&connection_id)
+                .and_then(|transport| self.transports.get_mut(transport))
+                .is_some_and(|transport| transport.close_with_reason(connection_id, &reason));
+
+            if !closed {
+                self.close_connection(connection_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the transport pin of `peer`.
+    pub fn unpin_peer_transport(&mut self, peer: &PeerId) {
+        self.pinned_transports.remove(peer);
+    }
+
+    /// Abort all dials to `peer` which are in progress.
+    ///
+    /// The installed protocols are notified of the dial failure and
+    /// [`TransportEvent::DialAborted`] is emitted. Returns `false` if `peer` wasn't being dialed.
+    pub async fn cancel_dial(&mut self, peer: PeerId) -> bool {
+        self.relay_fallback_dials.remove(&peer);
+
+        let (connection_id, transports) = {
+            let mut peers = self.peers.write();
+            let Some(context) = peers.get_mut(&peer) else {
+                return false;
+            };
+
+            match std::mem::replace(
+                &mut context.state,
+                PeerState::Disconnected { dial_record: None },
+            ) {
+                PeerState::Opening {
+                    records,
+                    connection_id,
+                    transports,
+                } => {
+                    for (_, record) in records {
+                        context.addresses.insert(record);
+                    }
+
+                    (connection_id, Some(transports))
+                }
+                PeerState::Dialing { record } => {
+                    let connection_id = record.connection_id().expect("`ConnectionId` to exist");
+
+                    // only addresses of a multi-address dial are tracked in the address store
+                    if self.opened_dials.remove(&connection_id).is_some() {
+                        context.addresses.insert(record);
+                    }
+
+                    (connection_id, None)
+                }
+                PeerState::Connected {
+                    record,
+                    dial_record: Some(dial_record),
+                } => {
+                    context.state = PeerState::Connected {
+                        record,
+                        dial_record: None,
+                    };
+
+                    (
+                        dial_record.connection_id().expect("`ConnectionId` to exist"),
+                        None,
+                    )
+                }
+                PeerState::Disconnected {
+                    dial_record: Some(dial_record),
+                } => (
+                    dial_record.connection_id().expect("`ConnectionId` to exist"),
+                    None,
+                ),
+                state => {
+                    context.state = state;
+                    return false;
+                }
+            }
+        };
+
+        tracing::debug!(target: LOG_TARGET, ?peer, ?connection_id, "abort dial");
+
+        match transports {
+            // open attempts which can't be aborted are canceled so their outcome isn't reported
+            Some(transports) =>
+                for transport in &transports {
+                    let transport = self.transports.get_mut(transport).expect("transport to exist");
+
+                    if !transport.abort(connection_id) {
+                        transport.cancel(connection_id);
+                    }
+                },
+            None =>
+                if !self.transports.abort(connection_id) {
+                    self.aborted_dials.insert(connection_id);
+                },
+        }
+
+        self.pending_connections.remove(&connection_id);
+        self.opening_errors.remove(&connection_id);
+        self.opened_dials.remove(&connection_id);
+
+        for context in self.protocols.values() {
+            let _ = context
+                .tx
+                .send(InnerTransportEvent::DialFailure {
+                    peer,
+                    address: Multiaddr::empty(),
+                })
+                .await;
+        }
+
+        self.pending_events.push_back(TransportEvent::DialAborted { peer });
+
+        true
+    }
+
     /// Dial peer using `Multiaddr`.
     ///
     /// Returns an error if address it not valid.
     pub async fn dial_address(&mut self, address: Multiaddr) -> crate::Result<()> {
+        self.dial_address_inner(address, None)
+    }
+
+    /// Dial peer using `Multiaddr` and report the milestones of the dial to `progress`.
+    ///
+    /// `progress` is dropped without receiving any events if no new dial was started, for example
+    /// because the peer is already being dialed.
+    pub async fn dial_address_with_progress(
+        &mut self,
+        address: Multiaddr,
+        progress: Sender<DialProgress>,
+    ) -> crate::Result<()> {
+        self.dial_address_inner(address, Some(progress))
+    }
+
+    /// Dial peer using `Multiaddr`, optionally reporting the progress of the dial.
+    fn dial_address_inner(
+        &mut self,
+        address: Multiaddr,
+        progress: Option<Sender<DialProgress>>,
+    ) -> crate::Result<()> {
         self.connection_limits.on_dial_address()?;
 
         let mut record = AddressRecord::from_multiaddr(address)
@@ -600,6 +1294,11 @@ impl TransportManager {
             return Err(Error::TriedToDialSelf);
         }
 
+        if !self.ip_filter.is_address_allowed(record.address()) {
+            tracing::debug!(target: LOG_TARGET, address = ?record.address(), "address denied by ip filter");
+            return Err(Error::AddressDenied(record.address().clone()));
+        }
+
         tracing::debug!(target: LOG_TARGET, address = ?record.address(), "dial address");
 
         let mut protocol_stack = record.as_ref().iter();
@@ -608,12 +1307,20 @@ impl TransportManager {
             .ok_or_else(|| Error::TransportNotSupported(record.address().clone()))?
         {
             Protocol::Ip4(_) | Protocol::Ip6(_) => {}
+            // `/ip6zone/<zone>` is immediately followed by `/ip6/<address>`
+            Protocol::Ip6zone(_) => match protocol_stack.next() {
+                Some(Protocol::Ip6(_)) => {}
+                _ => return Err(Error::TransportNotSupported(record.address().clone())),
+            },
             Protocol::Dns(_) | Protocol::Dns4(_) | Protocol::Dns6(_) => {}
+            Protocol::Onion3(_) => {}
+            #[cfg(unix)]
+            Protocol::Unix(_) => {}
             transport => {
                 tracing::error!(
                     target: LOG_TARGET,
                     ?transport,
-                    "invalid transport, expected `ip4`/`ip6`"
+                    "invalid transport, expected `ip4`/`ip6`/`unix`"
                 );
                 return Err(Error::TransportNotSupported(record.address().clone()));
             }
@@ -629,6 +1336,16 @@ impl TransportManager {
                 Some(Protocol::P2p(_)) => SupportedTransport::Tcp,
                 _ => return Err(Error::TransportNotSupported(record.address().clone())),
             },
+            // `/onion3/<address>:<port>` is immediately followed by `/p2p/<peer>` and can only
+            // be dialed over TCP
+            Protocol::P2p(_)
+                if std::matches!(record.as_ref().iter().next(), Some(Protocol::Onion3(_))) =>
+                SupportedTransport::Tcp,
+            // `/unix/<path>` is immediately followed by `/p2p/<peer>`
+            #[cfg(unix)]
+            Protocol::P2p(_)
+                if std::matches!(record.as_ref().iter().next(), Some(Protocol::Unix(_))) =>
+                SupportedTransport::Unix,
             #[cfg(feature = "quic")]
             Protocol::Udp(_) => match protocol_stack
                 .next()
@@ -655,6 +1372,23 @@ impl TransportManager {
         let remote_peer_id =
             PeerId::try_from_multiaddr(record.address()).expect("`PeerId` to exist");
 
+        if self.reputation.read().is_banned(&remote_peer_id) {
+            return Err(Error::PeerBanned(remote_peer_id));
+        }
+
+        if let Some(pinned) = self.pinned_transports.get(&remote_peer_id) {
+            if *pinned != supported_transport {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    peer = ?remote_peer_id,
+                    address = ?record.address(),
+                    ?pinned,
+                    "peer pinned to another transport",
+                );
+                return Err(Error::AddressDenied(record.address().clone()));
+            }
+        }
+
         // set connection id for the address record and put peer into `Dialing` state
         let connection_id = self.next_connection_id();
         record.set_connection_id(connection_id);
@@ -679,6 +1413,26 @@ impl TransportManager {
                     );
 
                     match context.state {
+                        // peers connected only over a relay may be dialed directly, for example
+                        // when upgrading the connection using hole punching, and the dialed
+                        // connection is accepted as a secondary connection
+                        PeerState::Connected {
+                            record: ref connected_record,
+                            ref mut dial_record,
+                        } if dial_record.is_none()
+                            && context.secondary_connection.is_none()
+                            && is_relayed_address(connected_record.address())
+                            && !is_relayed_address(record.address()) =>
+                        {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                peer = ?remote_peer_id,
+                                address = ?record.address(),
+                                "dial relayed peer directly",
+                            );
+
+                            *dial_record = Some(record.clone());
+                        }
                         PeerState::Connected { .. } => {
                             return Err(Error::AlreadyConnected);
                         }
@@ -715,10 +1469,20 @@ impl TransportManager {
             };
         }
 
-        self.transports
+        let transport = self
+            .transports
             .get_mut(&supported_transport)
-            .ok_or(Error::TransportNotSupported(record.address().clone()))?
-            .dial(connection_id, record.address().clone())?;
+            .ok_or(Error::TransportNotSupported(record.address().clone()))?;
+
+        match progress {
+            Some(tx) => transport.dial_with_progress(
+                connection_id,
+                record.address().clone(),
+                DialProgressSink::new(connection_id, tx),
+            )?,
+            None => transport.dial(connection_id, record.address().clone())?,
+        }
+        self.stats_mut(supported_transport).dial_attempts += 1;
         self.pending_connections.insert(connection_id, remote_peer_id);
 
         Ok(())
@@ -856,11 +1620,66 @@ impl TransportManager {
         }
     }
 
-    fn on_pending_incoming_connection(&mut self) -> crate::Result<()> {
+    fn on_pending_incoming_connection(&mut self, address: &Multiaddr) -> crate::Result<()> {
+        if !self.accepting {
+            return Err(Error::NotAccepting);
+        }
+
+        if !self.ip_filter.is_address_allowed(address) {
+            return Err(Error::AddressDenied(address.clone()));
+        }
+
         self.connection_limits.on_incoming()?;
         Ok(())
     }
 
+    /// Handle changed address of the remote peer of an open connection.
+    fn on_connection_address_changed(
+        &mut self,
+        peer: PeerId,
+        connection_id: ConnectionId,
+        address: Multiaddr,
+    ) {
+        let mut peers = self.peers.write();
+        let Some(context) = peers.get_mut(&peer) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                ?connection_id,
+                "cannot update connection address: peer doesn't exist",
+            );
+            return;
+        };
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?peer,
+            ?connection_id,
+            ?address,
+            "connection address changed",
+        );
+
+        let record = match &mut context.state {
+            PeerState::Connected { record, .. }
+                if record.connection_id() == &Some(connection_id) =>
+                Some(record),
+            _ => context
+                .secondary_connection
+                .as_mut()
+                .filter(|record| record.connection_id() == &Some(connection_id)),
+        };
+
+        match record {
+            Some(record) => record.set_address(&peer, address),
+            None => tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                ?connection_id,
+                "cannot update connection address: connection doesn't exist",
+            ),
+        }
+    }
+
     /// Handle closed connection.
     fn on_connection_closed(
         &mut self,
@@ -868,6 +1687,31 @@ impl TransportManager {
         connection_id: ConnectionId,
     ) -> crate::Result<Option<TransportEvent>> {
         self.connection_limits.on_connection_closed(connection_id);
+        self.connection_data.remove(&connection_id);
+
+        let mut reason = None;
+
+        if let Some(transport) = self.connection_transports.remove(&connection_id) {
+            let stats = self.stats_mut(transport);
+            stats.active_connections = stats.active_connections.saturating_sub(1);
+
+            reason = self
+                .transports
+                .get_mut(&transport)
+                .and_then(|transport| transport.take_close_reason(connection_id));
+        }
+        self.connection_handles.remove(&connection_id);
+        self.pending_closes.remove(&connection_id);
+
+        if self.replaced_connections.remove(&connection_id) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?peer,
+                ?connection_id,
+                "replaced connection closed",
+            );
+            return Ok(None);
+        }
 
         let mut peers = self.peers.write();
         let Some(context) = peers.get_mut(&peer) else {
@@ -908,9 +1752,14 @@ impl TransportManager {
                             dial_record: actual_dial_record,
                         };
 
+                        if let Some(cache) = &self.negotiation_cache {
+                            cache.remove_peer(&peer);
+                        }
+
                         Ok(Some(TransportEvent::ConnectionClosed {
                             peer,
                             connection_id,
+                            reason,
                         }))
                     }
                     Some(secondary_connection) => {
@@ -988,9 +1837,14 @@ impl TransportManager {
                 None => {
                     context.state = PeerState::Disconnected { dial_record };
 
+                    if let Some(cache) = &self.negotiation_cache {
+                        cache.remove_peer(&peer);
+                    }
+
                     Ok(Some(TransportEvent::ConnectionClosed {
                         peer,
                         connection_id,
+                        reason,
                     }))
                 }
             },
@@ -1021,6 +1875,30 @@ impl TransportManager {
             }
         };
 
+        // Reject the connection if the peer is banned.
+        if self.reputation.read().is_banned(&peer) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                ?endpoint,
+                "peer is banned, rejecting connection",
+            );
+            return Ok(ConnectionEstablishedResult::Reject);
+        }
+
+        // Reject inbound connections from denied networks. Most transports have already been
+        // checked when the connection was pending but transports which don't report pending
+        // inbound connections, such as WebRTC, are only checked here.
+        if endpoint.is_listener() && !self.ip_filter.is_address_allowed(endpoint.address()) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?peer,
+                ?endpoint,
+                "address denied by ip filter, rejecting connection",
+            );
+            return Ok(ConnectionEstablishedResult::Reject);
+        }
+
         // Reject the connection if exceeded limits.
         if let Err(error) = self
             .connection_limits
@@ -1036,9 +1914,77 @@ impl TransportManager {
             return Ok(ConnectionEstablishedResult::Reject);
         }
 
+        let mut replaced = Vec::new();
         let mut peers = self.peers.write();
         match peers.get_mut(&peer) {
             Some(context) => match context.state {
+                PeerState::Connected {
+                    ref mut dial_record,
+                    ..
+                } if self.duplicate_connection_policy == DuplicateConnectionPolicy::KeepOldest => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        connection_id = ?endpoint.connection_id(),
+                        ?endpoint,
+                        "peer already connected, rejecting duplicate connection",
+                    );
+
+                    if dial_record.as_ref().is_some_and(|record| {
+                        record.connection_id() == &Some(endpoint.connection_id())
+                    }) {
+                        *dial_record = None;
+                    }
+
+                    // same as for tertiary connections, only store addresses that were dialed
+                    if endpoint.is_listener() {
+                        context.addresses.insert(AddressRecord::new(
+                            &peer,
+                            endpoint.address().clone(),
+                            SCORE_CONNECT_SUCCESS,
+                            None,
+                        ))
+                    }
+
+                    return Ok(ConnectionEstablishedResult::Reject);
+                }
+                PeerState::Connected {
+                    ref record,
+                    ref mut dial_record,
+                } if self.duplicate_connection_policy == DuplicateConnectionPolicy::KeepNewest => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?peer,
+                        connection_id = ?endpoint.connection_id(),
+                        ?endpoint,
+                        "peer already connected, replacing existing connections",
+                    );
+
+                    if dial_record.as_ref().is_some_and(|record| {
+                        record.connection_id() == &Some(endpoint.connection_id())
+                    }) {
+                        *dial_record = None;
+                    }
+
+                    // the new connection is tracked as the secondary connection until the primary
+                    // connection has been closed, after which it becomes the primary connection
+                    replaced.extend(*record.connection_id());
+
+                    if let Some(secondary) =
+                        context.secondary_connection.replace(AddressRecord::new(
+                            &peer,
+                            endpoint.address().clone(),
+                            SCORE_CONNECT_SUCCESS,
+                            Some(endpoint.connection_id()),
+                        ))
+                    {
+                        if let Some(connection_id) = *secondary.connection_id() {
+                            self.replaced_connections.insert(connection_id);
+                            replaced.push(connection_id);
+                        }
+                        context.addresses.insert(secondary);
+                    }
+                }
                 PeerState::Connected {
                     ref mut dial_record,
                     ..
@@ -1281,10 +2227,56 @@ impl TransportManager {
                 );
             }
         }
+        drop(peers);
+
+        for connection_id in replaced {
+            self.close_connection(connection_id);
+        }
 
         Ok(ConnectionEstablishedResult::Accept)
     }
 
+    /// Handle established connection that has been reported to protocols.
+    fn on_connection_handle(&mut self, connection_id: ConnectionId, handle: ConnectionHandle) {
+        self.connection_handles.insert(connection_id, handle);
+
+        if self.pending_closes.remove(&connection_id) {
+            self.close_connection(connection_id);
+        }
+    }
+
+    /// Close all connections to banned `peer` and abort the dials to it which are in progress.
+    async fn on_peer_banned(&mut self, peer: PeerId) {
+        tracing::debug!(target: LOG_TARGET, ?peer, "peer banned, disconnect peer");
+
+        for connection_id in self.peer_connections(&peer) {
+            self.close_connection(connection_id);
+        }
+
+        self.cancel_dial(peer).await;
+    }
+
+    /// Forcibly close connection.
+    ///
+    /// If the connection hasn't been reported to protocols yet, it's closed once it has been.
+    fn close_connection(&mut self, connection_id: ConnectionId) {
+        let Some(handle) = self.connection_handles.get_mut(&connection_id) else {
+            self.pending_closes.insert(connection_id);
+            return;
+        };
+
+        tracing::debug!(target: LOG_TARGET, ?connection_id, "close connection");
+
+        if let Err(error) = handle.force_close() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?connection_id,
+                ?error,
+                "failed to close connection",
+            );
+        }
+    }
+
     fn on_connection_opened(
         &mut self,
         transport: SupportedTransport,
@@ -1369,6 +2361,7 @@ impl TransportManager {
                         );
 
                         self.pending_connections.insert(connection_id, peer);
+                        self.opened_dials.insert(connection_id, address);
 
                         context.state = PeerState::Dialing {
                             record: dial_record,
@@ -1501,8 +2494,16 @@ impl TransportManager {
     /// Poll next event from [`crate::transport::manager::TransportManager`].
     pub async fn next(&mut self) -> Option<TransportEvent> {
         loop {
+            if let Some(event) = self.pending_events.pop_front() {
+                return Some(event);
+            }
+
             tokio::select! {
                 event = self.event_rx.recv() => match event? {
+                    TransportManagerEvent::ConnectionEstablished {
+                        connection: connection_id,
+                        handle,
+                    } => self.on_connection_handle(connection_id, handle),
                     TransportManagerEvent::ConnectionClosed {
                         peer,
                         connection: connection_id,
@@ -1515,7 +2516,17 @@ impl TransportManager {
                             "failed to handle closed connection",
                         ),
                     }
+                    TransportManagerEvent::ConnectionAddressChanged {
+                        peer,
+                        connection: connection_id,
+                        address,
+                    } => self.on_connection_address_changed(peer, connection_id, address),
                 },
+                event = self.relayed_dials.next(), if !self.relayed_dials.is_empty() => {
+                    if let Some(Some(event)) = event {
+                        return Some(event);
+                    }
+                }
                 command = self.cmd_rx.recv() => match command? {
                     InnerTransportManagerCommand::DialPeer { peer } => {
                         if let Err(error) = self.dial(peer).await {
@@ -1527,11 +2538,38 @@ impl TransportManager {
                             tracing::debug!(target: LOG_TARGET, ?error, "failed to dial peer")
                         }
                     }
+                    InnerTransportManagerCommand::BanPeer { peer } => {
+                        self.on_peer_banned(peer).await;
+                    }
                 },
                 event = self.transports.next() => {
                     let (transport, event) = event?;
 
                     match event {
+                        TransportEvent::DialFailure { connection_id, .. }
+                            if self.aborted_dials.remove(&connection_id) =>
+                        {
+                            tracing::trace!(
+                                target: LOG_TARGET,
+                                ?connection_id,
+                                "aborted dial failed",
+                            );
+                        }
+                        TransportEvent::ConnectionEstablished { endpoint, .. }
+                            if self.aborted_dials.remove(&endpoint.connection_id()) =>
+                        {
+                            tracing::trace!(
+                                target: LOG_TARGET,
+                                ?endpoint,
+                                "aborted dial succeeded, reject connection",
+                            );
+
+                            let _ = self
+                                .transports
+                                .get_mut(&transport)
+                                .expect("transport to exist")
+                                .reject(endpoint.connection_id());
+                        }
                         TransportEvent::DialFailure { connection_id, address, error } => {
                             tracing::debug!(
                                 target: LOG_TARGET,
@@ -1540,6 +2578,8 @@ impl TransportManager {
                                 ?error,
                                 "failed to dial peer",
                             );
+                            self.opened_dials.remove(&connection_id);
+                            self.stats_mut(transport).dial_failures += 1;
 
                             if let Ok(()) = self.on_dial_failure(connection_id) {
                                 match address.iter().last() {
@@ -1613,15 +2653,33 @@ impl TransportManager {
                                     }
                                 }
 
-                                return Some(TransportEvent::DialFailure {
+                                let peer = match address.iter().last() {
+                                    Some(Protocol::P2p(hash)) => PeerId::from_multihash(hash).ok(),
+                                    _ => None,
+                                };
+                                let event = TransportEvent::DialFailure {
                                     connection_id,
                                     address,
                                     error,
-                                })
+                                };
+
+                                let Some(peer) = peer else {
+                                    return Some(event);
+                                };
+                                if let Some(event) = self.on_relay_fallback(peer, event) {
+                                    return Some(event);
+                                }
                             }
                         }
-                        TransportEvent::ConnectionEstablished { peer, endpoint } => {
+                        TransportEvent::ConnectionEstablished {
+                            peer,
+                            endpoint,
+                            security,
+                            muxer,
+                        } => {
                             self.opening_errors.remove(&endpoint.connection_id());
+                            let opened_dial = self.opened_dials.remove(&endpoint.connection_id());
+
                             match self.on_connection_established(peer, &endpoint) {
                                 Err(error) => {
                                     tracing::debug!(
@@ -1632,6 +2690,10 @@ impl TransportManager {
                                         "failed to handle established connection",
                                     );
 
+                                    if endpoint.is_listener() {
+                                        self.stats_mut(transport).accept_errors += 1;
+                                    }
+
                                     let _ = self
                                         .transports
                                         .get_mut(&transport)
@@ -1651,10 +2713,30 @@ impl TransportManager {
                                         .get_mut(&transport)
                                         .expect("transport to exist")
                                         .accept(endpoint.connection_id());
+                                    self.connection_data
+                                        .insert(endpoint.connection_id(), HashMap::new());
+                                    self.connection_transports
+                                        .insert(endpoint.connection_id(), transport);
+
+                                    self.relay_fallback_dials.remove(&peer);
+
+                                    let stats = self.stats_mut(transport);
+                                    stats.active_connections += 1;
+                                    if !endpoint.is_listener() {
+                                        stats.dial_successes += 1;
+                                    }
+
+                                    if let Some(address) = opened_dial {
+                                        self.pending_events.push_back(
+                                            TransportEvent::DialSucceeded { peer, address },
+                                        );
+                                    }
 
                                     return Some(TransportEvent::ConnectionEstablished {
                                         peer,
                                         endpoint,
+                                        security,
+                                        muxer,
                                     });
                                 }
                                 Ok(ConnectionEstablishedResult::Reject) => {
@@ -1665,6 +2747,10 @@ impl TransportManager {
                                         "reject connection",
                                     );
 
+                                    if endpoint.is_listener() {
+                                        self.stats_mut(transport).accept_errors += 1;
+                                    }
+
                                     let _ = self
                                         .transports
                                         .get_mut(&transport)
@@ -1686,6 +2772,8 @@ impl TransportManager {
                             }
                         }
                         TransportEvent::OpenFailure { connection_id, errors } => {
+                            self.stats_mut(transport).dial_failures += 1;
+
                             match self.on_open_failure(transport, connection_id) {
                                 Err(error) => tracing::debug!(
                                     target: LOG_TARGET,
@@ -1732,7 +2820,14 @@ impl TransportManager {
 
                                     let mut grouped_errors = self.opening_errors.remove(&connection_id).unwrap_or_default();
                                     grouped_errors.extend(errors);
-                                    return Some(TransportEvent::OpenFailure { connection_id, errors: grouped_errors });
+                                    let event = TransportEvent::OpenFailure {
+                                        connection_id,
+                                        errors: grouped_errors,
+                                    };
+
+                                    if let Some(event) = self.on_relay_fallback(peer, event) {
+                                        return Some(event);
+                                    }
                                 }
                                 Ok(None) => {
                                     tracing::trace!(
@@ -1745,33 +2840,70 @@ impl TransportManager {
                                 }
                             }
                         },
-                        TransportEvent::PendingInboundConnection { connection_id } => {
-                            if self.on_pending_incoming_connection().is_ok() {
-                                tracing::trace!(
+                        TransportEvent::PendingInboundConnection { connection_id, address } => {
+                            if let Err(error) = self.on_pending_incoming_connection(&address) {
+                                tracing::debug!(
                                     target: LOG_TARGET,
                                     ?connection_id,
-                                    "accept pending incoming connection",
+                                    ?address,
+                                    ?error,
+                                    "reject pending incoming connection",
                                 );
+                                self.stats_mut(transport).accept_errors += 1;
 
                                 let _ = self
                                     .transports
                                     .get_mut(&transport)
                                     .expect("transport to exist")
-                                    .accept_pending(connection_id);
+                                    .reject_pending(connection_id);
                             } else {
-                                tracing::debug!(
+                                tracing::trace!(
                                     target: LOG_TARGET,
                                     ?connection_id,
-                                    "reject pending incoming connection",
+                                    "accept pending incoming connection",
                                 );
 
                                 let _ = self
                                     .transports
                                     .get_mut(&transport)
                                     .expect("transport to exist")
-                                    .reject_pending(connection_id);
+                                    .accept_pending(connection_id);
                             }
                         },
+                        TransportEvent::NewListenAddress { address } => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?transport,
+                                ?address,
+                                "new listen address",
+                            );
+
+                            let mut listen_addresses = self.listen_addresses.write();
+                            listen_addresses.insert(address.clone());
+                            listen_addresses.insert(address.clone().with(Protocol::P2p(
+                                Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+                            )));
+                            drop(listen_addresses);
+
+                            return Some(TransportEvent::NewListenAddress { address });
+                        }
+                        TransportEvent::ExpiredListenAddress { address } => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?transport,
+                                ?address,
+                                "listen address expired",
+                            );
+
+                            let mut listen_addresses = self.listen_addresses.write();
+                            listen_addresses.remove(&address);
+                            listen_addresses.remove(&address.clone().with(Protocol::P2p(
+                                Multihash::from_bytes(&self.local_peer_id.to_bytes()).unwrap(),
+                            )));
+                            drop(listen_addresses);
+
+                            return Some(TransportEvent::ExpiredListenAddress { address });
+                        }
                         event => panic!("event not supported: {event:?}"),
                     }
                 },
@@ -1783,14 +2915,16 @@ impl TransportManager {
 #[cfg(test)]
 mod tests {
     use limits::ConnectionLimitsConfig;
+    use reputation::ReputationConfig;
 
     use multihash::Multihash;
 
     use super::*;
     use crate::{
-        crypto::ed25519::Keypair,
+        error::ImmediateDialError,
         executor::DefaultExecutor,
-        transport::{dummy::DummyTransport, KEEP_ALIVE_TIMEOUT},
+        protocol::ProtocolCommand,
+        transport::{dummy::DummyTransport, Muxer, SecurityProtocol, KEEP_ALIVE_TIMEOUT},
     };
     #[cfg(feature = "websocket")]
     use std::borrow::Cow;
@@ -1818,11 +2952,12 @@ mod tests {
     fn duplicate_protocol() {
         let sink = BandwidthSink::new();
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             sink,
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         manager.register_protocol(
@@ -1830,12 +2965,14 @@ mod tests {
             Vec::new(),
             ProtocolCodec::UnsignedVarint(None),
             KEEP_ALIVE_TIMEOUT,
+            true,
         );
         manager.register_protocol(
             ProtocolName::from("/notif/1"),
             Vec::new(),
             ProtocolCodec::UnsignedVarint(None),
             KEEP_ALIVE_TIMEOUT,
+            true,
         );
     }
 
@@ -1845,11 +2982,12 @@ mod tests {
     fn fallback_protocol_as_duplicate_main_protocol() {
         let sink = BandwidthSink::new();
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             sink,
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         manager.register_protocol(
@@ -1857,6 +2995,7 @@ mod tests {
             Vec::new(),
             ProtocolCodec::UnsignedVarint(None),
             KEEP_ALIVE_TIMEOUT,
+            true,
         );
         manager.register_protocol(
             ProtocolName::from("/notif/2"),
@@ -1866,6 +3005,7 @@ mod tests {
             ],
             ProtocolCodec::UnsignedVarint(None),
             KEEP_ALIVE_TIMEOUT,
+            true,
         );
     }
 
@@ -1875,11 +3015,12 @@ mod tests {
     fn duplicate_fallback_protocol() {
         let sink = BandwidthSink::new();
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             sink,
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         manager.register_protocol(
@@ -1890,6 +3031,7 @@ mod tests {
             ],
             ProtocolCodec::UnsignedVarint(None),
             KEEP_ALIVE_TIMEOUT,
+            true,
         );
         manager.register_protocol(
             ProtocolName::from("/notif/2"),
@@ -1899,6 +3041,7 @@ mod tests {
             ],
             ProtocolCodec::UnsignedVarint(None),
             KEEP_ALIVE_TIMEOUT,
+            true,
         );
     }
 
@@ -1908,11 +3051,12 @@ mod tests {
     fn duplicate_transport() {
         let sink = BandwidthSink::new();
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             sink,
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -1921,8 +3065,8 @@ mod tests {
 
     #[tokio::test]
     async fn tried_to_self_using_peer_id() {
-        let keypair = Keypair::generate();
-        let local_peer_id = PeerId::from_public_key(&keypair.public().into());
+        let keypair = Keypair::generate_ed25519();
+        let local_peer_id = keypair.to_peer_id();
         let sink = BandwidthSink::new();
         let (mut manager, _handle) = TransportManager::new(
             keypair,
@@ -1930,6 +3074,7 @@ mod tests {
             sink,
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         assert!(manager.dial(local_peer_id).await.is_err());
@@ -1938,11 +3083,12 @@ mod tests {
     #[tokio::test]
     async fn try_to_dial_over_disabled_transport() {
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -1968,11 +3114,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let peer = PeerId::random();
         let dial_address = Multiaddr::empty()
@@ -1987,6 +3134,8 @@ mod tests {
             transport.inject_event(TransportEvent::ConnectionEstablished {
                 peer,
                 endpoint: Endpoint::dialer(dial_address.clone(), ConnectionId::from(0usize)),
+                security: SecurityProtocol::Noise,
+                muxer: Some(Muxer::Yamux),
             });
             transport
         });
@@ -2023,6 +3172,94 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn cancel_dial_emits_dial_aborted() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
+        );
+        let peer = PeerId::random();
+        let (dial_address, connection_id) = setup_dial_addr(peer, 0);
+
+        // `DummyTransport` can't abort dials so it reports the outcome of the dial
+        let transport = Box::new({
+            let mut transport = DummyTransport::new();
+            transport.inject_event(TransportEvent::DialFailure {
+                connection_id,
+                address: dial_address.clone(),
+                error: DialError::Timeout,
+            });
+            transport
+        });
+        manager.register_transport(SupportedTransport::Tcp, transport);
+
+        assert!(!manager.cancel_dial(peer).await);
+        manager.dial_address(dial_address.clone()).await.unwrap();
+        assert!(manager.cancel_dial(peer).await);
+        assert!(manager.pending_connections.is_empty());
+
+        match manager.peers.read().get(&peer) {
+            Some(PeerContext {
+                state: PeerState::Disconnected { dial_record: None },
+                ..
+            }) => {}
+            state => panic!("invalid state for peer: {state:?}"),
+        }
+
+        match manager.next().await.unwrap() {
+            TransportEvent::DialAborted { peer: event_peer } => assert_eq!(event_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // outcome of the aborted dial is not reported
+        assert!(tokio::time::timeout(Duration::from_millis(200), manager.next()).await.is_err());
+        assert!(manager.aborted_dials.is_empty());
+    }
+
+    #[tokio::test]
+    async fn ban_aborts_dial_and_refuses_new_dials() {
+        let (mut manager, handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
+        );
+        let peer = PeerId::random();
+        let (dial_address, connection_id) = setup_dial_addr(peer, 0);
+
+        let transport = Box::new({
+            let mut transport = DummyTransport::new();
+            transport.inject_event(TransportEvent::DialFailure {
+                connection_id,
+                address: dial_address.clone(),
+                error: DialError::Timeout,
+            });
+            transport
+        });
+        manager.register_transport(SupportedTransport::Tcp, transport);
+        manager.dial_address(dial_address.clone()).await.unwrap();
+
+        // ban is handled by the transport manager even though it was reported through the handle
+        assert!(handle.report_peer(peer, i32::MIN));
+
+        match manager.next().await.unwrap() {
+            TransportEvent::DialAborted { peer: event_peer } => assert_eq!(event_peer, peer),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert!(manager.pending_connections.is_empty());
+
+        assert!(std::matches!(
+            manager.dial_address(dial_address).await,
+            Err(Error::PeerBanned(banned)) if banned == peer
+        ));
+    }
+
     #[tokio::test]
     async fn try_to_dial_same_peer_twice() {
         let _ = tracing_subscriber::fmt()
@@ -2030,11 +3267,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -2061,11 +3299,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -2106,11 +3345,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -2125,11 +3365,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -2159,11 +3400,12 @@ mod tests {
         transports.insert(SupportedTransport::Quic);
 
         let (_manager, handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             transports,
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         // ipv6
@@ -2221,11 +3463,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -2288,11 +3531,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -2375,11 +3619,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let _handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -2460,11 +3705,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
@@ -2568,11 +3814,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
@@ -2672,11 +3919,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
@@ -2775,11 +4023,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
@@ -2882,11 +4131,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
@@ -3011,11 +4261,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         manager.on_dial_failure(ConnectionId::random()).unwrap();
@@ -3030,11 +4281,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let connection_id = ConnectionId::random();
         let peer = PeerId::random();
@@ -3051,11 +4303,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager.on_connection_closed(PeerId::random(), ConnectionId::random()).unwrap();
     }
@@ -3069,11 +4322,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager
             .on_connection_opened(
@@ -3093,11 +4347,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let connection_id = ConnectionId::random();
         let peer = PeerId::random();
@@ -3117,11 +4372,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let connection_id = ConnectionId::random();
         let peer = PeerId::random();
@@ -3144,11 +4400,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         manager
@@ -3165,11 +4422,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let connection_id = ConnectionId::random();
         let peer = PeerId::random();
@@ -3185,11 +4443,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         assert!(manager.next().await.is_none());
@@ -3198,11 +4457,12 @@ mod tests {
     #[tokio::test]
     async fn dial_already_connected_peer() {
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         let peer = {
@@ -3246,11 +4506,12 @@ mod tests {
     #[tokio::test]
     async fn peer_already_being_dialed() {
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         let peer = {
@@ -3309,11 +4570,12 @@ mod tests {
     #[tokio::test]
     async fn pending_connection_for_disconnected_peer() {
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         let peer = {
@@ -3353,11 +4615,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         // transport doesn't start with ip/dns
@@ -3419,11 +4682,12 @@ mod tests {
     #[tokio::test]
     async fn dial_address_peer_id_missing() {
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
 
         async fn call_manager(manager: &mut TransportManager, address: Multiaddr) {
@@ -3473,11 +4737,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let peer = PeerId::random();
         let dial_address = Multiaddr::empty()
@@ -3493,6 +4758,8 @@ mod tests {
             transport.inject_event(TransportEvent::ConnectionEstablished {
                 peer,
                 endpoint: Endpoint::listener(dial_address.clone(), connection_id),
+                security: SecurityProtocol::Noise,
+                muxer: Some(Muxer::Yamux),
             });
             transport
         });
@@ -3565,11 +4832,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let peer = PeerId::random();
         let dial_address = Multiaddr::empty()
@@ -3585,6 +4853,8 @@ mod tests {
             transport.inject_event(TransportEvent::ConnectionEstablished {
                 peer,
                 endpoint: Endpoint::listener(dial_address.clone(), connection_id),
+                security: SecurityProtocol::Noise,
+                muxer: Some(Muxer::Yamux),
             });
             transport
         });
@@ -3657,13 +4927,14 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default()
                 .max_incoming_connections(Some(3))
                 .max_outgoing_connections(Some(2)),
+            ReputationConfig::default(),
         );
         // The connection limit is agnostic of the underlying transports.
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -3726,6 +4997,61 @@ mod tests {
         assert_eq!(result, ConnectionEstablishedResult::Accept);
     }
 
+    #[tokio::test]
+    async fn automatic_dials_suppressed_at_target_connections() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (mut manager, mut handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default().target_connections(1, 2),
+            ReputationConfig::default(),
+        );
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+        let peer3 = PeerId::random();
+        let (address1, connection_id1) = setup_dial_addr(peer1, 0);
+        let (address2, connection_id2) = setup_dial_addr(peer2, 1);
+        let (address3, _) = setup_dial_addr(peer3, 2);
+        handle.add_known_address(&peer3, std::iter::once(address3));
+
+        assert_eq!(handle.dial_automatic(&peer3), Ok(()));
+
+        for (peer, address, connection_id) in [
+            (peer1, address1, connection_id1),
+            (peer2, address2, connection_id2),
+        ] {
+            let result = manager
+                .on_connection_established(peer, &Endpoint::listener(address, connection_id))
+                .unwrap();
+            assert_eq!(result, ConnectionEstablishedResult::Accept);
+        }
+
+        // high water mark reached, automatic dials are suppressed but manual dials proceed
+        assert_eq!(
+            handle.dial_automatic(&peer3),
+            Err(ImmediateDialError::DialThrottled)
+        );
+        assert_eq!(handle.dial(&peer3), Ok(()));
+
+        // still at the low water mark
+        let _ = manager.on_connection_closed(peer1, connection_id1).unwrap();
+        assert_eq!(
+            handle.dial_automatic(&peer3),
+            Err(ImmediateDialError::DialThrottled)
+        );
+
+        // below the low water mark, automatic dials resume
+        let _ = manager.on_connection_closed(peer2, connection_id2).unwrap();
+        assert_eq!(handle.dial_automatic(&peer3), Ok(()));
+    }
+
     #[tokio::test]
     async fn manager_limits_outbound_connections() {
         let _ = tracing_subscriber::fmt()
@@ -3733,13 +5059,14 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default()
                 .max_incoming_connections(Some(3))
                 .max_outgoing_connections(Some(2)),
+            ReputationConfig::default(),
         );
         // The connection limit is agnostic of the underlying transports.
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
@@ -3822,11 +5149,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
@@ -3877,11 +5205,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
 
@@ -3951,6 +5280,7 @@ mod tests {
             TransportEvent::ConnectionClosed {
                 peer: event_peer,
                 connection_id: event_connection_id,
+                reason: None,
             } => {
                 assert_eq!(peer, event_peer);
                 assert_eq!(event_connection_id, remote_connection_id);
@@ -4031,11 +5361,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let peer = PeerId::random();
         let dial_address = Multiaddr::empty()
@@ -4051,6 +5382,8 @@ mod tests {
             transport.inject_event(TransportEvent::ConnectionEstablished {
                 peer,
                 endpoint: Endpoint::listener(dial_address.clone(), connection_id),
+                security: SecurityProtocol::Noise,
+                muxer: Some(Muxer::Yamux),
             });
             transport
         });
@@ -4099,6 +5432,72 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "quic")]
+    #[tokio::test]
+    async fn pinned_peer_dialed_only_over_pinned_transport() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
+        );
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+        manager.register_transport(SupportedTransport::Quic, Box::new(DummyTransport::new()));
+
+        let peer = PeerId::random();
+        let tcp_address = Multiaddr::empty()
+            .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Tcp(8888))
+            .with(Protocol::P2p(Multihash::from(peer)));
+        let quic_address = Multiaddr::empty()
+            .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Udp(8888))
+            .with(Protocol::QuicV1)
+            .with(Protocol::P2p(Multihash::from(peer)));
+
+        // the tcp address has the higher score
+        let mut tcp_record = AddressRecord::from_multiaddr(tcp_address.clone()).unwrap();
+        tcp_record.update_score(100);
+        manager.peers.write().insert(
+            peer,
+            PeerContext {
+                state: PeerState::Disconnected { dial_record: None },
+                secondary_connection: None,
+                addresses: AddressStore::from_iter([
+                    tcp_record,
+                    AddressRecord::from_multiaddr(quic_address.clone()).unwrap(),
+                ]),
+            },
+        );
+
+        manager.pin_peer_transport(peer, SupportedTransport::Quic, false);
+        assert!(std::matches!(
+            manager.dial_address(tcp_address.clone()).await,
+            Err(Error::AddressDenied(_))
+        ));
+
+        manager.dial(peer).await.unwrap();
+
+        let peers = manager.peers.read();
+        let context = peers.get(&peer).unwrap();
+        match &context.state {
+            PeerState::Opening {
+                records,
+                transports,
+                ..
+            } => {
+                assert_eq!(transports, &HashSet::from([SupportedTransport::Quic]));
+                assert_eq!(records.keys().collect::<Vec<_>>(), vec![&quic_address]);
+            }
+            state => panic!("invalid state: {state:?}"),
+        }
+
+        // the tcp address is kept for when the peer is unpinned
+        assert!(context.addresses.contains(&tcp_address));
+    }
+
     #[cfg(feature = "websocket")]
     #[tokio::test]
     async fn opening_errors_are_reported() {
@@ -4107,11 +5506,12 @@ mod tests {
             .try_init();
 
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let peer = PeerId::random();
         let connection_id = ConnectionId::from(0);
@@ -4205,4 +5605,162 @@ mod tests {
         assert!(manager.pending_connections.is_empty());
         assert!(manager.opening_errors.is_empty());
     }
+
+    #[tokio::test]
+    async fn inbound_webrtc_connection_from_denied_network_rejected() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
+        );
+        manager.set_ip_filter(
+            ip_filter::IpFilterConfig::default().deny("10.0.0.0/8".parse().unwrap()),
+        );
+
+        // WebRTC doesn't report pending inbound connections so the filter is only applied once
+        // the connection has been established
+        let webrtc_address = |peer: PeerId, address: Ipv4Addr| {
+            Multiaddr::empty()
+                .with(Protocol::Ip4(address))
+                .with(Protocol::Udp(8888))
+                .with(Protocol::WebRTC)
+                .with(Protocol::P2p(
+                    Multihash::from_bytes(&peer.to_bytes()).unwrap(),
+                ))
+        };
+
+        let peer1 = PeerId::random();
+        let result = manager
+            .on_connection_established(
+                peer1,
+                &Endpoint::listener(
+                    webrtc_address(peer1, Ipv4Addr::new(10, 1, 2, 3)),
+                    ConnectionId::from(0usize),
+                ),
+            )
+            .unwrap();
+        assert_eq!(result, ConnectionEstablishedResult::Reject);
+        assert!(!std::matches!(
+            manager.peers.read().get(&peer1).map(|context| &context.state),
+            Some(PeerState::Connected { .. })
+        ));
+
+        let peer2 = PeerId::random();
+        let result = manager
+            .on_connection_established(
+                peer2,
+                &Endpoint::listener(
+                    webrtc_address(peer2, Ipv4Addr::new(192, 168, 1, 1)),
+                    ConnectionId::from(1usize),
+                ),
+            )
+            .unwrap();
+        assert_eq!(result, ConnectionEstablishedResult::Accept);
+    }
+
+    #[tokio::test]
+    async fn keep_newest_closes_older_connection() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
+        );
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+        manager.set_duplicate_connection_policy(DuplicateConnectionPolicy::KeepNewest);
+
+        let peer = PeerId::random();
+        let (address1, connection1) = setup_dial_addr(peer, 0);
+        let (address2, connection2) = setup_dial_addr(peer, 1);
+
+        // first connection is established and reported to protocols
+        let result = manager
+            .on_connection_established(peer, &Endpoint::listener(address1, connection1))
+            .unwrap();
+        assert_eq!(result, ConnectionEstablishedResult::Accept);
+
+        let (tx1, mut rx1) = channel(8);
+        let mut handle1 = ConnectionHandle::new(connection1, tx1);
+        let _active1 = handle1.downgrade();
+        manager.on_connection_handle(connection1, handle1);
+
+        // second connection replaces the first connection which is closed
+        let result = manager
+            .on_connection_established(peer, &Endpoint::listener(address2, connection2))
+            .unwrap();
+        assert_eq!(result, ConnectionEstablishedResult::Accept);
+        assert!(std::matches!(
+            rx1.try_recv(),
+            Ok(ProtocolCommand::ForceClose)
+        ));
+
+        let (tx2, mut rx2) = channel(8);
+        let mut handle2 = ConnectionHandle::new(connection2, tx2);
+        let _active2 = handle2.downgrade();
+        manager.on_connection_handle(connection2, handle2);
+        assert!(rx2.try_recv().is_err());
+
+        // once the first connection is closed, the second connection becomes the primary
+        // connection and the peer stays connected
+        assert!(manager.on_connection_closed(peer, connection1).unwrap().is_none());
+
+        let peers = manager.peers.read();
+        let context = peers.get(&peer).unwrap();
+
+        match &context.state {
+            PeerState::Connected { record, .. } => {
+                assert_eq!(record.connection_id(), &Some(connection2));
+                assert!(context.secondary_connection.is_none());
+            }
+            state => panic!("invalid state: {state:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn keep_oldest_rejects_new_connection() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
+        );
+        manager.register_transport(SupportedTransport::Tcp, Box::new(DummyTransport::new()));
+        manager.set_duplicate_connection_policy(DuplicateConnectionPolicy::KeepOldest);
+
+        let peer = PeerId::random();
+        let (address1, connection1) = setup_dial_addr(peer, 0);
+        let (address2, connection2) = setup_dial_addr(peer, 1);
+
+        let result = manager
+            .on_connection_established(peer, &Endpoint::listener(address1, connection1))
+            .unwrap();
+        assert_eq!(result, ConnectionEstablishedResult::Accept);
+
+        let result = manager
+            .on_connection_established(peer, &Endpoint::listener(address2, connection2))
+            .unwrap();
+        assert_eq!(result, ConnectionEstablishedResult::Reject);
+
+        let peers = manager.peers.read();
+        let context = peers.get(&peer).unwrap();
+
+        match &context.state {
+            PeerState::Connected { record, .. } => {
+                assert_eq!(record.connection_id(), &Some(connection1));
+                assert!(context.secondary_connection.is_none());
+            }
+            state => panic!("invalid state: {state:?}"),
+        }
+    }
 }
@@ -0,0 +1,295 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Peer reputation tracking for the transport manager.
+
+use crate::PeerId;
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Default reputation at or below which a peer is banned.
+const DEFAULT_BAN_THRESHOLD: i32 = -1000;
+
+/// Default duration of a ban.
+const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(60);
+
+/// Default half-life of a reputation score.
+const DEFAULT_DECAY_HALF_LIFE: Duration = Duration::from_secs(60);
+
+/// How often decayed scores and expired bans are pruned.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Configuration for peer reputation.
+#[derive(Debug, Clone)]
+pub struct ReputationConfig {
+    /// Reputation at or below which the peer is banned.
+    ban_threshold: i32,
+
+    /// How long the peer stays banned.
+    ban_duration: Duration,
+
+    /// Time it takes for the reputation to decay halfway towards zero.
+    decay_half_life: Duration,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            ban_threshold: DEFAULT_BAN_THRESHOLD,
+            ban_duration: DEFAULT_BAN_DURATION,
+            decay_half_life: DEFAULT_DECAY_HALF_LIFE,
+        }
+    }
+}
+
+impl ReputationConfig {
+    /// Configures the reputation at or below which the peer is disconnected and banned.
+    pub fn ban_threshold(mut self, threshold: i32) -> Self {
+        self.ban_threshold = threshold;
+        self
+    }
+
+    /// Configures how long a banned peer is refused.
+    pub fn ban_duration(mut self, duration: Duration) -> Self {
+        self.ban_duration = duration;
+        self
+    }
+
+    /// Configures how fast reputation decays towards zero.
+    ///
+    /// After `half_life` has elapsed, the reputation of the peer is halved.
+    pub fn decay_half_life(mut self, half_life: Duration) -> Self {
+        self.decay_half_life = half_life;
+        self
+    }
+}
+
+/// Reputation score of a peer.
+#[derive(Debug, Clone, Copy)]
+struct Score {
+    /// Reputation value at the time of the last update.
+    value: i32,
+
+    /// When was the value last updated.
+    updated: Instant,
+}
+
+impl Score {
+    /// Get the value of the score at `now`, decayed towards zero.
+    fn decayed(&self, now: Instant, half_life: Duration) -> i32 {
+        if half_life.is_zero() {
+            return 0;
+        }
+
+        let elapsed = now.saturating_duration_since(self.updated);
+        let factor = 0.5f64.powf(elapsed.as_secs_f64() / half_life.as_secs_f64());
+
+        (self.value as f64 * factor).round() as i32
+    }
+}
+
+/// Peer reputation.
+#[derive(Debug)]
+pub struct Reputation {
+    /// Configuration for peer reputation.
+    config: ReputationConfig,
+
+    /// Reputation scores of peers.
+    scores: HashMap<PeerId, Score>,
+
+    /// Banned peers and the time when the ban expires.
+    banned: HashMap<PeerId, Instant>,
+
+    /// When were decayed scores and expired bans last pruned.
+    last_pruned: Instant,
+}
+
+impl Reputation {
+    /// Create new [`Reputation`].
+    pub fn new(config: ReputationConfig) -> Self {
+        Self {
+            config,
+            scores: HashMap::new(),
+            banned: HashMap::new(),
+            last_pruned: Instant::now(),
+        }
+    }
+
+    /// Get current reputation of `peer`.
+    pub fn reputation(&self, peer: &PeerId) -> i32 {
+        self.scores.get(peer).map_or(0, |score| {
+            score.decayed(Instant::now(), self.config.decay_half_life)
+        })
+    }
+
+    /// Check if `peer` is banned.
+    pub fn is_banned(&self, peer: &PeerId) -> bool {
+        self.banned.get(peer).map_or(false, |until| *until > Instant::now())
+    }
+
+    /// Adjust the reputation of `peer` by `change`.
+    ///
+    /// Returns `true` if the peer became banned as a result of the change.
+    pub fn report(&mut self, peer: PeerId, change: i32) -> bool {
+        let now = Instant::now();
+        let half_life = self.config.decay_half_life;
+
+        let value = self
+            .scores
+            .get(&peer)
+            .map_or(0, |score| score.decayed(now, half_life))
+            .saturating_add(change);
+
+        self.prune(now);
+
+        if value != 0 {
+            self.scores.insert(
+                peer,
+                Score {
+                    value,
+                    updated: now,
+                },
+            );
+        }
+
+        if value > self.config.ban_threshold
+            || self.banned.get(&peer).is_some_and(|until| *until > now)
+        {
+            return false;
+        }
+
+        tracing::debug!(
+            target: super::LOG_TARGET,
+            ?peer,
+            reputation = ?value,
+            duration = ?self.config.ban_duration,
+            "peer reputation crossed ban threshold, banning peer",
+        );
+
+        self.banned.insert(peer, now + self.config.ban_duration);
+        true
+    }
+
+    /// Remove scores which have decayed to zero and bans which have expired.
+    ///
+    /// Pruning is done at most once every [`PRUNE_INTERVAL`] so that the cost of going through
+    /// all tracked peers is amortized over the reports.
+    fn prune(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.last_pruned) < PRUNE_INTERVAL {
+            return;
+        }
+
+        let half_life = self.config.decay_half_life;
+
+        self.scores.retain(|_, score| score.decayed(now, half_life) != 0);
+        self.banned.retain(|_, until| *until > now);
+        self.last_pruned = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reputation_crosses_ban_threshold() {
+        let config = ReputationConfig::default()
+            .ban_threshold(-100)
+            .ban_duration(Duration::from_secs(60))
+            .decay_half_life(Duration::from_secs(3600));
+        let mut reputation = Reputation::new(config);
+        let peer = PeerId::random();
+
+        assert!(!reputation.report(peer, -60));
+        assert!(!reputation.is_banned(&peer));
+        assert_eq!(reputation.reputation(&peer), -60);
+
+        assert!(reputation.report(peer, -60));
+        assert!(reputation.is_banned(&peer));
+        assert_eq!(reputation.reputation(&peer), -120);
+
+        // already banned peer is not reported as newly banned
+        assert!(!reputation.report(peer, -60));
+        assert!(reputation.is_banned(&peer));
+
+        // other peers are not affected
+        assert!(!reputation.is_banned(&PeerId::random()));
+    }
+
+    #[test]
+    fn ban_expires() {
+        let config = ReputationConfig::default()
+            .ban_threshold(-100)
+            .ban_duration(Duration::from_millis(50))
+            .decay_half_life(Duration::from_secs(3600));
+        let mut reputation = Reputation::new(config);
+        let peer = PeerId::random();
+
+        assert!(reputation.report(peer, -200));
+        assert!(reputation.is_banned(&peer));
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!reputation.is_banned(&peer));
+    }
+
+    #[test]
+    fn expired_state_pruned_periodically() {
+        let config = ReputationConfig::default()
+            .ban_threshold(-100)
+            .ban_duration(Duration::from_millis(10))
+            .decay_half_life(Duration::from_millis(10));
+        let mut reputation = Reputation::new(config);
+        let peer = PeerId::random();
+
+        assert!(reputation.report(peer, -200));
+        std::thread::sleep(Duration::from_millis(100));
+
+        // expired state is not pruned on every report
+        assert!(!reputation.report(PeerId::random(), -10));
+        assert!(reputation.scores.contains_key(&peer));
+        assert!(reputation.banned.contains_key(&peer));
+
+        // but it is once the prune interval has passed
+        reputation.last_pruned -= PRUNE_INTERVAL;
+        assert!(!reputation.report(PeerId::random(), -10));
+        assert!(!reputation.scores.contains_key(&peer));
+        assert!(!reputation.banned.contains_key(&peer));
+
+        // expired ban doesn't prevent the peer from being banned again
+        assert!(reputation.report(peer, -200));
+    }
+
+    #[test]
+    fn reputation_decays() {
+        let config = ReputationConfig::default().decay_half_life(Duration::from_millis(50));
+        let mut reputation = Reputation::new(config);
+        let peer = PeerId::random();
+
+        assert!(!reputation.report(peer, -500));
+        assert!(!reputation.report(peer, 200));
+        assert!(reputation.reputation(&peer) >= -300);
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(reputation.reputation(&peer) > -100);
+    }
+}
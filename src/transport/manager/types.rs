@@ -44,6 +44,45 @@ pub enum SupportedTransport {
     /// WebSocket
     #[cfg(feature = "websocket")]
     WebSocket,
+
+    /// Unix domain socket.
+    #[cfg(unix)]
+    Unix,
+
+    /// Connections tunneled over substreams of other connections.
+    Tunnel,
+
+    /// Tor onion services, dialed over TCP through a SOCKS5 proxy.
+    ///
+    /// Only supported if the TCP transport is configured with a SOCKS5 proxy.
+    Onion3,
+}
+
+/// Listener and connection statistics of a transport.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TransportStats {
+    /// Number of listen addresses the transport is bound to.
+    pub listeners: usize,
+
+    /// Number of open connections established over the transport.
+    pub active_connections: usize,
+
+    /// Number of inbound connections which were rejected before or after they were upgraded.
+    pub accept_errors: u64,
+
+    /// Number of dials started over the transport.
+    ///
+    /// A dial to several addresses of a peer counts as one attempt for each transport it uses.
+    pub dial_attempts: u64,
+
+    /// Number of outbound connections established over the transport.
+    pub dial_successes: u64,
+
+    /// Number of dials the transport failed to establish a connection for.
+    ///
+    /// Dials canceled because the peer was reached over another address or transport count
+    /// neither as successes nor as failures.
+    pub dial_failures: u64,
 }
 
 /// Peer state.
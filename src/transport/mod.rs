@@ -31,6 +31,9 @@ pub(crate) mod common;
 #[cfg(feature = "quic")]
 pub mod quic;
 pub mod tcp;
+pub mod tunnel;
+#[cfg(unix)]
+pub mod unix;
 #[cfg(feature = "webrtc")]
 pub mod webrtc;
 #[cfg(feature = "websocket")]
@@ -39,7 +42,17 @@ pub mod websocket;
 pub(crate) mod dummy;
 pub(crate) mod manager;
 
-pub use manager::limits::{ConnectionLimitsConfig, ConnectionLimitsError};
+mod progress;
+
+pub use manager::{
+    ip_filter::{IpFilterConfig, IpNetwork, IpNetworkError},
+    limits::{ConnectionLimitsConfig, ConnectionLimitsError},
+    reputation::ReputationConfig,
+    SupportedTransport, TransportStats,
+};
+pub use progress::{DialMilestone, DialProgress};
+
+pub(crate) use progress::DialProgressSink;
 
 /// Timeout for opening a connection.
 pub(crate) const CONNECTION_OPEN_TIMEOUT: Duration = Duration::from_secs(10);
@@ -47,12 +60,24 @@ pub(crate) const CONNECTION_OPEN_TIMEOUT: Duration = Duration::from_secs(10);
 /// Timeout for opening a substream.
 pub(crate) const SUBSTREAM_OPEN_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Timeout for the Noise handshake.
+pub(crate) const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Timeout for connection waiting new substreams.
 pub(crate) const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Head start given to IPv6 addresses when dialing a dual-stack peer.
+pub(crate) const HAPPY_EYEBALLS_DELAY: Duration = Duration::from_millis(250);
+
+/// Maximum number of concurrent dial attempts to a single peer.
+pub(crate) const MAX_CONCURRENT_DIALS_PER_PEER: usize = 2;
+
 /// Maximum number of parallel dial attempts.
 pub(crate) const MAX_PARALLEL_DIALS: usize = 8;
 
+/// How often the addresses of the network interfaces are checked for changes.
+pub(crate) const INTERFACE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
 /// Connection endpoint.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Endpoint {
@@ -100,6 +125,15 @@ impl Endpoint {
         }
     }
 
+    /// Update `Multiaddr` of the [`Endpoint`] after the remote peer has changed its address.
+    #[cfg(feature = "quic")]
+    pub(crate) fn set_address(&mut self, new_address: Multiaddr) {
+        match self {
+            Self::Dialer { address, .. } => *address = new_address,
+            Self::Listener { address, .. } => *address = new_address,
+        }
+    }
+
     /// Get `ConnectionId` of the `Endpoint`.
     pub fn connection_id(&self) -> ConnectionId {
         match self {
@@ -112,6 +146,58 @@ impl Endpoint {
     pub fn is_listener(&self) -> bool {
         std::matches!(self, Self::Listener { .. })
     }
+
+    /// Is the connection relayed through another peer?
+    ///
+    /// Relayed connections are identified by the `/p2p-circuit` component in their address.
+    pub fn is_relayed(&self) -> bool {
+        is_relayed_address(self.address())
+    }
+}
+
+/// Application-defined reason for closing a connection.
+///
+/// Only transports with application close codes, currently QUIC, deliver the reason to the
+/// remote peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseReason {
+    /// Application close code.
+    pub code: u32,
+
+    /// Human-readable reason, may be empty.
+    pub reason: Vec<u8>,
+}
+
+/// Check if `address` is an address of a relayed connection.
+pub(crate) fn is_relayed_address(address: &Multiaddr) -> bool {
+    address
+        .iter()
+        .any(|protocol| std::matches!(protocol, multiaddr::Protocol::P2pCircuit))
+}
+
+/// Security protocol negotiated for a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityProtocol {
+    /// Noise.
+    Noise,
+
+    /// TLS 1.3, used by QUIC.
+    Tls,
+
+    /// DTLS, used by WebRTC.
+    ///
+    /// Peer identities are authenticated with a Noise handshake run over the DTLS channel.
+    Dtls,
+}
+
+/// Stream multiplexer negotiated for a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Muxer {
+    /// Yamux.
+    Yamux,
+
+    /// SCTP data channels, used by WebRTC.
+    Sctp,
 }
 
 /// Transport event.
@@ -124,11 +210,23 @@ pub(crate) enum TransportEvent {
 
         /// Endpoint.
         endpoint: Endpoint,
+
+        /// Negotiated security protocol.
+        security: SecurityProtocol,
+
+        /// Negotiated stream multiplexer.
+        ///
+        /// `None` if the transport provides streams natively, as QUIC does.
+        muxer: Option<Muxer>,
     },
 
+    /// Inbound connection is pending to be accepted or rejected by the transport manager.
     PendingInboundConnection {
         /// Connection ID.
         connection_id: ConnectionId,
+
+        /// Address of the remote peer.
+        address: Multiaddr,
     },
 
     /// Connection opened to remote but not yet negotiated.
@@ -148,6 +246,9 @@ pub(crate) enum TransportEvent {
 
         /// Connection ID.
         connection_id: ConnectionId,
+
+        /// Reason the remote peer gave for closing the connection.
+        reason: Option<CloseReason>,
     },
 
     /// Failed to dial remote peer.
@@ -170,6 +271,36 @@ pub(crate) enum TransportEvent {
         /// Errors.
         errors: Vec<(Multiaddr, DialError)>,
     },
+    /// Dials to a peer were aborted before they concluded.
+    ///
+    /// Only emitted by the transport manager.
+    DialAborted {
+        /// Peer ID.
+        peer: PeerId,
+    },
+
+    /// Dial started for a set of peer addresses succeeded.
+    ///
+    /// Only emitted by the transport manager, after the connection has been established.
+    DialSucceeded {
+        /// Peer ID.
+        peer: PeerId,
+
+        /// Address the connection was established over.
+        address: Multiaddr,
+    },
+
+    /// Transport started listening on a new address.
+    NewListenAddress {
+        /// Listen address.
+        address: Multiaddr,
+    },
+
+    /// Transport stopped listening on an address.
+    ExpiredListenAddress {
+        /// Listen address.
+        address: Multiaddr,
+    },
 }
 
 pub(crate) trait TransportBuilder {
@@ -186,6 +317,18 @@ pub(crate) trait Transport: Stream + Unpin + Send {
     /// Dial `address` and negotiate connection.
     fn dial(&mut self, connection_id: ConnectionId, address: Multiaddr) -> crate::Result<()>;
 
+    /// Dial `address` and negotiate connection, reporting the progress of the dial to `progress`.
+    ///
+    /// Transports that don't report dial progress dial the address normally.
+    fn dial_with_progress(
+        &mut self,
+        connection_id: ConnectionId,
+        address: Multiaddr,
+        _progress: DialProgressSink,
+    ) -> crate::Result<()> {
+        self.dial(connection_id, address)
+    }
+
     /// Accept negotiated connection.
     fn accept(&mut self, connection_id: ConnectionId) -> crate::Result<()>;
 
@@ -213,4 +356,36 @@ pub(crate) trait Transport: Stream + Unpin + Send {
     ///
     /// This is a no-op for connections that have already succeeded/canceled.
     fn cancel(&mut self, connection_id: ConnectionId);
+
+    /// Abort an outbound connection that is being dialed or negotiated.
+    ///
+    /// Returns `true` if the dial was aborted, in which case no events are reported for the
+    /// connection. Transports which can't abort a dial return `false` and report the outcome of
+    /// the dial as usual.
+    fn abort(&mut self, _connection_id: ConnectionId) -> bool {
+        false
+    }
+
+    /// Get the round-trip time estimate of an open connection.
+    ///
+    /// Returns `None` if the connection doesn't exist or if the transport doesn't track RTT.
+    fn connection_rtt(&self, _connection_id: ConnectionId) -> Option<Duration> {
+        None
+    }
+
+    /// Close an open connection, delivering `reason` to the remote peer.
+    ///
+    /// Returns `false` if the connection doesn't exist or if the transport can't deliver
+    /// application close reasons, in which case the connection is left open.
+    fn close_with_reason(&mut self, _connection_id: ConnectionId, _reason: &CloseReason) -> bool {
+        false
+    }
+
+    /// Take the reason the remote peer gave for closing the connection.
+    ///
+    /// Called once for each closed connection so the transport can release whatever it tracks
+    /// for it. Returns `None` if the remote peer didn't give a reason.
+    fn take_close_reason(&mut self, _connection_id: ConnectionId) -> Option<CloseReason> {
+        None
+    }
 }
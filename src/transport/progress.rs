@@ -0,0 +1,106 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Progress reporting for outbound dials.
+
+use crate::{types::ConnectionId, PeerId};
+
+use tokio::sync::mpsc::{error::TrySendError, Sender};
+
+use std::{net::SocketAddr, time::Instant};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::transport::progress";
+
+/// Milestone reached while establishing an outbound connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialMilestone {
+    /// Dialed address was resolved to a socket address.
+    AddressResolved {
+        /// Resolved socket address.
+        address: SocketAddr,
+    },
+
+    /// Transport-level connection to the remote peer was established.
+    TransportConnected,
+
+    /// Security handshake was started.
+    SecurityHandshakeStarted,
+
+    /// Security handshake was completed and the identity of the remote peer verified.
+    SecurityHandshakeCompleted {
+        /// Peer ID of the remote peer.
+        peer: PeerId,
+    },
+
+    /// Stream muxer was negotiated.
+    MuxerNegotiated,
+}
+
+/// Progress event of an outbound dial.
+#[derive(Debug, Clone)]
+pub struct DialProgress {
+    /// Connection ID of the dial.
+    pub connection_id: ConnectionId,
+
+    /// Reached milestone.
+    pub milestone: DialMilestone,
+
+    /// When was the milestone reached.
+    pub timestamp: Instant,
+}
+
+/// Sink for the progress events of a single dial.
+///
+/// Events are sent with [`Sender::try_send()`] so a slow reader never stalls the dial, an event
+/// that doesn't fit into the channel is dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct DialProgressSink {
+    /// Connection ID of the dial.
+    connection_id: ConnectionId,
+
+    /// TX channel for sending the progress events.
+    tx: Sender<DialProgress>,
+}
+
+impl DialProgressSink {
+    /// Create new [`DialProgressSink`].
+    pub(crate) fn new(connection_id: ConnectionId, tx: Sender<DialProgress>) -> Self {
+        Self { connection_id, tx }
+    }
+
+    /// Report `milestone`.
+    pub(crate) fn report(&self, milestone: DialMilestone) {
+        let event = DialProgress {
+            connection_id: self.connection_id,
+            milestone,
+            timestamp: Instant::now(),
+        };
+
+        if let Err(TrySendError::Full(event)) = self.tx.try_send(event) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                connection_id = ?self.connection_id,
+                milestone = ?event.milestone,
+                "progress channel is full, dropping event",
+            );
+        }
+    }
+}
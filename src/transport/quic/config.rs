@@ -24,7 +24,7 @@ use crate::transport::{CONNECTION_OPEN_TIMEOUT, SUBSTREAM_OPEN_TIMEOUT};
 
 use multiaddr::Multiaddr;
 
-use std::time::Duration;
+use std::{net::IpAddr, time::Duration};
 
 /// QUIC transport configuration.
 #[derive(Debug)]
@@ -34,6 +34,19 @@ pub struct Config {
     /// Default listen addres is `/ip4/127.0.0.1/udp/0/quic-v1`.
     pub listen_addresses: Vec<Multiaddr>,
 
+    /// Whether to set `SO_REUSEADDR` on listening sockets.
+    ///
+    /// Defaults to `false`.
+    pub reuse_address: bool,
+
+    /// Whether to set `SO_REUSEPORT` on listening sockets, allowing several processes to listen
+    /// on the same port.
+    ///
+    /// `SO_REUSEPORT` is only supported on Unix platforms.
+    ///
+    /// Defaults to `false`.
+    pub reuse_port: bool,
+
     /// Connection open timeout.
     ///
     /// How long should litep2p wait for a connection to be opend before the host
@@ -45,14 +58,98 @@ pub struct Config {
     /// How long should litep2p wait for a substream to be opened before considering
     /// the substream rejected.
     pub substream_open_timeout: Duration,
+
+    /// Allow remote peers to migrate connections to a new network path.
+    ///
+    /// If enabled, a connection stays open when the remote peer's address changes, for example,
+    /// when it moves from Wi-Fi to a cellular network, and protocols are notified of the new
+    /// address with [`TransportEvent::ConnectionAddressChanged`].
+    ///
+    /// Enabled by default.
+    ///
+    /// [`TransportEvent::ConnectionAddressChanged`]:
+    /// crate::protocol::TransportEvent::ConnectionAddressChanged
+    pub allow_migration: bool,
+
+    /// Maximum idle timeout.
+    ///
+    /// Connection is closed and reported closed if nothing is received from the remote peer
+    /// within the timeout, which detects peers that have gone away without closing the connection.
+    /// The timeout used for a connection is the smaller of the timeouts of the two peers.
+    ///
+    /// Defaults to `10` seconds.
+    pub max_idle_timeout: Duration,
+
+    /// Keep-alive interval.
+    ///
+    /// If set, a QUIC `PING` frame is sent when nothing has been sent over the connection for the
+    /// interval, keeping connections with a live but silent remote peer from reaching the idle
+    /// timeout. The interval should be a fraction of `max_idle_timeout`. This is independent of
+    /// the libp2p ping protocol.
+    ///
+    /// Disabled by default.
+    pub keep_alive_interval: Option<Duration>,
+
+    /// Enable 0-RTT connection resumption.
+    ///
+    /// If enabled, the session tickets received from a peer are cached and used to resume the
+    /// next connection to the same peer. A resumed connection is reported established before its
+    /// handshake has completed and substreams opened with
+    /// [`TransportService::open_substream_with_early_data()`] are sent as 0-RTT data. The
+    /// transport also accepts 0-RTT data from remote peers.
+    ///
+    /// 0-RTT data can be replayed by an attacker, see
+    /// [`TransportService::open_substream_with_early_data()`] for details.
+    ///
+    /// Disabled by default.
+    ///
+    /// [`TransportService::open_substream_with_early_data()`]:
+    /// crate::protocol::TransportService::open_substream_with_early_data
+    pub zero_rtt: bool,
+
+    /// Local IP address the sockets of outbound connections are bound to.
+    ///
+    /// On hosts with several network interfaces, this selects the source address of outbound
+    /// datagrams. The address is only used for dialing addresses of the same IP version, other
+    /// addresses are dialed from the unspecified address.
+    ///
+    /// By default the source address is chosen by the operating system.
+    pub outbound_bind_address: Option<IpAddr>,
+
+    /// Network interface the listening and outbound sockets are bound to.
+    ///
+    /// Only supported on Linux and Android.
+    ///
+    /// Defaults to `None`.
+    pub bind_device: Option<String>,
+}
+
+impl Config {
+    /// Create QUIC transport parameters from the configuration.
+    pub(crate) fn transport_config(&self) -> quinn::TransportConfig {
+        let mut transport_config = quinn::TransportConfig::default();
+        let timeout = quinn::IdleTimeout::try_from(self.max_idle_timeout).expect("to succeed");
+        transport_config.max_idle_timeout(Some(timeout));
+        transport_config.keep_alive_interval(self.keep_alive_interval);
+
+        transport_config
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             listen_addresses: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().expect("valid address")],
+            reuse_address: false,
+            reuse_port: false,
             connection_open_timeout: CONNECTION_OPEN_TIMEOUT,
             substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
+            allow_migration: true,
+            max_idle_timeout: CONNECTION_OPEN_TIMEOUT,
+            keep_alive_interval: None,
+            zero_rtt: false,
+            outbound_bind_address: None,
+            bind_device: None,
         }
     }
 }
@@ -20,13 +20,13 @@
 
 //! QUIC connection.
 
-use std::time::Duration;
+use std::{net::SocketAddr, time::Duration};
 
 use crate::{
     config::Role,
     error::{Error, NegotiationError, SubstreamError},
     multistream_select::{dialer_select_proto, listener_select_proto, Negotiated, Version},
-    protocol::{Direction, Permit, ProtocolCommand, ProtocolSet},
+    protocol::{Direction, NegotiationObserver, Permit, ProtocolCommand, ProtocolSet},
     substream,
     transport::{
         quic::substream::{NegotiatingSubstream, Substream},
@@ -36,8 +36,14 @@ use crate::{
     BandwidthSink, PeerId,
 };
 
-use futures::{future::BoxFuture, stream::FuturesUnordered, AsyncRead, AsyncWrite, StreamExt};
-use quinn::{Connection as QuinnConnection, RecvStream, SendStream};
+use futures::{
+    future::{BoxFuture, OptionFuture, Shared},
+    stream::FuturesUnordered,
+    AsyncRead, AsyncWrite, StreamExt,
+};
+use multiaddr::{Multiaddr, Protocol};
+use quinn::{Connection as QuinnConnection, RecvStream, SendStream, ZeroRttAccepted};
+use tracing::Instrument;
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::quic::connection";
@@ -101,6 +107,9 @@ pub struct QuicConnection {
     /// QUIC connection.
     connection: QuinnConnection,
 
+    /// Last known address of the remote peer.
+    remote_address: SocketAddr,
+
     /// Protocol set.
     protocol_set: ProtocolSet,
 
@@ -110,6 +119,15 @@ pub struct QuicConnection {
     /// Pending substreams.
     pending_substreams:
         FuturesUnordered<BoxFuture<'static, Result<NegotiatedSubstream, ConnectionError>>>,
+
+    /// Resolves to whether the remote peer accepted 0-RTT data once the handshake has completed.
+    ///
+    /// Only set for outbound connections which were resumed with 0-RTT and whose handshake is
+    /// still in progress.
+    early_data: Option<Shared<ZeroRttAccepted>>,
+
+    /// Outbound substreams which must not be opened before the handshake has completed.
+    deferred_substreams: Vec<(ProtocolName, Vec<ProtocolName>, SubstreamId, Permit)>,
 }
 
 impl QuicConnection {
@@ -121,15 +139,19 @@ impl QuicConnection {
         protocol_set: ProtocolSet,
         bandwidth_sink: BandwidthSink,
         substream_open_timeout: Duration,
+        early_data: Option<Shared<ZeroRttAccepted>>,
     ) -> Self {
         Self {
             peer,
             endpoint,
+            remote_address: connection.remote_address(),
             connection,
             protocol_set,
             bandwidth_sink,
             substream_open_timeout,
+            early_data,
             pending_substreams: FuturesUnordered::new(),
+            deferred_substreams: Vec::new(),
         }
     }
 
@@ -152,6 +174,32 @@ impl QuicConnection {
         Ok((socket, ProtocolName::from(protocol.to_string())))
     }
 
+    /// Accept an inbound substream as the listener.
+    ///
+    /// Besides the negotiation result, returns the protocols the remote proposed but which
+    /// aren't supported locally.
+    async fn accept_protocol<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: S,
+        protocols: Vec<&str>,
+    ) -> (
+        Result<(Negotiated<S>, ProtocolName), NegotiationError>,
+        Vec<ProtocolName>,
+    ) {
+        tracing::trace!(target: LOG_TARGET, ?protocols, "accepting protocols");
+
+        let mut future = listener_select_proto(stream, protocols);
+        let result = match (&mut future).await {
+            Err(error) => Err(NegotiationError::MultistreamSelectError(error)),
+            Ok((protocol, socket)) => {
+                tracing::trace!(target: LOG_TARGET, ?protocol, "protocol negotiated");
+
+                Ok((socket, ProtocolName::from(protocol.to_string())))
+            }
+        };
+
+        (result, future.rejected_protocols())
+    }
+
     /// Open substream for `protocol`.
     async fn open_substream(
         handle: QuinnConnection,
@@ -159,6 +207,8 @@ impl QuicConnection {
         substream_id: SubstreamId,
         protocol: ProtocolName,
         fallback_names: Vec<ProtocolName>,
+        peer: PeerId,
+        observer: Option<NegotiationObserver>,
     ) -> Result<NegotiatedSubstream, SubstreamError> {
         tracing::debug!(target: LOG_TARGET, ?protocol, ?substream_id, "open substream");
 
@@ -173,7 +223,19 @@ impl QuicConnection {
             .chain(fallback_names.iter().map(|protocol| &**protocol))
             .collect();
 
-        let (io, protocol) = Self::negotiate_protocol(stream, &Role::Dialer, protocols).await?;
+        let result = Self::negotiate_protocol(stream, &Role::Dialer, protocols).await;
+
+        if let Some(observer) = observer {
+            observer.observe(
+                peer,
+                Direction::Outbound(substream_id),
+                std::iter::once(protocol).chain(fallback_names).collect(),
+                result.as_ref().ok().map(|(_, protocol)| protocol.clone()),
+                Vec::new(),
+            );
+        }
+
+        let (io, protocol) = result?;
 
         tracing::trace!(
             target: LOG_TARGET,
@@ -201,6 +263,8 @@ impl QuicConnection {
         protocols: Vec<ProtocolName>,
         substream_id: SubstreamId,
         permit: Permit,
+        peer: PeerId,
+        observer: Option<NegotiationObserver>,
     ) -> Result<NegotiatedSubstream, NegotiationError> {
         tracing::trace!(
             target: LOG_TARGET,
@@ -208,8 +272,23 @@ impl QuicConnection {
             "accept inbound substream"
         );
 
-        let protocols = protocols.iter().map(|protocol| &**protocol).collect::<Vec<&str>>();
-        let (io, protocol) = Self::negotiate_protocol(stream, &Role::Listener, protocols).await?;
+        let (result, unsupported) = Self::accept_protocol(
+            stream,
+            protocols.iter().map(|protocol| &**protocol).collect(),
+        )
+        .await;
+
+        if let Some(observer) = observer {
+            observer.observe(
+                peer,
+                Direction::Inbound,
+                protocols,
+                result.as_ref().ok().map(|(_, protocol)| protocol.clone()),
+                unsupported,
+            );
+        }
+
+        let (io, protocol) = result?;
 
         tracing::trace!(
             target: LOG_TARGET,
@@ -231,27 +310,182 @@ impl QuicConnection {
         })
     }
 
+    /// Check if the remote peer has migrated the connection to a new address.
+    ///
+    /// `quinn` doesn't notify about path changes so the remote address is checked each time the
+    /// connection handles an event. If the address has changed, the endpoint of the connection
+    /// is updated and protocols are notified of the new address. Substreams are not affected by
+    /// the migration.
+    async fn check_migration(&mut self) {
+        let remote_address = self.connection.remote_address();
+
+        if remote_address == self.remote_address {
+            return;
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            peer = ?self.peer,
+            connection_id = ?self.endpoint.connection_id(),
+            old_address = ?self.remote_address,
+            new_address = ?remote_address,
+            "connection migrated to new address",
+        );
+
+        self.remote_address = remote_address;
+        self.endpoint.set_address(
+            Multiaddr::empty()
+                .with(Protocol::from(remote_address.ip()))
+                .with(Protocol::Udp(remote_address.port()))
+                .with(Protocol::QuicV1)
+                .with(Protocol::P2p(self.peer.into())),
+        );
+
+        if let Err(error) = self
+            .protocol_set
+            .report_connection_address_changed(self.peer, self.endpoint.clone())
+            .await
+        {
+            tracing::debug!(
+                target: LOG_TARGET,
+                peer = ?self.peer,
+                connection_id = ?self.endpoint.connection_id(),
+                ?error,
+                "failed to report connection address change",
+            );
+        }
+    }
+
+    /// Start opening outbound substream for `protocol`.
+    fn start_open_substream(
+        &mut self,
+        protocol: ProtocolName,
+        fallback_names: Vec<ProtocolName>,
+        substream_id: SubstreamId,
+        permit: Permit,
+    ) {
+        let connection = self.connection.clone();
+        let substream_open_timeout = self.substream_open_timeout;
+        let peer = self.peer;
+        let observer = self.protocol_set.negotiation_observer();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?protocol,
+            ?fallback_names,
+            ?substream_id,
+            "open substream"
+        );
+
+        self.pending_substreams.push(Box::pin(async move {
+            match tokio::time::timeout(
+                substream_open_timeout,
+                Self::open_substream(
+                    connection,
+                    permit,
+                    substream_id,
+                    protocol.clone(),
+                    fallback_names,
+                    peer,
+                    observer,
+                ),
+            )
+            .await
+            {
+                Ok(Ok(substream)) => Ok(substream),
+                Ok(Err(error)) => Err(ConnectionError::FailedToNegotiate {
+                    protocol: Some(protocol),
+                    substream_id: Some(substream_id),
+                    error,
+                }),
+                Err(_) => Err(ConnectionError::Timeout {
+                    protocol: None,
+                    substream_id: None,
+                }),
+            }
+        }));
+    }
+
+    /// Handle completed handshake of a connection resumed with 0-RTT.
+    ///
+    /// If the remote peer rejected the 0-RTT data, the substreams opened before the handshake
+    /// completed fail to negotiate and are reported as failed.
+    fn on_handshake_completed(&mut self, accepted: bool) {
+        tracing::trace!(
+            target: LOG_TARGET,
+            peer = ?self.peer,
+            connection_id = ?self.endpoint.connection_id(),
+            ?accepted,
+            num_deferred = ?self.deferred_substreams.len(),
+            "0-RTT handshake completed",
+        );
+
+        self.early_data = None;
+
+        for (protocol, fallback_names, substream_id, permit) in
+            std::mem::take(&mut self.deferred_substreams)
+        {
+            self.start_open_substream(protocol, fallback_names, substream_id, permit);
+        }
+    }
+
     /// Start event loop for [`QuicConnection`].
-    pub async fn start(mut self) -> crate::Result<()> {
+    pub async fn start(self) -> crate::Result<()> {
+        let span = tracing::debug_span!(
+            target: LOG_TARGET,
+            "connection",
+            peer = ?self.peer,
+            connection_id = ?self.endpoint.connection_id(),
+        );
+
+        self.event_loop().instrument(span).await
+    }
+
+    /// Connection event loop.
+    async fn event_loop(mut self) -> crate::Result<()> {
         self.protocol_set
             .report_connection_established(self.peer, self.endpoint.clone())
             .await?;
 
         loop {
+            self.check_migration().await;
+
             tokio::select! {
+                Some(accepted) = OptionFuture::from(self.early_data.clone()),
+                    if self.early_data.is_some() =>
+                {
+                    self.on_handshake_completed(accepted);
+                }
                 event = self.connection.accept_bi() => match event {
                     Ok((send_stream, receive_stream)) => {
+                        if !self.protocol_set.try_accept_substream() {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                peer = ?self.peer,
+                                "substream rate limit exceeded, closing inbound substream",
+                            );
+                            continue;
+                        }
 
                         let substream = self.protocol_set.next_substream_id();
                         let protocols = self.protocol_set.protocols();
                         let permit = self.protocol_set.try_get_permit().ok_or(Error::ConnectionClosed)?;
                         let stream = NegotiatingSubstream::new(send_stream, receive_stream);
                         let substream_open_timeout = self.substream_open_timeout;
+                        let peer = self.peer;
+                        let observer = self.protocol_set.negotiation_observer();
 
                         self.pending_substreams.push(Box::pin(async move {
                             match tokio::time::timeout(
                                 substream_open_timeout,
-                                Self::accept_substream(stream, protocols, substream, permit),
+                                Self::accept_substream(
+                                    stream,
+                                    protocols,
+                                    substream,
+                                    permit,
+                                    peer,
+                                    observer,
+                                ),
                             )
                             .await
                             {
@@ -302,11 +536,14 @@ impl QuicConnection {
                             let substream_id = substream.substream_id;
                             let direction = substream.direction;
                             let bandwidth_sink = self.bandwidth_sink.clone();
+                            let priority = self.protocol_set.substream_priority(&protocol).value();
+                            // fails only if the stream has already been closed by the remote
+                            let _ = substream.sender.set_priority(i32::from(priority));
                             let substream = substream::Substream::new_quic(
                                 self.peer,
                                 substream_id,
                                 Substream::new(
-                                    substream.permit,
+                                    self.protocol_set.substream_permit(&protocol, substream.permit),
                                     substream.sender,
                                     substream.receiver,
                                     bandwidth_sink
@@ -330,43 +567,26 @@ impl QuicConnection {
                         );
                         return self.protocol_set.report_connection_closed(self.peer, self.endpoint.connection_id()).await;
                     }
-                    Some(ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit }) => {
-                        let connection = self.connection.clone();
-                        let substream_open_timeout = self.substream_open_timeout;
-
-                        tracing::trace!(
-                            target: LOG_TARGET,
-                            ?protocol,
-                            ?fallback_names,
-                            ?substream_id,
-                            "open substream"
-                        );
-
-                        self.pending_substreams.push(Box::pin(async move {
-                            match tokio::time::timeout(
-                                substream_open_timeout,
-                                Self::open_substream(
-                                    connection,
-                                    permit,
-                                    substream_id,
-                                    protocol.clone(),
-                                    fallback_names,
-                                ),
-                            )
-                            .await
-                            {
-                                Ok(Ok(substream)) => Ok(substream),
-                                Ok(Err(error)) => Err(ConnectionError::FailedToNegotiate {
-                                    protocol: Some(protocol),
-                                    substream_id: Some(substream_id),
-                                    error,
-                                }),
-                                Err(_) => Err(ConnectionError::Timeout {
-                                    protocol: None,
-                                    substream_id: None
-                                }),
-                            }
-                        }));
+                    Some(ProtocolCommand::OpenSubstream {
+                        protocol,
+                        fallback_names,
+                        substream_id,
+                        permit,
+                        early_data,
+                    }) => {
+                        // until the handshake of a resumed connection has completed, only
+                        // substreams whose data the protocol has marked 0-RTT-safe are opened
+                        match self.early_data.is_some() && !early_data {
+                            true => self
+                                .deferred_substreams
+                                .push((protocol, fallback_names, substream_id, permit)),
+                            false => self.start_open_substream(
+                                protocol,
+                                fallback_names,
+                                substream_id,
+                                permit,
+                            ),
+                        }
                     }
                     Some(ProtocolCommand::ForceClose) => {
                         tracing::debug!(
@@ -19,17 +19,19 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    crypto::{ed25519::Keypair, tls::make_server_config},
+    crypto::{tls::make_server_config, Keypair},
     error::AddressError,
+    transport::common::listener::{bind_to_device, ipv6_scope_id},
     PeerId,
 };
 
 use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, Stream, StreamExt};
 use multiaddr::{Multiaddr, Protocol};
-use quinn::{Connecting, Endpoint, ServerConfig};
+use quinn::{Connecting, Endpoint, EndpointConfig, ServerConfig, TokioRuntime};
+use socket2::{Domain, Socket, Type};
 
 use std::{
-    net::{IpAddr, SocketAddr},
+    net::{IpAddr, SocketAddr, SocketAddrV6},
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
@@ -55,15 +57,33 @@ impl QuicListener {
     pub fn new(
         keypair: &Keypair,
         addresses: Vec<Multiaddr>,
+        reuse_address: bool,
+        reuse_port: bool,
+        allow_migration: bool,
+        zero_rtt: bool,
+        bind_device: Option<&str>,
+        transport_config: Arc<quinn::TransportConfig>,
     ) -> crate::Result<(Self, Vec<Multiaddr>)> {
         let mut listeners: Vec<Endpoint> = Vec::new();
         let mut listen_addresses = Vec::new();
 
         for address in addresses.into_iter() {
             let (listen_address, _) = Self::get_socket_address(&address)?;
-            let crypto_config = Arc::new(make_server_config(keypair).expect("to succeed"));
-            let server_config = ServerConfig::with_crypto(crypto_config);
-            let listener = Endpoint::server(server_config, listen_address).unwrap();
+            let mut crypto_config = make_server_config(keypair).expect("to succeed");
+            if zero_rtt {
+                // `quinn` only supports accepting either no early data or an unlimited amount
+                crypto_config.max_early_data_size = u32::MAX;
+            }
+            let mut server_config = ServerConfig::with_crypto(Arc::new(crypto_config));
+            server_config.migration(allow_migration);
+            server_config.transport_config(Arc::clone(&transport_config));
+            let socket = Self::bind(listen_address, reuse_address, reuse_port, bind_device)?;
+            let listener = Endpoint::new(
+                EndpointConfig::default(),
+                Some(server_config),
+                socket,
+                TokioRuntime,
+            )?;
 
             let listen_address = listener.local_addr()?;
             listen_addresses.push(listen_address);
@@ -100,6 +120,31 @@ impl QuicListener {
         ))
     }
 
+    /// Bind UDP socket to `address`, setting the requested socket options before binding.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    fn bind(
+        address: SocketAddr,
+        reuse_address: bool,
+        reuse_port: bool,
+        bind_device: Option<&str>,
+    ) -> std::io::Result<std::net::UdpSocket> {
+        let domain = match address {
+            SocketAddr::V4(_) => Domain::IPV4,
+            SocketAddr::V6(_) => Domain::IPV6,
+        };
+        let socket = Socket::new(domain, Type::DGRAM, Some(socket2::Protocol::UDP))?;
+
+        socket.set_reuse_address(reuse_address)?;
+        #[cfg(unix)]
+        socket.set_reuse_port(reuse_port)?;
+        if let Some(device) = bind_device {
+            bind_to_device(&socket, device)?;
+        }
+        socket.bind(&address.into())?;
+
+        Ok(socket.into())
+    }
+
     /// Extract socket address and `PeerId`, if found, from `address`.
     pub fn get_socket_address(
         address: &Multiaddr,
@@ -108,6 +153,22 @@ impl QuicListener {
 
         let mut iter = address.iter();
         let socket_address = match iter.next() {
+            Some(Protocol::Ip6zone(zone)) => {
+                let scope_id = ipv6_scope_id(&zone)?;
+
+                match (iter.next(), iter.next()) {
+                    (Some(Protocol::Ip6(address)), Some(Protocol::Udp(port))) =>
+                        SocketAddr::V6(SocketAddrV6::new(address, port, 0, scope_id)),
+                    protocol => {
+                        tracing::error!(
+                            target: LOG_TARGET,
+                            ?protocol,
+                            "invalid protocol, expected `Ip6` followed by `Udp`",
+                        );
+                        return Err(AddressError::InvalidProtocol);
+                    }
+                }
+            }
             Some(Protocol::Ip6(address)) => match iter.next() {
                 Some(Protocol::Udp(port)) => SocketAddr::new(IpAddr::V6(address), port),
                 protocol => {
@@ -241,11 +302,30 @@ mod tests {
             &"/ip6/::1/udp/8888/quic-v1/utp".parse().expect("valid multiaddress")
         )
         .is_err());
+
+        let (address, _) = QuicListener::get_socket_address(
+            &"/ip6zone/7/ip6/fe80::1/udp/8888/quic-v1".parse().expect("valid multiaddress"),
+        )
+        .unwrap();
+        match address {
+            SocketAddr::V6(address) => assert_eq!(address.scope_id(), 7),
+            address => panic!("invalid address: {address:?}"),
+        }
     }
 
     #[tokio::test]
     async fn no_listeners() {
-        let (mut listener, _) = QuicListener::new(&Keypair::generate(), Vec::new()).unwrap();
+        let (mut listener, _) = QuicListener::new(
+            &Keypair::generate_ed25519(),
+            Vec::new(),
+            false,
+            false,
+            true,
+            false,
+            None,
+            Default::default(),
+        )
+        .unwrap();
 
         futures::future::poll_fn(|cx| match listener.poll_next_unpin(cx) {
             Poll::Pending => Poll::Ready(()),
@@ -254,21 +334,79 @@ mod tests {
         .await;
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rebind_with_reuse_address() {
+        let address: Multiaddr = "/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap();
+        let keypair = Keypair::generate_ed25519();
+        let (listener, listen_addresses) = QuicListener::new(
+            &keypair,
+            vec![address],
+            true,
+            true,
+            true,
+            false,
+            None,
+            Default::default(),
+        )
+        .unwrap();
+
+        // with `SO_REUSEPORT`, the port can be shared while the first listener is still open
+        let (shared, shared_addresses) = QuicListener::new(
+            &keypair,
+            listen_addresses.clone(),
+            true,
+            true,
+            true,
+            false,
+            None,
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(shared_addresses, listen_addresses);
+
+        drop(listener);
+        drop(shared);
+
+        let (_listener, rebound_addresses) = QuicListener::new(
+            &keypair,
+            listen_addresses.clone(),
+            true,
+            true,
+            true,
+            false,
+            None,
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(rebound_addresses, listen_addresses);
+    }
+
     #[tokio::test]
     async fn one_listener() {
         let address: Multiaddr = "/ip6/::1/udp/0/quic-v1".parse().unwrap();
-        let keypair = Keypair::generate();
-        let peer = PeerId::from_public_key(&keypair.public().into());
-        let (mut listener, listen_addresses) =
-            QuicListener::new(&keypair, vec![address.clone()]).unwrap();
+        let keypair = Keypair::generate_ed25519();
+        let peer = keypair.to_peer_id();
+        let (mut listener, listen_addresses) = QuicListener::new(
+            &keypair,
+            vec![address.clone()],
+            false,
+            false,
+            true,
+            false,
+            None,
+            Default::default(),
+        )
+        .unwrap();
         let Some(Protocol::Udp(port)) =
             listen_addresses.iter().next().unwrap().clone().iter().skip(1).next()
         else {
             panic!("invalid address");
         };
 
-        let crypto_config =
-            Arc::new(make_client_config(&Keypair::generate(), Some(peer)).expect("to succeed"));
+        let crypto_config = Arc::new(
+            make_client_config(&Keypair::generate_ed25519(), Some(peer)).expect("to succeed"),
+        );
         let client_config = ClientConfig::new(crypto_config);
         let client =
             Endpoint::client(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)).unwrap();
@@ -297,11 +435,20 @@ mod tests {
 
         let address1: Multiaddr = "/ip6/::1/udp/0/quic-v1".parse().unwrap();
         let address2: Multiaddr = "/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap();
-        let keypair = Keypair::generate();
-        let peer = PeerId::from_public_key(&keypair.public().into());
+        let keypair = Keypair::generate_ed25519();
+        let peer = keypair.to_peer_id();
 
-        let (mut listener, listen_addresses) =
-            QuicListener::new(&keypair, vec![address1, address2]).unwrap();
+        let (mut listener, listen_addresses) = QuicListener::new(
+            &keypair,
+            vec![address1, address2],
+            false,
+            false,
+            true,
+            false,
+            None,
+            Default::default(),
+        )
+        .unwrap();
 
         let Some(Protocol::Udp(port1)) =
             listen_addresses.iter().next().unwrap().clone().iter().skip(1).next()
@@ -315,8 +462,9 @@ mod tests {
             panic!("invalid address");
         };
 
-        let crypto_config1 =
-            Arc::new(make_client_config(&Keypair::generate(), Some(peer)).expect("to succeed"));
+        let crypto_config1 = Arc::new(
+            make_client_config(&Keypair::generate_ed25519(), Some(peer)).expect("to succeed"),
+        );
         let client_config1 = ClientConfig::new(crypto_config1);
         let client1 =
             Endpoint::client(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)).unwrap();
@@ -328,8 +476,9 @@ mod tests {
             )
             .unwrap();
 
-        let crypto_config2 =
-            Arc::new(make_client_config(&Keypair::generate(), Some(peer)).expect("to succeed"));
+        let crypto_config2 = Arc::new(
+            make_client_config(&Keypair::generate_ed25519(), Some(peer)).expect("to succeed"),
+        );
         let client_config2 = ClientConfig::new(crypto_config2);
         let client2 =
             Endpoint::client(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)).unwrap();
@@ -366,8 +515,8 @@ mod tests {
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
             .try_init();
 
-        let keypair = Keypair::generate();
-        let peer = PeerId::from_public_key(&keypair.public().into());
+        let keypair = Keypair::generate_ed25519();
+        let peer = keypair.to_peer_id();
 
         let (mut listener, listen_addresses) = QuicListener::new(
             &keypair,
@@ -375,6 +524,12 @@ mod tests {
                 "/ip6/::1/udp/0/quic-v1".parse().unwrap(),
                 "/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap(),
             ],
+            false,
+            false,
+            true,
+            false,
+            None,
+            Default::default(),
         )
         .unwrap();
 
@@ -384,8 +539,9 @@ mod tests {
             panic!("invalid address");
         };
 
-        let crypto_config1 =
-            Arc::new(make_client_config(&Keypair::generate(), Some(peer)).expect("to succeed"));
+        let crypto_config1 = Arc::new(
+            make_client_config(&Keypair::generate_ed25519(), Some(peer)).expect("to succeed"),
+        );
         let client_config1 = ClientConfig::new(crypto_config1);
         let client1 =
             Endpoint::client(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)).unwrap();
@@ -397,8 +553,9 @@ mod tests {
             )
             .unwrap();
 
-        let crypto_config2 =
-            Arc::new(make_client_config(&Keypair::generate(), Some(peer)).expect("to succeed"));
+        let crypto_config2 = Arc::new(
+            make_client_config(&Keypair::generate_ed25519(), Some(peer)).expect("to succeed"),
+        );
         let client_config2 = ClientConfig::new(crypto_config2);
         let client2 =
             Endpoint::client(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)).unwrap();
@@ -23,27 +23,40 @@
 //! QUIC transport.
 
 use crate::{
-    crypto::tls::make_client_config,
+    crypto::{tls::make_client_config, Keypair},
     error::{AddressError, DialError, Error, QuicError},
     transport::{
+        common::listener::bind_to_device,
         manager::TransportHandle,
         quic::{config::Config as QuicConfig, connection::QuicConnection, listener::QuicListener},
-        Endpoint as Litep2pEndpoint, Transport, TransportBuilder, TransportEvent,
+        CloseReason, Endpoint as Litep2pEndpoint, SecurityProtocol, Transport, TransportBuilder,
+        TransportEvent,
     },
     types::ConnectionId,
     PeerId,
 };
 
-use futures::{future::BoxFuture, stream::FuturesUnordered, Stream, StreamExt};
+use futures::{
+    future::{BoxFuture, FutureExt, Shared},
+    stream::FuturesUnordered,
+    Stream, StreamExt,
+};
 use multiaddr::{Multiaddr, Protocol};
-use quinn::{ClientConfig, Connecting, Connection, Endpoint, IdleTimeout};
+use quinn::{
+    ClientConfig, Connecting, Connection, ConnectionError, Endpoint, EndpointConfig, TokioRuntime,
+    VarInt, ZeroRttAccepted,
+};
+use rustls::client::{ClientSessionMemoryCache, StoresClientSessions};
+use socket2::{Domain, Socket, Type};
 
 use std::{
     collections::{HashMap, HashSet},
+    io,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 pub(crate) use substream::Substream;
@@ -57,6 +70,12 @@ pub mod config;
 /// Logging target for the file.
 const LOG_TARGET: &str = "litep2p::quic";
 
+/// How many session tickets are cached for each peer.
+const SESSION_TICKETS_PER_PEER: usize = 4;
+
+/// How many peers session tickets are cached for.
+const MAX_SESSION_CACHES: usize = 1024;
+
 #[derive(Debug)]
 struct NegotiatedConnection {
     /// Remote peer ID.
@@ -64,6 +83,10 @@ struct NegotiatedConnection {
 
     /// QUIC connection.
     connection: Connection,
+
+    /// Resolves to whether the remote peer accepted 0-RTT data, if the connection was resumed
+    /// with 0-RTT.
+    early_data: Option<Shared<ZeroRttAccepted>>,
 }
 
 /// QUIC transport object.
@@ -107,6 +130,17 @@ pub(crate) struct QuicTransport {
 
     /// Canceled raw connections.
     canceled: HashSet<ConnectionId>,
+
+    /// Accepted connections, used for querying their RTT estimates and close reasons.
+    ///
+    /// Removed once the transport manager has taken the close reason of the connection.
+    connections: HashMap<ConnectionId, Connection>,
+
+    /// Session tickets received from remote peers, used for 0-RTT connection resumption.
+    ///
+    /// Each peer has its own cache so a connection can only ever be resumed with a ticket issued
+    /// by the peer that is being dialed.
+    session_caches: HashMap<PeerId, Arc<ClientSessionMemoryCache>>,
 }
 
 impl QuicTransport {
@@ -135,10 +169,131 @@ impl QuicTransport {
                 );
             };
 
-            (connection_id, Ok(NegotiatedConnection { peer, connection }))
+            (
+                connection_id,
+                Ok(NegotiatedConnection {
+                    peer,
+                    connection,
+                    early_data: None,
+                }),
+            )
         }));
     }
 
+    /// Get session ticket cache of `peer`.
+    fn session_storage(&mut self, peer: PeerId) -> Arc<dyn StoresClientSessions> {
+        if self.session_caches.len() >= MAX_SESSION_CACHES
+            && !self.session_caches.contains_key(&peer)
+        {
+            // evicting a peer only means its next connection is not resumed with 0-RTT
+            if let Some(evicted) = self.session_caches.keys().next().copied() {
+                self.session_caches.remove(&evicted);
+            }
+        }
+
+        self.session_caches
+            .entry(peer)
+            .or_insert_with(|| ClientSessionMemoryCache::new(SESSION_TICKETS_PER_PEER))
+            .clone()
+    }
+
+    /// Create client configuration for dialing `peer`.
+    ///
+    /// If `session_storage` is provided, 0-RTT is enabled for the connection.
+    fn client_config(
+        keypair: &Keypair,
+        peer: PeerId,
+        transport_config: Arc<quinn::TransportConfig>,
+        session_storage: Option<Arc<dyn StoresClientSessions>>,
+    ) -> ClientConfig {
+        let mut crypto_config = make_client_config(keypair, Some(peer)).expect("to succeed");
+
+        if let Some(session_storage) = session_storage {
+            crypto_config.enable_early_data = true;
+            crypto_config.session_storage = session_storage;
+        }
+
+        let mut client_config = ClientConfig::new(Arc::new(crypto_config));
+        client_config.transport_config(transport_config);
+        client_config
+    }
+
+    /// Get the local address of the client endpoint used for dialing `address`.
+    ///
+    /// `bind_address` is only used if it's of the same IP version as `address`.
+    fn client_listen_address(
+        address: &Multiaddr,
+        bind_address: Option<IpAddr>,
+    ) -> Result<SocketAddr, AddressError> {
+        let unspecified = match address.iter().next() {
+            Some(Protocol::Ip6(_)) | Some(Protocol::Ip6zone(_)) =>
+                IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            Some(Protocol::Ip4(_)) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            _ => return Err(AddressError::InvalidProtocol),
+        };
+        let address = bind_address
+            .filter(|address| address.is_ipv4() == unspecified.is_ipv4())
+            .unwrap_or(unspecified);
+
+        Ok(SocketAddr::new(address, 0))
+    }
+
+    /// Create client endpoint listening on `address`, bound to `bind_device` if it's set.
+    fn client_endpoint(address: SocketAddr, bind_device: Option<&str>) -> io::Result<Endpoint> {
+        let Some(device) = bind_device else {
+            return Endpoint::client(address);
+        };
+
+        let domain = match address {
+            SocketAddr::V4(_) => Domain::IPV4,
+            SocketAddr::V6(_) => Domain::IPV6,
+        };
+        let socket = Socket::new(domain, Type::DGRAM, Some(socket2::Protocol::UDP))?;
+        bind_to_device(&socket, device)?;
+        socket.bind(&address.into())?;
+
+        Endpoint::new(EndpointConfig::default(), None, socket.into(), TokioRuntime)
+    }
+
+    /// Establish outbound connection to `peer`.
+    ///
+    /// If the connection can be resumed with 0-RTT, it's returned immediately without waiting for
+    /// the handshake to complete.
+    async fn connect(
+        peer: PeerId,
+        connecting: Connecting,
+        connection_open_timeout: Duration,
+    ) -> Result<NegotiatedConnection, DialError> {
+        let connecting = match connecting.into_0rtt() {
+            Ok((connection, accepted)) => {
+                tracing::trace!(target: LOG_TARGET, ?peer, "connection resumed with 0-RTT");
+
+                return Ok(NegotiatedConnection {
+                    peer,
+                    connection,
+                    early_data: Some(accepted.shared()),
+                });
+            }
+            Err(connecting) => connecting,
+        };
+
+        let connection = match tokio::time::timeout(connection_open_timeout, connecting).await {
+            Err(_) => return Err(DialError::Timeout),
+            Ok(Err(error)) => return Err(DialError::from(error)),
+            Ok(Ok(connection)) => connection,
+        };
+
+        let Some(peer) = Self::extract_peer_id(&connection) else {
+            return Err(crate::error::NegotiationError::Quic(QuicError::InvalidCertificate).into());
+        };
+
+        Ok(NegotiatedConnection {
+            peer,
+            connection,
+            early_data: None,
+        })
+    }
+
     /// Handle established connection.
     fn on_connection_established(
         &mut self,
@@ -169,7 +324,12 @@ impl QuicTransport {
                 );
                 self.pending_open.insert(connection_id, (connection, endpoint.clone()));
 
-                return Some(TransportEvent::ConnectionEstablished { peer, endpoint });
+                return Some(TransportEvent::ConnectionEstablished {
+                    peer,
+                    endpoint,
+                    security: SecurityProtocol::Tls,
+                    muxer: None,
+                });
             }
             Err(error) => {
                 tracing::debug!(target: LOG_TARGET, ?connection_id, ?error, "failed to establish connection");
@@ -211,6 +371,12 @@ impl TransportBuilder for QuicTransport {
         let (listener, listen_addresses) = QuicListener::new(
             &context.keypair,
             std::mem::take(&mut config.listen_addresses),
+            config.reuse_address,
+            config.reuse_port,
+            config.allow_migration,
+            config.zero_rtt,
+            config.bind_device.as_deref(),
+            Arc::new(config.transport_config()),
         )?;
 
         Ok((
@@ -219,6 +385,8 @@ impl TransportBuilder for QuicTransport {
                 config,
                 listener,
                 canceled: HashSet::new(),
+                connections: HashMap::new(),
+                session_caches: HashMap::new(),
                 opened_raw: HashMap::new(),
                 pending_open: HashMap::new(),
                 pending_dials: HashMap::new(),
@@ -237,23 +405,19 @@ impl Transport for QuicTransport {
             return Err(Error::AddressError(AddressError::PeerIdMissing));
         };
 
-        let crypto_config =
-            Arc::new(make_client_config(&self.context.keypair, Some(peer)).expect("to succeed"));
-        let mut transport_config = quinn::TransportConfig::default();
-        let timeout =
-            IdleTimeout::try_from(self.config.connection_open_timeout).expect("to succeed");
-        transport_config.max_idle_timeout(Some(timeout));
-        let mut client_config = ClientConfig::new(crypto_config);
-        client_config.transport_config(Arc::new(transport_config));
-
-        let client_listen_address = match address.iter().next() {
-            Some(Protocol::Ip6(_)) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
-            Some(Protocol::Ip4(_)) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
-            _ => return Err(Error::AddressError(AddressError::InvalidProtocol)),
-        };
+        let session_storage = self.config.zero_rtt.then(|| self.session_storage(peer));
+        let client_config = Self::client_config(
+            &self.context.keypair,
+            peer,
+            Arc::new(self.config.transport_config()),
+            session_storage,
+        );
 
-        let client = Endpoint::client(client_listen_address)
-            .map_err(|error| Error::Other(error.to_string()))?;
+        let client_listen_address =
+            Self::client_listen_address(&address, self.config.outbound_bind_address)?;
+        let client =
+            Self::client_endpoint(client_listen_address, self.config.bind_device.as_deref())
+                .map_err(|error| Error::Other(error.to_string()))?;
         let connection = client
             .connect_with(client_config, socket_address, "l")
             .map_err(|error| Error::Other(error.to_string()))?;
@@ -267,21 +431,13 @@ impl Transport for QuicTransport {
         );
 
         self.pending_dials.insert(connection_id, address);
+        let connection_open_timeout = self.config.connection_open_timeout;
 
         self.pending_connections.push(Box::pin(async move {
-            let connection = match connection.await {
-                Ok(connection) => connection,
-                Err(error) => return (connection_id, Err(DialError::from(error))),
-            };
-
-            let Some(peer) = Self::extract_peer_id(&connection) else {
-                return (
-                    connection_id,
-                    Err(crate::error::NegotiationError::Quic(QuicError::InvalidCertificate).into()),
-                );
-            };
-
-            (connection_id, Ok(NegotiatedConnection { peer, connection }))
+            (
+                connection_id,
+                Self::connect(peer, connection, connection_open_timeout).await,
+            )
         }));
 
         Ok(())
@@ -302,6 +458,8 @@ impl Transport for QuicTransport {
             "start connection",
         );
 
+        self.connections.insert(connection_id, connection.connection.clone());
+
         self.context.executor.run(Box::pin(async move {
             let _ = QuicConnection::new(
                 connection.peer,
@@ -310,6 +468,7 @@ impl Transport for QuicTransport {
                 protocol_set,
                 bandwidth_sink,
                 substream_open_timeout,
+                connection.early_data,
             )
             .start()
             .await;
@@ -352,6 +511,13 @@ impl Transport for QuicTransport {
             .map(|address| {
                 let keypair = self.context.keypair.clone();
                 let connection_open_timeout = self.config.connection_open_timeout;
+                let transport_config = Arc::new(self.config.transport_config());
+                let session_storage = match QuicListener::get_socket_address(&address) {
+                    Ok((_, Some(peer))) if self.config.zero_rtt => Some(self.session_storage(peer)),
+                    _ => None,
+                };
+                let bind_address = self.config.outbound_bind_address;
+                let bind_device = self.config.bind_device.clone();
                 let addr = address.clone();
 
                 let future = async move {
@@ -360,24 +526,16 @@ impl Transport for QuicTransport {
                     let peer =
                         peer.ok_or_else(|| DialError::AddressError(AddressError::PeerIdMissing))?;
 
-                    let crypto_config =
-                        Arc::new(make_client_config(&keypair, Some(peer)).expect("to succeed"));
-                    let mut transport_config = quinn::TransportConfig::default();
-                    let timeout =
-                        IdleTimeout::try_from(connection_open_timeout).expect("to succeed");
-                    transport_config.max_idle_timeout(Some(timeout));
-                    let mut client_config = ClientConfig::new(crypto_config);
-                    client_config.transport_config(Arc::new(transport_config));
-
-                    let client_listen_address = match address.iter().next() {
-                        Some(Protocol::Ip6(_)) =>
-                            SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
-                        Some(Protocol::Ip4(_)) =>
-                            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
-                        _ => return Err(AddressError::InvalidProtocol.into()),
-                    };
+                    let client_config =
+                        Self::client_config(&keypair, peer, transport_config, session_storage);
 
-                    let client = match Endpoint::client(client_listen_address) {
+                    let client_listen_address =
+                        Self::client_listen_address(&address, bind_address)?;
+
+                    let client = match Self::client_endpoint(
+                        client_listen_address,
+                        bind_device.as_deref(),
+                    ) {
                         Ok(client) => client,
                         Err(error) => {
                             return Err(DialError::from(error));
@@ -388,19 +546,7 @@ impl Transport for QuicTransport {
                         Err(error) => return Err(DialError::from(error)),
                     };
 
-                    let connection = match connection.await {
-                        Ok(connection) => connection,
-                        Err(error) => return Err(DialError::from(error)),
-                    };
-
-                    let Some(peer) = Self::extract_peer_id(&connection) else {
-                        return Err(crate::error::NegotiationError::Quic(
-                            QuicError::InvalidCertificate,
-                        )
-                        .into());
-                    };
-
-                    Ok(NegotiatedConnection { peer, connection })
+                    Self::connect(peer, connection, connection_open_timeout).await
                 };
 
                 async move { future.await.map(|ok| (addr.clone(), ok)).map_err(|err| (addr, err)) }
@@ -447,6 +593,39 @@ impl Transport for QuicTransport {
     fn cancel(&mut self, connection_id: ConnectionId) {
         self.canceled.insert(connection_id);
     }
+
+    fn connection_rtt(&self, connection_id: ConnectionId) -> Option<Duration> {
+        self.connections
+            .get(&connection_id)
+            .filter(|connection| connection.close_reason().is_none())
+            .map(|connection| connection.rtt())
+    }
+
+    fn close_with_reason(&mut self, connection_id: ConnectionId, reason: &CloseReason) -> bool {
+        let Some(connection) = self.connections.get(&connection_id) else {
+            return false;
+        };
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?connection_id,
+            code = ?reason.code,
+            "close connection with application reason",
+        );
+
+        connection.close(VarInt::from_u32(reason.code), &reason.reason);
+        true
+    }
+
+    fn take_close_reason(&mut self, connection_id: ConnectionId) -> Option<CloseReason> {
+        match self.connections.remove(&connection_id)?.close_reason()? {
+            ConnectionError::ApplicationClosed(close) => Some(CloseReason {
+                code: u32::try_from(u64::from(close.error_code)).ok()?,
+                reason: close.reason.to_vec(),
+            }),
+            _ => None,
+        }
+    }
 }
 
 impl Stream for QuicTransport {
@@ -462,10 +641,16 @@ impl Stream for QuicTransport {
                 "pending inbound connection",
             );
 
+            let address = connection.remote_address();
+            let address = Multiaddr::empty()
+                .with(Protocol::from(address.ip()))
+                .with(Protocol::Udp(address.port()))
+                .with(Protocol::QuicV1);
             self.pending_inbound_connections.insert(connection_id, connection);
 
             return Poll::Ready(Some(TransportEvent::PendingInboundConnection {
                 connection_id,
+                address,
             }));
         }
 
@@ -517,14 +702,21 @@ mod tests {
     use super::*;
     use crate::{
         codec::ProtocolCodec,
-        crypto::ed25519::Keypair,
+        crypto::Keypair,
         executor::DefaultExecutor,
-        transport::manager::{ProtocolContext, TransportHandle},
-        types::protocol::ProtocolName,
+        multistream_select::{dialer_select_proto, Version},
+        protocol::InnerTransportEvent,
+        transport::{
+            manager::{ProtocolContext, TransportHandle},
+            quic::substream::NegotiatingSubstream,
+        },
+        types::{protocol::ProtocolName, SubstreamId},
         BandwidthSink,
     };
+    use bytes::Bytes;
+    use futures::AsyncWriteExt;
     use multihash::Multihash;
-    use tokio::sync::mpsc::channel;
+    use tokio::sync::mpsc::{channel, Receiver};
 
     #[tokio::test]
     async fn test_quinn() {
@@ -532,12 +724,20 @@ mod tests {
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
             .try_init();
 
-        let keypair1 = Keypair::generate();
+        let keypair1 = Keypair::generate_ed25519();
         let (tx1, _rx1) = channel(64);
         let (event_tx1, _event_rx1) = channel(64);
 
         let handle1 = TransportHandle {
             executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             next_substream_id: Default::default(),
             next_connection_id: Default::default(),
             keypair: keypair1.clone(),
@@ -550,6 +750,11 @@ mod tests {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
                     fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         };
@@ -558,12 +763,20 @@ mod tests {
             QuicTransport::new(handle1, Default::default()).unwrap();
         let listen_address = listen_addresses[0].clone();
 
-        let keypair2 = Keypair::generate();
+        let keypair2 = Keypair::generate_ed25519();
         let (tx2, _rx2) = channel(64);
         let (event_tx2, _event_rx2) = channel(64);
 
         let handle2 = TransportHandle {
             executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             next_substream_id: Default::default(),
             next_connection_id: Default::default(),
             keypair: keypair2.clone(),
@@ -576,13 +789,18 @@ mod tests {
                     tx: tx2,
                     codec: ProtocolCodec::Identity(32),
                     fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         };
 
         let (mut transport2, _) = QuicTransport::new(handle2, Default::default()).unwrap();
-        let peer1: PeerId = PeerId::from_public_key(&keypair1.public().into());
-        let _peer2: PeerId = PeerId::from_public_key(&keypair2.public().into());
+        let peer1: PeerId = keypair1.to_peer_id();
+        let _peer2: PeerId = keypair2.to_peer_id();
         let listen_address = listen_address.with(Protocol::P2p(
             Multihash::from_bytes(&peer1.to_bytes()).unwrap(),
         ));
@@ -591,7 +809,7 @@ mod tests {
 
         let event = transport1.next().await.unwrap();
         match event {
-            TransportEvent::PendingInboundConnection { connection_id } => {
+            TransportEvent::PendingInboundConnection { connection_id, .. } => {
                 transport1.accept_pending(connection_id).unwrap();
             }
             _ => panic!("unexpected event"),
@@ -608,4 +826,432 @@ mod tests {
             Some(TransportEvent::ConnectionEstablished { .. })
         ));
     }
+
+    #[tokio::test]
+    async fn connection_rtt_is_reported() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let make_handle = |keypair: Keypair| {
+            let (tx, _rx) = channel(64);
+            let (event_tx, _event_rx) = channel(64);
+
+            TransportHandle {
+                executor: Arc::new(DefaultExecutor {}),
+                substream_registry: None,
+                negotiation_observer: None,
+                negotiation_cache: None,
+                buffer_budget: None,
+                ip_filter: Default::default(),
+                substream_rate_limit: None,
+                #[cfg(feature = "traffic-tap")]
+                traffic_taps: HashMap::new(),
+                next_substream_id: Default::default(),
+                next_connection_id: Default::default(),
+                keypair,
+                tx: event_tx,
+                bandwidth_sink: BandwidthSink::new(),
+
+                protocols: HashMap::from_iter([(
+                    ProtocolName::from("/notif/1"),
+                    ProtocolContext {
+                        tx,
+                        codec: ProtocolCodec::Identity(32),
+                        fallback_names: Vec::new(),
+                        idle_timeout: None,
+                        inbound_filter: None,
+                        priority: Default::default(),
+                        latency_sensitive: false,
+                        inbound_order: Default::default(),
+                    },
+                )]),
+            }
+        };
+
+        let keypair1 = Keypair::generate_ed25519();
+        let peer1: PeerId = keypair1.to_peer_id();
+        let (mut transport1, listen_addresses) =
+            QuicTransport::new(make_handle(keypair1), Default::default()).unwrap();
+        let (mut transport2, _) =
+            QuicTransport::new(make_handle(Keypair::generate_ed25519()), Default::default())
+                .unwrap();
+        let listen_address = listen_addresses[0].clone().with(Protocol::P2p(
+            Multihash::from_bytes(&peer1.to_bytes()).unwrap(),
+        ));
+
+        transport2.dial(ConnectionId::new(), listen_address).unwrap();
+
+        match transport1.next().await.unwrap() {
+            TransportEvent::PendingInboundConnection { connection_id, .. } => {
+                transport1.accept_pending(connection_id).unwrap();
+            }
+            _ => panic!("unexpected event"),
+        }
+
+        let (res1, res2) = tokio::join!(transport1.next(), transport2.next());
+        let Some(TransportEvent::ConnectionEstablished { endpoint, .. }) = res1 else {
+            panic!("unexpected event: {res1:?}");
+        };
+        let Some(TransportEvent::ConnectionEstablished {
+            endpoint: endpoint2,
+            ..
+        }) = res2
+        else {
+            panic!("unexpected event: {res2:?}");
+        };
+
+        // no estimate before the connection has been accepted
+        assert!(transport1.connection_rtt(endpoint.connection_id()).is_none());
+
+        transport1.accept(endpoint.connection_id()).unwrap();
+        transport2.accept(endpoint2.connection_id()).unwrap();
+
+        // let the connection exchange some packets
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let rtt = transport1.connection_rtt(endpoint.connection_id()).unwrap();
+        assert!(rtt > Duration::ZERO && rtt < Duration::from_secs(1));
+        assert!(transport2.connection_rtt(endpoint2.connection_id()).is_some());
+        assert!(transport1.connection_rtt(ConnectionId::new()).is_none());
+    }
+
+    #[tokio::test]
+    async fn connection_survives_client_migration() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let keypair1 = Keypair::generate_ed25519();
+        let (tx1, mut rx1) = channel(64);
+        let (event_tx1, _event_rx1) = channel(64);
+
+        let handle1 = TransportHandle {
+            executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
+            next_substream_id: Default::default(),
+            next_connection_id: Default::default(),
+            keypair: keypair1.clone(),
+            tx: event_tx1,
+            bandwidth_sink: BandwidthSink::new(),
+
+            protocols: HashMap::from_iter([(
+                ProtocolName::from("/notif/1"),
+                ProtocolContext {
+                    tx: tx1,
+                    codec: ProtocolCodec::Identity(32),
+                    fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
+                },
+            )]),
+        };
+
+        let (mut transport1, listen_addresses) =
+            QuicTransport::new(handle1, Default::default()).unwrap();
+        let peer1: PeerId = keypair1.to_peer_id();
+        let (listen_address, _) = QuicListener::get_socket_address(&listen_addresses[0]).unwrap();
+
+        // connect to `transport1` using a raw QUIC client so its socket can be rebound
+        let keypair2 = Keypair::generate_ed25519();
+        let peer2 = keypair2.to_peer_id();
+        let crypto_config =
+            Arc::new(make_client_config(&keypair2, Some(peer1)).expect("to succeed"));
+        let client = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        let connecting = client
+            .connect_with(ClientConfig::new(crypto_config), listen_address, "l")
+            .unwrap();
+
+        let Some(TransportEvent::PendingInboundConnection { connection_id, .. }) =
+            transport1.next().await
+        else {
+            panic!("unexpected event");
+        };
+        transport1.accept_pending(connection_id).unwrap();
+
+        let (event, connection) = tokio::join!(transport1.next(), connecting);
+        let connection = connection.unwrap();
+        let Some(TransportEvent::ConnectionEstablished { endpoint, .. }) = event else {
+            panic!("unexpected event");
+        };
+        transport1.accept(endpoint.connection_id()).unwrap();
+
+        let Some(InnerTransportEvent::ConnectionEstablished { .. }) = rx1.recv().await else {
+            panic!("unexpected event");
+        };
+
+        // open substream and send one frame over it
+        let (send_stream, recv_stream) = connection.open_bi().await.unwrap();
+        let (_, mut stream) = dialer_select_proto(
+            NegotiatingSubstream::new(send_stream, recv_stream),
+            vec!["/notif/1"],
+            Version::V1,
+        )
+        .await
+        .unwrap();
+        stream.write_all(&[1u8; 32]).await.unwrap();
+        stream.flush().await.unwrap();
+
+        let Some(InnerTransportEvent::SubstreamOpened { mut substream, .. }) = rx1.recv().await
+        else {
+            panic!("unexpected event");
+        };
+        assert_eq!(substream.next().await.unwrap().unwrap(), vec![1u8; 32]);
+
+        // move the client to a new source address and verify the substream still works
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let new_address = socket.local_addr().unwrap();
+        client.rebind(socket).unwrap();
+
+        stream.write_all(&[2u8; 32]).await.unwrap();
+        stream.flush().await.unwrap();
+        assert_eq!(substream.next().await.unwrap().unwrap(), vec![2u8; 32]);
+
+        // the new address is picked up once the connection handles its next event
+        let (send_stream, recv_stream) = connection.open_bi().await.unwrap();
+        dialer_select_proto(
+            NegotiatingSubstream::new(send_stream, recv_stream),
+            vec!["/notif/1"],
+            Version::V1,
+        )
+        .await
+        .unwrap();
+
+        match tokio::time::timeout(std::time::Duration::from_secs(5), rx1.recv())
+            .await
+            .expect("address change to be reported")
+        {
+            Some(InnerTransportEvent::ConnectionAddressChanged { endpoint, .. }) => {
+                assert_eq!(
+                    endpoint.address(),
+                    &Multiaddr::empty()
+                        .with(Protocol::from(new_address.ip()))
+                        .with(Protocol::Udp(new_address.port()))
+                        .with(Protocol::QuicV1)
+                        .with(Protocol::P2p(peer2.into()))
+                );
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+    }
+
+    // every address of `127.0.0.0/8` is assigned to the loopback interface only on Linux
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn outbound_datagrams_sent_from_bind_address() {
+        let (event_tx, _event_rx) = channel(64);
+        let handle = TransportHandle {
+            executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
+            next_substream_id: Default::default(),
+            next_connection_id: Default::default(),
+            keypair: Keypair::generate_ed25519(),
+            tx: event_tx,
+            bandwidth_sink: BandwidthSink::new(),
+            protocols: HashMap::new(),
+        };
+        let bind_address = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+        let (mut transport, _) = QuicTransport::new(
+            handle,
+            QuicConfig {
+                outbound_bind_address: Some(bind_address),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let remote = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let peer = PeerId::random();
+        let address = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Udp(remote.local_addr().unwrap().port()))
+            .with(Protocol::QuicV1)
+            .with(Protocol::P2p(
+                Multihash::from_bytes(&peer.to_bytes()).unwrap(),
+            ));
+        transport.dial(ConnectionId::new(), address).unwrap();
+
+        // without the bind address, the initial packet would be sent from `127.0.0.1`
+        let mut buffer = vec![0u8; 2048];
+        let (_, source) =
+            tokio::time::timeout(Duration::from_secs(5), remote.recv_from(&mut buffer))
+                .await
+                .expect("initial packet to be sent")
+                .unwrap();
+        assert_eq!(source.ip(), bind_address);
+    }
+
+    fn make_zero_rtt_transport(
+        keypair: Keypair,
+    ) -> (QuicTransport, Vec<Multiaddr>, Receiver<InnerTransportEvent>) {
+        let (tx, rx) = channel(64);
+        let (event_tx, _event_rx) = channel(64);
+
+        let handle = TransportHandle {
+            executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
+            next_substream_id: Default::default(),
+            next_connection_id: Default::default(),
+            keypair,
+            tx: event_tx,
+            bandwidth_sink: BandwidthSink::new(),
+
+            protocols: HashMap::from_iter([(
+                ProtocolName::from("/notif/1"),
+                ProtocolContext {
+                    tx,
+                    codec: ProtocolCodec::Identity(32),
+                    fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
+                },
+            )]),
+        };
+        let config = QuicConfig {
+            zero_rtt: true,
+            ..Default::default()
+        };
+        let (transport, listen_addresses) = QuicTransport::new(handle, config).unwrap();
+
+        (transport, listen_addresses, rx)
+    }
+
+    /// Dial `listener` from `dialer`, accept the connection on both sides and return the 0-RTT
+    /// status of the outbound connection.
+    async fn connect_and_accept(
+        listener: &mut QuicTransport,
+        dialer: &mut QuicTransport,
+        address: Multiaddr,
+    ) -> Option<Shared<ZeroRttAccepted>> {
+        dialer.dial(ConnectionId::new(), address).unwrap();
+
+        match listener.next().await.unwrap() {
+            TransportEvent::PendingInboundConnection { connection_id, .. } => {
+                listener.accept_pending(connection_id).unwrap();
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        let (res1, res2) = tokio::join!(listener.next(), dialer.next());
+        let Some(TransportEvent::ConnectionEstablished { endpoint, .. }) = res1 else {
+            panic!("unexpected event: {res1:?}");
+        };
+        let Some(TransportEvent::ConnectionEstablished {
+            endpoint: endpoint2,
+            ..
+        }) = res2
+        else {
+            panic!("unexpected event: {res2:?}");
+        };
+
+        let early_data = dialer
+            .pending_open
+            .get(&endpoint2.connection_id())
+            .unwrap()
+            .0
+            .early_data
+            .clone();
+
+        listener.accept(endpoint.connection_id()).unwrap();
+        dialer.accept(endpoint2.connection_id()).unwrap();
+
+        early_data
+    }
+
+    #[tokio::test]
+    async fn early_data_sent_on_resumed_connection() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let keypair1 = Keypair::generate_ed25519();
+        let peer1: PeerId = keypair1.to_peer_id();
+        let (mut transport1, listen_addresses, mut rx1) = make_zero_rtt_transport(keypair1);
+        let (mut transport2, _, mut rx2) = make_zero_rtt_transport(Keypair::generate_ed25519());
+        let listen_address = listen_addresses[0].clone().with(Protocol::P2p(
+            Multihash::from_bytes(&peer1.to_bytes()).unwrap(),
+        ));
+
+        // no session ticket for the first connection
+        assert!(
+            connect_and_accept(&mut transport1, &mut transport2, listen_address.clone())
+                .await
+                .is_none()
+        );
+        assert!(std::matches!(
+            rx1.recv().await,
+            Some(InnerTransportEvent::ConnectionEstablished { .. })
+        ));
+        assert!(std::matches!(
+            rx2.recv().await,
+            Some(InnerTransportEvent::ConnectionEstablished { .. })
+        ));
+
+        // let the session tickets arrive
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let early_data = connect_and_accept(&mut transport1, &mut transport2, listen_address)
+            .await
+            .expect("connection to be resumed with 0-RTT");
+
+        let Some(InnerTransportEvent::ConnectionEstablished { mut sender, .. }) = rx2.recv().await
+        else {
+            panic!("unexpected event");
+        };
+        let permit = sender.try_get_permit().unwrap();
+        sender
+            .open_early_data_substream(
+                ProtocolName::from("/notif/1"),
+                Vec::new(),
+                SubstreamId::from(1337usize),
+                permit,
+            )
+            .unwrap();
+
+        let Some(InnerTransportEvent::SubstreamOpened { mut substream, .. }) = rx2.recv().await
+        else {
+            panic!("unexpected event");
+        };
+        substream.send_framed(Bytes::from(vec![1u8; 32])).await.unwrap();
+
+        assert!(std::matches!(
+            rx1.recv().await,
+            Some(InnerTransportEvent::ConnectionEstablished { .. })
+        ));
+        let Some(InnerTransportEvent::SubstreamOpened { mut substream, .. }) = rx1.recv().await
+        else {
+            panic!("unexpected event");
+        };
+        assert_eq!(substream.next().await.unwrap().unwrap(), vec![1u8; 32]);
+
+        // the remote peer accepted the data sent before the handshake completed
+        assert!(early_data.await);
+    }
 }
@@ -34,33 +34,71 @@ use std::{
 
 use crate::protocol::Permit;
 
+/// Maximum number of bytes buffered while the substream is corked.
+const MAX_CORK_BUFFER_SIZE: usize = 16 * 1024;
+
 /// QUIC substream.
 #[derive(Debug)]
 pub struct Substream {
-    _permit: Permit,
+    permit: Permit,
     bandwidth_sink: BandwidthSink,
     send_stream: SendStream,
     recv_stream: RecvStream,
+    corked: bool,
+    cork_buffer: Vec<u8>,
 }
 
 impl Substream {
     /// Create new [`Substream`].
     pub fn new(
-        _permit: Permit,
+        permit: Permit,
         send_stream: SendStream,
         recv_stream: RecvStream,
         bandwidth_sink: BandwidthSink,
     ) -> Self {
         Self {
-            _permit,
+            permit,
             send_stream,
             recv_stream,
             bandwidth_sink,
+            corked: false,
+            cork_buffer: Vec::new(),
+        }
+    }
+
+    /// Cork or uncork the substream.
+    ///
+    /// While the substream is corked, written data is buffered instead of being handed to the
+    /// stream on each write. The buffered data is written in one go when the substream is
+    /// uncorked and then flushed, when the buffer grows past its maximum size or when the
+    /// substream is shut down.
+    pub fn set_corked(&mut self, corked: bool) {
+        self.corked = corked;
+    }
+
+    /// Write data buffered while the substream was corked.
+    fn poll_send_corked(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.cork_buffer.is_empty() {
+            match futures::ready!(Pin::new(&mut self.send_stream).poll_write(cx, &self.cork_buffer))
+            {
+                Err(error) => return Poll::Ready(Err(error)),
+                Ok(nwritten) => {
+                    self.permit.on_activity();
+                    self.bandwidth_sink.increase_outbound(nwritten);
+                    self.cork_buffer.drain(..nwritten);
+                }
+            }
         }
+
+        Poll::Ready(Ok(()))
     }
 
     /// Write `buffers` to the underlying socket.
     pub async fn write_all_chunks(&mut self, buffers: &mut [Bytes]) -> Result<(), SubstreamError> {
+        futures::future::poll_fn(|cx| self.poll_send_corked(cx))
+            .await
+            .map_err(|_| SubstreamError::ConnectionClosed)?;
+
         let nwritten = buffers.iter().fold(0usize, |acc, buffer| acc + buffer.len());
 
         match self
@@ -70,6 +108,7 @@ impl Substream {
             .map_err(|_| SubstreamError::ConnectionClosed)
         {
             Ok(()) => {
+                self.permit.on_activity();
                 self.bandwidth_sink.increase_outbound(nwritten);
                 Ok(())
             }
@@ -84,9 +123,14 @@ impl TokioAsyncRead for Substream {
         cx: &mut Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
+        self.permit.poll_idle(cx);
+
         match futures::ready!(Pin::new(&mut self.recv_stream).poll_read(cx, buf)) {
             Err(error) => Poll::Ready(Err(error)),
             Ok(res) => {
+                if !buf.filled().is_empty() {
+                    self.permit.on_activity();
+                }
                 self.bandwidth_sink.increase_inbound(buf.filled().len());
                 Poll::Ready(Ok(res))
             }
@@ -100,9 +144,24 @@ impl TokioAsyncWrite for Substream {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, io::Error>> {
+        self.permit.poll_idle(cx);
+
+        if self.corked {
+            if self.cork_buffer.len() >= MAX_CORK_BUFFER_SIZE {
+                futures::ready!(self.poll_send_corked(cx))?;
+            }
+
+            let nbuffered = std::cmp::min(MAX_CORK_BUFFER_SIZE - self.cork_buffer.len(), buf.len());
+            self.cork_buffer.extend_from_slice(&buf[..nbuffered]);
+
+            return Poll::Ready(Ok(nbuffered));
+        }
+        futures::ready!(self.poll_send_corked(cx))?;
+
         match futures::ready!(Pin::new(&mut self.send_stream).poll_write(cx, buf)) {
             Err(error) => Poll::Ready(Err(error)),
             Ok(nwritten) => {
+                self.permit.on_activity();
                 self.bandwidth_sink.increase_outbound(nwritten);
                 Poll::Ready(Ok(nwritten))
             }
@@ -110,6 +169,10 @@ impl TokioAsyncWrite for Substream {
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        if !self.corked {
+            futures::ready!(self.poll_send_corked(cx))?;
+        }
+
         Pin::new(&mut self.send_stream).poll_flush(cx)
     }
 
@@ -117,6 +180,8 @@ impl TokioAsyncWrite for Substream {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), io::Error>> {
+        futures::ready!(self.poll_send_corked(cx))?;
+
         Pin::new(&mut self.send_stream).poll_shutdown(cx)
     }
 }
@@ -22,9 +22,18 @@
 
 use crate::{
     crypto::noise::{MAX_READ_AHEAD_FACTOR, MAX_WRITE_BUFFER_SIZE},
-    transport::{CONNECTION_OPEN_TIMEOUT, SUBSTREAM_OPEN_TIMEOUT},
+    transport::{
+        CONNECTION_OPEN_TIMEOUT, HANDSHAKE_TIMEOUT, HAPPY_EYEBALLS_DELAY,
+        INTERFACE_REFRESH_INTERVAL, MAX_CONCURRENT_DIALS_PER_PEER, SUBSTREAM_OPEN_TIMEOUT,
+    },
 };
 
+use std::{net::SocketAddr, time::Duration};
+
+pub use crate::crypto::noise::RekeyConfig;
+#[cfg(feature = "noise-observer")]
+pub use crate::crypto::noise::{HandshakeDirection, HandshakeMessage, NoiseObserver};
+
 /// TCP transport configuration.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -33,19 +42,69 @@ pub struct Config {
     /// Default listen addresses are ["/ip4/0.0.0.0/tcp/0", "/ip6/::/tcp/0"].
     pub listen_addresses: Vec<multiaddr::Multiaddr>,
 
+    /// Whether to set `SO_REUSEADDR` on listening sockets.
+    ///
+    /// Allows the listen address to be bound again right after the previous listener was closed,
+    /// even if connections of the previous listener are still in the `TIME_WAIT` state.
+    ///
+    /// Defaults to `true`.
+    pub reuse_address: bool,
+
     /// Whether to set `SO_REUSEPORT` and bind a socket to the listen address port for outbound
     /// connections.
     ///
-    /// Note that `SO_REUSEADDR` is always set on listening sockets.
+    /// `SO_REUSEPORT` is only supported on Unix platforms.
     ///
     /// Defaults to `true`.
     pub reuse_port: bool,
 
     /// Enable `TCP_NODELAY`.
     ///
+    /// `TCP_NODELAY` is also enabled if any of the installed protocols is marked as
+    /// latency-sensitive.
+    ///
     /// Defaults to `false`.
     pub nodelay: bool,
 
+    /// TCP keepalive configuration.
+    ///
+    /// If set, `SO_KEEPALIVE` is enabled for inbound and outbound connections so connections to
+    /// peers that have gone away without closing the connection are detected and reported closed
+    /// even if nothing is being written to the connection.
+    ///
+    /// Disabled by default.
+    pub keepalive: Option<KeepaliveConfig>,
+
+    /// Head start given to IPv6 addresses when dialing a peer with both IPv4 and IPv6 addresses.
+    ///
+    /// IPv4 addresses are dialed once the head start has elapsed or all IPv6 dial attempts have
+    /// failed, whichever happens first. The first connection that is opened is used and the other
+    /// dial attempts are cancelled. Setting the delay to zero dials IPv4 and IPv6 addresses at
+    /// once.
+    ///
+    /// Defaults to `250ms`.
+    pub happy_eyeballs_delay: std::time::Duration,
+
+    /// Maximum number of addresses of a peer that are dialed concurrently.
+    ///
+    /// If the peer has more addresses, the remaining addresses are dialed one by one as the
+    /// earlier dial attempts fail. A value of zero is treated as one.
+    ///
+    /// Defaults to `2`.
+    pub max_concurrent_dials: usize,
+
+    /// SOCKS5 proxy all outbound connections are opened through, such as a Tor client.
+    ///
+    /// If set, the proxy resolves DNS addresses of the remote peers so they never leak to the
+    /// local resolver, and `/onion3` addresses can be dialed. Listening is not affected by the
+    /// proxy and onion services cannot be listened on.
+    ///
+    /// If an IP filter is configured, DNS addresses are resolved locally instead and the proxy is
+    /// asked to connect to the resolved address so the filter can be applied to it.
+    ///
+    /// Disabled by default.
+    pub socks5_proxy: Option<Socks5Config>,
+
     /// Yamux configuration.
     pub yamux_config: crate::yamux::Config,
 
@@ -69,6 +128,25 @@ pub struct Config {
     /// the Noise code will allocate `2 * 65 KB + 5 * 65 KB = 455 KB` per connection.
     pub noise_write_buffer_size: usize,
 
+    /// Noise session re-key configuration.
+    ///
+    /// If set, the key used to encrypt outbound data is rotated after the configured number of
+    /// bytes or amount of time, allowing long-lived connections to limit how much data is
+    /// protected by a single key. The connection and its substreams are kept open across the
+    /// rotation.
+    ///
+    /// Disabled by default.
+    pub noise_rekey: Option<RekeyConfig>,
+
+    /// Observer of the raw Noise handshake messages of the connections.
+    ///
+    /// Receives a copy of each handshake message sent and received by the local node, which helps
+    /// diagnosing handshake failures against other implementations.
+    ///
+    /// Disabled by default.
+    #[cfg(feature = "noise-observer")]
+    pub noise_observer: Option<NoiseObserver>,
+
     /// Connection open timeout.
     ///
     /// How long should litep2p wait for a connection to be opened before the host
@@ -80,6 +158,21 @@ pub struct Config {
     /// How long should litep2p wait for a substream to be opened before considering
     /// the substream rejected.
     pub substream_open_timeout: std::time::Duration,
+
+    /// Noise handshake timeout.
+    ///
+    /// How long should litep2p wait for the Noise handshake to complete after the connection
+    /// has been established before the connection is closed.
+    pub handshake_timeout: std::time::Duration,
+
+    /// How often the network interfaces are checked for added or removed addresses.
+    ///
+    /// Listeners bound to an unspecified address, such as `/ip4/0.0.0.0/tcp/0`, are reported at
+    /// the addresses of the network interfaces. If set, the interfaces are checked periodically
+    /// and the listen addresses of the node are updated when the interfaces change.
+    ///
+    /// Defaults to 30 seconds.
+    pub interface_refresh_interval: Option<Duration>,
 }
 
 impl Default for Config {
@@ -89,13 +182,100 @@ impl Default for Config {
                 "/ip4/0.0.0.0/tcp/0".parse().expect("valid address"),
                 "/ip6/::/tcp/0".parse().expect("valid address"),
             ],
+            reuse_address: true,
             reuse_port: true,
             nodelay: false,
+            keepalive: None,
+            happy_eyeballs_delay: HAPPY_EYEBALLS_DELAY,
+            max_concurrent_dials: MAX_CONCURRENT_DIALS_PER_PEER,
+            socks5_proxy: None,
             yamux_config: Default::default(),
             noise_read_ahead_frame_count: MAX_READ_AHEAD_FACTOR,
             noise_write_buffer_size: MAX_WRITE_BUFFER_SIZE,
+            noise_rekey: None,
+            #[cfg(feature = "noise-observer")]
+            noise_observer: None,
             connection_open_timeout: CONNECTION_OPEN_TIMEOUT,
             substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
+            handshake_timeout: HANDSHAKE_TIMEOUT,
+            interface_refresh_interval: Some(INTERFACE_REFRESH_INTERVAL),
+        }
+    }
+}
+
+/// SOCKS5 proxy configuration.
+#[derive(Debug, Clone)]
+pub struct Socks5Config {
+    /// Address of the proxy.
+    pub address: SocketAddr,
+
+    /// Username and password used to authenticate to the proxy, if it requires authentication.
+    ///
+    /// Tor isolates connections opened with different credentials onto separate circuits.
+    pub credentials: Option<(String, String)>,
+}
+
+impl Socks5Config {
+    /// Create new [`Socks5Config`] for a proxy which doesn't require authentication.
+    pub fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            credentials: None,
+        }
+    }
+}
+
+/// TCP keepalive configuration.
+///
+/// Once nothing has been received over a connection for `idle`, the operating system starts
+/// sending keepalive probes every `interval` and closes the connection if `retries` consecutive
+/// probes go unanswered. A dead peer is thus detected within `idle + interval * retries`.
+#[derive(Debug, Clone, Copy)]
+pub struct KeepaliveConfig {
+    /// How long the connection must be idle before the first keepalive probe is sent.
+    ///
+    /// Defaults to `30` seconds.
+    pub idle: Duration,
+
+    /// Interval between keepalive probes.
+    ///
+    /// Only applied on platforms that support configuring it. Defaults to `10` seconds.
+    pub interval: Duration,
+
+    /// Number of unanswered keepalive probes after which the connection is closed.
+    ///
+    /// Only applied on platforms that support configuring it. Defaults to `3`.
+    pub retries: u32,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            idle: Duration::from_secs(30),
+            interval: Duration::from_secs(10),
+            retries: 3,
         }
     }
 }
+
+impl KeepaliveConfig {
+    /// Convert the configuration into socket keepalive parameters.
+    pub(crate) fn to_socket_keepalive(self) -> socket2::TcpKeepalive {
+        #[allow(unused_mut)]
+        let mut keepalive = socket2::TcpKeepalive::new().with_time(self.idle);
+
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+        ))]
+        {
+            keepalive = keepalive.with_interval(self.interval).with_retries(self.retries);
+        }
+
+        keepalive
+    }
+}
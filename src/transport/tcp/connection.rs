@@ -21,17 +21,20 @@
 use crate::{
     config::Role,
     crypto::{
-        ed25519::Keypair,
-        noise::{self, NoiseSocket},
+        noise::{self, NoiseSocket, RekeyConfig},
+        Keypair,
     },
     error::{Error, NegotiationError, SubstreamError},
     multistream_select::{dialer_select_proto, listener_select_proto, Negotiated, Version},
-    protocol::{Direction, Permit, ProtocolCommand, ProtocolSet},
+    protocol::{
+        Direction, NegotiationCache, NegotiationObserver, OptimisticProtocol, Permit,
+        ProtocolCommand, ProtocolSet,
+    },
     substream,
     transport::{
         common::listener::{AddressType, DnsType},
         tcp::substream::Substream,
-        Endpoint,
+        DialMilestone, DialProgressSink, Endpoint,
     },
     types::{protocol::ProtocolName, ConnectionId, SubstreamId},
     BandwidthSink, PeerId,
@@ -43,10 +46,17 @@ use futures::{
     AsyncRead, AsyncWrite,
 };
 use multiaddr::{Multiaddr, Protocol};
-use tokio::net::TcpStream;
+use tokio::{
+    io::{AsyncRead as TokioAsyncRead, AsyncWrite as TokioAsyncWrite},
+    net::TcpStream,
+};
 use tokio_util::compat::{
     Compat, FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
 };
+use tracing::Instrument;
+
+#[cfg(feature = "noise-observer")]
+use crate::crypto::noise::NoiseObserver;
 
 use std::{
     borrow::Cow,
@@ -74,10 +84,13 @@ pub struct NegotiatedSubstream {
     protocol: ProtocolName,
 
     /// Yamux substream.
-    io: crate::yamux::Stream,
+    io: Negotiated<crate::yamux::Stream>,
 
     /// Permit.
     permit: Permit,
+
+    /// Protocol awaiting confirmation from the remote peer, if negotiated optimistically.
+    optimistic: Option<OptimisticProtocol>,
 }
 
 /// TCP connection error.
@@ -106,9 +119,9 @@ enum ConnectionError {
 }
 
 /// Connection context for an opened connection that hasn't yet started its event loop.
-pub struct NegotiatedConnection {
+pub struct NegotiatedConnection<S = TcpStream> {
     /// Yamux connection.
-    connection: crate::yamux::ControlledConnection<NoiseSocket<Compat<TcpStream>>>,
+    connection: crate::yamux::ControlledConnection<NoiseSocket<Compat<S>>>,
 
     /// Yamux control.
     control: crate::yamux::Control,
@@ -123,7 +136,7 @@ pub struct NegotiatedConnection {
     substream_open_timeout: Duration,
 }
 
-impl NegotiatedConnection {
+impl<S> NegotiatedConnection<S> {
     /// Get `ConnectionId` of the negotiated connection.
     pub fn connection_id(&self) -> ConnectionId {
         self.endpoint.connection_id()
@@ -141,12 +154,15 @@ impl NegotiatedConnection {
 }
 
 /// TCP connection.
-pub struct TcpConnection {
+///
+/// The connection is generic over the socket so other stream transports which upgrade their
+/// sockets with Noise and Yamux, such as Unix domain sockets, can reuse it.
+pub struct TcpConnection<S = TcpStream> {
     /// Protocol context.
     protocol_set: ProtocolSet,
 
     /// Yamux connection.
-    connection: crate::yamux::ControlledConnection<NoiseSocket<Compat<TcpStream>>>,
+    connection: crate::yamux::ControlledConnection<NoiseSocket<Compat<S>>>,
 
     /// Yamux control.
     control: crate::yamux::Control,
@@ -171,7 +187,7 @@ pub struct TcpConnection {
         FuturesUnordered<BoxFuture<'static, Result<NegotiatedSubstream, ConnectionError>>>,
 }
 
-impl fmt::Debug for TcpConnection {
+impl<S> fmt::Debug for TcpConnection<S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TcpConnection")
             .field("peer", &self.peer)
@@ -180,10 +196,10 @@ impl fmt::Debug for TcpConnection {
     }
 }
 
-impl TcpConnection {
+impl<S: TokioAsyncRead + TokioAsyncWrite + Unpin + Send + 'static> TcpConnection<S> {
     /// Create new [`TcpConnection`] from [`NegotiatedConnection`].
-    pub(super) fn new(
-        context: NegotiatedConnection,
+    pub(crate) fn new(
+        context: NegotiatedConnection<S>,
         protocol_set: ProtocolSet,
         bandwidth_sink: BandwidthSink,
         next_substream_id: Arc<AtomicUsize>,
@@ -209,52 +225,6 @@ impl TcpConnection {
         }
     }
 
-    /// Open connection to remote peer at `address`.
-    // TODO: this function can be removed
-    pub(super) async fn open_connection(
-        connection_id: ConnectionId,
-        keypair: Keypair,
-        stream: TcpStream,
-        address: AddressType,
-        peer: Option<PeerId>,
-        yamux_config: crate::yamux::Config,
-        max_read_ahead_factor: usize,
-        max_write_buffer_size: usize,
-        connection_open_timeout: Duration,
-        substream_open_timeout: Duration,
-    ) -> Result<NegotiatedConnection, NegotiationError> {
-        tracing::debug!(
-            target: LOG_TARGET,
-            ?address,
-            ?peer,
-            "open connection to remote peer",
-        );
-
-        match tokio::time::timeout(connection_open_timeout, async move {
-            Self::negotiate_connection(
-                stream,
-                peer,
-                connection_id,
-                keypair,
-                Role::Dialer,
-                address,
-                yamux_config,
-                max_read_ahead_factor,
-                max_write_buffer_size,
-                substream_open_timeout,
-            )
-            .await
-        })
-        .await
-        {
-            Err(_) => {
-                tracing::trace!(target: LOG_TARGET, ?connection_id, "connection timed out during negotiation");
-                Err(NegotiationError::Timeout)
-            }
-            Ok(result) => result,
-        }
-    }
-
     /// Open substream for `protocol`.
     pub(super) async fn open_substream(
         mut control: crate::yamux::Control,
@@ -263,6 +233,9 @@ impl TcpConnection {
         protocol: ProtocolName,
         fallback_names: Vec<ProtocolName>,
         open_timeout: Duration,
+        peer: PeerId,
+        observer: Option<NegotiationObserver>,
+        cache: Option<NegotiationCache>,
     ) -> Result<NegotiatedSubstream, SubstreamError> {
         tracing::debug!(target: LOG_TARGET, ?protocol, ?substream_id, "open substream");
 
@@ -287,58 +260,33 @@ impl TcpConnection {
 
         // TODO: protocols don't change after they've been initialized so this should be done only
         // once
-        let protocols = std::iter::once(&*protocol)
-            .chain(fallback_names.iter().map(|protocol| &**protocol))
-            .collect();
+        let protocols: Vec<ProtocolName> =
+            std::iter::once(protocol).chain(fallback_names).collect();
 
-        let (io, protocol) =
-            Self::negotiate_protocol(stream, &Role::Dialer, protocols, open_timeout).await?;
+        let result = Self::negotiate_outbound(stream, peer, &protocols, open_timeout, cache).await;
+
+        if let Some(observer) = observer {
+            observer.observe(
+                peer,
+                Direction::Outbound(substream_id),
+                protocols,
+                result.as_ref().ok().map(|(_, protocol, _)| protocol.clone()),
+                Vec::new(),
+            );
+        }
+
+        let (io, protocol, optimistic) = result?;
 
         Ok(NegotiatedSubstream {
-            io: io.inner(),
+            io,
             substream_id,
             direction: Direction::Outbound(substream_id),
             protocol,
             permit,
+            optimistic,
         })
     }
 
-    /// Accept a new connection.
-    pub(super) async fn accept_connection(
-        stream: TcpStream,
-        connection_id: ConnectionId,
-        keypair: Keypair,
-        address: SocketAddr,
-        yamux_config: crate::yamux::Config,
-        max_read_ahead_factor: usize,
-        max_write_buffer_size: usize,
-        connection_open_timeout: Duration,
-        substream_open_timeout: Duration,
-    ) -> Result<NegotiatedConnection, NegotiationError> {
-        tracing::debug!(target: LOG_TARGET, ?address, "accept connection");
-
-        match tokio::time::timeout(connection_open_timeout, async move {
-            Self::negotiate_connection(
-                stream,
-                None,
-                connection_id,
-                keypair,
-                Role::Listener,
-                AddressType::Socket(address),
-                yamux_config,
-                max_read_ahead_factor,
-                max_write_buffer_size,
-                substream_open_timeout,
-            )
-            .await
-        })
-        .await
-        {
-            Err(_) => Err(NegotiationError::Timeout),
-            Ok(result) => result,
-        }
-    }
-
     /// Accept substream.
     pub(super) async fn accept_substream(
         stream: crate::yamux::Stream,
@@ -346,6 +294,8 @@ impl TcpConnection {
         substream_id: SubstreamId,
         protocols: Vec<ProtocolName>,
         open_timeout: Duration,
+        peer: PeerId,
+        observer: Option<NegotiationObserver>,
     ) -> Result<NegotiatedSubstream, NegotiationError> {
         tracing::trace!(
             target: LOG_TARGET,
@@ -353,9 +303,24 @@ impl TcpConnection {
             "accept inbound substream",
         );
 
-        let protocols = protocols.iter().map(|protocol| &**protocol).collect::<Vec<&str>>();
-        let (io, protocol) =
-            Self::negotiate_protocol(stream, &Role::Listener, protocols, open_timeout).await?;
+        let (result, unsupported) = Self::accept_protocol(
+            stream,
+            protocols.iter().map(|protocol| &**protocol).collect(),
+            open_timeout,
+        )
+        .await;
+
+        if let Some(observer) = observer {
+            observer.observe(
+                peer,
+                Direction::Inbound,
+                protocols,
+                result.as_ref().ok().map(|(_, protocol)| protocol.clone()),
+                unsupported,
+            );
+        }
+
+        let (io, protocol) = result?;
 
         tracing::trace!(
             target: LOG_TARGET,
@@ -364,26 +329,66 @@ impl TcpConnection {
         );
 
         Ok(NegotiatedSubstream {
-            io: io.inner(),
+            io,
             substream_id,
             direction: Direction::Inbound,
             protocol,
             permit,
+            optimistic: None,
         })
     }
 
+    /// Negotiate outbound substream for the first supported protocol of `protocols`.
+    ///
+    /// If one of `protocols` has been negotiated with `peer` before, it's proposed alone and the
+    /// stream is returned without waiting for the remote peer to confirm the protocol.
+    async fn negotiate_outbound<T: AsyncRead + AsyncWrite + Unpin>(
+        stream: T,
+        peer: PeerId,
+        protocols: &[ProtocolName],
+        open_timeout: Duration,
+        cache: Option<NegotiationCache>,
+    ) -> Result<(Negotiated<T>, ProtocolName, Option<OptimisticProtocol>), NegotiationError> {
+        let cached = cache.as_ref().and_then(|cache| cache.select(&peer, protocols));
+        let (proposed, version) = match &cached {
+            Some(protocol) => (vec![&**protocol], Version::V1Lazy),
+            None => (
+                protocols.iter().map(|protocol| &**protocol).collect(),
+                Version::V1,
+            ),
+        };
+
+        let (io, protocol) =
+            Self::negotiate_protocol(stream, &Role::Dialer, proposed, version, open_timeout)
+                .await?;
+
+        let optimistic = match (cache, cached) {
+            (Some(cache), Some(_)) => Some(cache.optimistic(peer, protocol.clone())),
+            (Some(cache), None) => {
+                cache.insert(peer, protocol.clone());
+                None
+            }
+            (None, _) => None,
+        };
+
+        Ok((io, protocol, optimistic))
+    }
+
     /// Negotiate protocol.
-    async fn negotiate_protocol<S: AsyncRead + AsyncWrite + Unpin>(
-        stream: S,
+    ///
+    /// `version` is only used by the dialer.
+    async fn negotiate_protocol<T: AsyncRead + AsyncWrite + Unpin>(
+        stream: T,
         role: &Role,
         protocols: Vec<&str>,
+        version: Version,
         substream_open_timeout: Duration,
-    ) -> Result<(Negotiated<S>, ProtocolName), NegotiationError> {
+    ) -> Result<(Negotiated<T>, ProtocolName), NegotiationError> {
         tracing::trace!(target: LOG_TARGET, ?protocols, "negotiating protocols");
 
         match tokio::time::timeout(substream_open_timeout, async move {
             match role {
-                Role::Dialer => dialer_select_proto(stream, protocols, Version::V1).await,
+                Role::Dialer => dialer_select_proto(stream, protocols, version).await,
                 Role::Listener => listener_select_proto(stream, protocols).await,
             }
         })
@@ -399,19 +404,51 @@ impl TcpConnection {
         }
     }
 
+    /// Accept an inbound substream as the listener.
+    ///
+    /// Besides the negotiation result, returns the protocols the remote proposed but which
+    /// aren't supported locally.
+    async fn accept_protocol<T: AsyncRead + AsyncWrite + Unpin>(
+        stream: T,
+        protocols: Vec<&str>,
+        substream_open_timeout: Duration,
+    ) -> (
+        Result<(Negotiated<T>, ProtocolName), NegotiationError>,
+        Vec<ProtocolName>,
+    ) {
+        tracing::trace!(target: LOG_TARGET, ?protocols, "accepting protocols");
+
+        let mut future = listener_select_proto(stream, protocols);
+        let result = match tokio::time::timeout(substream_open_timeout, &mut future).await {
+            Err(_) => Err(NegotiationError::Timeout),
+            Ok(Err(error)) => Err(NegotiationError::MultistreamSelectError(error)),
+            Ok(Ok((protocol, socket))) => {
+                tracing::trace!(target: LOG_TARGET, ?protocol, "protocol negotiated");
+
+                Ok((socket, ProtocolName::from(protocol.to_string())))
+            }
+        };
+
+        (result, future.rejected_protocols())
+    }
+
     /// Negotiate noise + yamux for the connection.
-    pub(super) async fn negotiate_connection(
-        stream: TcpStream,
+    pub(crate) async fn negotiate_connection(
+        stream: S,
         dialed_peer: Option<PeerId>,
         connection_id: ConnectionId,
         keypair: Keypair,
         role: Role,
-        address: AddressType,
+        address: Multiaddr,
         yamux_config: crate::yamux::Config,
         max_read_ahead_factor: usize,
         max_write_buffer_size: usize,
+        noise_rekey: Option<RekeyConfig>,
+        #[cfg(feature = "noise-observer")] noise_observer: Option<NoiseObserver>,
         substream_open_timeout: Duration,
-    ) -> Result<NegotiatedConnection, NegotiationError> {
+        handshake_timeout: Duration,
+        progress: Option<DialProgressSink>,
+    ) -> Result<NegotiatedConnection<S>, NegotiationError> {
         tracing::trace!(
             target: LOG_TARGET,
             ?role,
@@ -422,23 +459,46 @@ impl TcpConnection {
         let stream = TokioAsyncWriteCompatExt::compat_write(stream);
 
         // negotiate `noise`
-        let (stream, _) =
-            Self::negotiate_protocol(stream, &role, vec!["/noise"], substream_open_timeout).await?;
+        let (stream, _) = Self::negotiate_protocol(
+            stream,
+            &role,
+            vec!["/noise"],
+            Version::V1,
+            substream_open_timeout,
+        )
+        .await?;
 
         tracing::trace!(
             target: LOG_TARGET,
             "`multistream-select` and `noise` negotiated",
         );
 
+        if let Some(progress) = &progress {
+            progress.report(DialMilestone::SecurityHandshakeStarted);
+        }
+
         // perform noise handshake
-        let (stream, peer) = noise::handshake(
-            stream.inner(),
-            &keypair,
-            role,
-            max_read_ahead_factor,
-            max_write_buffer_size,
+        let (stream, peer) = match tokio::time::timeout(
+            handshake_timeout,
+            noise::handshake(
+                stream.inner(),
+                &keypair,
+                role,
+                max_read_ahead_factor,
+                max_write_buffer_size,
+                noise_rekey,
+                #[cfg(feature = "noise-observer")]
+                noise_observer,
+            ),
         )
-        .await?;
+        .await
+        {
+            Err(_) => {
+                tracing::debug!(target: LOG_TARGET, ?connection_id, "noise handshake timed out");
+                return Err(NegotiationError::Timeout);
+            }
+            Ok(result) => result?,
+        };
 
         if let Some(dialed_peer) = dialed_peer {
             if dialed_peer != peer {
@@ -448,37 +508,30 @@ impl TcpConnection {
         }
 
         tracing::trace!(target: LOG_TARGET, "noise handshake done");
-        let stream: NoiseSocket<Compat<TcpStream>> = stream;
+        let stream: NoiseSocket<Compat<S>> = stream;
+
+        if let Some(progress) = &progress {
+            progress.report(DialMilestone::SecurityHandshakeCompleted { peer });
+        }
 
         // negotiate `yamux`
-        let (stream, _) =
-            Self::negotiate_protocol(stream, &role, vec!["/yamux/1.0.0"], substream_open_timeout)
-                .await?;
+        let (stream, _) = Self::negotiate_protocol(
+            stream,
+            &role,
+            vec!["/yamux/1.0.0"],
+            Version::V1,
+            substream_open_timeout,
+        )
+        .await?;
         tracing::trace!(target: LOG_TARGET, "`yamux` negotiated");
 
+        if let Some(progress) = &progress {
+            progress.report(DialMilestone::MuxerNegotiated);
+        }
+
         let connection = crate::yamux::Connection::new(stream.inner(), yamux_config, role.into());
         let (control, connection) = crate::yamux::Control::new(connection);
 
-        let address = match address {
-            AddressType::Socket(address) => Multiaddr::empty()
-                .with(Protocol::from(address.ip()))
-                .with(Protocol::Tcp(address.port())),
-            AddressType::Dns {
-                address,
-                port,
-                dns_type,
-            } => match dns_type {
-                DnsType::Dns => Multiaddr::empty()
-                    .with(Protocol::Dns(Cow::Owned(address)))
-                    .with(Protocol::Tcp(port)),
-                DnsType::Dns4 => Multiaddr::empty()
-                    .with(Protocol::Dns4(Cow::Owned(address)))
-                    .with(Protocol::Tcp(port)),
-                DnsType::Dns6 => Multiaddr::empty()
-                    .with(Protocol::Dns6(Cow::Owned(address)))
-                    .with(Protocol::Tcp(port)),
-            },
-        };
         let endpoint = match role {
             Role::Dialer => Endpoint::dialer(address, connection_id),
             Role::Listener => Endpoint::listener(address, connection_id),
@@ -502,6 +555,15 @@ impl TcpConnection {
     ) -> crate::Result<bool> {
         match substream {
             Some(Ok(stream)) => {
+                if !self.protocol_set.try_accept_substream() {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        peer = ?self.peer,
+                        "substream rate limit exceeded, closing inbound substream",
+                    );
+                    return Ok(false);
+                }
+
                 let substream_id = {
                     let substream_id = self.next_substream_id.fetch_add(1usize, Ordering::Relaxed);
                     SubstreamId::from(substream_id)
@@ -509,6 +571,8 @@ impl TcpConnection {
                 let protocols = self.protocol_set.protocols();
                 let permit = self.protocol_set.try_get_permit().ok_or(Error::ConnectionClosed)?;
                 let open_timeout = self.substream_open_timeout;
+                let peer = self.peer;
+                let observer = self.protocol_set.negotiation_observer();
 
                 self.pending_substreams.push(Box::pin(async move {
                     match tokio::time::timeout(
@@ -519,6 +583,8 @@ impl TcpConnection {
                             substream_id,
                             protocols,
                             open_timeout,
+                            peer,
+                            observer,
                         ),
                     )
                     .await
@@ -614,13 +680,21 @@ impl TcpConnection {
                 let protocol = substream.protocol.clone();
                 let direction = substream.direction;
                 let substream_id = substream.substream_id;
-                let socket = FuturesAsyncReadCompatExt::compat(substream.io);
+                let mut io = substream.io;
+                io.get_mut()
+                    .set_priority(self.protocol_set.substream_priority(&protocol).value());
+                let socket = FuturesAsyncReadCompatExt::compat(io);
                 let bandwidth_sink = self.bandwidth_sink.clone();
 
                 let substream = substream::Substream::new_tcp(
                     self.peer,
                     substream_id,
-                    Substream::new(socket, bandwidth_sink, substream.permit),
+                    Substream::new(
+                        socket,
+                        bandwidth_sink,
+                        self.protocol_set.substream_permit(&protocol, substream.permit),
+                    )
+                    .with_optimistic_protocol(substream.optimistic),
                     self.protocol_set.protocol_codec(&protocol),
                 );
 
@@ -655,9 +729,13 @@ impl TcpConnection {
                 fallback_names,
                 substream_id,
                 permit,
+                ..
             }) => {
                 let control = self.control.clone();
                 let open_timeout = self.substream_open_timeout;
+                let peer = self.peer;
+                let observer = self.protocol_set.negotiation_observer();
+                let cache = self.protocol_set.negotiation_cache();
 
                 tracing::trace!(
                     target: LOG_TARGET,
@@ -676,6 +754,9 @@ impl TcpConnection {
                             protocol.clone(),
                             fallback_names,
                             open_timeout,
+                            peer,
+                            observer,
+                            cache,
                         ),
                     )
                     .await
@@ -719,7 +800,19 @@ impl TcpConnection {
     }
 
     /// Start connection event loop.
-    pub(crate) async fn start(mut self) -> crate::Result<()> {
+    pub(crate) async fn start(self) -> crate::Result<()> {
+        let span = tracing::debug_span!(
+            target: LOG_TARGET,
+            "connection",
+            peer = ?self.peer,
+            connection_id = ?self.endpoint.connection_id(),
+        );
+
+        self.event_loop().instrument(span).await
+    }
+
+    /// Connection event loop.
+    async fn event_loop(mut self) -> crate::Result<()> {
         self.protocol_set
             .report_connection_established(self.peer, self.endpoint.clone())
             .await?;
@@ -744,6 +837,131 @@ impl TcpConnection {
     }
 }
 
+impl TcpConnection {
+    /// Open connection to remote peer at `address`.
+    pub(super) async fn open_connection(
+        connection_id: ConnectionId,
+        keypair: Keypair,
+        stream: TcpStream,
+        address: AddressType,
+        peer: Option<PeerId>,
+        yamux_config: crate::yamux::Config,
+        max_read_ahead_factor: usize,
+        max_write_buffer_size: usize,
+        noise_rekey: Option<RekeyConfig>,
+        #[cfg(feature = "noise-observer")] noise_observer: Option<NoiseObserver>,
+        connection_open_timeout: Duration,
+        substream_open_timeout: Duration,
+        handshake_timeout: Duration,
+        progress: Option<DialProgressSink>,
+    ) -> Result<NegotiatedConnection, NegotiationError> {
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?address,
+            ?peer,
+            "open connection to remote peer",
+        );
+
+        match tokio::time::timeout(connection_open_timeout, async move {
+            Self::negotiate_connection(
+                stream,
+                peer,
+                connection_id,
+                keypair,
+                Role::Dialer,
+                address_to_multiaddr(address),
+                yamux_config,
+                max_read_ahead_factor,
+                max_write_buffer_size,
+                noise_rekey,
+                #[cfg(feature = "noise-observer")]
+                noise_observer,
+                substream_open_timeout,
+                handshake_timeout,
+                progress,
+            )
+            .await
+        })
+        .await
+        {
+            Err(_) => {
+                tracing::trace!(target: LOG_TARGET, ?connection_id, "connection timed out during negotiation");
+                Err(NegotiationError::Timeout)
+            }
+            Ok(result) => result,
+        }
+    }
+
+    /// Accept a new connection.
+    pub(super) async fn accept_connection(
+        stream: TcpStream,
+        connection_id: ConnectionId,
+        keypair: Keypair,
+        address: SocketAddr,
+        yamux_config: crate::yamux::Config,
+        max_read_ahead_factor: usize,
+        max_write_buffer_size: usize,
+        noise_rekey: Option<RekeyConfig>,
+        #[cfg(feature = "noise-observer")] noise_observer: Option<NoiseObserver>,
+        connection_open_timeout: Duration,
+        substream_open_timeout: Duration,
+        handshake_timeout: Duration,
+    ) -> Result<NegotiatedConnection, NegotiationError> {
+        tracing::debug!(target: LOG_TARGET, ?address, "accept connection");
+
+        match tokio::time::timeout(connection_open_timeout, async move {
+            Self::negotiate_connection(
+                stream,
+                None,
+                connection_id,
+                keypair,
+                Role::Listener,
+                address_to_multiaddr(AddressType::Socket(address)),
+                yamux_config,
+                max_read_ahead_factor,
+                max_write_buffer_size,
+                noise_rekey,
+                #[cfg(feature = "noise-observer")]
+                noise_observer,
+                substream_open_timeout,
+                handshake_timeout,
+                None,
+            )
+            .await
+        })
+        .await
+        {
+            Err(_) => Err(NegotiationError::Timeout),
+            Ok(result) => result,
+        }
+    }
+}
+
+/// Convert the address of a TCP socket into a [`Multiaddr`].
+pub(super) fn address_to_multiaddr(address: AddressType) -> Multiaddr {
+    match address {
+        AddressType::Socket(address) => Multiaddr::empty()
+            .with(Protocol::from(address.ip()))
+            .with(Protocol::Tcp(address.port())),
+        AddressType::Dns {
+            address,
+            port,
+            dns_type,
+        } => match dns_type {
+            DnsType::Dns => Multiaddr::empty()
+                .with(Protocol::Dns(Cow::Owned(address)))
+                .with(Protocol::Tcp(port)),
+            DnsType::Dns4 => Multiaddr::empty()
+                .with(Protocol::Dns4(Cow::Owned(address)))
+                .with(Protocol::Tcp(port)),
+            DnsType::Dns6 => Multiaddr::empty()
+                .with(Protocol::Dns6(Cow::Owned(address)))
+                .with(Protocol::Tcp(port)),
+        },
+        AddressType::Onion3(address) => Multiaddr::empty().with(Protocol::Onion3(address)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::transport::tcp::TcpTransport;
@@ -772,21 +990,28 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            None,
+            None,
         )
         .await
         .unwrap();
 
         match TcpConnection::open_connection(
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             stream,
             AddressType::Socket(address),
             None,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
             Duration::from_secs(10),
             Duration::from_secs(10),
+            Duration::from_secs(10),
+            None,
         )
         .await
         {
@@ -822,11 +1047,15 @@ mod tests {
         match TcpConnection::accept_connection(
             stream,
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             dialer_address,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
         )
@@ -867,21 +1096,28 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            None,
+            None,
         )
         .await
         .unwrap();
 
         match TcpConnection::open_connection(
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             stream,
             AddressType::Socket(address),
             None,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
             Duration::from_secs(10),
             Duration::from_secs(10),
+            Duration::from_secs(10),
+            None,
         )
         .await
         {
@@ -919,11 +1155,15 @@ mod tests {
         match TcpConnection::accept_connection(
             listener,
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             dialer_address,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
         )
@@ -966,11 +1206,15 @@ mod tests {
         match TcpConnection::accept_connection(
             listener,
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             dialer_address,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
         )
@@ -982,6 +1226,60 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn noise_handshake_timeout() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let listener = TcpListener::bind("[::1]:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let (Ok(dialer), Ok((listener, dialer_address))) =
+            tokio::join!(TcpStream::connect(address.clone()), listener.accept(),)
+        else {
+            panic!("failed to establish connection");
+        };
+
+        tokio::spawn(async move {
+            let dialer = TokioAsyncReadCompatExt::compat(dialer).into_inner();
+            let dialer = TokioAsyncWriteCompatExt::compat_write(dialer);
+
+            // negotiate noise but never send the first handshake message
+            let (_protocol, _socket) =
+                dialer_select_proto(dialer, vec!["/noise"], Version::V1).await.unwrap();
+
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        });
+
+        let started = std::time::Instant::now();
+
+        match TcpConnection::accept_connection(
+            listener,
+            ConnectionId::from(0usize),
+            Keypair::generate_ed25519(),
+            dialer_address,
+            Default::default(),
+            5,
+            2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(30),
+            Duration::from_secs(30),
+            Duration::from_secs(1),
+        )
+        .await
+        {
+            Ok(_) => panic!("connection was supposed to fail"),
+            Err(NegotiationError::Timeout) => {}
+            Err(error) => panic!("invalid error: {error:?}"),
+        }
+
+        // the handshake timeout fired before the connection open timeout
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
     #[tokio::test]
     async fn noise_timeout_dialer() {
         let _ = tracing_subscriber::fmt()
@@ -1009,21 +1307,28 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            None,
+            None,
         )
         .await
         .unwrap();
 
         match TcpConnection::open_connection(
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             stream,
             AddressType::Socket(address),
             None,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
+            None,
         )
         .await
         {
@@ -1055,21 +1360,28 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            None,
+            None,
         )
         .await
         .unwrap();
 
         match TcpConnection::open_connection(
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             stream,
             AddressType::Socket(address),
             None,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
+            None,
         )
         .await
         {
@@ -1103,11 +1415,15 @@ mod tests {
         match TcpConnection::accept_connection(
             listener,
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             dialer_address,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
         )
@@ -1142,11 +1458,21 @@ mod tests {
             let (_protocol, stream) =
                 dialer_select_proto(dialer, vec!["/noise"], Version::V1).await.unwrap();
 
-            let keypair = Keypair::generate();
+            let keypair = Keypair::generate_ed25519();
 
             // do a noise handshake
-            let (stream, _peer) =
-                noise::handshake(stream.inner(), &keypair, Role::Dialer, 5, 2).await.unwrap();
+            let (stream, _peer) = noise::handshake(
+                stream.inner(),
+                &keypair,
+                Role::Dialer,
+                5,
+                2,
+                None,
+                #[cfg(feature = "noise-observer")]
+                None,
+            )
+            .await
+            .unwrap();
             let stream: NoiseSocket<Compat<TcpStream>> = stream;
 
             // after the handshake, try to negotiate some random protocol instead of yamux
@@ -1158,11 +1484,15 @@ mod tests {
         match TcpConnection::accept_connection(
             listener,
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             dialer_address,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
         )
@@ -1194,9 +1524,19 @@ mod tests {
             let (_protocol, stream) = listener_select_proto(stream, vec!["/noise"]).await.unwrap();
 
             // do a noise handshake
-            let keypair = Keypair::generate();
-            let (stream, _peer) =
-                noise::handshake(stream.inner(), &keypair, Role::Listener, 5, 2).await.unwrap();
+            let keypair = Keypair::generate_ed25519();
+            let (stream, _peer) = noise::handshake(
+                stream.inner(),
+                &keypair,
+                Role::Listener,
+                5,
+                2,
+                None,
+                #[cfg(feature = "noise-observer")]
+                None,
+            )
+            .await
+            .unwrap();
             let stream: NoiseSocket<Compat<TcpStream>> = stream;
 
             // after the handshake, try to negotiate some random protocol instead of yamux
@@ -1210,21 +1550,28 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            None,
+            None,
         )
         .await
         .unwrap();
 
         match TcpConnection::open_connection(
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             stream,
             AddressType::Socket(address),
             None,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
+            None,
         )
         .await
         {
@@ -1260,9 +1607,19 @@ mod tests {
                 dialer_select_proto(dialer, vec!["/noise"], Version::V1).await.unwrap();
 
             // do a noise handshake
-            let keypair = Keypair::generate();
-            let (stream, _peer) =
-                noise::handshake(stream.inner(), &keypair, Role::Dialer, 5, 2).await.unwrap();
+            let keypair = Keypair::generate_ed25519();
+            let (stream, _peer) = noise::handshake(
+                stream.inner(),
+                &keypair,
+                Role::Dialer,
+                5,
+                2,
+                None,
+                #[cfg(feature = "noise-observer")]
+                None,
+            )
+            .await
+            .unwrap();
             let _stream: NoiseSocket<Compat<TcpStream>> = stream;
 
             tokio::time::sleep(std::time::Duration::from_secs(60)).await;
@@ -1271,11 +1628,15 @@ mod tests {
         match TcpConnection::accept_connection(
             listener,
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             dialer_address,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
+            Duration::from_secs(10),
             Duration::from_secs(10),
             Duration::from_secs(10),
         )
@@ -1305,9 +1666,19 @@ mod tests {
             let (_protocol, stream) = listener_select_proto(stream, vec!["/noise"]).await.unwrap();
 
             // do a noise handshake
-            let keypair = Keypair::generate();
-            let (stream, _peer) =
-                noise::handshake(stream.inner(), &keypair, Role::Listener, 5, 2).await.unwrap();
+            let keypair = Keypair::generate_ed25519();
+            let (stream, _peer) = noise::handshake(
+                stream.inner(),
+                &keypair,
+                Role::Listener,
+                5,
+                2,
+                None,
+                #[cfg(feature = "noise-observer")]
+                None,
+            )
+            .await
+            .unwrap();
             let _stream: NoiseSocket<Compat<TcpStream>> = stream;
 
             tokio::time::sleep(std::time::Duration::from_secs(60)).await;
@@ -1320,21 +1691,28 @@ mod tests {
             Default::default(),
             Duration::from_secs(10),
             false,
+            None,
+            None,
         )
         .await
         .unwrap();
 
         match TcpConnection::open_connection(
             ConnectionId::from(0usize),
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             stream,
             AddressType::Socket(address),
             None,
             Default::default(),
             5,
             2,
+            None,
+            #[cfg(feature = "noise-observer")]
+            None,
             Duration::from_secs(10),
             Duration::from_secs(10),
+            Duration::from_secs(10),
+            None,
         )
         .await
         {
@@ -1343,4 +1721,67 @@ mod tests {
             Err(error) => panic!("invalid error: {error:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn cached_protocol_negotiated_optimistically() {
+        use futures::{AsyncReadExt as _, AsyncWriteExt as _};
+
+        let peer = PeerId::random();
+        let cache = NegotiationCache::new();
+        let protocols = vec![
+            ProtocolName::from("/proto/2"),
+            ProtocolName::from("/proto/1"),
+        ];
+
+        // first substream waits for the listener to confirm the protocol
+        let (dialer, listener) = tokio::io::duplex(1024);
+        let listener = tokio::spawn(async move {
+            listener_select_proto(listener.compat(), vec!["/proto/1"]).await.map(|_| ())
+        });
+
+        let (_, protocol, optimistic) = TcpConnection::<TcpStream>::negotiate_outbound(
+            dialer.compat(),
+            peer,
+            &protocols,
+            Duration::from_secs(5),
+            Some(cache.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(protocol, ProtocolName::from("/proto/1"));
+        assert!(optimistic.is_none());
+        listener.await.unwrap().unwrap();
+
+        // second substream is returned before the listener has even read the proposal
+        let (dialer, listener) = tokio::io::duplex(1024);
+        let (mut io, protocol, optimistic) = tokio::time::timeout(
+            Duration::from_secs(1),
+            TcpConnection::<TcpStream>::negotiate_outbound(
+                dialer.compat(),
+                peer,
+                &protocols,
+                Duration::from_secs(5),
+                Some(cache.clone()),
+            ),
+        )
+        .await
+        .expect("negotiation not to wait for the listener")
+        .unwrap();
+
+        assert_eq!(protocol, ProtocolName::from("/proto/1"));
+        assert!(optimistic.is_some());
+
+        io.write_all(b"hello").await.unwrap();
+        io.flush().await.unwrap();
+
+        // proposal and payload are received together
+        let (protocol, mut io) =
+            listener_select_proto(listener.compat(), vec!["/proto/1"]).await.unwrap();
+        let mut payload = [0u8; 5];
+        io.read_exact(&mut payload).await.unwrap();
+
+        assert_eq!(protocol, "/proto/1");
+        assert_eq!(&payload, b"hello");
+    }
 }
@@ -25,22 +25,29 @@ use crate::{
     config::Role,
     error::{DialError, Error},
     transport::{
-        common::listener::{DialAddresses, GetSocketAddr, SocketListener, TcpAddress},
-        manager::TransportHandle,
+        common::{
+            happy_eyeballs,
+            listener::{
+                AddressType, DialAddresses, GetSocketAddr, ListenAddressChange, SocketListener,
+                TcpAddress,
+            },
+        },
+        manager::{ip_filter::IpFilterConfig, TransportHandle},
         tcp::{
-            config::Config,
-            connection::{NegotiatedConnection, TcpConnection},
+            config::{Config, KeepaliveConfig, Socks5Config},
+            connection::{address_to_multiaddr, NegotiatedConnection, TcpConnection},
         },
-        Transport, TransportBuilder, TransportEvent,
+        DialMilestone, DialProgressSink, Muxer, SecurityProtocol, Transport, TransportBuilder,
+        TransportEvent,
     },
     types::ConnectionId,
 };
 
 use futures::{
-    future::BoxFuture,
+    future::{AbortHandle, BoxFuture, FutureExt},
     stream::{FuturesUnordered, Stream, StreamExt},
 };
-use multiaddr::Multiaddr;
+use multiaddr::{Multiaddr, Protocol};
 use socket2::{Domain, Socket, Type};
 use tokio::net::TcpStream;
 
@@ -54,7 +61,8 @@ use std::{
 
 pub(crate) use substream::Substream;
 
-mod connection;
+pub(crate) mod connection;
+mod socks5;
 mod substream;
 
 pub mod config;
@@ -112,6 +120,9 @@ pub(crate) struct TcpTransport {
     /// Canceled raw connections.
     canceled: HashSet<ConnectionId>,
 
+    /// Abort handles of the outbound connections that are being opened or negotiated.
+    abort_handles: HashMap<ConnectionId, AbortHandle>,
+
     /// Connections which have been opened and negotiated but are being validated by the
     /// `TransportManager`.
     pending_open: HashMap<ConnectionId, NegotiatedConnection>,
@@ -128,8 +139,12 @@ impl TcpTransport {
         let yamux_config = self.config.yamux_config.clone();
         let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
         let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        #[cfg(feature = "noise-observer")]
+        let noise_observer = self.config.noise_observer.clone();
         let connection_open_timeout = self.config.connection_open_timeout;
         let substream_open_timeout = self.config.substream_open_timeout;
+        let handshake_timeout = self.config.handshake_timeout;
         let keypair = self.context.keypair.clone();
 
         tracing::trace!(
@@ -148,8 +163,12 @@ impl TcpTransport {
                 yamux_config,
                 max_read_ahead_factor,
                 max_write_buffer_size,
+                noise_rekey,
+                #[cfg(feature = "noise-observer")]
+                noise_observer,
                 connection_open_timeout,
                 substream_open_timeout,
+                handshake_timeout,
             )
             .await
             .map_err(|error| (connection_id, error.into()))
@@ -162,9 +181,27 @@ impl TcpTransport {
         dial_addresses: DialAddresses,
         connection_open_timeout: Duration,
         nodelay: bool,
+        keepalive: Option<KeepaliveConfig>,
+        proxy: Option<&Socks5Config>,
+        ip_filter: &IpFilterConfig,
+        progress: Option<&DialProgressSink>,
     ) -> Result<(Multiaddr, TcpStream), DialError> {
         let (socket_address, _) = TcpAddress::multiaddr_to_socket_address(&address)?;
 
+        if let Some(proxy) = proxy {
+            return Self::dial_peer_through_proxy(
+                address,
+                socket_address,
+                proxy,
+                ip_filter,
+                connection_open_timeout,
+                nodelay,
+                keepalive,
+                progress,
+            )
+            .await;
+        }
+
         let remote_address =
             match tokio::time::timeout(connection_open_timeout, socket_address.lookup_ip()).await {
                 Err(_) => {
@@ -180,6 +217,22 @@ impl TcpTransport {
                 Ok(Ok(address)) => address,
             };
 
+        if !ip_filter.is_allowed(&remote_address.ip()) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                ?remote_address,
+                "address denied by ip filter",
+            );
+            return Err(DialError::AddressDenied(remote_address.ip()));
+        }
+
+        if let Some(progress) = progress {
+            progress.report(DialMilestone::AddressResolved {
+                address: remote_address,
+            });
+        }
+
         let domain = match remote_address.is_ipv4() {
             true => Domain::IPV4,
             false => Domain::IPV6,
@@ -190,6 +243,9 @@ impl TcpTransport {
         }
         socket.set_nonblocking(true)?;
         socket.set_nodelay(nodelay)?;
+        if let Some(keepalive) = keepalive {
+            socket.set_tcp_keepalive(&keepalive.to_socket_keepalive())?;
+        }
 
         match dial_addresses.local_dial_address(&remote_address.ip()) {
             Ok(Some(dial_address)) => {
@@ -243,6 +299,95 @@ impl TcpTransport {
                     "connected",
                 );
 
+                if let Some(progress) = progress {
+                    progress.report(DialMilestone::TransportConnected);
+                }
+
+                Ok((address, stream))
+            }
+        }
+    }
+
+    /// Dial remote peer through a SOCKS5 proxy.
+    ///
+    /// The address of the peer is passed to the proxy without resolving it locally, unless the
+    /// IP filter is in use. The filter must then be applied to the address the proxy connects to
+    /// so DNS addresses are resolved locally and the proxy is asked to connect to the resolved
+    /// address.
+    async fn dial_peer_through_proxy(
+        address: Multiaddr,
+        target: AddressType,
+        proxy: &Socks5Config,
+        ip_filter: &IpFilterConfig,
+        connection_open_timeout: Duration,
+        nodelay: bool,
+        keepalive: Option<KeepaliveConfig>,
+        progress: Option<&DialProgressSink>,
+    ) -> Result<(Multiaddr, TcpStream), DialError> {
+        let future = async {
+            let target = match target {
+                AddressType::Dns { .. } if !ip_filter.is_empty() => {
+                    let remote_address = target.lookup_ip().await?;
+
+                    if let Some(progress) = progress {
+                        progress.report(DialMilestone::AddressResolved {
+                            address: remote_address,
+                        });
+                    }
+
+                    AddressType::Socket(remote_address)
+                }
+                target => target,
+            };
+
+            if let AddressType::Socket(remote_address) = target {
+                if !ip_filter.is_allowed(&remote_address.ip()) {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?address,
+                        ?remote_address,
+                        "address denied by ip filter",
+                    );
+                    return Err(DialError::AddressDenied(remote_address.ip()));
+                }
+            }
+
+            let mut stream = TcpStream::connect(proxy.address).await?;
+            stream.set_nodelay(nodelay)?;
+            if let Some(keepalive) = keepalive {
+                socket2::SockRef::from(&stream)
+                    .set_tcp_keepalive(&keepalive.to_socket_keepalive())?;
+            }
+
+            socks5::connect(&mut stream, &target, proxy.credentials.as_ref()).await?;
+
+            Ok::<_, DialError>(stream)
+        };
+
+        match tokio::time::timeout(connection_open_timeout, future).await {
+            Err(_) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?address,
+                    proxy = ?proxy.address,
+                    ?connection_open_timeout,
+                    "failed to connect through proxy within timeout",
+                );
+                Err(DialError::Timeout)
+            }
+            Ok(Err(error)) => Err(error),
+            Ok(Ok(stream)) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?address,
+                    proxy = ?proxy.address,
+                    "connected through proxy",
+                );
+
+                if let Some(progress) = progress {
+                    progress.report(DialMilestone::TransportConnected);
+                }
+
                 Ok((address, stream))
             }
         }
@@ -263,13 +408,25 @@ impl TransportBuilder for TcpTransport {
             listen_addresses = ?config.listen_addresses,
             "start tcp transport",
         );
+        config.yamux_config.validate()?;
+
+        // small writes of latency-sensitive protocols must not be delayed by Nagle's algorithm
+        if !config.nodelay && context.has_latency_sensitive_protocol() {
+            tracing::debug!(target: LOG_TARGET, "latency-sensitive protocol, enable `TCP_NODELAY`");
+            config.nodelay = true;
+        }
 
         // start tcp listeners for all listen addresses
         let (listener, listen_addresses, dial_addresses) = SocketListener::new::<TcpAddress>(
             std::mem::take(&mut config.listen_addresses),
+            config.reuse_address,
             config.reuse_port,
             config.nodelay,
         );
+        let listener = match config.interface_refresh_interval {
+            Some(interval) => listener.with_interface_refresh(interval),
+            None => listener,
+        };
 
         Ok((
             Self {
@@ -278,6 +435,7 @@ impl TransportBuilder for TcpTransport {
                 context,
                 dial_addresses,
                 canceled: HashSet::new(),
+                abort_handles: HashMap::new(),
                 opened_raw: HashMap::new(),
                 pending_open: HashMap::new(),
                 pending_dials: HashMap::new(),
@@ -290,26 +448,46 @@ impl TransportBuilder for TcpTransport {
     }
 }
 
-impl Transport for TcpTransport {
-    fn dial(&mut self, connection_id: ConnectionId, address: Multiaddr) -> crate::Result<()> {
+impl TcpTransport {
+    /// Dial `address` and negotiate connection, reporting the progress to `progress` if set.
+    fn dial_inner(
+        &mut self,
+        connection_id: ConnectionId,
+        address: Multiaddr,
+        progress: Option<DialProgressSink>,
+    ) -> crate::Result<()> {
         tracing::debug!(target: LOG_TARGET, ?connection_id, ?address, "open connection");
 
         let (socket_address, peer) = TcpAddress::multiaddr_to_socket_address(&address)?;
         let yamux_config = self.config.yamux_config.clone();
         let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
         let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        #[cfg(feature = "noise-observer")]
+        let noise_observer = self.config.noise_observer.clone();
         let connection_open_timeout = self.config.connection_open_timeout;
         let substream_open_timeout = self.config.substream_open_timeout;
+        let handshake_timeout = self.config.handshake_timeout;
         let dial_addresses = self.dial_addresses.clone();
         let keypair = self.context.keypair.clone();
         let nodelay = self.config.nodelay;
+        let keepalive = self.config.keepalive;
+        let proxy = self.config.socks5_proxy.clone();
+        let ip_filter = self.context.ip_filter.clone();
 
-        self.pending_dials.insert(connection_id, address.clone());
-        self.pending_connections.push(Box::pin(async move {
-            let (_, stream) =
-                TcpTransport::dial_peer(address, dial_addresses, connection_open_timeout, nodelay)
-                    .await
-                    .map_err(|error| (connection_id, error))?;
+        let (future, abort_handle) = futures::future::abortable(async move {
+            let (_, stream) = TcpTransport::dial_peer(
+                address,
+                dial_addresses,
+                connection_open_timeout,
+                nodelay,
+                keepalive,
+                proxy.as_ref(),
+                &ip_filter,
+                progress.as_ref(),
+            )
+            .await
+            .map_err(|error| (connection_id, error))?;
 
             TcpConnection::open_connection(
                 connection_id,
@@ -320,15 +498,41 @@ impl Transport for TcpTransport {
                 yamux_config,
                 max_read_ahead_factor,
                 max_write_buffer_size,
+                noise_rekey,
+                #[cfg(feature = "noise-observer")]
+                noise_observer,
                 connection_open_timeout,
                 substream_open_timeout,
+                handshake_timeout,
+                progress,
             )
             .await
             .map_err(|error| (connection_id, error.into()))
-        }));
+        });
+
+        self.pending_dials.insert(connection_id, address.clone());
+        self.abort_handles.insert(connection_id, abort_handle);
+        self.pending_connections.push(Box::pin(
+            future.map(move |result| result.unwrap_or(Err((connection_id, DialError::Aborted)))),
+        ));
 
         Ok(())
     }
+}
+
+impl Transport for TcpTransport {
+    fn dial(&mut self, connection_id: ConnectionId, address: Multiaddr) -> crate::Result<()> {
+        self.dial_inner(connection_id, address, None)
+    }
+
+    fn dial_with_progress(
+        &mut self,
+        connection_id: ConnectionId,
+        address: Multiaddr,
+        progress: DialProgressSink,
+    ) -> crate::Result<()> {
+        self.dial_inner(connection_id, address, Some(progress))
+    }
 
     fn accept(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
         let context = self
@@ -391,46 +595,60 @@ impl Transport for TcpTransport {
         connection_id: ConnectionId,
         addresses: Vec<Multiaddr>,
     ) -> crate::Result<()> {
-        let num_addresses = addresses.len();
-        let mut futures: FuturesUnordered<_> = addresses
-            .into_iter()
-            .map(|address| {
-                let dial_addresses = self.dial_addresses.clone();
-                let connection_open_timeout = self.config.connection_open_timeout;
-                let nodelay = self.config.nodelay;
-
-                async move {
-                    TcpTransport::dial_peer(
-                        address.clone(),
-                        dial_addresses,
-                        connection_open_timeout,
-                        nodelay,
-                    )
-                    .await
-                    .map_err(|error| (address, error))
-                }
-            })
-            .collect();
-
-        self.pending_raw_connections.push(Box::pin(async move {
-            let mut errors = Vec::with_capacity(num_addresses);
-            while let Some(result) = futures.next().await {
-                match result {
-                    Ok((address, stream)) => return Ok((connection_id, address, stream)),
-                    Err(error) => {
-                        tracing::debug!(
-                            target: LOG_TARGET,
-                            ?connection_id,
-                            ?error,
-                            "failed to open connection",
-                        );
-                        errors.push(error)
+        let dial_addresses = self.dial_addresses.clone();
+        let connection_open_timeout = self.config.connection_open_timeout;
+        let nodelay = self.config.nodelay;
+        let keepalive = self.config.keepalive;
+        let happy_eyeballs_delay = self.config.happy_eyeballs_delay;
+        let max_concurrent_dials = self.config.max_concurrent_dials;
+        let proxy = self.config.socks5_proxy.clone();
+        let ip_filter = self.context.ip_filter.clone();
+
+        let (future, abort_handle) = futures::future::abortable(async move {
+            happy_eyeballs::dial(
+                addresses,
+                happy_eyeballs_delay,
+                max_concurrent_dials,
+                |address| {
+                    let dial_addresses = dial_addresses.clone();
+                    let proxy = proxy.clone();
+                    let ip_filter = ip_filter.clone();
+
+                    async move {
+                        TcpTransport::dial_peer(
+                            address.clone(),
+                            dial_addresses,
+                            connection_open_timeout,
+                            nodelay,
+                            keepalive,
+                            proxy.as_ref(),
+                            &ip_filter,
+                            None,
+                        )
+                        .await
+                        .map_err(|error| {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?connection_id,
+                                ?address,
+                                ?error,
+                                "failed to open connection",
+                            );
+
+                            (address, error)
+                        })
                     }
-                }
-            }
+                },
+            )
+            .await
+            .map(|(address, stream)| (connection_id, address, stream))
+            .map_err(|errors| (connection_id, errors))
+        });
 
-            Err((connection_id, errors))
-        }));
+        self.abort_handles.insert(connection_id, abort_handle);
+        self.pending_raw_connections.push(Box::pin(
+            future.map(move |result| result.unwrap_or(Err((connection_id, Vec::new())))),
+        ));
 
         Ok(())
     }
@@ -445,8 +663,12 @@ impl Transport for TcpTransport {
         let yamux_config = self.config.yamux_config.clone();
         let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
         let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        #[cfg(feature = "noise-observer")]
+        let noise_observer = self.config.noise_observer.clone();
         let connection_open_timeout = self.config.connection_open_timeout;
         let substream_open_timeout = self.config.substream_open_timeout;
+        let handshake_timeout = self.config.handshake_timeout;
         let keypair = self.context.keypair.clone();
 
         tracing::trace!(
@@ -457,8 +679,7 @@ impl Transport for TcpTransport {
             "negotiate connection",
         );
 
-        self.pending_dials.insert(connection_id, address);
-        self.pending_connections.push(Box::pin(async move {
+        let (future, abort_handle) = futures::future::abortable(async move {
             match tokio::time::timeout(connection_open_timeout, async move {
                 TcpConnection::negotiate_connection(
                     stream,
@@ -466,11 +687,16 @@ impl Transport for TcpTransport {
                     connection_id,
                     keypair,
                     Role::Dialer,
-                    socket_address,
+                    address_to_multiaddr(socket_address),
                     yamux_config,
                     max_read_ahead_factor,
                     max_write_buffer_size,
+                    noise_rekey,
+                    #[cfg(feature = "noise-observer")]
+                    noise_observer,
                     substream_open_timeout,
+                    handshake_timeout,
+                    None,
                 )
                 .await
                 .map_err(|error| (connection_id, error.into()))
@@ -481,7 +707,13 @@ impl Transport for TcpTransport {
                 Ok(Err(error)) => Err(error),
                 Ok(Ok(connection)) => Ok(connection),
             }
-        }));
+        });
+
+        self.pending_dials.insert(connection_id, address);
+        self.abort_handles.insert(connection_id, abort_handle);
+        self.pending_connections.push(Box::pin(
+            future.map(move |result| result.unwrap_or(Err((connection_id, DialError::Aborted)))),
+        ));
 
         Ok(())
     }
@@ -489,12 +721,35 @@ impl Transport for TcpTransport {
     fn cancel(&mut self, connection_id: ConnectionId) {
         self.canceled.insert(connection_id);
     }
+
+    fn abort(&mut self, connection_id: ConnectionId) -> bool {
+        let Some(abort_handle) = self.abort_handles.remove(&connection_id) else {
+            return false;
+        };
+
+        tracing::debug!(target: LOG_TARGET, ?connection_id, "abort dial");
+
+        // the aborted future resolves with an error which is not reported
+        abort_handle.abort();
+        self.pending_dials.remove(&connection_id);
+        self.opened_raw.remove(&connection_id);
+
+        true
+    }
 }
 
 impl Stream for TcpTransport {
     type Item = TransportEvent;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Poll::Ready(change) = self.listener.poll_address_change(cx) {
+            return Poll::Ready(Some(match change {
+                ListenAddressChange::New(address) => TransportEvent::NewListenAddress { address },
+                ListenAddressChange::Expired(address) =>
+                    TransportEvent::ExpiredListenAddress { address },
+            }));
+        }
+
         if let Poll::Ready(event) = self.listener.poll_next_unpin(cx) {
             return match event {
                 None | Some(Err(_)) => Poll::Ready(None),
@@ -507,6 +762,23 @@ impl Stream for TcpTransport {
                         "pending inbound TCP connection",
                     );
 
+                    let remote_address = Multiaddr::empty()
+                        .with(Protocol::from(address.ip()))
+                        .with(Protocol::Tcp(address.port()));
+
+                    if let Some(keepalive) = self.config.keepalive {
+                        if let Err(error) = socket2::SockRef::from(&connection)
+                            .set_tcp_keepalive(&keepalive.to_socket_keepalive())
+                        {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?connection_id,
+                                ?error,
+                                "failed to enable keepalive for inbound connection",
+                            );
+                        }
+                    }
+
                     self.pending_inbound_connections.insert(
                         connection_id,
                         PendingInboundConnection {
@@ -517,6 +789,7 @@ impl Stream for TcpTransport {
 
                     Poll::Ready(Some(TransportEvent::PendingInboundConnection {
                         connection_id,
+                        address: remote_address,
                     }))
                 }
             };
@@ -532,6 +805,7 @@ impl Stream for TcpTransport {
                         canceled = self.canceled.contains(&connection_id),
                         "connection opened",
                     );
+                    self.abort_handles.remove(&connection_id);
 
                     if !self.canceled.remove(&connection_id) {
                         self.opened_raw.insert(connection_id, (stream, address.clone()));
@@ -542,19 +816,26 @@ impl Stream for TcpTransport {
                         }));
                     }
                 }
-                Err((connection_id, errors)) =>
-                    if !self.canceled.remove(&connection_id) {
+                Err((connection_id, errors)) => {
+                    // the abort handle of an aborted connection has already been removed
+                    let aborted = self.abort_handles.remove(&connection_id).is_none();
+                    let canceled = self.canceled.remove(&connection_id);
+
+                    if !aborted && !canceled {
                         return Poll::Ready(Some(TransportEvent::OpenFailure {
                             connection_id,
                             errors,
                         }));
-                    },
+                    }
+                }
             }
         }
 
         while let Poll::Ready(Some(connection)) = self.pending_connections.poll_next_unpin(cx) {
             match connection {
                 Ok(connection) => {
+                    self.abort_handles.remove(&connection.connection_id());
+
                     let peer = connection.peer();
                     let endpoint = connection.endpoint();
                     self.pending_open.insert(connection.connection_id(), connection);
@@ -562,9 +843,13 @@ impl Stream for TcpTransport {
                     return Poll::Ready(Some(TransportEvent::ConnectionEstablished {
                         peer,
                         endpoint,
+                        security: SecurityProtocol::Noise,
+                        muxer: Some(Muxer::Yamux),
                     }));
                 }
                 Err((connection_id, error)) => {
+                    self.abort_handles.remove(&connection_id);
+
                     if let Some(address) = self.pending_dials.remove(&connection_id) {
                         return Poll::Ready(Some(TransportEvent::DialFailure {
                             connection_id,
@@ -587,18 +872,20 @@ mod tests {
     use super::*;
     use crate::{
         codec::ProtocolCodec,
-        crypto::ed25519::Keypair,
+        crypto::Keypair,
+        error::SubstreamError,
         executor::DefaultExecutor,
+        protocol::InnerTransportEvent,
         transport::manager::{
-            limits::ConnectionLimitsConfig, ProtocolContext, SupportedTransport, TransportManager,
+            limits::ConnectionLimitsConfig, reputation::ReputationConfig, ProtocolContext,
+            SupportedTransport, TransportManager,
         },
-        types::protocol::ProtocolName,
+        types::{protocol::ProtocolName, SubstreamId},
         BandwidthSink, PeerId,
     };
-    use multiaddr::Protocol;
     use multihash::Multihash;
     use std::{collections::HashSet, sync::Arc};
-    use tokio::sync::mpsc::channel;
+    use tokio::sync::mpsc::{channel, Receiver};
 
     #[tokio::test]
     async fn connect_and_accept_works() {
@@ -606,13 +893,21 @@ mod tests {
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
             .try_init();
 
-        let keypair1 = Keypair::generate();
+        let keypair1 = Keypair::generate_ed25519();
         let (tx1, _rx1) = channel(64);
         let (event_tx1, _event_rx1) = channel(64);
         let bandwidth_sink = BandwidthSink::new();
 
         let handle1 = crate::transport::manager::TransportHandle {
             executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             next_substream_id: Default::default(),
             next_connection_id: Default::default(),
             keypair: keypair1.clone(),
@@ -625,6 +920,11 @@ mod tests {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
                     fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         };
@@ -637,12 +937,20 @@ mod tests {
             TcpTransport::new(handle1, transport_config1).unwrap();
         let listen_address = listen_addresses[0].clone();
 
-        let keypair2 = Keypair::generate();
+        let keypair2 = Keypair::generate_ed25519();
         let (tx2, _rx2) = channel(64);
         let (event_tx2, _event_rx2) = channel(64);
 
         let handle2 = crate::transport::manager::TransportHandle {
             executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             next_substream_id: Default::default(),
             next_connection_id: Default::default(),
             keypair: keypair2.clone(),
@@ -655,6 +963,11 @@ mod tests {
                     tx: tx2,
                     codec: ProtocolCodec::Identity(32),
                     fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         };
@@ -674,7 +987,7 @@ mod tests {
 
         let event = transport1.next().await.unwrap();
         match event {
-            TransportEvent::PendingInboundConnection { connection_id } => {
+            TransportEvent::PendingInboundConnection { connection_id, .. } => {
                 transport1.accept_pending(connection_id).unwrap();
             }
             _ => panic!("unexpected event"),
@@ -699,13 +1012,21 @@ mod tests {
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
             .try_init();
 
-        let keypair1 = Keypair::generate();
+        let keypair1 = Keypair::generate_ed25519();
         let (tx1, _rx1) = channel(64);
         let (event_tx1, _event_rx1) = channel(64);
         let bandwidth_sink = BandwidthSink::new();
 
         let handle1 = crate::transport::manager::TransportHandle {
             executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             next_substream_id: Default::default(),
             next_connection_id: Default::default(),
             keypair: keypair1.clone(),
@@ -718,6 +1039,11 @@ mod tests {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
                     fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         };
@@ -730,12 +1056,20 @@ mod tests {
             TcpTransport::new(handle1, transport_config1).unwrap();
         let listen_address = listen_addresses[0].clone();
 
-        let keypair2 = Keypair::generate();
+        let keypair2 = Keypair::generate_ed25519();
         let (tx2, _rx2) = channel(64);
         let (event_tx2, _event_rx2) = channel(64);
 
         let handle2 = crate::transport::manager::TransportHandle {
             executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             next_substream_id: Default::default(),
             next_connection_id: Default::default(),
             keypair: keypair2.clone(),
@@ -748,6 +1082,11 @@ mod tests {
                     tx: tx2,
                     codec: ProtocolCodec::Identity(32),
                     fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         };
@@ -768,7 +1107,7 @@ mod tests {
         // Reject connection.
         let event = transport1.next().await.unwrap();
         match event {
-            TransportEvent::PendingInboundConnection { connection_id } => {
+            TransportEvent::PendingInboundConnection { connection_id, .. } => {
                 transport1.reject_pending(connection_id).unwrap();
             }
             _ => panic!("unexpected event"),
@@ -787,13 +1126,21 @@ mod tests {
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
             .try_init();
 
-        let keypair1 = Keypair::generate();
+        let keypair1 = Keypair::generate_ed25519();
         let (tx1, _rx1) = channel(64);
         let (event_tx1, mut event_rx1) = channel(64);
         let bandwidth_sink = BandwidthSink::new();
 
         let handle1 = crate::transport::manager::TransportHandle {
             executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             next_substream_id: Default::default(),
             next_connection_id: Default::default(),
             keypair: keypair1.clone(),
@@ -806,6 +1153,11 @@ mod tests {
                     tx: tx1,
                     codec: ProtocolCodec::Identity(32),
                     fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         };
@@ -819,17 +1171,29 @@ mod tests {
                     TransportEvent::DialFailure { .. } => {}
                     TransportEvent::ConnectionOpened { .. } => {}
                     TransportEvent::OpenFailure { .. } => {}
+                    TransportEvent::DialSucceeded { .. } => {}
+                    TransportEvent::DialAborted { .. } => {}
                     TransportEvent::PendingInboundConnection { .. } => {}
+                    TransportEvent::NewListenAddress { .. } => {}
+                    TransportEvent::ExpiredListenAddress { .. } => {}
                 }
             }
         });
 
-        let keypair2 = Keypair::generate();
+        let keypair2 = Keypair::generate_ed25519();
         let (tx2, _rx2) = channel(64);
         let (event_tx2, _event_rx2) = channel(64);
 
         let handle2 = crate::transport::manager::TransportHandle {
             executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
             next_substream_id: Default::default(),
             next_connection_id: Default::default(),
             keypair: keypair2.clone(),
@@ -842,14 +1206,19 @@ mod tests {
                     tx: tx2,
                     codec: ProtocolCodec::Identity(32),
                     fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
                 },
             )]),
         };
 
         let (mut transport2, _) = TcpTransport::new(handle2, Default::default()).unwrap();
 
-        let peer1: PeerId = PeerId::from_public_key(&keypair1.public().into());
-        let peer2: PeerId = PeerId::from_public_key(&keypair2.public().into());
+        let peer1: PeerId = keypair1.to_peer_id();
+        let peer2: PeerId = keypair2.to_peer_id();
 
         tracing::info!(target: LOG_TARGET, "peer1 {peer1}, peer2 {peer2}");
 
@@ -880,11 +1249,12 @@ mod tests {
     #[tokio::test]
     async fn dial_error_reported_for_outbound_connections() {
         let (mut manager, _handle) = TransportManager::new(
-            Keypair::generate(),
+            Keypair::generate_ed25519(),
             HashSet::new(),
             BandwidthSink::new(),
             8usize,
             ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
         );
         let handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
         manager.register_transport(
@@ -900,8 +1270,8 @@ mod tests {
         )
         .unwrap();
 
-        let keypair = Keypair::generate();
-        let peer_id = PeerId::from_public_key(&keypair.public().into());
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.to_peer_id();
         let multiaddr = Multiaddr::empty()
             .with(Protocol::Ip4(std::net::Ipv4Addr::new(255, 254, 253, 252)))
             .with(Protocol::Tcp(8888))
@@ -928,4 +1298,382 @@ mod tests {
         ));
         assert!(transport.pending_dials.is_empty());
     }
+
+    fn make_transport_handle(keypair: Keypair) -> crate::transport::manager::TransportHandle {
+        make_transport_handle_with_events(keypair).0
+    }
+
+    /// Create transport handle and the receiver of the events sent to its `/notif/1` protocol.
+    fn make_transport_handle_with_events(
+        keypair: Keypair,
+    ) -> (
+        crate::transport::manager::TransportHandle,
+        Receiver<InnerTransportEvent>,
+    ) {
+        let (tx, rx) = channel(64);
+        let (event_tx, _event_rx) = channel(64);
+
+        let handle = crate::transport::manager::TransportHandle {
+            executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
+            next_substream_id: Default::default(),
+            next_connection_id: Default::default(),
+            keypair,
+            tx: event_tx,
+            bandwidth_sink: BandwidthSink::new(),
+
+            protocols: HashMap::from_iter([(
+                ProtocolName::from("/notif/1"),
+                ProtocolContext {
+                    tx,
+                    codec: ProtocolCodec::Identity(32),
+                    fallback_names: Vec::new(),
+                    idle_timeout: None,
+                    inbound_filter: None,
+                    priority: Default::default(),
+                    latency_sensitive: false,
+                    inbound_order: Default::default(),
+                },
+            )]),
+        };
+
+        (handle, rx)
+    }
+
+    #[tokio::test]
+    async fn closed_connection_fails_unopened_substreams() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (handle1, mut rx1) = make_transport_handle_with_events(Keypair::generate_ed25519());
+        let (mut transport1, listen_addresses) = TcpTransport::new(
+            handle1,
+            Config {
+                listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let (handle2, mut rx2) = make_transport_handle_with_events(Keypair::generate_ed25519());
+        let (mut transport2, _) = TcpTransport::new(
+            handle2,
+            Config {
+                listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        transport2.dial(ConnectionId::new(), listen_addresses[0].clone()).unwrap();
+
+        match transport1.next().await.unwrap() {
+            TransportEvent::PendingInboundConnection { connection_id, .. } => {
+                transport1.accept_pending(connection_id).unwrap();
+            }
+            _ => panic!("unexpected event"),
+        }
+
+        // start both connections like `TransportManager` would
+        let (event1, event2) = tokio::join!(transport1.next(), transport2.next());
+        for (transport, event) in [(&mut transport1, event1), (&mut transport2, event2)] {
+            match event {
+                Some(TransportEvent::ConnectionEstablished { endpoint, .. }) => {
+                    transport.accept(endpoint.connection_id()).unwrap();
+                }
+                event => panic!("unexpected event: {event:?}"),
+            }
+        }
+
+        let mut connection = match rx1.recv().await {
+            Some(InnerTransportEvent::ConnectionEstablished { sender, .. }) => sender,
+            event => panic!("unexpected event: {event:?}"),
+        };
+        assert!(std::matches!(
+            rx2.recv().await,
+            Some(InnerTransportEvent::ConnectionEstablished { .. })
+        ));
+
+        // open substream and close the connection before the substream has been opened
+        let permit = connection.try_get_permit().unwrap();
+        connection
+            .open_substream(
+                ProtocolName::from("/notif/1"),
+                Vec::new(),
+                SubstreamId::from(1337usize),
+                permit,
+            )
+            .unwrap();
+        connection.force_close().unwrap();
+
+        match rx1.recv().await {
+            Some(InnerTransportEvent::SubstreamOpenFailure { substream, error }) => {
+                assert_eq!(substream, SubstreamId::from(1337usize));
+                assert!(std::matches!(error, SubstreamError::ConnectionClosed));
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+        assert!(std::matches!(
+            rx1.recv().await,
+            Some(InnerTransportEvent::ConnectionClosed { .. })
+        ));
+
+        // remote peer's protocols are informed of the closed connection as well
+        assert!(std::matches!(
+            rx2.recv().await,
+            Some(InnerTransportEvent::ConnectionClosed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn aborted_dial_closes_socket() {
+        let (mut manager, _handle) = TransportManager::new(
+            Keypair::generate_ed25519(),
+            HashSet::new(),
+            BandwidthSink::new(),
+            8usize,
+            ConnectionLimitsConfig::default(),
+            ReputationConfig::default(),
+        );
+        let handle = manager.transport_handle(Arc::new(DefaultExecutor {}));
+        manager.register_transport(
+            SupportedTransport::Tcp,
+            Box::new(crate::transport::dummy::DummyTransport::new()),
+        );
+        let (mut transport, _) = TcpTransport::new(
+            handle,
+            Config {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // remote accepts the connection but never responds to the negotiation
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let peer = PeerId::random();
+        let address = Multiaddr::empty()
+            .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Tcp(listener.local_addr().unwrap().port()))
+            .with(Protocol::P2p(
+                Multihash::from_bytes(&peer.to_bytes()).unwrap(),
+            ));
+        transport.dial(ConnectionId::from(0usize), address).unwrap();
+
+        let mut stream = tokio::select! {
+            result = listener.accept() => result.unwrap().0,
+            event = transport.next() => panic!("unexpected event: {event:?}"),
+        };
+
+        assert!(transport.abort(ConnectionId::from(0usize)));
+        assert!(!transport.abort(ConnectionId::from(0usize)));
+        assert!(transport.pending_dials.is_empty());
+
+        // the aborted dial is dropped without reporting an event
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), transport.next())
+                .await
+                .is_err()
+        );
+
+        let mut buffer = Vec::new();
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut buffer),
+        )
+        .await
+        .expect("dial task to close the socket")
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn dial_onion_address_through_socks5_proxy() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let keypair1 = Keypair::generate_ed25519();
+        let peer1 = keypair1.to_peer_id();
+        let (mut transport1, listen_addresses) = TcpTransport::new(
+            make_transport_handle(keypair1),
+            Config {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let (AddressType::Socket(listen_address), _) =
+            TcpAddress::multiaddr_to_socket_address(&listen_addresses[0]).unwrap()
+        else {
+            panic!("invalid listen address");
+        };
+
+        // stub proxy which records the requested target and forwards the connection to
+        // `transport1` regardless of the target
+        let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_address = proxy.local_addr().unwrap();
+        let (target_tx, mut target_rx) = channel(1);
+
+        tokio::spawn(async move {
+            let (mut client, _) = proxy.accept().await.unwrap();
+
+            let mut greeting = [0u8; 3];
+            client.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            client.write_all(&[5, 0]).await.unwrap();
+
+            let mut request = [0u8; 5];
+            client.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[..4], [5, 1, 0, 3]);
+            let mut target = vec![0u8; request[4] as usize + 2];
+            client.read_exact(&mut target).await.unwrap();
+            target_tx.send(target).await.unwrap();
+
+            let mut server = TcpStream::connect(listen_address).await.unwrap();
+            client.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0]).await.unwrap();
+
+            let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+        });
+
+        let (mut transport2, _) = TcpTransport::new(
+            make_transport_handle(Keypair::generate_ed25519()),
+            Config {
+                listen_addresses: Vec::new(),
+                socks5_proxy: Some(Socks5Config::new(proxy_address)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let address: Multiaddr =
+            "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"
+                .parse()
+                .unwrap();
+        let address = address.with(Protocol::P2p(
+            Multihash::from_bytes(&peer1.to_bytes()).unwrap(),
+        ));
+        transport2.dial(ConnectionId::new(), address).unwrap();
+
+        let (tx, mut from_transport2) = channel(64);
+        tokio::spawn(async move {
+            let event = transport2.next().await;
+            tx.send(event).await.unwrap();
+        });
+
+        // the onion address is passed to the proxy without being resolved locally
+        let target = target_rx.recv().await.unwrap();
+        assert_eq!(
+            &target[..target.len() - 2],
+            b"vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd.onion"
+        );
+        assert_eq!(target[target.len() - 2..], 1234u16.to_be_bytes());
+
+        match transport1.next().await.unwrap() {
+            TransportEvent::PendingInboundConnection { connection_id, .. } => {
+                transport1.accept_pending(connection_id).unwrap();
+            }
+            _ => panic!("unexpected event"),
+        }
+
+        assert!(std::matches!(
+            transport1.next().await,
+            Some(TransportEvent::ConnectionEstablished { .. })
+        ));
+
+        match from_transport2.recv().await.unwrap() {
+            Some(TransportEvent::ConnectionEstablished { endpoint, .. }) => {
+                assert!(std::matches!(
+                    endpoint.address().iter().next(),
+                    Some(Protocol::Onion3(_))
+                ));
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn proxied_dns_dial_checked_against_ip_filter() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        // stub proxy which must never be asked to connect to the denied address
+        let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_address = proxy.local_addr().unwrap();
+        tokio::spawn(async move {
+            if let Ok((mut client, _)) = proxy.accept().await {
+                let mut buffer = [0u8; 64];
+                let _ = tokio::io::AsyncReadExt::read(&mut client, &mut buffer).await;
+                panic!("proxy contacted for a denied address");
+            }
+        });
+
+        let mut handle = make_transport_handle(Keypair::generate_ed25519());
+        handle.ip_filter = IpFilterConfig::default()
+            .deny("127.0.0.0/8".parse().unwrap())
+            .deny("::1".parse().unwrap());
+
+        let (mut transport, _) = TcpTransport::new(
+            handle,
+            Config {
+                listen_addresses: Vec::new(),
+                socks5_proxy: Some(Socks5Config::new(proxy_address)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let peer = PeerId::random();
+        let address: Multiaddr = "/dns/localhost/tcp/8888".parse().unwrap();
+        let address = address.with(Protocol::P2p(
+            Multihash::from_bytes(&peer.to_bytes()).unwrap(),
+        ));
+        transport.dial(ConnectionId::new(), address).unwrap();
+
+        match transport.next().await.unwrap() {
+            TransportEvent::DialFailure {
+                error: DialError::AddressDenied(address),
+                ..
+            } => assert!(address.is_loopback()),
+            event => panic!("unexpected event: {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn latency_sensitive_protocol_enables_nodelay() {
+        let mut handle = make_transport_handle(Keypair::generate_ed25519());
+        handle
+            .protocols
+            .get_mut(&ProtocolName::from("/notif/1"))
+            .unwrap()
+            .latency_sensitive = true;
+
+        let (mut transport, listen_addresses) = TcpTransport::new(
+            handle,
+            Config {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                nodelay: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(transport.config.nodelay);
+
+        let Some(Protocol::Tcp(port)) = listen_addresses[0].iter().nth(1) else {
+            panic!("invalid listen address");
+        };
+        let _client = tokio::net::TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+
+        let (stream, _) = transport.listener.next().await.unwrap().unwrap();
+        assert!(stream.nodelay().unwrap());
+    }
 }
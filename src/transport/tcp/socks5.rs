@@ -0,0 +1,248 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! SOCKS5 client handshake ([RFC 1928](https://www.rfc-editor.org/rfc/rfc1928)).
+
+use crate::{
+    error::{DialError, ProxyError},
+    transport::common::listener::{onion3_hostname, AddressType},
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use std::net::SocketAddr;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::tcp::socks5";
+
+/// SOCKS protocol version.
+const VERSION: u8 = 0x05;
+
+/// Version of the username/password authentication subnegotiation.
+const AUTH_VERSION: u8 = 0x01;
+
+/// No authentication required.
+const METHOD_NO_AUTH: u8 = 0x00;
+
+/// Username/password authentication.
+const METHOD_USERNAME_PASSWORD: u8 = 0x02;
+
+/// None of the offered methods is acceptable.
+const METHOD_NOT_ACCEPTABLE: u8 = 0xff;
+
+/// `CONNECT` command.
+const COMMAND_CONNECT: u8 = 0x01;
+
+/// IPv4 address type.
+const ATYP_IPV4: u8 = 0x01;
+
+/// Domain name address type.
+const ATYP_DOMAIN: u8 = 0x03;
+
+/// IPv6 address type.
+const ATYP_IPV6: u8 = 0x04;
+
+/// Successful reply.
+const REPLY_SUCCEEDED: u8 = 0x00;
+
+/// Ask the proxy connected to `io` to open a connection to `target`.
+///
+/// Domain names, including onion addresses, are passed to the proxy as-is so they're resolved
+/// by the proxy and never by the local node. Once the function returns, `io` is connected to
+/// `target`.
+pub(crate) async fn connect<T: AsyncRead + AsyncWrite + Unpin>(
+    io: &mut T,
+    target: &AddressType,
+    credentials: Option<&(String, String)>,
+) -> Result<(), DialError> {
+    authenticate(io, credentials).await?;
+
+    let mut request = vec![VERSION, COMMAND_CONNECT, 0x00];
+    let port = match target {
+        AddressType::Socket(SocketAddr::V4(address)) => {
+            request.push(ATYP_IPV4);
+            request.extend_from_slice(&address.ip().octets());
+            address.port()
+        }
+        AddressType::Socket(SocketAddr::V6(address)) => {
+            request.push(ATYP_IPV6);
+            request.extend_from_slice(&address.ip().octets());
+            address.port()
+        }
+        AddressType::Dns { address, port, .. } => {
+            push_domain(&mut request, address)?;
+            *port
+        }
+        AddressType::Onion3(address) => {
+            push_domain(&mut request, &onion3_hostname(address))?;
+            address.port()
+        }
+    };
+    request.extend_from_slice(&port.to_be_bytes());
+
+    tracing::trace!(target: LOG_TARGET, ?target, "send connect request");
+
+    io.write_all(&request).await?;
+    io.flush().await?;
+
+    let mut reply = [0u8; 4];
+    io.read_exact(&mut reply).await?;
+
+    if reply[0] != VERSION {
+        return Err(ProxyError::InvalidResponse.into());
+    }
+    if reply[1] != REPLY_SUCCEEDED {
+        tracing::debug!(target: LOG_TARGET, ?target, reply = ?reply[1], "proxy failed to connect");
+        return Err(ProxyError::ConnectFailed(reply[1]).into());
+    }
+
+    // the address the proxy bound for the connection is not needed
+    let bound_len = match reply[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => io.read_u8().await? as usize,
+        _ => return Err(ProxyError::InvalidResponse.into()),
+    };
+    let mut bound = vec![0u8; bound_len + 2];
+    io.read_exact(&mut bound).await?;
+
+    Ok(())
+}
+
+/// Negotiate the authentication method with the proxy and authenticate if required.
+async fn authenticate<T: AsyncRead + AsyncWrite + Unpin>(
+    io: &mut T,
+    credentials: Option<&(String, String)>,
+) -> Result<(), DialError> {
+    let greeting: &[u8] = match credentials {
+        Some(_) => &[VERSION, 2, METHOD_NO_AUTH, METHOD_USERNAME_PASSWORD],
+        None => &[VERSION, 1, METHOD_NO_AUTH],
+    };
+    io.write_all(greeting).await?;
+    io.flush().await?;
+
+    let mut response = [0u8; 2];
+    io.read_exact(&mut response).await?;
+
+    if response[0] != VERSION {
+        return Err(ProxyError::InvalidResponse.into());
+    }
+
+    match (response[1], credentials) {
+        (METHOD_NO_AUTH, _) => Ok(()),
+        (METHOD_USERNAME_PASSWORD, Some((username, password))) => {
+            let mut request = vec![AUTH_VERSION];
+            push_field(&mut request, username.as_bytes())?;
+            push_field(&mut request, password.as_bytes())?;
+
+            io.write_all(&request).await?;
+            io.flush().await?;
+
+            let mut response = [0u8; 2];
+            io.read_exact(&mut response).await?;
+
+            match response {
+                [AUTH_VERSION, 0x00] => Ok(()),
+                [AUTH_VERSION, _] => Err(ProxyError::AuthenticationFailed.into()),
+                _ => Err(ProxyError::InvalidResponse.into()),
+            }
+        }
+        (METHOD_NOT_ACCEPTABLE, _) => Err(ProxyError::NoAcceptableMethod.into()),
+        _ => Err(ProxyError::InvalidResponse.into()),
+    }
+}
+
+/// Append domain name address to `request`.
+fn push_domain(request: &mut Vec<u8>, domain: &str) -> Result<(), ProxyError> {
+    request.push(ATYP_DOMAIN);
+    push_field(request, domain.as_bytes())
+}
+
+/// Append length-prefixed `field` to `request`.
+fn push_field(request: &mut Vec<u8>, field: &[u8]) -> Result<(), ProxyError> {
+    let len = u8::try_from(field.len()).map_err(|_| ProxyError::FieldTooLong)?;
+
+    request.push(len);
+    request.extend_from_slice(field);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::common::listener::DnsType;
+
+    #[tokio::test]
+    async fn connect_with_credentials() {
+        let (mut client, mut proxy) = tokio::io::duplex(1024);
+        let credentials = (String::from("user"), String::from("pass"));
+
+        let proxy = tokio::spawn(async move {
+            let mut greeting = [0u8; 4];
+            proxy.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 2, 0, 2]);
+            proxy.write_all(&[5, 2]).await.unwrap();
+
+            let mut auth = [0u8; 11];
+            proxy.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth, b"\x01\x04user\x04pass");
+            proxy.write_all(&[1, 0]).await.unwrap();
+
+            let mut request = [0u8; 5 + 11 + 2];
+            proxy.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..5], &[5, 1, 0, 3, 11]);
+            assert_eq!(&request[5..16], b"example.com");
+            assert_eq!(&request[16..], &8888u16.to_be_bytes());
+            proxy.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 80]).await.unwrap();
+        });
+
+        let target = AddressType::Dns {
+            address: String::from("example.com"),
+            port: 8888,
+            dns_type: DnsType::Dns,
+        };
+        connect(&mut client, &target, Some(&credentials)).await.unwrap();
+        proxy.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn connect_failure_reported() {
+        let (mut client, mut proxy) = tokio::io::duplex(1024);
+
+        tokio::spawn(async move {
+            let mut greeting = [0u8; 3];
+            proxy.read_exact(&mut greeting).await.unwrap();
+            proxy.write_all(&[5, 0]).await.unwrap();
+
+            let mut request = [0u8; 10];
+            proxy.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..4], &[5, 1, 0, 1]);
+
+            // connection refused
+            proxy.write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0]).await.unwrap();
+        });
+
+        let target = AddressType::Socket("127.0.0.1:8888".parse().unwrap());
+        assert!(std::matches!(
+            connect(&mut client, &target, None).await,
+            Err(DialError::ProxyError(ProxyError::ConnectFailed(5)))
+        ));
+    }
+}
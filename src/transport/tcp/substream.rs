@@ -18,7 +18,11 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::{protocol::Permit, BandwidthSink};
+use crate::{
+    multistream_select::Negotiated,
+    protocol::{OptimisticProtocol, Permit},
+    BandwidthSink,
+};
 
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::compat::Compat;
@@ -36,26 +40,53 @@ use std::{
 #[derive(Debug)]
 pub struct Substream {
     /// Underlying socket.
-    io: Compat<crate::yamux::Stream>,
+    io: Compat<Negotiated<crate::yamux::Stream>>,
 
     /// Bandwidth sink.
     bandwidth_sink: BandwidthSink,
 
     /// Connection permit.
-    _permit: Permit,
+    permit: Permit,
+
+    /// Protocol awaiting confirmation from the remote peer, if negotiated optimistically.
+    optimistic: Option<OptimisticProtocol>,
 }
 
 impl Substream {
     /// Create new [`Substream`].
     pub fn new(
-        io: Compat<crate::yamux::Stream>,
+        io: Compat<Negotiated<crate::yamux::Stream>>,
         bandwidth_sink: BandwidthSink,
-        _permit: Permit,
+        permit: Permit,
     ) -> Self {
         Self {
             io,
             bandwidth_sink,
-            _permit,
+            permit,
+            optimistic: None,
+        }
+    }
+
+    /// Report I/O errors to `optimistic` so the protocol is forgotten if the remote rejects it.
+    pub(crate) fn with_optimistic_protocol(
+        mut self,
+        optimistic: Option<OptimisticProtocol>,
+    ) -> Self {
+        self.optimistic = optimistic;
+        self
+    }
+
+    /// Cork or uncork the substream.
+    ///
+    /// See [`crate::yamux::Stream::set_corked()`] for more details.
+    pub(crate) fn set_corked(&mut self, corked: bool) {
+        self.io.get_mut().get_mut().set_corked(corked);
+    }
+
+    /// Inspect I/O error of the substream.
+    fn on_error(&self, error: &io::Error) {
+        if let Some(optimistic) = &self.optimistic {
+            optimistic.on_error(error);
         }
     }
 }
@@ -66,9 +97,17 @@ impl AsyncRead for Substream {
         cx: &mut Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
+        self.permit.poll_idle(cx);
+
         match futures::ready!(Pin::new(&mut self.io).poll_read(cx, buf)) {
-            Err(error) => Poll::Ready(Err(error)),
+            Err(error) => {
+                self.on_error(&error);
+                Poll::Ready(Err(error))
+            }
             Ok(res) => {
+                if !buf.filled().is_empty() {
+                    self.permit.on_activity();
+                }
                 self.bandwidth_sink.increase_inbound(buf.filled().len());
                 Poll::Ready(Ok(res))
             }
@@ -82,9 +121,15 @@ impl AsyncWrite for Substream {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, io::Error>> {
+        self.permit.poll_idle(cx);
+
         match futures::ready!(Pin::new(&mut self.io).poll_write(cx, buf)) {
-            Err(error) => Poll::Ready(Err(error)),
+            Err(error) => {
+                self.on_error(&error);
+                Poll::Ready(Err(error))
+            }
             Ok(nwritten) => {
+                self.permit.on_activity();
                 self.bandwidth_sink.increase_outbound(nwritten);
                 Poll::Ready(Ok(nwritten))
             }
@@ -107,9 +152,15 @@ impl AsyncWrite for Substream {
         cx: &mut Context<'_>,
         bufs: &[io::IoSlice<'_>],
     ) -> Poll<Result<usize, io::Error>> {
+        self.permit.poll_idle(cx);
+
         match futures::ready!(Pin::new(&mut self.io).poll_write_vectored(cx, bufs)) {
-            Err(error) => Poll::Ready(Err(error)),
+            Err(error) => {
+                self.on_error(&error);
+                Poll::Ready(Err(error))
+            }
             Ok(nwritten) => {
+                self.permit.on_activity();
                 self.bandwidth_sink.increase_outbound(nwritten);
                 Poll::Ready(Ok(nwritten))
             }
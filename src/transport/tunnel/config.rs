@@ -0,0 +1,104 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Tunnel transport configuration.
+
+use crate::{
+    crypto::noise::{MAX_READ_AHEAD_FACTOR, MAX_WRITE_BUFFER_SIZE},
+    transport::{
+        tunnel::handle::{TunnelCommand, TunnelHandle},
+        CONNECTION_OPEN_TIMEOUT, HANDSHAKE_TIMEOUT, SUBSTREAM_OPEN_TIMEOUT,
+    },
+    DEFAULT_CHANNEL_SIZE,
+};
+
+use tokio::sync::mpsc::{channel, Receiver};
+
+pub use crate::crypto::noise::RekeyConfig;
+
+/// Tunnel transport configuration.
+#[derive(Debug)]
+pub struct Config {
+    /// Yamux configuration.
+    pub yamux_config: crate::yamux::Config,
+
+    /// Noise read-ahead frame count.
+    ///
+    /// Specifies how many Noise frames are read per call to the underlying substream.
+    pub noise_read_ahead_frame_count: usize,
+
+    /// Noise write buffer size.
+    ///
+    /// Specifes how many Noise frames are tried to be coalesced into a single write.
+    pub noise_write_buffer_size: usize,
+
+    /// Noise session re-key configuration.
+    ///
+    /// If set, the key used to encrypt outbound data is rotated after the configured number of
+    /// bytes or amount of time, allowing long-lived connections to limit how much data is
+    /// protected by a single key. The connection and its substreams are kept open across the
+    /// rotation.
+    ///
+    /// Disabled by default.
+    pub noise_rekey: Option<RekeyConfig>,
+
+    /// Connection open timeout.
+    ///
+    /// How long should litep2p wait for a connection to be negotiated over the tunnel substream
+    /// before the tunnel is closed.
+    pub connection_open_timeout: std::time::Duration,
+
+    /// Substream open timeout.
+    ///
+    /// How long should litep2p wait for a substream to be opened before considering
+    /// the substream rejected.
+    pub substream_open_timeout: std::time::Duration,
+
+    /// Noise handshake timeout.
+    ///
+    /// How long should litep2p wait for the Noise handshake to complete before the tunnel is
+    /// closed.
+    pub handshake_timeout: std::time::Duration,
+
+    /// RX channel for receiving tunnel substreams from [`TunnelHandle`].
+    pub(super) command_rx: Receiver<TunnelCommand>,
+}
+
+impl Config {
+    /// Create new [`Config`] and the [`TunnelHandle`] used to hand substreams over to the
+    /// transport.
+    pub fn new() -> (Self, TunnelHandle) {
+        let (command_tx, command_rx) = channel(DEFAULT_CHANNEL_SIZE);
+
+        (
+            Self {
+                yamux_config: Default::default(),
+                noise_read_ahead_frame_count: MAX_READ_AHEAD_FACTOR,
+                noise_write_buffer_size: MAX_WRITE_BUFFER_SIZE,
+                noise_rekey: None,
+                connection_open_timeout: CONNECTION_OPEN_TIMEOUT,
+                substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
+                handshake_timeout: HANDSHAKE_TIMEOUT,
+                command_rx,
+            },
+            TunnelHandle::new(command_tx),
+        )
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Tunneled connection.
+//!
+//! Tunnel substreams are upgraded with Noise and Yamux exactly like TCP sockets, so the
+//! connection implementation of the TCP transport is reused with [`Substream`] as the socket.
+
+use crate::{
+    substream::Substream,
+    transport::tcp::connection::{self, TcpConnection},
+};
+
+/// Connection tunneled over a substream.
+pub(super) type TunnelConnection = TcpConnection<Substream>;
+
+/// Connection context for an opened connection that hasn't yet started its event loop.
+pub(super) type NegotiatedConnection = connection::NegotiatedConnection<Substream>;
@@ -0,0 +1,81 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Handle for handing substreams over to the tunnel transport.
+
+use crate::{error::Error, substream::Substream};
+
+use tokio::sync::mpsc::Sender;
+
+/// Command sent by [`TunnelHandle`] to the tunnel transport.
+#[derive(Debug)]
+pub(super) enum TunnelCommand {
+    /// Establish a connection over `substream` as the dialer.
+    Open {
+        /// Tunnel substream.
+        substream: Substream,
+    },
+
+    /// Establish a connection over `substream` as the listener.
+    Accept {
+        /// Tunnel substream.
+        substream: Substream,
+    },
+}
+
+/// Handle for establishing connections over substreams of existing connections.
+///
+/// The substreams must be opened with [`ProtocolCodec::Unspecified`](crate::codec::ProtocolCodec)
+/// and they must not be read from or written to after they've been handed over to the transport.
+/// One side of the tunnel must call [`TunnelHandle::open()`] and the other side
+/// [`TunnelHandle::accept()`]. The tunneled connection is reported like any other connection,
+/// with an address of the form `/p2p/<peer>/p2p-circuit`, where `<peer>` is the peer the tunnel
+/// substream is opened to.
+#[derive(Debug, Clone)]
+pub struct TunnelHandle {
+    /// TX channel for sending commands to the transport.
+    command_tx: Sender<TunnelCommand>,
+}
+
+impl TunnelHandle {
+    /// Create new [`TunnelHandle`].
+    pub(super) fn new(command_tx: Sender<TunnelCommand>) -> Self {
+        Self { command_tx }
+    }
+
+    /// Establish a connection over `substream`, acting as the dialer.
+    pub async fn open(&self, substream: Substream) -> crate::Result<()> {
+        self.command_tx
+            .send(TunnelCommand::Open { substream })
+            .await
+            .map_err(|_| Error::EssentialTaskClosed)
+    }
+
+    /// Establish a connection over `substream`, acting as the listener.
+    ///
+    /// The connection is subject to the same checks as other inbound connections, such as the
+    /// connection limits.
+    pub async fn accept(&self, substream: Substream) -> crate::Result<()> {
+        self.command_tx
+            .send(TunnelCommand::Accept { substream })
+            .await
+            .map_err(|_| Error::EssentialTaskClosed)
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Transport which tunnels connections over substreams of existing connections.
+//!
+//! The substreams are provided by the user through [`TunnelHandle`] and upgraded with Noise and
+//! Yamux, the same way as TCP connections are. The tunnel can't be dialed using an address.
+
+use crate::{
+    config::Role,
+    error::{DialError, Error},
+    substream::Substream,
+    transport::{
+        manager::TransportHandle,
+        tunnel::{
+            config::Config,
+            connection::{NegotiatedConnection, TunnelConnection},
+            handle::TunnelCommand,
+        },
+        Muxer, SecurityProtocol, Transport, TransportBuilder, TransportEvent,
+    },
+    types::ConnectionId,
+};
+
+use futures::{
+    future::BoxFuture,
+    stream::{FuturesUnordered, Stream, StreamExt},
+};
+use multiaddr::{Multiaddr, Protocol};
+use multihash::Multihash;
+
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+mod connection;
+mod handle;
+
+pub mod config;
+
+pub use handle::TunnelHandle;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::tunnel";
+
+/// Tunnel transport.
+pub(crate) struct TunnelTransport {
+    /// Transport context.
+    context: TransportHandle,
+
+    /// Transport configuration.
+    config: Config,
+
+    /// Tunnel substreams waiting for approval/rejection from `TransportManager`.
+    pending_inbound_connections: HashMap<ConnectionId, (Substream, Multiaddr)>,
+
+    /// Connections being negotiated over tunnel substreams.
+    pending_connections: FuturesUnordered<
+        BoxFuture<'static, Result<NegotiatedConnection, (ConnectionId, DialError)>>,
+    >,
+
+    /// Connections which have been negotiated but are being validated by the
+    /// `TransportManager`.
+    pending_open: HashMap<ConnectionId, NegotiatedConnection>,
+}
+
+impl TunnelTransport {
+    /// Get the address of a connection tunneled over `substream`.
+    fn tunnel_address(substream: &Substream) -> Multiaddr {
+        Multiaddr::empty()
+            .with(Protocol::P2p(Multihash::from(substream.peer())))
+            .with(Protocol::P2pCircuit)
+    }
+
+    /// Negotiate a connection over `substream`.
+    fn negotiate_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        substream: Substream,
+        address: Multiaddr,
+        role: Role,
+    ) {
+        let yamux_config = self.config.yamux_config.clone();
+        let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
+        let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        let connection_open_timeout = self.config.connection_open_timeout;
+        let substream_open_timeout = self.config.substream_open_timeout;
+        let handshake_timeout = self.config.handshake_timeout;
+        let keypair = self.context.keypair.clone();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?connection_id,
+            ?address,
+            ?role,
+            "negotiate tunneled connection",
+        );
+
+        self.pending_connections.push(Box::pin(async move {
+            match tokio::time::timeout(connection_open_timeout, async move {
+                TunnelConnection::negotiate_connection(
+                    substream,
+                    None,
+                    connection_id,
+                    keypair,
+                    role,
+                    address,
+                    yamux_config,
+                    max_read_ahead_factor,
+                    max_write_buffer_size,
+                    noise_rekey,
+                    #[cfg(feature = "noise-observer")]
+                    None,
+                    substream_open_timeout,
+                    handshake_timeout,
+                    None,
+                )
+                .await
+                .map_err(|error| (connection_id, error.into()))
+            })
+            .await
+            {
+                Err(_) => Err((connection_id, DialError::Timeout)),
+                Ok(Err(error)) => Err(error),
+                Ok(Ok(connection)) => Ok(connection),
+            }
+        }));
+    }
+}
+
+impl TransportBuilder for TunnelTransport {
+    type Config = Config;
+    type Transport = TunnelTransport;
+
+    /// Create new [`TunnelTransport`].
+    fn new(
+        context: TransportHandle,
+        config: Self::Config,
+    ) -> crate::Result<(Self, Vec<Multiaddr>)> {
+        tracing::debug!(target: LOG_TARGET, "start tunnel transport");
+
+        Ok((
+            Self {
+                context,
+                config,
+                pending_open: HashMap::new(),
+                pending_inbound_connections: HashMap::new(),
+                pending_connections: FuturesUnordered::new(),
+            },
+            Vec::new(),
+        ))
+    }
+}
+
+impl Transport for TunnelTransport {
+    fn dial(&mut self, connection_id: ConnectionId, address: Multiaddr) -> crate::Result<()> {
+        tracing::warn!(
+            target: LOG_TARGET,
+            ?connection_id,
+            ?address,
+            "tunnels cannot be dialed",
+        );
+
+        Err(Error::NotSupported("tunnels cannot be dialed".to_string()))
+    }
+
+    fn accept(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        let context = self
+            .pending_open
+            .remove(&connection_id)
+            .ok_or(Error::ConnectionDoesntExist(connection_id))?;
+        let protocol_set = self.context.protocol_set(connection_id);
+        let bandwidth_sink = self.context.bandwidth_sink.clone();
+        let next_substream_id = self.context.next_substream_id.clone();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?connection_id,
+            "start connection",
+        );
+
+        self.context.executor.run(Box::pin(async move {
+            if let Err(error) =
+                TunnelConnection::new(context, protocol_set, bandwidth_sink, next_substream_id)
+                    .start()
+                    .await
+            {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?connection_id,
+                    ?error,
+                    "connection exited with error",
+                );
+            }
+        }));
+
+        Ok(())
+    }
+
+    fn accept_pending(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        let (substream, address) = self
+            .pending_inbound_connections
+            .remove(&connection_id)
+            .ok_or(Error::ConnectionDoesntExist(connection_id))?;
+
+        self.negotiate_connection(connection_id, substream, address, Role::Listener);
+
+        Ok(())
+    }
+
+    fn reject_pending(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        self.pending_inbound_connections
+            .remove(&connection_id)
+            .map_or(Err(Error::ConnectionDoesntExist(connection_id)), |_| Ok(()))
+    }
+
+    fn reject(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        self.pending_open
+            .remove(&connection_id)
+            .map_or(Err(Error::ConnectionDoesntExist(connection_id)), |_| Ok(()))
+    }
+
+    fn open(
+        &mut self,
+        _connection_id: ConnectionId,
+        _addresses: Vec<Multiaddr>,
+    ) -> crate::Result<()> {
+        Err(Error::NotSupported("tunnels cannot be dialed".to_string()))
+    }
+
+    fn negotiate(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        Err(Error::ConnectionDoesntExist(connection_id))
+    }
+
+    fn cancel(&mut self, _connection_id: ConnectionId) {}
+}
+
+impl Stream for TunnelTransport {
+    type Item = TransportEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        while let Poll::Ready(Some(command)) = self.config.command_rx.poll_recv(cx) {
+            let connection_id = self.context.next_connection_id();
+
+            match command {
+                TunnelCommand::Open { substream } => {
+                    let address = Self::tunnel_address(&substream);
+
+                    self.negotiate_connection(connection_id, substream, address, Role::Dialer);
+                }
+                TunnelCommand::Accept { substream } => {
+                    let address = Self::tunnel_address(&substream);
+
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        ?connection_id,
+                        ?address,
+                        "pending inbound tunnel",
+                    );
+
+                    self.pending_inbound_connections
+                        .insert(connection_id, (substream, address.clone()));
+
+                    return Poll::Ready(Some(TransportEvent::PendingInboundConnection {
+                        connection_id,
+                        address,
+                    }));
+                }
+            }
+        }
+
+        while let Poll::Ready(Some(connection)) = self.pending_connections.poll_next_unpin(cx) {
+            match connection {
+                Ok(connection) => {
+                    let peer = connection.peer();
+                    let endpoint = connection.endpoint();
+                    self.pending_open.insert(connection.connection_id(), connection);
+
+                    return Poll::Ready(Some(TransportEvent::ConnectionEstablished {
+                        peer,
+                        endpoint,
+                        security: SecurityProtocol::Noise,
+                        muxer: Some(Muxer::Yamux),
+                    }));
+                }
+                Err((connection_id, error)) => {
+                    // tunnels aren't dialed by `TransportManager` so failures are not reported
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?connection_id,
+                        ?error,
+                        "failed to negotiate tunneled connection",
+                    );
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
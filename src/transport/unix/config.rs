@@ -0,0 +1,110 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Unix domain socket transport configuration.
+
+use crate::{
+    crypto::noise::{MAX_READ_AHEAD_FACTOR, MAX_WRITE_BUFFER_SIZE},
+    transport::{CONNECTION_OPEN_TIMEOUT, HANDSHAKE_TIMEOUT, SUBSTREAM_OPEN_TIMEOUT},
+};
+
+pub use crate::crypto::noise::RekeyConfig;
+#[cfg(feature = "noise-observer")]
+pub use crate::crypto::noise::{HandshakeDirection, HandshakeMessage, NoiseObserver};
+
+/// Unix domain socket transport configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Listen addresses for the transport.
+    ///
+    /// Each address must be of the form `/unix/<path>`. The socket file is created when the
+    /// transport is started and removed when the transport is dropped. A socket file left behind
+    /// by a listener that is no longer running is removed before binding.
+    ///
+    /// By default the transport doesn't listen on any address.
+    pub listen_addresses: Vec<multiaddr::Multiaddr>,
+
+    /// Yamux configuration.
+    pub yamux_config: crate::yamux::Config,
+
+    /// Noise read-ahead frame count.
+    ///
+    /// Specifies how many Noise frames are read per call to the underlying socket.
+    pub noise_read_ahead_frame_count: usize,
+
+    /// Noise write buffer size.
+    ///
+    /// Specifes how many Noise frames are tried to be coalesced into a single system call.
+    pub noise_write_buffer_size: usize,
+
+    /// Noise session re-key configuration.
+    ///
+    /// If set, the key used to encrypt outbound data is rotated after the configured number of
+    /// bytes or amount of time, allowing long-lived connections to limit how much data is
+    /// protected by a single key. The connection and its substreams are kept open across the
+    /// rotation.
+    ///
+    /// Disabled by default.
+    pub noise_rekey: Option<RekeyConfig>,
+
+    /// Observer of the raw Noise handshake messages of the connections.
+    ///
+    /// Receives a copy of each handshake message sent and received by the local node, which helps
+    /// diagnosing handshake failures against other implementations.
+    ///
+    /// Disabled by default.
+    #[cfg(feature = "noise-observer")]
+    pub noise_observer: Option<NoiseObserver>,
+
+    /// Connection open timeout.
+    ///
+    /// How long should litep2p wait for a connection to be opened before the socket
+    /// is deemed unreachable.
+    pub connection_open_timeout: std::time::Duration,
+
+    /// Substream open timeout.
+    ///
+    /// How long should litep2p wait for a substream to be opened before considering
+    /// the substream rejected.
+    pub substream_open_timeout: std::time::Duration,
+
+    /// Noise handshake timeout.
+    ///
+    /// How long should litep2p wait for the Noise handshake to complete after the connection
+    /// has been established before the connection is closed.
+    pub handshake_timeout: std::time::Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            listen_addresses: Vec::new(),
+            yamux_config: Default::default(),
+            noise_read_ahead_frame_count: MAX_READ_AHEAD_FACTOR,
+            noise_write_buffer_size: MAX_WRITE_BUFFER_SIZE,
+            noise_rekey: None,
+            #[cfg(feature = "noise-observer")]
+            noise_observer: None,
+            connection_open_timeout: CONNECTION_OPEN_TIMEOUT,
+            substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
+            handshake_timeout: HANDSHAKE_TIMEOUT,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Unix domain socket connection.
+//!
+//! Unix domain sockets are upgraded with Noise and Yamux exactly like TCP sockets, so the
+//! connection implementation of the TCP transport is reused with [`UnixStream`] as the socket.
+
+use crate::{
+    config::Role,
+    crypto::{noise::RekeyConfig, Keypair},
+    error::NegotiationError,
+    transport::tcp::connection::{self, TcpConnection},
+    types::ConnectionId,
+    PeerId,
+};
+
+#[cfg(feature = "noise-observer")]
+use crate::crypto::noise::NoiseObserver;
+
+use multiaddr::Multiaddr;
+use tokio::net::UnixStream;
+
+use std::time::Duration;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::unix::connection";
+
+/// Unix domain socket connection.
+pub(super) type UnixConnection = TcpConnection<UnixStream>;
+
+/// Connection context for an opened connection that hasn't yet started its event loop.
+pub(super) type NegotiatedConnection = connection::NegotiatedConnection<UnixStream>;
+
+/// Open connection to remote peer at `address`.
+pub(super) async fn open_connection(
+    connection_id: ConnectionId,
+    keypair: Keypair,
+    stream: UnixStream,
+    address: Multiaddr,
+    peer: Option<PeerId>,
+    yamux_config: crate::yamux::Config,
+    max_read_ahead_factor: usize,
+    max_write_buffer_size: usize,
+    noise_rekey: Option<RekeyConfig>,
+    #[cfg(feature = "noise-observer")] noise_observer: Option<NoiseObserver>,
+    connection_open_timeout: Duration,
+    substream_open_timeout: Duration,
+    handshake_timeout: Duration,
+) -> Result<NegotiatedConnection, NegotiationError> {
+    tracing::debug!(
+        target: LOG_TARGET,
+        ?address,
+        ?peer,
+        "open connection to remote peer",
+    );
+
+    match tokio::time::timeout(connection_open_timeout, async move {
+        UnixConnection::negotiate_connection(
+            stream,
+            peer,
+            connection_id,
+            keypair,
+            Role::Dialer,
+            address,
+            yamux_config,
+            max_read_ahead_factor,
+            max_write_buffer_size,
+            noise_rekey,
+            #[cfg(feature = "noise-observer")]
+            noise_observer,
+            substream_open_timeout,
+            handshake_timeout,
+            None,
+        )
+        .await
+    })
+    .await
+    {
+        Err(_) => {
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?connection_id,
+                "connection timed out during negotiation",
+            );
+            Err(NegotiationError::Timeout)
+        }
+        Ok(result) => result,
+    }
+}
+
+/// Accept a new connection.
+pub(super) async fn accept_connection(
+    stream: UnixStream,
+    connection_id: ConnectionId,
+    keypair: Keypair,
+    address: Multiaddr,
+    yamux_config: crate::yamux::Config,
+    max_read_ahead_factor: usize,
+    max_write_buffer_size: usize,
+    noise_rekey: Option<RekeyConfig>,
+    #[cfg(feature = "noise-observer")] noise_observer: Option<NoiseObserver>,
+    connection_open_timeout: Duration,
+    substream_open_timeout: Duration,
+    handshake_timeout: Duration,
+) -> Result<NegotiatedConnection, NegotiationError> {
+    tracing::debug!(target: LOG_TARGET, ?address, "accept connection");
+
+    match tokio::time::timeout(connection_open_timeout, async move {
+        UnixConnection::negotiate_connection(
+            stream,
+            None,
+            connection_id,
+            keypair,
+            Role::Listener,
+            address,
+            yamux_config,
+            max_read_ahead_factor,
+            max_write_buffer_size,
+            noise_rekey,
+            #[cfg(feature = "noise-observer")]
+            noise_observer,
+            substream_open_timeout,
+            handshake_timeout,
+            None,
+        )
+        .await
+    })
+    .await
+    {
+        Err(_) => Err(NegotiationError::Timeout),
+        Ok(result) => result,
+    }
+}
@@ -0,0 +1,690 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Unix domain socket transport.
+//!
+//! Connections are upgraded with Noise and Yamux, the same way as TCP connections are.
+
+use crate::{
+    config::Role,
+    error::{AddressError, DialError, Error},
+    transport::{
+        manager::TransportHandle,
+        unix::{
+            config::Config,
+            connection::{
+                accept_connection, open_connection, NegotiatedConnection, UnixConnection,
+            },
+        },
+        Muxer, SecurityProtocol, Transport, TransportBuilder, TransportEvent,
+    },
+    types::ConnectionId,
+    PeerId,
+};
+
+use futures::{
+    future::BoxFuture,
+    stream::{FuturesUnordered, Stream, StreamExt},
+};
+use multiaddr::{Multiaddr, Protocol};
+use tokio::net::{UnixListener, UnixStream};
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::ErrorKind,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+mod connection;
+
+pub mod config;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::unix";
+
+/// Pending inbound connection.
+struct PendingInboundConnection {
+    /// Accepted socket.
+    connection: UnixStream,
+
+    /// Listen address which accepted the connection.
+    address: Multiaddr,
+}
+
+/// Unix domain socket listener.
+struct Listener {
+    /// Socket listener.
+    listener: UnixListener,
+
+    /// Path of the socket file.
+    path: PathBuf,
+
+    /// Listen address.
+    address: Multiaddr,
+}
+
+/// Unix domain socket transport.
+pub(crate) struct UnixTransport {
+    /// Transport context.
+    context: TransportHandle,
+
+    /// Transport configuration.
+    config: Config,
+
+    /// Socket listeners.
+    listeners: Vec<Listener>,
+
+    /// Index of the listener polled first.
+    poll_index: usize,
+
+    /// Pending dials.
+    pending_dials: HashMap<ConnectionId, Multiaddr>,
+
+    /// Pending inbound connections.
+    pending_inbound_connections: HashMap<ConnectionId, PendingInboundConnection>,
+
+    /// Pending opening connections.
+    pending_connections: FuturesUnordered<
+        BoxFuture<'static, Result<NegotiatedConnection, (ConnectionId, DialError)>>,
+    >,
+
+    /// Pending raw, unnegotiated connections.
+    pending_raw_connections: FuturesUnordered<
+        BoxFuture<
+            'static,
+            Result<
+                (ConnectionId, Multiaddr, UnixStream),
+                (ConnectionId, Vec<(Multiaddr, DialError)>),
+            >,
+        >,
+    >,
+
+    /// Opened raw connection, waiting for approval/rejection from `TransportManager`.
+    opened_raw: HashMap<ConnectionId, (UnixStream, Multiaddr)>,
+
+    /// Canceled raw connections.
+    canceled: HashSet<ConnectionId>,
+
+    /// Connections which have been opened and negotiated but are being validated by the
+    /// `TransportManager`.
+    pending_open: HashMap<ConnectionId, NegotiatedConnection>,
+}
+
+impl UnixTransport {
+    /// Extract socket path and the optional `PeerId` from `address`.
+    ///
+    /// The address must be of the form `/unix/<path>[/p2p/<peer>]`.
+    fn multiaddr_to_path(address: &Multiaddr) -> Result<(PathBuf, Option<PeerId>), AddressError> {
+        let mut iter = address.iter();
+
+        let path = match iter.next() {
+            Some(Protocol::Unix(path)) => PathBuf::from(path.as_ref()),
+            protocol => {
+                tracing::debug!(target: LOG_TARGET, ?protocol, "invalid protocol, expected `unix`");
+                return Err(AddressError::InvalidProtocol);
+            }
+        };
+
+        let maybe_peer = match iter.next() {
+            Some(Protocol::P2p(multihash)) =>
+                Some(PeerId::from_multihash(multihash).map_err(AddressError::InvalidPeerId)?),
+            None => None,
+            protocol => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?protocol,
+                    "invalid protocol, expected `P2p` or `None`"
+                );
+                return Err(AddressError::InvalidProtocol);
+            }
+        };
+
+        Ok((path, maybe_peer))
+    }
+
+    /// Remove socket file at `path` if it was left behind by a listener that is no longer running.
+    ///
+    /// A socket file that still accepts connections is left in place so binding to it fails.
+    fn remove_stale_socket(path: &Path) -> std::io::Result<()> {
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {}
+            Ok(_) => return Ok(()),
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error),
+        }
+
+        match std::os::unix::net::UnixStream::connect(path) {
+            Err(error) if error.kind() == ErrorKind::ConnectionRefused => {
+                tracing::debug!(target: LOG_TARGET, ?path, "remove stale socket file");
+                std::fs::remove_file(path)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Handle inbound connection.
+    fn on_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        connection: UnixStream,
+        address: Multiaddr,
+    ) {
+        let yamux_config = self.config.yamux_config.clone();
+        let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
+        let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        #[cfg(feature = "noise-observer")]
+        let noise_observer = self.config.noise_observer.clone();
+        let connection_open_timeout = self.config.connection_open_timeout;
+        let substream_open_timeout = self.config.substream_open_timeout;
+        let handshake_timeout = self.config.handshake_timeout;
+        let keypair = self.context.keypair.clone();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?connection_id,
+            ?address,
+            "accept connection",
+        );
+
+        self.pending_connections.push(Box::pin(async move {
+            accept_connection(
+                connection,
+                connection_id,
+                keypair,
+                address,
+                yamux_config,
+                max_read_ahead_factor,
+                max_write_buffer_size,
+                noise_rekey,
+                #[cfg(feature = "noise-observer")]
+                noise_observer,
+                connection_open_timeout,
+                substream_open_timeout,
+                handshake_timeout,
+            )
+            .await
+            .map_err(|error| (connection_id, error.into()))
+        }));
+    }
+
+    /// Connect to the socket at `address`.
+    async fn dial_peer(
+        address: Multiaddr,
+        connection_open_timeout: Duration,
+    ) -> Result<(Multiaddr, UnixStream), DialError> {
+        let (path, _) = Self::multiaddr_to_path(&address)?;
+
+        match tokio::time::timeout(connection_open_timeout, UnixStream::connect(&path)).await {
+            Err(_) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?connection_open_timeout,
+                    "failed to connect within timeout",
+                );
+                Err(DialError::Timeout)
+            }
+            Ok(Err(error)) => Err(error.into()),
+            Ok(Ok(stream)) => {
+                tracing::debug!(target: LOG_TARGET, ?address, "connected");
+
+                Ok((address, stream))
+            }
+        }
+    }
+}
+
+impl TransportBuilder for UnixTransport {
+    type Config = Config;
+    type Transport = UnixTransport;
+
+    /// Create new [`UnixTransport`].
+    fn new(
+        context: TransportHandle,
+        mut config: Self::Config,
+    ) -> crate::Result<(Self, Vec<Multiaddr>)> {
+        tracing::debug!(
+            target: LOG_TARGET,
+            listen_addresses = ?config.listen_addresses,
+            "start unix transport",
+        );
+
+        let mut listeners = Vec::new();
+
+        for address in std::mem::take(&mut config.listen_addresses) {
+            let (path, _) = Self::multiaddr_to_path(&address)?;
+
+            Self::remove_stale_socket(&path).map_err(|error| {
+                tracing::debug!(target: LOG_TARGET, ?path, ?error, "failed to remove stale socket");
+                Error::from(error)
+            })?;
+
+            let listener = UnixListener::bind(&path).map_err(|error| {
+                tracing::debug!(target: LOG_TARGET, ?path, ?error, "failed to bind socket");
+                Error::from(error)
+            })?;
+
+            listeners.push(Listener {
+                listener,
+                path,
+                address,
+            });
+        }
+
+        let listen_addresses = listeners.iter().map(|listener| listener.address.clone()).collect();
+
+        Ok((
+            Self {
+                config,
+                context,
+                listeners,
+                poll_index: 0usize,
+                canceled: HashSet::new(),
+                opened_raw: HashMap::new(),
+                pending_open: HashMap::new(),
+                pending_dials: HashMap::new(),
+                pending_inbound_connections: HashMap::new(),
+                pending_connections: FuturesUnordered::new(),
+                pending_raw_connections: FuturesUnordered::new(),
+            },
+            listen_addresses,
+        ))
+    }
+}
+
+impl Drop for UnixTransport {
+    fn drop(&mut self) {
+        for listener in &self.listeners {
+            if let Err(error) = std::fs::remove_file(&listener.path) {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    path = ?listener.path,
+                    ?error,
+                    "failed to remove socket file",
+                );
+            }
+        }
+    }
+}
+
+impl Transport for UnixTransport {
+    fn dial(&mut self, connection_id: ConnectionId, address: Multiaddr) -> crate::Result<()> {
+        tracing::debug!(target: LOG_TARGET, ?connection_id, ?address, "open connection");
+
+        let (_, peer) = Self::multiaddr_to_path(&address)?;
+        let yamux_config = self.config.yamux_config.clone();
+        let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
+        let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        #[cfg(feature = "noise-observer")]
+        let noise_observer = self.config.noise_observer.clone();
+        let connection_open_timeout = self.config.connection_open_timeout;
+        let substream_open_timeout = self.config.substream_open_timeout;
+        let handshake_timeout = self.config.handshake_timeout;
+        let keypair = self.context.keypair.clone();
+
+        self.pending_dials.insert(connection_id, address.clone());
+        self.pending_connections.push(Box::pin(async move {
+            let (address, stream) = UnixTransport::dial_peer(address, connection_open_timeout)
+                .await
+                .map_err(|error| (connection_id, error))?;
+
+            open_connection(
+                connection_id,
+                keypair,
+                stream,
+                address,
+                peer,
+                yamux_config,
+                max_read_ahead_factor,
+                max_write_buffer_size,
+                noise_rekey,
+                #[cfg(feature = "noise-observer")]
+                noise_observer,
+                connection_open_timeout,
+                substream_open_timeout,
+                handshake_timeout,
+            )
+            .await
+            .map_err(|error| (connection_id, error.into()))
+        }));
+
+        Ok(())
+    }
+
+    fn accept(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        let context = self
+            .pending_open
+            .remove(&connection_id)
+            .ok_or(Error::ConnectionDoesntExist(connection_id))?;
+        let protocol_set = self.context.protocol_set(connection_id);
+        let bandwidth_sink = self.context.bandwidth_sink.clone();
+        let next_substream_id = self.context.next_substream_id.clone();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?connection_id,
+            "start connection",
+        );
+
+        self.context.executor.run(Box::pin(async move {
+            if let Err(error) =
+                UnixConnection::new(context, protocol_set, bandwidth_sink, next_substream_id)
+                    .start()
+                    .await
+            {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?connection_id,
+                    ?error,
+                    "connection exited with error",
+                );
+            }
+        }));
+
+        Ok(())
+    }
+
+    fn accept_pending(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        let pending = self
+            .pending_inbound_connections
+            .remove(&connection_id)
+            .ok_or(Error::ConnectionDoesntExist(connection_id))?;
+
+        self.on_inbound_connection(connection_id, pending.connection, pending.address);
+
+        Ok(())
+    }
+
+    fn reject_pending(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        self.pending_inbound_connections
+            .remove(&connection_id)
+            .map_or(Err(Error::ConnectionDoesntExist(connection_id)), |_| Ok(()))
+    }
+
+    fn reject(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        self.pending_open
+            .remove(&connection_id)
+            .map_or(Err(Error::ConnectionDoesntExist(connection_id)), |_| Ok(()))
+    }
+
+    fn open(
+        &mut self,
+        connection_id: ConnectionId,
+        addresses: Vec<Multiaddr>,
+    ) -> crate::Result<()> {
+        let num_addresses = addresses.len();
+        let connection_open_timeout = self.config.connection_open_timeout;
+        let mut futures: FuturesUnordered<_> = addresses
+            .into_iter()
+            .map(|address| async move {
+                UnixTransport::dial_peer(address.clone(), connection_open_timeout)
+                    .await
+                    .map_err(|error| (address, error))
+            })
+            .collect();
+
+        self.pending_raw_connections.push(Box::pin(async move {
+            let mut errors = Vec::with_capacity(num_addresses);
+            while let Some(result) = futures.next().await {
+                match result {
+                    Ok((address, stream)) => return Ok((connection_id, address, stream)),
+                    Err(error) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?connection_id,
+                            ?error,
+                            "failed to open connection",
+                        );
+                        errors.push(error)
+                    }
+                }
+            }
+
+            Err((connection_id, errors))
+        }));
+
+        Ok(())
+    }
+
+    fn negotiate(&mut self, connection_id: ConnectionId) -> crate::Result<()> {
+        let (stream, address) = self
+            .opened_raw
+            .remove(&connection_id)
+            .ok_or(Error::ConnectionDoesntExist(connection_id))?;
+
+        let (_, peer) = Self::multiaddr_to_path(&address)?;
+        let yamux_config = self.config.yamux_config.clone();
+        let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
+        let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        #[cfg(feature = "noise-observer")]
+        let noise_observer = self.config.noise_observer.clone();
+        let connection_open_timeout = self.config.connection_open_timeout;
+        let substream_open_timeout = self.config.substream_open_timeout;
+        let handshake_timeout = self.config.handshake_timeout;
+        let keypair = self.context.keypair.clone();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?peer,
+            ?connection_id,
+            ?address,
+            "negotiate connection",
+        );
+
+        self.pending_dials.insert(connection_id, address.clone());
+        self.pending_connections.push(Box::pin(async move {
+            match tokio::time::timeout(connection_open_timeout, async move {
+                UnixConnection::negotiate_connection(
+                    stream,
+                    peer,
+                    connection_id,
+                    keypair,
+                    Role::Dialer,
+                    address,
+                    yamux_config,
+                    max_read_ahead_factor,
+                    max_write_buffer_size,
+                    noise_rekey,
+                    #[cfg(feature = "noise-observer")]
+                    noise_observer,
+                    substream_open_timeout,
+                    handshake_timeout,
+                    None,
+                )
+                .await
+                .map_err(|error| (connection_id, error.into()))
+            })
+            .await
+            {
+                Err(_) => Err((connection_id, DialError::Timeout)),
+                Ok(Err(error)) => Err(error),
+                Ok(Ok(connection)) => Ok(connection),
+            }
+        }));
+
+        Ok(())
+    }
+
+    fn cancel(&mut self, connection_id: ConnectionId) {
+        self.canceled.insert(connection_id);
+    }
+}
+
+impl Stream for UnixTransport {
+    type Item = TransportEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let len = self.listeners.len();
+        for index in 0..len {
+            let current = (self.poll_index + index) % len;
+
+            match self.listeners[current].listener.poll_accept(cx) {
+                Poll::Pending => {}
+                Poll::Ready(Err(error)) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        address = ?self.listeners[current].address,
+                        ?error,
+                        "failed to accept connection",
+                    );
+                }
+                Poll::Ready(Ok((connection, _))) => {
+                    self.poll_index = (current + 1) % len;
+
+                    let connection_id = self.context.next_connection_id();
+                    let address = self.listeners[current].address.clone();
+
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        ?connection_id,
+                        ?address,
+                        "pending inbound unix connection",
+                    );
+
+                    self.pending_inbound_connections.insert(
+                        connection_id,
+                        PendingInboundConnection {
+                            connection,
+                            address: address.clone(),
+                        },
+                    );
+
+                    return Poll::Ready(Some(TransportEvent::PendingInboundConnection {
+                        connection_id,
+                        address,
+                    }));
+                }
+            }
+        }
+
+        while let Poll::Ready(Some(result)) = self.pending_raw_connections.poll_next_unpin(cx) {
+            match result {
+                Ok((connection_id, address, stream)) => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        ?connection_id,
+                        ?address,
+                        canceled = self.canceled.contains(&connection_id),
+                        "connection opened",
+                    );
+
+                    if !self.canceled.remove(&connection_id) {
+                        self.opened_raw.insert(connection_id, (stream, address.clone()));
+
+                        return Poll::Ready(Some(TransportEvent::ConnectionOpened {
+                            connection_id,
+                            address,
+                        }));
+                    }
+                }
+                Err((connection_id, errors)) =>
+                    if !self.canceled.remove(&connection_id) {
+                        return Poll::Ready(Some(TransportEvent::OpenFailure {
+                            connection_id,
+                            errors,
+                        }));
+                    },
+            }
+        }
+
+        while let Poll::Ready(Some(connection)) = self.pending_connections.poll_next_unpin(cx) {
+            match connection {
+                Ok(connection) => {
+                    let peer = connection.peer();
+                    let endpoint = connection.endpoint();
+                    self.pending_open.insert(connection.connection_id(), connection);
+
+                    return Poll::Ready(Some(TransportEvent::ConnectionEstablished {
+                        peer,
+                        endpoint,
+                        security: SecurityProtocol::Noise,
+                        muxer: Some(Muxer::Yamux),
+                    }));
+                }
+                Err((connection_id, error)) => {
+                    if let Some(address) = self.pending_dials.remove(&connection_id) {
+                        return Poll::Ready(Some(TransportEvent::DialFailure {
+                            connection_id,
+                            address,
+                            error,
+                        }));
+                    } else {
+                        tracing::debug!(target: LOG_TARGET, ?error, ?connection_id, "Pending inbound connection failed");
+                    }
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_unix_address() {
+        let peer = PeerId::random();
+        let address = Multiaddr::empty()
+            .with(Protocol::Unix("/tmp/litep2p.sock".into()))
+            .with(Protocol::P2p(multihash::Multihash::from(peer)));
+
+        assert_eq!(
+            UnixTransport::multiaddr_to_path(&address).unwrap(),
+            (PathBuf::from("/tmp/litep2p.sock"), Some(peer))
+        );
+
+        let address = Multiaddr::empty().with(Protocol::Unix("/tmp/litep2p.sock".into()));
+        assert_eq!(
+            UnixTransport::multiaddr_to_path(&address).unwrap(),
+            (PathBuf::from("/tmp/litep2p.sock"), None)
+        );
+
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/8888".parse().unwrap();
+        assert!(UnixTransport::multiaddr_to_path(&address).is_err());
+    }
+
+    #[tokio::test]
+    async fn stale_socket_file_removed() {
+        let path = std::env::temp_dir().join(format!("litep2p-{}.sock", PeerId::random()));
+
+        // socket file left behind by a listener that is no longer running
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        UnixTransport::remove_stale_socket(&path).unwrap();
+        assert!(!path.exists());
+
+        // socket file of a running listener is left in place
+        let _listener = UnixListener::bind(&path).unwrap();
+        UnixTransport::remove_stale_socket(&path).unwrap();
+        assert!(path.exists());
+        assert!(UnixListener::bind(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -28,10 +28,34 @@ pub struct Config {
     /// WebRTC listening address.
     pub listen_addresses: Vec<Multiaddr>,
 
+    /// Whether to set `SO_REUSEADDR` on the listening socket.
+    ///
+    /// Defaults to `true`.
+    pub reuse_address: bool,
+
+    /// Whether to set `SO_REUSEPORT` on the listening socket.
+    ///
+    /// `SO_REUSEPORT` is only supported on Unix platforms.
+    ///
+    /// Defaults to `true`.
+    pub reuse_port: bool,
+
     /// Connection datagram buffer size.
     ///
     /// How many datagrams can the buffer between `WebRtcTransport` and a connection handler hold.
+    ///
+    /// If the connection handler falls behind and the buffer fills up, the oldest datagram is
+    /// dropped to make room for the new one instead of stalling the transport.
     pub datagram_buffer_size: usize,
+
+    /// Network interface the UDP socket is bound to.
+    ///
+    /// The socket is shared by all WebRTC connections, so binding it to an interface makes all
+    /// of their datagrams leave through that interface. The source address of the datagrams is
+    /// the listen address. Only supported on Linux and Android.
+    ///
+    /// Defaults to `None`.
+    pub bind_device: Option<String>,
 }
 
 impl Default for Config {
@@ -40,7 +64,10 @@ impl Default for Config {
             listen_addresses: vec!["/ip4/127.0.0.1/udp/8888/webrtc-direct"
                 .parse()
                 .expect("valid multiaddress")],
+            reuse_address: true,
+            reuse_port: true,
             datagram_buffer_size: 2048,
+            bind_device: None,
         }
     }
 }
@@ -25,8 +25,10 @@ use crate::{
     substream::Substream,
     transport::{
         webrtc::{
+            datagram::DatagramReceiver,
+            schema::webrtc::message::Flag,
             substream::{Event as SubstreamEvent, Substream as WebRtcSubstream, SubstreamHandle},
-            util::WebRtcMessage,
+            util::{self, WebRtcMessage},
         },
         Endpoint,
     },
@@ -34,14 +36,16 @@ use crate::{
     PeerId,
 };
 
+use bytes::BytesMut;
 use futures::{Stream, StreamExt};
 use indexmap::IndexMap;
 use str0m::{
     channel::{ChannelConfig, ChannelId},
-    net::{Protocol as Str0mProtocol, Receive},
-    Event, IceConnectionState, Input, Output, Rtc,
+    net::{DatagramRecv, Protocol as Str0mProtocol, Receive},
+    Event, IceConnectionState, Input, Output, Rtc, RtcError,
 };
-use tokio::{net::UdpSocket, sync::mpsc::Receiver};
+use tokio::net::UdpSocket;
+use tracing::Instrument;
 
 use std::{
     collections::HashMap,
@@ -103,6 +107,17 @@ impl SubstreamHandleSet {
     pub fn remove(&mut self, key: &ChannelId) -> Option<SubstreamHandle> {
         self.handles.shift_remove(key)
     }
+
+    /// Remove all handles from [`SubstreamHandleSet`], closing the substreams.
+    pub fn clear(&mut self) {
+        self.handles.clear();
+        self.index = 0usize;
+    }
+
+    /// Get the number of handles in [`SubstreamHandleSet`].
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
 }
 
 impl Stream for SubstreamHandleSet {
@@ -157,9 +172,6 @@ enum ChannelState {
 
         /// Channel ID.
         channel_id: ChannelId,
-
-        /// Connection permit.
-        permit: Permit,
     },
 }
 
@@ -187,7 +199,7 @@ pub struct WebRtcConnection {
     socket: Arc<UdpSocket>,
 
     /// RX channel for receiving datagrams from the transport.
-    dgram_rx: Receiver<Vec<u8>>,
+    dgram_rx: DatagramReceiver,
 
     /// Pending outbound channels.
     pending_outbound: HashMap<ChannelId, ChannelContext>,
@@ -209,7 +221,7 @@ impl WebRtcConnection {
         socket: Arc<UdpSocket>,
         protocol_set: ProtocolSet,
         endpoint: Endpoint,
-        dgram_rx: Receiver<Vec<u8>>,
+        dgram_rx: DatagramReceiver,
     ) -> Self {
         Self {
             rtc,
@@ -246,7 +258,7 @@ impl WebRtcConnection {
             "channel opened",
         );
 
-        let Some(mut context) = self.pending_outbound.remove(&channel_id) else {
+        let Some(context) = self.pending_outbound.remove(&channel_id) else {
             tracing::trace!(
                 target: LOG_TARGET,
                 peer = ?self.peer,
@@ -258,9 +270,8 @@ impl WebRtcConnection {
             return Ok(());
         };
 
-        let fallback_names = std::mem::take(&mut context.fallback_names);
         let (dialer_state, message) =
-            DialerState::propose(context.protocol.clone(), fallback_names)?;
+            DialerState::propose(context.protocol.clone(), context.fallback_names.clone())?;
         let message = WebRtcMessage::encode(message);
 
         self.rtc
@@ -316,12 +327,7 @@ impl WebRtcConnection {
             "handle opening inbound substream",
         );
 
-        let payload = WebRtcMessage::decode(&data)?.payload.ok_or(Error::InvalidData)?;
-        let (response, negotiated) =
-            match listener_negotiate(&mut self.protocol_set.protocols().iter(), payload.into())? {
-                ListenerSelectResult::Accepted { protocol, message } => (message, Some(protocol)),
-                ListenerSelectResult::Rejected { message } => (message, None),
-            };
+        let (response, negotiated) = self.negotiate_inbound_protocol(&data)?;
 
         self.rtc
             .channel(channel_id)
@@ -330,9 +336,57 @@ impl WebRtcConnection {
             .map_err(Error::WebRtc)?;
 
         let protocol = negotiated.ok_or(Error::SubstreamDoesntExist)?;
+        self.open_inbound_substream(channel_id, protocol).await
+    }
+
+    /// Negotiate protocol for an inbound channel.
+    ///
+    /// Parse the `multistream-select` handshake received from remote peer and return the response
+    /// that must be sent back to remote peer, along with the negotiated protocol if the local
+    /// node supports any of the proposed protocols.
+    fn negotiate_inbound_protocol(
+        &self,
+        data: &[u8],
+    ) -> crate::Result<(BytesMut, Option<ProtocolName>)> {
+        let payload = WebRtcMessage::decode(data)?.payload.ok_or(Error::InvalidData)?;
+        let protocols = self.protocol_set.protocols();
+
+        let (message, protocol, rejected) =
+            match listener_negotiate(&mut protocols.iter(), payload.into())? {
+                ListenerSelectResult::Accepted {
+                    protocol,
+                    message,
+                    rejected,
+                } => (message, Some(protocol), rejected),
+                ListenerSelectResult::Rejected { message, rejected } => (message, None, rejected),
+            };
+
+        if let Some(observer) = self.protocol_set.negotiation_observer() {
+            observer.observe(
+                self.peer,
+                Direction::Inbound,
+                protocols,
+                protocol.clone(),
+                rejected,
+            );
+        }
+
+        Ok((message, protocol))
+    }
+
+    /// Open inbound substream for a channel which has negotiated `protocol`.
+    ///
+    /// Each channel gets its own substream and the substream is reported to the protocol that was
+    /// negotiated for this particular channel, independent of any other channel that is open.
+    async fn open_inbound_substream(
+        &mut self,
+        channel_id: ChannelId,
+        protocol: ProtocolName,
+    ) -> crate::Result<(SubstreamId, SubstreamHandle, Permit)> {
         let substream_id = self.protocol_set.next_substream_id();
         let codec = self.protocol_set.protocol_codec(&protocol);
         let permit = self.protocol_set.try_get_permit().ok_or(Error::ConnectionClosed)?;
+        let permit = self.protocol_set.substream_permit(&protocol, permit);
         let (substream, handle) = WebRtcSubstream::new();
         let substream = Substream::new_webrtc(self.peer, substream_id, substream, codec);
 
@@ -387,7 +441,26 @@ impl WebRtcConnection {
             ParseError::InvalidData.into(),
         ))?;
 
-        let HandshakeResult::Succeeded(protocol) = dialer_state.register_response(message)? else {
+        let result = dialer_state.register_response(message);
+
+        // only report the negotiation to the observer once it has concluded
+        match (self.protocol_set.negotiation_observer(), &result) {
+            (None, _) | (_, Ok(HandshakeResult::NotReady)) => {}
+            (Some(observer), result) => observer.observe(
+                self.peer,
+                Direction::Outbound(context.substream_id),
+                std::iter::once(context.protocol.clone())
+                    .chain(context.fallback_names.iter().cloned())
+                    .collect(),
+                match result {
+                    Ok(HandshakeResult::Succeeded(protocol)) => Some(protocol.clone()),
+                    _ => None,
+                },
+                Vec::new(),
+            ),
+        }
+
+        let HandshakeResult::Succeeded(protocol) = result? else {
             tracing::trace!(
                 target: LOG_TARGET,
                 peer = ?self.peer,
@@ -411,6 +484,7 @@ impl WebRtcConnection {
             permit,
             ..
         } = context;
+        let permit = self.protocol_set.substream_permit(&protocol, permit);
         let codec = self.protocol_set.protocol_codec(&protocol);
         let (substream, handle) = WebRtcSubstream::new();
         let substream = Substream::new_webrtc(self.peer, substream_id, substream, codec);
@@ -468,6 +542,25 @@ impl WebRtcConnection {
             .await
     }
 
+    /// Register channel as open after its protocol has been negotiated.
+    fn on_channel_negotiated(
+        &mut self,
+        channel_id: ChannelId,
+        substream_id: SubstreamId,
+        mut handle: SubstreamHandle,
+        permit: Permit,
+    ) {
+        handle.set_permit(permit);
+        self.handles.insert(channel_id, handle);
+        self.channels.insert(
+            channel_id,
+            ChannelState::Open {
+                substream_id,
+                channel_id,
+            },
+        );
+    }
+
     /// Handle data received from a channel.
     async fn on_inbound_data(&mut self, channel_id: ChannelId, data: Vec<u8>) -> crate::Result<()> {
         let Some(state) = self.channels.remove(&channel_id) else {
@@ -485,15 +578,7 @@ impl WebRtcConnection {
             ChannelState::InboundOpening =>
                 match self.on_inbound_opening_channel_data(channel_id, data).await {
                     Ok((substream_id, handle, permit)) => {
-                        self.handles.insert(channel_id, handle);
-                        self.channels.insert(
-                            channel_id,
-                            ChannelState::Open {
-                                substream_id,
-                                channel_id,
-                                permit,
-                            },
-                        );
+                        self.on_channel_negotiated(channel_id, substream_id, handle, permit);
                     }
                     Err(error) => {
                         tracing::debug!(
@@ -520,15 +605,7 @@ impl WebRtcConnection {
                     .await
                 {
                     Ok(Some((substream_id, handle, permit))) => {
-                        self.handles.insert(channel_id, handle);
-                        self.channels.insert(
-                            channel_id,
-                            ChannelState::Open {
-                                substream_id,
-                                channel_id,
-                                permit,
-                            },
-                        );
+                        self.on_channel_negotiated(channel_id, substream_id, handle, permit);
                     }
                     Ok(None) => {}
                     Err(error) => {
@@ -553,7 +630,6 @@ impl WebRtcConnection {
             ChannelState::Open {
                 substream_id,
                 channel_id,
-                permit,
             } => match self.on_open_channel_data(channel_id, data).await {
                 Ok(()) => {
                     self.channels.insert(
@@ -561,7 +637,6 @@ impl WebRtcConnection {
                         ChannelState::Open {
                             substream_id,
                             channel_id,
-                            permit,
                         },
                     );
                 }
@@ -610,6 +685,29 @@ impl WebRtcConnection {
             .map(|_| ())
     }
 
+    /// Signal remote peer that the local node has closed its write side of the substream.
+    ///
+    /// `data` is sent in the same message as `FIN`.
+    fn on_outbound_fin(&mut self, channel_id: ChannelId, data: Vec<u8>) -> crate::Result<()> {
+        tracing::trace!(
+            target: LOG_TARGET,
+            peer = ?self.peer,
+            ?channel_id,
+            data_len = ?data.len(),
+            "send `FIN`",
+        );
+
+        self.rtc
+            .channel(channel_id)
+            .ok_or(Error::ChannelDoesntExist)?
+            .write(
+                true,
+                WebRtcMessage::encode_with_flags(data, Flag::Fin as i32).as_ref(),
+            )
+            .map_err(Error::WebRtc)
+            .map(|_| ())
+    }
+
     /// Open outbound substream.
     fn on_open_substream(
         &mut self,
@@ -647,14 +745,69 @@ impl WebRtcConnection {
         );
     }
 
+    /// Pass `input` to `str0m`.
+    ///
+    /// Recoverable errors are logged and ignored. Returns an error if `str0m` failed with a fatal
+    /// error, in which case the connection must be closed.
+    fn handle_input(&mut self, input: Input) -> Result<(), RtcError> {
+        match self.rtc.handle_input(input) {
+            Ok(()) => Ok(()),
+            Err(error) if util::is_recoverable(&error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    peer = ?self.peer,
+                    ?error,
+                    "recoverable error while handling input",
+                );
+                Ok(())
+            }
+            Err(error) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    peer = ?self.peer,
+                    ?error,
+                    "fatal error while handling input, closing connection",
+                );
+                Err(error)
+            }
+        }
+    }
+
     /// Connection to peer has been closed.
+    ///
+    /// Close all open substreams and report failure for substreams which were still being
+    /// negotiated before informing protocols that the connection has been closed.
     async fn on_connection_closed(&mut self) {
         tracing::trace!(
             target: LOG_TARGET,
             peer = ?self.peer,
+            num_substreams = ?self.handles.len(),
             "connection closed",
         );
 
+        self.handles.clear();
+
+        let pending = self
+            .pending_outbound
+            .drain()
+            .map(|(_, context)| context)
+            .chain(self.channels.drain().filter_map(|(_, state)| match state {
+                ChannelState::OutboundOpening { context, .. } => Some(context),
+                _ => None,
+            }))
+            .collect::<Vec<_>>();
+
+        for context in pending {
+            let _ = self
+                .protocol_set
+                .report_substream_open_failure(
+                    context.protocol,
+                    context.substream_id,
+                    SubstreamError::ConnectionClosed,
+                )
+                .await;
+        }
+
         let _ = self
             .protocol_set
             .report_connection_closed(self.peer, self.endpoint.connection_id())
@@ -662,7 +815,19 @@ impl WebRtcConnection {
     }
 
     /// Start running event loop of [`WebRtcConnection`].
-    pub async fn run(mut self) {
+    pub async fn run(self) {
+        let span = tracing::debug_span!(
+            target: LOG_TARGET,
+            "connection",
+            peer = ?self.peer,
+            connection_id = ?self.endpoint.connection_id(),
+        );
+
+        self.event_loop().instrument(span).await
+    }
+
+    /// Connection event loop.
+    async fn event_loop(mut self) {
         tracing::trace!(
             target: LOG_TARGET,
             peer = ?self.peer,
@@ -676,7 +841,20 @@ impl WebRtcConnection {
 
         loop {
             // poll output until we get a timeout
-            let timeout = match self.rtc.poll_output().unwrap() {
+            let output = match util::poll_output(&mut self.rtc) {
+                Ok(output) => output,
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        peer = ?self.peer,
+                        ?error,
+                        "fatal error while polling output, closing connection",
+                    );
+                    return self.on_connection_closed().await;
+                }
+            };
+
+            let timeout = match output {
                 Output::Timeout(v) => v,
                 Output::Transmit(v) => {
                     tracing::trace!(
@@ -751,7 +929,9 @@ impl WebRtcConnection {
 
             let duration = timeout - Instant::now();
             if duration.is_zero() {
-                self.rtc.handle_input(Input::Timeout(Instant::now())).unwrap();
+                if self.handle_input(Input::Timeout(Instant::now())).is_err() {
+                    return self.on_connection_closed().await;
+                }
                 continue;
             }
 
@@ -759,17 +939,42 @@ impl WebRtcConnection {
                 biased;
                 datagram = self.dgram_rx.recv() => match datagram {
                     Some(datagram) => {
+                        // `str0m` panics if the datagram is empty
+                        if datagram.is_empty() {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                peer = ?self.peer,
+                                "received empty datagram, ignoring",
+                            );
+                            continue;
+                        }
+
+                        let contents = match DatagramRecv::try_from(datagram.as_slice()) {
+                            Ok(contents) => contents,
+                            Err(error) => {
+                                tracing::debug!(
+                                    target: LOG_TARGET,
+                                    peer = ?self.peer,
+                                    ?error,
+                                    "failed to parse datagram, ignoring",
+                                );
+                                continue;
+                            }
+                        };
+
                         let input = Input::Receive(
                             Instant::now(),
                             Receive {
                                 proto: Str0mProtocol::Udp,
                                 source: self.peer_address,
                                 destination: self.local_address,
-                                contents: datagram.as_slice().try_into().unwrap(),
+                                contents,
                             },
                         );
 
-                        self.rtc.handle_input(input).unwrap();
+                        if self.handle_input(input).is_err() {
+                            return self.on_connection_closed().await;
+                        }
                     }
                     None => {
                         tracing::trace!(
@@ -804,6 +1009,26 @@ impl WebRtcConnection {
                             );
                         }
                     }
+                    Some((channel_id, Some(SubstreamEvent::SendClosed))) => {
+                        if let Err(error) = self.on_outbound_fin(channel_id, Vec::new()) {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?channel_id,
+                                ?error,
+                                "failed to send `FIN` to remote peer",
+                            );
+                        }
+                    }
+                    Some((channel_id, Some(SubstreamEvent::FinalMessage(data)))) => {
+                        if let Err(error) = self.on_outbound_fin(channel_id, data) {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?channel_id,
+                                ?error,
+                                "failed to send `FIN` to remote peer",
+                            );
+                        }
+                    }
                     Some((_, Some(SubstreamEvent::RecvClosed))) => {}
                 },
                 command = self.protocol_set.next() => match command {
@@ -816,14 +1041,287 @@ impl WebRtcConnection {
                         );
                         return self.on_connection_closed().await;
                     }
-                    Some(ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit }) => {
+                    Some(ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit, .. }) => {
                         self.on_open_substream(protocol, fallback_names, substream_id, permit);
                     }
                 },
                 _ = tokio::time::sleep(duration) => {
-                    self.rtc.handle_input(Input::Timeout(Instant::now())).unwrap();
+                    if self.handle_input(Input::Timeout(Instant::now())).is_err() {
+                        return self.on_connection_closed().await;
+                    }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        codec::ProtocolCodec, protocol::InnerTransportEvent, transport::manager::ProtocolContext,
+        types::ConnectionId,
+    };
+    use multiaddr::Multiaddr;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        sync::mpsc::channel,
+    };
+
+    /// Create new [`WebRtcConnection`] which supports `/proto/1` and `/proto/2`.
+    async fn make_connection() -> (
+        WebRtcConnection,
+        Receiver<InnerTransportEvent>,
+        Receiver<InnerTransportEvent>,
+    ) {
+        let (mgr_tx, _mgr_rx) = channel(64);
+        let (tx1, rx1) = channel(64);
+        let (tx2, rx2) = channel(64);
+
+        let protocol_set = ProtocolSet::new(
+            ConnectionId::from(0usize),
+            mgr_tx,
+            Default::default(),
+            HashMap::from_iter([
+                (
+                    ProtocolName::from("/proto/1"),
+                    ProtocolContext {
+                        tx: tx1,
+                        codec: ProtocolCodec::Unspecified,
+                        fallback_names: Vec::new(),
+                        idle_timeout: None,
+                        inbound_filter: None,
+                        priority: Default::default(),
+                        latency_sensitive: false,
+                        inbound_order: Default::default(),
+                    },
+                ),
+                (
+                    ProtocolName::from("/proto/2"),
+                    ProtocolContext {
+                        tx: tx2,
+                        codec: ProtocolCodec::Unspecified,
+                        fallback_names: Vec::new(),
+                        idle_timeout: None,
+                        inbound_filter: None,
+                        priority: Default::default(),
+                        latency_sensitive: false,
+                        inbound_order: Default::default(),
+                    },
+                ),
+            ]),
+        );
+
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let local_address = socket.local_addr().unwrap();
+        let (_dgram_tx, dgram_rx) = crate::transport::webrtc::datagram::channel(64);
+
+        let connection = WebRtcConnection::new(
+            Rtc::new(),
+            PeerId::random(),
+            "127.0.0.1:8888".parse().unwrap(),
+            local_address,
+            socket,
+            protocol_set,
+            Endpoint::listener(Multiaddr::empty(), ConnectionId::from(0usize)),
+            dgram_rx,
+        );
+
+        (connection, rx1, rx2)
+    }
+
+    /// Allocate new channel ID from `str0m`.
+    fn create_channel(connection: &mut WebRtcConnection) -> ChannelId {
+        connection.rtc.direct_api().create_data_channel(ChannelConfig {
+            label: "".to_string(),
+            ordered: false,
+            reliability: Default::default(),
+            negotiated: None,
+            protocol: "".to_string(),
+        })
+    }
+
+    /// Negotiate `protocol` for an inbound channel as if remote peer had proposed it.
+    async fn negotiate_channel(
+        connection: &mut WebRtcConnection,
+        channel_id: ChannelId,
+        protocol: &'static str,
+    ) {
+        let (_, message) = DialerState::propose(ProtocolName::from(protocol), Vec::new()).unwrap();
+        let (_, negotiated) =
+            connection.negotiate_inbound_protocol(&WebRtcMessage::encode(message)).unwrap();
+        assert_eq!(negotiated, Some(ProtocolName::from(protocol)));
+
+        let (substream_id, handle, permit) = connection
+            .open_inbound_substream(channel_id, negotiated.unwrap())
+            .await
+            .unwrap();
+        connection.on_channel_negotiated(channel_id, substream_id, handle, permit);
+    }
+
+    /// Receive negotiated substream from protocol's event channel.
+    async fn substream_opened(
+        rx: &mut Receiver<InnerTransportEvent>,
+        expected: &'static str,
+    ) -> Substream {
+        match rx.recv().await.unwrap() {
+            InnerTransportEvent::SubstreamOpened {
+                protocol,
+                direction: Direction::Inbound,
+                substream,
+                ..
+            } => {
+                assert_eq!(protocol, ProtocolName::from(expected));
+                substream
+            }
+            event => panic!("invalid event received: {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_channels_negotiate_different_protocols() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (mut connection, mut rx1, mut rx2) = make_connection().await;
+        let channel1 = create_channel(&mut connection);
+        let channel2 = create_channel(&mut connection);
+        assert_ne!(channel1, channel2);
+
+        negotiate_channel(&mut connection, channel1, "/proto/1").await;
+        negotiate_channel(&mut connection, channel2, "/proto/2").await;
+
+        let mut substream1 = substream_opened(&mut rx1, "/proto/1").await;
+        let mut substream2 = substream_opened(&mut rx2, "/proto/2").await;
+        assert!(rx1.try_recv().is_err());
+        assert!(rx2.try_recv().is_err());
+
+        // interleave data over both channels and verify that each protocol
+        // only receives the data sent over its own channel
+        connection
+            .on_inbound_data(channel2, WebRtcMessage::encode(vec![2u8; 4]))
+            .await
+            .unwrap();
+        connection
+            .on_inbound_data(channel1, WebRtcMessage::encode(vec![1u8; 4]))
+            .await
+            .unwrap();
+        connection
+            .on_inbound_data(channel2, WebRtcMessage::encode(vec![3u8; 4]))
+            .await
+            .unwrap();
+
+        let mut buffer = vec![0u8; 4];
+        substream1.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, vec![1u8; 4]);
+
+        substream2.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, vec![2u8; 4]);
+        substream2.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, vec![3u8; 4]);
+
+        // outbound data is tagged with the channel of the substream that wrote it
+        substream2.write_all(&[5u8; 8]).await.unwrap();
+        assert_eq!(
+            connection.handles.next().await,
+            Some((channel2, Some(SubstreamEvent::Message(vec![5u8; 8]))))
+        );
+
+        substream1.write_all(&[6u8; 8]).await.unwrap();
+        assert_eq!(
+            connection.handles.next().await,
+            Some((channel1, Some(SubstreamEvent::Message(vec![6u8; 8]))))
+        );
+    }
+
+    #[tokio::test]
+    async fn closing_one_channel_doesnt_affect_others() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (mut connection, mut rx1, mut rx2) = make_connection().await;
+        let channel1 = create_channel(&mut connection);
+        let channel2 = create_channel(&mut connection);
+
+        negotiate_channel(&mut connection, channel1, "/proto/1").await;
+        negotiate_channel(&mut connection, channel2, "/proto/2").await;
+
+        let _substream1 = substream_opened(&mut rx1, "/proto/1").await;
+        let mut substream2 = substream_opened(&mut rx2, "/proto/2").await;
+
+        connection.on_channel_closed(channel1).await.unwrap();
+
+        assert!(!connection.channels.contains_key(&channel1));
+        assert!(connection.handles.get_mut(&channel1).is_none());
+        assert!(std::matches!(
+            connection.channels.get(&channel2),
+            Some(ChannelState::Open { .. })
+        ));
+
+        connection
+            .on_inbound_data(channel2, WebRtcMessage::encode(vec![7u8; 4]))
+            .await
+            .unwrap();
+
+        let mut buffer = vec![0u8; 4];
+        substream2.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, vec![7u8; 4]);
+    }
+
+    #[tokio::test]
+    async fn transport_shutdown_closes_substreams() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        // the datagram sender is dropped by `make_connection()`,
+        // as if the transport had been shut down
+        let (mut connection, mut rx1, mut rx2) = make_connection().await;
+        let channel1 = create_channel(&mut connection);
+        negotiate_channel(&mut connection, channel1, "/proto/1").await;
+        let mut substream1 = substream_opened(&mut rx1, "/proto/1").await;
+
+        // outbound substream which is still being negotiated
+        let permit = connection.protocol_set.try_get_permit().unwrap();
+        connection.on_open_substream(
+            ProtocolName::from("/proto/2"),
+            Vec::new(),
+            SubstreamId::from(1337usize),
+            permit,
+        );
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), connection.run())
+            .await
+            .expect("connection to exit");
+
+        assert!(std::matches!(
+            rx1.recv().await,
+            Some(InnerTransportEvent::ConnectionEstablished { .. })
+        ));
+        assert!(std::matches!(
+            rx1.recv().await,
+            Some(InnerTransportEvent::ConnectionClosed { .. })
+        ));
+
+        assert!(std::matches!(
+            rx2.recv().await,
+            Some(InnerTransportEvent::ConnectionEstablished { .. })
+        ));
+        match rx2.recv().await {
+            Some(InnerTransportEvent::SubstreamOpenFailure { substream, error }) => {
+                assert_eq!(substream, SubstreamId::from(1337usize));
+                assert!(std::matches!(error, SubstreamError::ConnectionClosed));
+            }
+            event => panic!("invalid event received: {event:?}"),
+        }
+        assert!(std::matches!(
+            rx2.recv().await,
+            Some(InnerTransportEvent::ConnectionClosed { .. })
+        ));
+
+        let mut buffer = vec![0u8; 4];
+        assert!(substream1.read_exact(&mut buffer).await.is_err());
+    }
+}
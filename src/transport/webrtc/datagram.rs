@@ -0,0 +1,210 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Bounded datagram queue between [`super::WebRtcTransport`] and a connection event loop.
+//!
+//! Unlike a regular bounded channel, sending into the queue never blocks or fails because
+//! the queue is full. When the connection event loop falls behind, the oldest datagram is
+//! dropped to make room for the new one so a slow connection cannot stall the transport
+//! event loop shared by all connections. UDP makes no delivery guarantees, so dropping
+//! datagrams doesn't corrupt the connection state.
+
+use parking_lot::Mutex;
+
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+/// Shared state of the queue.
+#[derive(Debug)]
+struct Inner {
+    /// Queued datagrams.
+    queue: VecDeque<Vec<u8>>,
+
+    /// Maximum number of queued datagrams.
+    capacity: usize,
+
+    /// Number of datagrams dropped because the queue was full.
+    dropped: usize,
+
+    /// Waker of the receiver.
+    waker: Option<Waker>,
+
+    /// Has either half of the queue been dropped.
+    closed: bool,
+}
+
+/// Create new datagram queue which can hold at most `capacity` datagrams.
+pub fn channel(capacity: usize) -> (DatagramSender, DatagramReceiver) {
+    assert!(capacity > 0, "datagram queue capacity must be non-zero");
+
+    let inner = Arc::new(Mutex::new(Inner {
+        queue: VecDeque::with_capacity(capacity),
+        capacity,
+        dropped: 0usize,
+        waker: None,
+        closed: false,
+    }));
+
+    (
+        DatagramSender {
+            inner: Arc::clone(&inner),
+        },
+        DatagramReceiver { inner },
+    )
+}
+
+/// Error returned when the receiver has been dropped.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Closed;
+
+/// Sending half of the datagram queue.
+#[derive(Debug)]
+pub struct DatagramSender {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl DatagramSender {
+    /// Queue `datagram`, dropping the oldest queued datagram if the queue is full.
+    ///
+    /// Returns `true` if a datagram was dropped.
+    pub fn send(&self, datagram: Vec<u8>) -> Result<bool, Closed> {
+        let mut inner = self.inner.lock();
+
+        if inner.closed {
+            return Err(Closed);
+        }
+
+        let dropped = inner.queue.len() >= inner.capacity;
+        if dropped {
+            inner.queue.pop_front();
+            inner.dropped += 1;
+        }
+        inner.queue.push_back(datagram);
+
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+
+        Ok(dropped)
+    }
+
+    /// Get the number of datagrams dropped because the queue was full.
+    pub fn dropped(&self) -> usize {
+        self.inner.lock().dropped
+    }
+}
+
+impl Drop for DatagramSender {
+    fn drop(&mut self) {
+        let mut inner = self.inner.lock();
+
+        inner.closed = true;
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Receiving half of the datagram queue.
+#[derive(Debug)]
+pub struct DatagramReceiver {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl DatagramReceiver {
+    /// Poll next datagram from the queue.
+    ///
+    /// Returns `None` once the sender has been dropped and all queued datagrams have been read.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Vec<u8>>> {
+        let mut inner = self.inner.lock();
+
+        if let Some(datagram) = inner.queue.pop_front() {
+            return Poll::Ready(Some(datagram));
+        }
+
+        if inner.closed {
+            return Poll::Ready(None);
+        }
+
+        inner.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    /// Receive next datagram from the queue.
+    pub async fn recv(&mut self) -> Option<Vec<u8>> {
+        futures::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+}
+
+impl Drop for DatagramReceiver {
+    fn drop(&mut self) {
+        let mut inner = self.inner.lock();
+
+        inner.closed = true;
+        inner.queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn full_queue_drops_oldest_datagrams() {
+        let (tx, mut rx) = channel(2);
+
+        // the sender is never blocked even though nothing is reading the queue
+        for i in 0..5u8 {
+            assert_eq!(tx.send(vec![i]), Ok(i >= 2));
+        }
+        assert_eq!(tx.dropped(), 3);
+
+        assert_eq!(rx.recv().await, Some(vec![3u8]));
+        assert_eq!(rx.recv().await, Some(vec![4u8]));
+        assert!(futures::poll!(Box::pin(rx.recv())).is_pending());
+
+        // the queue has room again
+        assert_eq!(tx.send(vec![5u8]), Ok(false));
+        assert_eq!(rx.recv().await, Some(vec![5u8]));
+        assert_eq!(tx.dropped(), 3);
+    }
+
+    #[tokio::test]
+    async fn dropped_sender_closes_queue() {
+        let (tx, mut rx) = channel(4);
+
+        tx.send(vec![1u8]).unwrap();
+        drop(tx);
+
+        assert_eq!(rx.recv().await, Some(vec![1u8]));
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test]
+    fn dropped_receiver_closes_queue() {
+        let (tx, rx) = channel(4);
+        drop(rx);
+
+        assert_eq!(tx.send(vec![1u8]), Err(Closed));
+    }
+}
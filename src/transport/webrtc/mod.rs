@@ -19,13 +19,25 @@
 // DEALINGS IN THE SOFTWARE.
 
 //! WebRTC transport.
+//!
+//! The transport implements the WebRTC-direct (browser-to-server) variant of the libp2p WebRTC
+//! specification: the node listens on a UDP socket with a self-signed DTLS certificate and
+//! advertises `/webrtc-direct/certhash/<hash>` addresses which browsers can dial without any
+//! signalling. Browser-to-browser WebRTC, which is signalled over a relayed connection, is not
+//! supported and such addresses are rejected.
 
 use crate::{
     error::{AddressError, Error},
     transport::{
+        common::listener::bind_to_device,
         manager::TransportHandle,
-        webrtc::{config::Config, connection::WebRtcConnection, opening::OpeningWebRtcConnection},
-        Endpoint, Transport, TransportBuilder, TransportEvent,
+        webrtc::{
+            config::Config,
+            connection::WebRtcConnection,
+            datagram::{Closed, DatagramSender},
+            opening::OpeningWebRtcConnection,
+        },
+        Endpoint, Muxer, SecurityProtocol, Transport, TransportBuilder, TransportEvent,
     },
     types::ConnectionId,
     PeerId,
@@ -33,7 +45,7 @@ use crate::{
 
 use futures::{future::BoxFuture, Future, Stream};
 use futures_timer::Delay;
-use multiaddr::{multihash::Multihash, Multiaddr, Protocol};
+use multiaddr::{Multiaddr, Protocol};
 use socket2::{Domain, Socket, Type};
 use str0m::{
     change::DtlsCert,
@@ -42,11 +54,7 @@ use str0m::{
     net::{DatagramRecv, Protocol as Str0mProtocol, Receive},
     Candidate, Input, Rtc,
 };
-use tokio::{
-    io::ReadBuf,
-    net::UdpSocket,
-    sync::mpsc::{channel, error::TrySendError, Sender},
-};
+use tokio::{io::ReadBuf, net::UdpSocket};
 
 use std::{
     collections::{HashMap, VecDeque},
@@ -57,9 +65,12 @@ use std::{
     time::{Duration, Instant},
 };
 
+#[cfg(test)]
+pub(crate) use substream::Event as SubstreamEvent;
 pub(crate) use substream::Substream;
 
 mod connection;
+mod datagram;
 mod opening;
 mod substream;
 mod util;
@@ -92,7 +103,7 @@ struct ConnectionContext {
     connection_id: ConnectionId,
 
     /// TX channel for sending datagrams to the connection event loop.
-    tx: Sender<Vec<u8>>,
+    tx: DatagramSender,
 }
 
 /// Events received from opening connections that are handled
@@ -152,6 +163,9 @@ pub(crate) struct WebRtcTransport {
 
 impl WebRtcTransport {
     /// Extract socket address and `PeerId`, if found, from `address`.
+    ///
+    /// `address` must be a WebRTC-direct address, optionally followed by the `/certhash` of the
+    /// remote certificate.
     fn get_socket_address(address: &Multiaddr) -> crate::Result<(SocketAddr, Option<PeerId>)> {
         tracing::trace!(target: LOG_TARGET, ?address, "parse multi address");
 
@@ -187,6 +201,14 @@ impl WebRtcTransport {
 
         match iter.next() {
             Some(Protocol::WebRTC) => {}
+            Some(Protocol::P2pWebRtcStar) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?address,
+                    "browser-to-browser webrtc is not supported",
+                );
+                return Err(Error::AddressError(AddressError::InvalidProtocol));
+            }
             protocol => {
                 tracing::error!(
                     target: LOG_TARGET,
@@ -197,7 +219,22 @@ impl WebRtcTransport {
             }
         }
 
-        let maybe_peer = match iter.next() {
+        let mut next = iter.next();
+
+        if let Some(Protocol::Certhash(certhash)) = &next {
+            if !util::is_valid_certhash(certhash) {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    code = ?certhash.code(),
+                    "unsupported certificate hash, expected `sha-256`",
+                );
+                return Err(Error::AddressError(AddressError::InvalidProtocol));
+            }
+
+            next = iter.next();
+        }
+
+        let maybe_peer = match next {
             Some(Protocol::P2p(multihash)) => Some(PeerId::from_multihash(multihash)?),
             None => None,
             protocol => {
@@ -210,6 +247,18 @@ impl WebRtcTransport {
             }
         };
 
+        // `/p2p-circuit` after the server address means the address is for browser-to-browser
+        // webrtc where the connection is signalled over a relay
+        if let Some(protocol) = iter.next() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                ?protocol,
+                "address is not a webrtc-direct address",
+            );
+            return Err(Error::AddressError(AddressError::InvalidProtocol));
+        }
+
         Ok((socket_address, maybe_peer))
     }
 
@@ -321,20 +370,20 @@ impl WebRtcTransport {
             tx,
         }) = self.open.get_mut(&source)
         {
-            match tx.try_send(buffer) {
-                Ok(_) => return Ok(false),
-                Err(TrySendError::Full(_)) => {
-                    tracing::warn!(
+            match tx.send(buffer) {
+                Ok(false) | Err(Closed) => return Ok(false),
+                Ok(true) => {
+                    tracing::debug!(
                         target: LOG_TARGET,
                         ?source,
                         ?peer,
                         ?connection_id,
-                        "channel full, dropping datagram",
+                        dropped = ?tx.dropped(),
+                        "channel full, dropped oldest datagram",
                     );
 
                     return Ok(false);
                 }
-                Err(TrySendError::Closed(_)) => return Ok(false),
             }
         }
 
@@ -357,8 +406,16 @@ impl WebRtcTransport {
                 "received non-stun message"
             );
 
-            if let Err(error) = self.opening.get_mut(&source).expect("to exist").on_input(contents)
-            {
+            let Some(connection) = self.opening.get_mut(&source) else {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?source,
+                    "received non-stun message from unknown source",
+                );
+                return Err(Error::InvalidData);
+            };
+
+            if let Err(error) = connection.on_input(contents) {
                 tracing::error!(
                     target: LOG_TARGET,
                     ?error,
@@ -436,38 +493,31 @@ impl TransportBuilder for WebRtcTransport {
         let (listen_address, _) = Self::get_socket_address(&config.listen_addresses[0])?;
 
         let socket = if listen_address.is_ipv4() {
-            let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(socket2::Protocol::UDP))?;
-            socket.bind(&listen_address.into())?;
-            socket
+            Socket::new(Domain::IPV4, Type::DGRAM, Some(socket2::Protocol::UDP))?
         } else {
             let socket = Socket::new(Domain::IPV6, Type::DGRAM, Some(socket2::Protocol::UDP))?;
             socket.set_only_v6(true)?;
-            socket.bind(&listen_address.into())?;
             socket
         };
 
-        socket.set_reuse_address(true)?;
-        socket.set_nonblocking(true)?;
+        // socket options must be set before binding for them to have an effect
+        socket.set_reuse_address(config.reuse_address)?;
         #[cfg(unix)]
-        socket.set_reuse_port(true)?;
+        socket.set_reuse_port(config.reuse_port)?;
+        socket.set_nonblocking(true)?;
+        if let Some(device) = &config.bind_device {
+            bind_to_device(&socket, device)?;
+        }
+        socket.bind(&listen_address.into())?;
 
         let socket = UdpSocket::from_std(socket.into())?;
         let listen_address = socket.local_addr()?;
         let dtls_cert = DtlsCert::new_openssl();
 
-        let listen_multi_addresses = {
-            let fingerprint = dtls_cert.fingerprint().bytes;
-
-            const MULTIHASH_SHA256_CODE: u64 = 0x12;
-            let certificate = Multihash::wrap(MULTIHASH_SHA256_CODE, &fingerprint)
-                .expect("fingerprint's len to be 32 bytes");
-
-            vec![Multiaddr::empty()
-                .with(Protocol::from(listen_address.ip()))
-                .with(Protocol::Udp(listen_address.port()))
-                .with(Protocol::WebRTC)
-                .with(Protocol::Certhash(certificate))]
-        };
+        let listen_multi_addresses = vec![util::webrtc_direct_address(
+            listen_address,
+            &dtls_cert.fingerprint(),
+        )];
 
         Ok((
             Self {
@@ -489,6 +539,18 @@ impl TransportBuilder for WebRtcTransport {
 
 impl Transport for WebRtcTransport {
     fn dial(&mut self, connection_id: ConnectionId, address: Multiaddr) -> crate::Result<()> {
+        if let Err(error) = Self::get_socket_address(&address) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?connection_id,
+                ?address,
+                ?error,
+                "cannot dial address which is not a webrtc-direct address",
+            );
+
+            return Err(error);
+        }
+
         tracing::warn!(
             target: LOG_TARGET,
             ?connection_id,
@@ -555,7 +617,7 @@ impl Transport for WebRtcTransport {
         })?;
 
         let rtc = connection.on_accept()?;
-        let (tx, rx) = channel(self.datagram_buffer_size);
+        let (tx, rx) = datagram::channel(self.datagram_buffer_size);
         let protocol_set = self.context.protocol_set(connection_id);
         let connection_id = endpoint.connection_id();
 
@@ -672,7 +734,12 @@ impl Stream for WebRtcTransport {
 
                                     // keep polling the connection until it registers a timeout
                                     this.pending_events.push_back(
-                                        TransportEvent::ConnectionEstablished { peer, endpoint },
+                                        TransportEvent::ConnectionEstablished {
+                                            peer,
+                                            endpoint,
+                                            security: SecurityProtocol::Dtls,
+                                            muxer: Some(Muxer::Sctp),
+                                        },
                                     );
                                 }
                                 ConnectionEvent::ConnectionClosed => {
@@ -725,8 +792,12 @@ impl Stream for WebRtcTransport {
                                 .insert(endpoint.connection_id(), (peer, source, endpoint.clone()));
 
                             // keep polling the connection until it registers a timeout
-                            pending_event =
-                                Some(TransportEvent::ConnectionEstablished { peer, endpoint });
+                            pending_event = Some(TransportEvent::ConnectionEstablished {
+                                peer,
+                                endpoint,
+                                security: SecurityProtocol::Dtls,
+                                muxer: Some(Muxer::Sctp),
+                            });
                         }
                         ConnectionEvent::ConnectionClosed => {
                             this.opening.remove(&source);
@@ -780,3 +851,343 @@ fn is_stun_packet(bytes: &[u8]) -> bool {
     // 20 bytes for the header, then follows attributes.
     bytes.len() >= 20 && bytes[0] < 2
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ConfigBuilder, Role},
+        crypto::{noise::NoiseContext, Keypair},
+        executor::DefaultExecutor,
+        multistream_select::{DialerState, HandshakeResult},
+        protocol::libp2p::ping,
+        transport::{
+            manager::TransportHandle,
+            webrtc::util::{self, WebRtcMessage},
+        },
+        types::protocol::ProtocolName,
+        BandwidthSink, Litep2p, Litep2pEvent,
+    };
+    use multiaddr::multihash::Multihash;
+    use str0m::{Event, Output};
+    use tokio::sync::mpsc::channel;
+
+    fn make_transport_handle() -> TransportHandle {
+        let (tx, _rx) = channel(64);
+
+        TransportHandle {
+            executor: Arc::new(DefaultExecutor {}),
+            substream_registry: None,
+            negotiation_observer: None,
+            negotiation_cache: None,
+            buffer_budget: None,
+            ip_filter: Default::default(),
+            substream_rate_limit: None,
+            #[cfg(feature = "traffic-tap")]
+            traffic_taps: HashMap::new(),
+            next_substream_id: Default::default(),
+            next_connection_id: Default::default(),
+            keypair: Keypair::generate_ed25519(),
+            tx,
+            bandwidth_sink: BandwidthSink::new(),
+            protocols: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn listen_address_contains_certhash() {
+        let (transport, listen_addresses) = WebRtcTransport::new(
+            make_transport_handle(),
+            Config {
+                listen_addresses: vec!["/ip4/127.0.0.1/udp/0/webrtc-direct".parse().unwrap()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(listen_addresses.len(), 1);
+
+        let mut iter = listen_addresses[0].iter();
+        assert_eq!(iter.next(), Some(Protocol::Ip4([127, 0, 0, 1].into())));
+        assert_eq!(
+            iter.next(),
+            Some(Protocol::Udp(transport.listen_address.port()))
+        );
+        assert_eq!(iter.next(), Some(Protocol::WebRTC));
+
+        let Some(Protocol::Certhash(certhash)) = iter.next() else {
+            panic!("expected `/certhash` in {:?}", listen_addresses[0]);
+        };
+        assert_eq!(certhash.code(), 0x12);
+        assert_eq!(
+            certhash,
+            Multihash::wrap(0x12, &transport.dtls_cert.fingerprint().bytes).unwrap()
+        );
+        assert!(iter.next().is_none());
+
+        // the advertised address is accepted as a webrtc-direct address
+        let (socket_address, peer) =
+            WebRtcTransport::get_socket_address(&listen_addresses[0]).expect("address to be valid");
+        assert_eq!(socket_address, transport.listen_address);
+        assert!(peer.is_none());
+    }
+
+    #[test]
+    fn non_direct_addresses_rejected() {
+        let peer = PeerId::random();
+        let relay = PeerId::random();
+
+        for address in [
+            format!("/ip4/127.0.0.1/udp/8888/webrtc-direct/p2p/{relay}/p2p-circuit/p2p/{peer}"),
+            "/ip4/127.0.0.1/udp/8888/p2p-webrtc-star".to_string(),
+            "/ip4/127.0.0.1/udp/8888/quic-v1".to_string(),
+        ] {
+            let address: Multiaddr = address.parse().unwrap();
+
+            assert!(std::matches!(
+                WebRtcTransport::get_socket_address(&address),
+                Err(Error::AddressError(AddressError::InvalidProtocol))
+            ));
+        }
+
+        let address: Multiaddr =
+            format!("/ip4/127.0.0.1/udp/8888/webrtc-direct/p2p/{peer}").parse().unwrap();
+        assert_eq!(
+            WebRtcTransport::get_socket_address(&address).unwrap(),
+            ("127.0.0.1:8888".parse().unwrap(), Some(peer))
+        );
+    }
+
+    /// WebRTC-direct client, driving `str0m` over a UDP socket the way a browser would.
+    struct WebRtcClient {
+        rtc: Rtc,
+        socket: UdpSocket,
+        local_address: SocketAddr,
+        server_address: SocketAddr,
+        noise_channel_id: ChannelId,
+    }
+
+    impl WebRtcClient {
+        async fn new(server_address: SocketAddr) -> Self {
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let local_address = socket.local_addr().unwrap();
+            let ufrag = "libp2p+webrtc+v1/litep2p-test";
+
+            let mut rtc = Rtc::builder().set_fingerprint_verification(false).build();
+            rtc.add_local_candidate(Candidate::host(local_address, Str0mProtocol::Udp).unwrap());
+            rtc.add_remote_candidate(Candidate::host(server_address, Str0mProtocol::Udp).unwrap());
+            rtc.direct_api().set_remote_fingerprint(REMOTE_FINGERPRINT.parse().unwrap());
+            rtc.direct_api().set_remote_ice_credentials(IceCreds {
+                ufrag: ufrag.to_owned(),
+                pass: ufrag.to_owned(),
+            });
+            rtc.direct_api().set_local_ice_credentials(IceCreds {
+                ufrag: ufrag.to_owned(),
+                pass: ufrag.to_owned(),
+            });
+            rtc.direct_api().set_ice_controlling(true);
+            rtc.direct_api().start_dtls(true).unwrap();
+            rtc.direct_api().start_sctp(true);
+
+            let noise_channel_id = rtc.direct_api().create_data_channel(ChannelConfig {
+                label: "noise".to_string(),
+                ordered: false,
+                reliability: Default::default(),
+                negotiated: Some(0),
+                protocol: "".to_string(),
+            });
+
+            Self {
+                rtc,
+                socket,
+                local_address,
+                server_address,
+                noise_channel_id,
+            }
+        }
+
+        /// Drive `Rtc` until it emits an event.
+        async fn next_event(&mut self) -> Event {
+            let mut buffer = vec![0u8; 2048];
+
+            loop {
+                let timeout = match self.rtc.poll_output().unwrap() {
+                    Output::Transmit(transmit) => {
+                        self.socket
+                            .send_to(&transmit.contents, transmit.destination)
+                            .await
+                            .unwrap();
+                        continue;
+                    }
+                    Output::Timeout(timeout) => timeout,
+                    Output::Event(event) => return event,
+                };
+
+                tokio::select! {
+                    result = self.socket.recv_from(&mut buffer) => {
+                        let (nread, source) = result.unwrap();
+
+                        self.rtc
+                            .handle_input(Input::Receive(
+                                Instant::now(),
+                                Receive {
+                                    proto: Str0mProtocol::Udp,
+                                    source,
+                                    destination: self.local_address,
+                                    contents: buffer[..nread].try_into().unwrap(),
+                                },
+                            ))
+                            .unwrap();
+                    }
+                    _ = tokio::time::sleep_until(timeout.into()) => {
+                        self.rtc.handle_input(Input::Timeout(Instant::now())).unwrap();
+                    }
+                }
+            }
+        }
+
+        /// Wait until data is received over `channel_id` and return the message payload.
+        async fn next_message(&mut self, channel_id: ChannelId) -> Vec<u8> {
+            loop {
+                if let Event::ChannelData(data) = self.next_event().await {
+                    if data.id == channel_id {
+                        return WebRtcMessage::decode(&data.data).unwrap().payload.unwrap();
+                    }
+                }
+            }
+        }
+
+        /// Write `payload` to `channel_id`.
+        fn write(&mut self, channel_id: ChannelId, payload: Vec<u8>) {
+            self.rtc
+                .channel(channel_id)
+                .unwrap()
+                .write(true, &WebRtcMessage::encode(payload))
+                .unwrap();
+        }
+
+        /// Open connection to the server and conclude the Noise handshake as the responder.
+        ///
+        /// Returns the peer ID of the server.
+        async fn connect(&mut self, keypair: &Keypair) -> PeerId {
+            loop {
+                match self.next_event().await {
+                    Event::ChannelOpen(channel_id, _) if channel_id == self.noise_channel_id =>
+                        break,
+                    _ => {}
+                }
+            }
+
+            let local_fingerprint =
+                util::certhash(&self.rtc.direct_api().local_dtls_fingerprint()).to_bytes();
+            let remote_fingerprint =
+                util::certhash(&self.rtc.direct_api().remote_dtls_fingerprint().clone().unwrap())
+                    .to_bytes();
+            let prologue = [
+                b"libp2p-webrtc-noise:".as_slice(),
+                local_fingerprint.as_slice(),
+                remote_fingerprint.as_slice(),
+            ]
+            .concat();
+            let mut context =
+                NoiseContext::with_prologue(keypair, prologue, Role::Listener).unwrap();
+
+            let message = self.next_message(self.noise_channel_id).await;
+            context.read_message(&message).unwrap();
+            self.write(self.noise_channel_id, context.second_message().unwrap());
+
+            let message = self.next_message(self.noise_channel_id).await;
+            PeerId::from_public_key(&context.get_remote_public_key(&message).unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn connection_survives_malformed_datagrams() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        let (ping_config, _ping_event_stream) = ping::Config::default();
+        let mut litep2p = Litep2p::new(
+            ConfigBuilder::new()
+                .with_webrtc(Config {
+                    listen_addresses: vec!["/ip4/127.0.0.1/udp/0/webrtc-direct".parse().unwrap()],
+                    ..Default::default()
+                })
+                .with_libp2p_ping(ping_config)
+                .build(),
+        )
+        .unwrap();
+        let (server_address, _) =
+            WebRtcTransport::get_socket_address(litep2p.listen_addresses().next().unwrap())
+                .unwrap();
+        let server_peer = *litep2p.local_peer_id();
+
+        let (event_tx, mut event_rx) = channel(64);
+        tokio::spawn(async move {
+            while let Some(event) = litep2p.next_event().await {
+                let _ = event_tx.send(event).await;
+            }
+        });
+
+        // non-stun datagram from a source which hasn't opened a connection
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.send_to(&[0x17; 32], server_address).await.unwrap();
+
+        let keypair = Keypair::generate_ed25519();
+        let mut client = WebRtcClient::new(server_address).await;
+
+        tokio::time::timeout(Duration::from_secs(20), async {
+            assert_eq!(client.connect(&keypair).await, server_peer);
+
+            match event_rx.recv().await.unwrap() {
+                Litep2pEvent::ConnectionEstablished { peer, .. } =>
+                    assert_eq!(peer, PeerId::from_public_key(&keypair.public())),
+                event => panic!("unexpected event: {event:?}"),
+            }
+
+            // datagrams which `str0m` fails to handle must not close the connection
+            for datagram in [&[][..], &[0xff; 32][..]] {
+                client.socket.send_to(datagram, client.server_address).await.unwrap();
+            }
+
+            // verify the connection is still usable by negotiating a substream over it
+            let channel_id = client.rtc.direct_api().create_data_channel(ChannelConfig {
+                label: "".to_string(),
+                ordered: false,
+                reliability: Default::default(),
+                negotiated: None,
+                protocol: "".to_string(),
+            });
+            loop {
+                match client.next_event().await {
+                    Event::ChannelOpen(id, _) if id == channel_id => break,
+                    _ => {}
+                }
+            }
+
+            let (mut dialer_state, message) =
+                DialerState::propose(ProtocolName::from("/ipfs/ping/1.0.0"), Vec::new()).unwrap();
+            client.write(channel_id, message);
+
+            loop {
+                let message = client.next_message(channel_id).await;
+
+                match dialer_state.register_response(message).unwrap() {
+                    HandshakeResult::NotReady => {}
+                    HandshakeResult::Succeeded(protocol) => {
+                        assert_eq!(protocol, ProtocolName::from("/ipfs/ping/1.0.0"));
+                        break;
+                    }
+                }
+            }
+
+            assert!(!std::matches!(
+                event_rx.try_recv(),
+                Ok(Litep2pEvent::ConnectionClosed { .. })
+            ));
+        })
+        .await
+        .expect("test to finish in time");
+    }
+}
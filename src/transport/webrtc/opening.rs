@@ -22,13 +22,16 @@
 
 use crate::{
     config::Role,
-    crypto::{ed25519::Keypair, noise::NoiseContext},
-    transport::{webrtc::util::WebRtcMessage, Endpoint},
+    crypto::{noise::NoiseContext, Keypair},
+    transport::{
+        webrtc::util::{self, WebRtcMessage},
+        Endpoint,
+    },
     types::ConnectionId,
     Error, PeerId,
 };
 
-use multiaddr::{multihash::Multihash, Multiaddr, Protocol};
+use multiaddr::Protocol;
 use str0m::{
     change::Fingerprint,
     channel::ChannelId,
@@ -188,10 +191,7 @@ impl OpeningWebRtcConnection {
 
     /// Convert `Fingerprint` to bytes.
     fn fingerprint_to_bytes(fingerprint: &Fingerprint) -> Vec<u8> {
-        const MULTIHASH_SHA256_CODE: u64 = 0x12;
-        Multihash::wrap(MULTIHASH_SHA256_CODE, &fingerprint.bytes)
-            .expect("fingerprint's len to be 32 bytes")
-            .to_bytes()
+        util::certhash(fingerprint).to_bytes()
     }
 
     /// Once a Noise data channel has been opened, even though the light client was the dialer,
@@ -270,18 +270,9 @@ impl OpeningWebRtcConnection {
             .direct_api()
             .remote_dtls_fingerprint()
             .clone()
-            .expect("fingerprint to exist")
-            .bytes;
-
-        const MULTIHASH_SHA256_CODE: u64 = 0x12;
-        let certificate = Multihash::wrap(MULTIHASH_SHA256_CODE, &remote_fingerprint)
-            .expect("fingerprint's len to be 32 bytes");
-
-        let address = Multiaddr::empty()
-            .with(Protocol::from(self.peer_address.ip()))
-            .with(Protocol::Udp(self.peer_address.port()))
-            .with(Protocol::WebRTC)
-            .with(Protocol::Certhash(certificate))
+            .expect("fingerprint to exist");
+
+        let address = util::webrtc_direct_address(self.peer_address, &remote_fingerprint)
             .with(Protocol::P2p(PeerId::from(public_key).into()));
 
         Ok(WebRtcEvent::ConnectionOpened {
@@ -358,7 +349,7 @@ impl OpeningWebRtcConnection {
         }
 
         loop {
-            let output = match self.rtc.poll_output() {
+            let output = match util::poll_output(&mut self.rtc) {
                 Ok(output) => output,
                 Err(error) => {
                     tracing::debug!(
@@ -443,6 +434,7 @@ impl OpeningWebRtcConnection {
                             let context = match NoiseContext::with_prologue(
                                 &self.id_keypair,
                                 noise_prologue(local_fingerprint, remote_fingerprint),
+                                Role::Dialer,
                             ) {
                                 Ok(context) => context,
                                 Err(err) => {
@@ -19,11 +19,12 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    protocol::Permit,
     transport::webrtc::{schema::webrtc::message::Flag, util::WebRtcMessage},
     Error,
 };
 
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use futures::{Future, Stream};
 use parking_lot::Mutex;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
@@ -43,6 +44,12 @@ pub enum Event {
     /// Receiver closed.
     RecvClosed,
 
+    /// Sender closed.
+    SendClosed,
+
+    /// Send final message and close the sender.
+    FinalMessage(Vec<u8>),
+
     /// Send/receive message.
     Message(Vec<u8>),
 
@@ -67,11 +74,20 @@ pub struct Substream {
     /// Read buffer.
     read_buffer: BytesMut,
 
+    /// Has the remote peer closed its write side of the substream.
+    recv_closed: bool,
+
     /// TX channel for sending messages to `peer`.
     tx: Sender<Event>,
 
     /// RX channel for receiving messages from `peer`.
     rx: Receiver<Event>,
+
+    /// Is the substream corked.
+    corked: bool,
+
+    /// Data written while the substream is corked, sent once the substream is uncorked.
+    cork_buffer: BytesMut,
 }
 
 impl Substream {
@@ -84,6 +100,7 @@ impl Substream {
             tx: inbound_tx,
             rx: outbound_rx,
             state: Arc::clone(&state),
+            permit: None,
         };
 
         (
@@ -92,10 +109,95 @@ impl Substream {
                 tx: outbound_tx,
                 rx: inbound_rx,
                 read_buffer: BytesMut::new(),
+                corked: false,
+                cork_buffer: BytesMut::new(),
+                recv_closed: false,
             },
             handle,
         )
     }
+
+    /// Cork or uncork the substream.
+    ///
+    /// While the substream is corked, written data is buffered instead of being sent as a separate
+    /// message for each write. The buffered data is sent as a single message when the substream
+    /// is uncorked and then flushed, when the buffer grows to the maximum message size or when
+    /// the write side of the substream is closed.
+    pub fn set_corked(&mut self, corked: bool) {
+        self.corked = corked;
+    }
+
+    /// Send data buffered while the substream was corked.
+    fn poll_send_corked(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        if self.cork_buffer.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let future = self.tx.reserve();
+        futures::pin_mut!(future);
+
+        let permit = match futures::ready!(future.poll(cx)) {
+            Err(_) => return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into())),
+            Ok(permit) => permit,
+        };
+        permit.send(Event::Message(self.cork_buffer.split().to_vec()));
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Close the write side of the substream.
+    ///
+    /// Sends `FIN` to the remote peer while keeping the read side of the substream open.
+    pub fn poll_close_write(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        futures::ready!(self.poll_send_corked(cx))?;
+
+        let future = self.tx.reserve();
+        futures::pin_mut!(future);
+
+        let permit = match futures::ready!(future.poll(cx)) {
+            Err(_) => return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into())),
+            Ok(permit) => permit,
+        };
+        permit.send(Event::SendClosed);
+        *self.state.lock() = State::SendClosed;
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Send `frame` and close the write side of the substream.
+    ///
+    /// The last chunk of `frame` is sent in the same message as `FIN`. `frame` is advanced past
+    /// the data that has been sent so the call can be resumed after it returns [`Poll::Pending`].
+    pub fn poll_close_write_with(
+        &mut self,
+        cx: &mut Context<'_>,
+        frame: &mut Bytes,
+    ) -> Poll<std::io::Result<()>> {
+        // the buffered data must be sent before `frame`
+        self.corked = false;
+        futures::ready!(self.poll_send_corked(cx))?;
+
+        while frame.len() > MAX_FRAME_SIZE {
+            let nwritten = futures::ready!(tokio::io::AsyncWrite::poll_write(
+                Pin::new(&mut *self),
+                cx,
+                &frame[..]
+            ))?;
+            frame.advance(nwritten);
+        }
+
+        let future = self.tx.reserve();
+        futures::pin_mut!(future);
+
+        let permit = match futures::ready!(future.poll(cx)) {
+            Err(_) => return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into())),
+            Ok(permit) => permit,
+        };
+        permit.send(Event::FinalMessage(std::mem::take(frame).to_vec()));
+        *self.state.lock() = State::SendClosed;
+
+        Poll::Ready(Ok(()))
+    }
 }
 
 /// Substream handle that is given to the transport backend.
@@ -107,20 +209,24 @@ pub struct SubstreamHandle {
 
     /// RX channel for receiving messages from `peer`.
     rx: Receiver<Event>,
+
+    /// Permit which keeps the connection open, set once the substream has been negotiated.
+    permit: Option<Permit>,
 }
 
 impl SubstreamHandle {
+    /// Set the permit which keeps the connection open while the substream is alive.
+    pub fn set_permit(&mut self, permit: Permit) {
+        self.permit = Some(permit);
+    }
+
     /// Handle message received from a remote peer.
     ///
     /// If the message contains any flags, handle them first and appropriately close the correct
     /// side of the substream. If the message contained any payload, send it to the protocol for
     /// further processing.
-    pub async fn on_message(&self, message: WebRtcMessage) -> crate::Result<()> {
+    pub async fn on_message(&mut self, message: WebRtcMessage) -> crate::Result<()> {
         if let Some(flags) = message.flags {
-            if flags == Flag::Fin as i32 {
-                self.tx.send(Event::RecvClosed).await?;
-            }
-
             if flags & 1 == Flag::StopSending as i32 {
                 *self.state.lock() = State::SendClosed;
             }
@@ -130,12 +236,20 @@ impl SubstreamHandle {
             }
         }
 
+        // payload of a message carrying `FIN` is the last data sent by the remote peer
         if let Some(payload) = message.payload {
             if !payload.is_empty() {
-                return self.tx.send(Event::Message(payload)).await.map_err(From::from);
+                if let Some(permit) = &mut self.permit {
+                    permit.on_activity();
+                }
+                self.tx.send(Event::Message(payload)).await?;
             }
         }
 
+        if message.flags == Some(Flag::Fin as i32) {
+            self.tx.send(Event::RecvClosed).await?;
+        }
+
         Ok(())
     }
 }
@@ -144,7 +258,18 @@ impl Stream for SubstreamHandle {
     type Item = Event;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.rx.poll_recv(cx)
+        let this = &mut *self;
+
+        if let Some(permit) = &mut this.permit {
+            permit.poll_idle(cx);
+        }
+
+        let event = futures::ready!(this.rx.poll_recv(cx));
+        if let (Some(Event::Message(_)), Some(permit)) = (&event, &mut this.permit) {
+            permit.on_activity();
+        }
+
+        Poll::Ready(event)
     }
 }
 
@@ -165,9 +290,17 @@ impl tokio::io::AsyncRead for Substream {
             return Poll::Ready(Ok(()));
         }
 
+        if self.recv_closed {
+            return Poll::Ready(Ok(()));
+        }
+
         match futures::ready!(self.rx.poll_recv(cx)) {
-            None | Some(Event::Close) | Some(Event::RecvClosed) =>
+            None | Some(Event::Close) | Some(Event::SendClosed) | Some(Event::FinalMessage(_)) =>
                 Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into())),
+            Some(Event::RecvClosed) => {
+                self.recv_closed = true;
+                Poll::Ready(Ok(()))
+            }
             Some(Event::Message(message)) => {
                 if message.len() > MAX_FRAME_SIZE {
                     return Poll::Ready(Err(std::io::ErrorKind::PermissionDenied.into()));
@@ -190,7 +323,7 @@ impl tokio::io::AsyncRead for Substream {
 
 impl tokio::io::AsyncWrite for Substream {
     fn poll_write(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, std::io::Error>> {
@@ -198,6 +331,18 @@ impl tokio::io::AsyncWrite for Substream {
             return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
         }
 
+        if self.corked {
+            if self.cork_buffer.len() >= MAX_FRAME_SIZE {
+                futures::ready!(self.poll_send_corked(cx))?;
+            }
+
+            let num_bytes = std::cmp::min(MAX_FRAME_SIZE - self.cork_buffer.len(), buf.len());
+            self.cork_buffer.put_slice(&buf[..num_bytes]);
+
+            return Poll::Ready(Ok(num_bytes));
+        }
+        futures::ready!(self.poll_send_corked(cx))?;
+
         // TODO: try to coalesce multiple calls to `poll_write()` into single `Event::Message`
 
         let num_bytes = std::cmp::min(MAX_FRAME_SIZE, buf.len());
@@ -215,14 +360,23 @@ impl tokio::io::AsyncWrite for Substream {
         Poll::Ready(Ok(num_bytes))
     }
 
-    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), std::io::Error>> {
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        if !self.corked {
+            futures::ready!(self.poll_send_corked(cx))?;
+        }
+
         Poll::Ready(Ok(()))
     }
 
     fn poll_shutdown(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), std::io::Error>> {
+        futures::ready!(self.poll_send_corked(cx))?;
+
         let future = self.tx.reserve();
         futures::pin_mut!(future);
 
@@ -303,8 +457,8 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn try_to_read_from_closed_substream() {
-        let (mut substream, handle) = Substream::new();
+    async fn read_from_closed_substream_returns_eof() {
+        let (mut substream, mut handle) = Substream::new();
         handle
             .on_message(WebRtcMessage {
                 payload: None,
@@ -313,10 +467,65 @@ mod tests {
             .await
             .unwrap();
 
-        match substream.read(&mut vec![0u8; 256]).await {
+        assert_eq!(substream.read(&mut vec![0u8; 256]).await.unwrap(), 0);
+        assert_eq!(substream.read(&mut vec![0u8; 256]).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn close_write_side() {
+        let (mut substream, mut handle) = Substream::new();
+
+        futures::future::poll_fn(|cx| substream.poll_close_write(cx)).await.unwrap();
+        assert_eq!(handle.next().await, Some(Event::SendClosed));
+
+        match substream.write_all(&vec![0u8; 1337]).await {
             Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe),
             _ => panic!("invalid event"),
         }
+
+        // read side stays open
+        handle.tx.send(Event::Message(vec![1u8; 256])).await.unwrap();
+
+        let mut buf = vec![0u8; 256];
+        assert_eq!(substream.read(&mut buf).await.unwrap(), 256);
+        assert_eq!(buf, vec![1u8; 256]);
+    }
+
+    #[tokio::test]
+    async fn close_write_with_final_message() {
+        let (mut substream, mut handle) = Substream::new();
+        let mut frame = Bytes::from(vec![2u8; MAX_FRAME_SIZE + 16]);
+
+        futures::future::poll_fn(|cx| substream.poll_close_write_with(cx, &mut frame))
+            .await
+            .unwrap();
+
+        // last chunk of the frame is sent together with `FIN`
+        assert_eq!(
+            handle.next().await,
+            Some(Event::Message(vec![2u8; MAX_FRAME_SIZE]))
+        );
+        assert_eq!(
+            handle.next().await,
+            Some(Event::FinalMessage(vec![2u8; 16]))
+        );
+        assert!(substream.write_all(&[0u8; 8]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn final_message_read_before_eof() {
+        let (mut substream, mut handle) = Substream::new();
+        handle
+            .on_message(WebRtcMessage {
+                payload: Some(vec![3u8; 64]),
+                flags: Some(Flag::Fin as i32),
+            })
+            .await
+            .unwrap();
+
+        let mut buf = Vec::new();
+        substream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, vec![3u8; 64]);
     }
 
     #[tokio::test]
@@ -20,9 +20,101 @@
 
 use crate::{codec::unsigned_varint::UnsignedVarint, error::ParseError, transport::webrtc::schema};
 
+use multiaddr::{multihash::Multihash, Multiaddr, Protocol};
 use prost::Message;
+use str0m::{change::Fingerprint, Output, Rtc, RtcError};
 use tokio_util::codec::{Decoder, Encoder};
 
+use std::net::SocketAddr;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::webrtc::util";
+
+/// How many recoverable errors `poll_output()` can return in a row before the connection is
+/// considered broken.
+const MAX_RECOVERABLE_ERRORS: usize = 16;
+
+/// Multihash code of SHA-256.
+const MULTIHASH_SHA256_CODE: u64 = 0x12;
+
+/// Get the `/certhash` multihash of the DTLS certificate with `fingerprint`.
+pub fn certhash(fingerprint: &Fingerprint) -> Multihash {
+    Multihash::wrap(MULTIHASH_SHA256_CODE, &fingerprint.bytes)
+        .expect("fingerprint's len to be 32 bytes")
+}
+
+/// Check if `certhash` is a SHA-256 multihash, the only hash supported for certificates.
+pub fn is_valid_certhash(certhash: &Multihash) -> bool {
+    certhash.code() == MULTIHASH_SHA256_CODE && certhash.size() == 32
+}
+
+/// Create `/ip/udp/webrtc-direct/certhash` address of a WebRTC-direct server at `address`.
+///
+/// Browsers need the certificate hash to be able to dial the server as the certificate is
+/// self-signed.
+pub fn webrtc_direct_address(address: SocketAddr, fingerprint: &Fingerprint) -> Multiaddr {
+    Multiaddr::empty()
+        .with(Protocol::from(address.ip()))
+        .with(Protocol::Udp(address.port()))
+        .with(Protocol::WebRTC)
+        .with(Protocol::Certhash(certhash(fingerprint)))
+}
+
+/// Source of `str0m` output.
+///
+/// Implemented for [`Rtc`] and allows the output polling logic to be tested without a real
+/// WebRTC connection.
+pub trait PollOutput {
+    /// Poll next output.
+    fn poll_output(&mut self) -> Result<Output, RtcError>;
+}
+
+impl PollOutput for Rtc {
+    fn poll_output(&mut self) -> Result<Output, RtcError> {
+        Rtc::poll_output(self)
+    }
+}
+
+/// Check if `error` returned by `poll_output()` is recoverable.
+///
+/// Errors which concern a single packet or media write leave the connection in a usable state
+/// and the output can be polled again. All other errors are treated as fatal.
+pub fn is_recoverable(error: &RtcError) -> bool {
+    matches!(
+        error,
+        RtcError::Packet(..)
+            | RtcError::Net(..)
+            | RtcError::Srtp(..)
+            | RtcError::UnknownPt(..)
+            | RtcError::UnknownRid(..)
+            | RtcError::NoSenderSource
+            | RtcError::NoReceiverSource(..)
+    )
+}
+
+/// Poll `rtc` for output, skipping over recoverable errors.
+///
+/// Returns an error if `rtc` returned a fatal error or too many recoverable errors in a row.
+pub fn poll_output(rtc: &mut impl PollOutput) -> Result<Output, RtcError> {
+    let mut num_errors = 0usize;
+
+    loop {
+        match rtc.poll_output() {
+            Ok(output) => return Ok(output),
+            Err(error) if is_recoverable(&error) && num_errors < MAX_RECOVERABLE_ERRORS => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "recoverable error while polling output",
+                );
+
+                num_errors += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
 /// WebRTC mesage.
 #[derive(Debug)]
 pub struct WebRtcMessage {
@@ -94,6 +186,57 @@ impl WebRtcMessage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::{collections::VecDeque, time::Instant};
+
+    /// [`Rtc`] stub which returns pre-programmed output.
+    struct StubRtc(VecDeque<Result<Output, RtcError>>);
+
+    impl PollOutput for StubRtc {
+        fn poll_output(&mut self) -> Result<Output, RtcError> {
+            self.0.pop_front().expect("output to exist")
+        }
+    }
+
+    #[test]
+    fn recoverable_error_skipped() {
+        let mut rtc = StubRtc(VecDeque::from([
+            Err(RtcError::NoSenderSource),
+            Ok(Output::Timeout(Instant::now())),
+            Err(RtcError::NoSenderSource),
+            Ok(Output::Timeout(Instant::now())),
+        ]));
+
+        assert!(std::matches!(poll_output(&mut rtc), Ok(Output::Timeout(_))));
+        assert!(std::matches!(poll_output(&mut rtc), Ok(Output::Timeout(_))));
+        assert!(rtc.0.is_empty());
+    }
+
+    #[test]
+    fn fatal_error_returned() {
+        let mut rtc = StubRtc(VecDeque::from([
+            Err(RtcError::NoSenderSource),
+            Err(RtcError::Io(std::io::ErrorKind::BrokenPipe.into())),
+            Ok(Output::Timeout(Instant::now())),
+        ]));
+
+        assert!(std::matches!(poll_output(&mut rtc), Err(RtcError::Io(_))));
+        assert_eq!(rtc.0.len(), 1);
+    }
+
+    #[test]
+    fn too_many_recoverable_errors() {
+        let mut rtc = StubRtc(
+            (0..=MAX_RECOVERABLE_ERRORS)
+                .map(|_| Err(RtcError::NoSenderSource))
+                .chain(std::iter::once(Ok(Output::Timeout(Instant::now()))))
+                .collect(),
+        );
+
+        assert!(std::matches!(
+            poll_output(&mut rtc),
+            Err(RtcError::NoSenderSource)
+        ));
+    }
 
     #[test]
     fn with_payload_no_flags() {
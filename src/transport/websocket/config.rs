@@ -22,9 +22,16 @@
 
 use crate::{
     crypto::noise::{MAX_READ_AHEAD_FACTOR, MAX_WRITE_BUFFER_SIZE},
-    transport::{CONNECTION_OPEN_TIMEOUT, SUBSTREAM_OPEN_TIMEOUT},
+    transport::{
+        CONNECTION_OPEN_TIMEOUT, HANDSHAKE_TIMEOUT, HAPPY_EYEBALLS_DELAY,
+        INTERFACE_REFRESH_INTERVAL, MAX_CONCURRENT_DIALS_PER_PEER, SUBSTREAM_OPEN_TIMEOUT,
+    },
 };
 
+pub use crate::crypto::noise::RekeyConfig;
+
+use std::time::Duration;
+
 /// WebSocket transport configuration.
 #[derive(Debug)]
 pub struct Config {
@@ -33,19 +40,48 @@ pub struct Config {
     /// Default listen addreses are ["/ip4/0.0.0.0/tcp/0/ws", "/ip6/::/tcp/0/ws"].
     pub listen_addresses: Vec<multiaddr::Multiaddr>,
 
+    /// Whether to set `SO_REUSEADDR` on listening sockets.
+    ///
+    /// Allows the listen address to be bound again right after the previous listener was closed,
+    /// even if connections of the previous listener are still in the `TIME_WAIT` state.
+    ///
+    /// Defaults to `true`.
+    pub reuse_address: bool,
+
     /// Whether to set `SO_REUSEPORT` and bind a socket to the listen address port for outbound
     /// connections.
     ///
-    /// Note that `SO_REUSEADDR` is always set on listening sockets.
+    /// `SO_REUSEPORT` is only supported on Unix platforms.
     ///
     /// Defaults to `true`.
     pub reuse_port: bool,
 
     /// Enable `TCP_NODELAY`.
     ///
+    /// `TCP_NODELAY` is also enabled if any of the installed protocols is marked as
+    /// latency-sensitive.
+    ///
     /// Defaults to `false`.
     pub nodelay: bool,
 
+    /// Head start given to IPv6 addresses when dialing a peer with both IPv4 and IPv6 addresses.
+    ///
+    /// IPv4 addresses are dialed once the head start has elapsed or all IPv6 dial attempts have
+    /// failed, whichever happens first. The first connection that is opened is used and the other
+    /// dial attempts are cancelled. Setting the delay to zero dials IPv4 and IPv6 addresses at
+    /// once.
+    ///
+    /// Defaults to `250ms`.
+    pub happy_eyeballs_delay: std::time::Duration,
+
+    /// Maximum number of addresses of a peer that are dialed concurrently.
+    ///
+    /// If the peer has more addresses, the remaining addresses are dialed one by one as the
+    /// earlier dial attempts fail. A value of zero is treated as one.
+    ///
+    /// Defaults to `2`.
+    pub max_concurrent_dials: usize,
+
     /// Yamux configuration.
     pub yamux_config: crate::yamux::Config,
 
@@ -69,6 +105,16 @@ pub struct Config {
     /// the Noise code will allocate `2 * 65 KB + 5 * 65 KB = 455 KB` per connection.
     pub noise_write_buffer_size: usize,
 
+    /// Noise session re-key configuration.
+    ///
+    /// If set, the key used to encrypt outbound data is rotated after the configured number of
+    /// bytes or amount of time, allowing long-lived connections to limit how much data is
+    /// protected by a single key. The connection and its substreams are kept open across the
+    /// rotation.
+    ///
+    /// Disabled by default.
+    pub noise_rekey: Option<RekeyConfig>,
+
     /// Connection open timeout.
     ///
     /// How long should litep2p wait for a connection to be opened before the host
@@ -80,6 +126,20 @@ pub struct Config {
     /// How long should litep2p wait for a substream to be opened before considering
     /// the substream rejected.
     pub substream_open_timeout: std::time::Duration,
+
+    /// Noise handshake timeout.
+    ///
+    /// How long should litep2p wait for the Noise handshake to complete after the connection
+    /// has been established before the connection is closed.
+    pub handshake_timeout: std::time::Duration,
+
+    /// How often the network interfaces are checked for added or removed addresses.
+    ///
+    /// Only affects listeners bound to an unspecified address, such as `/ip4/0.0.0.0/tcp/0/ws`,
+    /// which are reported at the addresses of the network interfaces.
+    ///
+    /// Defaults to 30 seconds.
+    pub interface_refresh_interval: Option<Duration>,
 }
 
 impl Default for Config {
@@ -89,13 +149,19 @@ impl Default for Config {
                 "/ip4/0.0.0.0/tcp/0/ws".parse().expect("valid address"),
                 "/ip6/::/tcp/0/ws".parse().expect("valid address"),
             ],
+            reuse_address: true,
             reuse_port: true,
             nodelay: false,
+            happy_eyeballs_delay: HAPPY_EYEBALLS_DELAY,
+            max_concurrent_dials: MAX_CONCURRENT_DIALS_PER_PEER,
             yamux_config: Default::default(),
             noise_read_ahead_frame_count: MAX_READ_AHEAD_FACTOR,
             noise_write_buffer_size: MAX_WRITE_BUFFER_SIZE,
+            noise_rekey: None,
             connection_open_timeout: CONNECTION_OPEN_TIMEOUT,
             substream_open_timeout: SUBSTREAM_OPEN_TIMEOUT,
+            handshake_timeout: HANDSHAKE_TIMEOUT,
+            interface_refresh_interval: Some(INTERFACE_REFRESH_INTERVAL),
         }
     }
 }
@@ -21,12 +21,12 @@
 use crate::{
     config::Role,
     crypto::{
-        ed25519::Keypair,
-        noise::{self, NoiseSocket},
+        noise::{self, NoiseSocket, RekeyConfig},
+        Keypair,
     },
     error::{Error, NegotiationError, SubstreamError},
     multistream_select::{dialer_select_proto, listener_select_proto, Negotiated, Version},
-    protocol::{Direction, Permit, ProtocolCommand, ProtocolSet},
+    protocol::{Direction, NegotiationObserver, Permit, ProtocolCommand, ProtocolSet},
     substream,
     transport::{
         websocket::{stream::BufferedStream, substream::Substream},
@@ -41,6 +41,7 @@ use multiaddr::{multihash::Multihash, Multiaddr, Protocol};
 use tokio::net::TcpStream;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 use tokio_util::compat::FuturesAsyncReadCompatExt;
+use tracing::Instrument;
 use url::Url;
 
 use std::time::Duration;
@@ -209,6 +210,32 @@ impl WebSocketConnection {
         Ok((socket, ProtocolName::from(protocol.to_string())))
     }
 
+    /// Accept an inbound substream as the listener.
+    ///
+    /// Besides the negotiation result, returns the protocols the remote proposed but which
+    /// aren't supported locally.
+    async fn accept_protocol<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: S,
+        protocols: Vec<&str>,
+    ) -> (
+        Result<(Negotiated<S>, ProtocolName), NegotiationError>,
+        Vec<ProtocolName>,
+    ) {
+        tracing::trace!(target: LOG_TARGET, ?protocols, "accepting protocols");
+
+        let mut future = listener_select_proto(stream, protocols);
+        let result = match (&mut future).await {
+            Err(error) => Err(NegotiationError::MultistreamSelectError(error)),
+            Ok((protocol, socket)) => {
+                tracing::trace!(target: LOG_TARGET, ?protocol, "protocol negotiated");
+
+                Ok((socket, ProtocolName::from(protocol.to_string())))
+            }
+        };
+
+        (result, future.rejected_protocols())
+    }
+
     /// Open WebSocket connection.
     pub(super) async fn open_connection(
         connection_id: ConnectionId,
@@ -220,6 +247,8 @@ impl WebSocketConnection {
         yamux_config: crate::yamux::Config,
         max_read_ahead_factor: usize,
         max_write_buffer_size: usize,
+        noise_rekey: Option<RekeyConfig>,
+        handshake_timeout: Duration,
     ) -> Result<NegotiatedConnection, NegotiationError> {
         tracing::trace!(
             target: LOG_TARGET,
@@ -239,6 +268,8 @@ impl WebSocketConnection {
             yamux_config,
             max_read_ahead_factor,
             max_write_buffer_size,
+            noise_rekey,
+            handshake_timeout,
         )
         .await
     }
@@ -252,6 +283,8 @@ impl WebSocketConnection {
         yamux_config: crate::yamux::Config,
         max_read_ahead_factor: usize,
         max_write_buffer_size: usize,
+        noise_rekey: Option<RekeyConfig>,
+        handshake_timeout: Duration,
     ) -> Result<NegotiatedConnection, NegotiationError> {
         let stream = MaybeTlsStream::Plain(stream);
 
@@ -267,6 +300,8 @@ impl WebSocketConnection {
             yamux_config,
             max_read_ahead_factor,
             max_write_buffer_size,
+            noise_rekey,
+            handshake_timeout,
         )
         .await
     }
@@ -282,6 +317,8 @@ impl WebSocketConnection {
         yamux_config: crate::yamux::Config,
         max_read_ahead_factor: usize,
         max_write_buffer_size: usize,
+        noise_rekey: Option<RekeyConfig>,
+        handshake_timeout: Duration,
     ) -> Result<NegotiatedConnection, NegotiationError> {
         tracing::trace!(
             target: LOG_TARGET,
@@ -302,14 +339,27 @@ impl WebSocketConnection {
         );
 
         // perform noise handshake
-        let (stream, peer) = noise::handshake(
-            stream.inner(),
-            &keypair,
-            role,
-            max_read_ahead_factor,
-            max_write_buffer_size,
+        let (stream, peer) = match tokio::time::timeout(
+            handshake_timeout,
+            noise::handshake(
+                stream.inner(),
+                &keypair,
+                role,
+                max_read_ahead_factor,
+                max_write_buffer_size,
+                noise_rekey,
+                #[cfg(feature = "noise-observer")]
+                None,
+            ),
         )
-        .await?;
+        .await
+        {
+            Err(_) => {
+                tracing::debug!(target: LOG_TARGET, ?connection_id, "noise handshake timed out");
+                return Err(NegotiationError::Timeout);
+            }
+            Ok(result) => result?,
+        };
 
         if let Some(dialed_peer) = dialed_peer {
             if peer != dialed_peer {
@@ -349,6 +399,8 @@ impl WebSocketConnection {
         permit: Permit,
         substream_id: SubstreamId,
         protocols: Vec<ProtocolName>,
+        peer: PeerId,
+        observer: Option<NegotiationObserver>,
     ) -> Result<NegotiatedSubstream, NegotiationError> {
         tracing::trace!(
             target: LOG_TARGET,
@@ -356,8 +408,23 @@ impl WebSocketConnection {
             "accept inbound substream"
         );
 
-        let protocols = protocols.iter().map(|protocol| &**protocol).collect::<Vec<&str>>();
-        let (io, protocol) = Self::negotiate_protocol(stream, &Role::Listener, protocols).await?;
+        let (result, unsupported) = Self::accept_protocol(
+            stream,
+            protocols.iter().map(|protocol| &**protocol).collect(),
+        )
+        .await;
+
+        if let Some(observer) = observer {
+            observer.observe(
+                peer,
+                Direction::Inbound,
+                protocols,
+                result.as_ref().ok().map(|(_, protocol)| protocol.clone()),
+                unsupported,
+            );
+        }
+
+        let (io, protocol) = result?;
 
         tracing::trace!(
             target: LOG_TARGET,
@@ -381,6 +448,8 @@ impl WebSocketConnection {
         substream_id: SubstreamId,
         protocol: ProtocolName,
         fallback_names: Vec<ProtocolName>,
+        peer: PeerId,
+        observer: Option<NegotiationObserver>,
     ) -> Result<NegotiatedSubstream, SubstreamError> {
         tracing::debug!(target: LOG_TARGET, ?protocol, ?substream_id, "open substream");
 
@@ -409,7 +478,19 @@ impl WebSocketConnection {
             .chain(fallback_names.iter().map(|protocol| &**protocol))
             .collect();
 
-        let (io, protocol) = Self::negotiate_protocol(stream, &Role::Dialer, protocols).await?;
+        let result = Self::negotiate_protocol(stream, &Role::Dialer, protocols).await;
+
+        if let Some(observer) = observer {
+            observer.observe(
+                peer,
+                Direction::Outbound(substream_id),
+                std::iter::once(protocol).chain(fallback_names).collect(),
+                result.as_ref().ok().map(|(_, protocol)| protocol.clone()),
+                Vec::new(),
+            );
+        }
+
+        let (io, protocol) = result?;
 
         Ok(NegotiatedSubstream {
             io: io.inner(),
@@ -421,7 +502,19 @@ impl WebSocketConnection {
     }
 
     /// Start connection event loop.
-    pub(crate) async fn start(mut self) -> crate::Result<()> {
+    pub(crate) async fn start(self) -> crate::Result<()> {
+        let span = tracing::debug_span!(
+            target: LOG_TARGET,
+            "connection",
+            peer = ?self.peer,
+            connection_id = ?self.endpoint.connection_id(),
+        );
+
+        self.event_loop().instrument(span).await
+    }
+
+    /// Connection event loop.
+    async fn event_loop(mut self) -> crate::Result<()> {
         self.protocol_set
             .report_connection_established(self.peer, self.endpoint)
             .await?;
@@ -430,15 +523,33 @@ impl WebSocketConnection {
             tokio::select! {
                 substream = self.connection.next() => match substream {
                     Some(Ok(stream)) => {
+                        if !self.protocol_set.try_accept_substream() {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                peer = ?self.peer,
+                                "substream rate limit exceeded, closing inbound substream",
+                            );
+                            continue;
+                        }
+
                         let substream = self.protocol_set.next_substream_id();
                         let protocols = self.protocol_set.protocols();
                         let permit = self.protocol_set.try_get_permit().ok_or(Error::ConnectionClosed)?;
                         let substream_open_timeout = self.substream_open_timeout;
+                        let peer = self.peer;
+                        let observer = self.protocol_set.negotiation_observer();
 
                         self.pending_substreams.push(Box::pin(async move {
                             match tokio::time::timeout(
                                 substream_open_timeout,
-                                Self::accept_substream(stream, permit, substream, protocols),
+                                Self::accept_substream(
+                                    stream,
+                                    permit,
+                                    substream,
+                                    protocols,
+                                    peer,
+                                    observer,
+                                ),
                             )
                             .await
                             {
@@ -503,13 +614,20 @@ impl WebSocketConnection {
                             let protocol = substream.protocol.clone();
                             let direction = substream.direction;
                             let substream_id = substream.substream_id;
-                            let socket = FuturesAsyncReadCompatExt::compat(substream.io);
+                            let mut io = substream.io;
+                            let priority = self.protocol_set.substream_priority(&protocol);
+                            io.set_priority(priority.value());
+                            let socket = FuturesAsyncReadCompatExt::compat(io);
                             let bandwidth_sink = self.bandwidth_sink.clone();
 
                             let substream = substream::Substream::new_websocket(
                                 self.peer,
                                 substream_id,
-                                Substream::new(socket, bandwidth_sink, substream.permit),
+                                Substream::new(
+                                    socket,
+                                    bandwidth_sink,
+                                    self.protocol_set.substream_permit(&protocol, substream.permit),
+                                ),
                                 self.protocol_set.protocol_codec(&protocol)
                             );
 
@@ -520,9 +638,11 @@ impl WebSocketConnection {
                     }
                 }
                 protocol = self.protocol_set.next() => match protocol {
-                    Some(ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit }) => {
+                    Some(ProtocolCommand::OpenSubstream { protocol, fallback_names, substream_id, permit, .. }) => {
                         let control = self.control.clone();
                         let substream_open_timeout = self.substream_open_timeout;
+                        let peer = self.peer;
+                        let observer = self.protocol_set.negotiation_observer();
 
                         tracing::trace!(
                             target: LOG_TARGET,
@@ -539,7 +659,9 @@ impl WebSocketConnection {
                                     permit,
                                     substream_id,
                                     protocol.clone(),
-                                    fallback_names
+                                    fallback_names,
+                                    peer,
+                                    observer,
                                 ),
                             )
                             .await
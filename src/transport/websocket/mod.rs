@@ -24,13 +24,18 @@ use crate::{
     config::Role,
     error::{AddressError, Error, NegotiationError},
     transport::{
-        common::listener::{DialAddresses, GetSocketAddr, SocketListener, WebSocketAddress},
+        common::{
+            happy_eyeballs,
+            listener::{
+                DialAddresses, GetSocketAddr, ListenAddressChange, SocketListener, WebSocketAddress,
+            },
+        },
         manager::TransportHandle,
         websocket::{
             config::Config,
             connection::{NegotiatedConnection, WebSocketConnection},
         },
-        Transport, TransportBuilder, TransportEvent,
+        Muxer, SecurityProtocol, Transport, TransportBuilder, TransportEvent,
     },
     types::ConnectionId,
     DialError, PeerId,
@@ -134,6 +139,8 @@ impl WebSocketTransport {
         let connection_open_timeout = self.config.connection_open_timeout;
         let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
         let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        let handshake_timeout = self.config.handshake_timeout;
         let address = Multiaddr::empty()
             .with(Protocol::from(address.ip()))
             .with(Protocol::Tcp(address.port()))
@@ -149,6 +156,8 @@ impl WebSocketTransport {
                     yamux_config,
                     max_read_ahead_factor,
                     max_write_buffer_size,
+                    noise_rekey,
+                    handshake_timeout,
                 )
                 .await
                 .map_err(|error| (connection_id, error.into()))
@@ -169,6 +178,11 @@ impl WebSocketTransport {
         let dial_address = match protocol_stack.next().ok_or(AddressError::InvalidProtocol)? {
             Protocol::Ip4(address) => address.to_string(),
             Protocol::Ip6(address) => format!("[{address}]"),
+            // the zone only affects the socket address, the URL is only used for the handshake
+            Protocol::Ip6zone(_) => match protocol_stack.next() {
+                Some(Protocol::Ip6(address)) => format!("[{address}]"),
+                _ => return Err(AddressError::InvalidProtocol),
+            },
             Protocol::Dns(address) | Protocol::Dns4(address) | Protocol::Dns6(address) =>
                 address.to_string(),
 
@@ -296,11 +310,23 @@ impl TransportBuilder for WebSocketTransport {
             listen_addresses = ?config.listen_addresses,
             "start websocket transport",
         );
+        config.yamux_config.validate()?;
+
+        if !config.nodelay && context.has_latency_sensitive_protocol() {
+            tracing::debug!(target: LOG_TARGET, "latency-sensitive protocol, enable `TCP_NODELAY`");
+            config.nodelay = true;
+        }
+
         let (listener, listen_addresses, dial_addresses) = SocketListener::new::<WebSocketAddress>(
             std::mem::take(&mut config.listen_addresses),
+            config.reuse_address,
             config.reuse_port,
             config.nodelay,
         );
+        let listener = match config.interface_refresh_interval {
+            Some(interval) => listener.with_interface_refresh(interval),
+            None => listener,
+        };
 
         Ok((
             Self {
@@ -329,6 +355,8 @@ impl Transport for WebSocketTransport {
         let connection_open_timeout = self.config.connection_open_timeout;
         let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
         let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        let handshake_timeout = self.config.handshake_timeout;
         let dial_addresses = self.dial_addresses.clone();
         let nodelay = self.config.nodelay;
 
@@ -356,6 +384,8 @@ impl Transport for WebSocketTransport {
                 yamux_config,
                 max_read_ahead_factor,
                 max_write_buffer_size,
+                noise_rekey,
+                handshake_timeout,
             )
             .await
             .map_err(|error| (connection_id, error.into()))
@@ -437,46 +467,45 @@ impl Transport for WebSocketTransport {
         connection_id: ConnectionId,
         addresses: Vec<Multiaddr>,
     ) -> crate::Result<()> {
-        let num_addresses = addresses.len();
-        let mut futures: FuturesUnordered<_> = addresses
-            .into_iter()
-            .map(|address| {
-                let connection_open_timeout = self.config.connection_open_timeout;
-                let dial_addresses = self.dial_addresses.clone();
-                let nodelay = self.config.nodelay;
-
-                async move {
-                    WebSocketTransport::dial_peer(
-                        address.clone(),
-                        dial_addresses,
-                        connection_open_timeout,
-                        nodelay,
-                    )
-                    .await
-                    .map_err(|error| (address, error))
-                }
-            })
-            .collect();
+        let dial_addresses = self.dial_addresses.clone();
+        let connection_open_timeout = self.config.connection_open_timeout;
+        let nodelay = self.config.nodelay;
+        let happy_eyeballs_delay = self.config.happy_eyeballs_delay;
+        let max_concurrent_dials = self.config.max_concurrent_dials;
 
         self.pending_raw_connections.push(Box::pin(async move {
-            let mut errors = Vec::with_capacity(num_addresses);
-
-            while let Some(result) = futures.next().await {
-                match result {
-                    Ok((address, stream)) => return Ok((connection_id, address, stream)),
-                    Err(error) => {
-                        tracing::debug!(
-                            target: LOG_TARGET,
-                            ?connection_id,
-                            ?error,
-                            "failed to open connection",
-                        );
-                        errors.push(error)
+            happy_eyeballs::dial(
+                addresses,
+                happy_eyeballs_delay,
+                max_concurrent_dials,
+                |address| {
+                    let dial_addresses = dial_addresses.clone();
+
+                    async move {
+                        WebSocketTransport::dial_peer(
+                            address.clone(),
+                            dial_addresses,
+                            connection_open_timeout,
+                            nodelay,
+                        )
+                        .await
+                        .map_err(|error| {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                ?connection_id,
+                                ?address,
+                                ?error,
+                                "failed to open connection",
+                            );
+
+                            (address, error)
+                        })
                     }
-                }
-            }
-
-            Err((connection_id, errors))
+                },
+            )
+            .await
+            .map(|(address, stream)| (connection_id, address, stream))
+            .map_err(|errors| (connection_id, errors))
         }));
 
         Ok(())
@@ -495,6 +524,8 @@ impl Transport for WebSocketTransport {
         let yamux_config = self.config.yamux_config.clone();
         let max_read_ahead_factor = self.config.noise_read_ahead_frame_count;
         let max_write_buffer_size = self.config.noise_write_buffer_size;
+        let noise_rekey = self.config.noise_rekey;
+        let handshake_timeout = self.config.handshake_timeout;
         let connection_open_timeout = self.config.connection_open_timeout;
         let keypair = self.context.keypair.clone();
 
@@ -519,6 +550,8 @@ impl Transport for WebSocketTransport {
                     yamux_config,
                     max_read_ahead_factor,
                     max_write_buffer_size,
+                    noise_rekey,
+                    handshake_timeout,
                 )
                 .await
                 .map_err(|error| (connection_id, error.into()))
@@ -543,12 +576,25 @@ impl Stream for WebSocketTransport {
     type Item = TransportEvent;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Poll::Ready(change) = self.listener.poll_address_change(cx) {
+            return Poll::Ready(Some(match change {
+                ListenAddressChange::New(address) => TransportEvent::NewListenAddress { address },
+                ListenAddressChange::Expired(address) =>
+                    TransportEvent::ExpiredListenAddress { address },
+            }));
+        }
+
         if let Poll::Ready(Some(connection)) = self.listener.poll_next_unpin(cx) {
             return match connection {
                 Err(_) => Poll::Ready(None),
                 Ok((connection, address)) => {
                     let connection_id = self.context.next_connection_id();
 
+                    let remote_address = Multiaddr::empty()
+                        .with(Protocol::from(address.ip()))
+                        .with(Protocol::Tcp(address.port()))
+                        .with(Protocol::Ws(std::borrow::Cow::Borrowed("/")));
+
                     self.pending_inbound_connections.insert(
                         connection_id,
                         PendingInboundConnection {
@@ -559,6 +605,7 @@ impl Stream for WebSocketTransport {
 
                     Poll::Ready(Some(TransportEvent::PendingInboundConnection {
                         connection_id,
+                        address: remote_address,
                     }))
                 }
             };
@@ -604,6 +651,8 @@ impl Stream for WebSocketTransport {
                     return Poll::Ready(Some(TransportEvent::ConnectionEstablished {
                         peer,
                         endpoint,
+                        security: SecurityProtocol::Noise,
+                        muxer: Some(Muxer::Yamux),
                     }));
                 }
                 Err((connection_id, error)) => {
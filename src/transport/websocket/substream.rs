@@ -40,7 +40,7 @@ pub struct Substream {
     bandwidth_sink: BandwidthSink,
 
     /// Connection permit.
-    _permit: Permit,
+    permit: Permit,
 }
 
 impl Substream {
@@ -48,14 +48,21 @@ impl Substream {
     pub fn new(
         io: Compat<crate::yamux::Stream>,
         bandwidth_sink: BandwidthSink,
-        _permit: Permit,
+        permit: Permit,
     ) -> Self {
         Self {
             io,
             bandwidth_sink,
-            _permit,
+            permit,
         }
     }
+
+    /// Cork or uncork the substream.
+    ///
+    /// See [`crate::yamux::Stream::set_corked()`] for more details.
+    pub(crate) fn set_corked(&mut self, corked: bool) {
+        self.io.get_mut().set_corked(corked);
+    }
 }
 
 impl AsyncRead for Substream {
@@ -64,9 +71,14 @@ impl AsyncRead for Substream {
         cx: &mut Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
+        self.permit.poll_idle(cx);
+
         match futures::ready!(Pin::new(&mut self.io).poll_read(cx, buf)) {
             Err(error) => Poll::Ready(Err(error)),
             Ok(res) => {
+                if !buf.filled().is_empty() {
+                    self.permit.on_activity();
+                }
                 self.bandwidth_sink.increase_inbound(buf.filled().len());
                 Poll::Ready(Ok(res))
             }
@@ -80,9 +92,12 @@ impl AsyncWrite for Substream {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, io::Error>> {
+        self.permit.poll_idle(cx);
+
         match futures::ready!(Pin::new(&mut self.io).poll_write(cx, buf)) {
             Err(error) => Poll::Ready(Err(error)),
             Ok(nwritten) => {
+                self.permit.on_activity();
                 self.bandwidth_sink.increase_outbound(nwritten);
                 Poll::Ready(Ok(nwritten))
             }
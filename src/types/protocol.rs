@@ -23,6 +23,7 @@
 use std::{
     fmt::Display,
     hash::{Hash, Hasher},
+    ops::RangeInclusive,
     sync::Arc,
 };
 
@@ -85,6 +86,66 @@ impl PartialEq for ProtocolName {
 
 impl Eq for ProtocolName {}
 
+/// Protocol which supports a contiguous range of versions.
+///
+/// Version `v` of the protocol is advertised as `<prefix>/<v>`. The names are proposed highest
+/// version first, which makes `multistream-select` settle on the highest version that both
+/// peers support without having to maintain the list of fallback names by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionedProtocol {
+    /// Protocol name prefix, e.g., `/sync/block-announces`.
+    prefix: String,
+
+    /// Supported versions.
+    versions: RangeInclusive<u32>,
+}
+
+impl VersionedProtocol {
+    /// Create new [`VersionedProtocol`].
+    ///
+    /// Panics if `versions` is empty.
+    pub fn new(prefix: impl Into<String>, versions: RangeInclusive<u32>) -> Self {
+        assert!(
+            !versions.is_empty(),
+            "at least one protocol version must be supported"
+        );
+
+        Self {
+            prefix: prefix.into().trim_end_matches('/').to_string(),
+            versions,
+        }
+    }
+
+    /// Get protocol name of `version`.
+    pub fn name(&self, version: u32) -> ProtocolName {
+        ProtocolName::from(format!("{}/{version}", self.prefix))
+    }
+
+    /// Get the protocol name of the highest supported version and fallback names for the older
+    /// versions, ordered from newest to oldest.
+    pub fn names(&self) -> (ProtocolName, Vec<ProtocolName>) {
+        let mut versions = self.versions.clone().rev();
+        let highest = versions.next().expect("range to be non-empty");
+
+        (
+            self.name(highest),
+            versions.map(|version| self.name(version)).collect(),
+        )
+    }
+
+    /// Get the version of the negotiated `protocol`.
+    ///
+    /// Returns `None` if `protocol` is not one of the supported versions.
+    pub fn version(&self, protocol: &ProtocolName) -> Option<u32> {
+        protocol
+            .strip_prefix(self.prefix.as_str())?
+            .strip_prefix('/')?
+            .parse::<u32>()
+            .ok()
+            .filter(|version| self.versions.contains(version))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,4 +157,31 @@ mod tests {
 
         assert_eq!(protocol1, protocol2);
     }
+
+    #[test]
+    fn versioned_protocol_names() {
+        let protocol = VersionedProtocol::new("/proto/", 1..=3);
+        let (name, fallback_names) = protocol.names();
+
+        assert_eq!(name, ProtocolName::from("/proto/3"));
+        assert_eq!(
+            fallback_names,
+            vec![
+                ProtocolName::from("/proto/2"),
+                ProtocolName::from("/proto/1")
+            ]
+        );
+
+        assert_eq!(protocol.version(&ProtocolName::from("/proto/2")), Some(2));
+        assert_eq!(protocol.version(&ProtocolName::from("/proto/4")), None);
+        assert_eq!(protocol.version(&ProtocolName::from("/proto/x")), None);
+        assert_eq!(protocol.version(&ProtocolName::from("/other/2")), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_version_range() {
+        #[allow(clippy::reversed_empty_ranges)]
+        let _ = VersionedProtocol::new("/proto", 3..=1);
+    }
 }
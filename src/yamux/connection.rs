@@ -82,6 +82,7 @@
 
 mod cleanup;
 mod closing;
+mod rtt;
 mod stream;
 
 use crate::yamux::{
@@ -105,8 +106,9 @@ use futures::{
 };
 use nohash_hasher::IntMap;
 use parking_lot::Mutex;
+use rtt::Rtt;
 use std::{
-    collections::VecDeque,
+    collections::{BTreeMap, VecDeque},
     fmt,
     sync::Arc,
     task::{Context, Poll, Waker},
@@ -367,7 +369,12 @@ struct Active<T> {
     no_streams_waker: Option<Waker>,
 
     pending_frames: VecDeque<Frame<()>>,
+    /// Frames queued by the streams, by stream priority. Sent after `pending_frames`.
+    pending_stream_frames: BTreeMap<i8, StreamQueues>,
     new_outbound_stream_waker: Option<Waker>,
+
+    /// Round-trip time of the connection, measured only if receive window auto-tuning is enabled.
+    rtt: Rtt,
 }
 
 /// `Stream` to `Connection` commands.
@@ -379,6 +386,51 @@ pub(crate) enum StreamCommand {
     CloseStream { ack: bool },
 }
 
+/// Frames queued by the streams of one priority level.
+///
+/// The streams are served round-robin, one frame at a time, so a stream with a large write
+/// queued can't hold back the frames of the other streams until all of its data has been sent.
+#[derive(Debug, Default)]
+struct StreamQueues {
+    /// Streams with queued frames, in the order they're served.
+    ready: VecDeque<StreamId>,
+
+    /// Queued frames of each stream.
+    frames: IntMap<StreamId, VecDeque<Frame<()>>>,
+}
+
+impl StreamQueues {
+    /// Queue `frame` of stream `id`.
+    fn push(&mut self, id: StreamId, frame: Frame<()>) {
+        let frames = self.frames.entry(id).or_default();
+
+        if frames.is_empty() {
+            self.ready.push_back(id);
+        }
+        frames.push_back(frame);
+    }
+
+    /// Get the next frame of the stream whose turn it is.
+    fn pop(&mut self) -> Option<Frame<()>> {
+        let id = self.ready.pop_front()?;
+        let frames = self.frames.get_mut(&id)?;
+        let frame = frames.pop_front();
+
+        if frames.is_empty() {
+            self.frames.remove(&id);
+        } else {
+            self.ready.push_back(id);
+        }
+
+        frame
+    }
+
+    /// Check if there are any frames queued.
+    fn is_empty(&self) -> bool {
+        self.ready.is_empty()
+    }
+}
+
 /// Possible actions as a result of incoming frame handling.
 #[derive(Debug)]
 enum Action {
@@ -424,7 +476,9 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
     fn new(socket: T, cfg: Config, mode: Mode) -> Self {
         let id = Id::random();
         tracing::debug!(target: LOG_TARGET, "new connection: {} ({:?})", id, mode);
-        let socket = frame::Io::new(id, socket, cfg.max_buffer_size).fuse();
+        let socket = frame::Io::new(id, socket, cfg.max_buffer_size)
+            .with_write_coalescing(cfg.write_coalescing)
+            .fuse();
         Active {
             id,
             mode,
@@ -438,12 +492,20 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
                 Mode::Server => 2,
             },
             pending_frames: VecDeque::default(),
+            pending_stream_frames: BTreeMap::new(),
             new_outbound_stream_waker: None,
+            rtt: Rtt::new(),
         }
     }
 
     /// Gracefully close the connection to the remote.
-    fn close(self) -> Closing<T> {
+    fn close(mut self) -> Closing<T> {
+        while let Some((_, mut queues)) = self.pending_stream_frames.pop_last() {
+            while let Some(frame) = queues.pop() {
+                self.pending_frames.push_back(frame);
+            }
+        }
+
         Closing::new(self.stream_receivers, self.pending_frames, self.socket)
     }
 
@@ -457,9 +519,36 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
     }
 
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<Stream>> {
+        if self.config.max_receive_window.is_some() {
+            if let Some(frame) = self.rtt.next_ping() {
+                self.pending_frames.push_back(frame.into());
+            }
+        }
+
         loop {
+            // Queue everything the streams have ready before sending anything so that the frames
+            // of higher priority streams are sent first.
+            loop {
+                match self.stream_receivers.poll_next_unpin(cx) {
+                    Poll::Ready(Some((_, Some(StreamCommand::SendFrame(frame))))) => {
+                        self.on_send_frame(frame);
+                    }
+                    Poll::Ready(Some((id, Some(StreamCommand::CloseStream { ack })))) => {
+                        self.on_close_stream(id, ack);
+                    }
+                    Poll::Ready(Some((id, None))) => {
+                        self.on_drop_stream(id);
+                    }
+                    Poll::Ready(None) => {
+                        self.no_streams_waker = Some(cx.waker().clone());
+                        break;
+                    }
+                    Poll::Pending => break,
+                }
+            }
+
             if self.socket.poll_ready_unpin(cx).is_ready() {
-                if let Some(frame) = self.pending_frames.pop_front() {
+                if let Some(frame) = self.next_pending_frame() {
                     self.socket.start_send_unpin(frame)?;
                     continue;
                 }
@@ -470,25 +559,6 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
                 Poll::Pending => {}
             }
 
-            match self.stream_receivers.poll_next_unpin(cx) {
-                Poll::Ready(Some((_, Some(StreamCommand::SendFrame(frame))))) => {
-                    self.on_send_frame(frame);
-                    continue;
-                }
-                Poll::Ready(Some((id, Some(StreamCommand::CloseStream { ack })))) => {
-                    self.on_close_stream(id, ack);
-                    continue;
-                }
-                Poll::Ready(Some((id, None))) => {
-                    self.on_drop_stream(id);
-                    continue;
-                }
-                Poll::Ready(None) => {
-                    self.no_streams_waker = Some(cx.waker().clone());
-                }
-                Poll::Pending => {}
-            }
-
             match self.socket.poll_next_unpin(cx) {
                 Poll::Ready(Some(frame)) => {
                     if let Some(stream) = self.on_frame(frame?)? {
@@ -543,26 +613,63 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
         Poll::Ready(Ok(stream))
     }
 
-    fn on_send_frame(&mut self, frame: Frame<Either<Data, WindowUpdate>>) {
+    /// Get the next frame to send.
+    ///
+    /// Frames of the connection itself are sent first, followed by the frames of the streams in
+    /// the order of their priority. Streams of the same priority take turns.
+    fn next_pending_frame(&mut self) -> Option<Frame<()>> {
+        if let Some(frame) = self.pending_frames.pop_front() {
+            return Some(frame);
+        }
+
+        let mut entry = self.pending_stream_frames.last_entry()?;
+        let frame = entry.get_mut().pop();
+
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+
+        frame
+    }
+
+    /// Queue `frame` of stream `id`.
+    ///
+    /// All frames of a stream are queued with the same priority so they're sent in order.
+    fn queue_stream_frame(&mut self, id: StreamId, priority: i8, frame: Frame<()>) {
         tracing::trace!(target: LOG_TARGET,
-            "{}/{}: sending: {}",
+            "{}/{}: sending: {} (priority {})",
             self.id,
-            frame.header().stream_id(),
-            frame.header()
+            id,
+            frame.header(),
+            priority,
         );
-        self.pending_frames.push_back(frame.into());
+        self.pending_stream_frames.entry(priority).or_default().push(id, frame);
+    }
+
+    /// Get the priority of stream `id`.
+    fn stream_priority(&self, id: StreamId) -> i8 {
+        self.streams.get(&id).map_or(0, |shared| shared.lock().priority)
+    }
+
+    fn on_send_frame(&mut self, frame: Frame<Either<Data, WindowUpdate>>) {
+        let id = frame.header().stream_id();
+        let priority = self.stream_priority(id);
+
+        self.queue_stream_frame(id, priority, frame.into());
     }
 
     fn on_close_stream(&mut self, id: StreamId, ack: bool) {
         tracing::trace!(target: LOG_TARGET, "{}/{}: sending close", self.id, id);
-        self.pending_frames.push_back(Frame::close_stream(id, ack).into());
+        let priority = self.stream_priority(id);
+
+        self.queue_stream_frame(id, priority, Frame::close_stream(id, ack).into());
     }
 
     fn on_drop_stream(&mut self, stream_id: StreamId) {
         let s = self.streams.remove(&stream_id).expect("stream not found");
 
         tracing::trace!(target: LOG_TARGET, "{}: removing dropped stream {}", self.id, stream_id);
-        let frame = {
+        let (frame, priority) = {
             let mut shared = s.lock();
             let frame = match shared.update_state(self.id, stream_id, State::Closed) {
                 // The stream was dropped without calling `poll_close`.
@@ -612,11 +719,11 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
             if let Some(w) = shared.writer.take() {
                 w.wake()
             }
-            frame
+            (frame, shared.priority)
         };
         if let Some(f) = frame {
-            tracing::trace!(target: LOG_TARGET, "{}/{}: sending: {}", self.id, stream_id, f.header());
-            self.pending_frames.push_back(f.into());
+            // queued behind any data the stream still has queued
+            self.queue_stream_frame(stream_id, priority, f.into());
         }
     }
 
@@ -876,7 +983,10 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
     fn on_ping(&mut self, frame: &Frame<Ping>) -> Action {
         let stream_id = frame.header().stream_id();
         if frame.header().flags().contains(header::ACK) {
-            // pong
+            if stream_id == CONNECTION_ID {
+                self.rtt.handle_pong(frame.header().nonce());
+            }
+
             return Action::None;
         }
         if stream_id == CONNECTION_ID || self.streams.contains_key(&stream_id) {
@@ -909,7 +1019,7 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
             waker.wake();
         }
 
-        Stream::new_inbound(id, self.id, config, credit, sender)
+        Stream::new_inbound(id, self.id, config, credit, sender, self.rtt.clone())
     }
 
     fn make_new_outbound_stream(&mut self, id: StreamId, window: u32) -> Stream {
@@ -921,7 +1031,7 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Active<T> {
             waker.wake();
         }
 
-        Stream::new_outbound(id, self.id, config, window, sender)
+        Stream::new_outbound(id, self.id, config, window, sender, self.rtt.clone())
     }
 
     fn next_stream_id(&mut self) -> Result<StreamId> {
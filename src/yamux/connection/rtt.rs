@@ -0,0 +1,114 @@
+//! Connection round-trip time measurement used for receive window auto-tuning.
+
+use crate::yamux::frame::{
+    header::{Header, Ping},
+    Frame,
+};
+
+use parking_lot::Mutex;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::yamux";
+
+/// How often the round-trip time is re-measured.
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Round-trip time measurement state.
+#[derive(Debug)]
+enum State {
+    /// No ping has been sent yet.
+    Initial,
+
+    /// Ping has been sent and the connection is waiting for the pong.
+    AwaitingPong {
+        /// When was the ping sent.
+        sent_at: Instant,
+
+        /// Nonce of the ping.
+        nonce: u32,
+    },
+
+    /// Round-trip time has been measured and the next ping is sent after `next`.
+    Waiting {
+        /// When should the next ping be sent.
+        next: Instant,
+    },
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// Measurement state.
+    state: State,
+
+    /// Latest round-trip time, if measured.
+    rtt: Option<Duration>,
+}
+
+/// Round-trip time of the connection, shared between the connection and its streams.
+#[derive(Debug, Clone)]
+pub(crate) struct Rtt(Arc<Mutex<Inner>>);
+
+impl Rtt {
+    /// Create new [`Rtt`].
+    pub(crate) fn new() -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            state: State::Initial,
+            rtt: None,
+        })))
+    }
+
+    /// Get ping frame which should be sent to the remote, if a new measurement is due.
+    pub(crate) fn next_ping(&mut self) -> Option<Frame<Ping>> {
+        let mut inner = self.0.lock();
+
+        match inner.state {
+            State::Initial => {}
+            State::AwaitingPong { .. } => return None,
+            State::Waiting { next } if next > Instant::now() => return None,
+            State::Waiting { .. } => {}
+        }
+
+        let nonce = rand::random();
+        inner.state = State::AwaitingPong {
+            sent_at: Instant::now(),
+            nonce,
+        };
+
+        tracing::trace!(target: LOG_TARGET, ?nonce, "send ping to measure round-trip time");
+
+        Some(Frame::new(Header::ping(nonce)))
+    }
+
+    /// Handle pong received from the remote.
+    pub(crate) fn handle_pong(&mut self, received: u32) {
+        let mut inner = self.0.lock();
+
+        let State::AwaitingPong { sent_at, nonce } = inner.state else {
+            tracing::debug!(target: LOG_TARGET, ?received, "unexpected pong");
+            return;
+        };
+
+        if nonce != received {
+            tracing::debug!(target: LOG_TARGET, ?nonce, ?received, "pong nonce mismatch");
+            return;
+        }
+
+        let rtt = sent_at.elapsed();
+        tracing::trace!(target: LOG_TARGET, ?rtt, "round-trip time measured");
+
+        inner.rtt = Some(rtt);
+        inner.state = State::Waiting {
+            next: Instant::now() + PING_INTERVAL,
+        };
+    }
+
+    /// Get the latest round-trip time, if measured.
+    pub(crate) fn get(&self) -> Option<Duration> {
+        self.0.lock().rtt
+    }
+}
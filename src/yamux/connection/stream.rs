@@ -10,7 +10,7 @@
 
 use crate::yamux::{
     chunks::Chunks,
-    connection::{self, StreamCommand},
+    connection::{self, rtt::Rtt, StreamCommand},
     frame::{
         header::{Data, Header, StreamId, WindowUpdate, ACK},
         Frame,
@@ -30,6 +30,7 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::{Context, Poll, Waker},
+    time::Instant,
 };
 
 /// Logging target for the file.
@@ -96,6 +97,10 @@ pub struct Stream {
     sender: mpsc::Sender<StreamCommand>,
     flag: Flag,
     shared: Arc<Mutex<Shared>>,
+    /// Is the stream corked.
+    corked: bool,
+    /// Data written while the stream is corked, sent once the stream is uncorked.
+    cork_buffer: Vec<u8>,
 }
 
 impl fmt::Debug for Stream {
@@ -120,6 +125,7 @@ impl Stream {
         config: Arc<Config>,
         credit: u32,
         sender: mpsc::Sender<StreamCommand>,
+        rtt: Rtt,
     ) -> Self {
         Self {
             id,
@@ -127,7 +133,9 @@ impl Stream {
             config: config.clone(),
             sender,
             flag: Flag::None,
-            shared: Arc::new(Mutex::new(Shared::new(DEFAULT_CREDIT, credit, config))),
+            shared: Arc::new(Mutex::new(Shared::new(DEFAULT_CREDIT, credit, config, rtt))),
+            corked: false,
+            cork_buffer: Vec::new(),
         }
     }
 
@@ -137,6 +145,7 @@ impl Stream {
         config: Arc<Config>,
         window: u32,
         sender: mpsc::Sender<StreamCommand>,
+        rtt: Rtt,
     ) -> Self {
         Self {
             id,
@@ -144,7 +153,9 @@ impl Stream {
             config: config.clone(),
             sender,
             flag: Flag::None,
-            shared: Arc::new(Mutex::new(Shared::new(window, DEFAULT_CREDIT, config))),
+            shared: Arc::new(Mutex::new(Shared::new(window, DEFAULT_CREDIT, config, rtt))),
+            corked: false,
+            cork_buffer: Vec::new(),
         }
     }
 
@@ -166,6 +177,24 @@ impl Stream {
         self.shared().is_pending_ack()
     }
 
+    /// Cork or uncork the stream.
+    ///
+    /// While the stream is corked, written data is buffered instead of being sent to the remote
+    /// as a separate frame for each write. The buffered data is sent in as few frames as possible
+    /// when the stream is uncorked and then flushed, when the buffer grows past the split send
+    /// size or when the stream is closed.
+    pub fn set_corked(&mut self, corked: bool) {
+        self.corked = corked;
+    }
+
+    /// Set the priority of the stream.
+    ///
+    /// When the connection has frames of several streams queued, the frames of the streams with
+    /// the highest priority are sent first. Streams have priority `0` by default.
+    pub fn set_priority(&mut self, priority: i8) {
+        self.shared().priority = priority;
+    }
+
     /// Set the flag that should be set on the next outbound frame header.
     pub(crate) fn set_flag(&mut self, flag: Flag) {
         self.flag = flag
@@ -224,6 +253,59 @@ impl Stream {
 
         Poll::Ready(Ok(()))
     }
+
+    /// Send `buf` to the remote peer as a data frame.
+    fn poll_write_frame(&mut self, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        ready!(self.sender.poll_ready(cx).map_err(|_| self.write_zero_err())?);
+        let body = {
+            let mut shared = self.shared();
+            if !shared.state().can_write() {
+                tracing::debug!(target: LOG_TARGET,"{}/{}: can no longer write", self.conn, self.id);
+                return Poll::Ready(Err(self.write_zero_err()));
+            }
+            if shared.credit == 0 {
+                tracing::trace!(target: LOG_TARGET,"{}/{}: no more credit left", self.conn, self.id);
+                shared.writer = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            let k = std::cmp::min(shared.credit as usize, buf.len());
+            let k = std::cmp::min(k, self.config.split_send_size);
+            shared.credit = shared.credit.saturating_sub(k as u32);
+            Vec::from(&buf[..k])
+        };
+        let n = body.len();
+        let mut frame = Frame::data(self.id, body).expect("body <= u32::MAX").left();
+        self.add_flag(frame.header_mut());
+        tracing::trace!(target: LOG_TARGET,"{}/{}: write {} bytes", self.conn, self.id, n);
+
+        // technically, the frame hasn't been sent yet on the wire but from the perspective of this
+        // data structure, we've queued the frame for sending We are tracking this
+        // information: a) to be consistent with outbound streams
+        // b) to correctly test our behaviour around timing of when ACKs are sent. See
+        // `ack_timing.rs` test.
+        if frame.header().flags().contains(ACK) {
+            self.shared()
+                .update_state(self.conn, self.id, State::Open { acknowledged: true });
+        }
+
+        let cmd = StreamCommand::SendFrame(frame);
+        self.sender.start_send(cmd).map_err(|_| self.write_zero_err())?;
+        Poll::Ready(Ok(n))
+    }
+
+    /// Send data buffered while the stream was corked.
+    fn poll_send_corked(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while !self.cork_buffer.is_empty() {
+            let buffer = std::mem::take(&mut self.cork_buffer);
+            let result = self.poll_write_frame(cx, &buffer);
+            self.cork_buffer = buffer;
+
+            let nwritten = ready!(result)?;
+            self.cork_buffer.drain(..nwritten);
+        }
+
+        Poll::Ready(Ok(()))
+    }
 }
 
 /// Byte data produced by the [`futures::stream::Stream`] impl of [`Stream`].
@@ -347,44 +429,29 @@ impl AsyncWrite for Stream {
         cx: &mut Context,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        ready!(self.sender.poll_ready(cx).map_err(|_| self.write_zero_err())?);
-        let body = {
-            let mut shared = self.shared();
-            if !shared.state().can_write() {
-                tracing::debug!(target: LOG_TARGET,"{}/{}: can no longer write", self.conn, self.id);
-                return Poll::Ready(Err(self.write_zero_err()));
+        if self.corked {
+            if self.cork_buffer.len() >= self.config.split_send_size {
+                ready!(self.poll_send_corked(cx))?;
             }
-            if shared.credit == 0 {
-                tracing::trace!(target: LOG_TARGET,"{}/{}: no more credit left", self.conn, self.id);
-                shared.writer = Some(cx.waker().clone());
-                return Poll::Pending;
-            }
-            let k = std::cmp::min(shared.credit as usize, buf.len());
-            let k = std::cmp::min(k, self.config.split_send_size);
-            shared.credit = shared.credit.saturating_sub(k as u32);
-            Vec::from(&buf[..k])
-        };
-        let n = body.len();
-        let mut frame = Frame::data(self.id, body).expect("body <= u32::MAX").left();
-        self.add_flag(frame.header_mut());
-        tracing::trace!(target: LOG_TARGET,"{}/{}: write {} bytes", self.conn, self.id, n);
 
-        // technically, the frame hasn't been sent yet on the wire but from the perspective of this
-        // data structure, we've queued the frame for sending We are tracking this
-        // information: a) to be consistent with outbound streams
-        // b) to correctly test our behaviour around timing of when ACKs are sent. See
-        // `ack_timing.rs` test.
-        if frame.header().flags().contains(ACK) {
-            self.shared()
-                .update_state(self.conn, self.id, State::Open { acknowledged: true });
+            let k = std::cmp::min(
+                self.config.split_send_size - self.cork_buffer.len(),
+                buf.len(),
+            );
+            self.cork_buffer.extend_from_slice(&buf[..k]);
+
+            return Poll::Ready(Ok(k));
         }
 
-        let cmd = StreamCommand::SendFrame(frame);
-        self.sender.start_send(cmd).map_err(|_| self.write_zero_err())?;
-        Poll::Ready(Ok(n))
+        ready!(self.poll_send_corked(cx))?;
+        self.poll_write_frame(cx, buf)
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        if !self.corked {
+            ready!(self.poll_send_corked(cx))?;
+        }
+
         self.sender.poll_flush_unpin(cx).map_err(|_| self.write_zero_err())
     }
 
@@ -392,6 +459,7 @@ impl AsyncWrite for Stream {
         if self.is_closed() {
             return Poll::Ready(Ok(()));
         }
+        ready!(self.poll_send_corked(cx))?;
         ready!(self.sender.poll_ready(cx).map_err(|_| self.write_zero_err())?);
         let ack = if self.flag == Flag::Ack {
             self.flag = Flag::None;
@@ -415,11 +483,19 @@ pub(crate) struct Shared {
     pub(crate) buffer: Chunks,
     pub(crate) reader: Option<Waker>,
     pub(crate) writer: Option<Waker>,
+    /// Current receive window of the stream, grows up to `Config::max_receive_window`.
+    receive_window: u32,
+    /// When was the last window update granted.
+    last_window_update: Instant,
+    /// Round-trip time of the connection.
+    rtt: Rtt,
+    /// Priority of the stream's outbound frames.
+    pub(crate) priority: i8,
     config: Arc<Config>,
 }
 
 impl Shared {
-    fn new(window: u32, credit: u32, config: Arc<Config>) -> Self {
+    fn new(window: u32, credit: u32, config: Arc<Config>, rtt: Rtt) -> Self {
         Shared {
             state: State::Open {
                 acknowledged: false,
@@ -429,6 +505,10 @@ impl Shared {
             buffer: Chunks::new(),
             reader: None,
             writer: None,
+            receive_window: config.receive_window,
+            last_window_update: Instant::now(),
+            rtt,
+            priority: 0,
             config,
         }
     }
@@ -485,31 +565,59 @@ impl Shared {
             return None;
         }
 
-        let new_credit = match self.config.window_update_mode {
+        let mut new_credit = match self.config.window_update_mode {
             WindowUpdateMode::OnReceive => {
-                debug_assert!(self.config.receive_window >= self.window);
+                debug_assert!(self.receive_window >= self.window);
 
-                self.config.receive_window.saturating_sub(self.window)
+                self.receive_window.saturating_sub(self.window)
             }
             WindowUpdateMode::OnRead => {
-                debug_assert!(self.config.receive_window >= self.window);
-                let bytes_received = self.config.receive_window.saturating_sub(self.window);
+                debug_assert!(self.receive_window >= self.window);
+                let bytes_received = self.receive_window.saturating_sub(self.window);
                 let buffer_len: u32 = self.buffer.len().try_into().unwrap_or(u32::MAX);
 
                 bytes_received.saturating_sub(buffer_len)
             }
         };
 
-        // Send WindowUpdate message when half or more of the configured receive
+        // Send WindowUpdate message when half or more of the current receive
         // window can be granted as additional credit to the sender.
         //
         // See https://github.com/paritytech/yamux/issues/100 for a detailed
         // discussion.
-        if new_credit >= self.config.receive_window / 2 {
-            Some(new_credit)
-        } else {
-            None
+        if new_credit < self.receive_window / 2 {
+            return None;
         }
+
+        // If the remote used up the window within a few round-trips, the window is what limits
+        // the throughput of the stream so grow it, bounded by the configured maximum and by the
+        // buffer of the stream, which must be able to hold a full window.
+        if let (Some(max_receive_window), Some(rtt)) =
+            (self.config.max_receive_window, self.rtt.get())
+        {
+            let max_receive_window = max_receive_window
+                .min(u32::try_from(self.config.max_buffer_size).unwrap_or(u32::MAX));
+
+            if self.last_window_update.elapsed() < rtt * 4
+                && self.receive_window < max_receive_window
+            {
+                let previous = self.receive_window;
+                self.receive_window = previous.saturating_mul(2).min(max_receive_window);
+                new_credit += self.receive_window - previous;
+
+                tracing::trace!(
+                    target: LOG_TARGET,
+                    ?rtt,
+                    previous,
+                    receive_window = self.receive_window,
+                    "receive window grown",
+                );
+            }
+        }
+
+        self.last_window_update = Instant::now();
+
+        Some(new_credit)
     }
 
     /// Whether we are still waiting for the remote to acknowledge this stream.
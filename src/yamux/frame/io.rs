@@ -31,6 +31,12 @@ pub(crate) struct Io<T> {
     read_state: ReadState,
     write_state: WriteState,
     max_body_len: usize,
+    /// Max. number of bytes coalesced into one write, if write coalescing is enabled.
+    max_coalesced_len: Option<usize>,
+    /// Frames coalesced into the next write.
+    coalesced: Vec<u8>,
+    /// Number of bytes of `coalesced` already written.
+    coalesced_offset: usize,
 }
 
 impl<T: AsyncRead + AsyncWrite + Unpin> Io<T> {
@@ -41,8 +47,50 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Io<T> {
             read_state: ReadState::Init,
             write_state: WriteState::Init,
             max_body_len: max_frame_body_len,
+            max_coalesced_len: None,
+            coalesced: Vec::new(),
+            coalesced_offset: 0,
         }
     }
+
+    /// Coalesce the frames sent between flushes into writes of up to `max_len` bytes.
+    ///
+    /// Frames are buffered instead of being written to the socket one by one and the buffer is
+    /// written when the sink is flushed or once it has grown to `max_len` bytes. Frames larger
+    /// than `max_len` are written as-is if nothing is buffered.
+    pub(crate) fn with_write_coalescing(mut self, max_len: Option<usize>) -> Self {
+        self.max_coalesced_len = max_len;
+        self
+    }
+
+    /// Write the coalesced frames to the socket.
+    fn poll_write_coalesced(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.coalesced.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+
+        while self.coalesced_offset < self.coalesced.len() {
+            let pending = &self.coalesced[self.coalesced_offset..];
+
+            match ready!(Pin::new(&mut self.io).poll_write(cx, pending)) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) => self.coalesced_offset += n,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            "{}: coalesced write of {} bytes",
+            self.id,
+            self.coalesced.len()
+        );
+
+        self.coalesced.clear();
+        self.coalesced_offset = 0;
+
+        Poll::Ready(Ok(()))
+    }
 }
 
 /// The stages of writing a new `Frame`.
@@ -86,7 +134,13 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Sink<Frame<()>> for Io<T> {
         loop {
             tracing::trace!(target: LOG_TARGET, "{}: write: {:?}", this.id, this.write_state);
             match &mut this.write_state {
-                WriteState::Init => return Poll::Ready(Ok(())),
+                WriteState::Init => {
+                    if this.max_coalesced_len.is_some_and(|max| this.coalesced.len() >= max) {
+                        ready!(this.poll_write_coalesced(cx))?;
+                    }
+
+                    return Poll::Ready(Ok(()));
+                }
                 WriteState::Header {
                     header,
                     buffer,
@@ -130,9 +184,19 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Sink<Frame<()>> for Io<T> {
     }
 
     fn start_send(self: Pin<&mut Self>, f: Frame<()>) -> Result<(), Self::Error> {
+        let this = self.get_mut();
         let header = header::encode(&f.header);
         let buffer = f.body;
-        self.get_mut().write_state = WriteState::Header {
+
+        if let Some(max) = this.max_coalesced_len {
+            if !this.coalesced.is_empty() || header.len() + buffer.len() < max {
+                this.coalesced.extend_from_slice(&header);
+                this.coalesced.extend_from_slice(&buffer);
+                return Ok(());
+            }
+        }
+
+        this.write_state = WriteState::Header {
             header,
             buffer,
             offset: 0,
@@ -143,12 +207,14 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Sink<Frame<()>> for Io<T> {
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         let this = Pin::into_inner(self);
         ready!(this.poll_ready_unpin(cx))?;
+        ready!(this.poll_write_coalesced(cx))?;
         Pin::new(&mut this.io).poll_flush(cx)
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         let this = Pin::into_inner(self);
         ready!(this.poll_ready_unpin(cx))?;
+        ready!(this.poll_write_coalesced(cx))?;
         Pin::new(&mut this.io).poll_close(cx)
     }
 }
@@ -358,6 +424,84 @@ mod tests {
         }
     }
 
+    /// Socket which records each write it receives.
+    #[derive(Debug, Default)]
+    struct RecordingSocket {
+        writes: Vec<Vec<u8>>,
+    }
+
+    impl AsyncRead for RecordingSocket {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            _: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+    }
+
+    impl AsyncWrite for RecordingSocket {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.writes.push(buf.to_vec());
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn data_frame(byte: u8, len: usize) -> Frame<()> {
+        Frame::data(header::StreamId::new(1), vec![byte; len]).unwrap().into()
+    }
+
+    #[test]
+    fn frames_coalesced_into_single_write() {
+        futures::executor::block_on(async move {
+            let id = crate::yamux::connection::Id::random();
+            let mut io =
+                Io::new(id, RecordingSocket::default(), 1024).with_write_coalescing(Some(1024));
+
+            for byte in 0..10u8 {
+                io.feed(data_frame(byte, 10)).await.unwrap();
+            }
+            assert!(io.io.writes.is_empty());
+
+            // all frames queued before the flush are written at once
+            io.flush().await.unwrap();
+            assert_eq!(io.io.writes.len(), 1);
+            assert_eq!(io.io.writes[0].len(), 10 * (header::HEADER_SIZE + 10));
+
+            // frames larger than the buffer are written as-is
+            io.send(data_frame(0xff, 2048)).await.unwrap();
+            assert_eq!(io.io.writes.len(), 3);
+        })
+    }
+
+    #[test]
+    fn frames_written_separately_without_coalescing() {
+        futures::executor::block_on(async move {
+            let id = crate::yamux::connection::Id::random();
+            let mut io = Io::new(id, RecordingSocket::default(), 1024);
+
+            for byte in 0..10u8 {
+                io.feed(data_frame(byte, 10)).await.unwrap();
+            }
+            io.flush().await.unwrap();
+
+            // header and body of each frame are written separately
+            assert_eq!(io.io.writes.len(), 20);
+        })
+    }
+
     #[test]
     fn encode_decode_identity() {
         fn property(f: Frame<()>) -> bool {
@@ -43,6 +43,9 @@ pub use crate::yamux::{
 
 pub const DEFAULT_CREDIT: u32 = 256 * 1024; // as per yamux specification
 
+/// Logging target for the file.
+const LOG_TARGET: &str = "litep2p::yamux::config";
+
 pub type Result<T> = std::result::Result<T, ConnectionError>;
 
 /// The maximum number of streams we will open without an acknowledgement from the other peer.
@@ -96,30 +99,36 @@ pub enum WindowUpdateMode {
 /// The default configuration values are as follows:
 ///
 /// - receive window = 256 KiB
+/// - max. receive window = none (auto-tuning disabled)
 /// - max. buffer size (per stream) = 1 MiB
 /// - max. number of streams = 8192
 /// - window update mode = on read
 /// - read after close = true
 /// - split send size = 16 KiB
+/// - write coalescing = none (disabled)
 #[derive(Debug, Clone)]
 pub struct Config {
     receive_window: u32,
+    max_receive_window: Option<u32>,
     max_buffer_size: usize,
     max_num_streams: usize,
     window_update_mode: WindowUpdateMode,
     read_after_close: bool,
     split_send_size: usize,
+    write_coalescing: Option<usize>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             receive_window: DEFAULT_CREDIT,
+            max_receive_window: None,
             max_buffer_size: 1024 * 1024,
             max_num_streams: 8192,
             window_update_mode: WindowUpdateMode::OnRead,
             read_after_close: true,
             split_send_size: DEFAULT_SPLIT_SEND_SIZE,
+            write_coalescing: None,
         }
     }
 }
@@ -136,6 +145,24 @@ impl Config {
         self
     }
 
+    /// Enable receive window auto-tuning with the given upper bound.
+    ///
+    /// With auto-tuning enabled, the receive window set with [`Config::set_receive_window`]
+    /// is the initial window of each stream. The window of a stream is doubled, up to `n`,
+    /// whenever the remote consumes it in less than four round-trip times, letting streams on
+    /// high-latency links use the available bandwidth without allocating large windows
+    /// for every stream up front.
+    ///
+    /// `n` must not be smaller than the receive window or larger than the max. buffer size set
+    /// with [`Config::set_max_buffer_size`], as the buffer of a stream must be able to hold a full
+    /// window. Transports refuse configurations which violate either bound.
+    ///
+    /// `None` disables auto-tuning and keeps the receive window fixed.
+    pub fn set_max_receive_window(&mut self, n: Option<u32>) -> &mut Self {
+        self.max_receive_window = n;
+        self
+    }
+
     /// Set the max. buffer size per stream.
     pub fn set_max_buffer_size(&mut self, n: usize) -> &mut Self {
         self.max_buffer_size = n;
@@ -167,6 +194,90 @@ impl Config {
         self.split_send_size = n;
         self
     }
+
+    /// Coalesce the frames the connection has ready to send into writes of up to `n` bytes.
+    ///
+    /// Instead of writing each frame to the socket separately, such as the frames of many small
+    /// notifications or the frames sent when a corked stream is flushed, the frames queued by the
+    /// connection are written together once it flushes the socket or `n` bytes have been queued.
+    ///
+    /// `None` disables coalescing.
+    pub fn set_write_coalescing(&mut self, n: Option<usize>) -> &mut Self {
+        self.write_coalescing = n;
+        self
+    }
+
+    /// Get the receive window per stream.
+    pub fn receive_window(&self) -> u32 {
+        self.receive_window
+    }
+
+    /// Get the upper bound of the auto-tuned receive window, if auto-tuning is enabled.
+    pub fn max_receive_window(&self) -> Option<u32> {
+        self.max_receive_window
+    }
+
+    /// Get the max. number of streams.
+    pub fn max_num_streams(&self) -> usize {
+        self.max_num_streams
+    }
+
+    /// Validate the configuration.
+    ///
+    /// Called by the transports before any connection is constructed with the configuration so
+    /// a misconfigured muxer is reported when the transport is created.
+    ///
+    /// A max. buffer size smaller than the receive window is only warned about, as such
+    /// configurations were accepted before the configuration was validated.
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        if self.max_num_streams == 0 {
+            return Err(crate::Error::Other(
+                "yamux: max. number of streams must be non-zero".to_string(),
+            ));
+        }
+
+        if self.split_send_size == 0 {
+            return Err(crate::Error::Other(
+                "yamux: split send size must be non-zero".to_string(),
+            ));
+        }
+
+        if self.write_coalescing == Some(0) {
+            return Err(crate::Error::Other(
+                "yamux: write coalescing size must be non-zero".to_string(),
+            ));
+        }
+
+        // the remote is allowed to send a full receive window worth of data before the stream is
+        // read so the buffer should be large enough to hold it, otherwise the stream is reset if
+        // the remote sends more than the buffer can hold
+        if self.max_buffer_size < self.receive_window as usize {
+            tracing::warn!(
+                target: LOG_TARGET,
+                max_buffer_size = self.max_buffer_size,
+                receive_window = self.receive_window,
+                "yamux: max. buffer size is smaller than the receive window",
+            );
+        }
+
+        if let Some(max_receive_window) = self.max_receive_window {
+            if max_receive_window < self.receive_window {
+                return Err(crate::Error::Other(format!(
+                    "yamux: max. receive window ({}) is smaller than the receive window ({})",
+                    max_receive_window, self.receive_window,
+                )));
+            }
+
+            if self.max_buffer_size < max_receive_window as usize {
+                return Err(crate::Error::Other(format!(
+                    "yamux: max. buffer size ({}) is smaller than the max. receive window ({})",
+                    self.max_buffer_size, max_receive_window,
+                )));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 // Check that we can safely cast a `usize` to a `u64`.
@@ -178,3 +289,260 @@ static_assertions::const_assert! {
 static_assertions::const_assert! {
     std::mem::size_of::<u32>() <= std::mem::size_of::<usize>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncRead as TokioAsyncRead, AsyncWrite as TokioAsyncWrite};
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_config() {
+        let mut config = Config::default();
+        config.set_max_num_streams(0);
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.set_split_send_size(0);
+        assert!(config.validate().is_err());
+
+        // accepted for backwards compatibility
+        let mut config = Config::default();
+        config.set_receive_window(4 * DEFAULT_CREDIT);
+        config.set_max_buffer_size(DEFAULT_CREDIT as usize);
+        assert!(config.validate().is_ok());
+
+        let mut config = Config::default();
+        config.set_receive_window(4 * DEFAULT_CREDIT);
+        config.set_max_receive_window(Some(2 * DEFAULT_CREDIT));
+        assert!(config.validate().is_err());
+
+        // auto-tuned window can't grow beyond the buffer even if the initial window fits in it
+        let mut config = Config::default();
+        config.set_max_receive_window(Some(16 * 1024 * 1024));
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.set_max_buffer_size(4 * DEFAULT_CREDIT as usize);
+        config.set_max_receive_window(Some(8 * DEFAULT_CREDIT));
+        assert!(config.validate().is_err());
+
+        config.set_max_receive_window(Some(4 * DEFAULT_CREDIT));
+        assert!(config.validate().is_ok());
+
+        let mut config = Config::default();
+        config.set_write_coalescing(Some(0));
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn opening_streams_beyond_limit_fails() {
+        let (io, _remote) = tokio::io::duplex(64 * 1024);
+        let mut config = Config::default();
+        config.set_max_num_streams(2);
+
+        let mut connection = Connection::new(io.compat(), config, Mode::Client);
+        let mut streams = Vec::new();
+
+        for _ in 0..2 {
+            streams.push(
+                futures::future::poll_fn(|cx| connection.poll_new_outbound(cx)).await.unwrap(),
+            );
+        }
+
+        assert!(std::matches!(
+            futures::future::poll_fn(|cx| connection.poll_new_outbound(cx)).await,
+            Err(ConnectionError::TooManyStreams)
+        ));
+    }
+
+    /// Forward everything read from `from` to `to` after `delay`.
+    async fn delayed_forward(
+        mut from: impl TokioAsyncRead + Unpin + Send + 'static,
+        mut to: impl TokioAsyncWrite + Unpin + Send + 'static,
+        delay: Duration,
+    ) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<(Instant, Vec<u8>)>();
+
+        tokio::spawn(async move {
+            while let Some((deadline, data)) = rx.recv().await {
+                tokio::time::sleep_until(deadline.into()).await;
+
+                if to.write_all(&data).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            match from.read(&mut buffer).await {
+                Ok(0) | Err(_) => return,
+                Ok(nread) => {
+                    let _ = tx.send((Instant::now() + delay, buffer[..nread].to_vec()));
+                }
+            }
+        }
+    }
+
+    /// Send `size` bytes over a stream of a link with `delay` one-way latency and return how
+    /// long it took for the receiver to get all of it.
+    async fn transfer_over_delayed_link(config: Config, size: usize, delay: Duration) -> Duration {
+        let (client_io, link_client) = tokio::io::duplex(1024 * 1024);
+        let (server_io, link_server) = tokio::io::duplex(1024 * 1024);
+        let (client_read, client_write) = tokio::io::split(link_client);
+        let (server_read, server_write) = tokio::io::split(link_server);
+
+        tokio::spawn(delayed_forward(client_read, server_write, delay));
+        tokio::spawn(delayed_forward(server_read, client_write, delay));
+
+        let mut client = Connection::new(client_io.compat(), config.clone(), Mode::Client);
+        let mut server = Connection::new(server_io.compat(), config, Mode::Server);
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let mut tx = Some(tx);
+
+            while let Some(Ok(mut stream)) =
+                futures::future::poll_fn(|cx| server.poll_next_inbound(cx)).await
+            {
+                let tx = tx.take().expect("one stream");
+
+                tokio::spawn(async move {
+                    let mut received = Vec::new();
+                    stream.read_to_end(&mut received).await.unwrap();
+                    tx.send((received.len(), Instant::now())).unwrap();
+                });
+            }
+        });
+
+        let mut stream = futures::future::poll_fn(|cx| client.poll_new_outbound(cx)).await.unwrap();
+        tokio::spawn(async move {
+            while let Some(Ok(_)) =
+                futures::future::poll_fn(|cx| client.poll_next_inbound(cx)).await
+            {}
+        });
+
+        let started = Instant::now();
+        stream.write_all(&vec![1u8; size]).await.unwrap();
+        stream.close().await.unwrap();
+
+        let (received, finished) = rx.await.unwrap();
+        assert_eq!(received, size);
+
+        finished - started
+    }
+
+    #[tokio::test]
+    async fn receive_window_auto_tuning_improves_throughput_on_high_latency_link() {
+        const SIZE: usize = 4 * 1024 * 1024;
+        const DELAY: Duration = Duration::from_millis(25);
+
+        let fixed = transfer_over_delayed_link(Config::default(), SIZE, DELAY).await;
+
+        let mut config = Config::default();
+        config.set_max_buffer_size(SIZE);
+        config.set_max_receive_window(Some(SIZE as u32));
+        let tuned = transfer_over_delayed_link(config, SIZE, DELAY).await;
+
+        assert!(tuned < fixed, "auto-tuned: {tuned:?}, fixed: {fixed:?}");
+    }
+
+    #[tokio::test]
+    async fn higher_priority_stream_frames_sent_first() {
+        use crate::protocol::SubstreamPriority;
+        use tokio::io::AsyncReadExt as _;
+
+        let (io, mut remote) = tokio::io::duplex(1024 * 1024);
+        let mut connection = Connection::new(io.compat(), Config::default(), Mode::Client);
+
+        let mut notification =
+            futures::future::poll_fn(|cx| connection.poll_new_outbound(cx)).await.unwrap();
+        let mut request_response =
+            futures::future::poll_fn(|cx| connection.poll_new_outbound(cx)).await.unwrap();
+        notification.set_priority(SubstreamPriority::Normal.value());
+        request_response.set_priority(SubstreamPriority::High.value());
+
+        // notifications are queued before the request
+        for _ in 0..4 {
+            notification.write_all(&[1u8; 1024]).await.unwrap();
+        }
+        request_response.write_all(&[2u8; 1024]).await.unwrap();
+
+        tokio::spawn(async move {
+            while let Some(Ok(_)) =
+                futures::future::poll_fn(|cx| connection.poll_next_inbound(cx)).await
+            {}
+        });
+
+        let mut order = Vec::new();
+        while order.len() < 5 {
+            let mut buffer = [0u8; frame::header::HEADER_SIZE];
+            remote.read_exact(&mut buffer).await.unwrap();
+            let header = frame::header::decode(&buffer).unwrap();
+
+            // only data frames have a body
+            if header.tag() == frame::header::Tag::Data {
+                let mut body = vec![0u8; header.len().val() as usize];
+                remote.read_exact(&mut body).await.unwrap();
+                order.push(header.stream_id());
+            }
+        }
+
+        assert_eq!(order[0], request_response.id());
+        assert!(order[1..].iter().all(|id| *id == notification.id()));
+    }
+
+    #[tokio::test]
+    async fn large_write_does_not_block_other_streams() {
+        use tokio::io::AsyncReadExt as _;
+
+        // small socket buffer so that the frames of the large write pile up in the connection
+        let (io, mut remote) = tokio::io::duplex(1024);
+        let mut connection = Connection::new(io.compat(), Config::default(), Mode::Client);
+
+        let mut bulk =
+            futures::future::poll_fn(|cx| connection.poll_new_outbound(cx)).await.unwrap();
+        let mut small =
+            futures::future::poll_fn(|cx| connection.poll_new_outbound(cx)).await.unwrap();
+
+        bulk.write_all(&vec![1u8; 8 * DEFAULT_SPLIT_SEND_SIZE]).await.unwrap();
+
+        tokio::spawn(async move {
+            while let Some(Ok(_)) =
+                futures::future::poll_fn(|cx| connection.poll_next_inbound(cx)).await
+            {}
+        });
+
+        // let the connection queue all frames of the large write before the small one is written
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        small.write_all(&[2u8; 16]).await.unwrap();
+
+        let mut order = Vec::new();
+        while order.len() < 9 {
+            let mut buffer = [0u8; frame::header::HEADER_SIZE];
+            remote.read_exact(&mut buffer).await.unwrap();
+            let header = frame::header::decode(&buffer).unwrap();
+
+            if header.tag() == frame::header::Tag::Data {
+                let mut body = vec![0u8; header.len().val() as usize];
+                remote.read_exact(&mut body).await.unwrap();
+                order.push(header.stream_id());
+            }
+        }
+
+        // only the frames already handed to the socket are sent before the small write
+        let position = order.iter().position(|id| *id == small.id()).unwrap();
+        assert!(position <= 2, "small write sent as frame {position}");
+        assert_eq!(order.iter().filter(|id| **id == bulk.id()).count(), 8);
+    }
+}
@@ -22,6 +22,8 @@ use litep2p::{config::ConfigBuilder, transport::tcp::config::Config as TcpConfig
 
 #[cfg(feature = "quic")]
 use litep2p::transport::quic::config::Config as QuicConfig;
+#[cfg(unix)]
+use litep2p::transport::unix::config::Config as UnixConfig;
 #[cfg(feature = "websocket")]
 use litep2p::transport::websocket::config::Config as WebSocketConfig;
 
@@ -31,6 +33,8 @@ pub(crate) enum Transport {
     Quic(QuicConfig),
     #[cfg(feature = "websocket")]
     WebSocket(WebSocketConfig),
+    #[cfg(unix)]
+    Unix(UnixConfig),
 }
 
 pub(crate) fn add_transport(config: ConfigBuilder, transport: Transport) -> ConfigBuilder {
@@ -40,5 +44,7 @@ pub(crate) fn add_transport(config: ConfigBuilder, transport: Transport) -> Conf
         Transport::Quic(transport) => config.with_quic(transport),
         #[cfg(feature = "websocket")]
         Transport::WebSocket(transport) => config.with_websocket(transport),
+        #[cfg(unix)]
+        Transport::Unix(transport) => config.with_unix(transport),
     }
 }
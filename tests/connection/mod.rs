@@ -22,9 +22,16 @@ use litep2p::{
     config::ConfigBuilder,
     crypto::ed25519::Keypair,
     error::{DialError, Error, NegotiationError},
-    protocol::libp2p::ping::{Config as PingConfig, PingEvent},
-    transport::tcp::config::Config as TcpConfig,
-    Litep2p, Litep2pEvent, PeerId,
+    protocol::{
+        libp2p::ping::{Config as PingConfig, PingEvent},
+        request_response::{Config as RequestResponseConfig, DialOptions},
+        Direction, NegotiationObserver,
+    },
+    transport::{
+        tcp::config::Config as TcpConfig, DialMilestone, IpFilterConfig, Muxer, SecurityProtocol,
+        SupportedTransport,
+    },
+    Litep2p, Litep2pEvent, PeerId, ProtocolName,
 };
 
 #[cfg(feature = "websocket")]
@@ -40,6 +47,8 @@ use tokio::net::TcpListener;
 #[cfg(feature = "quic")]
 use tokio::net::UdpSocket;
 
+use std::sync::{Arc, Mutex};
+
 use crate::common::{add_transport, Transport};
 
 #[cfg(test)]
@@ -123,6 +132,151 @@ async fn two_litep2ps_work(transport1: Transport, transport2: Transport) {
     ));
 }
 
+#[cfg(feature = "secp256k1")]
+#[tokio::test]
+async fn secp256k1_identity_works_tcp() {
+    secp256k1_identity_works(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await
+}
+
+#[cfg(all(feature = "secp256k1", feature = "quic"))]
+#[tokio::test]
+async fn secp256k1_identity_works_quic() {
+    secp256k1_identity_works(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "secp256k1")]
+async fn secp256k1_identity_works(transport1: Transport, transport2: Transport) {
+    use litep2p::crypto::secp256k1;
+
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let keypair1 = secp256k1::Keypair::generate();
+    let peer1 = PeerId::from_public_key(&keypair1.public().into());
+    let config1 = add_transport(ConfigBuilder::new().with_keypair(keypair1), transport1).build();
+
+    let keypair2 = Keypair::generate();
+    let peer2 = PeerId::from_public_key(&keypair2.public().into());
+    let config2 = add_transport(ConfigBuilder::new().with_keypair(keypair2), transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    assert_eq!(litep2p1.local_peer_id(), &peer1);
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    let (res1, res2) = tokio::join!(litep2p1.next_event(), litep2p2.next_event());
+
+    match res1 {
+        Some(Litep2pEvent::ConnectionEstablished { peer, .. }) => assert_eq!(peer, peer2),
+        event => panic!("invalid event: {event:?}"),
+    }
+    match res2 {
+        Some(Litep2pEvent::ConnectionEstablished { peer, .. }) => assert_eq!(peer, peer1),
+        event => panic!("invalid event: {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn negotiated_security_and_muxer_reported_tcp() {
+    negotiated_security_and_muxer_reported(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        SecurityProtocol::Noise,
+        Some(Muxer::Yamux),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn negotiated_security_and_muxer_reported_quic() {
+    negotiated_security_and_muxer_reported(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+        SecurityProtocol::Tls,
+        None,
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn negotiated_security_and_muxer_reported_websocket() {
+    negotiated_security_and_muxer_reported(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        SecurityProtocol::Noise,
+        Some(Muxer::Yamux),
+    )
+    .await;
+}
+
+async fn negotiated_security_and_muxer_reported(
+    transport1: Transport,
+    transport2: Transport,
+    expected_security: SecurityProtocol,
+    expected_muxer: Option<Muxer>,
+) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let config1 = ConfigBuilder::new().with_keypair(Keypair::generate());
+    let config1 = add_transport(config1, transport1).build();
+
+    let config2 = ConfigBuilder::new().with_keypair(Keypair::generate());
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    let (res1, res2) = tokio::join!(litep2p1.next_event(), litep2p2.next_event());
+
+    for event in [res1, res2] {
+        match event {
+            Some(Litep2pEvent::ConnectionEstablished {
+                security, muxer, ..
+            }) => {
+                assert_eq!(security, expected_security);
+                assert_eq!(muxer, expected_muxer);
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+    }
+}
+
 #[tokio::test]
 async fn dial_failure_tcp() {
     dial_failure(
@@ -221,6 +375,74 @@ async fn dial_failure(transport1: Transport, transport2: Transport, dial_address
     ));
 }
 
+#[tokio::test]
+async fn dial_reports_winning_address() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // one address nobody listens on and the actual listen address of `litep2p2`
+    let unreachable = Multiaddr::empty()
+        .with(Protocol::Ip4(std::net::Ipv4Addr::new(127, 0, 0, 1)))
+        .with(Protocol::Tcp(1))
+        .with(Protocol::P2p(Multihash::from(peer2)));
+    let reachable = litep2p2.listen_addresses().next().unwrap().clone();
+
+    assert_eq!(
+        litep2p1.add_known_address(peer2, vec![unreachable, reachable.clone()].into_iter()),
+        2
+    );
+    litep2p1.dial(&peer2).await.unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let _ = litep2p2.next_event().await;
+        }
+    });
+
+    let mut established = false;
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            match litep2p1.next_event().await.unwrap() {
+                Litep2pEvent::ConnectionEstablished { peer, .. } => {
+                    assert_eq!(peer, peer2);
+                    established = true;
+                }
+                Litep2pEvent::DialSucceeded { peer, address } => {
+                    assert!(established);
+                    assert_eq!(peer, peer2);
+                    assert_eq!(address, reachable);
+                    break;
+                }
+                event => panic!("invalid event: {event:?}"),
+            }
+        }
+    })
+    .await
+    .expect("dial to succeed in time");
+}
+
 #[tokio::test]
 async fn connect_over_dns() {
     let _ = tracing_subscriber::fmt()
@@ -1021,6 +1243,8 @@ async fn make_dummy_litep2p(
         Transport::Quic(config) => litep2p_config.with_quic(config),
         #[cfg(feature = "websocket")]
         Transport::WebSocket(config) => litep2p_config.with_websocket(config),
+        #[cfg(unix)]
+        Transport::Unix(config) => litep2p_config.with_unix(config),
     }
     .build();
 
@@ -1495,3 +1719,942 @@ async fn simultaneous_dial_then_redial(transport1: Transport, transport2: Transp
         _ => {}
     }
 }
+
+#[tokio::test]
+async fn inbound_connection_from_denied_network_rejected_tcp() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let mut listener = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec![
+                    "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
+                    "/ip6/::1/tcp/0".parse().unwrap(),
+                ],
+                ..Default::default()
+            })
+            .with_ip_filter(IpFilterConfig::default().deny("::1/128".parse().unwrap()))
+            .build(),
+    )
+    .unwrap();
+
+    let make_dialer = |listen_address: &str| {
+        Litep2p::new(
+            ConfigBuilder::new()
+                .with_keypair(Keypair::generate())
+                .with_tcp(TcpConfig {
+                    listen_addresses: vec![listen_address.parse().unwrap()],
+                    ..Default::default()
+                })
+                .build(),
+        )
+        .unwrap()
+    };
+    let mut dialer_ipv4 = make_dialer("/ip4/127.0.0.1/tcp/0");
+    let mut dialer_ipv6 = make_dialer("/ip6/::1/tcp/0");
+
+    let listen_address = |ipv4: bool| {
+        listener
+            .listen_addresses()
+            .find(|address| match address.iter().next() {
+                Some(Protocol::Ip4(_)) => ipv4,
+                Some(Protocol::Ip6(_)) => !ipv4,
+                _ => false,
+            })
+            .unwrap()
+            .clone()
+    };
+    let address_ipv4 = listen_address(true);
+    let address_ipv6 = listen_address(false);
+
+    dialer_ipv6.dial_address(address_ipv6).await.unwrap();
+    dialer_ipv4.dial_address(address_ipv4).await.unwrap();
+
+    let dialer_ipv4_peer = *dialer_ipv4.local_peer_id();
+    let mut ipv4_connected = false;
+    let mut ipv6_rejected = false;
+    let mut listener_connected = false;
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        while !ipv4_connected || !ipv6_rejected || !listener_connected {
+            tokio::select! {
+                event = listener.next_event() => match event.unwrap() {
+                    Litep2pEvent::ConnectionEstablished { peer, .. } => {
+                        assert_eq!(peer, dialer_ipv4_peer);
+                        listener_connected = true;
+                    }
+                    event => tracing::debug!("listener event: {event:?}"),
+                },
+                event = dialer_ipv4.next_event() => match event.unwrap() {
+                    Litep2pEvent::ConnectionEstablished { .. } => ipv4_connected = true,
+                    event => panic!("unexpected event: {event:?}"),
+                },
+                event = dialer_ipv6.next_event() => match event.unwrap() {
+                    Litep2pEvent::DialFailure { .. } => ipv6_rejected = true,
+                    event => panic!("unexpected event: {event:?}"),
+                },
+            }
+        }
+    })
+    .await
+    .expect("connections to finish in time");
+}
+
+#[tokio::test]
+async fn dial_denied_address_fails() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let mut litep2p = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_ip_filter(IpFilterConfig::default().deny("10.0.0.0/8".parse().unwrap()))
+            .build(),
+    )
+    .unwrap();
+
+    let address = Multiaddr::empty()
+        .with(Protocol::Ip4(std::net::Ipv4Addr::new(10, 1, 2, 3)))
+        .with(Protocol::Tcp(8888))
+        .with(Protocol::P2p(
+            Multihash::from_bytes(&PeerId::random().to_bytes()).unwrap(),
+        ));
+
+    assert!(std::matches!(
+        litep2p.dial_address(address).await,
+        Err(Error::AddressDenied(_))
+    ));
+}
+
+#[tokio::test]
+async fn connection_data_dropped_on_close() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let make_litep2p = || {
+        Litep2p::new(
+            ConfigBuilder::new()
+                .with_keypair(Keypair::generate())
+                .with_tcp(TcpConfig {
+                    listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                    ..Default::default()
+                })
+                .with_keep_alive_timeout(std::time::Duration::from_secs(1))
+                .build(),
+        )
+        .unwrap()
+    };
+    let mut litep2p1 = make_litep2p();
+    let mut litep2p2 = make_litep2p();
+    let mut litep2p3 = make_litep2p();
+
+    let address2 = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address2).await.unwrap();
+
+    #[derive(Debug, PartialEq)]
+    struct ConnectionTag(PeerId);
+
+    let mut first_connection = None;
+    let mut closed = 0usize;
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        while closed < 2 {
+            tokio::select! {
+                event = litep2p1.next_event() => match event.unwrap() {
+                    Litep2pEvent::ConnectionEstablished { peer, endpoint, .. } => {
+                        match first_connection {
+                            None => {
+                                litep2p1
+                                    .set_connection_data(
+                                        endpoint.connection_id(),
+                                        ConnectionTag(peer),
+                                    )
+                                    .unwrap();
+                                first_connection = Some((peer, endpoint.connection_id()));
+
+                                let address3 =
+                                    litep2p3.listen_addresses().next().unwrap().clone();
+                                litep2p1.dial_address(address3).await.unwrap();
+                            }
+                            Some((first_peer, first_connection)) => {
+                                assert_eq!(
+                                    litep2p1.connection_data::<ConnectionTag>(first_connection),
+                                    Some(&ConnectionTag(first_peer)),
+                                );
+                                assert!(litep2p1
+                                    .connection_data::<u32>(first_connection)
+                                    .is_none());
+                                assert!(litep2p1
+                                    .connection_data::<ConnectionTag>(endpoint.connection_id())
+                                    .is_none());
+                            }
+                        }
+                    }
+                    Litep2pEvent::ConnectionClosed { connection_id, .. } => {
+                        assert!(litep2p1.connection_data::<ConnectionTag>(connection_id).is_none());
+                        assert!(std::matches!(
+                            litep2p1.set_connection_data(connection_id, 1u32),
+                            Err(Error::ConnectionDoesntExist(_))
+                        ));
+                        closed += 1;
+                    }
+                    event => tracing::debug!("litep2p1 event: {event:?}"),
+                },
+                _ = litep2p2.next_event() => {},
+                _ = litep2p3.next_event() => {},
+            }
+        }
+    })
+    .await
+    .expect("connections to close in time");
+}
+
+#[tokio::test]
+async fn only_enabled_transports_are_dialable() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let litep2p = Litep2p::new(
+        ConfigBuilder::new()
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        litep2p.supported_transports(),
+        vec![SupportedTransport::Tcp]
+    );
+
+    let peer = PeerId::random();
+    let tcp_address: Multiaddr = format!("/ip4/127.0.0.1/tcp/8888/p2p/{peer}").parse().unwrap();
+    let quic_address: Multiaddr =
+        format!("/ip4/127.0.0.1/udp/8888/quic-v1/p2p/{peer}").parse().unwrap();
+    let ws_address: Multiaddr = format!("/ip4/127.0.0.1/tcp/8888/ws/p2p/{peer}").parse().unwrap();
+
+    assert!(litep2p.is_dialable(&tcp_address));
+    assert!(!litep2p.is_dialable(&quic_address));
+    assert!(!litep2p.is_dialable(&ws_address));
+
+    // addresses without a peer ID cannot be dialed
+    assert!(!litep2p.is_dialable(&"/ip4/127.0.0.1/tcp/8888".parse().unwrap()));
+}
+
+#[tokio::test]
+async fn negotiation_observer_reports_offered_and_selected_protocols() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_libp2p_ping(ping_config1)
+        .with_negotiation_observer(NegotiationObserver::new(move |negotiation| {
+            let _ = tx.send(negotiation.clone());
+        }))
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_libp2p_ping(ping_config2)
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    tokio::spawn(async move { while litep2p1.next_event().await.is_some() {} });
+    tokio::spawn(async move { while litep2p2.next_event().await.is_some() {} });
+
+    let ping = ProtocolName::from("/ipfs/ping/1.0.0");
+    let (mut outbound, mut inbound) = (None, None);
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        while outbound.is_none() || inbound.is_none() {
+            let negotiation = rx.recv().await.unwrap();
+
+            match negotiation.direction {
+                Direction::Inbound => inbound = Some(negotiation),
+                Direction::Outbound(_) => outbound = Some(negotiation),
+            }
+        }
+    })
+    .await
+    .expect("negotiations to be observed in time");
+
+    let outbound = outbound.unwrap();
+    assert_eq!(outbound.peer, peer2);
+    assert_eq!(outbound.offered, vec![ping.clone()]);
+    assert_eq!(outbound.selected, Some(ping.clone()));
+
+    let inbound = inbound.unwrap();
+    assert_eq!(inbound.peer, peer2);
+    assert!(inbound.offered.contains(&ping));
+    assert_eq!(inbound.selected, Some(ping));
+}
+
+#[tokio::test]
+async fn negotiation_observer_reports_unsupported_inbound_protocols() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_libp2p_ping(ping_config1)
+        .with_negotiation_observer(NegotiationObserver::new(move |negotiation| {
+            let _ = tx.send(negotiation.clone());
+        }))
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let (req_resp_config, mut handle) = RequestResponseConfig::new(
+        ProtocolName::from("/unknown/1"),
+        Vec::new(),
+        1024,
+        std::time::Duration::from_secs(5),
+        None,
+    );
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config)
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p1.listen_addresses().next().unwrap().clone();
+    litep2p2.add_known_address(peer1, std::iter::once(address));
+
+    tokio::spawn(async move { while litep2p1.next_event().await.is_some() {} });
+    tokio::spawn(async move { while litep2p2.next_event().await.is_some() {} });
+
+    handle.send_request(peer1, vec![1, 2, 3], DialOptions::Dial).await.unwrap();
+
+    let negotiation = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            let negotiation = rx.recv().await.unwrap();
+
+            if negotiation.direction == Direction::Inbound && negotiation.selected.is_none() {
+                break negotiation;
+            }
+        }
+    })
+    .await
+    .expect("unsupported protocol to be observed in time");
+
+    assert_eq!(negotiation.peer, peer2);
+    assert_eq!(
+        negotiation.unsupported,
+        vec![ProtocolName::from("/unknown/1")]
+    );
+}
+
+/// Connection fields recorded by the connection span.
+#[derive(Debug, Default, Clone)]
+struct ConnectionFields {
+    peer: Option<String>,
+    connection_id: Option<String>,
+}
+
+impl tracing::field::Visit for ConnectionFields {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "peer" => self.peer = Some(format!("{value:?}")),
+            "connection_id" => self.connection_id = Some(format!("{value:?}")),
+            _ => {}
+        }
+    }
+}
+
+/// Layer capturing the target of each event and the connection fields of its enclosing span.
+#[derive(Default, Clone)]
+struct CaptureLayer {
+    events: Arc<Mutex<Vec<(String, Option<ConnectionFields>)>>>,
+}
+
+impl<S> tracing_subscriber::Layer<S> for CaptureLayer
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        if attrs.metadata().name() != "connection" {
+            return;
+        }
+
+        let mut fields = ConnectionFields::default();
+        attrs.record(&mut fields);
+        ctx.span(id).expect("span to exist").extensions_mut().insert(fields);
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let fields = ctx.event_scope(event).and_then(|scope| {
+            scope
+                .from_root()
+                .find_map(|span| span.extensions().get::<ConnectionFields>().cloned())
+        });
+
+        self.events
+            .lock()
+            .unwrap()
+            .push((event.metadata().target().to_string(), fields));
+    }
+}
+
+#[tokio::test]
+async fn connection_events_carry_connection_span() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let layer = CaptureLayer::default();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let (ping_config1, mut ping_event_stream1) = PingConfig::default();
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_libp2p_ping(ping_config1)
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let (ping_config2, _ping_event_stream2) = PingConfig::default();
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_libp2p_ping(ping_config2)
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    tokio::spawn(async move { while litep2p1.next_event().await.is_some() {} });
+    tokio::spawn(async move { while litep2p2.next_event().await.is_some() {} });
+
+    // wait until a ping has been exchanged over the connection
+    let event = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        ping_event_stream1.next(),
+    )
+    .await
+    .expect("ping to succeed in time");
+    assert!(std::matches!(event, Some(PingEvent::Ping { peer, .. }) if peer == peer2));
+
+    let peers = [format!("{peer1:?}"), format!("{peer2:?}")];
+    let events = layer.events.lock().unwrap().clone();
+    let in_connection = events
+        .iter()
+        .filter_map(|(target, fields)| fields.as_ref().map(|fields| (target, fields)))
+        .collect::<Vec<_>>();
+
+    // events logged by the connection task are recorded within the connection span
+    assert!(in_connection
+        .iter()
+        .any(|(target, _)| target.as_str() == "litep2p::tcp::connection"));
+
+    for (target, fields) in in_connection {
+        assert!(
+            fields.connection_id.is_some(),
+            "connection id missing: {target}"
+        );
+        assert!(
+            peers.contains(fields.peer.as_ref().unwrap()),
+            "invalid peer for {target}: {fields:?}",
+        );
+    }
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn silently_dropped_peer_detected_quic() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let quic_config = || QuicConfig {
+        max_idle_timeout: std::time::Duration::from_secs(1),
+        keep_alive_interval: Some(std::time::Duration::from_millis(250)),
+        ..Default::default()
+    };
+
+    // keep the connections open without any substreams so only the transport can close them
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_keep_alive_timeout(std::time::Duration::from_secs(60));
+    let mut litep2p1 =
+        Litep2p::new(add_transport(config1, Transport::Quic(quic_config())).build()).unwrap();
+
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_keep_alive_timeout(std::time::Duration::from_secs(60));
+    let mut litep2p2 =
+        Litep2p::new(add_transport(config2, Transport::Quic(quic_config())).build()).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let mut address2 = litep2p2.listen_addresses().next().unwrap().iter();
+    let listen_address = match (address2.next(), address2.next()) {
+        (Some(Protocol::Ip4(ip)), Some(Protocol::Udp(port))) =>
+            std::net::SocketAddr::from((ip, port)),
+        _ => panic!("invalid listen address"),
+    };
+
+    // forward datagrams between the peers until `blackhole` is set after which all datagrams are
+    // dropped, as if the remote peer had crashed or the network cable had been pulled
+    let proxy = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let proxy_address = proxy.local_addr().unwrap();
+    let blackhole = Arc::new(AtomicBool::new(false));
+
+    tokio::spawn({
+        let blackhole = Arc::clone(&blackhole);
+
+        async move {
+            let mut buffer = vec![0u8; 65536];
+            let mut client = None;
+
+            loop {
+                let (nread, from) = proxy.recv_from(&mut buffer).await.unwrap();
+
+                if blackhole.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                let to = match from == listen_address {
+                    true => match client {
+                        Some(client) => client,
+                        None => continue,
+                    },
+                    false => {
+                        client = Some(from);
+                        listen_address
+                    }
+                };
+
+                let _ = proxy.send_to(&buffer[..nread], to).await;
+            }
+        }
+    });
+
+    let address = Multiaddr::empty()
+        .with(Protocol::from(proxy_address.ip()))
+        .with(Protocol::Udp(proxy_address.port()))
+        .with(Protocol::QuicV1)
+        .with(Protocol::P2p(Multihash::from(peer2)));
+    litep2p1.dial_address(address).await.unwrap();
+
+    let mut litep2p1_connected = false;
+    let mut litep2p2_connected = false;
+    while !litep2p1_connected || !litep2p2_connected {
+        tokio::select! {
+            event = litep2p1.next_event() => match event {
+                Some(Litep2pEvent::ConnectionEstablished { .. }) => litep2p1_connected = true,
+                _ => {}
+            },
+            event = litep2p2.next_event() => match event {
+                Some(Litep2pEvent::ConnectionEstablished { .. }) => litep2p2_connected = true,
+                _ => {}
+            },
+        }
+    }
+
+    // keep-alive frames keep the connection open past the idle timeout while the peer is alive
+    let alive = tokio::time::sleep(std::time::Duration::from_secs(3));
+    tokio::pin!(alive);
+
+    loop {
+        tokio::select! {
+            event = litep2p1.next_event() => match event {
+                Some(Litep2pEvent::ConnectionClosed { .. }) =>
+                    panic!("connection closed while the peer was alive"),
+                _ => {}
+            },
+            event = litep2p2.next_event() => match event {
+                Some(Litep2pEvent::ConnectionClosed { .. }) =>
+                    panic!("connection closed while the peer was alive"),
+                _ => {}
+            },
+            _ = &mut alive => break,
+        }
+    }
+
+    blackhole.store(true, Ordering::Relaxed);
+
+    tokio::time::timeout(std::time::Duration::from_secs(3), async {
+        loop {
+            tokio::select! {
+                event = litep2p1.next_event() => match event {
+                    Some(Litep2pEvent::ConnectionClosed { peer, .. }) => {
+                        assert_eq!(peer, peer2);
+                        break;
+                    }
+                    _ => {}
+                },
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    })
+    .await
+    .expect("connection to be reported closed within the idle timeout");
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn close_reason_delivered_to_remote_quic() {
+    use litep2p::transport::CloseReason;
+
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_keep_alive_timeout(std::time::Duration::from_secs(60));
+    let mut litep2p1 =
+        Litep2p::new(add_transport(config1, Transport::Quic(Default::default())).build()).unwrap();
+
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_keep_alive_timeout(std::time::Duration::from_secs(60));
+    let mut litep2p2 =
+        Litep2p::new(add_transport(config2, Transport::Quic(Default::default())).build()).unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p2
+        .listen_addresses()
+        .next()
+        .unwrap()
+        .clone()
+        .with(Protocol::P2p(Multihash::from(peer2)));
+    litep2p1.dial_address(address).await.unwrap();
+
+    let mut litep2p1_connected = false;
+    let mut litep2p2_connected = false;
+    while !litep2p1_connected || !litep2p2_connected {
+        tokio::select! {
+            event = litep2p1.next_event() => match event {
+                Some(Litep2pEvent::ConnectionEstablished { .. }) => litep2p1_connected = true,
+                _ => {}
+            },
+            event = litep2p2.next_event() => match event {
+                Some(Litep2pEvent::ConnectionEstablished { .. }) => litep2p2_connected = true,
+                _ => {}
+            },
+        }
+    }
+
+    litep2p1.disconnect_with_reason(peer2, 1337, "banned").unwrap();
+
+    let mut litep2p1_closed = false;
+    let mut litep2p2_closed = false;
+    while !litep2p1_closed || !litep2p2_closed {
+        tokio::select! {
+            event = litep2p1.next_event() => match event {
+                // the reason is only reported to the remote peer
+                Some(Litep2pEvent::ConnectionClosed { peer, reason, .. }) => {
+                    assert_eq!(peer, peer2);
+                    assert_eq!(reason, None);
+                    litep2p1_closed = true;
+                }
+                _ => {}
+            },
+            event = litep2p2.next_event() => match event {
+                Some(Litep2pEvent::ConnectionClosed { peer, reason, .. }) => {
+                    assert_eq!(peer, peer1);
+                    assert_eq!(
+                        reason,
+                        Some(CloseReason {
+                            code: 1337,
+                            reason: b"banned".to_vec(),
+                        })
+                    );
+                    litep2p2_closed = true;
+                }
+                _ => {}
+            },
+        }
+    }
+
+    // there's nothing left to close
+    assert!(std::matches!(
+        litep2p1.disconnect_with_reason(peer2, 1337, "banned"),
+        Err(Error::PeerDoesntExist(_))
+    ));
+}
+
+#[tokio::test]
+async fn dial_progress_reported_tcp() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let make_transport = || {
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+    };
+
+    let config1 = ConfigBuilder::new().with_keypair(Keypair::generate());
+    let config1 = add_transport(config1, make_transport()).build();
+
+    let config2 = ConfigBuilder::new().with_keypair(Keypair::generate());
+    let config2 = add_transport(config2, make_transport()).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address_with_progress(address, tx).await.unwrap();
+
+    let (res1, res2) = tokio::join!(litep2p1.next_event(), litep2p2.next_event());
+    assert!(std::matches!(
+        res1,
+        Some(Litep2pEvent::ConnectionEstablished { .. })
+    ));
+    assert!(std::matches!(
+        res2,
+        Some(Litep2pEvent::ConnectionEstablished { .. })
+    ));
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event);
+    }
+
+    let milestones = events.iter().map(|event| event.milestone.clone()).collect::<Vec<_>>();
+    assert!(std::matches!(
+        milestones.as_slice(),
+        [
+            DialMilestone::AddressResolved { .. },
+            DialMilestone::TransportConnected,
+            DialMilestone::SecurityHandshakeStarted,
+            DialMilestone::SecurityHandshakeCompleted { peer },
+            DialMilestone::MuxerNegotiated,
+        ] if *peer == peer2
+    ));
+
+    // all events belong to the same dial and are timestamped in order
+    assert!(events.iter().all(|event| event.connection_id == events[0].connection_id));
+    assert!(events.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+}
+
+#[tokio::test]
+async fn inbound_connections_gated_by_accepting_tcp() {
+    inbound_connections_gated_by_accepting(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn inbound_connections_gated_by_accepting_websocket() {
+    inbound_connections_gated_by_accepting(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn inbound_connections_gated_by_accepting(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (ping_config1, _ping_event_stream1) = PingConfig::default();
+    let config1 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_libp2p_ping(ping_config1);
+    let config1 = add_transport(config1, transport1).build();
+
+    let (ping_config2, mut ping_event_stream2) = PingConfig::default();
+    let config2 = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_libp2p_ping(ping_config2);
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+
+    // listeners are bound but inbound connections are not accepted
+    litep2p2.set_accepting(false);
+    assert!(!litep2p2.is_accepting());
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address.clone()).await.unwrap();
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        loop {
+            tokio::select! {
+                event = litep2p1.next_event() => match event {
+                    Some(Litep2pEvent::DialFailure { .. }) => break,
+                    event => panic!("unexpected event: {event:?}"),
+                },
+                event = litep2p2.next_event() => panic!("unexpected event: {event:?}"),
+                event = ping_event_stream2.next() => panic!("unexpected ping: {event:?}"),
+            }
+        }
+    })
+    .await
+    .expect("dial to fail");
+
+    // once accepting is enabled, the connection reaches the protocols
+    litep2p2.set_accepting(true);
+    litep2p1.dial_address(address).await.unwrap();
+
+    let (res1, res2) = tokio::join!(litep2p1.next_event(), litep2p2.next_event());
+    assert!(std::matches!(
+        res1,
+        Some(Litep2pEvent::ConnectionEstablished { .. })
+    ));
+    assert!(std::matches!(
+        res2,
+        Some(Litep2pEvent::ConnectionEstablished { .. })
+    ));
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {}
+                _ = litep2p2.next_event() => {}
+            }
+        }
+    });
+
+    let event = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        ping_event_stream2.next(),
+    )
+    .await
+    .expect("ping to be received");
+    assert!(std::matches!(event, Some(PingEvent::Ping { peer, .. }) if peer == peer1));
+}
+
+#[tokio::test]
+async fn transport_stats_track_dials_tcp() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let make_config = || {
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .build()
+    };
+    let mut litep2p1 = Litep2p::new(make_config()).unwrap();
+    let mut litep2p2 = Litep2p::new(make_config()).unwrap();
+
+    // nothing listens on the port so the dial fails
+    let unreachable = Multiaddr::empty()
+        .with(Protocol::Ip6(std::net::Ipv6Addr::LOCALHOST))
+        .with(Protocol::Tcp(1))
+        .with(Protocol::P2p(
+            Multihash::from_bytes(&PeerId::random().to_bytes()).unwrap(),
+        ));
+    litep2p1.dial_address(unreachable).await.unwrap();
+
+    assert!(std::matches!(
+        litep2p1.next_event().await,
+        Some(Litep2pEvent::DialFailure { .. })
+    ));
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    let (res1, res2) = tokio::join!(litep2p1.next_event(), litep2p2.next_event());
+    assert!(std::matches!(
+        res1,
+        Some(Litep2pEvent::ConnectionEstablished { .. })
+    ));
+    assert!(std::matches!(
+        res2,
+        Some(Litep2pEvent::ConnectionEstablished { .. })
+    ));
+
+    let stats1 = litep2p1.transport_stats()[&SupportedTransport::Tcp];
+    assert_eq!(stats1.listeners, 1);
+    assert_eq!(stats1.active_connections, 1);
+    assert_eq!(stats1.dial_attempts, 2);
+    assert_eq!(stats1.dial_successes, 1);
+    assert_eq!(stats1.dial_failures, 1);
+    assert_eq!(stats1.accept_errors, 0);
+
+    let stats2 = litep2p2.transport_stats()[&SupportedTransport::Tcp];
+    assert_eq!(stats2.listeners, 1);
+    assert_eq!(stats2.active_connections, 1);
+    assert_eq!(stats2.dial_attempts, 0);
+}
@@ -34,6 +34,8 @@ use litep2p::{
 };
 use sc_network::config::MultiaddrWithPeerId;
 
+use std::collections::{HashMap, HashSet};
+
 fn spawn_litep2p(port: u16) {
     let (kad_config1, _kad_handle1) = KademliaConfigBuilder::new().build();
     let config1 = ConfigBuilder::new()
@@ -633,3 +635,91 @@ async fn provider_added_to_remote_node() {
         }
     }
 }
+
+/// Spawn node which knows `known_peers` and return its peer ID and listen addresses.
+fn spawn_bootstrap_node(known_peers: HashMap<PeerId, Vec<Multiaddr>>) -> (PeerId, Vec<Multiaddr>) {
+    let (kad_config, mut kad_handle) =
+        KademliaConfigBuilder::new().with_known_peers(known_peers).build();
+    let config = ConfigBuilder::new()
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_libp2p_kademlia(kad_config)
+        .build();
+
+    let mut litep2p = Litep2p::new(config).unwrap();
+    let peer = *litep2p.local_peer_id();
+    let addresses = litep2p.listen_addresses().cloned().collect();
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p.next_event() => {}
+                _ = kad_handle.next() => {}
+            }
+        }
+    });
+
+    (peer, addresses)
+}
+
+#[tokio::test]
+async fn bootstrap_discovers_network() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    // nodes which only become reachable through `hub`
+    let nodes = (0..4).map(|_| spawn_bootstrap_node(HashMap::new())).collect::<HashMap<_, _>>();
+    let (hub, hub_addresses) = spawn_bootstrap_node(nodes.clone());
+
+    let (kad_config, mut kad_handle) = KademliaConfigBuilder::new()
+        .with_known_peers(HashMap::from_iter([(hub, hub_addresses)]))
+        .build();
+    let config = ConfigBuilder::new()
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_libp2p_kademlia(kad_config)
+        .build();
+    let mut litep2p = Litep2p::new(config).unwrap();
+
+    let query = kad_handle.bootstrap().await;
+    let mut discovered = HashSet::new();
+    let mut progress = Vec::new();
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(tokio::time::Duration::from_secs(10)) => {
+                panic!("bootstrap didn't complete in 10 secs")
+            }
+            _ = litep2p.next_event() => {}
+            event = kad_handle.next() => match event.unwrap() {
+                KademliaEvent::RoutingTableUpdate { peers } => discovered.extend(peers),
+                KademliaEvent::BootstrapProgress { query_id, queried, remaining } => {
+                    assert_eq!(query_id, query);
+                    progress.push((queried, remaining));
+                }
+                KademliaEvent::BootstrapComplete { query_id } => {
+                    assert_eq!(query_id, query);
+                    break
+                }
+                KademliaEvent::QueryFailed { query_id } if query_id == query => {
+                    panic!("bootstrap failed")
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // every finished lookup is reported and the last one has nothing left to do
+    assert!(!progress.is_empty());
+    assert!(progress.iter().enumerate().all(|(i, (queried, _))| *queried == i + 1));
+    assert_eq!(progress.last().unwrap().1, 0);
+
+    for peer in nodes.keys() {
+        assert!(discovered.contains(peer), "{peer} not discovered");
+    }
+}
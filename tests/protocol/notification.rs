@@ -23,14 +23,16 @@ use litep2p::{
     crypto::ed25519::Keypair,
     error::Error,
     protocol::notification::{
-        Config as NotificationConfig, ConfigBuilder, Direction, NotificationError,
-        NotificationEvent, NotificationHandle, ValidationResult,
+        Compression, Config as NotificationConfig, ConfigBuilder, Direction, NotificationError,
+        NotificationEvent, NotificationHandle, PeerStateKind, PeerStateObserver, ValidationResult,
     },
     transport::tcp::config::Config as TcpConfig,
-    types::protocol::ProtocolName,
+    types::protocol::{ProtocolName, VersionedProtocol},
     Litep2p, Litep2pEvent, PeerId,
 };
 
+#[cfg(feature = "traffic-tap")]
+use litep2p::substream::{TapDirection, TrafficTap};
 #[cfg(feature = "websocket")]
 use litep2p::transport::websocket::config::Config as WebSocketConfig;
 
@@ -538,6 +540,166 @@ async fn notification_stream_closed(transport1: Transport, transport2: Transport
     }
 }
 
+#[tokio::test]
+async fn stream_direction_reported_tcp() {
+    stream_direction_reported(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn stream_direction_reported_quic() {
+    stream_direction_reported(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn stream_direction_reported_websocket() {
+    stream_direction_reported(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn stream_direction_reported(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config2, mut handle2) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // `peer1` opens the stream
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        open_stream(&mut handle1, &mut handle2, peer1, peer2).await,
+        (Direction::Outbound, Direction::Inbound)
+    );
+
+    handle1.close_substream(peer2).await;
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamClosed { peer: peer2 }
+    );
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamClosed { peer: peer1 }
+    );
+
+    // `peer2` opens the stream
+    handle2.open_substream(peer1).await.unwrap();
+    assert_eq!(
+        open_stream(&mut handle2, &mut handle1, peer2, peer1).await,
+        (Direction::Outbound, Direction::Inbound)
+    );
+}
+
+/// Accept the stream `dialer` opened to `listener` and return the directions reported by the
+/// dialer and the listener.
+async fn open_stream(
+    dialer: &mut NotificationHandle,
+    listener: &mut NotificationHandle,
+    dialer_peer: PeerId,
+    listener_peer: PeerId,
+) -> (Direction, Direction) {
+    assert_eq!(
+        listener.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: dialer_peer,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    listener.send_validation_result(dialer_peer, ValidationResult::Accept);
+
+    assert_eq!(
+        dialer.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: listener_peer,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    dialer.send_validation_result(listener_peer, ValidationResult::Accept);
+
+    let opened = |event: NotificationEvent| match event {
+        NotificationEvent::NotificationStreamOpened { direction, .. } => direction,
+        event => panic!("invalid event received: {event:?}"),
+    };
+    let listener_direction = opened(listener.next().await.unwrap());
+    let dialer_direction = opened(dialer.next().await.unwrap());
+
+    (dialer_direction, listener_direction)
+}
+
 #[tokio::test]
 async fn reconnect_after_disconnect_tcp() {
     reconnect_after_disconnect(
@@ -827,6 +989,8 @@ async fn set_new_handshake(transport1: Transport, transport2: Transport) {
         Transport::Quic(config) => config1.with_quic(config),
         #[cfg(feature = "websocket")]
         Transport::WebSocket(config) => config1.with_websocket(config),
+        #[cfg(unix)]
+        Transport::Unix(config) => config1.with_unix(config),
     }
     .build();
 
@@ -3360,9 +3524,10 @@ async fn auto_accept_inbound(transport1: Transport, transport2: Transport) {
         }
     );
 }
+
 #[tokio::test]
-async fn dial_failure_tcp() {
-    dial_failure(
+async fn auto_accept_all_inbound_tcp() {
+    auto_accept_all_inbound(
         Transport::Tcp(Default::default()),
         Transport::Tcp(Default::default()),
     )
@@ -3371,8 +3536,8 @@ async fn dial_failure_tcp() {
 
 #[cfg(feature = "quic")]
 #[tokio::test]
-async fn dial_failure_quic() {
-    dial_failure(
+async fn auto_accept_all_inbound_quic() {
+    auto_accept_all_inbound(
         Transport::Quic(Default::default()),
         Transport::Quic(Default::default()),
     )
@@ -3381,15 +3546,15 @@ async fn dial_failure_quic() {
 
 #[cfg(feature = "websocket")]
 #[tokio::test]
-async fn dial_failure_websocket() {
-    dial_failure(
+async fn auto_accept_all_inbound_websocket() {
+    auto_accept_all_inbound(
         Transport::WebSocket(Default::default()),
         Transport::WebSocket(Default::default()),
     )
     .await;
 }
 
-async fn dial_failure(transport1: Transport, transport2: Transport) {
+async fn auto_accept_all_inbound(transport1: Transport, transport2: Transport) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .try_init();
@@ -3398,59 +3563,34 @@ async fn dial_failure(transport1: Transport, transport2: Transport) {
         .with_max_size(1024usize)
         .with_handshake(vec![1, 2, 3, 4])
         .with_auto_accept_inbound(true)
-        .with_sync_channel_size(1024usize)
-        .with_async_channel_size(1024usize)
-        .build();
-    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/2"))
-        .with_max_size(1024usize)
-        .with_handshake(vec![7, 7, 7, 7])
         .build();
 
     let config1 = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_notification_protocol(notif_config1)
-        .with_notification_protocol(notif_config2);
+        .with_notification_protocol(notif_config1);
 
     let config1 = add_transport(config1, transport1).build();
 
-    let (notif_config3, _handle3) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
         .with_max_size(1024usize)
-        .with_handshake(vec![1, 2, 3, 4])
-        .with_auto_accept_inbound(true)
-        .with_sync_channel_size(1024usize)
-        .with_async_channel_size(1024usize)
+        .with_handshake(vec![1, 3, 3, 7])
+        .with_auto_accept_all_inbound(true)
         .build();
 
     let config2 = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_notification_protocol(notif_config3);
-
-    let known_address = match &transport2 {
-        Transport::Tcp(_) => Multiaddr::empty()
-            .with(Protocol::Ip6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
-            .with(Protocol::Tcp(5)),
-        #[cfg(feature = "quic")]
-        Transport::Quic(_) => Multiaddr::empty()
-            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
-            .with(Protocol::Udp(5))
-            .with(Protocol::QuicV1),
-        #[cfg(feature = "websocket")]
-        Transport::WebSocket(_) => Multiaddr::empty()
-            .with(Protocol::Ip6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
-            .with(Protocol::Tcp(5))
-            .with(Protocol::Ws(std::borrow::Cow::Owned("/".to_string()))),
-    };
+        .with_notification_protocol(notif_config2);
 
     let config2 = add_transport(config2, transport2).build();
 
     let mut litep2p1 = Litep2p::new(config1).unwrap();
     let mut litep2p2 = Litep2p::new(config2).unwrap();
 
+    let peer1 = *litep2p1.local_peer_id();
     let peer2 = *litep2p2.local_peer_id();
-    let known_address = known_address.with(Protocol::P2p(Multihash::from(peer2)));
-
-    litep2p1.add_known_address(peer2, vec![known_address].into_iter());
 
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
     tokio::spawn(async move {
         loop {
             tokio::select! {
@@ -3460,26 +3600,34 @@ async fn dial_failure(transport1: Transport, transport2: Transport) {
         }
     });
 
-    // open substream for `peer2` and accept it
+    // open substream to `peer2` which accepts it without emitting `ValidateSubstream`
     handle1.open_substream(peer2).await.unwrap();
+
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            direction: Direction::Inbound,
+            fallback: None,
+            peer: peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
     assert_eq!(
         handle1.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpenFailure {
+        NotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            direction: Direction::Outbound,
             peer: peer2,
-            error: NotificationError::DialFailure,
+            handshake: vec![1, 3, 3, 7],
         }
     );
-
-    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
-        Poll::Pending => Poll::Ready(()),
-        _ => panic!("invalid event"),
-    })
-    .await;
 }
 
 #[tokio::test]
-async fn dialing_disabled_tcp() {
-    dialing_disabled(
+async fn highest_common_version_negotiated_tcp() {
+    highest_common_version_negotiated(
         Transport::Tcp(Default::default()),
         Transport::Tcp(Default::default()),
     )
@@ -3488,8 +3636,8 @@ async fn dialing_disabled_tcp() {
 
 #[cfg(feature = "quic")]
 #[tokio::test]
-async fn dialing_disabled_quic() {
-    dialing_disabled(
+async fn highest_common_version_negotiated_quic() {
+    highest_common_version_negotiated(
         Transport::Quic(Default::default()),
         Transport::Quic(Default::default()),
     )
@@ -3498,62 +3646,52 @@ async fn dialing_disabled_quic() {
 
 #[cfg(feature = "websocket")]
 #[tokio::test]
-async fn dialing_disabled_websocket() {
-    dialing_disabled(
+async fn highest_common_version_negotiated_websocket() {
+    highest_common_version_negotiated(
         Transport::WebSocket(Default::default()),
         Transport::WebSocket(Default::default()),
     )
     .await;
 }
 
-async fn dialing_disabled(transport1: Transport, transport2: Transport) {
+async fn highest_common_version_negotiated(transport1: Transport, transport2: Transport) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .try_init();
 
-    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+    let versions1 = VersionedProtocol::new("/notif", 1..=3);
+    let (notif_config1, mut handle1) = ConfigBuilder::new_versioned(&versions1)
         .with_max_size(1024usize)
         .with_handshake(vec![1, 2, 3, 4])
         .with_auto_accept_inbound(true)
-        .with_sync_channel_size(1024usize)
-        .with_async_channel_size(1024usize)
-        .with_dialing_enabled(false)
-        .build();
-    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/2"))
-        .with_max_size(1024usize)
-        .with_handshake(vec![7, 7, 7, 7])
-        .with_dialing_enabled(false)
         .build();
 
     let config1 = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_notification_protocol(notif_config1)
-        .with_notification_protocol(notif_config2);
+        .with_notification_protocol(notif_config1);
 
     let config1 = add_transport(config1, transport1).build();
 
-    let (notif_config3, _handle3) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+    let versions2 = VersionedProtocol::new("/notif", 2..=4);
+    let (notif_config2, mut handle2) = ConfigBuilder::new_versioned(&versions2)
         .with_max_size(1024usize)
         .with_handshake(vec![1, 2, 3, 4])
-        .with_auto_accept_inbound(true)
-        .with_sync_channel_size(1024usize)
-        .with_async_channel_size(1024usize)
         .build();
 
     let config2 = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_notification_protocol(notif_config3);
+        .with_notification_protocol(notif_config2);
 
     let config2 = add_transport(config2, transport2).build();
 
     let mut litep2p1 = Litep2p::new(config1).unwrap();
     let mut litep2p2 = Litep2p::new(config2).unwrap();
 
+    let peer1 = *litep2p1.local_peer_id();
     let peer2 = *litep2p2.local_peer_id();
-    let listen_address = litep2p2.listen_addresses().next().unwrap().clone();
-
-    litep2p1.add_known_address(peer2, vec![listen_address].into_iter());
 
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
     tokio::spawn(async move {
         loop {
             tokio::select! {
@@ -3563,60 +3701,1332 @@ async fn dialing_disabled(transport1: Transport, transport2: Transport) {
         }
     });
 
-    // open substream for `peer2` and accept it
     handle1.open_substream(peer2).await.unwrap();
-    assert_eq!(
-        handle1.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpenFailure {
-            peer: peer2,
-            error: NotificationError::DialFailure,
-        }
-    );
 
-    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
-        Poll::Pending => Poll::Ready(()),
-        _ => panic!("invalid event"),
-    })
-    .await;
-}
+    match handle2.next().await.unwrap() {
+        NotificationEvent::ValidateSubstream {
+            protocol,
+            fallback,
+            peer,
+            ..
+        } => {
+            assert_eq!(peer, peer1);
+            assert_eq!(versions2.version(&fallback.unwrap_or(protocol)), Some(3));
+        }
+        event => panic!("unexpected event: {event:?}"),
+    }
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
 
-#[tokio::test]
-async fn validation_takes_too_long_tcp() {
-    validation_takes_too_long(
-        Transport::Tcp(Default::default()),
-        Transport::Tcp(Default::default()),
-    )
-    .await
+    match handle2.next().await.unwrap() {
+        NotificationEvent::NotificationStreamOpened {
+            protocol,
+            fallback,
+            peer,
+            ..
+        } => {
+            assert_eq!(peer, peer1);
+            assert_eq!(versions2.version(&fallback.unwrap_or(protocol)), Some(3));
+        }
+        event => panic!("unexpected event: {event:?}"),
+    }
+    match handle1.next().await.unwrap() {
+        NotificationEvent::NotificationStreamOpened {
+            protocol,
+            fallback,
+            peer,
+            ..
+        } => {
+            assert_eq!(peer, peer2);
+            assert_eq!(versions1.version(&fallback.unwrap_or(protocol)), Some(3));
+        }
+        event => panic!("unexpected event: {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn peer_state_transitions_observed_tcp() {
+    peer_state_transitions_observed(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn peer_state_transitions_observed_quic() {
+    peer_state_transitions_observed(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn peer_state_transitions_observed_websocket() {
+    peer_state_transitions_observed(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn peer_state_transitions_observed(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .with_peer_state_observer(PeerStateObserver::new(move |peer, old, new| {
+            let _ = tx.send((peer, old, new));
+        }))
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 3, 3, 7])
+        .with_auto_accept_all_inbound(true)
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    handle1.open_substream(peer2).await.unwrap();
+
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+
+    handle1.close_substream(peer2).await;
+
+    let mut transitions = Vec::new();
+    while let Some((peer, old, new)) = rx.recv().await {
+        assert_eq!(peer, peer2);
+
+        // the peer is dialed if the protocol is notified of the connection after the substream
+        // open request has been received
+        if new == PeerStateKind::Dialing {
+            continue;
+        }
+        transitions.push(new);
+
+        if old == PeerStateKind::Open {
+            break;
+        }
+    }
+
+    assert_eq!(
+        transitions,
+        vec![
+            PeerStateKind::OutboundInitiated,
+            PeerStateKind::Validating,
+            PeerStateKind::Open,
+            PeerStateKind::Closed,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn dial_failure_tcp() {
+    dial_failure(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn dial_failure_quic() {
+    dial_failure(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn dial_failure_websocket() {
+    dial_failure(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn dial_failure(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .with_sync_channel_size(1024usize)
+        .with_async_channel_size(1024usize)
+        .build();
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/2"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![7, 7, 7, 7])
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1)
+        .with_notification_protocol(notif_config2);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config3, _handle3) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .with_sync_channel_size(1024usize)
+        .with_async_channel_size(1024usize)
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config3);
+
+    let known_address = match &transport2 {
+        Transport::Tcp(_) => Multiaddr::empty()
+            .with(Protocol::Ip6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
+            .with(Protocol::Tcp(5)),
+        #[cfg(feature = "quic")]
+        Transport::Quic(_) => Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::new(127, 0, 0, 1)))
+            .with(Protocol::Udp(5))
+            .with(Protocol::QuicV1),
+        #[cfg(feature = "websocket")]
+        Transport::WebSocket(_) => Multiaddr::empty()
+            .with(Protocol::Ip6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
+            .with(Protocol::Tcp(5))
+            .with(Protocol::Ws(std::borrow::Cow::Owned("/".to_string()))),
+        #[cfg(unix)]
+        Transport::Unix(_) =>
+            Multiaddr::empty().with(Protocol::Unix("/tmp/litep2p-unreachable.sock".into())),
+    };
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer2 = *litep2p2.local_peer_id();
+    let known_address = known_address.with(Protocol::P2p(Multihash::from(peer2)));
+
+    litep2p1.add_known_address(peer2, vec![known_address].into_iter());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer2,
+            error: NotificationError::DialFailure,
+        }
+    );
+
+    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
+        Poll::Pending => Poll::Ready(()),
+        _ => panic!("invalid event"),
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn dialing_disabled_tcp() {
+    dialing_disabled(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn dialing_disabled_quic() {
+    dialing_disabled(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn dialing_disabled_websocket() {
+    dialing_disabled(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn dialing_disabled(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .with_sync_channel_size(1024usize)
+        .with_async_channel_size(1024usize)
+        .with_dialing_enabled(false)
+        .build();
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/2"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![7, 7, 7, 7])
+        .with_dialing_enabled(false)
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1)
+        .with_notification_protocol(notif_config2);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config3, _handle3) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .with_sync_channel_size(1024usize)
+        .with_async_channel_size(1024usize)
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config3);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer2 = *litep2p2.local_peer_id();
+    let listen_address = litep2p2.listen_addresses().next().unwrap().clone();
+
+    litep2p1.add_known_address(peer2, vec![listen_address].into_iter());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer2,
+            error: NotificationError::DialFailure,
+        }
+    );
+
+    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
+        Poll::Pending => Poll::Ready(()),
+        _ => panic!("invalid event"),
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn validation_takes_too_long_tcp() {
+    validation_takes_too_long(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn validation_takes_too_long_quic() {
+    validation_takes_too_long(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn validation_takes_too_long_websocket() {
+    validation_takes_too_long(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn validation_takes_too_long(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config3, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config3);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+    let listen_address = litep2p2.listen_addresses().next().unwrap().clone();
+
+    litep2p1.add_known_address(peer2, vec![listen_address].into_iter());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer2,
+            error: NotificationError::Rejected,
+        }
+    );
+
+    // give theh connection a moment to close
+    tokio::time::sleep(Duration::from_secs(5)).await;
+
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer1,
+            error: NotificationError::NoConnection,
+        }
+    );
+}
+
+#[tokio::test]
+async fn ignored_validation_open_substream_tcp() {
+    ignored_validation_open_substream(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn ignored_validation_open_substream_quic() {
+    ignored_validation_open_substream(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn ignored_validation_open_substream_websocket() {
+    ignored_validation_open_substream(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn ignored_validation_open_substream(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config3, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config3);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+    let listen_address = litep2p2.listen_addresses().next().unwrap().clone();
+
+    litep2p1.add_known_address(peer2, vec![listen_address].into_iter());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer2,
+            error: NotificationError::Rejected,
+        }
+    );
+
+    // wait a moment to allow the connection to close
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    // verify that there are no events pending
+    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
+        Poll::Pending => Poll::Ready(()),
+        event => panic!("invalid event: {event:?}"),
+    })
+    .await;
+
+    // try to open a substream while the previous validation is still in progress
+    // and verify that the substream is rejected with `ValidationPending`
+    handle2.open_substream(peer1).await.unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer1,
+            error: NotificationError::ValidationPending,
+        }
+    );
+
+    // try to open substream as `peer1` and verify the inbound substream gets rejected
+    // because the previous substream is still pending
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer2,
+            error: NotificationError::Rejected,
+        }
+    );
+
+    // verify `peer2` is not notified of the new substream
+    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
+        Poll::Pending => Poll::Ready(()),
+        event => panic!("invalid event: {event:?}"),
+    })
+    .await;
+
+    // finally try to accept the original substream and verify it fails to open with `NoConnection`
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer1,
+            error: NotificationError::Rejected,
+        }
+    );
+}
+
+#[tokio::test]
+async fn clogged_channel_disconnects_peer_tcp() {
+    clogged_channel_disconnects_peer(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn clogged_channel_disconnects_peer_quic() {
+    clogged_channel_disconnects_peer(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn clogged_channel_disconnects_peer_websocket() {
+    clogged_channel_disconnects_peer(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn clogged_channel_disconnects_peer(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(100 * 1024)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config3, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(100 * 1024)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config3);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+    let listen_address = litep2p2.listen_addresses().next().unwrap().clone();
+
+    litep2p1.add_known_address(peer2, vec![listen_address].into_iter());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
+
+    // verify both peers have the substream open
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: peer2,
+            handshake: vec![1, 2, 3, 4],
+            direction: Direction::Outbound,
+        }
+    );
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: peer1,
+            handshake: vec![1, 2, 3, 4],
+            direction: Direction::Inbound,
+        }
+    );
+
+    // start sending notifications to `peer2` which never reads them,
+    // causing `peer1` to consume all available credit
+    loop {
+        match handle1.send_sync_notification(peer2, vec![0u8; 99 * 1024]) {
+            Ok(()) => {}
+            Err(NotificationError::ChannelClogged) => break,
+            error => panic!("invalid error: {error:?}"),
+        }
+    }
+
+    // stream closed from `peer1`'s PoV
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamClosed { peer: peer2 },
+    );
+
+    // `peer2` is also reported that the substream is closed
+    match tokio::time::timeout(Duration::from_secs(5), async move {
+        loop {
+            if let Some(NotificationEvent::NotificationStreamClosed { peer }) = handle2.next().await
+            {
+                assert_eq!(peer, peer1);
+                break;
+            }
+        }
+    })
+    .await
+    {
+        Err(_) => panic!("timeout"),
+        Ok(()) => {}
+    }
+}
+
+#[tokio::test]
+async fn idle_substream_doesnt_keep_connection_alive_tcp() {
+    idle_substream_doesnt_keep_connection_alive(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn idle_substream_doesnt_keep_connection_alive_quic() {
+    idle_substream_doesnt_keep_connection_alive(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn idle_substream_doesnt_keep_connection_alive_websocket() {
+    idle_substream_doesnt_keep_connection_alive(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn idle_substream_doesnt_keep_connection_alive(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_substream_keep_alive(false)
+        .build();
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_keep_alive_timeout(Duration::from_secs(3))
+        .with_notification_protocol(notif_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_substream_keep_alive(false)
+        .build();
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_keep_alive_timeout(Duration::from_secs(3))
+        .with_notification_protocol(notif_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+
+    let (closed_tx, mut closed_rx) = tokio::sync::mpsc::channel(2);
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                event = litep2p1.next_event() => if let Some(Litep2pEvent::ConnectionClosed { .. }) = event {
+                    closed_tx.send(peer1).await.unwrap();
+                },
+                event = litep2p2.next_event() => if let Some(Litep2pEvent::ConnectionClosed { .. }) = event {
+                    closed_tx.send(peer2).await.unwrap();
+                },
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream { .. }
+    ));
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream { .. }
+    ));
+    handle1.send_validation_result(peer2, ValidationResult::Accept);
+
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+
+    // the substreams are idle so the connection is closed after the keep-alive timeout,
+    // closing the notification substreams with it
+    match tokio::time::timeout(Duration::from_secs(20), async move {
+        assert_eq!(
+            handle1.next().await.unwrap(),
+            NotificationEvent::NotificationStreamClosed { peer: peer2 }
+        );
+        assert_eq!(
+            handle2.next().await.unwrap(),
+            NotificationEvent::NotificationStreamClosed { peer: peer1 }
+        );
+
+        let mut closed = vec![
+            closed_rx.recv().await.unwrap(),
+            closed_rx.recv().await.unwrap(),
+        ];
+        closed.sort();
+
+        let mut expected = vec![peer1, peer2];
+        expected.sort();
+        assert_eq!(closed, expected);
+    })
+    .await
+    {
+        Err(_) => panic!("connection was not closed"),
+        Ok(()) => {}
+    }
+}
+
+#[tokio::test]
+async fn active_substream_keeps_connection_alive_tcp() {
+    active_substream_keeps_connection_alive(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn active_substream_keeps_connection_alive_quic() {
+    active_substream_keeps_connection_alive(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn active_substream_keeps_connection_alive_websocket() {
+    active_substream_keeps_connection_alive(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn active_substream_keeps_connection_alive(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_substream_keep_alive(false)
+        .build();
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_keep_alive_timeout(Duration::from_secs(2))
+        .with_notification_protocol(notif_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(vec![1, 2, 3, 4])
+        .with_substream_keep_alive(false)
+        .build();
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_keep_alive_timeout(Duration::from_secs(2))
+        .with_notification_protocol(notif_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+
+    let (closed_tx, mut closed_rx) = tokio::sync::mpsc::channel(2);
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                event = litep2p1.next_event() => {
+                    if let Some(Litep2pEvent::ConnectionClosed { .. }) = event {
+                        closed_tx.send(peer1).await.unwrap();
+                    }
+                },
+                event = litep2p2.next_event() => {
+                    if let Some(Litep2pEvent::ConnectionClosed { .. }) = event {
+                        closed_tx.send(peer2).await.unwrap();
+                    }
+                },
+            }
+        }
+    });
+
+    // open substream for `peer2` and accept it
+    handle1.open_substream(peer2).await.unwrap();
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream { .. }
+    ));
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream { .. }
+    ));
+    handle1.send_validation_result(peer2, ValidationResult::Accept);
+
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { .. }
+    ));
+
+    // send notifications for several keep-alive timeouts and verify that the traffic keeps
+    // the connection open
+    for i in 0..16u8 {
+        handle1.send_sync_notification(peer2, vec![1, 3, 3, i]).unwrap();
+        assert_eq!(
+            handle2.next().await.unwrap(),
+            NotificationEvent::NotificationReceived {
+                peer: peer1,
+                notification: BytesMut::from(&[1, 3, 3, i][..]),
+            }
+        );
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    assert!(closed_rx.try_recv().is_err());
+
+    // once the substreams are idle, the connection is closed after the keep-alive timeout
+    match tokio::time::timeout(Duration::from_secs(20), async move {
+        assert_eq!(
+            handle1.next().await.unwrap(),
+            NotificationEvent::NotificationStreamClosed { peer: peer2 }
+        );
+        assert_eq!(
+            handle2.next().await.unwrap(),
+            NotificationEvent::NotificationStreamClosed { peer: peer1 }
+        );
+
+        let mut closed = vec![
+            closed_rx.recv().await.unwrap(),
+            closed_rx.recv().await.unwrap(),
+        ];
+        closed.sort();
+
+        let mut expected = vec![peer1, peer2];
+        expected.sort();
+        assert_eq!(closed, expected);
+    })
+    .await
+    {
+        Err(_) => panic!("connection was not closed"),
+        Ok(()) => {}
+    }
+}
+
+#[tokio::test]
+async fn pause_and_resume_tcp() {
+    pause_and_resume(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn pause_and_resume_quic() {
+    pause_and_resume(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn pause_and_resume_websocket() {
+    pause_and_resume(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn pause_and_resume(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (notif_config1, mut handle1) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (notif_config2, mut handle2) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_notification_protocol(notif_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // pause the protocol of `peer2` and verify it can't open substreams
+    handle2.pause(false).await;
+    assert!(handle2.is_paused());
+    assert!(std::matches!(
+        handle2.open_substream(peer1).await,
+        Err(Error::NotificationError(NotificationError::Paused))
+    ));
+
+    // inbound substream is rejected without asking the user to validate it
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpenFailure {
+            peer: peer2,
+            error: NotificationError::Rejected,
+        }
+    );
+    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
+        Poll::Ready(event) => panic!("invalid event received: {event:?}"),
+        Poll::Pending => Poll::Ready(()),
+    })
+    .await;
+
+    // resume the protocol and verify that inbound substreams are accepted again
+    handle2.resume();
+    handle1.open_substream(peer2).await.unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
+
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            direction: Direction::Inbound,
+            fallback: None,
+            peer: peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            direction: Direction::Outbound,
+            fallback: None,
+            peer: peer2,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+
+    // notifications can't be sent while the protocol is paused
+    handle2.pause(false).await;
+    assert_eq!(
+        handle2.send_sync_notification(peer1, vec![1, 3, 3, 7]),
+        Err(NotificationError::Paused),
+    );
+
+    handle2.resume();
+    handle2.send_sync_notification(peer1, vec![1, 3, 3, 8]).unwrap();
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationReceived {
+            peer: peer2,
+            notification: BytesMut::from(&[1, 3, 3, 8][..]),
+        }
+    );
+}
+
+#[tokio::test]
+async fn compressed_notifications_tcp() {
+    compressed_notifications(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        true,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn compression_falls_back_to_uncompressed_tcp() {
+    compressed_notifications(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        false,
+    )
+    .await
 }
 
 #[cfg(feature = "quic")]
 #[tokio::test]
-async fn validation_takes_too_long_quic() {
-    validation_takes_too_long(
+async fn compressed_notifications_quic() {
+    compressed_notifications(
         Transport::Quic(Default::default()),
         Transport::Quic(Default::default()),
+        true,
     )
     .await;
 }
 
 #[cfg(feature = "websocket")]
 #[tokio::test]
-async fn validation_takes_too_long_websocket() {
-    validation_takes_too_long(
-        Transport::WebSocket(Default::default()),
-        Transport::WebSocket(Default::default()),
+async fn compressed_notifications_websocket() {
+    compressed_notifications(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        true,
     )
     .await;
 }
 
-async fn validation_takes_too_long(transport1: Transport, transport2: Transport) {
+async fn compressed_notifications(
+    transport1: Transport,
+    transport2: Transport,
+    remote_compression: bool,
+) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .try_init();
 
     let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
-        .with_max_size(1024usize)
+        .with_max_size(128 * 1024)
         .with_handshake(vec![1, 2, 3, 4])
+        .with_compression(Compression::Snappy)
         .build();
 
     let config1 = Litep2pConfigBuilder::new()
@@ -3625,26 +5035,29 @@ async fn validation_takes_too_long(transport1: Transport, transport2: Transport)
 
     let config1 = add_transport(config1, transport1).build();
 
-    let (notif_config3, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
-        .with_max_size(1024usize)
-        .with_handshake(vec![1, 2, 3, 4])
-        .build();
+    let builder2 = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(128 * 1024)
+        .with_handshake(vec![1, 2, 3, 4]);
+    let (notif_config2, mut handle2) = match remote_compression {
+        true => builder2.with_compression(Compression::Snappy).build(),
+        false => builder2.build(),
+    };
 
     let config2 = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_notification_protocol(notif_config3);
+        .with_notification_protocol(notif_config2);
 
     let config2 = add_transport(config2, transport2).build();
 
     let mut litep2p1 = Litep2p::new(config1).unwrap();
     let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let bandwidth1 = litep2p1.bandwidth_sink();
 
     let peer1 = *litep2p1.local_peer_id();
     let peer2 = *litep2p2.local_peer_id();
-    let listen_address = litep2p2.listen_addresses().next().unwrap().clone();
-
-    litep2p1.add_known_address(peer2, vec![listen_address].into_iter());
 
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
     tokio::spawn(async move {
         loop {
             tokio::select! {
@@ -3654,7 +5067,6 @@ async fn validation_takes_too_long(transport1: Transport, transport2: Transport)
         }
     });
 
-    // open substream for `peer2` and accept it
     handle1.open_substream(peer2).await.unwrap();
     assert_eq!(
         handle2.next().await.unwrap(),
@@ -3665,31 +5077,61 @@ async fn validation_takes_too_long(transport1: Transport, transport2: Transport)
             handshake: vec![1, 2, 3, 4],
         }
     );
+    handle2.send_validation_result(peer1, ValidationResult::Accept);
 
     assert_eq!(
         handle1.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpenFailure {
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
             peer: peer2,
-            error: NotificationError::Rejected,
+            handshake: vec![1, 2, 3, 4],
         }
     );
+    handle1.send_validation_result(peer2, ValidationResult::Accept);
 
-    // give theh connection a moment to close
-    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert!(std::matches!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { fallback: None, .. }
+    ));
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { fallback: None, .. }
+    ));
 
-    handle2.send_validation_result(peer1, ValidationResult::Accept);
+    // send a highly compressible notification in both directions
+    let notification = vec![7u8; 64 * 1024];
+    let sent = bandwidth1.outbound();
+
+    handle1.send_sync_notification(peer2, notification.clone()).unwrap();
     assert_eq!(
         handle2.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpenFailure {
+        NotificationEvent::NotificationReceived {
             peer: peer1,
-            error: NotificationError::NoConnection,
+            notification: BytesMut::from(&notification[..]),
+        }
+    );
+
+    handle2.send_sync_notification(peer1, notification.clone()).unwrap();
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationReceived {
+            peer: peer2,
+            notification: BytesMut::from(&notification[..]),
         }
     );
+
+    // the notification is compressed on the wire only if both peers support compression
+    let sent = bandwidth1.outbound() - sent;
+    match remote_compression {
+        true => assert!(sent < notification.len() / 4, "sent {sent} bytes"),
+        false => assert!(sent >= notification.len(), "sent {sent} bytes"),
+    }
 }
 
 #[tokio::test]
-async fn ignored_validation_open_substream_tcp() {
-    ignored_validation_open_substream(
+async fn credit_based_flow_control_tcp() {
+    credit_based_flow_control(
         Transport::Tcp(Default::default()),
         Transport::Tcp(Default::default()),
     )
@@ -3698,8 +5140,8 @@ async fn ignored_validation_open_substream_tcp() {
 
 #[cfg(feature = "quic")]
 #[tokio::test]
-async fn ignored_validation_open_substream_quic() {
-    ignored_validation_open_substream(
+async fn credit_based_flow_control_quic() {
+    credit_based_flow_control(
         Transport::Quic(Default::default()),
         Transport::Quic(Default::default()),
     )
@@ -3708,22 +5150,27 @@ async fn ignored_validation_open_substream_quic() {
 
 #[cfg(feature = "websocket")]
 #[tokio::test]
-async fn ignored_validation_open_substream_websocket() {
-    ignored_validation_open_substream(
+async fn credit_based_flow_control_websocket() {
+    credit_based_flow_control(
         Transport::WebSocket(Default::default()),
         Transport::WebSocket(Default::default()),
     )
     .await;
 }
 
-async fn ignored_validation_open_substream(transport1: Transport, transport2: Transport) {
+async fn credit_based_flow_control(transport1: Transport, transport2: Transport) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .try_init();
 
+    const WINDOW: usize = 4;
+
     let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
         .with_max_size(1024usize)
         .with_handshake(vec![1, 2, 3, 4])
+        .with_auto_accept_inbound(true)
+        .with_sync_channel_size(1usize)
+        .with_credit(WINDOW)
         .build();
 
     let config1 = Litep2pConfigBuilder::new()
@@ -3732,14 +5179,16 @@ async fn ignored_validation_open_substream(transport1: Transport, transport2: Tr
 
     let config1 = add_transport(config1, transport1).build();
 
-    let (notif_config3, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
         .with_max_size(1024usize)
-        .with_handshake(vec![1, 2, 3, 4])
+        .with_handshake(vec![1, 3, 3, 7])
+        .with_auto_accept_all_inbound(true)
+        .with_credit(WINDOW)
         .build();
 
     let config2 = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_notification_protocol(notif_config3);
+        .with_notification_protocol(notif_config2);
 
     let config2 = add_transport(config2, transport2).build();
 
@@ -3748,10 +5197,9 @@ async fn ignored_validation_open_substream(transport1: Transport, transport2: Tr
 
     let peer1 = *litep2p1.local_peer_id();
     let peer2 = *litep2p2.local_peer_id();
-    let listen_address = litep2p2.listen_addresses().next().unwrap().clone();
-
-    litep2p1.add_known_address(peer2, vec![listen_address].into_iter());
 
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
     tokio::spawn(async move {
         loop {
             tokio::select! {
@@ -3761,130 +5209,129 @@ async fn ignored_validation_open_substream(transport1: Transport, transport2: Tr
         }
     });
 
-    // open substream for `peer2` and accept it
     handle1.open_substream(peer2).await.unwrap();
-    assert_eq!(
-        handle2.next().await.unwrap(),
-        NotificationEvent::ValidateSubstream {
-            protocol: ProtocolName::from("/notif/1"),
-            fallback: None,
-            peer: peer1,
-            handshake: vec![1, 2, 3, 4],
-        }
-    );
-    assert_eq!(
-        handle1.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpenFailure {
-            peer: peer2,
-            error: NotificationError::Rejected,
-        }
-    );
-
-    // wait a moment to allow the connection to close
-    tokio::time::sleep(Duration::from_secs(2)).await;
-
-    // verify that there are no events pending
-    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
-        Poll::Pending => Poll::Ready(()),
-        event => panic!("invalid event: {event:?}"),
-    })
-    .await;
 
-    // try to open a substream while the previous validation is still in progress
-    // and verify that the substream is rejected with `ValidationPending`
-    handle2.open_substream(peer1).await.unwrap();
-    assert_eq!(
+    assert!(std::matches!(
         handle2.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpenFailure {
-            peer: peer1,
-            error: NotificationError::ValidationPending,
-        }
-    );
-
-    // try to open substream as `peer1` and verify the inbound substream gets rejected
-    // because the previous substream is still pending
-    handle1.open_substream(peer2).await.unwrap();
-    assert_eq!(
+        NotificationEvent::NotificationStreamOpened { fallback: None, .. }
+    ));
+    assert!(std::matches!(
         handle1.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpenFailure {
-            peer: peer2,
-            error: NotificationError::Rejected,
+        NotificationEvent::NotificationStreamOpened { fallback: None, .. }
+    ));
+
+    // send notifications until the channel stays clogged, without reading them on the receiver
+    let sink = handle1.notification_sink(peer2).unwrap();
+    let mut sent = 0usize;
+    let mut clogged = 0usize;
+
+    while clogged < 5 {
+        match sink.send_sync_notification(vec![sent as u8]) {
+            Ok(()) => {
+                sent += 1;
+                clogged = 0;
+            }
+            Err(NotificationError::ChannelClogged) => clogged += 1,
+            Err(error) => panic!("unexpected error: {error:?}"),
         }
-    );
 
-    // verify `peer2` is not notified of the new substream
-    futures::future::poll_fn(|cx| match handle2.poll_next_unpin(cx) {
-        Poll::Pending => Poll::Ready(()),
-        event => panic!("invalid event: {event:?}"),
-    })
-    .await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
 
-    // finally try to accept the original substream and verify it fails to open with `NoConnection`
-    handle2.send_validation_result(peer1, ValidationResult::Accept);
+    // the sender stalls after `WINDOW` unacknowledged notifications: the next notification is
+    // held in the outbound queue and the one after that in the channel
+    assert_eq!(sent, WINDOW + 2);
+
+    // reading the notifications grants credit back to the sender which sends the stalled
+    // notifications
+    for expected in 0..sent {
+        assert_eq!(
+            handle2.next().await.unwrap(),
+            NotificationEvent::NotificationReceived {
+                peer: peer1,
+                notification: BytesMut::from(&[expected as u8][..]),
+            }
+        );
+    }
+
+    // and can send new notifications again
+    sink.send_sync_notification(vec![sent as u8]).unwrap();
     assert_eq!(
         handle2.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpenFailure {
+        NotificationEvent::NotificationReceived {
             peer: peer1,
-            error: NotificationError::Rejected,
+            notification: BytesMut::from(&[sent as u8][..]),
         }
     );
 }
 
+#[cfg(feature = "traffic-tap")]
 #[tokio::test]
-async fn clogged_channel_disconnects_peer_tcp() {
-    clogged_channel_disconnects_peer(
+async fn traffic_tap_tcp() {
+    traffic_tap(
         Transport::Tcp(Default::default()),
         Transport::Tcp(Default::default()),
     )
     .await
 }
 
-#[cfg(feature = "quic")]
+#[cfg(all(feature = "traffic-tap", feature = "quic"))]
 #[tokio::test]
-async fn clogged_channel_disconnects_peer_quic() {
-    clogged_channel_disconnects_peer(
+async fn traffic_tap_quic() {
+    traffic_tap(
         Transport::Quic(Default::default()),
         Transport::Quic(Default::default()),
     )
     .await;
 }
 
-#[cfg(feature = "websocket")]
+#[cfg(all(feature = "traffic-tap", feature = "websocket"))]
 #[tokio::test]
-async fn clogged_channel_disconnects_peer_websocket() {
-    clogged_channel_disconnects_peer(
+async fn traffic_tap_websocket() {
+    traffic_tap(
         Transport::WebSocket(Default::default()),
         Transport::WebSocket(Default::default()),
     )
     .await;
 }
 
-async fn clogged_channel_disconnects_peer(transport1: Transport, transport2: Transport) {
+#[cfg(feature = "traffic-tap")]
+async fn traffic_tap(transport1: Transport, transport2: Transport) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .try_init();
 
+    const HANDSHAKE: [u8; 4] = [1, 2, 3, 4];
+
+    let (tap_tx1, mut tap_rx1) = tokio::sync::mpsc::channel(64);
+    let (tap_tx2, mut tap_rx2) = tokio::sync::mpsc::channel(64);
+
     let (notif_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
-        .with_max_size(100 * 1024)
-        .with_handshake(vec![1, 2, 3, 4])
+        .with_max_size(1024usize)
+        .with_handshake(HANDSHAKE.to_vec())
         .with_auto_accept_inbound(true)
         .build();
 
     let config1 = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_notification_protocol(notif_config1);
+        .with_notification_protocol(notif_config1)
+        .with_traffic_tap(
+            ProtocolName::from("/notif/1"),
+            TrafficTap::new(tap_tx1).with_outbound(true),
+        );
 
     let config1 = add_transport(config1, transport1).build();
 
-    let (notif_config3, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
-        .with_max_size(100 * 1024)
-        .with_handshake(vec![1, 2, 3, 4])
+    let (notif_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/notif/1"))
+        .with_max_size(1024usize)
+        .with_handshake(HANDSHAKE.to_vec())
         .with_auto_accept_inbound(true)
         .build();
 
     let config2 = Litep2pConfigBuilder::new()
         .with_keypair(Keypair::generate())
-        .with_notification_protocol(notif_config3);
+        .with_notification_protocol(notif_config2)
+        .with_traffic_tap(ProtocolName::from("/notif/1"), TrafficTap::new(tap_tx2));
 
     let config2 = add_transport(config2, transport2).build();
 
@@ -3893,10 +5340,9 @@ async fn clogged_channel_disconnects_peer(transport1: Transport, transport2: Tra
 
     let peer1 = *litep2p1.local_peer_id();
     let peer2 = *litep2p2.local_peer_id();
-    let listen_address = litep2p2.listen_addresses().next().unwrap().clone();
-
-    litep2p1.add_known_address(peer2, vec![listen_address].into_iter());
 
+    // wait until peers have connected and spawn the litep2p objects in the background
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
     tokio::spawn(async move {
         loop {
             tokio::select! {
@@ -3906,70 +5352,80 @@ async fn clogged_channel_disconnects_peer(transport1: Transport, transport2: Tra
         }
     });
 
-    // open substream for `peer2` and accept it
     handle1.open_substream(peer2).await.unwrap();
-    assert_eq!(
-        handle2.next().await.unwrap(),
-        NotificationEvent::ValidateSubstream {
-            protocol: ProtocolName::from("/notif/1"),
-            fallback: None,
-            peer: peer1,
-            handshake: vec![1, 2, 3, 4],
-        }
-    );
-    handle2.send_validation_result(peer1, ValidationResult::Accept);
 
-    // verify both peers have the substream open
-    assert_eq!(
-        handle1.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpened {
-            protocol: ProtocolName::from("/notif/1"),
-            fallback: None,
-            peer: peer2,
-            handshake: vec![1, 2, 3, 4],
-            direction: Direction::Outbound,
-        }
-    );
-    assert_eq!(
+    assert!(std::matches!(
         handle2.next().await.unwrap(),
-        NotificationEvent::NotificationStreamOpened {
-            protocol: ProtocolName::from("/notif/1"),
-            fallback: None,
-            peer: peer1,
-            handshake: vec![1, 2, 3, 4],
-            direction: Direction::Inbound,
-        }
-    );
+        NotificationEvent::NotificationStreamOpened { fallback: None, .. }
+    ));
+    assert!(std::matches!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened { fallback: None, .. }
+    ));
+
+    let notifications = (0..5u8).map(|i| vec![0xaa, i]).collect::<Vec<_>>();
+
+    for notification in &notifications {
+        handle1.send_async_notification(peer2, notification.clone()).await.unwrap();
+        assert_eq!(
+            handle2.next().await.unwrap(),
+            NotificationEvent::NotificationReceived {
+                peer: peer1,
+                notification: BytesMut::from(&notification[..]),
+            }
+        );
+    }
 
-    // start sending notifications to `peer2` which never reads them,
-    // causing `peer1` to consume all available credit
-    loop {
-        match handle1.send_sync_notification(peer2, vec![0u8; 99 * 1024]) {
-            Ok(()) => {}
-            Err(NotificationError::ChannelClogged) => break,
-            error => panic!("invalid error: {error:?}"),
+    // collect the tapped frames, ignoring the handshakes
+    async fn collect(
+        rx: &mut tokio::sync::mpsc::Receiver<litep2p::substream::TappedFrame>,
+        direction: TapDirection,
+        peer: PeerId,
+        count: usize,
+    ) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+
+        while frames.len() < count {
+            let frame = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("frame to be tapped")
+                .unwrap();
+
+            assert_eq!(frame.peer, peer);
+            assert_eq!(frame.protocol, ProtocolName::from("/notif/1"));
+
+            if frame.direction == direction && frame.frame[..] != HANDSHAKE[..] {
+                frames.push(frame.frame.to_vec());
+            }
         }
+
+        frames
     }
 
-    // stream closed from `peer1`'s PoV
+    // the sender tapped the outbound frames and the receiver the inbound frames
     assert_eq!(
-        handle1.next().await.unwrap(),
-        NotificationEvent::NotificationStreamClosed { peer: peer2 },
+        collect(
+            &mut tap_rx1,
+            TapDirection::Outbound,
+            peer2,
+            notifications.len()
+        )
+        .await,
+        notifications
     );
-
-    // `peer2` is also reported that the substream is closed
-    match tokio::time::timeout(Duration::from_secs(5), async move {
-        loop {
-            if let Some(NotificationEvent::NotificationStreamClosed { peer }) = handle2.next().await
-            {
-                assert_eq!(peer, peer1);
-                break;
-            }
-        }
-    })
-    .await
-    {
-        Err(_) => panic!("timeout"),
-        Ok(()) => {}
+    assert_eq!(
+        collect(
+            &mut tap_rx2,
+            TapDirection::Inbound,
+            peer1,
+            notifications.len()
+        )
+        .await,
+        notifications
+    );
+
+    // the receiver didn't tap its outbound frames
+    while let Ok(frame) = tap_rx2.try_recv() {
+        assert_eq!(frame.direction, TapDirection::Inbound);
     }
 }
@@ -23,13 +23,15 @@ use litep2p::{
     crypto::ed25519::Keypair,
     protocol::request_response::{
         Config as RequestResponseConfig, ConfigBuilder, DialOptions, RejectReason,
-        RequestResponseError, RequestResponseEvent,
+        RequestResponseError, RequestResponseEvent, Role,
     },
     transport::tcp::config::Config as TcpConfig,
     types::{protocol::ProtocolName, RequestId},
     Litep2p, Litep2pEvent, PeerId,
 };
 
+#[cfg(unix)]
+use litep2p::transport::unix::config::Config as UnixConfig;
 #[cfg(feature = "websocket")]
 use litep2p::transport::websocket::config::Config as WebSocketConfig;
 
@@ -124,6 +126,29 @@ async fn send_request_receive_response_websocket() {
     .await;
 }
 
+#[cfg(unix)]
+#[tokio::test]
+async fn send_request_receive_response_unix() {
+    let socket_address = || {
+        let path =
+            std::env::temp_dir().join(format!("litep2p-{}.sock", rand::thread_rng().gen::<u64>()));
+
+        Multiaddr::empty().with(Protocol::Unix(path.to_string_lossy().into_owned().into()))
+    };
+
+    send_request_receive_response(
+        Transport::Unix(UnixConfig {
+            listen_addresses: vec![socket_address()],
+            ..Default::default()
+        }),
+        Transport::Unix(UnixConfig {
+            listen_addresses: vec![socket_address()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
 async fn send_request_receive_response(transport1: Transport, transport2: Transport) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -1241,6 +1266,125 @@ async fn dialer_fallback_protocol_works(transport1: Transport, transport2: Trans
     );
 }
 
+#[tokio::test]
+async fn dialer_multiple_fallback_names_tcp() {
+    dialer_multiple_fallback_names(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn dialer_multiple_fallback_names_quic() {
+    dialer_multiple_fallback_names(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn dialer_multiple_fallback_names_websocket() {
+    dialer_multiple_fallback_names(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn dialer_multiple_fallback_names(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/3"))
+        .with_max_size(1024usize)
+        .with_fallback_names(vec![
+            ProtocolName::from("/protocol/2"),
+            ProtocolName::from("/protocol/1"),
+        ])
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    // remote only supports the oldest version of the protocol
+    let (req_resp_config2, mut handle2) = RequestResponseConfig::new(
+        ProtocolName::from("/protocol/1"),
+        Vec::new(),
+        1024,
+        Duration::from_secs(5),
+        None,
+    );
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // send request to remote peer
+    let request_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        RequestResponseEvent::RequestReceived {
+            peer: peer1,
+            fallback: None,
+            request_id,
+            request: vec![1, 3, 3, 7],
+        }
+    );
+
+    // send response to the received request
+    handle2.send_response(request_id, vec![1, 3, 3, 8]);
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::ResponseReceived {
+            peer: peer2,
+            request_id,
+            response: vec![1, 3, 3, 8],
+            fallback: Some(ProtocolName::from("/protocol/1")),
+        }
+    );
+}
+
 #[tokio::test]
 async fn listener_fallback_protocol_works_tcp() {
     listener_fallback_protocol_works(
@@ -2336,6 +2480,10 @@ async fn dial_failure(transport: Transport) {
             .with(Protocol::Tcp(5))
             .with(Protocol::Ws(std::borrow::Cow::Owned("/".to_string())))
             .with(Protocol::P2p(Multihash::from(peer))),
+        #[cfg(unix)]
+        Transport::Unix(_) => Multiaddr::empty()
+            .with(Protocol::Unix("/tmp/litep2p-unreachable.sock".into()))
+            .with(Protocol::P2p(Multihash::from(peer))),
     };
 
     let config = add_transport(litep2p_config, transport).build();
@@ -3007,3 +3155,423 @@ async fn binary_incompatible_fallback_compatible_nodes(
         }
     );
 }
+
+#[tokio::test]
+async fn outbound_only_protocol_rejects_requests_tcp() {
+    outbound_only_protocol_rejects_requests(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn outbound_only_protocol_rejects_requests_quic() {
+    outbound_only_protocol_rejects_requests(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn outbound_only_protocol_rejects_requests_websocket() {
+    outbound_only_protocol_rejects_requests(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn outbound_only_protocol_rejects_requests(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_role(Role::InboundOnly)
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (req_resp_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_role(Role::OutboundOnly)
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    // inbound-only node cannot send requests
+    assert!(matches!(
+        handle1.send_request(peer2, vec![1, 3, 3, 7], DialOptions::Reject).await,
+        Err(litep2p::Error::NotSupported(_))
+    ));
+
+    // outbound-only node can send requests and receive responses
+    let request_id = handle2
+        .send_request(peer1, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+
+    match handle1.next().await.unwrap() {
+        RequestResponseEvent::RequestReceived {
+            peer,
+            request_id,
+            request,
+            ..
+        } => {
+            assert_eq!(peer, peer2);
+            assert_eq!(request, vec![1, 3, 3, 7]);
+            handle1.send_response(request_id, vec![1, 3, 3, 8]);
+        }
+        event => panic!("invalid event received: {event:?}"),
+    }
+
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        RequestResponseEvent::ResponseReceived {
+            peer: peer1,
+            request_id,
+            response: vec![1, 3, 3, 8],
+            fallback: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn request_to_outbound_only_protocol_is_rejected_tcp() {
+    request_to_outbound_only_protocol_is_rejected(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn request_to_outbound_only_protocol_is_rejected_quic() {
+    request_to_outbound_only_protocol_is_rejected(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn request_to_outbound_only_protocol_is_rejected_websocket() {
+    request_to_outbound_only_protocol_is_rejected(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+async fn request_to_outbound_only_protocol_is_rejected(
+    transport1: Transport,
+    transport2: Transport,
+) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .build();
+
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (req_resp_config2, mut handle2) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .with_role(Role::OutboundOnly)
+        .build();
+
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    // wait until peers have connected
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = litep2p1.next_event() => {},
+                _ = litep2p2.next_event() => {},
+            }
+        }
+    });
+
+    let request_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        RequestResponseEvent::RequestFailed {
+            peer: peer2,
+            request_id,
+            error: RequestResponseError::Rejected(RejectReason::SubstreamClosed)
+        }
+    );
+
+    // the request was never reported to the outbound-only node
+    assert!(tokio::time::timeout(Duration::from_millis(500), handle2.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn send_request_to_any_returns_first_valid_response() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config, mut handle) = ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+        .with_max_size(1024)
+        .build();
+    let config = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config);
+    let mut litep2p =
+        Litep2p::new(add_transport(config, Transport::Tcp(Default::default())).build()).unwrap();
+
+    // the fastest peer responds with an invalid response
+    let mut peers = Vec::new();
+    for (delay, response) in [(0u64, vec![0u8]), (200, vec![1u8]), (5_000, vec![2u8])] {
+        let (req_resp_config, mut server_handle) =
+            ConfigBuilder::new(ProtocolName::from("/protocol/1"))
+                .with_max_size(1024)
+                .build();
+        let config = Litep2pConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_request_response_protocol(req_resp_config);
+        let mut server =
+            Litep2p::new(add_transport(config, Transport::Tcp(Default::default())).build())
+                .unwrap();
+
+        connect_peers(&mut litep2p, &mut server).await;
+        peers.push(*server.local_peer_id());
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = server.next_event() => {}
+                    event = server_handle.next() => match event.unwrap() {
+                        RequestResponseEvent::RequestReceived { request_id, .. } => {
+                            sleep(Duration::from_millis(delay)).await;
+                            server_handle.send_response(request_id, response.clone());
+                        }
+                        event => panic!("invalid event received: {event:?}"),
+                    },
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        loop {
+            let _ = litep2p.next_event().await;
+        }
+    });
+
+    let (peer, response) = handle
+        .send_request_to_any(&peers, vec![1, 3, 3, 7], |response| response != [0u8])
+        .await
+        .unwrap();
+
+    assert_eq!(peer, peers[1]);
+    assert_eq!(response, vec![1u8]);
+}
+
+#[tokio::test]
+async fn drain_connection_lets_request_finish_tcp() {
+    drain_connection_lets_request_finish(
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::Tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn drain_connection_lets_request_finish_websocket() {
+    drain_connection_lets_request_finish(
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+        Transport::WebSocket(WebSocketConfig {
+            listen_addresses: vec!["/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()],
+            ..Default::default()
+        }),
+    )
+    .await;
+}
+
+async fn drain_connection_lets_request_finish(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (req_resp_config1, mut handle1) = RequestResponseConfig::new(
+        ProtocolName::from("/protocol/1"),
+        Vec::new(),
+        1024,
+        Duration::from_secs(5),
+        None,
+    );
+    let config1 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config1);
+
+    let config1 = add_transport(config1, transport1).build();
+
+    let (req_resp_config2, mut handle2) = RequestResponseConfig::new(
+        ProtocolName::from("/protocol/1"),
+        Vec::new(),
+        1024,
+        Duration::from_secs(5),
+        None,
+    );
+    let config2 = Litep2pConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_request_response_protocol(req_resp_config2);
+
+    let config2 = add_transport(config2, transport2).build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+
+    let peer1 = *litep2p1.local_peer_id();
+    let peer2 = *litep2p2.local_peer_id();
+
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    let connection_id = loop {
+        tokio::select! {
+            event = litep2p1.next_event() => match event.unwrap() {
+                Litep2pEvent::ConnectionEstablished { endpoint, .. } =>
+                    break endpoint.connection_id(),
+                _ => {},
+            },
+            _ = litep2p2.next_event() => {},
+        }
+    };
+
+    tokio::spawn(async move { while litep2p2.next_event().await.is_some() {} });
+
+    // open a substream over the connection and wait until the remote has received the request
+    let request_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 7], DialOptions::Reject)
+        .await
+        .unwrap();
+
+    loop {
+        tokio::select! {
+            event = handle2.next() => {
+                assert_eq!(
+                    event.unwrap(),
+                    RequestResponseEvent::RequestReceived {
+                        peer: peer1,
+                        fallback: None,
+                        request_id,
+                        request: vec![1, 3, 3, 7],
+                    }
+                );
+                break;
+            }
+            _ = litep2p1.next_event() => {},
+        }
+    }
+
+    let started = std::time::Instant::now();
+    let deadline = Duration::from_secs(10);
+    litep2p1.drain_connection(connection_id, started + deadline).unwrap();
+
+    // no new substreams can be opened over a draining connection
+    let rejected_id = handle1
+        .send_request(peer2, vec![1, 3, 3, 9], DialOptions::Reject)
+        .await
+        .unwrap();
+
+    // the request that was already in flight is allowed to finish
+    handle2.send_response(request_id, vec![1, 3, 3, 8]);
+
+    let mut rejected = false;
+    let mut response_received = false;
+    let mut closed = false;
+
+    while !rejected || !response_received || !closed {
+        tokio::select! {
+            event = handle1.next() => match event.unwrap() {
+                RequestResponseEvent::RequestFailed { peer, request_id, error } => {
+                    assert_eq!(peer, peer2);
+                    assert_eq!(request_id, rejected_id);
+                    assert!(std::matches!(error, RequestResponseError::Rejected(_)));
+                    rejected = true;
+                }
+                RequestResponseEvent::ResponseReceived { peer, request_id: id, response, .. } => {
+                    assert_eq!(peer, peer2);
+                    assert_eq!(id, request_id);
+                    assert_eq!(response, vec![1, 3, 3, 8]);
+                    response_received = true;
+                }
+                event => panic!("unexpected event: {event:?}"),
+            },
+            event = litep2p1.next_event() => match event.unwrap() {
+                Litep2pEvent::ConnectionClosed { peer, connection_id: closed_id, .. } => {
+                    assert_eq!(peer, peer2);
+                    assert_eq!(closed_id, connection_id);
+
+                    // the substream has finished before the connection was closed
+                    assert!(started.elapsed() < deadline);
+                    closed = true;
+                }
+                _ => {},
+            },
+        }
+    }
+}
@@ -23,7 +23,7 @@ use litep2p::{
     config::ConfigBuilder,
     error::SubstreamError,
     protocol::{Direction, TransportEvent, TransportService, UserProtocol},
-    substream::{Substream, SubstreamSet},
+    substream::{Substream, SubstreamRateLimit, SubstreamSet},
     transport::tcp::config::Config as TcpConfig,
     types::{protocol::ProtocolName, SubstreamId},
     Error, Litep2p, Litep2pEvent, PeerId,
@@ -608,3 +608,165 @@ async fn correct_payload_size_async_write(transport1: Transport, transport2: Tra
         event => panic!("invalid event received: {event:?}"),
     }
 }
+
+/// Protocol which opens substreams on request and reports every opened substream to the test.
+struct RateLimitedProtocol {
+    rx: Receiver<(PeerId, usize)>,
+    tx: Sender<Result<Substream, SubstreamError>>,
+}
+
+impl RateLimitedProtocol {
+    fn new() -> (
+        Self,
+        Sender<(PeerId, usize)>,
+        Receiver<Result<Substream, SubstreamError>>,
+    ) {
+        let (cmd_tx, rx) = channel(64);
+        let (tx, event_rx) = channel(64);
+
+        (Self { rx, tx }, cmd_tx, event_rx)
+    }
+}
+
+#[async_trait::async_trait]
+impl UserProtocol for RateLimitedProtocol {
+    fn protocol(&self) -> ProtocolName {
+        ProtocolName::from("/rate-limited/1")
+    }
+
+    fn codec(&self) -> ProtocolCodec {
+        ProtocolCodec::UnsignedVarint(None)
+    }
+
+    async fn run(mut self: Box<Self>, mut service: TransportService) -> litep2p::Result<()> {
+        loop {
+            tokio::select! {
+                event = service.next() => match event.unwrap() {
+                    TransportEvent::SubstreamOpened { substream, .. } => {
+                        self.tx.send(Ok(substream)).await.unwrap();
+                    }
+                    TransportEvent::SubstreamOpenFailure { error, .. } => {
+                        self.tx.send(Err(error)).await.unwrap();
+                    }
+                    _ => {}
+                },
+                command = self.rx.recv() => {
+                    let (peer, count) = command.unwrap();
+
+                    for _ in 0..count {
+                        service.open_substream(peer).unwrap();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn substream_rate_limit_enforced_tcp() {
+    substream_rate_limit_enforced(
+        Transport::Tcp(Default::default()),
+        Transport::Tcp(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "quic")]
+#[tokio::test]
+async fn substream_rate_limit_enforced_quic() {
+    substream_rate_limit_enforced(
+        Transport::Quic(Default::default()),
+        Transport::Quic(Default::default()),
+    )
+    .await;
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn substream_rate_limit_enforced_websocket() {
+    substream_rate_limit_enforced(
+        Transport::WebSocket(Default::default()),
+        Transport::WebSocket(Default::default()),
+    )
+    .await;
+}
+
+// open substreams faster than the remote peer allows and verify the excess substreams are closed
+async fn substream_rate_limit_enforced(transport1: Transport, transport2: Transport) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (protocol1, tx1, mut rx1) = RateLimitedProtocol::new();
+    let config1 = match transport1 {
+        Transport::Tcp(config) => ConfigBuilder::new().with_tcp(config),
+        #[cfg(feature = "quic")]
+        Transport::Quic(config) => ConfigBuilder::new().with_quic(config),
+        #[cfg(feature = "websocket")]
+        Transport::WebSocket(config) => ConfigBuilder::new().with_websocket(config),
+    }
+    .with_user_protocol(Box::new(protocol1))
+    .build();
+
+    let (protocol2, _tx2, mut rx2) = RateLimitedProtocol::new();
+    let config2 = match transport2 {
+        Transport::Tcp(config) => ConfigBuilder::new().with_tcp(config),
+        #[cfg(feature = "quic")]
+        Transport::Quic(config) => ConfigBuilder::new().with_quic(config),
+        #[cfg(feature = "websocket")]
+        Transport::WebSocket(config) => ConfigBuilder::new().with_websocket(config),
+    }
+    .with_user_protocol(Box::new(protocol2))
+    .with_substream_rate_limit(SubstreamRateLimit { rate: 1, burst: 3 })
+    .build();
+
+    let mut litep2p1 = Litep2p::new(config1).unwrap();
+    let mut litep2p2 = Litep2p::new(config2).unwrap();
+    let peer2 = *litep2p2.local_peer_id();
+
+    connect_peers(&mut litep2p1, &mut litep2p2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _event = litep2p1.next_event() => {}
+                _event = litep2p2.next_event() => {}
+            }
+        }
+    });
+
+    // `litep2p2` accepts a burst of three substreams and the rest are closed
+    tx1.send((peer2, 6)).await.unwrap();
+
+    let mut opened = Vec::new();
+    let mut failed = 0usize;
+
+    while opened.len() + failed < 6 {
+        match tokio::time::timeout(std::time::Duration::from_secs(10), rx1.recv())
+            .await
+            .expect("substream open to finish")
+            .unwrap()
+        {
+            Ok(substream) => opened.push(substream),
+            Err(_) => failed += 1,
+        }
+    }
+    assert_eq!(opened.len(), 3);
+    assert_eq!(failed, 3);
+
+    let mut accepted = Vec::new();
+    for _ in 0..3 {
+        accepted.push(rx2.recv().await.unwrap().unwrap());
+    }
+
+    // the accepted substreams are still open
+    for (i, substream) in opened.iter_mut().enumerate() {
+        substream.send_framed(Bytes::from(vec![i as u8; 8])).await.unwrap();
+    }
+
+    let mut received = Vec::new();
+    for substream in &mut accepted {
+        received.push(substream.next().await.unwrap().unwrap()[0]);
+    }
+    received.sort();
+    assert_eq!(received, vec![0, 1, 2]);
+}
@@ -0,0 +1,380 @@
+// Copyright 2024 litep2p developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use litep2p::{
+    codec::ProtocolCodec,
+    config::ConfigBuilder,
+    crypto::ed25519::Keypair,
+    protocol::{
+        libp2p::relay::{
+            ClientConfig as RelayClientConfig, ConfigBuilder as RelayConfigBuilder, RelayEvent,
+        },
+        notification::{
+            Config as NotificationConfig, Direction, NotificationEvent, NotificationHandle,
+            ValidationResult,
+        },
+        TransportEvent, TransportService, UserProtocol,
+    },
+    substream::Substream,
+    transport::{
+        tcp::config::Config as TcpConfig,
+        tunnel::{config::Config as TunnelConfig, TunnelHandle},
+    },
+    types::protocol::ProtocolName,
+    Litep2p, Litep2pEvent, PeerId,
+};
+
+use multiaddr::{Multiaddr, Protocol};
+use multihash::Multihash;
+
+use bytes::BytesMut;
+use futures::StreamExt;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+/// Protocol which opens raw substreams over the outer connection and hands them to the test.
+struct TunnelProtocol {
+    rx: Receiver<PeerId>,
+    tx: Sender<Substream>,
+}
+
+impl TunnelProtocol {
+    fn new() -> (Self, Sender<PeerId>, Receiver<Substream>) {
+        let (peer_tx, peer_rx) = channel(64);
+        let (substream_tx, substream_rx) = channel(64);
+
+        (
+            Self {
+                rx: peer_rx,
+                tx: substream_tx,
+            },
+            peer_tx,
+            substream_rx,
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl UserProtocol for TunnelProtocol {
+    fn protocol(&self) -> ProtocolName {
+        ProtocolName::from("/tunnel/1")
+    }
+
+    fn codec(&self) -> ProtocolCodec {
+        ProtocolCodec::Unspecified
+    }
+
+    async fn run(mut self: Box<Self>, mut service: TransportService) -> litep2p::Result<()> {
+        loop {
+            tokio::select! {
+                event = service.next() => match event.unwrap() {
+                    TransportEvent::SubstreamOpened { substream, .. } => {
+                        self.tx.send(substream).await.unwrap();
+                    }
+                    _ => {}
+                },
+                peer = self.rx.recv() => {
+                    service.open_substream(peer.unwrap()).unwrap();
+                }
+            }
+        }
+    }
+}
+
+async fn connect_peers(litep2p1: &mut Litep2p, litep2p2: &mut Litep2p) {
+    let address = litep2p2.listen_addresses().next().unwrap().clone();
+    litep2p1.dial_address(address).await.unwrap();
+
+    let mut litep2p1_connected = false;
+    let mut litep2p2_connected = false;
+
+    loop {
+        tokio::select! {
+            event = litep2p1.next_event() => if let Litep2pEvent::ConnectionEstablished { .. } =
+                event.unwrap()
+            {
+                litep2p1_connected = true;
+            },
+            event = litep2p2.next_event() => if let Litep2pEvent::ConnectionEstablished { .. } =
+                event.unwrap()
+            {
+                litep2p2_connected = true;
+            },
+        }
+
+        if litep2p1_connected && litep2p2_connected {
+            break;
+        }
+    }
+}
+
+fn make_outer_litep2p() -> (Litep2p, Sender<PeerId>, Receiver<Substream>) {
+    let (protocol, tx, rx) = TunnelProtocol::new();
+    let config = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_user_protocol(Box::new(protocol))
+        .build();
+
+    (Litep2p::new(config).unwrap(), tx, rx)
+}
+
+fn make_inner_litep2p() -> (Litep2p, TunnelHandle, NotificationHandle) {
+    let (tunnel_config, tunnel_handle) = TunnelConfig::new();
+    let (notif_config, notif_handle) = NotificationConfig::new(
+        ProtocolName::from("/notif/1"),
+        1024usize,
+        vec![1, 2, 3, 4],
+        Vec::new(),
+        false,
+        64,
+        64,
+        true,
+    );
+    let config = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tunnel(tunnel_config)
+        .with_notification_protocol(notif_config)
+        .build();
+
+    (Litep2p::new(config).unwrap(), tunnel_handle, notif_handle)
+}
+
+#[tokio::test]
+async fn notification_protocol_over_tunnel() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (mut outer1, tunnel_tx1, mut tunnel_rx1) = make_outer_litep2p();
+    let (mut outer2, _tunnel_tx2, mut tunnel_rx2) = make_outer_litep2p();
+    let outer_peer2 = *outer2.local_peer_id();
+
+    connect_peers(&mut outer1, &mut outer2).await;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = outer1.next_event() => {},
+                _ = outer2.next_event() => {},
+            }
+        }
+    });
+
+    // open the tunnel substream over the outer connection
+    tunnel_tx1.send(outer_peer2).await.unwrap();
+    let substream1 = tunnel_rx1.recv().await.unwrap();
+    let substream2 = tunnel_rx2.recv().await.unwrap();
+
+    // establish inner connection over the tunnel
+    let (mut inner1, tunnel_handle1, mut handle1) = make_inner_litep2p();
+    let (mut inner2, tunnel_handle2, mut handle2) = make_inner_litep2p();
+    let inner_peer1 = *inner1.local_peer_id();
+    let inner_peer2 = *inner2.local_peer_id();
+
+    tunnel_handle1.open(substream1).await.unwrap();
+    tunnel_handle2.accept(substream2).await.unwrap();
+
+    let mut inner1_connected = false;
+    let mut inner2_connected = false;
+
+    while !inner1_connected || !inner2_connected {
+        tokio::select! {
+            event = inner1.next_event() => match event.unwrap() {
+                Litep2pEvent::ConnectionEstablished { peer, endpoint, .. } => {
+                    assert_eq!(peer, inner_peer2);
+                    assert!(!endpoint.is_listener());
+                    assert!(endpoint.is_relayed());
+                    inner1_connected = true;
+                }
+                _ => {}
+            },
+            event = inner2.next_event() => match event.unwrap() {
+                Litep2pEvent::ConnectionEstablished { peer, endpoint, .. } => {
+                    assert_eq!(peer, inner_peer1);
+                    assert!(endpoint.is_listener());
+                    inner2_connected = true;
+                }
+                _ => {}
+            },
+        }
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = inner1.next_event() => {},
+                _ = inner2.next_event() => {},
+            }
+        }
+    });
+
+    // run the notification protocol over the inner connection
+    handle1.open_substream(inner_peer2).await.unwrap();
+
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: inner_peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    handle2.send_validation_result(inner_peer1, ValidationResult::Accept);
+
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::ValidateSubstream {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            peer: inner_peer2,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    handle1.send_validation_result(inner_peer2, ValidationResult::Accept);
+
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            direction: Direction::Inbound,
+            peer: inner_peer1,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationStreamOpened {
+            protocol: ProtocolName::from("/notif/1"),
+            fallback: None,
+            direction: Direction::Outbound,
+            peer: inner_peer2,
+            handshake: vec![1, 2, 3, 4],
+        }
+    );
+
+    handle1.send_sync_notification(inner_peer2, vec![1, 3, 3, 7]).unwrap();
+    handle2.send_sync_notification(inner_peer1, vec![1, 3, 3, 8]).unwrap();
+
+    assert_eq!(
+        handle2.next().await.unwrap(),
+        NotificationEvent::NotificationReceived {
+            peer: inner_peer1,
+            notification: BytesMut::from(&[1, 3, 3, 7][..]),
+        }
+    );
+    assert_eq!(
+        handle1.next().await.unwrap(),
+        NotificationEvent::NotificationReceived {
+            peer: inner_peer2,
+            notification: BytesMut::from(&[1, 3, 3, 8][..]),
+        }
+    );
+}
+
+fn make_relay_client_litep2p(relay: Multiaddr) -> Litep2p {
+    let (tunnel_config, tunnel_handle) = TunnelConfig::new();
+    let config = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+            ..Default::default()
+        })
+        .with_tunnel(tunnel_config)
+        .with_libp2p_relay_client(RelayClientConfig::new(vec![relay], tunnel_handle))
+        .build();
+
+    Litep2p::new(config).unwrap()
+}
+
+#[tokio::test]
+async fn dial_through_relay_when_direct_dial_fails() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (relay_config, mut relay_events) = RelayConfigBuilder::new().build();
+    let mut relay = Litep2p::new(
+        ConfigBuilder::new()
+            .with_keypair(Keypair::generate())
+            .with_tcp(TcpConfig {
+                listen_addresses: vec!["/ip6/::1/tcp/0".parse().unwrap()],
+                ..Default::default()
+            })
+            .with_libp2p_relay_server(relay_config)
+            .build(),
+    )
+    .unwrap();
+    let relay_address = relay.listen_addresses().next().unwrap().clone().with(Protocol::P2p(
+        Multihash::from_bytes(&relay.local_peer_id().to_bytes()).unwrap(),
+    ));
+
+    let mut source = make_relay_client_litep2p(relay_address.clone());
+    let mut destination = make_relay_client_litep2p(relay_address);
+    let source_peer = *source.local_peer_id();
+    let destination_peer = *destination.local_peer_id();
+
+    tokio::spawn(async move { while relay.next_event().await.is_some() {} });
+    tokio::spawn(async move { while destination.next_event().await.is_some() {} });
+
+    // wait until both clients have a reservation on the relay
+    let mut reserved = Vec::new();
+    while reserved.len() < 2 {
+        tokio::select! {
+            event = relay_events.next() => match event.unwrap() {
+                RelayEvent::ReservationAccepted { peer } => reserved.push(peer),
+                _ => {}
+            },
+            _ = source.next_event() => {},
+        }
+    }
+    assert!(reserved.contains(&source_peer));
+    assert!(reserved.contains(&destination_peer));
+
+    // the only known direct address of the destination is unreachable
+    let unreachable = Multiaddr::empty()
+        .with(Protocol::Ip6(std::net::Ipv6Addr::LOCALHOST))
+        .with(Protocol::Tcp(1))
+        .with(Protocol::P2p(
+            Multihash::from_bytes(&destination_peer.to_bytes()).unwrap(),
+        ));
+    source.add_known_address(destination_peer, std::iter::once(unreachable));
+    source.dial(&destination_peer).await.unwrap();
+
+    tokio::spawn(async move { while relay_events.next().await.is_some() {} });
+
+    // the failure of the direct dial is held back and the relayed connection reported instead
+    loop {
+        match source.next_event().await.unwrap() {
+            Litep2pEvent::ConnectionEstablished { peer, endpoint, .. }
+                if peer == destination_peer =>
+            {
+                assert!(endpoint.is_relayed());
+                break;
+            }
+            Litep2pEvent::DialFailure { .. } | Litep2pEvent::ListDialFailures { .. } =>
+                panic!("dial failure reported before the dial through the relay finished"),
+            _ => {}
+        }
+    }
+}
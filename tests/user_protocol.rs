@@ -152,3 +152,79 @@ async fn user_protocol() {
     let sink = litep2p2.bandwidth_sink();
     tracing::trace!("inbound {}, outbound {}", sink.outbound(), sink.inbound());
 }
+
+/// Protocol which blocks its thread for long stretches between yields.
+struct BusyProtocol;
+
+#[async_trait::async_trait]
+impl UserProtocol for BusyProtocol {
+    fn protocol(&self) -> ProtocolName {
+        ProtocolName::from("/busy/1")
+    }
+
+    fn codec(&self) -> ProtocolCodec {
+        ProtocolCodec::UnsignedVarint(None)
+    }
+
+    async fn run(self: Box<Self>, _service: TransportService) -> litep2p::Result<()> {
+        loop {
+            std::thread::sleep(Duration::from_millis(200));
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+/// Protocol which reports a tick every 10 milliseconds.
+struct TickingProtocol {
+    tx: tokio::sync::mpsc::Sender<()>,
+}
+
+#[async_trait::async_trait]
+impl UserProtocol for TickingProtocol {
+    fn protocol(&self) -> ProtocolName {
+        ProtocolName::from("/ticking/1")
+    }
+
+    fn codec(&self) -> ProtocolCodec {
+        ProtocolCodec::UnsignedVarint(None)
+    }
+
+    async fn run(self: Box<Self>, _service: TransportService) -> litep2p::Result<()> {
+        loop {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+
+            if self.tx.send(()).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn isolated_protocol_does_not_starve_other_protocols() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+    let config = ConfigBuilder::new()
+        .with_keypair(Keypair::generate())
+        .with_tcp(TcpConfig {
+            ..Default::default()
+        })
+        .with_user_protocol(Box::new(BusyProtocol))
+        .with_user_protocol(Box::new(TickingProtocol { tx }))
+        .with_isolated_protocol(ProtocolName::from("/busy/1"))
+        .build();
+    let _litep2p = Litep2p::new(config).unwrap();
+
+    // sharing the test's single-threaded runtime with the busy protocol, ticks would be at least
+    // 200 milliseconds apart
+    tokio::time::timeout(Duration::from_secs(1), async {
+        for _ in 0..10 {
+            rx.recv().await.unwrap();
+        }
+    })
+    .await
+    .expect("ticking protocol to make progress");
+}
@@ -87,6 +87,7 @@ impl UserProtocol for CustomProtocol {
                         error: _,
                     } => {}
                     TransportEvent::DialFailure { .. } => {}
+                    TransportEvent::ConnectionAddressChanged { .. } => {}
                 },
                 address = self.rx.recv() => {
                     service.dial_address(address.unwrap()).unwrap();